@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use napi::{Error, Result};
+use napi_derive::napi;
+use serde_json::{json, Value};
+
+use todo_tracker::discovery::FileDiscovery;
+use todo_tracker::git::diff::diff_todos;
+use todo_tracker::model::ScanResult;
+use todo_tracker::policy::{check_policies, PolicyConfig};
+use todo_tracker::scanner::regex::RegexScanner;
+use todo_tracker::scanner::ScanOrchestrator;
+use todo_tracker::vcs;
+
+fn to_js_error(e: impl std::fmt::Display) -> Error {
+    Error::from_reason(e.to_string())
+}
+
+fn scan_path(path: &str) -> Result<ScanResult> {
+    let scanner = RegexScanner::new().map_err(to_js_error)?;
+    ScanOrchestrator::new(Box::new(scanner), FileDiscovery::new(path))
+        .scan()
+        .map_err(to_js_error)
+}
+
+/// Scan the directory at `path`. Returns the same object as
+/// `todos --format json`: `{ items, stats, metadata }`.
+#[napi]
+pub fn scan(path: String) -> Result<Value> {
+    serde_json::to_value(scan_path(&path)?).map_err(to_js_error)
+}
+
+/// TODOs added and removed between two revisions of the repository
+/// containing `path`: `{ added, removed, base_ref, head_ref, errors }`.
+#[napi]
+pub fn diff(path: String, base: String, head: String) -> Result<Value> {
+    let vcs = vcs::detect(Path::new(&path)).map_err(to_js_error)?;
+    let scanner = RegexScanner::new().map_err(to_js_error)?;
+    let diff = diff_todos(&scanner, &base, &head, vcs.as_ref()).map_err(to_js_error)?;
+    serde_json::to_value(diff).map_err(to_js_error)
+}
+
+/// Check the TODOs under `path` against `policy`, an object with the
+/// `todos check` rules (`max_todos`, `require_issue`, `deny_tags`, ...).
+/// Returns `{ passed, violations }`.
+#[napi]
+pub fn check(path: String, policy: Option<Value>) -> Result<Value> {
+    let config: PolicyConfig = match policy {
+        Some(policy) => serde_json::from_value(policy)
+            .map_err(|e| to_js_error(format!("Invalid policy: {}", e)))?,
+        None => PolicyConfig::default(),
+    };
+    let violations = check_policies(&scan_path(&path)?, &config);
+    Ok(json!({
+        "passed": violations.is_empty(),
+        "violations": violations,
+    }))
+}
@@ -0,0 +1,23 @@
+// Smoke test for the built addon: `npm run build && npm test`
+const assert = require('assert');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const { scan, check } = require('./index.js');
+
+const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'todo-tracker-'));
+fs.writeFileSync(path.join(dir, 'main.rs'), '// TODO: one\n// FIXME: two\n');
+
+const result = scan(dir);
+assert.strictEqual(result.stats.total_todos, 2);
+assert.deepStrictEqual(result.items.map((i) => i.tag).sort(), ['Fixme', 'Todo']);
+
+assert.strictEqual(check(dir, { max_todos: 5 }).passed, true);
+const failed = check(dir, { max_todos: 1 });
+assert.strictEqual(failed.passed, false);
+assert.strictEqual(failed.violations[0].rule, 'max_todos');
+
+assert.throws(() => check(dir, { max_todos: 'many' }), /Invalid policy/);
+
+fs.rmSync(dir, { recursive: true });
+console.log('ok');
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TodoError};
+use crate::model::ScanResult;
+use crate::ordering::path_sort_key;
+
+/// Default baseline location, relative to the scan root.
+pub const BASELINE_FILE: &str = ".todo-baseline.json";
+
+/// One known item. The file, tag and message are kept for readable diffs of
+/// the baseline; matching uses the fingerprint alone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub fingerprint: String,
+    pub file: String,
+    pub tag: String,
+    pub message: String,
+}
+
+/// TODOs accepted as existing debt, written by `todos baseline write`.
+/// `todos check --baseline` only holds items missing from it to the policies.
+/// Identical TODOs in one file share a fingerprint and get an entry each, so
+/// adding another copy still counts as new.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub items: Vec<BaselineEntry>,
+}
+
+/// How a scan compares to the baseline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BaselineMatch {
+    /// Items found in the baseline and left out of the check
+    pub known: usize,
+    /// Baseline entries no longer found, which a new baseline would drop
+    pub fixed: usize,
+}
+
+impl Baseline {
+    /// Baseline of every item in `result`, sorted by file, tag and message.
+    pub fn from_result(result: &ScanResult) -> Self {
+        let root = &result.metadata.root_path;
+        let mut items: Vec<BaselineEntry> = result
+            .items
+            .iter()
+            .map(|item| BaselineEntry {
+                fingerprint: item.fingerprint(root),
+                file: item.relative_path(root),
+                tag: item.tag.as_str().to_string(),
+                message: item.message.trim().to_string(),
+            })
+            .collect();
+        items.sort_by_cached_key(|entry| {
+            (
+                path_sort_key(Path::new(&entry.file)),
+                entry.tag.clone(),
+                entry.message.clone(),
+            )
+        });
+        Baseline { items }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            TodoError::Config(format!(
+                "Failed to read baseline {}: {} (create it with `todos baseline write`)",
+                path.display(),
+                e
+            ))
+        })?;
+        serde_json::from_str(&contents).map_err(|e| {
+            TodoError::Config(format!("Failed to parse baseline {}: {}", path.display(), e))
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| TodoError::Config(e.to_string()))?;
+        std::fs::write(path, json + "\n")?;
+        Ok(())
+    }
+
+    /// Remove the items in the baseline from `result`, leaving the new ones.
+    pub fn apply(&self, result: &mut ScanResult) -> BaselineMatch {
+        let root = result.metadata.root_path.clone();
+        let mut remaining: HashMap<&str, usize> = HashMap::new();
+        for entry in &self.items {
+            *remaining.entry(entry.fingerprint.as_str()).or_default() += 1;
+        }
+        let before = result.items.len();
+        result.items.retain(|item| {
+            match remaining.get_mut(item.fingerprint(&root).as_str()) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    false
+                }
+                _ => true,
+            }
+        });
+        let known = before - result.items.len();
+        if known > 0 {
+            result.recompute_stats();
+        }
+        BaselineMatch {
+            known,
+            fixed: remaining.values().sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats, TodoItem, TodoTag};
+    use std::path::PathBuf;
+
+    fn item(file: &str, line: usize, message: &str) -> TodoItem {
+        TodoItem {
+            tag: TodoTag::Todo,
+            message: message.to_string(),
+            file: PathBuf::from("/repo").join(file),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    fn result(items: Vec<TodoItem>) -> ScanResult {
+        let mut result = ScanResult {
+            items,
+            stats: ScanStats::default(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("/repo"),
+                timestamp: String::new(),
+                blame_cache_hits: None,
+            },
+        };
+        result.recompute_stats();
+        result
+    }
+
+    #[test]
+    fn test_baseline_keeps_new_items() {
+        let old = result(vec![
+            item("b.rs", 1, "retry"),
+            item("a.rs", 4, "dup"),
+            item("a.rs", 9, "dup"),
+            item("c.rs", 2, "gone"),
+        ]);
+        let baseline = Baseline::from_result(&old);
+        let files: Vec<&str> = baseline.items.iter().map(|e| e.file.as_str()).collect();
+        assert_eq!(files, ["a.rs", "a.rs", "b.rs", "c.rs"]);
+
+        // Moved lines still match; a third copy of "dup" and a new file don't
+        let mut new = result(vec![
+            item("b.rs", 30, "retry"),
+            item("a.rs", 5, "dup"),
+            item("a.rs", 10, "dup"),
+            item("a.rs", 11, "dup"),
+            item("d.rs", 1, "new"),
+        ]);
+        let matched = baseline.apply(&mut new);
+        assert_eq!(matched, BaselineMatch { known: 3, fixed: 1 });
+        let left: Vec<(&str, usize)> = new
+            .items
+            .iter()
+            .map(|i| (i.message.as_str(), i.line))
+            .collect();
+        assert_eq!(left, [("dup", 11), ("new", 1)]);
+        assert_eq!(new.stats.total_todos, 2);
+    }
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(BASELINE_FILE);
+        assert!(Baseline::load(&path).unwrap_err().to_string().contains("baseline write"));
+        let baseline = Baseline::from_result(&result(vec![item("a.rs", 1, "x")]));
+        baseline.save(&path).unwrap();
+        assert_eq!(Baseline::load(&path).unwrap(), baseline);
+    }
+}
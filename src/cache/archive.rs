@@ -0,0 +1,132 @@
+use std::fs::{self, File};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::Connection;
+
+use crate::cache::{migrations, CacheDb};
+use crate::error::{Result, TodoError};
+
+/// Name of the database inside an export archive.
+const ARCHIVE_ENTRY: &str = "cache.db";
+
+/// Write `db` to `archive` as a gzipped tarball holding one
+/// `cache.db`, for `cache import` on another machine.
+pub fn export(db: &CacheDb, archive: &Path) -> Result<()> {
+    let staging = tempfile::tempdir()?;
+    let copy = staging.path().join(ARCHIVE_ENTRY);
+    db.vacuum_into(&copy)?;
+
+    let file = File::create(archive).map_err(|e| {
+        TodoError::Cache(format!("Failed to create {}: {}", archive.display(), e))
+    })?;
+    let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    tar.append_path_with_name(&copy, ARCHIVE_ENTRY)?;
+    tar.into_inner().and_then(|gz| gz.finish())?;
+    Ok(())
+}
+
+/// Replace the cache at `root` with the one in `archive`. The archived
+/// database is checked and migrated before it replaces anything, so a bad
+/// archive leaves the current cache alone.
+pub fn import(root: &Path, archive: &Path) -> Result<()> {
+    let file = File::open(archive).map_err(|e| {
+        TodoError::Cache(format!("Failed to open {}: {}", archive.display(), e))
+    })?;
+    let cache_dir = root.join(".todo-tracker");
+    fs::create_dir_all(&cache_dir)
+        .map_err(|e| TodoError::Cache(format!("Failed to create cache dir: {}", e)))?;
+    // Unpack next to the cache so the final rename stays on one filesystem
+    let staging = tempfile::tempdir_in(&cache_dir)?;
+    let unpacked = staging.path().join(ARCHIVE_ENTRY);
+
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+    let mut found = false;
+    let not_an_archive = |e: std::io::Error| TodoError::Cache(format!("Not a cache archive: {}", e));
+    for entry in tar.entries().map_err(not_an_archive)? {
+        let mut entry = entry.map_err(not_an_archive)?;
+        if entry.path()?.as_ref() == Path::new(ARCHIVE_ENTRY) {
+            entry.unpack(&unpacked)?;
+            found = true;
+            break;
+        }
+    }
+    if !found {
+        return Err(TodoError::Cache(format!(
+            "Not a cache archive: no {} entry",
+            ARCHIVE_ENTRY
+        )));
+    }
+    check_database(&unpacked)?;
+
+    CacheDb::open(root)?.replace_with(&unpacked)
+}
+
+/// Check that `path` is an intact cache database and bring it up to the
+/// current schema.
+fn check_database(path: &Path) -> Result<()> {
+    let invalid = |e: rusqlite::Error| TodoError::Cache(format!("Not a valid cache database: {}", e));
+    let conn = Connection::open(path).map_err(invalid)?;
+    let status: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(invalid)?;
+    if status != "ok" {
+        return Err(TodoError::Cache(format!("Not a valid cache database: {}", status)));
+    }
+    conn.query_row("SELECT COUNT(*) FROM file_fingerprints", [], |row| row.get::<_, i64>(0))
+        .map_err(invalid)?;
+    migrations::run_migrations(&conn).map_err(invalid)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let from = tempfile::tempdir().unwrap();
+        let to = tempfile::tempdir().unwrap();
+        let archive = from.path().join("cache.tar.gz");
+
+        let db = CacheDb::open(from.path()).unwrap();
+        db.store_file(Path::new("./src/a.rs"), 1000, 500, 10, Some("00ff"), &[])
+            .unwrap();
+        export(&db, &archive).unwrap();
+        drop(db);
+
+        // The destination's own entries are replaced, not merged
+        let existing = CacheDb::open(to.path()).unwrap();
+        existing
+            .store_file(Path::new("./src/old.rs"), 1, 1, 1, None, &[])
+            .unwrap();
+        drop(existing);
+
+        import(to.path(), &archive).unwrap();
+        let db = CacheDb::open(to.path()).unwrap();
+        assert_eq!(db.cached_paths(), vec![PathBuf::from("./src/a.rs")]);
+        assert_eq!(db.get_hash(Path::new("./src/a.rs")).as_deref(), Some("00ff"));
+        assert!(db.is_file_fresh(Path::new("./src/a.rs"), 1000, 500));
+    }
+
+    #[test]
+    fn test_import_rejects_bad_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("bogus.tar.gz");
+        fs::write(&archive, b"not a tarball").unwrap();
+        assert!(import(dir.path(), &archive).is_err());
+
+        // A tarball without a cache database in it
+        let file = File::create(&archive).unwrap();
+        let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let readme = dir.path().join("README");
+        fs::write(&readme, "hello").unwrap();
+        tar.append_path_with_name(&readme, "README").unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+        let err = import(dir.path(), &archive).unwrap_err();
+        assert!(err.to_string().contains("no cache.db entry"), "{}", err);
+    }
+}
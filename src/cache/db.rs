@@ -1,44 +1,333 @@
-use std::fs;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use rusqlite::Connection;
+use rusqlite::{Connection, ErrorCode, OpenFlags, Transaction, TransactionBehavior};
 
 use crate::cache::migrations;
-use crate::model::{Priority, TodoItem, TodoTag};
+use crate::date::unix_now;
+use crate::error::{Result, TodoError};
+use crate::discovery::DirRecord;
+use crate::git::blame::BlameInfo;
+use crate::model::{Priority, ScanResult, ScanStats, TodoItem, TodoTag};
+
+/// Number of whole-scan results kept by `store_result`.
+pub const MAX_CACHED_RESULTS: usize = 20;
+
+/// One row of the history table, recorded per snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub taken_at: String,
+    pub total_todos: usize,
+    pub files_with_todos: usize,
+    pub by_tag: std::collections::HashMap<String, usize>,
+}
+
+/// What a file's cached blame is valid for: the last commit that touched the
+/// file, and the working-tree file's mtime and size to catch uncommitted
+/// edits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlameStamp {
+    pub last_commit: String,
+    pub mtime: u64,
+    pub size: u64,
+}
+
+/// How long a statement waits for another process's lock before failing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Attempts at a write that keeps failing because the database is locked.
+const BUSY_ATTEMPTS: u32 = 3;
+
+/// Lock file next to the cache. Only the process holding a lock on it writes
+/// the cache; others read it and skip their writes.
+pub const WRITER_LOCK_FILE: &str = "writer.lock";
 
 pub struct CacheDb {
     conn: Connection,
+    /// Set when the cache was opened read-only, or a write gave up waiting
+    /// for another process; writes are skipped from then on.
+    read_only: Cell<bool>,
+    /// Handle on the writer lock file; `None` when there is no lock to take
+    lock: Option<File>,
+    /// Whether this process holds the writer lock
+    writer: Cell<bool>,
+    /// Namespace that file fingerprints and items are read from and written
+    /// to, e.g. the current branch; `""` by default
+    namespace: String,
+}
+
+fn is_busy(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+/// `CacheLocked` when another process holds the database, `Cache` otherwise.
+fn cache_error(e: rusqlite::Error) -> TodoError {
+    if is_busy(&e) {
+        TodoError::CacheLocked(e.to_string())
+    } else {
+        TodoError::Cache(e.to_string())
+    }
+}
+
+/// Run `f`, retrying with backoff while the database is locked by another
+/// process (each attempt already waits up to the busy timeout).
+fn retry_busy<T>(mut f: impl FnMut() -> rusqlite::Result<T>) -> rusqlite::Result<T> {
+    let mut delay = Duration::from_millis(25);
+    for _ in 1..BUSY_ATTEMPTS {
+        match f() {
+            Err(e) if is_busy(&e) => {
+                std::thread::sleep(delay);
+                delay *= 4;
+            }
+            other => return other,
+        }
+    }
+    f()
+}
+
+/// Remove the WAL and shared-memory files that belong to `db_path`.
+fn remove_sidecars(db_path: &Path) -> Result<()> {
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = db_path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        match fs::remove_file(&sidecar) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
 }
 
 impl CacheDb {
-    /// Open or create cache database at .todo-tracker/cache.db
-    pub fn open(root: &Path) -> Result<Self, String> {
+    /// Open or create cache database at .todo-tracker/cache.db. When another
+    /// `todos` process keeps the database locked, or it can't be written,
+    /// an existing cache is opened read-only instead.
+    pub fn open(root: &Path) -> Result<Self> {
+        Self::open_with_timeout(root, BUSY_TIMEOUT)
+    }
+
+    fn open_with_timeout(root: &Path, busy_timeout: Duration) -> Result<Self> {
         let cache_dir = root.join(".todo-tracker");
-        fs::create_dir_all(&cache_dir)
-            .map_err(|e| format!("Failed to create cache dir: {}", e))?;
         let db_path = cache_dir.join("cache.db");
-        let conn = Connection::open(&db_path)
-            .map_err(|e| format!("Failed to open cache db: {}", e))?;
-        conn.pragma_update(None, "journal_mode", "WAL")
-            .map_err(|e| e.to_string())?;
-        migrations::run_migrations(&conn).map_err(|e| e.to_string())?;
-        Ok(CacheDb { conn })
+        let read_write = || -> Result<Connection> {
+            fs::create_dir_all(&cache_dir)
+                .map_err(|e| TodoError::Cache(format!("Failed to create cache dir: {}", e)))?;
+            let conn = Connection::open(&db_path)
+                .map_err(|e| TodoError::Cache(format!("Failed to open cache db: {}", e)))?;
+            conn.busy_timeout(busy_timeout).map_err(cache_error)?;
+            retry_busy(|| {
+                conn.pragma_update(None, "journal_mode", "WAL")?;
+                migrations::run_migrations(&conn)
+            })
+            .map_err(cache_error)?;
+            Ok(conn)
+        };
+        match read_write() {
+            Ok(conn) => {
+                let lock = fs::OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(cache_dir.join(WRITER_LOCK_FILE))
+                    .ok();
+                let db = CacheDb {
+                    conn,
+                    read_only: Cell::new(false),
+                    lock,
+                    writer: Cell::new(false),
+                    namespace: String::new(),
+                };
+                // Take the lock right away, so a long-running process that
+                // opens the cache first stays the writer
+                db.try_become_writer();
+                Ok(db)
+            }
+            Err(e) if db_path.exists() => {
+                let conn = Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                    .map_err(|_| e)?;
+                conn.busy_timeout(busy_timeout).map_err(cache_error)?;
+                Ok(CacheDb {
+                    conn,
+                    read_only: Cell::new(true),
+                    lock: None,
+                    writer: Cell::new(false),
+                    namespace: String::new(),
+                })
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Take the writer lock if no other process holds it. Readers retry on
+    /// every write, so they take over once the writer exits.
+    fn try_become_writer(&self) -> bool {
+        if self.writer.get() {
+            return true;
+        }
+        let Some(ref lock) = self.lock else {
+            // Nothing to coordinate on, e.g. an in-memory database
+            return true;
+        };
+        if lock.try_lock().is_err() {
+            return false;
+        }
+        self.writer.set(true);
+        // The pid is only for people wondering who holds the lock
+        let mut file = lock;
+        let _ = file.set_len(0).and_then(|_| write!(file, "{}", std::process::id()));
+        true
+    }
+
+    /// Whether this process writes the cache, rather than deferring to
+    /// another `todos` process that holds the writer lock.
+    pub fn is_writer(&self) -> bool {
+        !self.is_read_only() && self.try_become_writer()
+    }
+
+    /// Whether writes to the cache are being skipped, see `open`.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.get()
+    }
+
+    /// Run `write` in one transaction, so concurrent readers never see a
+    /// half-written file. Skipped on a read-only cache or while another
+    /// process holds the writer lock; if the database lock can't be
+    /// taken after retrying, the cache turns read-only for the rest of the run
+    /// rather than stalling every later write.
+    fn write(&self, write: impl Fn(&Transaction) -> rusqlite::Result<()>) -> Result<()> {
+        if !self.is_writer() {
+            return Ok(());
+        }
+        let result = retry_busy(|| {
+            let tx = Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)?;
+            write(&tx)?;
+            tx.commit()
+        });
+        match result {
+            Err(e) if is_busy(&e) => {
+                self.read_only.set(true);
+                Ok(())
+            }
+            other => other.map_err(cache_error),
+        }
+    }
+
+    /// Error for writes that must not be skipped silently.
+    fn require_writable(&self) -> Result<()> {
+        if self.is_read_only() {
+            return Err(TodoError::CacheLocked(
+                "cache is read-only (another todos process may be using it)".to_string(),
+            ));
+        }
+        if !self.try_become_writer() {
+            return Err(TodoError::CacheLocked(
+                "another todos process is writing the cache".to_string(),
+            ));
+        }
+        Ok(())
     }
 
     /// Open an in-memory database (for testing)
     #[cfg(test)]
-    pub fn open_in_memory() -> Result<Self, String> {
-        let conn = Connection::open_in_memory().map_err(|e| e.to_string())?;
-        migrations::run_migrations(&conn).map_err(|e| e.to_string())?;
-        Ok(CacheDb { conn })
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory().map_err(cache_error)?;
+        migrations::run_migrations(&conn).map_err(cache_error)?;
+        Ok(CacheDb {
+            conn,
+            read_only: Cell::new(false),
+            lock: None,
+            writer: Cell::new(true),
+            namespace: String::new(),
+        })
+    }
+
+    /// Read and write file fingerprints and items under `namespace` from now
+    /// on, and record it as used. A namespace without any files yet starts as
+    /// a copy of the most recently used one, so a new branch starts warm.
+    pub fn use_namespace(&mut self, namespace: &str) -> Result<()> {
+        self.namespace = namespace.to_string();
+        let now = unix_now();
+        self.write(|tx| {
+            let empty: bool = tx.query_row(
+                "SELECT NOT EXISTS (SELECT 1 FROM file_fingerprints WHERE namespace = ?1)",
+                [namespace],
+                |row| row.get(0),
+            )?;
+            let previous: Option<String> = tx
+                .query_row(
+                    "SELECT name FROM cache_namespaces WHERE name <> ?1 \
+                     ORDER BY last_used DESC, rowid DESC LIMIT 1",
+                    [namespace],
+                    |row| row.get(0),
+                )
+                .ok();
+            if let (true, Some(previous)) = (empty, previous) {
+                tx.execute(
+                    "INSERT INTO file_fingerprints (namespace, path, mtime, size, hash, lines) \
+                     SELECT ?1, path, mtime, size, hash, lines FROM file_fingerprints \
+                     WHERE namespace = ?2",
+                    [namespace, previous.as_str()],
+                )?;
+                tx.execute(
+                    "INSERT INTO todos (namespace, file_path, line, col, tag, message, author, \
+                     issue, priority, context_line, exemptions, in_test_code) \
+                     SELECT ?1, file_path, line, col, tag, message, author, issue, priority, \
+                     context_line, exemptions, in_test_code FROM todos WHERE namespace = ?2",
+                    [namespace, previous.as_str()],
+                )?;
+            }
+            tx.execute(
+                "INSERT OR REPLACE INTO cache_namespaces (name, last_used) VALUES (?1, ?2)",
+                rusqlite::params![namespace, now],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Recorded namespaces with when each was last used, in seconds since
+    /// the Unix epoch, by name.
+    pub fn namespaces(&self) -> Vec<(String, i64)> {
+        let mut stmt = match self
+            .conn
+            .prepare("SELECT name, last_used FROM cache_namespaces ORDER BY name")
+        {
+            Ok(s) => s,
+            Err(_) => return vec![],
+        };
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)));
+        match rows {
+            Ok(iter) => iter.filter_map(|r| r.ok()).collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// Delete the files and items cached under `namespaces`, and forget them.
+    pub fn remove_namespaces(&self, namespaces: &[String]) -> Result<()> {
+        self.require_writable()?;
+        self.write(|tx| {
+            for namespace in namespaces {
+                tx.execute("DELETE FROM todos WHERE namespace = ?1", [namespace])?;
+                tx.execute("DELETE FROM file_fingerprints WHERE namespace = ?1", [namespace])?;
+                tx.execute("DELETE FROM cache_namespaces WHERE name = ?1", [namespace])?;
+            }
+            Ok(())
+        })
     }
 
     /// Check if a file needs rescanning by comparing mtime and size
     pub fn is_file_fresh(&self, path: &Path, mtime: u64, size: u64) -> bool {
         let path_str = path.display().to_string();
-        let result: Result<(i64, i64), _> = self.conn.query_row(
-            "SELECT mtime, size FROM file_fingerprints WHERE path = ?1",
-            [&path_str],
+        let result: rusqlite::Result<(i64, i64)> = self.conn.query_row(
+            "SELECT mtime, size FROM file_fingerprints WHERE path = ?1 AND namespace = ?2",
+            [&path_str, &self.namespace],
             |row| Ok((row.get(0)?, row.get(1)?)),
         );
         match result {
@@ -49,20 +338,37 @@ impl CacheDb {
         }
     }
 
+    /// Paths of every file with a stored fingerprint
+    pub fn cached_paths(&self) -> Vec<PathBuf> {
+        let mut stmt = match self
+            .conn
+            .prepare("SELECT path FROM file_fingerprints WHERE namespace = ?1 ORDER BY path")
+        {
+            Ok(s) => s,
+            Err(_) => return vec![],
+        };
+        let rows = stmt.query_map([&self.namespace], |row| row.get::<_, String>(0));
+        match rows {
+            Ok(iter) => iter.filter_map(|r| r.ok()).map(PathBuf::from).collect(),
+            Err(_) => vec![],
+        }
+    }
+
     /// Get cached TODOs for a file
     pub fn get_todos(&self, path: &Path) -> Vec<TodoItem> {
         let path_str = path.display().to_string();
         let mut stmt = match self.conn.prepare(
-            "SELECT file_path, line, col, tag, message, author, issue, priority, context_line \
-             FROM todos WHERE file_path = ?1",
+            "SELECT file_path, line, col, tag, message, author, issue, priority, context_line, \
+             exemptions, in_test_code FROM todos WHERE file_path = ?1 AND namespace = ?2",
         ) {
             Ok(s) => s,
             Err(_) => return vec![],
         };
 
-        let items = stmt.query_map([&path_str], |row| {
+        let items = stmt.query_map([&path_str, &self.namespace], |row| {
             let tag_str: String = row.get(3)?;
             let priority_str: Option<String> = row.get(7)?;
+            let exemptions: Option<String> = row.get(9)?;
             Ok(TodoItem {
                 file: PathBuf::from(row.get::<_, String>(0)?),
                 line: row.get::<_, i64>(1)? as usize,
@@ -75,6 +381,16 @@ impl CacheDb {
                 context_line: row.get(8)?,
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: exemptions
+                    .map(|e| e.split(',').map(str::to_string).collect())
+                    .unwrap_or_default(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: row.get::<_, Option<bool>>(10)?.unwrap_or(false),
+                extra: Default::default(),
             })
         });
 
@@ -84,69 +400,389 @@ impl CacheDb {
         }
     }
 
-    /// Store file fingerprint and its TODOs
+    /// Cached lines of code for a file, if recorded
+    pub fn get_lines(&self, path: &Path) -> Option<usize> {
+        let path_str = path.display().to_string();
+        self.conn
+            .query_row(
+                "SELECT lines FROM file_fingerprints WHERE path = ?1 AND namespace = ?2",
+                [&path_str, &self.namespace],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .ok()
+            .flatten()
+            .map(|n| n as usize)
+    }
+
+    /// Content hash stored with a file's fingerprint, if any
+    pub fn get_hash(&self, path: &Path) -> Option<String> {
+        let path_str = path.display().to_string();
+        self.conn
+            .query_row(
+                "SELECT hash FROM file_fingerprints WHERE path = ?1 AND namespace = ?2",
+                [&path_str, &self.namespace],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .ok()
+            .flatten()
+    }
+
+    /// Update a file's mtime and size without touching its TODOs, for files
+    /// whose content is unchanged (e.g. after a fresh checkout)
+    pub fn touch_file(&self, path: &Path, mtime: u64, size: u64) -> Result<()> {
+        let path_str = path.display().to_string();
+        self.write(|tx| {
+            tx.execute(
+                "UPDATE file_fingerprints SET mtime = ?2, size = ?3 \
+                 WHERE path = ?1 AND namespace = ?4",
+                rusqlite::params![path_str, mtime as i64, size as i64, self.namespace],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Store file fingerprint, its lines of code and its TODOs
     pub fn store_file(
         &self,
         path: &Path,
         mtime: u64,
         size: u64,
+        lines: usize,
+        hash: Option<&str>,
         items: &[TodoItem],
-    ) -> Result<(), String> {
+    ) -> Result<()> {
         let path_str = path.display().to_string();
+        self.write(|tx| {
+            // Update fingerprint
+            tx.execute(
+                "INSERT OR REPLACE INTO file_fingerprints \
+                 (namespace, path, mtime, size, hash, lines) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![
+                    self.namespace,
+                    path_str,
+                    mtime as i64,
+                    size as i64,
+                    hash,
+                    lines as i64
+                ],
+            )?;
+
+            // Delete old TODOs for this file
+            tx.execute(
+                "DELETE FROM todos WHERE file_path = ?1 AND namespace = ?2",
+                [&path_str, &self.namespace],
+            )?;
+
+            // Insert new TODOs
+            let mut stmt = tx.prepare(
+                "INSERT INTO todos (file_path, line, col, tag, message, author, issue, priority, \
+                 context_line, exemptions, in_test_code, namespace) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            )?;
+            for item in items {
+                let priority_str = item.priority.as_ref().map(|p| match p {
+                    Priority::Low => "low",
+                    Priority::Medium => "medium",
+                    Priority::High => "high",
+                    Priority::Critical => "critical",
+                });
+                stmt.execute(rusqlite::params![
+                    path_str,
+                    item.line as i64,
+                    item.column as i64,
+                    item.tag.as_str(),
+                    item.message,
+                    item.author,
+                    item.issue,
+                    priority_str,
+                    item.context_line,
+                    (!item.exemptions.is_empty()).then(|| item.exemptions.join(",")),
+                    item.in_test_code,
+                    self.namespace,
+                ])?;
+            }
+            Ok(())
+        })
+    }
 
-        // Update fingerprint
+    /// Append a history row. History is not cache data and survives `clear`.
+    pub fn record_history(&self, taken_at: &str, stats: &ScanStats) -> Result<()> {
+        self.require_writable()?;
+        let by_tag = serde_json::to_string(&stats.by_tag.iter().collect::<std::collections::BTreeMap<_, _>>()).map_err(|e| TodoError::Cache(e.to_string()))?;
         self.conn
             .execute(
-                "INSERT OR REPLACE INTO file_fingerprints (path, mtime, size) VALUES (?1, ?2, ?3)",
-                rusqlite::params![path_str, mtime as i64, size as i64],
+                "INSERT INTO history (taken_at, total_todos, files_with_todos, by_tag) \
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![
+                    taken_at,
+                    stats.total_todos as i64,
+                    stats.files_with_todos as i64,
+                    by_tag
+                ],
             )
-            .map_err(|e| e.to_string())?;
+            .map_err(cache_error)?;
+        Ok(())
+    }
 
-        // Delete old TODOs for this file
-        self.conn
-            .execute("DELETE FROM todos WHERE file_path = ?1", [&path_str])
-            .map_err(|e| e.to_string())?;
+    /// All history rows, oldest first
+    pub fn history(&self) -> Vec<HistoryEntry> {
+        let mut stmt = match self.conn.prepare(
+            "SELECT taken_at, total_todos, files_with_todos, by_tag FROM history ORDER BY id",
+        ) {
+            Ok(s) => s,
+            Err(_) => return vec![],
+        };
 
-        // Insert new TODOs
-        let mut stmt = self
+        let rows = stmt.query_map([], |row| {
+            let by_tag: String = row.get(3)?;
+            Ok(HistoryEntry {
+                taken_at: row.get(0)?,
+                total_todos: row.get::<_, i64>(1)? as usize,
+                files_with_todos: row.get::<_, i64>(2)? as usize,
+                by_tag: serde_json::from_str(&by_tag).unwrap_or_default(),
+            })
+        });
+
+        match rows {
+            Ok(iter) => iter.filter_map(|r| r.ok()).collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// Forget the cached items when they were found with scanner settings
+    /// other than `stamp`, and record `stamp` for the items cached from now on.
+    pub fn use_scanner_stamp(&self, stamp: &str) -> Result<()> {
+        let stored: Option<String> = self
+            .conn
+            .query_row("SELECT value FROM scan_meta WHERE key = 'scanner_stamp'", [], |row| {
+                row.get(0)
+            })
+            .ok();
+        if stored.as_deref().unwrap_or_default() == stamp {
+            return Ok(());
+        }
+        self.write(|tx| {
+            tx.execute_batch(
+                "DELETE FROM todos; DELETE FROM file_fingerprints; DELETE FROM scan_results;",
+            )?;
+            tx.execute(
+                "INSERT OR REPLACE INTO scan_meta (key, value) VALUES ('scanner_stamp', ?1)",
+                [stamp],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Directory records written with the same discovery settings `stamp`.
+    /// Records from other settings are useless, so none are returned.
+    pub fn dir_records(&self, stamp: &str) -> HashMap<PathBuf, DirRecord> {
+        let stored: Option<String> = self
             .conn
-            .prepare(
-                "INSERT INTO todos (file_path, line, col, tag, message, author, issue, priority, context_line) \
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            .query_row(
+                "SELECT value FROM scan_meta WHERE key = 'discovery_stamp'",
+                [],
+                |row| row.get(0),
             )
-            .map_err(|e| e.to_string())?;
-
-        for item in items {
-            let priority_str = item.priority.as_ref().map(|p| match p {
-                Priority::Low => "low",
-                Priority::Medium => "medium",
-                Priority::High => "high",
-                Priority::Critical => "critical",
-            });
-            stmt.execute(rusqlite::params![
-                path_str,
-                item.line as i64,
-                item.column as i64,
-                item.tag.as_str(),
-                item.message,
-                item.author,
-                item.issue,
-                priority_str,
-                item.context_line,
-            ])
-            .map_err(|e| e.to_string())?;
+            .ok();
+        if stored.as_deref() != Some(stamp) {
+            return HashMap::new();
         }
 
+        let mut stmt = match self.conn.prepare(
+            "SELECT path, mtime, ignore_stamp, files, subdirs, recorded_at FROM dir_fingerprints",
+        ) {
+            Ok(s) => s,
+            Err(_) => return HashMap::new(),
+        };
+        let rows = stmt.query_map([], |row| {
+            let files: String = row.get(3)?;
+            let subdirs: String = row.get(4)?;
+            Ok((
+                PathBuf::from(row.get::<_, String>(0)?),
+                DirRecord {
+                    mtime: row.get(1)?,
+                    ignore_stamp: row.get(2)?,
+                    files: serde_json::from_str(&files).unwrap_or_default(),
+                    subdirs: serde_json::from_str(&subdirs).unwrap_or_default(),
+                    recorded_at: row.get(5)?,
+                },
+            ))
+        });
+        match rows {
+            Ok(iter) => iter.filter_map(|r| r.ok()).collect(),
+            Err(_) => HashMap::new(),
+        }
+    }
+
+    /// Replace all directory records with `records`, written under `stamp`.
+    pub fn store_dir_records(
+        &self,
+        stamp: &str,
+        records: &[(PathBuf, DirRecord)],
+    ) -> Result<()> {
+        let rows = records
+            .iter()
+            .map(|(path, record)| {
+                let files = serde_json::to_string(&record.files)
+                    .map_err(|e| TodoError::Cache(e.to_string()))?;
+                let subdirs = serde_json::to_string(&record.subdirs)
+                    .map_err(|e| TodoError::Cache(e.to_string()))?;
+                Ok((path.display().to_string(), record, files, subdirs))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.write(|tx| {
+            tx.execute("DELETE FROM dir_fingerprints", [])?;
+            tx.execute(
+                "INSERT OR REPLACE INTO scan_meta (key, value) VALUES ('discovery_stamp', ?1)",
+                [stamp],
+            )?;
+            let mut stmt = tx.prepare(
+                "INSERT INTO dir_fingerprints (path, mtime, ignore_stamp, files, subdirs, recorded_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for (path, record, files, subdirs) in &rows {
+                stmt.execute(rusqlite::params![
+                    path,
+                    record.mtime,
+                    record.ignore_stamp,
+                    files,
+                    subdirs,
+                    record.recorded_at,
+                ])?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Whole-scan result stored under `key`, if any
+    pub fn get_result(&self, key: &str) -> Option<ScanResult> {
+        let json: String = self
+            .conn
+            .query_row("SELECT result FROM scan_results WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Store a whole-scan result under `key`, keeping only the newest
+    /// `MAX_CACHED_RESULTS` entries.
+    pub fn store_result(&self, key: &str, result: &ScanResult) -> Result<()> {
+        let json = serde_json::to_string(result).map_err(|e| TodoError::Cache(e.to_string()))?;
+        let now = unix_now();
+        self.write(|tx| {
+            tx.execute(
+                "INSERT OR REPLACE INTO scan_results (key, result, stored_at) VALUES (?1, ?2, ?3)",
+                rusqlite::params![key, json, now],
+            )?;
+            tx.execute(
+                "DELETE FROM scan_results WHERE key NOT IN \
+                 (SELECT key FROM scan_results ORDER BY stored_at DESC, rowid DESC LIMIT ?1)",
+                [MAX_CACHED_RESULTS as i64],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Cached blame for a file, if it was stored under the same stamp
+    pub fn get_blame(&self, path: &Path, stamp: &BlameStamp) -> Option<HashMap<usize, BlameInfo>> {
+        let path_str = path.display().to_string();
+        let blame: String = self
+            .conn
+            .query_row(
+                "SELECT blame FROM blame_cache \
+                 WHERE path = ?1 AND last_commit = ?2 AND mtime = ?3 AND size = ?4",
+                rusqlite::params![path_str, stamp.last_commit, stamp.mtime as i64, stamp.size as i64],
+                |row| row.get(0),
+            )
+            .ok()?;
+        serde_json::from_str(&blame).ok()
+    }
+
+    /// Store a file's blame, replacing whatever was cached for it
+    pub fn store_blame(
+        &self,
+        path: &Path,
+        stamp: &BlameStamp,
+        blame: &HashMap<usize, BlameInfo>,
+    ) -> Result<()> {
+        let path_str = path.display().to_string();
+        let json = serde_json::to_string(blame).map_err(|e| TodoError::Cache(e.to_string()))?;
+        self.write(|tx| {
+            tx.execute(
+                "INSERT OR REPLACE INTO blame_cache (path, last_commit, mtime, size, blame) \
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![path_str, stamp.last_commit, stamp.mtime as i64, stamp.size as i64, json],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Write a consistent copy of the whole database, WAL included, to `dest`.
+    pub fn vacuum_into(&self, dest: &Path) -> Result<()> {
+        self.conn
+            .execute("VACUUM INTO ?1", [dest.display().to_string()])
+            .map(|_| ())
+            .map_err(cache_error)
+    }
+
+    /// Replace the database file with `source`, a database file on the same
+    /// filesystem. Requires the writer lock, which is held until the swap is
+    /// done.
+    pub fn replace_with(self, source: &Path) -> Result<()> {
+        self.require_writable()?;
+        let db_path = PathBuf::from(
+            self.conn
+                .path()
+                .ok_or_else(|| TodoError::Cache("cache has no database file".to_string()))?,
+        );
+        let CacheDb { conn, lock, .. } = self;
+        conn.close().map_err(|(_, e)| cache_error(e))?;
+        // The old WAL belongs to the old database and must not be replayed
+        // into the new one
+        remove_sidecars(&db_path)?;
+        fs::rename(source, &db_path)?;
+        drop(lock);
         Ok(())
     }
 
+    /// Delete the cache database at `root` if it isn't a readable database,
+    /// e.g. a truncated file restored by a CI cache step. Returns whether it
+    /// was deleted.
+    pub fn discard_if_corrupt(root: &Path) -> Result<bool> {
+        let db_path = root.join(".todo-tracker").join("cache.db");
+        if !db_path.exists() {
+            return Ok(false);
+        }
+        let check = Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .and_then(|conn| {
+                conn.busy_timeout(BUSY_TIMEOUT)?;
+                conn.query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0))
+            });
+        let corrupt = match check {
+            Ok(status) => status != "ok",
+            Err(e) => matches!(
+                e.sqlite_error_code(),
+                Some(ErrorCode::NotADatabase | ErrorCode::DatabaseCorrupt)
+            ),
+        };
+        if corrupt {
+            remove_sidecars(&db_path)?;
+            fs::remove_file(&db_path)?;
+        }
+        Ok(corrupt)
+    }
+
     /// Clear all cached data
-    pub fn clear(&self) -> Result<(), String> {
+    pub fn clear(&self) -> Result<()> {
+        self.require_writable()?;
         self.conn
             .execute_batch(
-                "DELETE FROM todos; DELETE FROM file_fingerprints; DELETE FROM scan_meta;",
+                "DELETE FROM todos; DELETE FROM file_fingerprints; DELETE FROM cache_namespaces; \
+                 DELETE FROM scan_meta WHERE key <> 'scanner_revision'; \
+                 DELETE FROM scan_results; DELETE FROM dir_fingerprints; DELETE FROM blame_cache;",
             )
-            .map_err(|e| e.to_string())
+            .map_err(cache_error)
     }
 }
 
@@ -170,6 +806,14 @@ mod tests {
             context_line,
             git_author: None,
             git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
         }
     }
 
@@ -195,7 +839,7 @@ mod tests {
             make_todo("src/main.rs", 20, TodoTag::Fixme, "urgent fix"),
         ];
 
-        db.store_file(path, 1000, 500, &items).unwrap();
+        db.store_file(path, 1000, 500, 10, None, &items).unwrap();
 
         let retrieved = db.get_todos(path);
         assert_eq!(retrieved.len(), 2);
@@ -205,6 +849,8 @@ mod tests {
         assert_eq!(retrieved[1].line, 20);
         assert_eq!(retrieved[1].tag, TodoTag::Fixme);
         assert_eq!(retrieved[1].message, "urgent fix");
+        assert_eq!(db.get_lines(path), Some(10));
+        assert_eq!(db.get_lines(Path::new("src/other.rs")), None);
     }
 
     #[test]
@@ -212,7 +858,7 @@ mod tests {
         let db = CacheDb::open_in_memory().unwrap();
         let path = Path::new("src/lib.rs");
 
-        db.store_file(path, 1000, 500, &[]).unwrap();
+        db.store_file(path, 1000, 500, 10, None, &[]).unwrap();
 
         // Same mtime and size: fresh
         assert!(db.is_file_fresh(path, 1000, 500));
@@ -230,13 +876,13 @@ mod tests {
         let path = Path::new("src/main.rs");
 
         let items_v1 = vec![make_todo("src/main.rs", 10, TodoTag::Todo, "old task")];
-        db.store_file(path, 1000, 500, &items_v1).unwrap();
+        db.store_file(path, 1000, 500, 10, None, &items_v1).unwrap();
 
         let items_v2 = vec![
             make_todo("src/main.rs", 5, TodoTag::Hack, "new hack"),
             make_todo("src/main.rs", 15, TodoTag::Bug, "new bug"),
         ];
-        db.store_file(path, 1001, 600, &items_v2).unwrap();
+        db.store_file(path, 1001, 600, 12, None, &items_v2).unwrap();
 
         let retrieved = db.get_todos(path);
         assert_eq!(retrieved.len(), 2);
@@ -254,7 +900,7 @@ mod tests {
         item.author = Some("alice".to_string());
         item.issue = Some("#123".to_string());
 
-        db.store_file(path, 1000, 500, &[item]).unwrap();
+        db.store_file(path, 1000, 500, 10, None, &[item]).unwrap();
 
         let retrieved = db.get_todos(path);
         assert_eq!(retrieved.len(), 1);
@@ -270,13 +916,34 @@ mod tests {
         assert!(retrieved.is_empty());
     }
 
+    #[test]
+    fn test_history_survives_clear() {
+        let db = CacheDb::open_in_memory().unwrap();
+        let mut stats = ScanStats::new();
+        stats.total_todos = 3;
+        stats.files_with_todos = 2;
+        stats.by_tag.insert("TODO".to_string(), 3);
+
+        db.record_history("2026-02-05T000000Z", &stats).unwrap();
+        stats.total_todos = 1;
+        db.record_history("2026-02-06T000000Z", &stats).unwrap();
+        db.clear().unwrap();
+
+        let history = db.history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].taken_at, "2026-02-05T000000Z");
+        assert_eq!(history[0].total_todos, 3);
+        assert_eq!(history[0].by_tag.get("TODO"), Some(&3));
+        assert_eq!(history[1].total_todos, 1);
+    }
+
     #[test]
     fn test_clear() {
         let db = CacheDb::open_in_memory().unwrap();
         let path = Path::new("src/main.rs");
         let items = vec![make_todo("src/main.rs", 10, TodoTag::Todo, "task")];
 
-        db.store_file(path, 1000, 500, &items).unwrap();
+        db.store_file(path, 1000, 500, 10, None, &items).unwrap();
         assert!(db.is_file_fresh(path, 1000, 500));
         assert_eq!(db.get_todos(path).len(), 1);
 
@@ -284,4 +951,210 @@ mod tests {
         assert!(!db.is_file_fresh(path, 1000, 500));
         assert!(db.get_todos(path).is_empty());
     }
+
+    #[test]
+    fn test_namespaces_keep_files_apart() {
+        let mut db = CacheDb::open_in_memory().unwrap();
+        let path = Path::new("src/main.rs");
+        db.use_namespace("main").unwrap();
+        let items = vec![make_todo("src/main.rs", 10, TodoTag::Todo, "on main")];
+        db.store_file(path, 1000, 500, 10, None, &items).unwrap();
+
+        // A new branch starts as a copy of the last one used
+        db.use_namespace("feature").unwrap();
+        assert!(db.is_file_fresh(path, 1000, 500));
+        let items = vec![make_todo("src/main.rs", 12, TodoTag::Fixme, "on feature")];
+        db.store_file(path, 2000, 600, 12, None, &items).unwrap();
+
+        db.use_namespace("main").unwrap();
+        assert!(db.is_file_fresh(path, 1000, 500));
+        assert_eq!(db.get_todos(path)[0].message, "on main");
+        let names: Vec<String> = db.namespaces().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["feature", "main"]);
+
+        db.remove_namespaces(&["feature".to_string()]).unwrap();
+        db.use_namespace("feature").unwrap();
+        assert!(db.is_file_fresh(path, 1000, 500));
+        assert_eq!(db.get_todos(path)[0].message, "on main");
+    }
+
+    #[test]
+    fn test_store_and_get_result() {
+        let db = CacheDb::open_in_memory().unwrap();
+        let result = ScanResult {
+            items: vec![make_todo("src/main.rs", 10, TodoTag::Todo, "task")],
+            stats: ScanStats::new(),
+            metadata: crate::model::ScanMetadata {
+                scan_duration_ms: 5,
+                root_path: PathBuf::from("."),
+                timestamp: "t".to_string(),
+                blame_cache_hits: None,
+            },
+        };
+        assert!(db.get_result("abc").is_none());
+        db.store_result("abc", &result).unwrap();
+        assert_eq!(db.get_result("abc").unwrap().items.len(), 1);
+
+        for n in 0..MAX_CACHED_RESULTS {
+            db.store_result(&format!("k{}", n), &result).unwrap();
+        }
+        assert!(db.get_result("abc").is_none());
+        assert!(db.get_result("k0").is_some());
+
+        db.clear().unwrap();
+        assert!(db.get_result("k0").is_none());
+    }
+
+    #[test]
+    fn test_dir_records_keyed_by_stamp() {
+        let db = CacheDb::open_in_memory().unwrap();
+        let record = DirRecord {
+            mtime: 100,
+            ignore_stamp: String::new(),
+            files: vec!["a.rs".to_string()],
+            subdirs: vec!["tests".to_string()],
+            recorded_at: 200,
+        };
+        db.store_dir_records("v1", &[(PathBuf::from("./src"), record.clone())])
+            .unwrap();
+
+        let records = db.dir_records("v1");
+        assert_eq!(records.get(Path::new("./src")), Some(&record));
+        assert!(db.dir_records("v2").is_empty());
+
+        db.store_dir_records("v1", &[]).unwrap();
+        assert!(db.dir_records("v1").is_empty());
+    }
+
+    #[test]
+    fn test_scanner_stamp_change_forgets_todos() {
+        let db = CacheDb::open_in_memory().unwrap();
+        let path = Path::new("src/main.rs");
+        db.use_scanner_stamp("word").unwrap();
+        db.store_file(path, 1000, 500, 10, None, &[make_todo("src/main.rs", 1, TodoTag::Todo, "a")])
+            .unwrap();
+
+        db.use_scanner_stamp("word").unwrap();
+        assert!(db.is_file_fresh(path, 1000, 500));
+        db.use_scanner_stamp("substring").unwrap();
+        assert!(!db.is_file_fresh(path, 1000, 500));
+        assert!(db.get_todos(path).is_empty());
+    }
+
+    #[test]
+    fn test_writes_give_way_to_a_locked_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = CacheDb::open_with_timeout(dir.path(), Duration::from_millis(20)).unwrap();
+        let path = Path::new("src/main.rs");
+        let items = vec![make_todo("src/main.rs", 1, TodoTag::Todo, "cached")];
+        db.store_file(path, 1000, 500, 10, None, &items).unwrap();
+
+        // Another process holds the write lock
+        let other = Connection::open(dir.path().join(".todo-tracker/cache.db")).unwrap();
+        other.execute_batch("BEGIN EXCLUSIVE").unwrap();
+
+        db.store_file(path, 1001, 600, 12, None, &[]).unwrap();
+        assert!(db.is_read_only());
+        assert!(matches!(db.clear(), Err(TodoError::CacheLocked(_))));
+        // Reads still see the last committed state
+        assert_eq!(db.get_todos(path).len(), 1);
+        assert!(db.is_file_fresh(path, 1000, 500));
+    }
+
+    #[test]
+    fn test_open_falls_back_to_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        CacheDb::open(dir.path()).unwrap();
+        let db_path = dir.path().join(".todo-tracker/cache.db");
+        let other = Connection::open(&db_path).unwrap();
+        other.pragma_update(None, "journal_mode", "DELETE").unwrap();
+        other.execute_batch("BEGIN EXCLUSIVE").unwrap();
+
+        let db = CacheDb::open_with_timeout(dir.path(), Duration::from_millis(20)).unwrap();
+        assert!(db.is_read_only());
+        assert!(db.store_file(Path::new("a.rs"), 1, 1, 1, None, &[]).is_ok());
+        assert!(db.record_history("2026-02-05T000000Z", &ScanStats::new()).is_err());
+    }
+
+    #[test]
+    fn test_touch_file_keeps_todos() {
+        let db = CacheDb::open_in_memory().unwrap();
+        let path = Path::new("src/main.rs");
+        let items = vec![make_todo("src/main.rs", 10, TodoTag::Todo, "test")];
+        db.store_file(path, 1000, 500, 10, Some("abcd"), &items).unwrap();
+        db.touch_file(path, 2000, 500).unwrap();
+        assert!(db.is_file_fresh(path, 2000, 500));
+        assert_eq!(db.get_hash(path).as_deref(), Some("abcd"));
+        assert_eq!(db.get_todos(path).len(), 1);
+    }
+
+    #[test]
+    fn test_blame_cache_keyed_by_stamp() {
+        let db = CacheDb::open_in_memory().unwrap();
+        let path = Path::new("src/main.rs");
+        let stamp = BlameStamp {
+            last_commit: "abc123".to_string(),
+            mtime: 1000,
+            size: 500,
+        };
+        let mut blame = HashMap::new();
+        blame.insert(
+            3,
+            BlameInfo {
+                author: "Alice".to_string(),
+                email: "alice@example.com".to_string(),
+                date: "2026-01-02".to_string(),
+                commit: "abc123".to_string(),
+                incomplete: false,
+            },
+        );
+        db.store_blame(path, &stamp, &blame).unwrap();
+        assert_eq!(db.get_blame(path, &stamp).unwrap()[&3].author, "Alice");
+
+        // An amended commit or an uncommitted edit invalidates the entry
+        let amended = BlameStamp {
+            last_commit: "def456".to_string(),
+            ..stamp.clone()
+        };
+        assert!(db.get_blame(path, &amended).is_none());
+        let edited = BlameStamp { size: 501, ..stamp.clone() };
+        assert!(db.get_blame(path, &edited).is_none());
+
+        db.clear().unwrap();
+        assert!(db.get_blame(path, &stamp).is_none());
+    }
+
+    #[test]
+    fn test_discard_if_corrupt() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!CacheDb::discard_if_corrupt(dir.path()).unwrap());
+        drop(CacheDb::open(dir.path()).unwrap());
+        assert!(!CacheDb::discard_if_corrupt(dir.path()).unwrap());
+
+        let db_path = dir.path().join(".todo-tracker/cache.db");
+        fs::write(&db_path, b"truncated by a cache restore").unwrap();
+        assert!(CacheDb::discard_if_corrupt(dir.path()).unwrap());
+        assert!(!db_path.exists());
+    }
+
+    #[test]
+    fn test_single_writer_election() {
+        let dir = tempfile::tempdir().unwrap();
+        let daemon = CacheDb::open(dir.path()).unwrap();
+        let adhoc = CacheDb::open(dir.path()).unwrap();
+        assert!(daemon.is_writer());
+        assert!(!adhoc.is_writer());
+
+        let path = Path::new("src/main.rs");
+        let items = vec![make_todo("src/main.rs", 1, TodoTag::Todo, "x")];
+        adhoc.store_file(path, 1000, 500, 10, None, &items).unwrap();
+        assert!(daemon.get_todos(path).is_empty());
+        assert!(matches!(adhoc.clear(), Err(TodoError::CacheLocked(_))));
+
+        // Once the writer exits, the next write takes over the lock
+        drop(daemon);
+        adhoc.store_file(path, 1000, 500, 10, None, &items).unwrap();
+        assert!(adhoc.is_writer());
+        assert_eq!(adhoc.get_todos(path).len(), 1);
+    }
 }
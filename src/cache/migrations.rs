@@ -1,6 +1,25 @@
 use rusqlite::Connection;
 
+/// Bumped whenever the scanner starts finding different items in unchanged
+/// files, so caches filled by an older scanner rescan every file once.
+/// 2: license headers are skipped. 3: one item per tag on a line. 4: items
+/// in `#[cfg(test)]` modules are marked as test code.
+const SCANNER_REVISION: &str = "4";
+
 pub fn run_migrations(conn: &Connection) -> Result<(), rusqlite::Error> {
+    // Fingerprints and items are keyed by namespace now; the old tables only
+    // hold cache data, so they are rebuilt empty
+    if table_exists(conn, "file_fingerprints")?
+        && !column_exists(conn, "file_fingerprints", "namespace")?
+    {
+        conn.execute_batch("DROP TABLE file_fingerprints; DROP TABLE IF EXISTS todos;")?;
+    }
+    // Directory records list their subdirectories instead of counting entries
+    if table_exists(conn, "dir_fingerprints")?
+        && !column_exists(conn, "dir_fingerprints", "subdirs")?
+    {
+        conn.execute_batch("DROP TABLE dir_fingerprints;")?;
+    }
     conn.execute_batch(
         "
         CREATE TABLE IF NOT EXISTS scan_meta (
@@ -9,14 +28,18 @@ pub fn run_migrations(conn: &Connection) -> Result<(), rusqlite::Error> {
         );
 
         CREATE TABLE IF NOT EXISTS file_fingerprints (
-            path TEXT PRIMARY KEY,
+            namespace TEXT NOT NULL DEFAULT '',
+            path TEXT NOT NULL,
             mtime INTEGER NOT NULL,
             size INTEGER NOT NULL,
-            hash TEXT
+            hash TEXT,
+            lines INTEGER,
+            PRIMARY KEY (namespace, path)
         );
 
         CREATE TABLE IF NOT EXISTS todos (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
+            namespace TEXT NOT NULL DEFAULT '',
             file_path TEXT NOT NULL,
             line INTEGER NOT NULL,
             col INTEGER NOT NULL,
@@ -25,11 +48,105 @@ pub fn run_migrations(conn: &Connection) -> Result<(), rusqlite::Error> {
             author TEXT,
             issue TEXT,
             priority TEXT,
-            context_line TEXT NOT NULL
+            context_line TEXT NOT NULL,
+            exemptions TEXT,
+            in_test_code INTEGER
         );
 
-        CREATE INDEX IF NOT EXISTS idx_todos_file ON todos(file_path);
+        CREATE INDEX IF NOT EXISTS idx_todos_namespace_file ON todos(namespace, file_path);
+
+        CREATE TABLE IF NOT EXISTS cache_namespaces (
+            name TEXT PRIMARY KEY,
+            last_used INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            taken_at TEXT NOT NULL,
+            total_todos INTEGER NOT NULL,
+            files_with_todos INTEGER NOT NULL,
+            by_tag TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS dir_fingerprints (
+            path TEXT PRIMARY KEY,
+            mtime INTEGER NOT NULL,
+            ignore_stamp TEXT NOT NULL,
+            files TEXT NOT NULL,
+            subdirs TEXT NOT NULL,
+            recorded_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS scan_results (
+            key TEXT PRIMARY KEY,
+            result TEXT NOT NULL,
+            stored_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS blame_cache (
+            path TEXT PRIMARY KEY,
+            last_commit TEXT NOT NULL,
+            mtime INTEGER NOT NULL,
+            size INTEGER NOT NULL,
+            blame TEXT NOT NULL
+        );
     ",
     )?;
+    add_column_if_missing(conn, "file_fingerprints", "lines", "INTEGER")?;
+    if add_column_if_missing(conn, "todos", "exemptions", "TEXT")? {
+        // Cached items predate exemption parsing; rescan every file once
+        conn.execute_batch(
+            "DELETE FROM todos; DELETE FROM file_fingerprints; DELETE FROM scan_results;",
+        )?;
+    }
+    // Filled by scanner revision 4, which clears older caches below
+    add_column_if_missing(conn, "todos", "in_test_code", "INTEGER")?;
+    let revision: Option<String> = conn
+        .query_row("SELECT value FROM scan_meta WHERE key = 'scanner_revision'", [], |row| {
+            row.get(0)
+        })
+        .ok();
+    if revision.as_deref() != Some(SCANNER_REVISION) {
+        conn.execute_batch(
+            "DELETE FROM todos; DELETE FROM file_fingerprints; DELETE FROM scan_results;",
+        )?;
+        conn.execute(
+            "INSERT OR REPLACE INTO scan_meta (key, value) VALUES ('scanner_revision', ?1)",
+            [SCANNER_REVISION],
+        )?;
+    }
     Ok(())
 }
+
+/// `ALTER TABLE ... ADD COLUMN` for caches created before the column existed.
+/// Returns whether the column was added.
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    decl: &str,
+) -> Result<bool, rusqlite::Error> {
+    let exists = column_exists(conn, table, column)?;
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl))?;
+    }
+    Ok(!exists)
+}
+
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|r| r.ok())
+        .any(|name| name == column);
+    Ok(exists)
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool, rusqlite::Error> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
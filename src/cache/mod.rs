@@ -1,4 +1,7 @@
+pub mod archive;
 pub mod db;
 pub mod migrations;
+pub mod result;
 
-pub use db::CacheDb;
+pub use crate::discovery::DirRecord;
+pub use db::{BlameStamp, CacheDb, HistoryEntry};
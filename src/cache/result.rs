@@ -0,0 +1,139 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::git::utils::git_command;
+use crate::model::{fnv1a64, ScanResult};
+
+/// Key for a whole-scan result: the commit checked out at `root` plus a hash
+/// of everything else that shapes the scan (tool version, the scanned
+/// subdirectory, the effective config and the scanner and discovery settings
+/// from `ScanOrchestrator::settings_stamp`, which include the command-line
+/// flags). Returns `None` when `root` is not in a git repository or its
+/// subtree has uncommitted or untracked changes, since the commit alone would
+/// not describe the files on disk.
+pub fn result_key(root: &Path, config: &Config, settings: &str) -> Option<String> {
+    let status = git_command(
+        &["status", "--porcelain", "--", ".", ":(exclude).todo-tracker"],
+        root,
+    )
+    .ok()?;
+    if !status.trim().is_empty() {
+        return None;
+    }
+    let commit = git_command(&["rev-parse", "HEAD"], root).ok()?;
+    let prefix = git_command(&["rev-parse", "--show-prefix"], root).ok()?;
+    let config = serde_json::to_string(config).ok()?;
+    let salt = format!(
+        "{}\0{}\0{}\0{}",
+        env!("CARGO_PKG_VERSION"),
+        prefix.trim(),
+        config,
+        settings
+    );
+    Some(format!("{}-{:016x}", commit.trim(), fnv1a64(salt.as_bytes())))
+}
+
+/// Copy of `result` with item paths made relative to its root, so it can be
+/// reused from a checkout at a different location.
+pub fn relativize(result: &ScanResult) -> ScanResult {
+    let mut result = result.clone();
+    for item in &mut result.items {
+        item.file = PathBuf::from(item.relative_path(&result.metadata.root_path));
+    }
+    result
+}
+
+/// Inverse of `relativize`: re-anchor item paths under `root`.
+pub fn rebase(mut result: ScanResult, root: &Path) -> ScanResult {
+    for item in &mut result.items {
+        item.file = root.join(&item.file);
+    }
+    result.metadata.root_path = root.to_path_buf();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats, TodoItem, TodoTag};
+    use std::process::Command;
+
+    fn make_result(root: &str, file: &str) -> ScanResult {
+        ScanResult {
+            items: vec![TodoItem {
+                tag: TodoTag::Todo,
+                message: "x".to_string(),
+                file: PathBuf::from(file),
+                line: 1,
+                column: 1,
+                author: None,
+                issue: None,
+                priority: None,
+                context_line: String::new(),
+                git_author: None,
+                git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
+            }],
+            stats: ScanStats::default(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from(root),
+                timestamp: String::new(),
+                blame_cache_hits: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_relativize_and_rebase() {
+        let stored = relativize(&make_result("/ci/build-1", "/ci/build-1/src/a.rs"));
+        assert_eq!(stored.items[0].file, PathBuf::from("src/a.rs"));
+        let reused = rebase(stored, Path::new("/ci/build-2"));
+        assert_eq!(reused.items[0].file, PathBuf::from("/ci/build-2/src/a.rs"));
+        assert_eq!(reused.metadata.root_path, PathBuf::from("/ci/build-2"));
+    }
+
+    #[test]
+    fn test_result_key_requires_clean_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        assert!(result_key(root, &Config::default(), "").is_none());
+
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(root)
+                .output()
+                .unwrap()
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.email", "t@example.com"]);
+        git(&["config", "user.name", "T"]);
+        std::fs::write(root.join("a.rs"), "// TODO: x\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-qm", "init"]);
+
+        // Cache files don't make the tree dirty
+        std::fs::create_dir(root.join(".todo-tracker")).unwrap();
+        std::fs::write(root.join(".todo-tracker/cache.db"), "").unwrap();
+        let key = result_key(root, &Config::default(), "").unwrap();
+        assert_eq!(result_key(root, &Config::default(), "").unwrap(), key);
+
+        let config = Config {
+            scan: Some(Default::default()),
+            ..Default::default()
+        };
+        assert_ne!(result_key(root, &config, "").unwrap(), key);
+        assert_ne!(result_key(root, &Config::default(), "depth").unwrap(), key);
+
+        std::fs::write(root.join("b.rs"), "// TODO: y\n").unwrap();
+        assert!(result_key(root, &Config::default(), "").is_none());
+    }
+}
@@ -1,5 +1,7 @@
 use clap::{Parser, Subcommand, ValueEnum};
 
+use crate::config::Config;
+
 #[derive(Parser)]
 #[command(name = "todos", about = "A fast, cross-language TODO linter", version)]
 pub struct Cli {
@@ -10,6 +12,37 @@ pub struct Cli {
     #[arg(long, default_value = "auto", global = true)]
     pub color: ColorMode,
 
+    /// Use only ASCII in text output and progress bars (also chosen
+    /// automatically for TERM=dumb and non-UTF-8 locales)
+    #[arg(long, global = true)]
+    pub ascii: bool,
+
+    /// Print text output messages in full instead of truncating them to the
+    /// terminal width
+    #[arg(long, global = true)]
+    pub full: bool,
+
+    /// Show the source line under each item in text output;
+    /// `--snippets=N` adds N lines of context on either side
+    #[arg(
+        long,
+        global = true,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0"
+    )]
+    pub snippets: Option<usize>,
+
+    /// List items hidden by triage ignore decisions, dimmed, after the text
+    /// output
+    #[arg(long, global = true)]
+    pub show_suppressed: bool,
+
+    /// How text output groups items: by file, or by the component from the
+    /// [components] config section, then file
+    #[arg(long, default_value = "file", global = true)]
+    pub group_by: GroupBy,
+
     /// Filter by tag (comma-separated: TODO,FIXME,HACK)
     #[arg(long, global = true)]
     pub tag: Option<String>,
@@ -30,17 +63,154 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub has_issue: bool,
 
+    /// Only show items with an empty message
+    #[arg(long, global = true)]
+    pub no_message: bool,
+
     /// Path to scan (defaults to current directory)
     #[arg(long, default_value = ".", global = true)]
     pub path: String,
 
-    /// Output format: text, json, csv, markdown, count
+    /// Output format: text, json, csv, tsv, markdown, count, sarif, github-actions,
+    /// azure-pipelines, gitlab, bitbucket, bitbucket-server, teamcity, vscode, junit,
+    /// or auto-ci for the current CI system's format plus a JSON file (see --ci-artifact)
     #[arg(long, default_value = "text", global = true)]
     pub format: String,
 
+    /// Don't print a summary to stderr when json, csv, tsv or sarif output is
+    /// piped
+    #[arg(long, short, global = true)]
+    pub quiet: bool,
+
+    /// Where --format auto-ci writes the result as JSON
+    #[arg(long, default_value = "todos-report.json", global = true)]
+    pub ci_artifact: String,
+
+    /// CSV columns to write, in order (comma-separated: file, line, column,
+    /// tag, message, author, issue, priority, context_line, git_author,
+    /// git_date, git_commit, age). Git columns and age run git blame.
+    #[arg(long, alias = "columns", global = true)]
+    pub csv_columns: Option<String>,
+
+    /// Encrypt json and sarif output, and the --format auto-ci artifact,
+    /// to an age recipient (age:<recipient>); needs `age` on PATH. Only
+    /// scan and list support it
+    #[arg(long, global = true, value_name = "SCHEME:RECIPIENT")]
+    pub encrypt: Option<String>,
+
+    /// Report paths relative to --path rather than the root of the git
+    /// repository containing it
+    #[arg(long, global = true)]
+    pub no_root_resolve: bool,
+
+    /// Make output byte-identical for identical inputs: relative paths,
+    /// canonical order, zero durations and a fixed timestamp, also used
+    /// for csv ages (from SOURCE_DATE_EPOCH when set)
+    #[arg(long, global = true)]
+    pub deterministic: bool,
+
+    /// Print JSON on a single line instead of pretty printing it (stats JSON
+    /// then appends cleanly to an NDJSON log)
+    #[arg(long, global = true)]
+    pub json_compact: bool,
+
+    /// Keep only these JSON fields (comma-separated dotted paths such as
+    /// items.file,items.line,stats; bare names are item fields)
+    #[arg(long, global = true)]
+    pub fields: Option<String>,
+
+    /// Drop these JSON fields (comma-separated, same paths as --fields),
+    /// e.g. --omit context_line
+    #[arg(long, global = true)]
+    pub omit: Option<String>,
+
+    /// Leave items below this level out of SARIF and github-actions output:
+    /// note, warning or error
+    #[arg(long, global = true, value_parser = ["note", "warning", "error"])]
+    pub annotate_min_level: Option<String>,
+
+    /// Annotate at most this many items in github-actions output, most
+    /// severe first, and add a notice counting the rest
+    #[arg(long, global = true)]
+    pub max_annotations: Option<usize>,
+
+    /// Keep only SARIF and github-actions items on lines a pull request
+    /// adds, compared with --annotate-base
+    #[arg(long, global = true)]
+    pub annotate_changed_only: bool,
+
+    /// Base branch for --annotate-changed-only (default: origin/$GITHUB_BASE_REF
+    /// in a GitHub Actions pull request, else the repository's default branch)
+    #[arg(long, global = true)]
+    pub annotate_base: Option<String>,
+
+    /// CSV field separator: a single character, or "tab"
+    #[arg(long, global = true)]
+    pub delimiter: Option<String>,
+
+    /// Follow symbolic links (cycles are skipped and each file is scanned once)
+    #[arg(long, global = true)]
+    pub follow_symlinks: bool,
+
+    /// Also scan dependency and build directories (node_modules, target,
+    /// vendor, ...) that are skipped by default
+    #[arg(long, global = true)]
+    pub no_default_excludes: bool,
+
+    /// Keep at most about this many MB of items in memory, spilling the rest
+    /// to temp files (text, json, csv, tsv, count, github-actions and vscode
+    /// output only; json without --fields or --omit)
+    #[arg(long, global = true)]
+    pub memory_budget: Option<usize>,
+
+    /// Reuse cached directory listings for unchanged directories (faster warm
+    /// scans of very large trees)
+    #[arg(long, global = true)]
+    pub incremental_discovery: bool,
+
+    /// Maximum directory depth to descend below --path
+    #[arg(long, global = true)]
+    pub max_depth: Option<usize>,
+
+    /// Fail instead of scanning more than this many files
+    #[arg(long, global = true)]
+    pub max_files: Option<usize>,
+
+    /// In a shallow clone, fetch more history before blame and diff:
+    /// `--auto-fetch-depth=N` deepens it by N commits, bare or 0 fetches all
+    #[arg(
+        long,
+        global = true,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0"
+    )]
+    pub auto_fetch_depth: Option<u32>,
+
+    /// Have blame look past the commits listed in FILE, such as mass
+    /// reformats (default: [blame] ignore_revs_file, or
+    /// .git-blame-ignore-revs when present); an empty value uses none
+    #[arg(long, global = true, value_name = "FILE")]
+    pub ignore_revs_file: Option<String>,
+
     /// Clear the scan cache before running
     #[arg(long, global = true)]
     pub clear_cache: bool,
+
+    /// Reuse the whole-scan result cached for the current commit, config and
+    /// flags (only when the working tree is clean and ignore files are honored)
+    #[arg(long, global = true)]
+    pub cache_result: bool,
+
+    /// Run the [hooks] post_scan command and post to the [[webhooks]] of a
+    /// .todo-tracker.toml found in the scanned tree (those of the user's own
+    /// config always run)
+    #[arg(long, global = true)]
+    pub allow_hooks: bool,
+
+    /// The config file, loaded once at startup rather than by each step
+    #[arg(skip)]
+    pub config: Config,
 }
 
 #[derive(Subcommand)]
@@ -48,11 +218,19 @@ pub enum Commands {
     /// List all TODOs (default command)
     List,
     /// Scan for TODOs (alias for list)
-    Scan,
+    Scan {
+        /// Rescan only this subdirectory (relative to --path) and take the
+        /// rest of the result from the cache
+        subpath: Option<String>,
+    },
     /// Initialize a .todo-tracker.toml config file
     Init,
     /// Show TODO statistics with charts
-    Stats,
+    Stats {
+        /// Attribute TODOs with git blame and show average and oldest age per author
+        #[arg(long)]
+        blame: bool,
+    },
     /// Compare TODOs between git refs
     Diff {
         /// Git ref range (e.g., main..HEAD) or --staged
@@ -61,24 +239,178 @@ pub enum Commands {
         /// Compare staged changes
         #[arg(long)]
         staged: bool,
+        /// Compare the working tree to a stored snapshot (id or "latest"); no git needed
+        #[arg(long, conflicts_with = "staged")]
+        against_snapshot: Option<String>,
+        /// Report only TODOs on added (or removed) lines of the diff hunks
+        #[arg(long, conflicts_with = "against_snapshot")]
+        hunks: bool,
     },
     /// Run policy checks (for CI)
     Check {
         /// Maximum TODOs allowed
         #[arg(long)]
         max_todos: Option<usize>,
+        /// Maximum TODOs per thousand lines of code
+        #[arg(long)]
+        max_todos_per_kloc: Option<f64>,
+        /// Maximum disabled tests (needs `skipped_tests` in the [scan] config)
+        #[arg(long)]
+        max_skipped_tests: Option<usize>,
+        /// Maximum Rust unwrap/expect/panic! call sites (needs `panic_audit` in the [scan] config)
+        #[arg(long)]
+        max_panic_sites: Option<usize>,
+        /// Leave TODOs in test code out of --max-todos, --max-todos-per-kloc and component budgets
+        #[arg(long)]
+        exempt_test_code: bool,
         /// Tags requiring issue refs (comma-separated)
         #[arg(long)]
         require_issue: Option<String>,
         /// Denied tags (comma-separated)
         #[arg(long)]
         deny: Option<String>,
-        /// Only check files in the diff (requires git)
+        /// Only check files changed since the base branch (origin/$GITHUB_BASE_REF,
+        /// else the default branch; requires git)
         #[arg(long)]
         diff_only: bool,
-        /// Only check staged files
-        #[arg(long)]
+        /// Only check files staged in the index (requires git)
+        #[arg(long, conflicts_with = "diff_only")]
         staged_only: bool,
+        /// Only hold TODOs missing from the baseline file to the policies
+        /// (defaults to .todo-baseline.json in the scan root)
+        #[arg(long, num_args = 0..=1, value_name = "FILE")]
+        baseline: Option<Option<String>>,
+        /// Lint: minimum TODO message length
+        #[arg(long)]
+        min_message_length: Option<usize>,
+        /// Lint: reject TODOs with empty messages
+        #[arg(long)]
+        deny_empty_message: bool,
+        /// Lint: reject generic messages like "fix this"
+        #[arg(long)]
+        deny_generic_message: bool,
+        /// Lint: regex template messages must match
+        #[arg(long)]
+        message_pattern: Option<String>,
+        /// Fail on TODOs whose line was last committed more than this many days ago (git blame)
+        #[arg(long)]
+        max_age_days: Option<u64>,
+        /// Warn about TODOs whose line is older than this many days (git blame)
+        #[arg(long)]
+        warn_after_days: Option<u64>,
+        /// Fail on TODOs whose line is older than this many days (git blame)
+        #[arg(long)]
+        error_after_days: Option<u64>,
+        /// Fail if the normalized TODO list differs from this golden file (.json for JSON, text otherwise)
+        #[arg(long)]
+        against_file: Option<String>,
+        /// Rewrite the golden file from the current scan instead of comparing
+        #[arg(long, requires = "against_file")]
+        update_golden: bool,
+        /// Print each limit's current value, headroom and trend since the
+        /// latest snapshot, whether or not the checks pass
+        #[arg(long)]
+        budget: bool,
+        /// Write rules evaluated, counts, violations and timings to this JSON file
+        #[arg(long)]
+        report_file: Option<String>,
+    },
+    /// Walk TODOs one by one and record priority/author/issue or ignore decisions
+    Triage {
+        /// Include items that already have a triage decision
+        #[arg(long)]
+        all: bool,
+        /// Bulk: set this priority on every matching item without prompting
+        #[arg(long)]
+        set_priority: Option<String>,
+        /// Bulk: set this author on every matching item without prompting
+        #[arg(long)]
+        set_author: Option<String>,
+        /// Bulk: set this issue reference on every matching item without prompting
+        #[arg(long)]
+        set_issue: Option<String>,
+        /// Bulk: mark every matching item as ignored
+        #[arg(long)]
+        ignore: bool,
+        /// Also write decisions back into the source comments
+        #[arg(long)]
+        write_back: bool,
+    },
+    /// List active suppressions (triage ignores and allow: exemption tokens)
+    /// with who added them and how long ago
+    Suppressions,
+    /// Export TODOs for editing in a spreadsheet, or as GitHub issues kept in
+    /// sync through .todo-issues.json
+    Export {
+        /// Export format
+        #[arg(value_parser = ["csv", "github-issues"])]
+        format: String,
+        /// Write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<String>,
+        /// Repository to open issues in, as owner/name (defaults to
+        /// GITHUB_REPOSITORY, then the origin remote)
+        #[arg(long)]
+        repo: Option<String>,
+        /// Print the issue changes without making them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Import triage decisions from an edited export
+    Import {
+        /// Import format
+        #[arg(value_parser = ["csv"])]
+        format: String,
+        /// File to import
+        file: String,
+        /// Also write imported decisions back into the source comments
+        #[arg(long)]
+        write_back: bool,
+    },
+    /// Per-author digest of outstanding TODOs, attributed by git blame
+    Digest {
+        /// Group authors by blame email rather than name
+        #[arg(long)]
+        author_emails: bool,
+        /// Write one digest file per author into this directory
+        #[arg(long)]
+        out_dir: Option<String>,
+        /// Email each digest using the [digest] SMTP settings (implies --author-emails)
+        #[arg(long)]
+        send: bool,
+    },
+    /// Save a timestamped scan result and record it in the history table (for cron/CI)
+    Snapshot {
+        /// Number of snapshots to keep; older ones are deleted
+        #[arg(long, default_value_t = crate::snapshot::DEFAULT_KEEP)]
+        keep: usize,
+        /// List stored snapshots instead of taking one
+        #[arg(long)]
+        list: bool,
+        /// Also record a summary on HEAD as a git note (refs/notes/todo-tracker);
+        /// with --list, list the recorded summaries instead
+        #[arg(long)]
+        git_notes: bool,
+    },
+    /// Manage the scan cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// Manage the baseline of accepted TODOs used by `check --baseline`
+    Baseline {
+        #[command(subcommand)]
+        command: BaselineCommand,
+    },
+    /// Work with the .todo-tracker.toml config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Git hook entry points
+    Hook {
+        #[command(subcommand)]
+        hook: HookCommand,
     },
     /// Show TODOs with git blame information
     Blame {
@@ -88,7 +420,172 @@ pub enum Commands {
         /// Show only TODOs since this date (YYYY-MM-DD)
         #[arg(long)]
         since: Option<String>,
+        /// Show only TODOs up to and including this date (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
+        /// Show only TODOs older than this many days (e.g. 90 or 90d)
+        #[arg(long)]
+        age_over: Option<String>,
+        /// Also fetch the introducing commit's subject and any PR number
+        #[arg(long)]
+        commit_info: bool,
+    },
+    /// Show TODO density per directory as a tree (text) or treemap (--format html)
+    Heatmap {
+        /// Directory levels below --path to break down; deeper directories
+        /// count toward their ancestor
+        #[arg(long, default_value = "3")]
+        depth: usize,
+    },
+    /// Reports on how the TODO count changed over the repository history
+    Report {
+        /// TODOs added and removed by each merged PR (merge commits and
+        /// squash commits with a PR number on HEAD's first-parent history)
+        #[arg(long)]
+        by_pr: bool,
+        /// Only PRs merged on or after this date (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only PRs merged up to and including this date (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
     },
+    /// Clone or update and scan every repository listed in a repos file or
+    /// belonging to a GitHub organization, reporting each one and the totals
+    /// across all of them
+    Multi {
+        /// TOML file with a [[repo]] table per repository (url or path,
+        /// optional name and branch)
+        #[arg(long, required_unless_present = "github_org")]
+        repos: Option<String>,
+        /// Scan every repository of this GitHub organization, authenticating
+        /// with GITHUB_TOKEN or GH_TOKEN (GITHUB_API_URL selects the server)
+        #[arg(long, conflicts_with = "repos")]
+        github_org: Option<String>,
+        /// Include archived repositories of the organization
+        #[arg(long, requires = "github_org")]
+        include_archived: bool,
+        /// Include forks in the organization
+        #[arg(long, requires = "github_org")]
+        include_forks: bool,
+        /// Directory to keep clones in (defaults to the repos file's
+        /// clone_dir, or .todo-tracker/repos/<org>)
+        #[arg(long)]
+        clone_dir: Option<String>,
+        /// Scan existing clones as they are instead of fetching first
+        #[arg(long)]
+        no_update: bool,
+    },
+    /// For each remote branch ahead of the base branch, count the TODOs it
+    /// would add and remove if merged
+    AuditBranches {
+        /// Branch the others merge into (defaults to origin/HEAD, then
+        /// origin/main, origin/master, main or master)
+        #[arg(long)]
+        base: Option<String>,
+    },
+    /// Show when a TODO was introduced, moved between files and removed in
+    /// HEAD's history, with the commits and authors involved
+    Trace {
+        /// The TODO as file:line, file:line@rev for a committed version, or
+        /// its fingerprint (the fingerprint column of `todos export`), which
+        /// also finds removed TODOs
+        target: String,
+    },
+    /// List TODOs whose referenced GitHub issue (#123) is closed, and
+    /// optionally open a reminder issue or post them to Slack
+    Remind {
+        /// Repository the issue numbers belong to, as owner/name (defaults
+        /// to GITHUB_REPOSITORY, then the origin remote)
+        #[arg(long)]
+        repo: Option<String>,
+        /// Open an issue listing the stale TODOs, or update the open one
+        /// labelled stale-todos
+        #[arg(long)]
+        open_issue: bool,
+        /// Post the list to this Slack incoming webhook URL
+        #[arg(long)]
+        slack_webhook: Option<String>,
+    },
+    /// Print one randomly chosen TODO with its surrounding code (combine with
+    /// --priority, --tag etc. to narrow the pool)
+    Random {
+        /// Lines of code to show on either side of the TODO
+        #[arg(long, default_value = "3")]
+        context: usize,
+        /// Seed for a reproducible pick
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Answer JSON-RPC 2.0 requests on stdin/stdout, one per line, for editor
+    /// plugins (methods: query, statsForFile, rescan, subscribe,
+    /// unsubscribe, shutdown)
+    Serve {
+        /// Seconds between rescans while a client is subscribed
+        #[arg(long, default_value = "2")]
+        poll_interval: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HookCommand {
+    /// Check staged TODO additions for issue references (install as .git/hooks/commit-msg)
+    CommitMsg {
+        /// Commit message file passed by git
+        file: String,
+        /// block: reject the commit; trailer: append a count trailer instead
+        #[arg(long, value_parser = ["block", "trailer"])]
+        mode: Option<String>,
+        /// Trailer key for trailer mode
+        #[arg(long)]
+        trailer: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// Populate the cache without printing results (e.g. in a CI cache-restore step)
+    Warm,
+    /// Write the cache to a .tar.gz archive
+    Export {
+        /// Archive to write
+        file: String,
+    },
+    /// Replace the cache with one written by `cache export`
+    Import {
+        /// Archive to read
+        file: String,
+    },
+    /// Remove cached branches that were deleted or, with --older-than, not
+    /// used for a while
+    Gc {
+        /// Also remove branches not scanned for this many days (e.g. 30 or 30d)
+        #[arg(long)]
+        older_than: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BaselineCommand {
+    /// Record the current TODOs as the baseline
+    Write {
+        /// File to write (defaults to .todo-baseline.json in the scan root)
+        #[arg(long, short)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print a JSON Schema of the config file, for completion and validation
+    /// in editors (taplo, Even Better TOML)
+    Schema,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    File,
+    Component,
 }
 
 #[derive(Debug, Clone, ValueEnum)]
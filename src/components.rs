@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::error::{Result, TodoError};
+use crate::model::ScanResult;
+
+/// Label for items outside every configured component, in grouped output and
+/// stats.
+pub const UNASSIGNED: &str = "(no component)";
+
+/// Assigns items to components by path. Patterns use gitignore syntax
+/// relative to the scan root (`services/billing/`, `**/*.proto`); when
+/// several match a file, the longest pattern wins.
+pub struct ComponentMap {
+    /// Longest pattern first
+    patterns: Vec<(Gitignore, String)>,
+}
+
+impl ComponentMap {
+    /// Build the map from the `[components]` config section, which maps
+    /// patterns to component names.
+    pub fn new(root: &Path, config: &BTreeMap<String, String>) -> Result<Self> {
+        let mut patterns = Vec::new();
+        for (pattern, name) in config {
+            let mut builder = GitignoreBuilder::new(root);
+            builder.add_line(None, pattern).map_err(|e| {
+                TodoError::Config(format!("Invalid component pattern '{}': {}", pattern, e))
+            })?;
+            let matcher = builder.build().map_err(|e| {
+                TodoError::Config(format!("Invalid component pattern '{}': {}", pattern, e))
+            })?;
+            patterns.push((pattern.len(), matcher, name.clone()));
+        }
+        // Stable sort keeps equally long patterns in key order
+        patterns.sort_by_key(|p| std::cmp::Reverse(p.0));
+        Ok(ComponentMap {
+            patterns: patterns.into_iter().map(|(_, m, name)| (m, name)).collect(),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// The component owning `path`, which is under the root or relative to it.
+    pub fn component(&self, path: &Path) -> Option<&str> {
+        self.patterns
+            .iter()
+            .find(|(matcher, _)| {
+                let relative = path.strip_prefix(matcher.path()).unwrap_or(path);
+                matcher.matched_path_or_any_parents(relative, false).is_ignore()
+            })
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Set every item's component and recount the per-component stats.
+    pub fn assign(&self, result: &mut ScanResult) {
+        if self.is_empty() {
+            return;
+        }
+        for item in &mut result.items {
+            item.component = self.component(&item.file).map(str::to_string);
+        }
+        result.recompute_stats();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_component_for_path() {
+        let config: BTreeMap<String, String> = [
+            ("services/billing/", "billing"),
+            ("services/billing/legacy/", "billing-legacy"),
+            ("*.proto", "api"),
+            ("web/**", "frontend"),
+        ]
+        .iter()
+        .map(|(p, n)| (p.to_string(), n.to_string()))
+        .collect();
+        let root = PathBuf::from("/repo");
+        let map = ComponentMap::new(&root, &config).unwrap();
+
+        let component = |path: &str| map.component(&root.join(path));
+        assert_eq!(component("services/billing/pay.rs"), Some("billing"));
+        assert_eq!(component("services/billing/legacy/old.rs"), Some("billing-legacy"));
+        assert_eq!(component("protos/billing/v1.proto"), Some("api"));
+        assert_eq!(component("web/src/app.ts"), Some("frontend"));
+        assert_eq!(component("tools/build.rs"), None);
+        // Relative paths work too
+        assert_eq!(map.component(Path::new("web/index.html")), Some("frontend"));
+    }
+}
@@ -1,11 +1,41 @@
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 
+use crate::digest::DigestConfig;
+use crate::error::{Result, TodoError};
+use crate::escalation::EscalationConfig;
+use crate::hook::HookConfig;
+use crate::lint::LintConfig;
+use crate::output::theme::ThemeConfig;
+use crate::post_scan::HooksConfig;
+use crate::test_code::TestCodeConfig;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     pub scan: Option<ScanConfig>,
     pub output: Option<OutputConfig>,
     pub filter: Option<FilterConfig>,
+    pub lint: Option<LintConfig>,
+    pub escalation: Option<EscalationConfig>,
+    /// Path patterns (gitignore syntax) to component names
+    pub components: Option<BTreeMap<String, String>>,
+    /// Maximum TODOs per component, enforced by `check`
+    pub component_budgets: Option<BTreeMap<String, usize>>,
+    pub test_code: Option<TestCodeConfig>,
+    pub cache: Option<CacheConfig>,
+    pub blame: Option<BlameConfig>,
+    /// URLs `todos serve` posts to when TODOs appear or disappear
+    pub webhooks: Option<Vec<WebhookConfig>>,
+    pub digest: Option<DigestConfig>,
+    pub hook: Option<HookConfig>,
+    pub hooks: Option<HooksConfig>,
+    pub theme: Option<ThemeConfig>,
+    /// The file was named explicitly or is the user's own config, rather than
+    /// a `.todo-tracker.toml` found in the tree being scanned. Commands and
+    /// URLs from an untrusted file are only used with `--allow-hooks`.
+    #[serde(skip)]
+    pub trusted: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -13,12 +43,36 @@ pub struct ScanConfig {
     pub max_file_size: Option<u64>,
     pub respect_gitignore: Option<bool>,
     pub tags: Option<Vec<String>>,
+    /// Where tags may start and end: "word" (default) or "substring"
+    pub tag_boundary: Option<String>,
+    /// Characters allowed directly before a tag in word mode
+    pub tag_prefix_chars: Option<String>,
+    /// Also match lowercase and mixed-case tags such as `todo:` and `Fixme(`,
+    /// when followed by `:` or `(`
+    pub case_insensitive: Option<bool>,
+    /// Extra tags per language name, e.g. `"C#" = ["UNDONE"]`
+    pub language_tags: Option<BTreeMap<String, Vec<String>>>,
+    /// Also report disabled tests (`#[ignore]`, `it.skip`, `@Disabled`, ...)
+    pub skipped_tests: Option<bool>,
+    /// Also report deprecations (`#[deprecated]`, `@Deprecated`, `@deprecated`, ...)
+    pub deprecations: Option<bool>,
+    /// Also report Rust `unwrap`, `expect` and `panic!` call sites; needs a
+    /// build with the `precise` feature
+    pub panic_audit: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct OutputConfig {
     pub format: Option<String>,
     pub color: Option<String>,
+    /// Item indicators in text and markdown: "none", "emoji" or "ascii"
+    pub glyphs: Option<String>,
+    /// Emoji overrides per tag when `glyphs = "emoji"`
+    pub tag_emoji: Option<HashMap<String, String>>,
+    /// URL of an item's line, e.g.
+    /// `https://github.com/acme/app/blob/{commit}/{path}#L{line}`; JSON
+    /// output then gives each item a `url`
+    pub link_template: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -26,12 +80,41 @@ pub struct FilterConfig {
     pub exclude_patterns: Option<Vec<String>>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CacheConfig {
+    /// Keep cached files and items per git branch, so switching branches
+    /// doesn't throw away the other branch's cache
+    pub branch_namespaces: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BlameConfig {
+    /// Commits blame looks past, one hash per line as in git's
+    /// `--ignore-revs-file`; relative to the repository root
+    pub ignore_revs_file: Option<String>,
+}
+
+/// One `[[webhooks]]` entry.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Only report items with these tags
+    pub tags: Option<Vec<String>>,
+}
+
 impl Config {
     /// Load configuration from an explicit path, or by searching up from CWD,
-    /// or from the user's home config directory. Returns default if nothing found.
+    /// or from the user's home config directory. Returns default if nothing
+    /// found, or if the file can't be read or parsed, after a warning naming it.
     pub fn load(explicit_path: Option<&str>) -> Config {
+        Self::warn_or_default(Self::try_load(explicit_path))
+    }
+
+    /// Like [`Config::load`], but an explicit path that can't be read or
+    /// parsed is an error. A discovered file that can't be only warns.
+    pub fn try_load(explicit_path: Option<&str>) -> Result<Config> {
         if let Some(path) = explicit_path {
-            return Self::load_from_file(Path::new(path)).unwrap_or_default();
+            return Self::load_trusted(Path::new(path));
         }
 
         // Walk up from CWD looking for .todo-tracker.toml
@@ -40,7 +123,7 @@ impl Config {
             while let Some(d) = dir {
                 let candidate = d.join(".todo-tracker.toml");
                 if candidate.is_file() {
-                    return Self::load_from_file(&candidate).unwrap_or_default();
+                    return Ok(Self::warn_or_default(Self::load_from_file(&candidate)));
                 }
                 dir = d.parent().map(|p| p.to_path_buf());
             }
@@ -50,11 +133,11 @@ impl Config {
         if let Some(config_dir) = Self::user_config_dir() {
             let candidate = config_dir.join("todo-tracker").join("config.toml");
             if candidate.is_file() {
-                return Self::load_from_file(&candidate).unwrap_or_default();
+                return Ok(Self::warn_or_default(Self::load_trusted(&candidate)));
             }
         }
 
-        Config::default()
+        Ok(Config::default())
     }
 
     /// Returns a commented TOML template suitable for writing to a new config file.
@@ -65,14 +148,88 @@ impl Config {
 # [scan]
 # max_file_size = 1048576  # 1MB
 # respect_gitignore = true
-# tags = ["TODO", "FIXME", "HACK", "BUG", "XXX"]
+# tags = ["TODO", "FIXME", "HACK", "BUG", "XXX"]  # replaces the default tags
+# tag_boundary = "word"           # word, or "substring" to match inside words too
+# tag_prefix_chars = "/*#!;([{<\"'`,|"  # may come directly before a tag in word mode
+# case_insensitive = false        # also match todo, Todo, fixme, ...
+# skipped_tests = false           # also report #[ignore], it.skip, @Disabled, xit, ...
+# deprecations = false            # also report #[deprecated], @Deprecated, @deprecated, ...
+# panic_audit = false             # also report Rust unwrap/expect/panic! (precise builds)
+
+# Extra tags for files of one language, by language name
+# [scan.language_tags]
+# "C#" = ["UNDONE"]
+# JavaScript = ["PENDING"]
 
 # [output]
-# format = "text"  # text, json, csv, markdown, count
-# color = "auto"   # auto, always, never
+# format = "text"  # text, json, csv, tsv, markdown, count; --format overrides
+# color = "auto"   # auto, always, never; --color overrides
+# glyphs = "none"  # none, emoji (per tag and priority) or ascii ([!!!] priority markers)
+# link_template = "https://github.com/acme/app/blob/{commit}/{path}#L{line}"  # url in JSON
+# [output.tag_emoji]
+# NOTE = "🗒"
 
 # [filter]
-# exclude_patterns = []
+# exclude_patterns = []  # gitignore-style, e.g. ["*.min.js", "generated/"]
+
+# [lint]
+# min_length = 10
+# deny_empty = true
+# deny_generic = true             # "fix this", "later", "cleanup", ...
+# generic_phrases = ["look into it"]
+# pattern = '^\[[A-Z]+-\d+\]'     # messages must match this regex
+
+# [escalation]                    # by git blame age, in `check` and SARIF output
+# warn_after_days = 365
+# error_after_days = 730
+
+# [components]                    # gitignore-style patterns; the longest match wins
+# "services/billing/" = "billing"
+# "web/**" = "frontend"
+# "*.proto" = "api"
+
+# [component_budgets]             # max TODOs per component, in `check`
+# billing = 20
+
+# [test_code]                     # test files, replacing the built-in patterns
+# paths = ["tests/", "*_test.go", "__tests__/", "*.spec.ts"]
+
+# [cache]
+# branch_namespaces = false       # keep a cache per git branch; see `todos cache gc`
+
+# [blame]
+# ignore_revs_file = ".git-blame-ignore-revs"  # commits blame looks past, e.g. reformats
+
+# Posted to by `todos serve` when TODOs appear or disappear
+# [[webhooks]]
+# url = "https://hooks.example.com/todos"
+# tags = ["NOCOMMIT", "FIXME"]    # only these tags; all when omitted
+
+# [digest]
+# smtp_host = "localhost"         # plain SMTP relay used by `todos digest --send`
+# smtp_port = 25
+# allow_remote = false            # send through a host other than this machine, unencrypted
+# from = "todos@example.com"
+# subject = "Your outstanding TODOs"
+
+# [hook]
+# commit_msg = "block"            # block, or "trailer" to append a count instead
+# trailer = "Introduces-TODO"
+
+# [hooks]
+# post_scan = "./scripts/add-owners.py"  # gets the scan result as JSON on stdin;
+#                                        # JSON it prints replaces the result
+
+# [theme]
+# name = "default"                # default, high-contrast, monochrome
+# symbols = false                 # prefix tags with symbols (on in monochrome)
+# path = "bold"
+# line = "cyan dimmed"
+# metadata = "dimmed"
+# snippet = "dimmed"              # source lines shown by --snippets
+# [theme.tags]
+# TODO = "yellow"
+# NOTE = "bright blue bold"
 "#
         .to_string()
     }
@@ -93,11 +250,30 @@ impl Config {
             .unwrap_or_else(|| "text".to_string())
     }
 
-    fn load_from_file(path: &Path) -> Result<Config, String> {
-        let contents = std::fs::read_to_string(path)
-            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
-        toml::from_str(&contents)
-            .map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))
+    fn warn_or_default(loaded: Result<Config>) -> Config {
+        loaded.unwrap_or_else(|e| {
+            eprintln!("warning: {}; using the default config", e);
+            Config::default()
+        })
+    }
+
+    fn load_trusted(path: &Path) -> Result<Config> {
+        let mut config = Self::load_from_file(path)?;
+        config.trusted = true;
+        Ok(config)
+    }
+
+    fn load_from_file(path: &Path) -> Result<Config> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            TodoError::Config(format!("Failed to read config file {}: {}", path.display(), e))
+        })?;
+        toml::from_str(&contents).map_err(|e| {
+            TodoError::Config(format!(
+                "Invalid config file {}: {}",
+                path.display(),
+                describe_toml_error(&contents, &e)
+            ))
+        })
     }
 
     fn user_config_dir() -> Option<PathBuf> {
@@ -119,6 +295,35 @@ impl Config {
     }
 }
 
+/// A TOML error with the key it is about, e.g. `scan.max_file_size:
+/// invalid type: string "big", expected u64 (line 2)`.
+fn describe_toml_error(contents: &str, error: &toml::de::Error) -> String {
+    let message = error.message().trim();
+    let Some(span) = error.span() else {
+        return message.to_string();
+    };
+    let before = contents.get(..span.start).unwrap_or(contents);
+    let line = before.matches('\n').count() + 1;
+    // The innermost table header at or above the error, and the key on its line
+    let table = before
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|l| l.starts_with('['))
+        .map(|l| l.trim_matches(|c| c == '[' || c == ']').trim());
+    let key = contents
+        .lines()
+        .nth(line - 1)
+        .and_then(|l| l.split_once('='))
+        .map(|(key, _)| key.trim())
+        .filter(|key| !key.is_empty() && !key.starts_with('['));
+    match (table, key) {
+        (Some(table), Some(key)) => format!("{}.{}: {} (line {})", table, key, message, line),
+        (Some(key), None) | (None, Some(key)) => format!("{}: {} (line {})", key, message, line),
+        (None, None) => format!("{} (line {})", message, line),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,6 +393,28 @@ color = "never"
         assert!(config.scan.is_none());
     }
 
+    #[test]
+    fn test_try_load_missing_explicit_path_fails() {
+        let err = Config::try_load(Some("/nonexistent/path/config.toml")).unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/path/config.toml"));
+    }
+
+    #[test]
+    fn test_try_load_invalid_config_names_file_and_key() {
+        let dir = TempDir::new().unwrap();
+        let config_path = dir.path().join("bad-config.toml");
+        fs::write(&config_path, "this is not valid toml [[[").unwrap();
+        let err = Config::try_load(Some(config_path.to_str().unwrap())).unwrap_err();
+        assert!(err.to_string().contains("bad-config.toml"), "{}", err);
+
+        fs::write(&config_path, "[output]\nformat = \"json\"\n\n[scan]\nmax_file_size = \"big\"\n")
+            .unwrap();
+        let err = Config::try_load(Some(config_path.to_str().unwrap())).unwrap_err().to_string();
+        assert!(err.contains("bad-config.toml"), "{}", err);
+        assert!(err.contains("scan.max_file_size: invalid type"), "{}", err);
+        assert!(err.contains("(line 5)"), "{}", err);
+    }
+
     #[test]
     fn test_load_partial_config() {
         let dir = TempDir::new().unwrap();
@@ -236,6 +463,30 @@ exclude_patterns = ["*.min.js", "vendor/**"]
         assert!(config.scan.is_none());
         assert!(config.output.is_none());
         assert!(config.filter.is_none());
+        assert!(config.lint.is_none());
+    }
+
+    #[test]
+    fn test_load_with_lint_config() {
+        let dir = TempDir::new().unwrap();
+        let config_path = dir.path().join("lint.toml");
+        fs::write(
+            &config_path,
+            r#"
+[lint]
+min_length = 12
+deny_generic = true
+pattern = '^\[[A-Z]+-\d+\]'
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(Some(config_path.to_str().unwrap()));
+        let lint = config.lint.unwrap();
+        assert_eq!(lint.min_length, Some(12));
+        assert_eq!(lint.deny_generic, Some(true));
+        assert!(lint.deny_empty.is_none());
+        assert!(lint.compiled_pattern().unwrap().is_some());
     }
 
     #[test]
@@ -0,0 +1,259 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Result, TodoError};
+
+/// A calendar date (proleptic Gregorian, UTC) without external dependencies.
+/// Ordering is chronological.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+/// Seconds since the Unix epoch, or 0 if the clock is before it.
+pub fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn is_leap_year(y: i32) -> bool {
+    (y % 4 == 0 && y % 100 != 0) || y % 400 == 0
+}
+
+fn days_in_month(y: i32, m: u32) -> u32 {
+    match m {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(y) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+impl Date {
+    /// Build a date, returning `None` for out-of-range months or days.
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Date> {
+        if day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Date { year, month, day })
+    }
+
+    /// Parse a strict `YYYY-MM-DD` date.
+    pub fn parse(s: &str) -> Result<Date> {
+        let invalid = || TodoError::InvalidDate(format!("'{}' (expected YYYY-MM-DD)", s));
+        let parts: Vec<&str> = s.trim().split('-').collect();
+        if parts.len() != 3
+            || parts[0].len() != 4
+            || parts[1].len() != 2
+            || parts[2].len() != 2
+            || !parts.iter().all(|p| p.chars().all(|c| c.is_ascii_digit()))
+        {
+            return Err(invalid());
+        }
+        let year = parts[0].parse().map_err(|_| invalid())?;
+        let month = parts[1].parse().map_err(|_| invalid())?;
+        let day = parts[2].parse().map_err(|_| invalid())?;
+        Date::new(year, month, day).ok_or_else(invalid)
+    }
+
+    /// Date of a Unix timestamp, in UTC.
+    pub fn from_timestamp(secs: i64) -> Date {
+        Date::from_days(secs.div_euclid(86400))
+    }
+
+    /// Today's date, in UTC.
+    pub fn today() -> Date {
+        Date::from_timestamp(unix_now())
+    }
+
+    /// Days since 1970-01-01 (negative before the epoch).
+    pub fn days_since_epoch(&self) -> i64 {
+        // Howard Hinnant's days_from_civil
+        let y = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let m = self.month as i64;
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146097 + doe - 719468
+    }
+
+    fn from_days(days: i64) -> Date {
+        // Howard Hinnant's civil_from_days
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        Date { year, month, day }
+    }
+
+    /// Whole days from `self` to `later` (negative if `later` is earlier).
+    pub fn days_until(&self, later: Date) -> i64 {
+        later.days_since_epoch() - self.days_since_epoch()
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = TodoError;
+
+    fn from_str(s: &str) -> Result<Date> {
+        Date::parse(s)
+    }
+}
+
+/// Parse a day count such as `30` or `30d`.
+pub fn parse_days(s: &str) -> Result<u64> {
+    s.trim()
+        .trim_end_matches(['d', 'D'])
+        .parse()
+        .map_err(|_| TodoError::InvalidDate(format!("'{}' is not a number of days (e.g. 30d)", s)))
+}
+
+/// Date-based selection for blame results. Items whose date is missing or
+/// unparsable never match an active filter.
+#[derive(Debug, Clone, Default)]
+pub struct DateFilter {
+    pub since: Option<Date>,
+    pub until: Option<Date>,
+    pub age_over_days: Option<u64>,
+}
+
+impl DateFilter {
+    pub fn is_empty(&self) -> bool {
+        self.since.is_none() && self.until.is_none() && self.age_over_days.is_none()
+    }
+
+    /// `since` and `until` are inclusive; `age_over_days` requires the item to
+    /// be strictly older than that many days as of `today`.
+    pub fn matches(&self, date: Option<&str>, today: Date) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let Some(date) = date.and_then(|d| Date::parse(d).ok()) else {
+            return false;
+        };
+        if self.since.is_some_and(|since| date < since) {
+            return false;
+        }
+        if self.until.is_some_and(|until| date > until) {
+            return false;
+        }
+        if let Some(days) = self.age_over_days {
+            if date.days_until(today) <= days as i64 {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valid() {
+        let d = Date::parse("2024-02-29").unwrap();
+        assert_eq!(d.to_string(), "2024-02-29");
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed() {
+        for bad in [
+            "2023-02-29",
+            "2024-13-01",
+            "2024-1-01",
+            "20240101",
+            "yesterday",
+            "2024-01-00",
+            "",
+        ] {
+            assert!(Date::parse(bad).is_err(), "{} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_timestamp_round_trip() {
+        assert_eq!(Date::from_timestamp(0).to_string(), "1970-01-01");
+        assert_eq!(Date::from_timestamp(1709164800).to_string(), "2024-02-29");
+        assert_eq!(Date::from_timestamp(-1).to_string(), "1969-12-31");
+        assert_eq!(Date::from_timestamp(946684800).to_string(), "2000-01-01");
+        assert_eq!(Date::from_timestamp(1709251199).to_string(), "2024-02-29");
+        assert_eq!(Date::from_timestamp(1709251200).to_string(), "2024-03-01");
+        let d = Date::parse("2023-10-15").unwrap();
+        assert_eq!(d.days_since_epoch() * 86400, 1697328000);
+    }
+
+    #[test]
+    fn test_is_leap_year() {
+        assert!(is_leap_year(2000)); // divisible by 400
+        assert!(!is_leap_year(1900)); // divisible by 100 but not 400
+        assert!(is_leap_year(2024)); // divisible by 4 but not 100
+        assert!(!is_leap_year(2023)); // not divisible by 4
+    }
+
+    #[test]
+    fn test_ordering_and_days_until() {
+        let a = Date::parse("2023-12-31").unwrap();
+        let b = Date::parse("2024-03-01").unwrap();
+        assert!(a < b);
+        assert_eq!(a.days_until(b), 61);
+        assert_eq!(b.days_until(a), -61);
+    }
+
+    #[test]
+    fn test_parse_days() {
+        assert_eq!(parse_days("30").unwrap(), 30);
+        assert_eq!(parse_days("90d").unwrap(), 90);
+        assert!(parse_days("a month").is_err());
+    }
+
+    #[test]
+    fn test_date_filter() {
+        let today = Date::parse("2024-06-30").unwrap();
+        let filter = DateFilter {
+            since: Some(Date::parse("2024-01-01").unwrap()),
+            until: Some(Date::parse("2024-06-01").unwrap()),
+            age_over_days: Some(30),
+        };
+        assert!(filter.matches(Some("2024-01-01"), today));
+        assert!(filter.matches(Some("2024-05-15"), today));
+        assert!(!filter.matches(Some("2023-12-31"), today));
+        assert!(!filter.matches(Some("2024-06-02"), today));
+        // Exactly 30 days old is not over 30
+        let recent = DateFilter {
+            age_over_days: Some(30),
+            ..Default::default()
+        };
+        assert!(!recent.matches(Some("2024-05-31"), today));
+        assert!(recent.matches(Some("2024-05-30"), today));
+    }
+
+    #[test]
+    fn test_date_filter_rejects_missing_and_malformed() {
+        let today = Date::parse("2024-06-30").unwrap();
+        let filter = DateFilter {
+            since: Some(Date::parse("2000-01-01").unwrap()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(None, today));
+        assert!(!filter.matches(Some("unknown"), today));
+        assert!(DateFilter::default().matches(None, today));
+    }
+}
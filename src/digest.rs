@@ -0,0 +1,429 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TodoError};
+use crate::model::{BlameInfo, TodoItem};
+
+/// `[digest]` config section. Sending speaks plain SMTP without TLS or
+/// authentication, which suits local relays; other hosts need `allow_remote`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DigestConfig {
+    pub smtp_host: Option<String>,
+    pub smtp_port: Option<u16>,
+    /// Send through an SMTP server that isn't on this machine, such as an
+    /// internal smarthost. Mail to it travels unencrypted.
+    pub allow_remote: Option<bool>,
+    /// Envelope and header sender address
+    pub from: Option<String>,
+    pub subject: Option<String>,
+}
+
+impl DigestConfig {
+    pub fn get_subject(&self) -> String {
+        self.subject
+            .clone()
+            .unwrap_or_else(|| "Your outstanding TODOs".to_string())
+    }
+}
+
+/// Outstanding items owned by one author, as attributed by git blame.
+#[derive(Debug, Clone)]
+pub struct AuthorDigest {
+    pub name: String,
+    pub email: Option<String>,
+    pub items: Vec<TodoItem>,
+}
+
+impl AuthorDigest {
+    /// File-name friendly identifier for the author.
+    pub fn slug(&self) -> String {
+        let source = self.email.as_deref().unwrap_or(&self.name);
+        let slug: String = source
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+            .collect();
+        slug.trim_matches('-').to_string()
+    }
+}
+
+/// Group items by blame author. With `by_email`, authors are keyed by email so
+/// one person committing under several names gets a single digest. Items
+/// without blame data are left out. Digests are sorted by item count,
+/// descending, and each digest lists its oldest items first.
+pub fn build_digests(
+    items: &[TodoItem],
+    blame: &[Option<BlameInfo>],
+    by_email: bool,
+) -> Vec<AuthorDigest> {
+    let mut groups: BTreeMap<String, AuthorDigest> = BTreeMap::new();
+    for (item, info) in items.iter().zip(blame) {
+        let Some(info) = info else { continue };
+        let key = if by_email && !info.email.is_empty() {
+            info.email.to_lowercase()
+        } else {
+            info.author.clone()
+        };
+        let mut item = item.clone();
+        item.git_author = Some(info.author.clone());
+        item.git_date = Some(info.date.clone());
+        groups
+            .entry(key)
+            .or_insert_with(|| AuthorDigest {
+                name: info.author.clone(),
+                email: (!info.email.is_empty()).then(|| info.email.clone()),
+                items: Vec::new(),
+            })
+            .items
+            .push(item);
+    }
+
+    let mut digests: Vec<AuthorDigest> = groups.into_values().collect();
+    for digest in &mut digests {
+        digest.items.sort_by(|a, b| a.git_date.cmp(&b.git_date));
+    }
+    digests.sort_by_key(|d| std::cmp::Reverse(d.items.len()));
+    digests
+}
+
+fn heading(digest: &AuthorDigest) -> String {
+    match digest.email {
+        Some(ref email) => format!("{} <{}>", digest.name, email),
+        None => digest.name.clone(),
+    }
+}
+
+pub fn render_markdown(digest: &AuthorDigest, root: &Path) -> String {
+    let mut out = String::new();
+    writeln!(out, "# TODO digest for {}", heading(digest)).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "You have **{}** outstanding item(s):", digest.items.len()).unwrap();
+    writeln!(out).unwrap();
+    for item in &digest.items {
+        writeln!(
+            out,
+            "- **{}** `{}:{}` {} *(since {})*",
+            item.tag,
+            item.relative_path(root),
+            item.line,
+            item.message,
+            item.git_date.as_deref().unwrap_or("unknown")
+        )
+        .unwrap();
+    }
+    out
+}
+
+pub fn render_text(digest: &AuthorDigest, root: &Path) -> String {
+    let mut out = String::new();
+    writeln!(out, "TODO digest for {}", heading(digest)).unwrap();
+    writeln!(out, "{} outstanding item(s):", digest.items.len()).unwrap();
+    for item in &digest.items {
+        writeln!(
+            out,
+            "  {} {}:{} [{}] {}",
+            item.git_date.as_deref().unwrap_or("unknown"),
+            item.relative_path(root),
+            item.line,
+            item.tag,
+            item.message
+        )
+        .unwrap();
+    }
+    out
+}
+
+fn smtp_expect<R: BufRead>(reader: &mut R, code: &str) -> Result<()> {
+    // Multi-line replies use "250-..." for all but the last line
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(TodoError::Smtp("server closed the connection".to_string()));
+        }
+        if !line.starts_with(code) {
+            return Err(TodoError::Smtp(format!(
+                "Unexpected SMTP reply (wanted {}): {}",
+                code,
+                line.trim_end()
+            )));
+        }
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok(());
+        }
+    }
+}
+
+/// Reject header values that would break out of their header line.
+fn header_value<'a>(name: &str, value: &'a str) -> Result<&'a str> {
+    if value.contains(['\r', '\n']) {
+        return Err(TodoError::Config(format!(
+            "[digest] {} must not contain line breaks",
+            name
+        )));
+    }
+    Ok(value)
+}
+
+/// The addresses of the SMTP server. Unless `allow_remote`, they must all be
+/// loopback addresses, since the conversation isn't encrypted.
+fn smtp_addrs(host: &str, port: u16, allow_remote: bool) -> Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| TodoError::Smtp(format!("Failed to resolve {}: {}", host, e)))?
+        .collect();
+    if !allow_remote && addrs.iter().any(|addr| !addr.ip().is_loopback()) {
+        return Err(TodoError::Config(format!(
+            "[digest] smtp_host {} is not on this machine; mail is sent without TLS, so set \
+             allow_remote = true to send through it anyway",
+            host
+        )));
+    }
+    Ok(addrs)
+}
+
+/// Send one plain-text message over SMTP.
+pub fn send_smtp(config: &DigestConfig, to: &str, subject: &str, body: &str) -> Result<()> {
+    let host = config.smtp_host.as_deref().ok_or_else(|| {
+        TodoError::Config("[digest] smtp_host is required to send digests".to_string())
+    })?;
+    let from = config.from.as_deref().ok_or_else(|| {
+        TodoError::Config("[digest] from is required to send digests".to_string())
+    })?;
+    let from = header_value("from", from)?;
+    let to = header_value("recipient", to)?;
+    let subject = header_value("subject", subject)?;
+    let port = config.smtp_port.unwrap_or(25);
+    let addrs = smtp_addrs(host, port, config.allow_remote.unwrap_or(false))?;
+
+    let stream = TcpStream::connect(&addrs[..])
+        .map_err(|e| TodoError::Smtp(format!("Failed to connect to {}:{}: {}", host, port, e)))?;
+    stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    smtp_expect(&mut reader, "220")?;
+    write!(writer, "HELO todo-tracker\r\n")?;
+    smtp_expect(&mut reader, "250")?;
+    write!(writer, "MAIL FROM:<{}>\r\n", from)?;
+    smtp_expect(&mut reader, "250")?;
+    write!(writer, "RCPT TO:<{}>\r\n", to)?;
+    smtp_expect(&mut reader, "250")?;
+    write!(writer, "DATA\r\n")?;
+    smtp_expect(&mut reader, "354")?;
+
+    write!(
+        writer,
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n",
+        from, to, subject
+    )?;
+    for line in body.lines() {
+        // Dot-stuffing per RFC 5321 section 4.5.2
+        if line.starts_with('.') {
+            write!(writer, ".")?;
+        }
+        write!(writer, "{}\r\n", line)?;
+    }
+    write!(writer, ".\r\n")?;
+    smtp_expect(&mut reader, "250")?;
+    write!(writer, "QUIT\r\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TodoTag;
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+
+    fn make_item(file: &str, line: usize, message: &str) -> TodoItem {
+        TodoItem {
+            tag: TodoTag::Todo,
+            message: message.to_string(),
+            file: PathBuf::from(file),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    fn blame(author: &str, email: &str, date: &str) -> Option<BlameInfo> {
+        Some(BlameInfo {
+            author: author.to_string(),
+            email: email.to_string(),
+            date: date.to_string(),
+            commit: "abc".to_string(),
+            incomplete: false,
+        })
+    }
+
+    #[test]
+    fn test_build_digests_by_name_and_email() {
+        let items = vec![
+            make_item("src/a.rs", 1, "one"),
+            make_item("src/a.rs", 2, "two"),
+            make_item("src/b.rs", 3, "three"),
+            make_item("src/b.rs", 4, "unblamed"),
+        ];
+        let infos = vec![
+            blame("Alice", "alice@example.com", "2024-05-01"),
+            blame("Alice Smith", "Alice@example.com", "2023-01-01"),
+            blame("Bob", "bob@example.com", "2024-01-01"),
+            None,
+        ];
+
+        let by_name = build_digests(&items, &infos, false);
+        assert_eq!(by_name.len(), 3);
+
+        let by_email = build_digests(&items, &infos, true);
+        assert_eq!(by_email.len(), 2);
+        assert_eq!(by_email[0].items.len(), 2);
+        assert_eq!(by_email[0].email.as_deref(), Some("alice@example.com"));
+        // Oldest first
+        assert_eq!(by_email[0].items[0].message, "two");
+    }
+
+    #[test]
+    fn test_render_markdown_and_text() {
+        let items = vec![make_item("./src/a.rs", 7, "refactor parser")];
+        let infos = vec![blame("Alice", "alice@example.com", "2024-05-01")];
+        let digest = &build_digests(&items, &infos, true)[0];
+
+        let md = render_markdown(digest, Path::new("."));
+        assert!(md.starts_with("# TODO digest for Alice <alice@example.com>"));
+        assert!(md.contains("- **TODO** `src/a.rs:7` refactor parser *(since 2024-05-01)*"));
+
+        let text = render_text(digest, Path::new("."));
+        assert!(text.contains("2024-05-01 src/a.rs:7 [TODO] refactor parser"));
+        assert_eq!(digest.slug(), "alice-example-com");
+    }
+
+    #[test]
+    fn test_send_smtp_conversation() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut transcript = String::new();
+            let mut in_data = false;
+            write!(writer, "220 ready\r\n").unwrap();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                transcript.push_str(&line);
+                if in_data {
+                    if line == ".\r\n" {
+                        in_data = false;
+                        write!(writer, "250 ok\r\n").unwrap();
+                    }
+                    continue;
+                }
+                let reply = match line.trim_end() {
+                    "DATA" => {
+                        in_data = true;
+                        "354 go ahead\r\n"
+                    }
+                    "QUIT" => break,
+                    l if l.starts_with("HELO") => "250-hello\r\n250 ok\r\n",
+                    _ => "250 ok\r\n",
+                };
+                write!(writer, "{}", reply).unwrap();
+            }
+            transcript
+        });
+
+        let config = DigestConfig {
+            smtp_host: Some("127.0.0.1".to_string()),
+            smtp_port: Some(port),
+            from: Some("todos@example.com".to_string()),
+            subject: None,
+            allow_remote: None,
+        };
+        send_smtp(&config, "alice@example.com", &config.get_subject(), "hi\n.dot\n").unwrap();
+
+        let transcript = server.join().unwrap();
+        assert!(transcript.contains("RCPT TO:<alice@example.com>"));
+        assert!(transcript.contains("Subject: Your outstanding TODOs"));
+        assert!(transcript.contains("\r\n..dot\r\n"));
+    }
+
+    #[test]
+    fn test_send_smtp_rejects_header_line_breaks() {
+        let config = DigestConfig {
+            smtp_host: Some("127.0.0.1".to_string()),
+            smtp_port: Some(1),
+            from: Some("todos@example.com".to_string()),
+            subject: None,
+            allow_remote: None,
+        };
+        let err = send_smtp(&config, "a@b.c", "hi\r\nBcc: x@y.z", "b").unwrap_err();
+        assert!(err.to_string().contains("subject must not contain line breaks"));
+        let err = send_smtp(&config, "a@b.c\nBcc: x@y.z", "s", "b").unwrap_err();
+        assert!(err.to_string().contains("recipient"));
+    }
+
+    #[test]
+    fn test_send_smtp_errors() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            write!(stream, "554 no service\r\n").unwrap();
+        });
+        let mut config = DigestConfig {
+            smtp_host: Some("127.0.0.1".to_string()),
+            smtp_port: Some(port),
+            from: Some("todos@example.com".to_string()),
+            subject: None,
+            allow_remote: None,
+        };
+        let err = send_smtp(&config, "a@b.c", "s", "b").unwrap_err();
+        assert!(matches!(err, TodoError::Smtp(_)), "{}", err);
+        assert!(err.to_string().contains("554 no service"), "{}", err);
+        server.join().unwrap();
+
+        // Nothing listens on the port any more
+        let err = send_smtp(&config, "a@b.c", "s", "b").unwrap_err();
+        assert!(matches!(err, TodoError::Smtp(_)), "{}", err);
+
+        config.smtp_host = Some("192.0.2.1".to_string());
+        let err = send_smtp(&config, "a@b.c", "s", "b").unwrap_err();
+        assert!(err.to_string().contains("allow_remote"), "{}", err);
+    }
+
+    #[test]
+    fn test_smtp_addrs_allows_loopback_only_by_default() {
+        assert!(smtp_addrs("127.0.0.1", 25, false).is_ok());
+        assert!(smtp_addrs("::1", 25, false).is_ok());
+        assert!(smtp_addrs("192.0.2.1", 25, false).is_err());
+        assert!(smtp_addrs("192.0.2.1", 25, true).is_ok());
+    }
+
+    #[test]
+    fn test_send_smtp_requires_host() {
+        let err = send_smtp(&DigestConfig::default(), "a@b.c", "s", "b").unwrap_err();
+        assert!(err.to_string().contains("smtp_host"));
+    }
+}
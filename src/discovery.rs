@@ -1,10 +1,53 @@
+use std::collections::HashMap;
 use std::fs;
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
+use ignore::overrides::{Override, OverrideBuilder};
 use ignore::WalkBuilder;
 
-use crate::error::Result;
+#[cfg(feature = "cache")]
+use crate::cache::CacheDb;
+use crate::date::unix_now;
+use crate::error::{Result, TodoError};
+use crate::ordering::{compare_paths, path_sort_key};
+
+/// Stand-in for builds without the `cache` feature, where the walk never
+/// gets a cache to reuse directory records from
+#[cfg(not(feature = "cache"))]
+enum CacheDb {}
+
+#[cfg(not(feature = "cache"))]
+impl CacheDb {
+    fn dir_records(&self, _stamp: &str) -> HashMap<PathBuf, DirRecord> {
+        match *self {}
+    }
+
+    fn store_dir_records(
+        &self,
+        _stamp: &str,
+        _records: &[(PathBuf, DirRecord)],
+    ) -> Result<()> {
+        match *self {}
+    }
+}
+
+/// Stored state of one directory for incremental discovery.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirRecord {
+    /// Directory mtime, in seconds
+    pub mtime: i64,
+    /// Stamp of the ignore files in the directory
+    pub ignore_stamp: String,
+    /// Names of files directly inside that passed discovery
+    pub files: Vec<String>,
+    /// Names of the subdirectories discovery descended into
+    pub subdirs: Vec<String>,
+    /// When the record was written, in seconds
+    pub recorded_at: i64,
+}
+
 
 const BINARY_EXTENSIONS: &[&str] = &[
     "exe", "dll", "so", "dylib", "o", "obj", "bin", "a", "lib", "png", "jpg", "jpeg", "gif",
@@ -12,10 +55,37 @@ const BINARY_EXTENSIONS: &[&str] = &[
     "class", "pyc", "pdb",
 ];
 
+/// Dependency and build output directories skipped even without a
+/// `.gitignore` (e.g. in an extracted source archive). Matched by directory
+/// name at any depth below the root.
+pub const DEFAULT_EXCLUDES: &[&str] = &[
+    "node_modules",
+    "bower_components",
+    "jspm_packages",
+    "vendor",
+    "target",
+    "__pycache__",
+    "venv",
+    "site-packages",
+    "Pods",
+];
+
+/// Asked whether to keep going once discovery passes the confirmation
+/// threshold; receives the root and the number of files found so far.
+pub type ConfirmFn = Box<dyn Fn(&Path, usize) -> bool + Send + Sync>;
+
 pub struct FileDiscovery {
     root: PathBuf,
     max_file_size: u64,
     respect_gitignore: bool,
+    follow_symlinks: bool,
+    default_excludes: bool,
+    /// Gitignore-style patterns of paths to skip, relative to the root
+    exclude_patterns: Vec<String>,
+    max_depth: Option<usize>,
+    max_files: Option<usize>,
+    incremental: bool,
+    confirm: Option<(usize, ConfirmFn)>,
 }
 
 impl FileDiscovery {
@@ -24,6 +94,13 @@ impl FileDiscovery {
             root: root.into(),
             max_file_size: 1_048_576,
             respect_gitignore: true,
+            follow_symlinks: false,
+            default_excludes: true,
+            exclude_patterns: Vec::new(),
+            max_depth: None,
+            max_files: None,
+            incremental: false,
+            confirm: None,
         }
     }
 
@@ -37,26 +114,193 @@ impl FileDiscovery {
         self
     }
 
+    /// Whether `.gitignore` and the other ignore files are honored.
+    pub fn respects_gitignore(&self) -> bool {
+        self.respect_gitignore
+    }
+
+    /// Follow symbolic links. Symlink cycles are detected and skipped, and a
+    /// file reachable through several links (or hard links) is scanned once,
+    /// under the path that sorts first.
+    pub fn with_follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Skip `DEFAULT_EXCLUDES` directories (on by default).
+    pub fn with_default_excludes(mut self, enabled: bool) -> Self {
+        self.default_excludes = enabled;
+        self
+    }
+
+    /// Skip paths matching these gitignore-style patterns (e.g. `*.log`,
+    /// `generated/`), on top of the ignore files.
+    pub fn with_exclude_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.exclude_patterns = patterns;
+        self
+    }
+
+    /// Don't descend more than `depth` directories below the root.
+    pub fn with_max_depth(mut self, depth: Option<usize>) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Fail instead of scanning more than `count` files.
+    pub fn with_max_files(mut self, count: Option<usize>) -> Self {
+        self.max_files = count;
+        self
+    }
+
+    /// Let `discover_cached` skip listing directories that haven't changed
+    /// since the previous run. Off by default.
+    pub fn with_incremental(mut self, enabled: bool) -> Self {
+        self.incremental = enabled;
+        self
+    }
+
+    /// Call `confirm` once when more than `threshold` files have been found;
+    /// discovery aborts if it returns false.
+    pub fn with_confirm(mut self, threshold: usize, confirm: ConfirmFn) -> Self {
+        self.confirm = Some((threshold, confirm));
+        self
+    }
+
     pub fn root(&self) -> &Path {
         &self.root
     }
 
     pub fn discover(&self) -> Result<Vec<PathBuf>> {
-        let mut files = Vec::new();
+        self.discover_under(&self.root)
+    }
+
+    /// Discover files below `dir` only, with the same rules as `discover`.
+    /// `dir` should be the root or a path inside it.
+    pub fn discover_under(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        self.walk(dir, None)
+    }
 
-        let walker = WalkBuilder::new(&self.root)
+    /// Discover files, reusing the cached file list of every directory whose
+    /// mtime, entry count and ignore files are unchanged since the last run
+    /// (see `with_incremental`). Without incremental discovery enabled this
+    /// is `discover`.
+    #[cfg(feature = "cache")]
+    pub fn discover_cached(&self, cache: Option<&CacheDb>) -> Result<Vec<PathBuf>> {
+        match cache {
+            Some(cache) if self.incremental => self.walk(&self.root, Some(cache)),
+            _ => self.discover(),
+        }
+    }
+
+    /// Settings other than the root that change which files are discovered.
+    pub fn settings_stamp(&self) -> String {
+        format!(
+            "{}:{}:{}:{:?}:{:?}:{}",
+            self.max_file_size,
+            self.respect_gitignore,
+            self.default_excludes,
+            self.exclude_patterns,
+            self.max_depth,
+            self.follow_symlinks
+        )
+    }
+
+    /// The exclude patterns as walker overrides. Overrides only ever
+    /// exclude here, so paths they don't match are left to the ignore files.
+    fn exclude_overrides(&self) -> Result<Override> {
+        let mut builder = OverrideBuilder::new(&self.root);
+        for pattern in &self.exclude_patterns {
+            builder.add(&format!("!{}", pattern)).map_err(|e| {
+                TodoError::Config(format!("Invalid exclude pattern '{}': {}", pattern, e))
+            })?;
+        }
+        builder
+            .build()
+            .map_err(|e| TodoError::Config(format!("Invalid exclude patterns: {}", e)))
+    }
+
+    fn walk(&self, dir: &Path, dir_cache: Option<&CacheDb>) -> Result<Vec<PathBuf>> {
+        let mut files: Vec<PathBuf> = Vec::new();
+        let mut seen: HashMap<FileId, usize> = HashMap::new();
+
+        // Directory records made under other settings are not reused
+        let stamp = format!("{}:{}", self.root.display(), self.settings_stamp());
+        let records = Arc::new(dir_cache.map(|c| c.dir_records(&stamp)).unwrap_or_default());
+        let dirs: Arc<Mutex<HashMap<PathBuf, DirState>>> = Arc::default();
+        if dir_cache.is_some() {
+            // The walk root doesn't go through filter_entry
+            let state = DirState::check(dir, records.get(dir), true);
+            dirs.lock().unwrap().insert(dir.to_path_buf(), state);
+        }
+
+        let default_excludes = self.default_excludes;
+        let incremental = dir_cache.is_some();
+        let walk_records = Arc::clone(&records);
+        let walk_dirs = Arc::clone(&dirs);
+        let mut walker = WalkBuilder::new(dir);
+        walker
             .git_ignore(self.respect_gitignore)
             .add_custom_ignore_filename(".todoignore")
-            .build();
+            .max_depth(self.max_depth)
+            .follow_links(self.follow_symlinks)
+            .filter_entry(move |entry| {
+                let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+                // The walk root is never excluded, so `--path vendor` still works
+                if default_excludes
+                    && entry.depth() > 0
+                    && is_dir
+                    && entry
+                        .file_name()
+                        .to_str()
+                        .is_some_and(|name| DEFAULT_EXCLUDES.contains(&name))
+                {
+                    return false;
+                }
+                if !incremental || entry.depth() == 0 {
+                    return true;
+                }
+
+                let path = entry.path();
+                let mut dirs = walk_dirs.lock().unwrap();
+                let parent = path.parent().and_then(|p| dirs.get(p)).cloned();
+                if is_dir {
+                    let parent_ignore_ok = parent.as_ref().is_some_and(|p| p.ignore_ok);
+                    let state =
+                        DirState::check(path, walk_records.get(path), parent_ignore_ok);
+                    // A subtree where nothing changed isn't walked at all; the
+                    // cached file lists of its directories are used instead
+                    let mut subtree = Vec::new();
+                    if unchanged_subtree(path, state.clone(), &walk_records, &mut subtree) {
+                        dirs.extend(subtree);
+                        return false;
+                    }
+                    dirs.insert(path.to_path_buf(), state);
+                    true
+                } else {
+                    // Files of unchanged directories come from the cache
+                    !parent.is_some_and(|p| p.reuse)
+                }
+            });
+        if !self.exclude_patterns.is_empty() {
+            walker.overrides(self.exclude_overrides()?);
+        }
+        // With GIT_DIR set the work tree has no `.git` of its own, which the
+        // walker looks for before applying .gitignore files and the excludes
+        if let Some(git_dir) = std::env::var_os("GIT_DIR").filter(|_| self.respect_gitignore) {
+            walker.require_git(false);
+            let _ = walker.add_ignore(Path::new(&git_dir).join("info").join("exclude"));
+        }
+        let walker = walker.build();
 
         for entry in walker {
+            // Symlink loops surface here as errors and are skipped
             let entry = match entry {
                 Ok(e) => e,
                 Err(_) => continue,
             };
 
             // Skip directories
-            if entry.file_type().map_or(true, |ft| !ft.is_file()) {
+            if entry.file_type().is_none_or(|ft| !ft.is_file()) {
                 continue;
             }
 
@@ -81,12 +325,257 @@ impl FileDiscovery {
                 continue;
             }
 
-            files.push(path);
+            self.push_file(&mut files, &mut seen, path, &metadata)?;
         }
 
-        files.sort();
+        if let Some(cache) = dir_cache {
+            let dirs = std::mem::take(&mut *dirs.lock().unwrap());
+            for (dir, state) in &dirs {
+                if !state.reuse {
+                    continue;
+                }
+                for name in &records[dir].files {
+                    let path = dir.join(name);
+                    // Content edits don't touch the directory mtime, so the
+                    // size limit is re-checked; binary sniffing is not redone
+                    match fs::metadata(&path) {
+                        Ok(m) if m.is_file() && m.len() <= self.max_file_size => {
+                            self.push_file(&mut files, &mut seen, path, &m)?
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            record_dirs(cache, &stamp, dirs, &files);
+        }
+
+        files.sort_by_cached_key(|path| path_sort_key(path));
         Ok(files)
     }
+
+    /// Add an accepted file, applying symlink dedup and the file limits.
+    fn push_file(
+        &self,
+        files: &mut Vec<PathBuf>,
+        seen: &mut HashMap<FileId, usize>,
+        path: PathBuf,
+        metadata: &fs::Metadata,
+    ) -> Result<()> {
+        if self.follow_symlinks {
+            if let Some(id) = file_id(&path, metadata) {
+                if let Some(&index) = seen.get(&id) {
+                    if compare_paths(&path, &files[index]).is_lt() {
+                        files[index] = path;
+                    }
+                    return Ok(());
+                }
+                seen.insert(id, files.len());
+            }
+        }
+
+        files.push(path);
+
+        if let Some(max) = self.max_files {
+            if files.len() > max {
+                return Err(TodoError::LimitExceeded(format!(
+                    "more than {} files under {} (raise --max-files or narrow --path)",
+                    max,
+                    self.root.display()
+                )));
+            }
+        }
+        if let Some((threshold, ref confirm)) = self.confirm {
+            if files.len() == threshold + 1 && !confirm(&self.root, files.len()) {
+                return Err(TodoError::LimitExceeded(format!(
+                    "scan of {} aborted after {} files",
+                    self.root.display(),
+                    files.len()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What incremental discovery knows about a directory during a walk.
+#[derive(Debug, Clone)]
+struct DirState {
+    /// Current fingerprint, `None` if the directory couldn't be read
+    current: Option<DirRecord>,
+    /// This directory's and every ancestor's ignore files are unchanged
+    ignore_ok: bool,
+    /// The cached file list can be used instead of listing the directory
+    reuse: bool,
+}
+
+impl DirState {
+    fn check(
+        dir: &Path,
+        cached: Option<&DirRecord>,
+        parent_ignore_ok: bool,
+    ) -> DirState {
+        let current = dir_fingerprint(dir);
+        let (ignore_ok, reuse) = match (&current, cached) {
+            (Some(now), Some(then)) => {
+                let ignore_ok = parent_ignore_ok && now.ignore_stamp == then.ignore_stamp;
+                // An mtime in the same second as the record can hide a later
+                // change (mtimes are compared in whole seconds)
+                let reuse =
+                    ignore_ok && now.mtime == then.mtime && then.mtime < then.recorded_at;
+                (ignore_ok, reuse)
+            }
+            _ => (false, false),
+        };
+        DirState {
+            current,
+            ignore_ok,
+            reuse,
+        }
+    }
+}
+
+/// Collect the states of `dir` and of every directory below it that the
+/// last walk descended into, as long as all of them can reuse their records.
+/// Only directory metadata is read, so an unchanged subtree is never listed.
+fn unchanged_subtree(
+    dir: &Path,
+    state: DirState,
+    records: &HashMap<PathBuf, DirRecord>,
+    subtree: &mut Vec<(PathBuf, DirState)>,
+) -> bool {
+    if !state.reuse {
+        return false;
+    }
+    let ignore_ok = state.ignore_ok;
+    subtree.push((dir.to_path_buf(), state));
+    records[dir].subdirs.iter().all(|name| {
+        let path = dir.join(name);
+        let state = DirState::check(&path, records.get(&path), ignore_ok);
+        unchanged_subtree(&path, state, records, subtree)
+    })
+}
+
+const IGNORE_FILES: &[&str] = &[".gitignore", ".todoignore", ".ignore"];
+
+/// The `info/exclude` file of the repository whose work tree is `dir`. In a
+/// linked worktree `.git` is a file naming the worktree's git directory, and
+/// the excludes live in the main repository's.
+fn git_exclude_file(dir: &Path) -> Option<PathBuf> {
+    let dot_git = dir.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git.join("info").join("exclude"));
+    }
+    let contents = fs::read_to_string(&dot_git).ok()?;
+    let git_dir = dir.join(contents.strip_prefix("gitdir:")?.trim());
+    let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common) => git_dir.join(common.trim()),
+        Err(_) => git_dir,
+    };
+    Some(common_dir.join("info").join("exclude"))
+}
+
+fn mtime_secs(metadata: &fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Current mtime and ignore-file stamp of a directory. Added, removed and
+/// renamed entries all change the mtime, so the directory isn't listed.
+fn dir_fingerprint(dir: &Path) -> Option<DirRecord> {
+    let metadata = fs::metadata(dir).ok()?;
+    let exclude = git_exclude_file(dir).map(|path| (".git/info/exclude", path));
+    let ignore_stamp = IGNORE_FILES
+        .iter()
+        .map(|name| (*name, dir.join(name)))
+        .chain(exclude)
+        .filter_map(|(name, path)| {
+            let m = fs::metadata(path).ok()?;
+            let nanos = m
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos())
+                .unwrap_or(0);
+            Some(format!("{}:{}:{}", name, nanos, m.len()))
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    Some(DirRecord {
+        mtime: mtime_secs(&metadata),
+        ignore_stamp,
+        files: Vec::new(),
+        subdirs: Vec::new(),
+        recorded_at: 0,
+    })
+}
+
+/// Store the fingerprint and accepted files of every walked directory.
+fn record_dirs(
+    cache: &CacheDb,
+    stamp: &str,
+    dirs: HashMap<PathBuf, DirState>,
+    files: &[PathBuf],
+) {
+    let now = unix_now();
+    let mut by_dir: HashMap<&Path, Vec<String>> = HashMap::new();
+    for file in files {
+        if let (Some(parent), Some(name)) = (file.parent(), file.file_name()) {
+            by_dir
+                .entry(parent)
+                .or_default()
+                .push(name.to_string_lossy().into_owned());
+        }
+    }
+    let mut subdirs: HashMap<PathBuf, Vec<String>> = HashMap::new();
+    for dir in dirs.keys() {
+        if let (Some(parent), Some(name)) = (dir.parent(), dir.file_name()) {
+            if dirs.contains_key(parent) {
+                subdirs
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push(name.to_string_lossy().into_owned());
+            }
+        }
+    }
+    let records: Vec<(PathBuf, DirRecord)> = dirs
+        .into_iter()
+        .filter_map(|(dir, state)| {
+            let mut record = state.current?;
+            record.files = by_dir.remove(dir.as_path()).unwrap_or_default();
+            record.subdirs = subdirs.remove(&dir).unwrap_or_default();
+            record.recorded_at = now;
+            Some((dir, record))
+        })
+        .collect();
+    // A failed write only costs the next run its shortcut
+    let _ = cache.store_dir_records(stamp, &records);
+}
+
+/// Identity of the physical file behind a path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum FileId {
+    /// Device and inode
+    #[cfg(unix)]
+    Inode(u64, u64),
+    /// Fully resolved path, where no portable file id is available. Catches
+    /// symlinks but not hard links.
+    #[cfg_attr(unix, allow(dead_code))]
+    Canonical(PathBuf),
+}
+
+#[cfg(unix)]
+fn file_id(_path: &Path, metadata: &fs::Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Some(FileId::Inode(metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(path: &Path, _metadata: &fs::Metadata) -> Option<FileId> {
+    path.canonicalize().ok().map(FileId::Canonical)
 }
 
 fn is_binary_extension(path: &Path) -> bool {
@@ -137,8 +626,8 @@ mod tests {
     #[test]
     fn test_discover_skips_binary_extensions() {
         let dir = create_test_dir();
-        fs::write(dir.path().join("image.png"), &[0x89, 0x50, 0x4E, 0x47]).unwrap();
-        fs::write(dir.path().join("program.exe"), &[0x4D, 0x5A, 0x00]).unwrap();
+        fs::write(dir.path().join("image.png"), [0x89, 0x50, 0x4E, 0x47]).unwrap();
+        fs::write(dir.path().join("program.exe"), [0x4D, 0x5A, 0x00]).unwrap();
 
         let discovery = FileDiscovery::new(dir.path());
         let files = discovery.discover().unwrap();
@@ -206,4 +695,236 @@ mod tests {
         assert_eq!(discovery.max_file_size, 500);
         assert!(!discovery.respect_gitignore);
     }
+
+    #[test]
+    fn test_max_depth() {
+        let dir = create_test_dir();
+        let discovery = FileDiscovery::new(dir.path()).with_max_depth(Some(1));
+        let files = discovery.discover().unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|f| f.parent() == Some(dir.path())));
+    }
+
+    #[test]
+    fn test_max_files() {
+        let dir = create_test_dir();
+        let err = FileDiscovery::new(dir.path())
+            .with_max_files(Some(2))
+            .discover()
+            .unwrap_err();
+        assert!(err.to_string().contains("more than 2 files"));
+        let files = FileDiscovery::new(dir.path())
+            .with_max_files(Some(3))
+            .discover()
+            .unwrap();
+        assert_eq!(files.len(), 3);
+    }
+
+    #[test]
+    fn test_confirm_threshold() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let dir = create_test_dir();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let seen = calls.clone();
+        let files = FileDiscovery::new(dir.path())
+            .with_confirm(
+                1,
+                Box::new(move |_, count| {
+                    assert_eq!(count, 2);
+                    seen.fetch_add(1, Ordering::SeqCst);
+                    true
+                }),
+            )
+            .discover()
+            .unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let declined = FileDiscovery::new(dir.path())
+            .with_confirm(1, Box::new(|_, _| false))
+            .discover();
+        assert!(declined.is_err());
+    }
+
+    #[test]
+    fn test_default_excludes() {
+        let dir = create_test_dir();
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        fs::write(dir.path().join("node_modules/pkg/index.js"), "// TODO: dep").unwrap();
+        fs::create_dir_all(dir.path().join("sub/vendor")).unwrap();
+        fs::write(dir.path().join("sub/vendor/lib.go"), "// TODO: dep").unwrap();
+
+        let files = FileDiscovery::new(dir.path()).discover().unwrap();
+        assert_eq!(files.len(), 3);
+
+        let files = FileDiscovery::new(dir.path())
+            .with_default_excludes(false)
+            .discover()
+            .unwrap();
+        assert_eq!(files.len(), 5);
+
+        // Scanning an excluded directory directly still works
+        let files = FileDiscovery::new(dir.path().join("node_modules"))
+            .discover()
+            .unwrap();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn test_exclude_patterns() {
+        let dir = create_test_dir();
+        fs::write(dir.path().join("build.log"), "TODO: noise").unwrap();
+        let files = FileDiscovery::new(dir.path())
+            .with_exclude_patterns(vec!["*.log".to_string(), "sub/".to_string()])
+            .discover()
+            .unwrap();
+        let names: Vec<_> = files.iter().map(|f| f.file_name().unwrap()).collect();
+        assert_eq!(names, ["lib.rs", "main.rs"]);
+
+        let invalid = FileDiscovery::new(dir.path()).with_exclude_patterns(vec!["a[".to_string()]);
+        assert!(invalid.discover().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_symlinks_dedup_and_cycles() {
+        use std::os::unix::fs::symlink;
+
+        let dir = create_test_dir();
+        // A link back to the root would loop forever without cycle detection
+        symlink(dir.path(), dir.path().join("sub").join("loop")).unwrap();
+        symlink(dir.path().join("main.rs"), dir.path().join("alias.rs")).unwrap();
+        fs::hard_link(dir.path().join("lib.rs"), dir.path().join("hard.rs")).unwrap();
+
+        let files = FileDiscovery::new(dir.path()).discover().unwrap();
+        // Symlinks are not followed by default; the hard link is a regular file
+        assert_eq!(files.len(), 4);
+
+        let files = FileDiscovery::new(dir.path())
+            .with_follow_symlinks(true)
+            .discover()
+            .unwrap();
+        let names: Vec<String> = files
+            .iter()
+            .map(|f| f.strip_prefix(dir.path()).unwrap().display().to_string())
+            .collect();
+        assert_eq!(names, vec!["alias.rs", "hard.rs", "sub/mod.rs"]);
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn test_incremental_discovery() {
+        let dir = create_test_dir();
+        let an_hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        let backdate = |path: &Path| {
+            fs::File::open(path).unwrap().set_modified(an_hour_ago).unwrap();
+        };
+        backdate(dir.path());
+        backdate(&dir.path().join("sub"));
+
+        let cache = CacheDb::open_in_memory().unwrap();
+        let discovery = FileDiscovery::new(dir.path()).with_incremental(true);
+        let first = discovery.discover_cached(Some(&cache)).unwrap();
+        assert_eq!(first.len(), 3);
+
+        // Editing a file in place leaves the directory untouched, so its cached
+        // listing is reused (the binary sniff isn't redone)
+        fs::write(dir.path().join("lib.rs"), b"\0binary").unwrap();
+        // Adding a file changes the directory, which is listed again
+        fs::write(dir.path().join("sub").join("new.rs"), "// TODO: new").unwrap();
+
+        let second = discovery.discover_cached(Some(&cache)).unwrap();
+        assert_eq!(second.len(), 4);
+        assert!(second.contains(&dir.path().join("lib.rs")));
+        assert!(second.contains(&dir.path().join("sub").join("new.rs")));
+
+        // A full discovery sees the binary file
+        assert_eq!(discovery.discover().unwrap().len(), 3);
+
+        // Without incremental mode the cache is not consulted
+        let plain = FileDiscovery::new(dir.path());
+        assert_eq!(plain.discover_cached(Some(&cache)).unwrap().len(), 3);
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn test_incremental_discovery_skips_unchanged_subtrees() {
+        let dir = create_test_dir();
+        let deep = dir.path().join("sub").join("deep");
+        fs::create_dir(&deep).unwrap();
+        fs::write(deep.join("leaf.rs"), "// TODO: leaf").unwrap();
+        let an_hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        let backdate = |path: &Path| {
+            fs::File::open(path).unwrap().set_modified(an_hour_ago).unwrap();
+        };
+        backdate(&dir.path().join("sub"));
+        backdate(&deep);
+
+        let cache = CacheDb::open_in_memory().unwrap();
+        let discovery = FileDiscovery::new(dir.path()).with_incremental(true);
+        assert_eq!(discovery.discover_cached(Some(&cache)).unwrap().len(), 4);
+
+        // With its mtime put back, a new directory under `sub` is invisible:
+        // the unchanged subtree is not listed
+        fs::create_dir(dir.path().join("sub").join("hidden")).unwrap();
+        fs::write(dir.path().join("sub").join("hidden").join("x.rs"), "").unwrap();
+        backdate(&dir.path().join("sub"));
+        assert_eq!(discovery.discover_cached(Some(&cache)).unwrap().len(), 4);
+
+        // A change deep down gets the walk back into `sub`, which then finds
+        // the new directory too
+        fs::write(deep.join("new.rs"), "// TODO: new").unwrap();
+        let files = discovery.discover_cached(Some(&cache)).unwrap();
+        assert_eq!(files.len(), 6);
+        assert!(files.contains(&deep.join("new.rs")));
+        assert!(files.contains(&dir.path().join("sub").join("hidden").join("x.rs")));
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn test_incremental_discovery_ignore_change() {
+        let dir = create_test_dir();
+        let an_hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        fs::File::open(dir.path().join("sub"))
+            .unwrap()
+            .set_modified(an_hour_ago)
+            .unwrap();
+
+        let cache = CacheDb::open_in_memory().unwrap();
+        let discovery = FileDiscovery::new(dir.path()).with_incremental(true);
+        assert_eq!(discovery.discover_cached(Some(&cache)).unwrap().len(), 3);
+
+        // An ignore file in the root invalidates every directory below it
+        fs::write(dir.path().join(".todoignore"), "mod.rs\n").unwrap();
+        let files = discovery.discover_cached(Some(&cache)).unwrap();
+        assert_eq!(files.len(), 2);
+    }
+
+    #[test]
+    fn test_git_exclude_file_in_linked_worktree() {
+        let dir = TempDir::new().unwrap();
+        let main = dir.path().join("main");
+        let linked = dir.path().join("linked");
+        let worktree_git_dir = main.join(".git").join("worktrees").join("linked");
+        fs::create_dir_all(&worktree_git_dir).unwrap();
+        fs::create_dir_all(&linked).unwrap();
+        fs::write(worktree_git_dir.join("commondir"), "../..\n").unwrap();
+        fs::write(
+            linked.join(".git"),
+            format!("gitdir: {}\n", worktree_git_dir.display()),
+        )
+        .unwrap();
+
+        let exclude = main.join(".git").join("info").join("exclude");
+        fs::create_dir_all(exclude.parent().unwrap()).unwrap();
+        fs::write(&exclude, "secret.rs\n").unwrap();
+
+        assert_eq!(git_exclude_file(&main), Some(exclude.clone()));
+        // Resolved through the worktree's git dir, e.g. `.git/worktrees/linked/../..`
+        let resolved = git_exclude_file(&linked).unwrap();
+        assert_eq!(resolved.canonicalize().unwrap(), exclude.canonicalize().unwrap());
+        assert_eq!(git_exclude_file(dir.path()), None);
+    }
 }
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -14,11 +16,51 @@ pub enum TodoError {
     #[error("Git error: {0}")]
     Git(String),
 
+    /// The `git` executable couldn't be run
+    #[error("git is not installed or not on PATH")]
+    GitNotFound,
+
+    /// The path isn't inside a git or Mercurial checkout
+    #[error("Not a git or Mercurial repository: {}", .0.display())]
+    NotARepo(PathBuf),
+
+    /// A web API such as GitHub's failed or returned an error status
+    #[error("HTTP error: {0}")]
+    Http(String),
+
+    /// A command run from the `[hooks]` config failed or printed something
+    /// unusable
+    #[error("Hook error: {0}")]
+    Hook(String),
+
+    /// Sending a digest email failed: the SMTP server couldn't be reached or
+    /// replied with an error
+    #[error("SMTP error: {0}")]
+    Smtp(String),
+
+    /// Output encryption failed, or `age` couldn't be run
+    #[error("Encryption error: {0}")]
+    Encrypt(String),
+
+    #[error("Invalid date: {0}")]
+    InvalidDate(String),
+
     #[error("Cache error: {0}")]
     Cache(String),
 
+    /// Another `todos` process holds the cache
+    #[error("Cache locked: {0}")]
+    CacheLocked(String),
+
+    #[error("Scan limit exceeded: {0}")]
+    LimitExceeded(String),
+
     #[error("Scan error in {file}: {message}")]
     Scan { file: String, message: String },
+
+    /// A language's parser rejected a file, e.g. a tree-sitter grammar
+    #[error("Failed to parse {lang}: {message}")]
+    ParseFailure { lang: String, message: String },
 }
 
 pub type Result<T> = std::result::Result<T, TodoError>;
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+
+use crate::date::Date;
+use crate::model::{ScanResult, TodoItem};
+use crate::policy::{is_exempt, PolicyViolation, ViolationSeverity};
+
+/// Rule name escalated items are reported under; `allow:age` opts out.
+pub const RULE: &str = "age_escalation";
+
+/// Age thresholds past which a TODO counts as a warning or an error, in
+/// `check` and in SARIF output. Ages come from git blame.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct EscalationConfig {
+    /// Items older than this many days are warnings
+    pub warn_after_days: Option<u64>,
+    /// Items older than this many days are errors
+    pub error_after_days: Option<u64>,
+}
+
+impl EscalationConfig {
+    pub fn is_empty(&self) -> bool {
+        self.warn_after_days.is_none() && self.error_after_days.is_none()
+    }
+
+    /// Severity of an item `age` days old, if it is past a threshold.
+    pub fn severity(&self, age: u64) -> Option<ViolationSeverity> {
+        if self.error_after_days.is_some_and(|days| age > days) {
+            Some(ViolationSeverity::Error)
+        } else if self.warn_after_days.is_some_and(|days| age > days) {
+            Some(ViolationSeverity::Warning)
+        } else {
+            None
+        }
+    }
+}
+
+/// Days since the item's line was last committed, or `None` without blame.
+pub fn age_days(item: &TodoItem, today: Date) -> Option<u64> {
+    let date = item.git_date.as_deref()?;
+    let date = Date::parse(date.get(..10).unwrap_or(date)).ok()?;
+    Some((today.days_since_epoch() - date.days_since_epoch()).max(0) as u64)
+}
+
+/// Applies an [`EscalationConfig`] as of a given day.
+#[derive(Debug, Clone)]
+pub struct Escalator {
+    pub config: EscalationConfig,
+    pub today: Date,
+}
+
+impl Escalator {
+    /// The item's escalated severity with its age, unless it is young enough,
+    /// has no blame date or is exempt.
+    pub fn escalate(&self, item: &TodoItem) -> Option<(ViolationSeverity, u64)> {
+        if is_exempt(item, RULE) {
+            return None;
+        }
+        let age = age_days(item, self.today)?;
+        Some((self.config.severity(age)?, age))
+    }
+
+    /// One violation per escalated item.
+    pub fn check(&self, result: &ScanResult) -> Vec<PolicyViolation> {
+        result
+            .items
+            .iter()
+            .filter_map(|item| {
+                let (severity, age) = self.escalate(item)?;
+                Some(PolicyViolation {
+                    rule: RULE.to_string(),
+                    message: format!(
+                        "{} at {}:{} is {} days old",
+                        item.tag,
+                        item.file.display(),
+                        item.line,
+                        age
+                    ),
+                    file: Some(item.file.display().to_string()),
+                    line: Some(item.line),
+                    severity,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats, TodoTag};
+    use std::path::PathBuf;
+
+    fn item(line: usize, date: Option<&str>, exemptions: &[&str]) -> TodoItem {
+        TodoItem {
+            tag: TodoTag::Todo,
+            message: "old".to_string(),
+            file: PathBuf::from("a.rs"),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: date.map(str::to_string),
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: exemptions.iter().map(|e| e.to_string()).collect(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_escalation_by_age() {
+        let escalator = Escalator {
+            config: EscalationConfig {
+                warn_after_days: Some(365),
+                error_after_days: Some(730),
+            },
+            today: Date::parse("2026-01-01").unwrap(),
+        };
+        let result = ScanResult {
+            items: vec![
+                item(1, Some("2025-06-01"), &[]),
+                item(2, Some("2024-12-01"), &[]),
+                item(3, Some("2023-01-01"), &[]),
+                item(4, None, &[]),
+                item(5, Some("2020-01-01"), &["age"]),
+            ],
+            stats: ScanStats::default(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("."),
+                timestamp: String::new(),
+                blame_cache_hits: None,
+            },
+        };
+        let violations = escalator.check(&result);
+        let found: Vec<(usize, String)> = violations
+            .iter()
+            .map(|v| (v.line.unwrap(), v.severity.to_string()))
+            .collect();
+        assert_eq!(found, [(2, "warning".to_string()), (3, "error".to_string())]);
+        assert_eq!(violations[0].message, "TODO at a.rs:2 is 396 days old");
+    }
+
+    #[test]
+    fn test_warning_only() {
+        let config = EscalationConfig {
+            warn_after_days: Some(30),
+            error_after_days: None,
+        };
+        assert!(matches!(config.severity(1000), Some(ViolationSeverity::Warning)));
+        assert!(config.severity(30).is_none());
+    }
+}
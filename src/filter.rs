@@ -7,6 +7,7 @@ pub struct FilterCriteria {
     pub file_pattern: Option<String>,
     pub priority: Option<Priority>,
     pub has_issue: Option<bool>,
+    pub no_message: Option<bool>,
 }
 
 impl FilterCriteria {
@@ -20,6 +21,7 @@ impl FilterCriteria {
             && self.file_pattern.is_none()
             && self.priority.is_none()
             && self.has_issue.is_none()
+            && self.no_message.is_none()
     }
 
     pub fn apply(&self, items: &[TodoItem]) -> Vec<TodoItem> {
@@ -73,6 +75,12 @@ impl FilterCriteria {
             }
         }
 
+        if let Some(no_message) = self.no_message {
+            if no_message != item.message.trim().is_empty() {
+                return false;
+            }
+        }
+
         true
     }
 }
@@ -126,6 +134,14 @@ mod tests {
             context_line: String::new(),
             git_author: None,
             git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
         }
     }
 
@@ -148,6 +164,14 @@ mod tests {
             context_line: String::new(),
             git_author: None,
             git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
         }
     }
 
@@ -321,6 +345,23 @@ mod tests {
         assert!(result[0].issue.is_none());
     }
 
+    #[test]
+    fn test_filter_no_message() {
+        let filter = FilterCriteria {
+            no_message: Some(true),
+            ..Default::default()
+        };
+
+        let items = vec![
+            make_item("TODO", ""),
+            make_item("TODO", "   "),
+            make_item("FIXME", "fix the parser"),
+        ];
+        let result = filter.apply(&items);
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().all(|i| i.message.trim().is_empty()));
+    }
+
     #[test]
     fn test_combined_filters_and_logic() {
         let filter = FilterCriteria {
@@ -1,35 +1,54 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::OnceLock;
 
+use regex::Regex;
+use serde::Serialize;
+
+#[cfg(feature = "cache")]
+use crate::cache::{BlameStamp, CacheDb};
+use crate::date::Date;
+use crate::error::Result;
 use crate::git::utils::git_command;
 use crate::model::TodoItem;
+use crate::vcs::{worktree_relative, Vcs};
 
-/// Blame info for a specific line
-#[derive(Debug, Clone)]
-pub struct BlameInfo {
-    pub author: String,
-    pub date: String,
-    pub commit: String,
-}
-
-/// Run git blame on a file and parse the porcelain output.
-/// Returns a map of line_number -> BlameInfo
-pub fn blame_file(file_path: &Path, repo_root: &Path) -> Result<HashMap<usize, BlameInfo>, String> {
-    let relative = file_path
-        .strip_prefix(repo_root)
-        .unwrap_or(file_path);
+pub use crate::model::BlameInfo;
 
-    let rel_str = relative.to_str().unwrap_or("");
+/// File of commits for blame to look past, by the convention GitHub and
+/// GitLab follow, used when present at the repository root.
+pub const IGNORE_REVS_FILE: &str = ".git-blame-ignore-revs";
 
-    let output = git_command(&["blame", "--porcelain", rel_str], repo_root)?;
+/// Run git blame on a file and parse the porcelain output.
+/// Returns a map of line_number -> BlameInfo. In a `shallow` clone, lines
+/// blamed to the boundary commit are marked incomplete. Commits listed in
+/// `ignore_revs` are looked past, as with `git blame --ignore-revs-file`.
+pub fn blame_file(
+    file_path: &Path,
+    repo_root: &Path,
+    shallow: bool,
+    ignore_revs: Option<&Path>,
+) -> Result<HashMap<usize, BlameInfo>> {
+    let relative = worktree_relative(file_path, repo_root);
+
+    let mut args = vec!["blame", "--porcelain"];
+    if let Some(ignore_revs) = ignore_revs {
+        args.extend(["--ignore-revs-file", ignore_revs.to_str().unwrap_or("")]);
+    }
+    args.extend(["--", relative.as_str()]);
+    let output = git_command(&args, repo_root)?;
 
-    parse_blame_porcelain(&output)
+    parse_blame_porcelain(&output, shallow)
 }
 
 /// Parse git blame --porcelain output into line -> BlameInfo map
-fn parse_blame_porcelain(output: &str) -> Result<HashMap<usize, BlameInfo>, String> {
+fn parse_blame_porcelain(output: &str, shallow: bool) -> Result<HashMap<usize, BlameInfo>> {
     let mut result = HashMap::new();
+    // Commits flagged `boundary`: the root commit, or a shallow clone's
+    // cut-off that absorbs all older history
+    let mut boundaries: HashSet<String> = HashSet::new();
     let mut current_author = String::new();
+    let mut current_email = String::new();
     let mut current_date = String::new();
     let mut current_commit = String::new();
     let mut current_line: usize = 0;
@@ -44,10 +63,14 @@ fn parse_blame_porcelain(output: &str) -> Result<HashMap<usize, BlameInfo>, Stri
             }
         } else if let Some(author) = line.strip_prefix("author ") {
             current_author = author.to_string();
+        } else if let Some(mail) = line.strip_prefix("author-mail ") {
+            current_email = mail.trim_start_matches('<').trim_end_matches('>').to_string();
         } else if let Some(date) = line.strip_prefix("author-time ") {
             if let Ok(ts) = date.parse::<i64>() {
-                current_date = format_timestamp(ts);
+                current_date = Date::from_timestamp(ts).to_string();
             }
+        } else if line == "boundary" {
+            boundaries.insert(current_commit.clone());
         } else if line.starts_with('\t') {
             // Content line -- save blame info for this line number
             if current_line > 0 {
@@ -55,8 +78,10 @@ fn parse_blame_porcelain(output: &str) -> Result<HashMap<usize, BlameInfo>, Stri
                     current_line,
                     BlameInfo {
                         author: current_author.clone(),
+                        email: current_email.clone(),
                         date: current_date.clone(),
                         commit: current_commit.clone(),
+                        incomplete: shallow && boundaries.contains(&current_commit),
                     },
                 );
             }
@@ -66,55 +91,31 @@ fn parse_blame_porcelain(output: &str) -> Result<HashMap<usize, BlameInfo>, Stri
     Ok(result)
 }
 
-/// Format a Unix timestamp into YYYY-MM-DD without external dependencies.
-fn format_timestamp(ts: i64) -> String {
-    let days = ts / 86400;
-    let mut y = 1970i64;
-    let mut remaining_days = days;
-
-    loop {
-        let days_in_year = if is_leap_year(y) { 366 } else { 365 };
-        if remaining_days < days_in_year {
-            break;
-        }
-        remaining_days -= days_in_year;
-        y += 1;
-    }
-
-    let months = [
-        31,
-        if is_leap_year(y) { 29 } else { 28 },
-        31,
-        30,
-        31,
-        30,
-        31,
-        31,
-        30,
-        31,
-        30,
-        31,
-    ];
-    let mut m = 1;
-    for &days_in_month in &months {
-        if remaining_days < days_in_month {
-            break;
-        }
-        remaining_days -= days_in_month;
-        m += 1;
-    }
-    let d = remaining_days + 1;
-
-    format!("{:04}-{:02}-{:02}", y, m, d)
+/// Look up blame information for each item, in item order.
+/// Groups items by file to avoid blaming the same file multiple times.
+pub fn blame_items(items: &[TodoItem], vcs: &dyn Vcs) -> Vec<Option<BlameInfo>> {
+    blame_grouped(items, |path| (vcs.blame_file(path), false)).0
 }
 
-fn is_leap_year(y: i64) -> bool {
-    (y % 4 == 0 && y % 100 != 0) || y % 400 == 0
+/// [`blame_items`], reusing blame stored in `cache` for files whose last
+/// commit and working-tree fingerprint haven't changed. Also returns the
+/// number of files served from the cache.
+#[cfg(feature = "cache")]
+pub fn blame_items_cached(
+    items: &[TodoItem],
+    vcs: &dyn Vcs,
+    cache: Option<&CacheDb>,
+) -> (Vec<Option<BlameInfo>>, usize) {
+    blame_grouped(items, |path| blame_file_cached(path, vcs, cache))
 }
 
-/// Enrich TodoItems with git blame information.
-/// Groups items by file to avoid blaming the same file multiple times.
-pub fn enrich_with_blame(items: &mut [TodoItem], repo_root: &Path) {
+/// Blame each file once with `blame`, which also reports whether the result
+/// came from the cache, and look up every item's line. Returns the number of
+/// cache hits alongside.
+fn blame_grouped(
+    items: &[TodoItem],
+    blame: impl Fn(&Path) -> (Result<HashMap<usize, BlameInfo>>, bool),
+) -> (Vec<Option<BlameInfo>>, usize) {
     let mut files: HashMap<String, Vec<usize>> = HashMap::new();
     for (idx, item) in items.iter().enumerate() {
         files
@@ -123,55 +124,188 @@ pub fn enrich_with_blame(items: &mut [TodoItem], repo_root: &Path) {
             .push(idx);
     }
 
+    let mut result = vec![None; items.len()];
+    let mut hits = 0;
     for (file_path, indices) in &files {
         let path = Path::new(file_path);
-        if let Ok(blame_info) = blame_file(path, repo_root) {
+        let (blame_info, hit) = blame(path);
+        hits += hit as usize;
+        if let Ok(blame_info) = blame_info {
             for &idx in indices {
-                if let Some(info) = blame_info.get(&items[idx].line) {
-                    items[idx].git_author = Some(info.author.clone());
-                    items[idx].git_date = Some(info.date.clone());
-                }
+                result[idx] = blame_info.get(&items[idx].line).cloned();
             }
         }
     }
+    (result, hits)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_format_timestamp_epoch() {
-        // Unix epoch: 1970-01-01
-        assert_eq!(format_timestamp(0), "1970-01-01");
+/// Blame one file through the cache. Returns whether it was a cache hit.
+#[cfg(feature = "cache")]
+fn blame_file_cached(
+    path: &Path,
+    vcs: &dyn Vcs,
+    cache: Option<&CacheDb>,
+) -> (Result<HashMap<usize, BlameInfo>>, bool) {
+    let stamp = cache.and_then(|_| {
+        let metadata = std::fs::metadata(path).ok()?;
+        let mtime = metadata
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+        // HEAD moving, an amend or a rebase shows up as a different last
+        // commit for the file
+        let last_commit = vcs.last_commit(path).ok()? + &vcs.blame_options_key();
+        Some(BlameStamp {
+            last_commit,
+            mtime,
+            size: metadata.len(),
+        })
+    });
+    let (Some(cache), Some(stamp)) = (cache, stamp) else {
+        return (vcs.blame_file(path), false);
+    };
+    if let Some(blame) = cache.get_blame(path, &stamp) {
+        return (Ok(blame), true);
     }
+    let blame = vcs.blame_file(path);
+    // Incomplete blame changes once more history is fetched, without the
+    // file's last commit changing, so it isn't cached
+    if let Ok(ref blame) = blame {
+        if !blame.values().any(|info| info.incomplete) {
+            let _ = cache.store_blame(path, &stamp, blame);
+        }
+    }
+    (blame, false)
+}
 
-    #[test]
-    fn test_format_timestamp_known_date() {
-        // 2023-10-15 is 1697328000 (midnight UTC)
-        assert_eq!(format_timestamp(1697328000), "2023-10-15");
+/// Enrich TodoItems with blame information.
+pub fn enrich_with_blame(items: &mut [TodoItem], vcs: &dyn Vcs) {
+    apply_blame(items, blame_items(items, vcs));
+}
+
+/// [`enrich_with_blame`] through the blame cache. Returns the number of
+/// files whose blame came from the cache.
+#[cfg(feature = "cache")]
+pub fn enrich_with_blame_cached(
+    items: &mut [TodoItem],
+    vcs: &dyn Vcs,
+    cache: Option<&CacheDb>,
+) -> usize {
+    let (infos, hits) = blame_items_cached(items, vcs, cache);
+    apply_blame(items, infos);
+    hits
+}
+
+fn apply_blame(items: &mut [TodoItem], infos: Vec<Option<BlameInfo>>) {
+    for (item, info) in items.iter_mut().zip(infos) {
+        if let Some(info) = info {
+            item.git_author = Some(info.author);
+            item.git_date = Some(info.date);
+            item.blame_incomplete = info.incomplete;
+            // Lines not yet committed blame to the all-zero commit
+            if !info.commit.chars().all(|c| c == '0') {
+                item.git_commit = Some(info.commit);
+            }
+        }
     }
+}
 
-    #[test]
-    fn test_format_timestamp_leap_year() {
-        // 2024-02-29 is a leap day; 2024-03-01 00:00:00 UTC = 1709251200
-        assert_eq!(format_timestamp(1709251200), "2024-03-01");
-        // 2024-02-29 00:00:00 UTC = 1709164800
-        assert_eq!(format_timestamp(1709164800), "2024-02-29");
+/// Age of one author's blamed TODOs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuthorAge {
+    pub author: String,
+    pub count: usize,
+    pub avg_days: u64,
+    pub max_days: u64,
+}
+
+/// Average and maximum TODO age per blame author, oldest average first.
+/// Items without blame information are skipped.
+pub fn author_ages(items: &[TodoItem], today: Date) -> Vec<AuthorAge> {
+    let mut ages: HashMap<&str, Vec<u64>> = HashMap::new();
+    for item in items {
+        let (Some(author), Some(date)) = (&item.git_author, &item.git_date) else {
+            continue;
+        };
+        let Ok(date) = Date::parse(date) else {
+            continue;
+        };
+        ages.entry(author)
+            .or_default()
+            .push(date.days_until(today).max(0) as u64);
     }
 
-    #[test]
-    fn test_format_timestamp_year_2000() {
-        // 2000-01-01 00:00:00 UTC = 946684800
-        assert_eq!(format_timestamp(946684800), "2000-01-01");
+    let mut rows: Vec<AuthorAge> = ages
+        .into_iter()
+        .map(|(author, days)| AuthorAge {
+            author: author.to_string(),
+            count: days.len(),
+            avg_days: days.iter().sum::<u64>() / days.len() as u64,
+            max_days: days.iter().copied().max().unwrap_or(0),
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.avg_days
+            .cmp(&a.avg_days)
+            .then(b.max_days.cmp(&a.max_days))
+            .then_with(|| a.author.cmp(&b.author))
+    });
+    rows
+}
+
+/// Extract a pull/merge request number from a commit message. Recognizes
+/// GitHub merge and squash subjects ("Merge pull request #12", "Title (#12)"),
+/// Bitbucket ("pull request #12") and GitLab ("See merge request group/repo!12").
+pub fn parse_pr_number(message: &str) -> Option<u64> {
+    static PR_RE: OnceLock<Regex> = OnceLock::new();
+    let re = PR_RE.get_or_init(|| {
+        Regex::new(r"(?i)(?:pull request #|merge request \S*!|\(#)(\d+)").unwrap()
+    });
+    re.captures(message)
+        .and_then(|caps| caps.get(1))
+        .and_then(|m| m.as_str().parse().ok())
+}
+
+/// Fill `git_summary` and `pr_number` from the message of each item's
+/// `git_commit`. Runs after [`enrich_with_blame`]; each commit is read once.
+pub fn enrich_with_commit_info(items: &mut [TodoItem], vcs: &dyn Vcs) {
+    let mut messages: HashMap<String, Option<String>> = HashMap::new();
+    for item in items.iter_mut() {
+        let Some(ref commit) = item.git_commit else {
+            continue;
+        };
+        let message = messages
+            .entry(commit.clone())
+            .or_insert_with(|| vcs.commit_message(commit).ok());
+        if let Some(message) = message {
+            item.git_summary = message.lines().next().map(|l| l.trim().to_string());
+            item.pr_number = parse_pr_number(message);
+        }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     #[test]
-    fn test_is_leap_year() {
-        assert!(is_leap_year(2000)); // divisible by 400
-        assert!(!is_leap_year(1900)); // divisible by 100 but not 400
-        assert!(is_leap_year(2024)); // divisible by 4 but not 100
-        assert!(!is_leap_year(2023)); // not divisible by 4
+    fn test_parse_pr_number() {
+        assert_eq!(parse_pr_number("Add parser (#482)"), Some(482));
+        assert_eq!(
+            parse_pr_number("Merge pull request #17 from alice/feature"),
+            Some(17)
+        );
+        assert_eq!(
+            parse_pr_number("Fix retries\n\nSee merge request infra/todos!93"),
+            Some(93)
+        );
+        assert_eq!(
+            parse_pr_number("Merged in fix (pull request #5)"),
+            Some(5)
+        );
+        assert_eq!(parse_pr_number("Fix issue #12 in parser"), None);
     }
 
     #[test]
@@ -203,12 +337,13 @@ filename src/main.rs
 \tfn main() {}
 ";
 
-        let result = parse_blame_porcelain(porcelain).unwrap();
+        let result = parse_blame_porcelain(porcelain, false).unwrap();
 
         assert_eq!(result.len(), 2);
 
         let line1 = result.get(&1).expect("should have line 1");
         assert_eq!(line1.author, "Alice Smith");
+        assert_eq!(line1.email, "alice@example.com");
         assert_eq!(line1.date, "2023-10-15");
         assert_eq!(
             line1.commit,
@@ -226,7 +361,7 @@ filename src/main.rs
 
     #[test]
     fn test_parse_blame_porcelain_empty() {
-        let result = parse_blame_porcelain("").unwrap();
+        let result = parse_blame_porcelain("", false).unwrap();
         assert!(result.is_empty());
     }
 
@@ -253,7 +388,7 @@ abc1234567890abcdef1234567890abcdef123456 3 3
 \tline three
 ";
 
-        let result = parse_blame_porcelain(porcelain).unwrap();
+        let result = parse_blame_porcelain(porcelain, false).unwrap();
 
         assert_eq!(result.len(), 3);
 
@@ -264,4 +399,78 @@ abc1234567890abcdef1234567890abcdef123456 3 3
             assert_eq!(info.commit, "abc1234567890abcdef1234567890abcdef123456");
         }
     }
+
+    #[test]
+    fn test_parse_blame_porcelain_shallow_boundary() {
+        let porcelain = "\
+abc1234567890abcdef1234567890abcdef123456 1 1 1
+author Alice Smith
+author-time 1697328000
+boundary
+filename src/main.rs
+\t// TODO: older than the clone
+def4567890abcdef1234567890abcdef12345678 2 2 1
+author Bob Jones
+author-time 1709164800
+filename src/main.rs
+\t// TODO: recent
+";
+        let shallow = parse_blame_porcelain(porcelain, true).unwrap();
+        assert!(shallow[&1].incomplete);
+        assert!(!shallow[&2].incomplete);
+
+        // In a full clone the boundary is the root commit, which is accurate
+        let full = parse_blame_porcelain(porcelain, false).unwrap();
+        assert!(!full[&1].incomplete);
+    }
+
+    #[test]
+    fn test_author_ages() {
+        let item = |author: Option<&str>, date: &str| TodoItem {
+            tag: crate::model::TodoTag::Todo,
+            message: String::new(),
+            file: std::path::PathBuf::from("a.rs"),
+            line: 1,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: author.map(String::from),
+            git_date: Some(date.to_string()),
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        };
+        let items = vec![
+            item(Some("fresh"), "2026-02-01"),
+            item(Some("fresh"), "2026-02-03"),
+            item(Some("fresh"), "2026-02-05"),
+            item(Some("old"), "2025-02-05"),
+            item(None, "2020-01-01"),
+        ];
+        let ages = author_ages(&items, Date::new(2026, 2, 5).unwrap());
+        assert_eq!(
+            ages,
+            vec![
+                AuthorAge {
+                    author: "old".to_string(),
+                    count: 1,
+                    avg_days: 365,
+                    max_days: 365,
+                },
+                AuthorAge {
+                    author: "fresh".to_string(),
+                    count: 3,
+                    avg_days: 2,
+                    max_days: 4,
+                },
+            ]
+        );
+    }
 }
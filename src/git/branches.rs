@@ -0,0 +1,266 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::date::Date;
+use crate::error::{Result, TodoError};
+use crate::git::diff::{diff_todos, DiffFileError};
+use crate::git::utils::git_command;
+use crate::scanner::FileScanner;
+use crate::vcs::Vcs;
+
+/// Branches tried, in order, when the remote has no HEAD to say which one is
+/// the default.
+const BASE_CANDIDATES: &[&str] = &["origin/main", "origin/master", "main", "master"];
+
+/// The branch others are merged into: `origin/HEAD` (set by `git clone`),
+/// else the first of `origin/main`, `origin/master`, `main`, `master` that
+/// exists.
+pub fn default_base(repo: &Path) -> Result<String> {
+    let origin_head = ["symbolic-ref", "--quiet", "--short", "refs/remotes/origin/HEAD"];
+    if let Ok(head) = git_command(&origin_head, repo) {
+        return Ok(head.trim().to_string());
+    }
+    BASE_CANDIDATES
+        .iter()
+        .find(|name| git_command(&["rev-parse", "--verify", "--quiet", name], repo).is_ok())
+        .map(|name| name.to_string())
+        .ok_or_else(|| TodoError::Git("Could not find the base branch; pass --base".to_string()))
+}
+
+/// The checked-out branch's short name; `None` on a detached HEAD or outside
+/// a git repository.
+pub fn current_branch(repo: &Path) -> Option<String> {
+    git_command(&["symbolic-ref", "--quiet", "--short", "HEAD"], repo)
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Short names of the local branches.
+pub fn local_branches(repo: &Path) -> Result<Vec<String>> {
+    let output = git_command(&["for-each-ref", "--format=%(refname:short)", "refs/heads"], repo)?;
+    Ok(output.lines().map(|line| line.trim().to_string()).collect())
+}
+
+/// A remote-tracking branch with commits the base doesn't have.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteBranch {
+    /// Short name, e.g. `origin/feature`
+    pub name: String,
+    pub commit: String,
+    /// Date of the branch's last commit
+    pub date: Date,
+    /// Commits on the branch but not the base
+    pub ahead: usize,
+    /// Commits on the base but not the branch
+    pub behind: usize,
+    /// Where the branch forked from the base
+    pub merge_base: String,
+}
+
+/// Parse `git for-each-ref` output of name, commit, commit date and symref
+/// target separated by `\x1f` into (name, commit, date), skipping symbolic
+/// refs such as `origin/HEAD`.
+fn parse_refs(output: &str) -> Vec<(String, String, Date)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\x1f');
+            let name = fields.next()?.trim();
+            let commit = fields.next()?.trim();
+            let secs: i64 = fields.next()?.trim().parse().ok()?;
+            if !fields.next()?.trim().is_empty() {
+                return None;
+            }
+            Some((name.to_string(), commit.to_string(), Date::from_timestamp(secs)))
+        })
+        .collect()
+}
+
+/// Remote-tracking branches that are ahead of `base`, by name.
+pub fn remote_branches_ahead(repo: &Path, base: &str) -> Result<Vec<RemoteBranch>> {
+    let base_commit = git_command(
+        &["rev-parse", "--verify", &format!("{}^{{commit}}", base)],
+        repo,
+    )?;
+    let output = git_command(
+        &[
+            "for-each-ref",
+            "--format=%(refname:short)%1f%(objectname)%1f%(committerdate:unix)%1f%(symref)",
+            "refs/remotes",
+        ],
+        repo,
+    )?;
+
+    let mut branches = Vec::new();
+    for (name, commit, date) in parse_refs(&output) {
+        if name == base || commit == base_commit.trim() {
+            continue;
+        }
+        // "<behind>\t<ahead>"
+        let counts = git_command(
+            &["rev-list", "--left-right", "--count", &format!("{}...{}", base, commit)],
+            repo,
+        )?;
+        let mut counts = counts.split_whitespace().map(|n| n.parse().unwrap_or(0));
+        let (behind, ahead) = (counts.next().unwrap_or(0), counts.next().unwrap_or(0));
+        if ahead == 0 {
+            continue;
+        }
+        let merge_base = git_command(&["merge-base", base, &commit], repo)?;
+        branches.push(RemoteBranch {
+            name,
+            commit,
+            date,
+            ahead,
+            behind,
+            merge_base: merge_base.trim().to_string(),
+        });
+    }
+    Ok(branches)
+}
+
+/// TODOs one branch would add and remove if merged into the base.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BranchAudit {
+    pub branch: String,
+    pub commit: String,
+    /// Date of the branch's last commit
+    pub date: String,
+    pub ahead: usize,
+    pub behind: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl BranchAudit {
+    pub fn net(&self) -> i64 {
+        self.added as i64 - self.removed as i64
+    }
+}
+
+/// Diff each branch against its merge base, so changes made on the base since
+/// the branch forked aren't counted. Results are ordered by TODOs added, most
+/// first. Files that couldn't be read are returned alongside; their TODOs are
+/// missing from the counts.
+pub fn audit_branches(
+    scanner: &dyn FileScanner,
+    vcs: &dyn Vcs,
+    branches: &[RemoteBranch],
+) -> Result<(Vec<BranchAudit>, Vec<DiffFileError>)> {
+    let mut audits = Vec::new();
+    let mut errors = Vec::new();
+    for branch in branches {
+        let diff = diff_todos(scanner, &branch.merge_base, &branch.commit, vcs)?;
+        errors.extend(diff.errors);
+        audits.push(BranchAudit {
+            branch: branch.name.clone(),
+            commit: branch.commit.clone(),
+            date: branch.date.to_string(),
+            ahead: branch.ahead,
+            behind: branch.behind,
+            added: diff.added.len(),
+            removed: diff.removed.len(),
+        });
+    }
+    sort_audits(&mut audits);
+    Ok((audits, errors))
+}
+
+fn sort_audits(audits: &mut [BranchAudit]) {
+    audits.sort_by(|a, b| {
+        b.added
+            .cmp(&a.added)
+            .then(b.net().cmp(&a.net()))
+            .then_with(|| a.date.cmp(&b.date))
+    });
+}
+
+/// Table of branches ahead of `base`, with the oldest last commit shown first
+/// among equal TODO counts.
+pub fn render_text(base: &str, audits: &[BranchAudit]) -> String {
+    let mut out = String::new();
+    writeln!(out, "Branches ahead of {}: {}", base, audits.len()).unwrap();
+    if audits.is_empty() {
+        return out;
+    }
+    writeln!(out).unwrap();
+    let width = audits.iter().map(|a| a.branch.len()).max().unwrap_or(0).max(6);
+    writeln!(
+        out,
+        "  {:<width$}  {:>5}  {:>7}  {:>5}  {:>5}  {:>6}  Last commit",
+        "Branch",
+        "Added",
+        "Removed",
+        "Net",
+        "Ahead",
+        "Behind",
+        width = width
+    )
+    .unwrap();
+    for a in audits {
+        writeln!(
+            out,
+            "  {:<width$}  {:>5}  {:>7}  {:>+5}  {:>5}  {:>6}  {}",
+            a.branch,
+            a.added,
+            a.removed,
+            a.net(),
+            a.ahead,
+            a.behind,
+            a.date,
+            width = width
+        )
+        .unwrap();
+    }
+    let added: usize = audits.iter().map(|a| a.added).sum();
+    writeln!(out).unwrap();
+    writeln!(out, "Total: {} TODO(s) waiting to be merged", added).unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audit(branch: &str, added: usize, removed: usize, date: &str) -> BranchAudit {
+        BranchAudit {
+            branch: branch.to_string(),
+            commit: "0123456789abcdef".to_string(),
+            date: date.to_string(),
+            ahead: 3,
+            behind: 1,
+            added,
+            removed,
+        }
+    }
+
+    #[test]
+    fn test_parse_refs_skips_symbolic_refs() {
+        let output = "origin/HEAD\x1fabc\x1f86400\x1frefs/remotes/origin/main\n\
+                      origin/feature\x1fdef\x1f172800\x1f\n";
+        let refs = parse_refs(output);
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].0, "origin/feature");
+        assert_eq!(refs[0].2.to_string(), "1970-01-03");
+    }
+
+    #[test]
+    fn test_sort_and_render() {
+        let mut audits = vec![
+            audit("origin/small", 1, 0, "2024-05-01"),
+            audit("origin/old", 4, 1, "2023-01-01"),
+            audit("origin/new", 4, 1, "2024-06-01"),
+        ];
+        sort_audits(&mut audits);
+        let order: Vec<&str> = audits.iter().map(|a| a.branch.as_str()).collect();
+        assert_eq!(order, ["origin/old", "origin/new", "origin/small"]);
+
+        let text = render_text("origin/main", &audits);
+        assert!(text.starts_with("Branches ahead of origin/main: 3"));
+        assert!(text.contains("origin/old        4        1     +3      3       1  2023-01-01"));
+        assert!(text.contains("Total: 9 TODO(s) waiting to be merged"));
+    }
+}
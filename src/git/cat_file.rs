@@ -0,0 +1,111 @@
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use crate::error::{Result, TodoError};
+
+/// A long-lived `git cat-file --batch` process. Reading many files at a
+/// revision through one process avoids spawning `git show` per file, which
+/// dominates diff time on large changes.
+pub(crate) struct CatFile {
+    child: Child,
+    /// Taken on drop to close git's input
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl CatFile {
+    pub(crate) fn spawn(repo_root: &Path) -> Result<Self> {
+        let mut child = Command::new("git")
+            .args(["cat-file", "--batch"])
+            .current_dir(repo_root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound if repo_root.is_dir() => TodoError::GitNotFound,
+                _ => TodoError::Git(format!("Failed to execute git: {}", e)),
+            })?;
+        let stdin = child.stdin.take();
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        Ok(CatFile {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    /// Contents of the blob named by `object`, e.g. `HEAD~1:src/lib.rs`.
+    /// The name must not contain a newline. An `Io` error leaves the process
+    /// out of step with its output, so it should not be used again.
+    pub(crate) fn read(&mut self, object: &str) -> Result<Vec<u8>> {
+        let stdin = self.stdin.as_mut().expect("stdin is open until drop");
+        writeln!(stdin, "{}", object)?;
+        stdin.flush()?;
+
+        let mut header = String::new();
+        if self.stdout.read_line(&mut header)? == 0 {
+            return Err(TodoError::Io(ErrorKind::UnexpectedEof.into()));
+        }
+        // "<oid> <type> <size>", or "<object> missing" / "<object> ambiguous"
+        let mut fields = header.trim_end().rsplitn(3, ' ');
+        let (Some(last), Some(middle)) = (fields.next(), fields.next()) else {
+            let header = header.trim_end();
+            return Err(TodoError::Git(format!("Unexpected cat-file output: {}", header)));
+        };
+        let size: usize = match last.parse() {
+            Ok(size) => size,
+            Err(_) => return Err(TodoError::Git(format!("{} is {}", object, last))),
+        };
+
+        let mut contents = vec![0; size + 1];
+        self.stdout.read_exact(&mut contents)?;
+        // Drop the newline that terminates each object
+        contents.pop();
+        if middle != "blob" {
+            return Err(TodoError::Git(format!("{} is a {}, not a file", object, middle)));
+        }
+        Ok(contents)
+    }
+}
+
+impl Drop for CatFile {
+    fn drop(&mut self) {
+        // git exits once its input is closed
+        drop(self.stdin.take());
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::utils::git_command;
+
+    #[test]
+    fn test_read_blobs_from_one_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let mut full = vec!["-c", "user.name=Test", "-c", "user.email=test@example.com"];
+            full.extend_from_slice(args);
+            git_command(&full, dir.path()).unwrap();
+        };
+        git(&["init", "-q"]);
+        std::fs::create_dir(dir.path().join("my dir")).unwrap();
+        std::fs::write(dir.path().join("my dir/a.rs"), "// TODO: one\n").unwrap();
+        std::fs::write(dir.path().join("bin"), b"\0\n\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-qm", "first"]);
+
+        let mut cat_file = CatFile::spawn(dir.path()).unwrap();
+        assert_eq!(cat_file.read("HEAD:my dir/a.rs").unwrap(), b"// TODO: one\n");
+        let err = cat_file.read("HEAD:gone.rs").unwrap_err();
+        assert!(err.to_string().contains("HEAD:gone.rs is missing"), "{}", err);
+        let err = cat_file.read("HEAD:my dir").unwrap_err();
+        assert!(err.to_string().contains("not a file"), "{}", err);
+        // Still in step after errors and binary contents
+        assert_eq!(cat_file.read("HEAD:bin").unwrap(), b"\0\n\n");
+        assert_eq!(cat_file.read("HEAD:my dir/a.rs").unwrap(), b"// TODO: one\n");
+    }
+}
@@ -1,11 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-use crate::git::utils::git_command;
+use crate::error::Result;
+use crate::git::utils::{git_command, git_command_input, git_command_raw};
 use crate::model::TodoItem;
 use crate::scanner::FileScanner;
+use crate::vcs::{repo_relative, Vcs};
+
+/// Largest file read at a revision, matching the default `scan.max_file_size`.
+const MAX_FILE_SIZE_AT_REV: usize = 1_048_576;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DiffResult {
@@ -13,42 +18,206 @@ pub struct DiffResult {
     pub removed: Vec<TodoItem>,
     pub base_ref: String,
     pub head_ref: String,
+    /// Files that could not be compared; their TODOs are missing from
+    /// `added`/`removed`
+    #[serde(default)]
+    pub errors: Vec<DiffFileError>,
+}
+
+/// A per-file failure while reading one side of a diff.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffFileError {
+    pub file: String,
+    pub message: String,
+}
+
+/// How a file changed between the two sides of a diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeStatus {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+}
+
+/// A file touched by a change. `source` is set when the file was renamed or
+/// copied, so TODOs can be matched against the file's previous location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangedFile {
+    /// Path on the new side (the old path for deleted files)
+    pub path: PathBuf,
+    /// Path on the old side of a rename or copy
+    pub source: Option<PathBuf>,
+    pub status: ChangeStatus,
+}
+
+impl ChangedFile {
+    /// Path to read on the old side of the change.
+    pub fn base_path(&self) -> &Path {
+        self.source.as_deref().unwrap_or(&self.path)
+    }
+
+    /// Whether the file exists on the old side.
+    pub fn in_base(&self) -> bool {
+        self.status != ChangeStatus::Added
+    }
+
+    /// Whether the file exists on the new side.
+    pub fn in_head(&self) -> bool {
+        self.status != ChangeStatus::Deleted
+    }
+}
+
+/// Parse `git diff --name-status` output. Rename and copy lines
+/// (`R100\told\tnew`, `C75\told\tnew`) carry the source path.
+pub fn parse_name_status(output: &str, repo_root: &Path) -> Vec<ChangedFile> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split('\t').collect();
+            let status = match parts.first()?.chars().next()? {
+                'A' => ChangeStatus::Added,
+                'D' => ChangeStatus::Deleted,
+                'R' => ChangeStatus::Renamed,
+                'C' => ChangeStatus::Copied,
+                _ => ChangeStatus::Modified,
+            };
+            match parts.as_slice() {
+                [_, old, new] => Some(ChangedFile {
+                    path: repo_root.join(new.trim()),
+                    source: Some(repo_root.join(old.trim())),
+                    status,
+                }),
+                [_, path] => Some(ChangedFile {
+                    path: repo_root.join(path.trim()),
+                    source: None,
+                    status,
+                }),
+                _ => None,
+            }
+        })
+        .collect()
 }
 
-/// Get list of files changed between two refs.
-pub fn changed_files(base: &str, head: &str, repo_root: &Path) -> Result<Vec<PathBuf>, String> {
+/// Get list of files changed between two refs, with renames and copies detected.
+pub fn changed_files(base: &str, head: &str, repo_root: &Path) -> Result<Vec<ChangedFile>> {
     let output = git_command(
-        &["diff", "--name-only", &format!("{}...{}", base, head)],
+        &["diff", "--name-status", "-M", "-C", &format!("{}...{}", base, head)],
         repo_root,
     )?;
-    Ok(output
-        .lines()
-        .filter(|l| !l.is_empty())
-        .map(|l| repo_root.join(l.trim()))
-        .collect())
+    Ok(parse_name_status(&output, repo_root))
 }
 
-/// Get list of staged files.
-pub fn staged_files(repo_root: &Path) -> Result<Vec<PathBuf>, String> {
-    let output = git_command(&["diff", "--name-only", "--staged"], repo_root)?;
-    Ok(output
-        .lines()
-        .filter(|l| !l.is_empty())
-        .map(|l| repo_root.join(l.trim()))
-        .collect())
+/// Get list of staged files, with renames and copies detected.
+pub fn staged_files(repo_root: &Path) -> Result<Vec<ChangedFile>> {
+    let output = git_command(&["diff", "--name-status", "-M", "-C", "--staged"], repo_root)?;
+    Ok(parse_name_status(&output, repo_root))
 }
 
-/// Get file content at a specific git ref.
-fn file_at_ref(file_path: &Path, git_ref: &str, repo_root: &Path) -> Result<String, String> {
+/// Get raw file content at a specific git ref.
+pub(crate) fn file_at_ref(file_path: &Path, git_ref: &str, repo_root: &Path) -> Result<Vec<u8>> {
     let relative = file_path.strip_prefix(repo_root).unwrap_or(file_path);
     // Normalize path separators for git (Windows uses backslashes)
     let path_str = relative.to_str().unwrap_or("").replace('\\', "/");
-    git_command(
+    git_command_raw(
         &["show", &format!("{}:{}", git_ref, path_str)],
         repo_root,
     )
 }
 
+/// Blobs of `files` at `git_ref` that are missing locally, as in a partial
+/// clone where file contents are only fetched when needed.
+pub(crate) fn missing_blobs(files: &[&Path], git_ref: &str, repo_root: &Path) -> Result<Vec<String>> {
+    if files.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut input = format!("{}\n--\n", git_ref);
+    for file in files {
+        input.push_str(&repo_relative(file, repo_root));
+        input.push('\n');
+    }
+    let output = git_command_input(
+        &[
+            "--literal-pathspecs",
+            "rev-list",
+            "--objects",
+            "--no-object-names",
+            "--no-walk",
+            "--missing=print",
+            "--stdin",
+        ],
+        Some(input.as_bytes()),
+        repo_root,
+    )?;
+    Ok(String::from_utf8_lossy(&output)
+        .lines()
+        .filter_map(|line| line.strip_prefix('?'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Fetch `blobs` from the promisor `remote` in a single request, the way git
+/// fetches one missing object on demand.
+pub(crate) fn fetch_blobs(blobs: &[String], remote: &str, repo_root: &Path) -> Result<()> {
+    let input = blobs.join("\n") + "\n";
+    git_command_input(
+        &[
+            "-c",
+            "fetch.negotiationAlgorithm=noop",
+            "fetch",
+            "--quiet",
+            "--no-tags",
+            "--no-write-fetch-head",
+            "--recurse-submodules=no",
+            "--filter=blob:none",
+            "--stdin",
+            remote,
+        ],
+        Some(input.as_bytes()),
+        repo_root,
+    )
+    .map(|_| ())
+}
+
+/// Content looks binary if it has a NUL byte near the start (git's heuristic).
+fn is_binary(content: &[u8]) -> bool {
+    content.iter().take(8000).any(|&b| b == 0)
+}
+
+/// Read a file at a revision for scanning. Binary files are skipped; read
+/// failures and oversized files are recorded in `errors`.
+pub(crate) fn read_at_rev(
+    vcs: &dyn Vcs,
+    file: &Path,
+    rev: &str,
+    errors: &mut Vec<DiffFileError>,
+) -> Option<String> {
+    let mut error = |message: String| {
+        errors.push(DiffFileError {
+            file: file.display().to_string(),
+            message,
+        })
+    };
+    match vcs.file_at_rev(file, rev) {
+        Ok(bytes) if is_binary(&bytes) => None,
+        Ok(bytes) if bytes.len() > MAX_FILE_SIZE_AT_REV => {
+            error(format!(
+                "skipped at {}: {} bytes exceeds the {} byte limit",
+                rev,
+                bytes.len(),
+                MAX_FILE_SIZE_AT_REV
+            ));
+            None
+        }
+        Ok(bytes) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+        Err(e) => {
+            error(e.to_string());
+            None
+        }
+    }
+}
+
 /// Build an identity key for a TodoItem based on (file, tag, message).
 fn item_key(item: &TodoItem) -> (String, String, String) {
     (
@@ -58,119 +227,437 @@ fn item_key(item: &TodoItem) -> (String, String, String) {
     )
 }
 
-/// Scan TODOs in a set of files at a specific git ref.
-fn scan_at_ref(
+/// Scan TODOs in file content that is not on disk (e.g. a file at a revision).
+/// Item paths point at `file`.
+pub(crate) fn scan_content(scanner: &dyn FileScanner, file: &Path, content: &str) -> Vec<TodoItem> {
+    let ext = file
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    if let Ok(temp) = tempfile::Builder::new()
+        .suffix(&format!(".{}", ext))
+        .tempfile()
+    {
+        let temp_path = temp.path().to_path_buf();
+        if std::fs::write(&temp_path, content).is_ok() {
+            if let Ok(mut file_items) = scanner.scan_file(&temp_path) {
+                // Fix the file paths to point to the original file
+                for item in &mut file_items {
+                    item.file = file.to_path_buf();
+                }
+                return file_items;
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// Scan the new side of changed files at a revision, skipping deleted files.
+fn scan_head(
     scanner: &dyn FileScanner,
-    files: &[PathBuf],
-    git_ref: &str,
-    repo_root: &Path,
+    files: &[ChangedFile],
+    rev: &str,
+    vcs: &dyn Vcs,
+    errors: &mut Vec<DiffFileError>,
 ) -> Vec<TodoItem> {
     let mut items = Vec::new();
-    for file in files {
-        if let Ok(content) = file_at_ref(file, git_ref, repo_root) {
-            let ext = file
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("");
-            if let Ok(temp) = tempfile::Builder::new()
-                .suffix(&format!(".{}", ext))
-                .tempfile()
-            {
-                let temp_path = temp.path().to_path_buf();
-                if std::fs::write(&temp_path, &content).is_ok() {
-                    if let Ok(mut file_items) = scanner.scan_file(&temp_path) {
-                        // Fix the file paths to point to the original file
-                        for item in &mut file_items {
-                            item.file = file.clone();
-                        }
-                        items.extend(file_items);
-                    }
-                }
-            }
+    for file in files.iter().filter(|f| f.in_head()) {
+        if let Some(content) = read_at_rev(vcs, &file.path, rev, errors) {
+            items.extend(scan_content(scanner, &file.path, &content));
         }
     }
     items
 }
 
-/// Compare TODOs between two git refs.
-pub fn diff_todos(
+/// Scan the old side of changed files, skipping added files. Renamed and
+/// copied files are read from their source path but reported under the new
+/// path so a move alone produces no added/removed churn.
+fn scan_base(
     scanner: &dyn FileScanner,
-    base_ref: &str,
-    head_ref: &str,
-    repo_root: &Path,
-) -> Result<DiffResult, String> {
-    let files = changed_files(base_ref, head_ref, repo_root)?;
-
-    let base_todos = scan_at_ref(scanner, &files, base_ref, repo_root);
-    let head_todos = scan_at_ref(scanner, &files, head_ref, repo_root);
+    files: &[ChangedFile],
+    rev: &str,
+    vcs: &dyn Vcs,
+    errors: &mut Vec<DiffFileError>,
+) -> Vec<TodoItem> {
+    let mut items = Vec::new();
+    for file in files.iter().filter(|f| f.in_base()) {
+        if let Some(content) = read_at_rev(vcs, file.base_path(), rev, errors) {
+            items.extend(scan_content(scanner, &file.path, &content));
+        }
+    }
+    items
+}
 
+/// Items present only in `head` (added) and only in `base` (removed), matched
+/// by (file, tag, message) so line shifts are not reported as changes.
+pub fn diff_items(base: &[TodoItem], head: &[TodoItem]) -> (Vec<TodoItem>, Vec<TodoItem>) {
     let base_keys: HashMap<(String, String, String), &TodoItem> =
-        base_todos.iter().map(|item| (item_key(item), item)).collect();
+        base.iter().map(|item| (item_key(item), item)).collect();
     let head_keys: HashMap<(String, String, String), &TodoItem> =
-        head_todos.iter().map(|item| (item_key(item), item)).collect();
+        head.iter().map(|item| (item_key(item), item)).collect();
 
-    let added: Vec<TodoItem> = head_todos
+    let added: Vec<TodoItem> = head
         .iter()
         .filter(|item| !base_keys.contains_key(&item_key(item)))
         .cloned()
         .collect();
 
-    let removed: Vec<TodoItem> = base_todos
+    let removed: Vec<TodoItem> = base
         .iter()
         .filter(|item| !head_keys.contains_key(&item_key(item)))
         .cloned()
         .collect();
 
+    (added, removed)
+}
+
+/// Compare TODOs between two revisions.
+pub fn diff_todos(
+    scanner: &dyn FileScanner,
+    base_ref: &str,
+    head_ref: &str,
+    vcs: &dyn Vcs,
+) -> Result<DiffResult> {
+    let files = vcs.changed_files(base_ref, head_ref)?;
+    let base_paths: Vec<&Path> = files
+        .iter()
+        .filter(|f| f.in_base())
+        .map(ChangedFile::base_path)
+        .collect();
+    let head_paths: Vec<&Path> = files
+        .iter()
+        .filter(|f| f.in_head())
+        .map(|f| f.path.as_path())
+        .collect();
+    vcs.prefetch(&base_paths, base_ref)?;
+    vcs.prefetch(&head_paths, head_ref)?;
+    let mut errors = Vec::new();
+
+    let base_todos = scan_base(scanner, &files, base_ref, vcs, &mut errors);
+    let head_todos = scan_head(scanner, &files, head_ref, vcs, &mut errors);
+
+    let (added, removed) = diff_items(&base_todos, &head_todos);
+
     Ok(DiffResult {
         added,
         removed,
         base_ref: base_ref.to_string(),
         head_ref: head_ref.to_string(),
+        errors,
     })
 }
 
-/// Diff against staged changes (scan working tree vs HEAD for staged files).
-pub fn diff_staged(
-    scanner: &dyn FileScanner,
-    repo_root: &Path,
-) -> Result<DiffResult, String> {
-    let files = staged_files(repo_root)?;
+/// Diff against staged changes (scan working tree vs the working parent for
+/// pending files).
+pub fn diff_staged(scanner: &dyn FileScanner, vcs: &dyn Vcs) -> Result<DiffResult> {
+    let files = vcs.pending_files()?;
+    let parent = vcs.working_parent();
+    let base_paths: Vec<&Path> = files
+        .iter()
+        .filter(|f| f.in_base())
+        .map(ChangedFile::base_path)
+        .collect();
+    vcs.prefetch(&base_paths, parent)?;
 
-    let head_todos = scan_at_ref(scanner, &files, "HEAD", repo_root);
+    let mut errors = Vec::new();
+    let head_todos = scan_base(scanner, &files, parent, vcs, &mut errors);
 
     // Scan current working tree versions
     let mut working_todos = Vec::new();
-    for file in &files {
-        if file.exists() {
-            if let Ok(items) = scanner.scan_file(file) {
-                working_todos.extend(items);
+    for file in files.iter().filter(|f| f.in_head() && f.path.exists()) {
+        match scanner.scan_file(&file.path) {
+            Ok(items) => working_todos.extend(items),
+            Err(e) => errors.push(DiffFileError {
+                file: file.path.display().to_string(),
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    let (added, removed) = diff_items(&head_todos, &working_todos);
+
+    Ok(DiffResult {
+        added,
+        removed,
+        base_ref: parent.to_string(),
+        head_ref: "working-tree".to_string(),
+        errors,
+    })
+}
+
+/// Line numbers touched by a unified diff for one file.
+#[derive(Debug, Default, PartialEq)]
+pub struct FileHunks {
+    /// Repo-relative path on the old side, `None` for added files
+    pub old_path: Option<String>,
+    /// Repo-relative path on the new side, `None` for deleted files
+    pub new_path: Option<String>,
+    /// New-side line numbers of added lines
+    pub added: HashSet<usize>,
+    /// Old-side line numbers of removed lines
+    pub removed: HashSet<usize>,
+}
+
+/// Parse the path from a `---`/`+++` header, dropping the `a/`/`b/` prefix and
+/// any tab-separated timestamp (Mercurial).
+fn header_path(rest: &str) -> Option<String> {
+    let path = rest.split('\t').next().unwrap_or(rest).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// Parse a `-a,b` or `+c,d` range into (start, count); count defaults to 1.
+fn hunk_range(range: &str) -> Option<(usize, usize)> {
+    let mut parts = range.splitn(2, ',');
+    let start = parts.next()?.parse().ok()?;
+    let count = match parts.next() {
+        Some(c) => c.parse().ok()?,
+        None => 1,
+    };
+    Some((start, count))
+}
+
+/// Parse `@@ -a,b +c,d @@` into old and new (start, count) ranges.
+fn hunk_header(header: &str) -> Option<((usize, usize), (usize, usize))> {
+    let mut parts = header.split_whitespace().skip(1);
+    let old = hunk_range(parts.next()?.strip_prefix('-')?)?;
+    let new = hunk_range(parts.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+/// Parse unified diff output (git or Mercurial) into per-file added and
+/// removed line numbers. Hunk line counts are tracked so content lines that
+/// look like `---`/`+++` headers are not misread.
+pub fn parse_unified_diff(diff: &str) -> Vec<FileHunks> {
+    let mut files: Vec<FileHunks> = Vec::new();
+    let (mut old_line, mut new_line) = (0, 0);
+    let (mut old_left, mut new_left) = (0usize, 0usize);
+
+    for line in diff.lines() {
+        if old_left > 0 || new_left > 0 {
+            let Some(file) = files.last_mut() else {
+                break;
+            };
+            match line.as_bytes().first() {
+                Some(b'+') => {
+                    file.added.insert(new_line);
+                    new_line += 1;
+                    new_left = new_left.saturating_sub(1);
+                }
+                Some(b'-') => {
+                    file.removed.insert(old_line);
+                    old_line += 1;
+                    old_left = old_left.saturating_sub(1);
+                }
+                // "\ No newline at end of file"
+                Some(b'\\') => {}
+                _ => {
+                    old_line += 1;
+                    new_line += 1;
+                    old_left = old_left.saturating_sub(1);
+                    new_left = new_left.saturating_sub(1);
+                }
+            }
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("--- ") {
+            files.push(FileHunks {
+                old_path: header_path(rest),
+                ..Default::default()
+            });
+        } else if let Some(rest) = line.strip_prefix("+++ ") {
+            if let Some(file) = files.last_mut() {
+                file.new_path = header_path(rest);
+            }
+        } else if line.starts_with("@@") {
+            if let Some(((old_start, old_count), (new_start, new_count))) = hunk_header(line) {
+                old_line = old_start;
+                new_line = new_start;
+                old_left = old_count;
+                new_left = new_count;
             }
         }
     }
+    files
+}
 
-    let head_keys: HashMap<(String, String, String), &TodoItem> =
-        head_todos.iter().map(|item| (item_key(item), item)).collect();
-    let working_keys: HashMap<(String, String, String), &TodoItem> = working_todos
-        .iter()
-        .map(|item| (item_key(item), item))
-        .collect();
+/// New-side line numbers added between the merge base of `base` and `head`,
+/// and `head`, by file under the repository root.
+pub fn added_lines(
+    base: &str,
+    head: &str,
+    vcs: &dyn Vcs,
+) -> Result<HashMap<PathBuf, HashSet<usize>>> {
+    let diff = vcs.unified_diff(base, head)?;
+    Ok(parse_unified_diff(&diff)
+        .into_iter()
+        .filter_map(|hunks| Some((vcs.root().join(hunks.new_path?), hunks.added)))
+        .collect())
+}
 
-    let added: Vec<TodoItem> = working_todos
+/// Compare TODOs on changed lines only: `added` holds TODOs on added lines of
+/// the new side, `removed` TODOs on deleted lines of the old side. Unlike
+/// [`diff_todos`], a new TODO next to an existing identical one is still
+/// reported. `head` of `None` compares pending changes in the working tree.
+pub fn diff_hunks(
+    scanner: &dyn FileScanner,
+    base_ref: &str,
+    head_ref: Option<&str>,
+    vcs: &dyn Vcs,
+) -> Result<DiffResult> {
+    let diff = match head_ref {
+        Some(head) => vcs.unified_diff(base_ref, head)?,
+        None => vcs.pending_diff()?,
+    };
+    let root = vcs.root();
+    let file_hunks = parse_unified_diff(&diff);
+    let old_files: Vec<PathBuf> = file_hunks
         .iter()
-        .filter(|item| !head_keys.contains_key(&item_key(item)))
-        .cloned()
+        .filter(|hunks| !hunks.removed.is_empty())
+        .filter_map(|hunks| hunks.old_path.as_ref().map(|path| root.join(path)))
         .collect();
+    vcs.prefetch(&old_files.iter().map(PathBuf::as_path).collect::<Vec<_>>(), base_ref)?;
+    if let Some(head) = head_ref {
+        let new_files: Vec<PathBuf> = file_hunks
+            .iter()
+            .filter(|hunks| !hunks.added.is_empty())
+            .filter_map(|hunks| hunks.new_path.as_ref().map(|path| root.join(path)))
+            .collect();
+        vcs.prefetch(&new_files.iter().map(PathBuf::as_path).collect::<Vec<_>>(), head)?;
+    }
 
-    let removed: Vec<TodoItem> = head_todos
-        .iter()
-        .filter(|item| !working_keys.contains_key(&item_key(item)))
-        .cloned()
-        .collect();
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut errors = Vec::new();
+
+    for hunks in file_hunks {
+        if let (Some(path), false) = (&hunks.new_path, hunks.added.is_empty()) {
+            let file = root.join(path);
+            // Pending changes are read as they would be committed, which
+            // for git is the index rather than the working tree
+            let items = match head_ref.or(vcs.pending_rev()) {
+                Some(head) => read_at_rev(vcs, &file, head, &mut errors)
+                    .map(|content| scan_content(scanner, &file, &content))
+                    .unwrap_or_default(),
+                None => scanner.scan_file(&file).unwrap_or_else(|e| {
+                    errors.push(DiffFileError {
+                        file: file.display().to_string(),
+                        message: e.to_string(),
+                    });
+                    Vec::new()
+                }),
+            };
+            added.extend(
+                items
+                    .into_iter()
+                    .filter(|item| hunks.added.contains(&item.line)),
+            );
+        }
+        if let (Some(path), false) = (&hunks.old_path, hunks.removed.is_empty()) {
+            let file = root.join(path);
+            if let Some(content) = read_at_rev(vcs, &file, base_ref, &mut errors) {
+                removed.extend(
+                    scan_content(scanner, &file, &content)
+                        .into_iter()
+                        .filter(|item| hunks.removed.contains(&item.line)),
+                );
+            }
+        }
+    }
 
     Ok(DiffResult {
         added,
         removed,
-        base_ref: "HEAD".to_string(),
-        head_ref: "working-tree".to_string(),
+        base_ref: base_ref.to_string(),
+        head_ref: head_ref.unwrap_or("working-tree").to_string(),
+        errors,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_name_status() {
+        let output = "M\tsrc/lib.rs\nR100\tsrc/old.rs\tsrc/new.rs\nC75\tsrc/a.rs\tsrc/b.rs\nD\tgone.rs\n";
+        let files = parse_name_status(output, Path::new("/repo"));
+        assert_eq!(files.len(), 4);
+        assert_eq!(files[0].path, PathBuf::from("/repo/src/lib.rs"));
+        assert_eq!(files[0].source, None);
+        assert_eq!(files[1].path, PathBuf::from("/repo/src/new.rs"));
+        assert_eq!(files[1].base_path(), Path::new("/repo/src/old.rs"));
+        assert_eq!(files[2].source, Some(PathBuf::from("/repo/src/a.rs")));
+        assert_eq!(files[3].base_path(), Path::new("/repo/gone.rs"));
+        assert!(files[3].in_base() && !files[3].in_head());
+        assert_eq!(files[1].status, ChangeStatus::Renamed);
+        assert_eq!(files[2].status, ChangeStatus::Copied);
+    }
+
+    #[test]
+    fn test_is_binary() {
+        assert!(is_binary(b"PNG\0\x01"));
+        assert!(!is_binary("// TODO: caf\u{e9}".as_bytes()));
+    }
+
+    #[test]
+    fn test_parse_unified_diff_git() {
+        let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 111..222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -3,0 +4,2 @@ fn a() {}
++// TODO: new one
++fn b() {}
+@@ -10,2 +12 @@ fn c() {}
+-// FIXME: gone
+-fn d() {}
++fn e() {}
+diff --git a/old.rs b/old.rs
+deleted file mode 100644
+--- a/old.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-// TODO: deleted file
+";
+        let files = parse_unified_diff(diff);
+        assert_eq!(files.len(), 2);
+
+        let lib = &files[0];
+        assert_eq!(lib.old_path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(lib.new_path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(lib.added, HashSet::from([4, 5, 12]));
+        assert_eq!(lib.removed, HashSet::from([10, 11]));
+
+        assert_eq!(files[1].new_path, None);
+        assert_eq!(files[1].removed, HashSet::from([1]));
+    }
+
+    #[test]
+    fn test_parse_unified_diff_with_context_and_hg_headers() {
+        let diff = "\
+diff -r 000 -r 111 main.py
+--- a/main.py\tThu Jan 01 00:00:00 1970 +0000
++++ b/main.py\tThu Jan 01 00:00:00 1970 +0000
+@@ -1,4 +1,4 @@
+ import os
++# TODO: added between context
+ x = 1
+--- old comment kept as context
+ y = 2
+";
+        let files = parse_unified_diff(diff);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].new_path.as_deref(), Some("main.py"));
+        assert_eq!(files[0].added, HashSet::from([2]));
+        assert_eq!(files[0].removed, HashSet::from([3]));
+    }
+}
@@ -1,3 +1,8 @@
 pub mod blame;
+pub mod branches;
+pub(crate) mod cat_file;
 pub mod diff;
+pub mod notes;
+pub mod prs;
+pub mod trace;
 pub mod utils;
@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TodoError};
+use crate::git::utils::git_command;
+use crate::model::ScanStats;
+
+/// Notes ref that `todos snapshot --git-notes` writes to. Share it with
+/// `git push origin refs/notes/todo-tracker` and fetch it with
+/// `git fetch origin refs/notes/todo-tracker:refs/notes/todo-tracker`.
+pub const NOTES_REF: &str = "refs/notes/todo-tracker";
+
+/// TODO summary stored as a JSON git note on a commit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteSummary {
+    /// Commit the note is attached to. Not part of the note body, since git
+    /// already keys notes by commit.
+    #[serde(skip)]
+    pub commit: String,
+    pub taken_at: String,
+    pub total_todos: usize,
+    pub files_with_todos: usize,
+    pub by_tag: BTreeMap<String, usize>,
+}
+
+impl NoteSummary {
+    pub fn from_stats(taken_at: &str, stats: &ScanStats) -> Self {
+        NoteSummary {
+            commit: String::new(),
+            taken_at: taken_at.to_string(),
+            total_todos: stats.total_todos,
+            files_with_todos: stats.files_with_todos,
+            by_tag: stats.by_tag.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+        }
+    }
+}
+
+/// Attach `summary` to `rev`, replacing any earlier summary on that commit.
+/// Returns the full commit hash.
+pub fn write_note(repo: &Path, rev: &str, summary: &NoteSummary) -> Result<String> {
+    let commit = git_command(&["rev-parse", "--verify", &format!("{}^{{commit}}", rev)], repo)?
+        .trim()
+        .to_string();
+    let body = serde_json::to_string(summary).map_err(|e| TodoError::Git(e.to_string()))?;
+    let notes_ref = format!("--ref={}", NOTES_REF);
+    git_command(&["notes", &notes_ref, "add", "-f", "-m", &body, &commit], repo)?;
+    Ok(commit)
+}
+
+/// Commits with a note, from `git notes list` output (`<note> <commit>` lines).
+fn parse_notes_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(str::to_string)
+        .collect()
+}
+
+/// All summaries under `NOTES_REF`, oldest first. Notes that aren't valid
+/// summaries, e.g. written by hand, are skipped.
+pub fn read_notes(repo: &Path) -> Result<Vec<NoteSummary>> {
+    let notes_ref = format!("--ref={}", NOTES_REF);
+    // A repo without the notes ref simply has no summaries yet
+    if git_command(&["rev-parse", "--verify", "--quiet", NOTES_REF], repo).is_err() {
+        return Ok(Vec::new());
+    }
+    let list = git_command(&["notes", &notes_ref, "list"], repo)?;
+    let mut summaries: Vec<NoteSummary> = parse_notes_list(&list)
+        .into_iter()
+        .filter_map(|commit| {
+            let body = git_command(&["notes", &notes_ref, "show", &commit], repo).ok()?;
+            let mut summary: NoteSummary = serde_json::from_str(body.trim()).ok()?;
+            summary.commit = commit;
+            Some(summary)
+        })
+        .collect();
+    summaries.sort_by(|a, b| a.taken_at.cmp(&b.taken_at));
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_round_trip() {
+        let mut stats = ScanStats::new();
+        stats.total_todos = 3;
+        stats.files_with_todos = 2;
+        stats.by_tag.insert("TODO".to_string(), 2);
+        stats.by_tag.insert("FIXME".to_string(), 1);
+
+        let summary = NoteSummary::from_stats("2026-02-05T000000Z", &stats);
+        let body = serde_json::to_string(&summary).unwrap();
+        assert_eq!(
+            body,
+            r#"{"taken_at":"2026-02-05T000000Z","total_todos":3,"files_with_todos":2,"by_tag":{"FIXME":1,"TODO":2}}"#
+        );
+        let parsed: NoteSummary = serde_json::from_str(&body).unwrap();
+        assert_eq!(parsed, summary);
+    }
+
+    #[test]
+    fn test_parse_notes_list() {
+        let output = "1111111111111111111111111111111111111111 aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\n\
+                      2222222222222222222222222222222222222222 bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb\n";
+        assert_eq!(
+            parse_notes_list(output),
+            vec![
+                "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+                "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"
+            ]
+        );
+        assert!(parse_notes_list("").is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::date::Date;
+use crate::error::Result;
+use crate::git::blame::parse_pr_number;
+use crate::git::diff::{diff_todos, DiffFileError};
+use crate::git::utils::git_command;
+use crate::scanner::FileScanner;
+use crate::vcs::Vcs;
+
+/// A commit on the first-parent history of HEAD that landed a change: a
+/// merge commit, or a squash/rebase commit whose message names a PR.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LandedCommit {
+    pub commit: String,
+    /// First parent, the mainline before the change landed
+    pub parent: String,
+    pub date: Date,
+    pub subject: String,
+    pub pr_number: Option<u64>,
+}
+
+/// Parse `git log --format=%H%x1f%P%x1f%ct%x1f%B%x1e` output, keeping merges
+/// and commits with a PR number. Root commits have nothing to compare against
+/// and are dropped.
+fn parse_log(output: &str) -> Vec<LandedCommit> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').splitn(4, '\x1f');
+            let commit = fields.next()?.trim();
+            let parents: Vec<&str> = fields.next()?.split_whitespace().collect();
+            let secs: i64 = fields.next()?.trim().parse().ok()?;
+            let message = fields.next()?;
+            let parent = parents.first()?;
+            let pr_number = parse_pr_number(message);
+            if parents.len() < 2 && pr_number.is_none() {
+                return None;
+            }
+            Some(LandedCommit {
+                commit: commit.to_string(),
+                parent: parent.to_string(),
+                date: Date::from_timestamp(secs),
+                subject: message.lines().next().unwrap_or("").trim().to_string(),
+                pr_number,
+            })
+        })
+        .collect()
+}
+
+/// Changes landed on HEAD's first-parent history between `since` and `until`
+/// (inclusive), newest first.
+pub fn landed_commits(
+    repo: &Path,
+    since: Option<Date>,
+    until: Option<Date>,
+) -> Result<Vec<LandedCommit>> {
+    let output = git_command(
+        &["log", "--first-parent", "--format=%H%x1f%P%x1f%ct%x1f%B%x1e", "HEAD"],
+        repo,
+    )?;
+    Ok(parse_log(&output)
+        .into_iter()
+        .filter(|c| since.is_none_or(|since| c.date >= since))
+        .filter(|c| until.is_none_or(|until| c.date <= until))
+        .collect())
+}
+
+/// TODOs added and removed by one landed change.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PrDelta {
+    pub pr_number: Option<u64>,
+    pub commit: String,
+    pub date: String,
+    pub subject: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl PrDelta {
+    pub fn net(&self) -> i64 {
+        self.added as i64 - self.removed as i64
+    }
+
+    /// `#482`, or the short commit hash for merges without a PR number.
+    pub fn label(&self) -> String {
+        match self.pr_number {
+            Some(n) => format!("#{}", n),
+            None => self.commit[..self.commit.len().min(7)].to_string(),
+        }
+    }
+}
+
+/// Diff each landed change against its first parent. Results are ordered by
+/// TODOs added, most first. Files that couldn't be read are returned
+/// alongside; their TODOs are missing from the counts.
+pub fn pr_deltas(
+    scanner: &dyn FileScanner,
+    vcs: &dyn Vcs,
+    commits: &[LandedCommit],
+) -> Result<(Vec<PrDelta>, Vec<DiffFileError>)> {
+    let mut deltas = Vec::new();
+    let mut errors = Vec::new();
+    for landed in commits {
+        let diff = diff_todos(scanner, &landed.parent, &landed.commit, vcs)?;
+        errors.extend(diff.errors);
+        deltas.push(PrDelta {
+            pr_number: landed.pr_number,
+            commit: landed.commit.clone(),
+            date: landed.date.to_string(),
+            subject: landed.subject.clone(),
+            added: diff.added.len(),
+            removed: diff.removed.len(),
+        });
+    }
+    sort_deltas(&mut deltas);
+    Ok((deltas, errors))
+}
+
+fn sort_deltas(deltas: &mut [PrDelta]) {
+    deltas.sort_by(|a, b| {
+        b.added
+            .cmp(&a.added)
+            .then(b.net().cmp(&a.net()))
+            .then_with(|| b.date.cmp(&a.date))
+    });
+}
+
+/// Table of PRs that changed the TODO count. PRs that neither added nor
+/// removed TODOs are counted in the heading but not listed.
+pub fn render_text(deltas: &[PrDelta]) -> String {
+    let mut out = String::new();
+    let changed: Vec<&PrDelta> = deltas.iter().filter(|d| d.added + d.removed > 0).collect();
+    writeln!(
+        out,
+        "TODOs by PR: {} PRs, {} changed the TODO count",
+        deltas.len(),
+        changed.len()
+    )
+    .unwrap();
+    if changed.is_empty() {
+        return out;
+    }
+    writeln!(out).unwrap();
+    let width = changed.iter().map(|d| d.label().len()).max().unwrap_or(0).max(2);
+    writeln!(
+        out,
+        "  {:<width$}  {:>5}  {:>7}  {:>5}  {:<10}  Subject",
+        "PR",
+        "Added",
+        "Removed",
+        "Net",
+        "Date",
+        width = width
+    )
+    .unwrap();
+    for d in changed {
+        writeln!(
+            out,
+            "  {:<width$}  {:>5}  {:>7}  {:>+5}  {:<10}  {}",
+            d.label(),
+            d.added,
+            d.removed,
+            d.net(),
+            d.date,
+            d.subject,
+            width = width
+        )
+        .unwrap();
+    }
+    let added: usize = deltas.iter().map(|d| d.added).sum();
+    let removed: usize = deltas.iter().map(|d| d.removed).sum();
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "Total: {} added, {} removed ({:+})",
+        added,
+        removed,
+        added as i64 - removed as i64
+    )
+    .unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(pr_number: Option<u64>, added: usize, removed: usize, date: &str) -> PrDelta {
+        PrDelta {
+            pr_number,
+            commit: "0123456789abcdef".to_string(),
+            date: date.to_string(),
+            subject: "subject".to_string(),
+            added,
+            removed,
+        }
+    }
+
+    #[test]
+    fn test_parse_log_keeps_merges_and_pr_commits() {
+        let output = "aaa\x1fp1 p2\x1f1697328000\x1fMerge pull request #17 from alice/x\n\nBody\n\x1e\n\
+                      bbb\x1fp3\x1f1697328000\x1fAdd parser (#482)\n\x1e\n\
+                      ccc\x1fp4\x1f1697328000\x1fFix typo\n\x1e\n\
+                      ddd\x1fp5 p6\x1f1697328000\x1fMerge branch 'main'\n\x1e\n\
+                      eee\x1f\x1f1697328000\x1fInitial commit (#1)\n\x1e\n";
+        let commits = parse_log(output);
+        assert_eq!(commits.len(), 3);
+        assert_eq!(commits[0].commit, "aaa");
+        assert_eq!(commits[0].parent, "p1");
+        assert_eq!(commits[0].pr_number, Some(17));
+        assert_eq!(commits[0].subject, "Merge pull request #17 from alice/x");
+        assert_eq!(commits[0].date.to_string(), "2023-10-15");
+        assert_eq!(commits[1].pr_number, Some(482));
+        assert_eq!(commits[2].commit, "ddd");
+        assert_eq!(commits[2].pr_number, None);
+    }
+
+    #[test]
+    fn test_sort_and_label() {
+        let mut deltas = vec![
+            delta(Some(1), 1, 0, "2026-01-01"),
+            delta(None, 3, 3, "2026-01-02"),
+            delta(Some(2), 3, 0, "2026-01-03"),
+        ];
+        sort_deltas(&mut deltas);
+        assert_eq!(deltas[0].label(), "#2");
+        assert_eq!(deltas[1].label(), "0123456");
+        assert_eq!(deltas[1].net(), 0);
+        assert_eq!(deltas[2].label(), "#1");
+    }
+
+    #[test]
+    fn test_render_text() {
+        let deltas = vec![delta(Some(482), 4, 1, "2026-01-03"), delta(Some(7), 0, 0, "2026-01-01")];
+        let text = render_text(&deltas);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "TODOs by PR: 2 PRs, 1 changed the TODO count");
+        assert_eq!(lines[2], "  PR    Added  Removed    Net  Date        Subject");
+        assert_eq!(lines[3], "  #482      4        1     +3  2026-01-03  subject");
+        assert_eq!(lines[5], "Total: 4 added, 1 removed (+3)");
+        assert_eq!(render_text(&[]), "TODOs by PR: 0 PRs, 0 changed the TODO count\n");
+    }
+}
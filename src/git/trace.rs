@@ -0,0 +1,377 @@
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::date::Date;
+use crate::error::Result;
+use crate::git::diff::{parse_name_status, read_at_rev, scan_content, DiffFileError};
+use crate::git::utils::git_command;
+use crate::model::{TodoItem, TodoTag};
+use crate::scanner::FileScanner;
+use crate::vcs::Vcs;
+
+/// A commit that may have touched the traced TODO: one that changed how often
+/// its text occurs in the tree.
+#[derive(Debug, Clone, PartialEq)]
+struct Candidate {
+    commit: String,
+    has_parent: bool,
+    author: String,
+    email: String,
+    date: Date,
+    subject: String,
+}
+
+/// Parse `git log --format=%H%x1f%P%x1f%an%x1f%ae%x1f%ct%x1f%s%x1e` output.
+fn parse_log(output: &str) -> Vec<Candidate> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').splitn(6, '\x1f');
+            let commit = fields.next()?.trim();
+            let has_parent = !fields.next()?.trim().is_empty();
+            let author = fields.next()?;
+            let email = fields.next()?;
+            let secs: i64 = fields.next()?.trim().parse().ok()?;
+            let subject = fields.next()?;
+            Some(Candidate {
+                commit: commit.to_string(),
+                has_parent,
+                author: author.to_string(),
+                email: email.to_string(),
+                date: Date::from_timestamp(secs),
+                subject: subject.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceEventKind {
+    Introduced,
+    Moved,
+    Removed,
+}
+
+impl TraceEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TraceEventKind::Introduced => "introduced",
+            TraceEventKind::Moved => "moved",
+            TraceEventKind::Removed => "removed",
+        }
+    }
+}
+
+/// One step in a TODO's history. Locations are `file:line`, relative to the
+/// repository root.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraceEvent {
+    pub kind: TraceEventKind,
+    pub commit: String,
+    pub author: String,
+    pub email: String,
+    pub date: String,
+    pub subject: String,
+    /// Where the TODO was before the commit, for moves and removals
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// Where the TODO was after the commit, for introductions and moves
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+/// The history of one TODO, oldest event first.
+#[derive(Debug, Clone, Serialize)]
+pub struct Trace {
+    pub tag: String,
+    pub message: String,
+    pub events: Vec<TraceEvent>,
+}
+
+/// Locations of TODOs matching (`tag`, `message`) in one side of a commit.
+fn locations(
+    items: Vec<TodoItem>,
+    tag: &TodoTag,
+    message: &str,
+    root: &Path,
+) -> BTreeSet<(String, usize)> {
+    items
+        .into_iter()
+        .filter(|item| &item.tag == tag && item.message.trim() == message)
+        .map(|item| (item.relative_path(root), item.line))
+        .collect()
+}
+
+/// Events for one commit, given where the TODO was in the files it changed
+/// before and after. A path that lost the TODO paired with a path that gained
+/// it is a move; unpaired paths are removals or introductions.
+fn events_for(
+    candidate: &Candidate,
+    before: &BTreeSet<(String, usize)>,
+    after: &BTreeSet<(String, usize)>,
+) -> Vec<TraceEvent> {
+    let before_paths: BTreeSet<&str> = before.iter().map(|(p, _)| p.as_str()).collect();
+    let after_paths: BTreeSet<&str> = after.iter().map(|(p, _)| p.as_str()).collect();
+    let lost: Vec<String> = before
+        .iter()
+        .filter(|(p, _)| !after_paths.contains(p.as_str()))
+        .map(|(p, line)| format!("{}:{}", p, line))
+        .collect();
+    let gained: Vec<String> = after
+        .iter()
+        .filter(|(p, _)| !before_paths.contains(p.as_str()))
+        .map(|(p, line)| format!("{}:{}", p, line))
+        .collect();
+
+    let event = |kind, from: Option<&String>, to: Option<&String>| TraceEvent {
+        kind,
+        commit: candidate.commit.clone(),
+        author: candidate.author.clone(),
+        email: candidate.email.clone(),
+        date: candidate.date.to_string(),
+        subject: candidate.subject.clone(),
+        from: from.cloned(),
+        to: to.cloned(),
+    };
+    let mut events = Vec::new();
+    for i in 0..lost.len().max(gained.len()) {
+        events.push(match (lost.get(i), gained.get(i)) {
+            (Some(from), Some(to)) => event(TraceEventKind::Moved, Some(from), Some(to)),
+            (Some(from), None) => event(TraceEventKind::Removed, Some(from), None),
+            (None, to) => event(TraceEventKind::Introduced, None, to),
+        });
+    }
+    events
+}
+
+/// Walk HEAD's history for the TODO `tag`/`message`, using git's pickaxe to
+/// find commits that changed how often the message occurs and then scanning
+/// both sides of each. Files that couldn't be read are returned alongside.
+pub fn trace(
+    scanner: &dyn FileScanner,
+    vcs: &dyn Vcs,
+    tag: &TodoTag,
+    message: &str,
+) -> Result<(Trace, Vec<DiffFileError>)> {
+    let root = vcs.root();
+    let message = message.trim();
+    // An empty message gives the pickaxe nothing better to look for
+    let needle = if message.is_empty() { tag.as_str() } else { message };
+    let output = git_command(
+        &[
+            "log",
+            "--reverse",
+            "--no-renames",
+            "--format=%H%x1f%P%x1f%an%x1f%ae%x1f%ct%x1f%s%x1e",
+            &format!("-S{}", needle),
+            "HEAD",
+        ],
+        root,
+    )?;
+
+    let mut events = Vec::new();
+    let mut errors = Vec::new();
+    for candidate in parse_log(&output) {
+        let changes = git_command(
+            &[
+                "diff-tree",
+                "-r",
+                "--root",
+                "--no-renames",
+                "--no-commit-id",
+                "--name-status",
+                &candidate.commit,
+            ],
+            root,
+        )?;
+        let parent = format!("{}^", candidate.commit);
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        for file in parse_name_status(&changes, root) {
+            if candidate.has_parent && file.in_base() {
+                if let Some(content) = read_at_rev(vcs, &file.path, &parent, &mut errors) {
+                    before.extend(scan_content(scanner, &file.path, &content));
+                }
+            }
+            if file.in_head() {
+                if let Some(content) = read_at_rev(vcs, &file.path, &candidate.commit, &mut errors) {
+                    after.extend(scan_content(scanner, &file.path, &content));
+                }
+            }
+        }
+        events.extend(events_for(
+            &candidate,
+            &locations(before, tag, message, root),
+            &locations(after, tag, message, root),
+        ));
+    }
+
+    Ok((
+        Trace {
+            tag: tag.as_str().to_string(),
+            message: message.to_string(),
+            events,
+        },
+        errors,
+    ))
+}
+
+/// Parse a `file:line` trace target, or `file:line@rev` to name the line
+/// as committed at `rev`.
+pub fn parse_location(target: &str) -> Option<(PathBuf, usize, Option<String>)> {
+    if let Some((location, rev)) = target.rsplit_once('@') {
+        if let Some((file, line, None)) = parse_location(location).filter(|_| !rev.is_empty()) {
+            return Some((file, line, Some(rev.to_string())));
+        }
+    }
+    let (file, line) = target.rsplit_once(':')?;
+    let line = line.parse().ok().filter(|&line| line > 0)?;
+    Some((PathBuf::from(file), line, None))
+}
+
+/// The TODO on `line` of `file` as committed at `rev`.
+pub fn item_at_rev(
+    scanner: &dyn FileScanner,
+    vcs: &dyn Vcs,
+    file: &Path,
+    line: usize,
+    rev: &str,
+) -> Result<Option<TodoItem>> {
+    let content = vcs.file_at_rev(file, rev)?;
+    let content = String::from_utf8_lossy(&content);
+    Ok(scan_content(scanner, file, &content)
+        .into_iter()
+        .find(|item| item.line == line))
+}
+
+/// The last committed version of a TODO that is gone from HEAD, by its
+/// fingerprint relative to `scan_root`: HEAD's history is searched, newest
+/// first, for a commit whose parent had the TODO in a file the commit
+/// changed.
+pub fn find_removed(
+    scanner: &dyn FileScanner,
+    vcs: &dyn Vcs,
+    fingerprint: &str,
+    scan_root: &Path,
+) -> Result<Option<TodoItem>> {
+    let root = vcs.root();
+    let output = git_command(
+        &["log", "--no-renames", "--name-status", "--format=%x1e%H", "HEAD"],
+        root,
+    )?;
+    let mut errors = Vec::new();
+    for record in output.split('\x1e').skip(1) {
+        let (commit, changes) = record.split_once('\n').unwrap_or((record, ""));
+        let parent = format!("{}^", commit.trim());
+        for file in parse_name_status(changes, root) {
+            if !file.in_base() || !file.path.starts_with(scan_root) {
+                continue;
+            }
+            let Some(content) = read_at_rev(vcs, &file.path, &parent, &mut errors) else {
+                continue;
+            };
+            let found = scan_content(scanner, &file.path, &content)
+                .into_iter()
+                .find(|item| item.fingerprint(scan_root) == fingerprint);
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+    }
+    Ok(None)
+}
+
+pub fn render_text(trace: &Trace) -> String {
+    let mut out = String::new();
+    writeln!(out, "History of [{}] {}", trace.tag, trace.message).unwrap();
+    if trace.events.is_empty() {
+        writeln!(out, "  No commits on HEAD touched it; it has not been committed yet.").unwrap();
+        return out;
+    }
+    writeln!(out).unwrap();
+    for event in &trace.events {
+        let location = match (&event.from, &event.to) {
+            (Some(from), Some(to)) => format!("{} -> {}", from, to),
+            (Some(location), None) | (None, Some(location)) => location.clone(),
+            (None, None) => String::new(),
+        };
+        writeln!(
+            out,
+            "  {}  {}  {:<10}  {}",
+            event.date,
+            &event.commit[..event.commit.len().min(7)],
+            event.kind.as_str(),
+            location
+        )
+        .unwrap();
+        writeln!(out, "      {} <{}>: {}", event.author, event.email, event.subject).unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate() -> Candidate {
+        Candidate {
+            commit: "0123456789abcdef".to_string(),
+            has_parent: true,
+            author: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            date: Date::from_timestamp(0),
+            subject: "subject".to_string(),
+        }
+    }
+
+    fn locs(locations: &[(&str, usize)]) -> BTreeSet<(String, usize)> {
+        locations.iter().map(|(p, l)| (p.to_string(), *l)).collect()
+    }
+
+    #[test]
+    fn test_parse_log() {
+        let output = "abc\x1f\x1fAlice\x1falice@example.com\x1f86400\x1fFirst\x1e\n\
+                      def\x1fabc\x1fBob\x1fbob@example.com\x1f172800\x1fSecond\x1e\n";
+        let commits = parse_log(output);
+        assert_eq!(commits.len(), 2);
+        assert!(!commits[0].has_parent);
+        assert_eq!(commits[1].author, "Bob");
+        assert_eq!(commits[1].date.to_string(), "1970-01-03");
+    }
+
+    #[test]
+    fn test_events_for_moves_introductions_and_removals() {
+        let c = candidate();
+        let introduced = events_for(&c, &locs(&[]), &locs(&[("a.rs", 3)]));
+        assert_eq!(introduced.len(), 1);
+        assert_eq!(introduced[0].kind, TraceEventKind::Introduced);
+        assert_eq!(introduced[0].to.as_deref(), Some("a.rs:3"));
+
+        let moved = events_for(&c, &locs(&[("a.rs", 3)]), &locs(&[("b.rs", 9)]));
+        assert_eq!(moved[0].kind, TraceEventKind::Moved);
+        assert_eq!(moved[0].from.as_deref(), Some("a.rs:3"));
+        assert_eq!(moved[0].to.as_deref(), Some("b.rs:9"));
+
+        let removed = events_for(&c, &locs(&[("a.rs", 3)]), &locs(&[]));
+        assert_eq!(removed[0].kind, TraceEventKind::Removed);
+
+        // Shifting within a file is not a move
+        assert!(events_for(&c, &locs(&[("a.rs", 3)]), &locs(&[("a.rs", 5)])).is_empty());
+    }
+
+    #[test]
+    fn test_parse_location() {
+        let at = |file: &str, line, rev: Option<&str>| {
+            Some((PathBuf::from(file), line, rev.map(str::to_string)))
+        };
+        assert_eq!(parse_location("src/a.rs:12"), at("src/a.rs", 12, None));
+        assert_eq!(parse_location("src/a.rs:12@HEAD~3"), at("src/a.rs", 12, Some("HEAD~3")));
+        assert_eq!(parse_location("0123456789abcdef"), None);
+        assert_eq!(parse_location("src/a.rs:0"), None);
+        assert_eq!(parse_location("src/a.rs:12@"), None);
+    }
+}
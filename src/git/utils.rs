@@ -1,35 +1,225 @@
+use std::io::{ErrorKind, Write};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::error::{Result, TodoError};
 
 /// Run a git command in the given repo directory and return stdout as a String.
-pub fn git_command(args: &[&str], repo_root: &Path) -> Result<String, String> {
-    let output = Command::new("git")
-        .args(args)
-        .current_dir(repo_root)
-        .output()
-        .map_err(|e| format!("Failed to execute git: {}", e))?;
+pub fn git_command(args: &[&str], repo_root: &Path) -> Result<String> {
+    let stdout = git_command_raw(args, repo_root)?;
+    String::from_utf8(stdout)
+        .map_err(|e| TodoError::Git(format!("Invalid UTF-8 in git output: {}", e)))
+}
+
+/// Attempts at a git command that fails because another git process holds
+/// a lock in the repository, e.g. `index.lock` during a concurrent commit.
+const LOCK_ATTEMPTS: u32 = 3;
+
+/// Whether git failed only because another git process holds a lock.
+fn is_lock_contention(stderr: &str) -> bool {
+    stderr.contains(".lock': File exists")
+        || stderr.contains("Another git process seems to be running")
+}
+
+/// Run a git command and return stdout as raw bytes (e.g. file contents that
+/// may be binary or in a non-UTF-8 encoding). Retried with backoff while
+/// another git process holds a lock.
+pub fn git_command_raw(args: &[&str], repo_root: &Path) -> Result<Vec<u8>> {
+    git_command_input(args, None, repo_root)
+}
+
+/// Run a git command with `input` on stdin, for `--stdin` modes taking more
+/// revisions or paths than fit on a command line.
+pub fn git_command_input(
+    args: &[&str],
+    input: Option<&[u8]>,
+    repo_root: &Path,
+) -> Result<Vec<u8>> {
+    run_git(args, input, &[], repo_root)
+}
+
+/// Run a git command with `config` settings passed through the environment
+/// instead of `-c`, so secrets such as auth headers stay out of the process
+/// list and out of error messages, which echo the arguments.
+pub fn git_command_with_config(
+    args: &[&str],
+    config: &[(String, String)],
+    repo_root: &Path,
+) -> Result<String> {
+    let stdout = run_git(args, None, config, repo_root)?;
+    String::from_utf8(stdout)
+        .map_err(|e| TodoError::Git(format!("Invalid UTF-8 in git output: {}", e)))
+}
+
+fn run_git(
+    args: &[&str],
+    input: Option<&[u8]>,
+    config: &[(String, String)],
+    repo_root: &Path,
+) -> Result<Vec<u8>> {
+    let mut delay = Duration::from_millis(50);
+    let mut attempt = 1;
+    let output = loop {
+        let output = spawn_git(args, input, config, repo_root).map_err(|e| match e.kind() {
+            // A missing working directory is reported the same way
+            ErrorKind::NotFound if repo_root.is_dir() => TodoError::GitNotFound,
+            _ => TodoError::Git(format!("Failed to execute git: {}", e)),
+        })?;
+        if output.status.success()
+            || attempt == LOCK_ATTEMPTS
+            || !is_lock_contention(&String::from_utf8_lossy(&output.stderr))
+        {
+            break output;
+        }
+        std::thread::sleep(delay);
+        delay *= 4;
+        attempt += 1;
+    };
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("git {} failed: {}", args.join(" "), stderr.trim()));
+        if stderr.contains("not a git repository") {
+            return Err(TodoError::NotARepo(repo_root.to_path_buf()));
+        }
+        return Err(TodoError::Git(format!("git {} failed: {}", args.join(" "), stderr.trim())));
     }
 
-    String::from_utf8(output.stdout)
-        .map_err(|e| format!("Invalid UTF-8 in git output: {}", e))
+    Ok(output.stdout)
 }
 
-/// Check if the given path is inside a git repository.
+/// A git command with `config` set through `GIT_CONFIG_COUNT`,
+/// `GIT_CONFIG_KEY_<n>` and `GIT_CONFIG_VALUE_<n>`.
+fn git_process(args: &[&str], config: &[(String, String)], repo_root: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.args(args).current_dir(repo_root);
+    if !config.is_empty() {
+        command.env("GIT_CONFIG_COUNT", config.len().to_string());
+        for (n, (key, value)) in config.iter().enumerate() {
+            command.env(format!("GIT_CONFIG_KEY_{}", n), key);
+            command.env(format!("GIT_CONFIG_VALUE_{}", n), value);
+        }
+    }
+    command
+}
+
+fn spawn_git(
+    args: &[&str],
+    input: Option<&[u8]>,
+    config: &[(String, String)],
+    repo_root: &Path,
+) -> std::io::Result<std::process::Output> {
+    let mut command = git_process(args, config, repo_root);
+    let Some(input) = input else {
+        return command.output();
+    };
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // Write from a thread so a full stdout pipe can't deadlock the two sides
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            // git exiting early is reported through its status
+            let _ = stdin.write_all(input);
+        });
+        child.wait_with_output()
+    })
+}
+
+/// Check if the given path is inside a git working tree, including linked
+/// worktrees. A bare repository has none.
 pub fn is_git_repo(path: &Path) -> bool {
     Command::new("git")
         .args(["rev-parse", "--is-inside-work-tree"])
         .current_dir(path)
         .output()
-        .map(|o| o.status.success())
+        .map(|o| o.status.success() && o.stdout.trim_ascii() == b"true")
         .unwrap_or(false)
 }
 
+/// Check if `path` is a bare repository, or inside one's git directory.
+pub fn is_bare_repo(path: &Path) -> bool {
+    git_command(&["rev-parse", "--is-bare-repository"], path)
+        .map(|out| out.trim() == "true")
+        .unwrap_or(false)
+}
+
+/// Absolute path of the git directory for `path`.
+pub fn git_dir(path: &Path) -> Result<std::path::PathBuf> {
+    let output = git_command(&["rev-parse", "--absolute-git-dir"], path)?;
+    Ok(std::path::PathBuf::from(output.trim()))
+}
+
+/// Check if the repository at `path` is a shallow clone.
+pub fn is_shallow(path: &Path) -> bool {
+    git_command(&["rev-parse", "--is-shallow-repository"], path)
+        .map(|out| out.trim() == "true")
+        .unwrap_or(false)
+}
+
+/// The promisor remote of a partial clone (`git clone --filter=...`), which
+/// file contents missing locally are fetched from on demand.
+/// Clones mark the remote with `remote.<name>.promisor`; older repositories
+/// may only have `extensions.partialClone`.
+pub fn partial_clone_remote(path: &Path) -> Option<String> {
+    let promisors = git_command(
+        &["config", "--bool", "--get-regexp", r"^remote\..*\.promisor$"],
+        path,
+    )
+    .unwrap_or_default();
+    promisors
+        .lines()
+        .filter_map(|line| line.strip_suffix(" true"))
+        .filter_map(|key| key.strip_prefix("remote.")?.strip_suffix(".promisor"))
+        .map(str::to_string)
+        .next()
+        .or_else(|| {
+            git_command(&["config", "--get", "extensions.partialClone"], path)
+                .ok()
+                .map(|out| out.trim().to_string())
+                .filter(|remote| !remote.is_empty())
+        })
+}
+
 /// Get the root directory of the git repository containing `path`.
-pub fn repo_root(path: &Path) -> Result<std::path::PathBuf, String> {
+pub fn repo_root(path: &Path) -> Result<std::path::PathBuf> {
     let output = git_command(&["rev-parse", "--show-toplevel"], path)?;
     Ok(std::path::PathBuf::from(output.trim()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_is_passed_through_environment() {
+        let config = [("http.extraHeader".to_string(), "Authorization: Basic s3cret".to_string())];
+        let command = git_process(&["fetch", "origin"], &config, Path::new("."));
+        assert!(command.get_args().all(|arg| !arg.to_string_lossy().contains("s3cret")));
+        let envs: Vec<_> = command
+            .get_envs()
+            .map(|(k, v)| (k.to_owned(), v.map(|v| v.to_owned())))
+            .collect();
+        assert!(envs.contains(&("GIT_CONFIG_COUNT".into(), Some("1".into()))));
+        assert!(envs.contains(&("GIT_CONFIG_KEY_0".into(), Some("http.extraHeader".into()))));
+        assert!(envs.contains(&("GIT_CONFIG_VALUE_0".into(), Some(config[0].1.clone().into()))));
+    }
+
+    #[test]
+    fn test_is_lock_contention() {
+        let stderr = "fatal: Unable to create '/repo/.git/index.lock': File exists.\n\n\
+                      Another git process seems to be running in this repository";
+        assert!(is_lock_contention(stderr));
+        assert!(!is_lock_contention("fatal: not a git repository (or any of the parent directories): .git"));
+    }
+
+    #[test]
+    fn test_not_a_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = git_command(&["rev-parse", "--show-toplevel"], dir.path()).unwrap_err();
+        assert!(matches!(err, TodoError::NotARepo(_)), "{}", err);
+    }
+}
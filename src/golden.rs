@@ -0,0 +1,335 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::error::{Result, TodoError};
+use crate::model::ScanResult;
+use crate::ordering::path_sort_key;
+
+/// Representation of a golden file, chosen from its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoldenFormat {
+    /// One `path: TAG: message` line per item
+    Text,
+    /// A JSON array of items
+    Json,
+}
+
+impl GoldenFormat {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => GoldenFormat::Json,
+            _ => GoldenFormat::Text,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct GoldenItem {
+    file: String,
+    tag: String,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    issue: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<String>,
+}
+
+impl GoldenItem {
+    fn to_line(&self) -> String {
+        let mut line = format!("{}: {}: {}", self.file, self.tag, self.message);
+        let meta: Vec<String> = [
+            ("author", &self.author),
+            ("issue", &self.issue),
+            ("priority", &self.priority),
+        ]
+        .iter()
+        .filter_map(|(key, value)| value.as_ref().map(|v| format!("{}={}", key, v)))
+        .collect();
+        if !meta.is_empty() {
+            line.push_str(&format!(" [{}]", meta.join(", ")));
+        }
+        line
+    }
+}
+
+/// Normalized rendering of a scan for golden comparison. Paths are relative
+/// to the scan root and line numbers are left out, so the output only changes
+/// when a TODO is added, removed or reworded, not when code around it moves.
+/// Entries are sorted by canonical path order, then by content.
+pub fn render_golden(result: &ScanResult, format: GoldenFormat) -> String {
+    let root = &result.metadata.root_path;
+    let mut items: Vec<GoldenItem> = result
+        .items
+        .iter()
+        .map(|item| GoldenItem {
+            file: item.relative_path(root),
+            tag: item.tag.to_string(),
+            message: item.message.clone(),
+            author: item.author.clone(),
+            issue: item.issue.clone(),
+            priority: item.priority.as_ref().map(|p| p.as_str().to_string()),
+        })
+        .collect();
+    items.sort_by_cached_key(|item| item.to_line());
+    items.sort_by_cached_key(|item| path_sort_key(Path::new(&item.file)));
+
+    match format {
+        GoldenFormat::Text => items.iter().map(|i| i.to_line() + "\n").collect(),
+        GoldenFormat::Json => {
+            // Serializing plain strings and options cannot fail
+            serde_json::to_string_pretty(&items).unwrap_or_default() + "\n"
+        }
+    }
+}
+
+/// One line of a line diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Line diff of `expected` against `actual` (longest common subsequence).
+/// Common leading and trailing lines are matched up front, which keeps the
+/// quadratic part small for the usual handful of changed lines.
+pub fn line_diff<'a>(expected: &'a str, actual: &'a str) -> Vec<DiffLine<'a>> {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out: Vec<DiffLine> = old[..prefix].iter().map(|l| DiffLine::Same(l)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push(DiffLine::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push(DiffLine::Removed(a[i]));
+            i += 1;
+        } else {
+            out.push(DiffLine::Added(b[j]));
+            j += 1;
+        }
+    }
+    out.extend(old[old.len() - suffix..].iter().map(|l| DiffLine::Same(l)));
+    out
+}
+
+/// Render changed lines with `context` unchanged lines around each change, in
+/// the style of a unified diff. Returns an empty string when nothing changed.
+pub fn format_diff(diff: &[DiffLine], context: usize) -> String {
+    let changed: Vec<usize> = diff
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| !matches!(l, DiffLine::Same(_)))
+        .map(|(i, _)| i)
+        .collect();
+    let near_change = |i: usize| {
+        changed
+            .iter()
+            .any(|&c| i + context >= c && i <= c + context)
+    };
+
+    let mut out = String::new();
+    let mut skipped = false;
+    for (i, line) in diff.iter().enumerate() {
+        if !near_change(i) {
+            skipped = true;
+            continue;
+        }
+        if skipped && !out.is_empty() {
+            writeln!(out, "...").unwrap();
+        }
+        skipped = false;
+        match line {
+            DiffLine::Same(l) => writeln!(out, "  {}", l).unwrap(),
+            DiffLine::Removed(l) => writeln!(out, "- {}", l).unwrap(),
+            DiffLine::Added(l) => writeln!(out, "+ {}", l).unwrap(),
+        }
+    }
+    out
+}
+
+/// Compare a scan with a golden file. Returns the formatted diff, or `None`
+/// when the file matches.
+pub fn check_golden(path: &Path, result: &ScanResult) -> Result<Option<String>> {
+    let expected = std::fs::read_to_string(path).map_err(|e| {
+        TodoError::Config(format!(
+            "Failed to read golden file {}: {} (create it with --update-golden)",
+            path.display(),
+            e
+        ))
+    })?;
+    let actual = render_golden(result, GoldenFormat::from_path(path));
+    // Tolerate CRLF checkouts of the golden file
+    let expected = expected.replace("\r\n", "\n");
+    if expected == actual {
+        return Ok(None);
+    }
+    // Differences in trailing newlines alone produce an empty diff
+    let diff = format_diff(&line_diff(&expected, &actual), 2);
+    Ok((!diff.is_empty()).then_some(diff))
+}
+
+/// Drop items found in the golden file itself, which holds TODO text when it
+/// lives inside the scanned tree.
+pub fn exclude_golden_file(path: &Path, result: &mut ScanResult) {
+    let Ok(golden) = path.canonicalize() else {
+        return;
+    };
+    let before = result.items.len();
+    result
+        .items
+        .retain(|item| item.file.canonicalize().map_or(true, |f| f != golden));
+    if result.items.len() != before {
+        result.recompute_stats();
+    }
+}
+
+/// Write the current scan as the golden file.
+pub fn update_golden(path: &Path, result: &ScanResult) -> Result<()> {
+    std::fs::write(path, render_golden(result, GoldenFormat::from_path(path)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Priority, ScanMetadata, ScanStats, TodoItem, TodoTag};
+    use std::path::PathBuf;
+
+    fn make_item(tag: TodoTag, file: &str, line: usize, message: &str) -> TodoItem {
+        TodoItem {
+            tag,
+            message: message.to_string(),
+            file: PathBuf::from(file),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    fn make_result(items: Vec<TodoItem>) -> ScanResult {
+        ScanResult {
+            items,
+            stats: ScanStats::default(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("/repo"),
+                timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_render_text_is_normalized() {
+        let mut with_meta = make_item(TodoTag::Fixme, "/repo/src/b.rs", 9, "leak");
+        with_meta.author = Some("alice".to_string());
+        with_meta.priority = Some(Priority::High);
+        let result = make_result(vec![
+            with_meta,
+            make_item(TodoTag::Todo, "/repo/src/a.rs", 40, "second"),
+            make_item(TodoTag::Todo, "/repo/src/a.rs", 2, "first"),
+        ]);
+        assert_eq!(
+            render_golden(&result, GoldenFormat::Text),
+            "src/a.rs: TODO: first\nsrc/a.rs: TODO: second\nsrc/b.rs: FIXME: leak [author=alice, priority=high]\n"
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        let result = make_result(vec![make_item(TodoTag::Todo, "/repo/a.rs", 1, "x")]);
+        let json = render_golden(&result, GoldenFormat::Json);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[0]["file"], "a.rs");
+        assert!(value[0].get("author").is_none());
+        assert!(value[0].get("line").is_none());
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(GoldenFormat::from_path(Path::new("todos.JSON")), GoldenFormat::Json);
+        assert_eq!(GoldenFormat::from_path(Path::new("expected.txt")), GoldenFormat::Text);
+        assert_eq!(GoldenFormat::from_path(Path::new("golden")), GoldenFormat::Text);
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let diff = line_diff("a\nb\nc\nd\n", "a\nc\nx\nd\n");
+        assert_eq!(
+            diff,
+            vec![
+                DiffLine::Same("a"),
+                DiffLine::Removed("b"),
+                DiffLine::Same("c"),
+                DiffLine::Added("x"),
+                DiffLine::Same("d"),
+            ]
+        );
+        assert!(line_diff("a\n", "a\n").iter().all(|l| matches!(l, DiffLine::Same(_))));
+    }
+
+    #[test]
+    fn test_format_diff_elides_distant_context() {
+        let expected: String = (1..=10).map(|n| format!("{}\n", n)).collect();
+        let actual = expected.replace("2\n", "two\n").replace("9\n", "nine\n");
+        let out = format_diff(&line_diff(&expected, &actual), 1);
+        assert_eq!(out, "  1\n- 2\n+ two\n  3\n...\n  8\n- 9\n+ nine\n  10\n");
+    }
+
+    #[test]
+    fn test_check_and_update_golden() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("expected.txt");
+        let result = make_result(vec![make_item(TodoTag::Todo, "/repo/a.rs", 1, "x")]);
+        assert!(check_golden(&path, &result).is_err());
+
+        update_golden(&path, &result).unwrap();
+        assert_eq!(check_golden(&path, &result).unwrap(), None);
+
+        let changed = make_result(vec![make_item(TodoTag::Todo, "/repo/a.rs", 1, "y")]);
+        let diff = check_golden(&path, &changed).unwrap().unwrap();
+        assert_eq!(diff, "- a.rs: TODO: x\n+ a.rs: TODO: y\n");
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::model::ScanResult;
+use crate::output::charset;
+
+/// A directory in the heatmap tree. Counts include all subdirectories.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeatNode {
+    pub name: String,
+    /// Path relative to the scan root, `.` for the root itself
+    pub path: String,
+    pub todos: usize,
+    /// Lines of code under this directory, when known
+    pub lines: Option<usize>,
+    pub children: Vec<HeatNode>,
+}
+
+impl HeatNode {
+    fn new(name: &str, path: String) -> Self {
+        HeatNode {
+            name: name.to_string(),
+            path,
+            todos: 0,
+            lines: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// TODOs per thousand lines of code, when lines are known.
+    pub fn density(&self) -> Option<f64> {
+        match self.lines {
+            Some(lines) if lines > 0 => Some(self.todos as f64 * 1000.0 / lines as f64),
+            _ => None,
+        }
+    }
+
+    /// What the heat color is based on: density when known, else the count.
+    fn heat_value(&self) -> f64 {
+        self.density().unwrap_or(self.todos as f64)
+    }
+
+    fn max_heat(&self) -> f64 {
+        self.children
+            .iter()
+            .map(|c| c.heat_value().max(c.max_heat()))
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Aggregate items into a directory tree, `max_depth` levels below the root.
+/// Deeper directories are folded into their ancestor at the depth limit.
+/// Children are ordered by TODO count, largest first. When the scan counted
+/// lines of code, each directory also gets the lines of all files under it,
+/// including files without TODOs.
+pub fn build_heatmap(result: &ScanResult, max_depth: usize) -> HeatNode {
+    #[derive(Default)]
+    struct Dir {
+        todos: usize,
+        lines: usize,
+        children: BTreeMap<String, Dir>,
+    }
+
+    let root_path = &result.metadata.root_path;
+    let dirs_of = |file: &Path| -> Vec<String> {
+        let rel = file.strip_prefix(root_path).unwrap_or(file);
+        let rel = rel.display().to_string().replace('\\', "/");
+        let mut dirs: Vec<String> = rel
+            .split('/')
+            .filter(|c| !c.is_empty() && *c != ".")
+            .map(str::to_string)
+            .collect();
+        dirs.pop(); // the file name
+        dirs.truncate(max_depth);
+        dirs
+    };
+
+    let mut root = Dir::default();
+    for item in &result.items {
+        root.todos += 1;
+        let mut node = &mut root;
+        for dir in dirs_of(&item.file) {
+            node = node.children.entry(dir).or_default();
+            node.todos += 1;
+        }
+    }
+
+    // Lines only go to directories that made it into the tree
+    for (file, lines) in &result.stats.lines_by_file {
+        root.lines += lines;
+        let mut node = &mut root;
+        for dir in dirs_of(file) {
+            match node.children.get_mut(&dir) {
+                Some(child) => node = child,
+                None => break,
+            }
+            node.lines += lines;
+        }
+    }
+
+    fn convert(name: &str, path: String, dir: Dir, has_lines: bool) -> HeatNode {
+        let mut node = HeatNode::new(name, path.clone());
+        node.todos = dir.todos;
+        node.lines = has_lines.then_some(dir.lines);
+        node.children = dir
+            .children
+            .into_iter()
+            .map(|(child, d)| {
+                let child_path = if path == "." {
+                    child.clone()
+                } else {
+                    format!("{}/{}", path, child)
+                };
+                convert(&child, child_path, d, has_lines)
+            })
+            .collect();
+        node.children.sort_by(|a, b| b.todos.cmp(&a.todos).then_with(|| a.name.cmp(&b.name)));
+        node
+    }
+    let has_lines = !result.stats.lines_by_file.is_empty();
+    convert(".", ".".to_string(), root, has_lines)
+}
+
+/// Heat bucket 0 (cool) to 3 (hot) relative to `max`.
+fn heat_level(value: f64, max: f64) -> usize {
+    if max <= 0.0 {
+        return 0;
+    }
+    match value / max {
+        r if r > 0.75 => 3,
+        r if r > 0.5 => 2,
+        r if r > 0.25 => 1,
+        _ => 0,
+    }
+}
+
+const BAR_WIDTH: usize = 30;
+
+/// Indented directory tree with bars sized by TODO count and colored by heat.
+pub fn render_text(root: &HeatNode) -> String {
+    use crate::output::paint::Colorize;
+
+    fn rows<'a>(node: &'a HeatNode, depth: usize, out: &mut Vec<(usize, &'a HeatNode)>) {
+        out.push((depth, node));
+        for child in &node.children {
+            rows(child, depth + 1, out);
+        }
+    }
+    let mut all = Vec::new();
+    rows(root, 0, &mut all);
+
+    let label = |depth: usize, node: &HeatNode| {
+        let suffix = if node.path == "." { "" } else { "/" };
+        format!("{}{}{}", "  ".repeat(depth), node.name, suffix)
+    };
+    let width = all.iter().map(|(d, n)| label(*d, n).len()).max().unwrap_or(0);
+    let max_heat = root.max_heat();
+
+    let mut out = String::new();
+    for (depth, node) in all {
+        let bar_len = (node.todos * BAR_WIDTH)
+            .checked_div(root.todos)
+            .unwrap_or(0)
+            .max(1);
+        let bar = charset::bar(bar_len);
+        let bar = match heat_level(node.heat_value(), max_heat) {
+            _ if depth == 0 => bar.normal(),
+            3 => bar.red(),
+            2 => bar.yellow(),
+            1 => bar.green(),
+            _ => bar.blue(),
+        };
+        write!(
+            out,
+            "{:<width$}  {}{} {:>5}",
+            label(depth, node),
+            bar,
+            " ".repeat(BAR_WIDTH - bar_len.min(BAR_WIDTH)),
+            node.todos,
+            width = width
+        )
+        .unwrap();
+        if let Some(density) = node.density() {
+            write!(out, "  {:.1}/KLOC", density).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+    out
+}
+
+const HEAT_COLORS: [&str; 4] = ["#4e79a7", "#59a14f", "#edc948", "#e15759"];
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Self-contained HTML treemap. Each directory's box is split among its
+/// subdirectories in proportion to their TODO counts, alternating between
+/// rows and columns at each level; TODOs in the directory's own files take
+/// up the remaining space.
+pub fn render_html(root: &HeatNode) -> String {
+    fn boxes(node: &HeatNode, depth: usize, max_heat: f64, out: &mut String) {
+        let mut offset = 0.0;
+        for child in &node.children {
+            let share = child.todos as f64 * 100.0 / node.todos.max(1) as f64;
+            let (left, top, width, height) = if depth.is_multiple_of(2) {
+                (offset, 0.0, share, 100.0)
+            } else {
+                (0.0, offset, 100.0, share)
+            };
+            offset += share;
+
+            let mut title = format!("{}: {} TODOs", child.path, child.todos);
+            if let Some(density) = child.density() {
+                write!(title, ", {:.1}/KLOC", density).unwrap();
+            }
+            write!(
+                out,
+                "<div class=\"box\" style=\"left:{:.3}%;top:{:.3}%;width:{:.3}%;height:{:.3}%;background:{}\" title=\"{}\">\
+                 <span>{} ({})</span>",
+                left,
+                top,
+                width,
+                height,
+                HEAT_COLORS[heat_level(child.heat_value(), max_heat)],
+                escape_html(&title),
+                escape_html(&child.name),
+                child.todos
+            )
+            .unwrap();
+            boxes(child, depth + 1, max_heat, out);
+            out.push_str("</div>\n");
+        }
+    }
+
+    let mut body = String::new();
+    boxes(root, 0, root.max_heat(), &mut body);
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>TODO heatmap</title>
+<style>
+body {{ font-family: sans-serif; margin: 1em; }}
+#map {{ position: relative; width: 100%; height: 80vh; background: #eee; }}
+.box {{ position: absolute; box-sizing: border-box; border: 1px solid #fff; overflow: hidden; }}
+.box span {{ font-size: 12px; padding: 2px; color: #111; }}
+</style>
+</head>
+<body>
+<h1>TODO heatmap</h1>
+<p>{} TODOs. Box size is the TODO count; color runs from blue (cool) to red (hot).</p>
+<div id="map">
+{}</div>
+</body>
+</html>
+"#,
+        root.todos, body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats, TodoItem, TodoTag};
+    use std::path::PathBuf;
+
+    fn make_item(file: &str) -> TodoItem {
+        TodoItem {
+            tag: TodoTag::Todo,
+            message: "x".to_string(),
+            file: PathBuf::from(file),
+            line: 1,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    fn make_result(files: &[&str]) -> ScanResult {
+        ScanResult {
+            items: files.iter().map(|f| make_item(f)).collect(),
+            stats: ScanStats::default(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("/repo"),
+                timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_build_heatmap_aggregates_and_folds_deep_dirs() {
+        let result = make_result(&[
+            "/repo/README.md",
+            "/repo/src/a.rs",
+            "/repo/src/output/b.rs",
+            "/repo/src/output/deep/c.rs",
+            "/repo/docs/d.md",
+        ]);
+        let root = build_heatmap(&result, 2);
+        assert_eq!(root.todos, 5);
+        assert_eq!(root.children.len(), 2);
+        let src = &root.children[0];
+        assert_eq!((src.path.as_str(), src.todos), ("src", 3));
+        // deep/ is folded into src/output at depth 2
+        assert_eq!(src.children[0].path, "src/output");
+        assert_eq!(src.children[0].todos, 2);
+        assert!(src.children[0].children.is_empty());
+    }
+
+    #[test]
+    fn test_render_text_and_html() {
+        crate::output::paint::control::set_override(false);
+        let result = make_result(&["/repo/src/a.rs", "/repo/src/b.rs", "/repo/<lib>/c.rs"]);
+        let root = build_heatmap(&result, 3);
+
+        let text = render_text(&root);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with(".  "));
+        assert!(lines[1].starts_with("  src/") && lines[1].ends_with("2"));
+
+        let html = render_html(&root);
+        assert!(html.contains("width:66.667%"));
+        assert!(html.contains("src (2)"));
+        assert!(html.contains("&lt;lib&gt; (1)"));
+    }
+
+    #[test]
+    fn test_build_heatmap_with_lines() {
+        let mut result = make_result(&["/repo/src/a.rs", "/repo/src/b.rs"]);
+        let lines = [
+            ("/repo/src/a.rs", 400),
+            ("/repo/src/c.rs", 600),
+            ("/repo/docs/d.md", 1000),
+        ];
+        for (file, lines) in lines {
+            result.stats.add_file_lines(Path::new(file), lines);
+        }
+        let root = build_heatmap(&result, 3);
+        assert_eq!(root.lines, Some(2000));
+        // docs/ has no TODOs, so only src/ is in the tree, with all its files' lines
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(root.children[0].lines, Some(1000));
+        assert_eq!(root.children[0].density(), Some(2.0));
+
+        assert_eq!(build_heatmap(&make_result(&["/repo/a.rs"]), 3).lines, None);
+    }
+
+    #[test]
+    fn test_density() {
+        let mut node = HeatNode::new("src", "src".to_string());
+        node.todos = 3;
+        assert_eq!(node.density(), None);
+        node.lines = Some(1500);
+        assert_eq!(node.density(), Some(2.0));
+    }
+}
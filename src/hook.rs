@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TodoError};
+use crate::model::TodoItem;
+
+/// Default trailer key appended by `hook commit-msg` in trailer mode.
+pub const DEFAULT_TRAILER: &str = "Introduces-TODO";
+
+/// `[hook]` config section.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HookConfig {
+    /// What `hook commit-msg` does about new TODOs without an issue: "block" or "trailer"
+    pub commit_msg: Option<String>,
+    /// Trailer key used in trailer mode
+    pub trailer: Option<String>,
+}
+
+/// How `hook commit-msg` reacts to new TODOs without an issue reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitMsgMode {
+    /// Reject the commit
+    Block,
+    /// Let the commit through and record the count in a message trailer
+    Trailer,
+}
+
+impl CommitMsgMode {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "block" => Ok(CommitMsgMode::Block),
+            "trailer" => Ok(CommitMsgMode::Trailer),
+            other => Err(TodoError::Config(format!(
+                "Unknown commit-msg mode '{}' (expected block or trailer)",
+                other
+            ))),
+        }
+    }
+}
+
+/// New items that carry no issue reference.
+pub fn missing_issue(added: &[TodoItem]) -> Vec<&TodoItem> {
+    added.iter().filter(|item| item.issue.is_none()).collect()
+}
+
+fn is_trailer_line(line: &str) -> bool {
+    match line.split_once(": ") {
+        Some((key, _)) => {
+            !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        }
+        None => false,
+    }
+}
+
+/// Append `key: count` to a commit message. An existing trailer with the same
+/// key is replaced (so amending doesn't stack trailers), the trailer joins an
+/// existing trailer block or starts a new paragraph, and trailing `#` comment
+/// lines stay at the end.
+pub fn append_trailer(message: &str, key: &str, count: usize) -> String {
+    let prefix = format!("{}: ", key);
+    let mut lines: Vec<&str> = message
+        .lines()
+        .filter(|l| !l.starts_with(&prefix))
+        .collect();
+
+    let mut comments = Vec::new();
+    while lines.last().is_some_and(|l| l.starts_with('#') || l.trim().is_empty()) {
+        comments.push(lines.pop().unwrap_or_default());
+    }
+    comments.reverse();
+    while comments.first().is_some_and(|l| l.trim().is_empty()) {
+        comments.remove(0);
+    }
+
+    let mut out = lines.join("\n");
+    let in_trailer_block = lines.last().is_some_and(|l| is_trailer_line(l))
+        && lines.iter().rev().take_while(|l| !l.trim().is_empty()).count() < lines.len();
+    if !out.is_empty() {
+        out.push_str(if in_trailer_block { "\n" } else { "\n\n" });
+    }
+    out.push_str(&format!("{}{}\n", prefix, count));
+
+    if !comments.is_empty() {
+        out.push('\n');
+        out.push_str(&comments.join("\n"));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_parse() {
+        assert_eq!(CommitMsgMode::parse("Block").unwrap(), CommitMsgMode::Block);
+        assert_eq!(
+            CommitMsgMode::parse("trailer").unwrap(),
+            CommitMsgMode::Trailer
+        );
+        assert!(CommitMsgMode::parse("warn").is_err());
+    }
+
+    #[test]
+    fn test_append_trailer_new_paragraph() {
+        let out = append_trailer("Add parser\n\nHandles nested blocks.\n", DEFAULT_TRAILER, 2);
+        assert_eq!(
+            out,
+            "Add parser\n\nHandles nested blocks.\n\nIntroduces-TODO: 2\n"
+        );
+    }
+
+    #[test]
+    fn test_append_trailer_subject_only() {
+        assert_eq!(
+            append_trailer("Add parser", DEFAULT_TRAILER, 1),
+            "Add parser\n\nIntroduces-TODO: 1\n"
+        );
+    }
+
+    #[test]
+    fn test_append_trailer_joins_existing_block_and_replaces() {
+        let message = "Add parser\n\nSigned-off-by: A <a@example.com>\nIntroduces-TODO: 5\n";
+        assert_eq!(
+            append_trailer(message, DEFAULT_TRAILER, 3),
+            "Add parser\n\nSigned-off-by: A <a@example.com>\nIntroduces-TODO: 3\n"
+        );
+    }
+
+    #[test]
+    fn test_append_trailer_keeps_comments_last() {
+        let message = "Add parser\n\n# Please enter the commit message\n# Lines starting with '#'\n";
+        assert_eq!(
+            append_trailer(message, "X-Todos", 1),
+            "Add parser\n\nX-Todos: 1\n\n# Please enter the commit message\n# Lines starting with '#'\n"
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::error::{Result, TodoError};
+use crate::git::utils::git_command;
+
+/// API root used when `GITHUB_API_URL` isn't set (GitHub Actions sets it,
+/// including on GitHub Enterprise Server).
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// A minimal GitHub REST API client.
+pub struct GitHub {
+    agent: ureq::Agent,
+    api_url: String,
+    /// Personal access or app installation token; without one only public
+    /// data is readable, under a low rate limit
+    token: Option<String>,
+}
+
+impl GitHub {
+    pub fn new(api_url: Option<&str>, token: Option<String>) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .build()
+            .into();
+        GitHub {
+            agent,
+            api_url: api_url.unwrap_or(DEFAULT_API_URL).trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    /// A client with the token and API root taken from the environment:
+    /// `GITHUB_TOKEN` or `GH_TOKEN`, and `GITHUB_API_URL`.
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let token = var("GITHUB_TOKEN").or_else(|| var("GH_TOKEN"));
+        GitHub::new(var("GITHUB_API_URL").as_deref(), token)
+    }
+
+    pub fn has_token(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// Git settings that authenticate clones and fetches with the token
+    /// without writing it into the clone's config, as `(key, value)` pairs for
+    /// [`git_command_with_config`](crate::git::utils::git_command_with_config).
+    pub fn git_config(&self) -> Vec<(String, String)> {
+        match self.token {
+            Some(ref token) => vec![(
+                "http.extraHeader".to_string(),
+                format!(
+                    "Authorization: Basic {}",
+                    base64(format!("x-access-token:{}", token).as_bytes())
+                ),
+            )],
+            None => Vec::new(),
+        }
+    }
+
+    /// `path` relative to the API root, or a full URL from a `Link` header.
+    fn url(&self, path: &str) -> String {
+        if path.starts_with("http://") || path.starts_with("https://") {
+            path.to_string()
+        } else {
+            format!("{}{}", self.api_url, path)
+        }
+    }
+
+    /// GET `path` and return the parsed body and the next page, if any.
+    pub fn get_page(&self, path: &str) -> Result<(Value, Option<String>)> {
+        let url = self.url(path);
+        let mut request = self.agent.get(&url);
+        for (name, value) in self.headers() {
+            request = request.header(name, &value);
+        }
+        self.finish("GET", &url, request.call())
+    }
+
+    /// GET `path` and every page after it, concatenating the arrays.
+    pub fn get_all(&self, path: &str) -> Result<Vec<Value>> {
+        let mut items = Vec::new();
+        let mut next = Some(path.to_string());
+        while let Some(page) = next.take() {
+            let (body, after) = self.get_page(&page)?;
+            match body {
+                Value::Array(page_items) => items.extend(page_items),
+                other => {
+                    return Err(TodoError::Http(format!(
+                        "GET {}: expected a list, got {}",
+                        page, other
+                    )))
+                }
+            }
+            next = after;
+        }
+        Ok(items)
+    }
+
+    pub fn get(&self, path: &str) -> Result<Value> {
+        self.get_page(path).map(|(body, _)| body)
+    }
+
+    pub fn post(&self, path: &str, body: &Value) -> Result<Value> {
+        let url = self.url(path);
+        let mut request = self.agent.post(&url).header("Content-Type", "application/json");
+        for (name, value) in self.headers() {
+            request = request.header(name, &value);
+        }
+        let result = request.send(body.to_string().as_bytes());
+        self.finish("POST", &url, result).map(|(body, _)| body)
+    }
+
+    pub fn patch(&self, path: &str, body: &Value) -> Result<Value> {
+        let url = self.url(path);
+        let mut request = self.agent.patch(&url).header("Content-Type", "application/json");
+        for (name, value) in self.headers() {
+            request = request.header(name, &value);
+        }
+        let result = request.send(body.to_string().as_bytes());
+        self.finish("PATCH", &url, result).map(|(body, _)| body)
+    }
+
+    fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![
+            ("Accept", "application/vnd.github+json".to_string()),
+            ("User-Agent", "todo-tracker".to_string()),
+        ];
+        if let Some(ref token) = self.token {
+            headers.push(("Authorization", format!("Bearer {}", token)));
+        }
+        headers
+    }
+
+    /// Read the response body, turning error statuses into `Http` errors
+    /// carrying GitHub's message.
+    fn finish(
+        &self,
+        method: &str,
+        url: &str,
+        result: std::result::Result<ureq::http::Response<ureq::Body>, ureq::Error>,
+    ) -> Result<(Value, Option<String>)> {
+        let mut response = result.map_err(|e| TodoError::Http(format!("{} {}: {}", method, url, e)))?;
+        let body = response
+            .body_mut()
+            .read_to_string()
+            .map_err(|e| TodoError::Http(format!("{} {}: {}", method, url, e)))?;
+        let status = response.status().as_u16();
+        let parsed: Option<Value> = serde_json::from_str(&body).ok();
+        if !(200..300).contains(&status) {
+            let message = parsed
+                .as_ref()
+                .and_then(|v| v["message"].as_str())
+                .map(str::to_string)
+                .unwrap_or(body);
+            return Err(TodoError::Http(format!(
+                "{} {} failed ({}): {}",
+                method, url, status, message
+            )));
+        }
+        let next = response
+            .headers()
+            .get("link")
+            .and_then(|v| v.to_str().ok())
+            .and_then(next_link);
+        Ok((parsed.unwrap_or(Value::Null), next))
+    }
+}
+
+/// The `rel="next"` target of a `Link` header, which GitHub paginates with.
+fn next_link(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|p| p.trim() == r#"rel="next""#)
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+/// `owner/name` of a GitHub repository from a remote URL in any of the forms
+/// git accepts: `https://host/owner/name.git`, `git@host:owner/name` or
+/// `ssh://git@host/owner/name`.
+pub fn repo_from_remote(url: &str) -> Option<String> {
+    let url = url.trim().trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?.1,
+        None => url.split_once(':')?.1,
+    };
+    let mut parts = path.rsplit('/');
+    let name = parts.next().filter(|s| !s.is_empty())?;
+    let owner = parts.next().filter(|s| !s.is_empty())?;
+    Some(format!("{}/{}", owner, name))
+}
+
+/// `owner/name` of the repository at `root`: `GITHUB_REPOSITORY` when set
+/// (as in GitHub Actions), else parsed from the `origin` remote.
+pub fn current_repo(root: &Path) -> Option<String> {
+    if let Some(repo) = std::env::var("GITHUB_REPOSITORY").ok().filter(|r| r.contains('/')) {
+        return Some(repo);
+    }
+    let url = git_command(&["remote", "get-url", "origin"], root).ok()?;
+    repo_from_remote(&url)
+}
+
+/// Standard base64 with padding, for the git authorization header.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().fold(0u32, |n, &b| n << 8 | b as u32) << (8 * (3 - chunk.len()));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// A local HTTP server for tests that answers each connection with the next
+/// canned response and records the requests it got.
+#[cfg(test)]
+pub(crate) mod fake {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// A request's head and body.
+    pub(crate) type Request = (String, String);
+
+    /// Serve the responses built from the server's URL in order, one per
+    /// connection.
+    pub(crate) fn serve(
+        responses: impl FnOnce(&str) -> Vec<String>,
+    ) -> (String, JoinHandle<Vec<Request>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let responses = responses(&url);
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    head.push_str(&line);
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                requests.push((head, String::from_utf8(body).unwrap()));
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    pub(crate) fn response(status: &str, link: Option<&str>, body: &str) -> String {
+        let link = link.map(|l| format!("Link: {}\r\n", l)).unwrap_or_default();
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            link,
+            body.len(),
+            body
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"x-access-token:abc"), "eC1hY2Nlc3MtdG9rZW46YWJj");
+    }
+
+    #[test]
+    fn test_next_link() {
+        let link = r#"<https://api.github.com/organizations/1/repos?page=2>; rel="next", <https://api.github.com/organizations/1/repos?page=5>; rel="last""#;
+        assert_eq!(
+            next_link(link).as_deref(),
+            Some("https://api.github.com/organizations/1/repos?page=2")
+        );
+        assert_eq!(next_link(r#"<https://x/?page=1>; rel="prev""#), None);
+    }
+
+    #[test]
+    fn test_repo_from_remote() {
+        let expected = Some("acme/billing".to_string());
+        assert_eq!(repo_from_remote("https://github.com/acme/billing.git"), expected);
+        assert_eq!(repo_from_remote("git@github.com:acme/billing.git"), expected);
+        assert_eq!(repo_from_remote("ssh://git@github.example.com/acme/billing/"), expected);
+        assert_eq!(repo_from_remote("/srv/git/billing"), None);
+    }
+
+    #[test]
+    fn test_error_status_carries_message() {
+        let (url, server) = fake::serve(|_| {
+            vec![fake::response("404 Not Found", None, r#"{"message": "Not Found"}"#)]
+        });
+        let github = GitHub::new(Some(&url), None);
+        let err = github.get("/repos/acme/gone").unwrap_err();
+        assert!(matches!(err, TodoError::Http(_)));
+        assert!(err.to_string().ends_with("/repos/acme/gone failed (404): Not Found"), "{}", err);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::{Result, TodoError};
+use crate::integrations::github::GitHub;
+use crate::multi::RepoSpec;
+
+/// Which repositories of an organization to list.
+#[derive(Debug, Clone, Default)]
+pub struct OrgOptions {
+    pub org: String,
+    pub include_archived: bool,
+    pub include_forks: bool,
+}
+
+/// The fields of the GitHub repository object that are used.
+#[derive(Debug, Deserialize)]
+struct ApiRepo {
+    name: String,
+    clone_url: String,
+    #[serde(default)]
+    archived: bool,
+    #[serde(default)]
+    fork: bool,
+}
+
+/// Turn the `GET /orgs/{org}/repos` listing into repo specs, leaving out
+/// archived repositories and forks unless asked for.
+fn parse_repos(listing: Vec<Value>, options: &OrgOptions) -> Result<Vec<RepoSpec>> {
+    let repos: Vec<ApiRepo> = serde_json::from_value(Value::Array(listing))
+        .map_err(|e| TodoError::Http(format!("Unexpected repository list from GitHub: {}", e)))?;
+    Ok(repos
+        .into_iter()
+        .filter(|r| options.include_archived || !r.archived)
+        .filter(|r| options.include_forks || !r.fork)
+        .map(|r| RepoSpec {
+            name: Some(r.name),
+            url: Some(r.clone_url),
+            path: None,
+            branch: None,
+        })
+        .collect())
+}
+
+/// Every repository of the organization, following pagination.
+pub fn list_repos(github: &GitHub, options: &OrgOptions) -> Result<Vec<RepoSpec>> {
+    let path = format!("/orgs/{}/repos?per_page=100&type=all", options.org);
+    let listing = github.get_all(&path).map_err(|e| match e {
+        TodoError::Http(message) => {
+            TodoError::Http(format!("listing repositories of {}: {}", options.org, message))
+        }
+        other => other,
+    })?;
+    parse_repos(listing, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrations::github::fake;
+
+    #[test]
+    fn test_parse_repos_skips_archived_and_forks() {
+        let listing: Vec<Value> = serde_json::from_str(
+            r#"[
+            {"name": "api", "clone_url": "https://github.com/acme/api.git", "archived": false, "fork": false},
+            {"name": "old", "clone_url": "https://github.com/acme/old.git", "archived": true, "fork": false},
+            {"name": "fork", "clone_url": "https://github.com/acme/fork.git", "archived": false, "fork": true}
+        ]"#,
+        )
+        .unwrap();
+        let repos = parse_repos(listing.clone(), &OrgOptions::default()).unwrap();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].name(), "api");
+        assert_eq!(repos[0].url.as_deref(), Some("https://github.com/acme/api.git"));
+
+        let all = OrgOptions {
+            include_archived: true,
+            include_forks: true,
+            ..Default::default()
+        };
+        assert_eq!(parse_repos(listing, &all).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_list_repos_follows_pages() {
+        let page1 = r#"[{"name": "api", "clone_url": "https://github.com/acme/api.git"}]"#;
+        let page2 = r#"[{"name": "web", "clone_url": "https://github.com/acme/web.git"}]"#;
+        let (url, server) = fake::serve(|url| {
+            let next = format!(r#"<{}/page2>; rel="next""#, url);
+            vec![
+                fake::response("200 OK", Some(&next), page1),
+                fake::response("200 OK", None, page2),
+            ]
+        });
+
+        let github = GitHub::new(Some(&url), Some("secret".to_string()));
+        let options = OrgOptions {
+            org: "acme".to_string(),
+            ..Default::default()
+        };
+        let repos = list_repos(&github, &options).unwrap();
+        let names: Vec<String> = repos.iter().map(RepoSpec::name).collect();
+        assert_eq!(names, ["api", "web"]);
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].0.starts_with("GET /orgs/acme/repos?per_page=100&type=all "));
+        assert!(requests[0].0.contains("Bearer secret"));
+        assert!(requests[1].0.starts_with("GET /page2 "));
+    }
+
+    #[test]
+    fn test_list_repos_reports_api_errors() {
+        let (url, server) = fake::serve(|_| {
+            vec![fake::response("404 Not Found", None, r#"{"message": "Not Found"}"#)]
+        });
+        let options = OrgOptions {
+            org: "nobody".to_string(),
+            ..Default::default()
+        };
+        let err = list_repos(&GitHub::new(Some(&url), None), &options).unwrap_err();
+        assert!(err.to_string().starts_with("HTTP error: listing repositories of nobody: "), "{}", err);
+        assert!(err.to_string().ends_with("failed (404): Not Found"), "{}", err);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,440 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::error::{Result, TodoError};
+use crate::integrations::github::GitHub;
+use crate::model::{fnv1a64, TodoItem};
+
+/// Default sync state location, relative to the scan root.
+pub const ISSUES_FILE: &str = ".todo-issues.json";
+
+/// Label put on every exported issue. Together with the fingerprint marker in
+/// the body it lets a lost or stale state file be rebuilt from the tracker.
+pub const EXPORT_LABEL: &str = "todo-tracker";
+
+const MARKER_PREFIX: &str = "<!-- todo-tracker:";
+
+/// Longest issue title; longer messages are cut at a word boundary.
+const MAX_TITLE: usize = 100;
+
+/// The issue a TODO was exported to, keyed by fingerprint in the state file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncedIssue {
+    pub number: u64,
+    pub url: String,
+    /// False once the TODO disappeared and the issue was closed
+    pub open: bool,
+    /// Hash of the title and body last written, to skip unchanged updates
+    #[serde(default)]
+    pub digest: String,
+}
+
+/// Which issue each exported TODO maps to. Reusing it on the next export
+/// updates those issues instead of opening new ones.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    /// `owner/name` the issue numbers belong to
+    pub repo: Option<String>,
+    pub issues: BTreeMap<String, SyncedIssue>,
+}
+
+impl SyncState {
+    /// Load the state from `path`. A missing file yields an empty state.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(SyncState::default());
+        }
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            TodoError::Config(format!("Failed to parse issue sync file {}: {}", path.display(), e))
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| TodoError::Config(e.to_string()))?;
+        std::fs::write(path, json + "\n")?;
+        Ok(())
+    }
+}
+
+/// Issue title and body for a TODO. The body ends with a marker naming the
+/// fingerprint.
+pub fn render_issue(item: &TodoItem, root: &Path) -> (String, String) {
+    let message = item.message.trim();
+    let mut title = if message.is_empty() {
+        format!("{} in {}", item.tag, item.relative_path(root))
+    } else {
+        format!("{}: {}", item.tag, message)
+    };
+    if title.chars().count() > MAX_TITLE {
+        let cut: String = title.chars().take(MAX_TITLE).collect();
+        title = match cut.rfind(' ') {
+            Some(space) => format!("{}...", &cut[..space]),
+            None => format!("{}...", cut),
+        };
+    }
+
+    let mut body = String::new();
+    writeln!(body, "`{}:{}`", item.relative_path(root), item.line).unwrap();
+    writeln!(body).unwrap();
+    writeln!(body, "```").unwrap();
+    writeln!(body, "{}", item.context_line.trim()).unwrap();
+    writeln!(body, "```").unwrap();
+    if let Some(ref author) = item.author {
+        writeln!(body, "\nAuthor: {}", author).unwrap();
+    }
+    if let Some(ref priority) = item.priority {
+        writeln!(body, "\nPriority: {}", priority.as_str()).unwrap();
+    }
+    writeln!(body).unwrap();
+    write!(body, "{}{} -->", MARKER_PREFIX, item.fingerprint(root)).unwrap();
+    (title, body)
+}
+
+fn digest(title: &str, body: &str) -> String {
+    format!("{:016x}", fnv1a64(format!("{}\0{}", title, body).as_bytes()))
+}
+
+/// The fingerprint in an exported issue's body, if it has the marker.
+fn marker_fingerprint(body: &str) -> Option<&str> {
+    let start = body.rfind(MARKER_PREFIX)? + MARKER_PREFIX.len();
+    let rest = &body[start..];
+    Some(rest[..rest.find(" -->")?].trim())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncActionKind {
+    Create,
+    Update,
+    Reopen,
+    Close,
+}
+
+impl SyncActionKind {
+    pub fn past_tense(&self) -> &'static str {
+        match self {
+            SyncActionKind::Create => "created",
+            SyncActionKind::Update => "updated",
+            SyncActionKind::Reopen => "reopened",
+            SyncActionKind::Close => "closed",
+        }
+    }
+}
+
+/// One change to make in the tracker.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SyncAction {
+    pub kind: SyncActionKind,
+    pub fingerprint: String,
+    /// Existing issue; `None` for creations until they are applied
+    pub number: Option<u64>,
+    pub title: String,
+    #[serde(skip)]
+    pub body: String,
+}
+
+/// Work out what to change so the tracker matches the scan: create issues for
+/// new TODOs, update or reopen issues whose TODO changed or came back, and
+/// close open issues whose TODO is gone. Unchanged open issues are left alone.
+pub fn plan(items: &[TodoItem], root: &Path, state: &SyncState) -> Vec<SyncAction> {
+    let mut actions = Vec::new();
+    let mut seen = HashSet::new();
+    for item in items {
+        let fingerprint = item.fingerprint(root);
+        // Identical TODOs in one file share a fingerprint and an issue
+        if !seen.insert(fingerprint.clone()) {
+            continue;
+        }
+        let (title, body) = render_issue(item, root);
+        let (kind, number) = match state.issues.get(&fingerprint) {
+            None => (SyncActionKind::Create, None),
+            Some(issue) if !issue.open => (SyncActionKind::Reopen, Some(issue.number)),
+            Some(issue) if issue.digest != digest(&title, &body) => {
+                (SyncActionKind::Update, Some(issue.number))
+            }
+            Some(_) => continue,
+        };
+        actions.push(SyncAction {
+            kind,
+            fingerprint,
+            number,
+            title,
+            body,
+        });
+    }
+    for (fingerprint, issue) in &state.issues {
+        if issue.open && !seen.contains(fingerprint) {
+            actions.push(SyncAction {
+                kind: SyncActionKind::Close,
+                fingerprint: fingerprint.clone(),
+                number: Some(issue.number),
+                title: String::new(),
+                body: String::new(),
+            });
+        }
+    }
+    actions
+}
+
+/// Record issues already exported to `repo` that the state doesn't know
+/// about, found by label and body marker, so a lost state file doesn't lead
+/// to duplicates. Returns how many were adopted.
+pub fn adopt_existing(github: &GitHub, repo: &str, state: &mut SyncState) -> Result<usize> {
+    let issues = github.get_all(&format!(
+        "/repos/{}/issues?state=all&labels={}&per_page=100",
+        repo, EXPORT_LABEL
+    ))?;
+    let mut adopted = 0;
+    for issue in issues {
+        // The issues API lists pull requests too
+        if issue.get("pull_request").is_some() {
+            continue;
+        }
+        let Some(fingerprint) = issue["body"].as_str().and_then(marker_fingerprint) else {
+            continue;
+        };
+        if state.issues.contains_key(fingerprint) {
+            continue;
+        }
+        state.issues.insert(
+            fingerprint.to_string(),
+            SyncedIssue {
+                number: issue["number"].as_u64().unwrap_or_default(),
+                url: issue["html_url"].as_str().unwrap_or_default().to_string(),
+                open: issue["state"].as_str() == Some("open"),
+                digest: String::new(),
+            },
+        );
+        adopted += 1;
+    }
+    Ok(adopted)
+}
+
+/// Apply `actions` to `repo`, recording each issue in `state` as soon as it
+/// is written so a failure part-way loses nothing. Creations get their issue
+/// number filled in.
+pub fn apply(
+    github: &GitHub,
+    repo: &str,
+    actions: &mut [SyncAction],
+    state: &mut SyncState,
+) -> Result<()> {
+    state.repo = Some(repo.to_string());
+    for action in actions.iter_mut() {
+        let path = |number: u64| format!("/repos/{}/issues/{}", repo, number);
+        let issue = match (action.kind, action.number) {
+            (SyncActionKind::Create, _) => github.post(
+                &format!("/repos/{}/issues", repo),
+                &json!({ "title": action.title, "body": action.body, "labels": [EXPORT_LABEL] }),
+            )?,
+            (SyncActionKind::Update, Some(number)) => github.patch(
+                &path(number),
+                &json!({ "title": action.title, "body": action.body }),
+            )?,
+            (SyncActionKind::Reopen, Some(number)) => github.patch(
+                &path(number),
+                &json!({ "title": action.title, "body": action.body, "state": "open" }),
+            )?,
+            (SyncActionKind::Close, Some(number)) => github.patch(
+                &path(number),
+                &json!({ "state": "closed", "state_reason": "completed" }),
+            )?,
+            (_, None) => continue,
+        };
+
+        if action.kind == SyncActionKind::Close {
+            if let Some(synced) = state.issues.get_mut(&action.fingerprint) {
+                synced.open = false;
+            }
+            continue;
+        }
+        let number = issue["number"].as_u64().or(action.number).unwrap_or_default();
+        action.number = Some(number);
+        state.issues.insert(
+            action.fingerprint.clone(),
+            SyncedIssue {
+                number,
+                url: issue["html_url"].as_str().unwrap_or_default().to_string(),
+                open: true,
+                digest: digest(&action.title, &action.body),
+            },
+        );
+    }
+    Ok(())
+}
+
+/// One line per action, then counts.
+pub fn render_text(repo: &str, actions: &[SyncAction], dry_run: bool) -> String {
+    let mut out = String::new();
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for action in actions {
+        let number = action.number.map(|n| format!("#{}", n)).unwrap_or_else(|| "new".to_string());
+        let kind = action.kind.past_tense();
+        *counts.entry(kind).or_default() += 1;
+        if action.title.is_empty() {
+            writeln!(out, "  {:<9} {}", kind, number).unwrap();
+        } else {
+            writeln!(out, "  {:<9} {}  {}", kind, number, action.title).unwrap();
+        }
+    }
+    if actions.is_empty() {
+        writeln!(out, "Issues in {} are up to date", repo).unwrap();
+        return out;
+    }
+    let summary: Vec<String> = ["created", "updated", "reopened", "closed"]
+        .iter()
+        .filter_map(|kind| counts.get(kind).map(|n| format!("{} {}", kind, n)))
+        .collect();
+    writeln!(
+        out,
+        "{}{} in {}",
+        if dry_run { "Would have " } else { "" },
+        summary.join(", "),
+        repo
+    )
+    .unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrations::github::fake;
+    use crate::model::TodoTag;
+    use std::path::PathBuf;
+
+    fn item(file: &str, line: usize, message: &str) -> TodoItem {
+        TodoItem {
+            tag: TodoTag::Todo,
+            message: message.to_string(),
+            file: PathBuf::from("/repo").join(file),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: format!("// TODO: {}", message),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    fn synced(item: &TodoItem, number: u64, open: bool) -> SyncedIssue {
+        let (title, body) = render_issue(item, Path::new("/repo"));
+        SyncedIssue {
+            number,
+            url: String::new(),
+            open,
+            digest: digest(&title, &body),
+        }
+    }
+
+    #[test]
+    fn test_render_issue() {
+        let root = Path::new("/repo");
+        let (title, body) = render_issue(&item("src/a.rs", 3, "retry payments"), root);
+        assert_eq!(title, "TODO: retry payments");
+        assert!(body.starts_with("`src/a.rs:3`\n\n```\n// TODO: retry payments\n```\n"));
+        let fingerprint = item("src/a.rs", 3, "retry payments").fingerprint(root);
+        assert_eq!(marker_fingerprint(&body), Some(fingerprint.as_str()));
+
+        let (title, _) = render_issue(&item("a.rs", 1, &"word ".repeat(40)), root);
+        assert!(title.len() <= MAX_TITLE + 3 && title.ends_with("word..."), "{}", title);
+    }
+
+    #[test]
+    fn test_plan() {
+        let root = Path::new("/repo");
+        let kept = item("a.rs", 1, "kept");
+        let moved = item("a.rs", 9, "moved");
+        let back = item("b.rs", 1, "came back");
+        let gone = item("c.rs", 1, "gone");
+        let new = item("d.rs", 1, "new");
+
+        let mut state = SyncState::default();
+        state.issues.insert(kept.fingerprint(root), synced(&kept, 1, true));
+        // Recorded at another line, so the body changed
+        state.issues.insert(moved.fingerprint(root), synced(&item("a.rs", 5, "moved"), 2, true));
+        state.issues.insert(back.fingerprint(root), synced(&back, 3, false));
+        state.issues.insert(gone.fingerprint(root), synced(&gone, 4, true));
+
+        let actions = plan(&[kept, moved, back, new.clone(), new], root, &state);
+        let summary: Vec<(SyncActionKind, Option<u64>)> =
+            actions.iter().map(|a| (a.kind, a.number)).collect();
+        assert_eq!(
+            summary,
+            [
+                (SyncActionKind::Update, Some(2)),
+                (SyncActionKind::Reopen, Some(3)),
+                (SyncActionKind::Create, None),
+                (SyncActionKind::Close, Some(4)),
+            ]
+        );
+
+        let text = render_text("acme/api", &actions, true);
+        assert!(text.contains("  closed    #4\n"));
+        assert!(text.contains("  created   new  TODO: new\n"));
+        assert!(text.ends_with("Would have created 1, updated 1, reopened 1, closed 1 in acme/api\n"));
+    }
+
+    #[test]
+    fn test_adopt_and_apply() {
+        let root = Path::new("/repo");
+        let old = item("a.rs", 1, "old");
+        let new = item("b.rs", 1, "new");
+        let (_, old_body) = render_issue(&old, root);
+        let listing = json!([
+            { "number": 5, "state": "open", "html_url": "u5", "body": old_body },
+            { "number": 6, "state": "open", "body": "no marker" },
+            { "number": 7, "state": "open", "body": old_body, "pull_request": {} },
+        ])
+        .to_string();
+        let (url, server) = fake::serve(|_| {
+            vec![
+                fake::response("200 OK", None, &listing),
+                fake::response("201 Created", None, r#"{"number": 8, "html_url": "u8"}"#),
+                fake::response("200 OK", None, r#"{"number": 5, "html_url": "u5"}"#),
+            ]
+        });
+        let github = GitHub::new(Some(&url), Some("t".to_string()));
+
+        let mut state = SyncState::default();
+        assert_eq!(adopt_existing(&github, "acme/api", &mut state).unwrap(), 1);
+        assert_eq!(state.issues[&old.fingerprint(root)].number, 5);
+
+        let mut actions = plan(std::slice::from_ref(&new), root, &state);
+        apply(&github, "acme/api", &mut actions, &mut state).unwrap();
+        assert_eq!(actions[0].number, Some(8));
+        assert_eq!(state.repo.as_deref(), Some("acme/api"));
+        assert!(state.issues[&new.fingerprint(root)].open);
+        assert!(!state.issues[&old.fingerprint(root)].open);
+        // Nothing left to do
+        assert!(plan(&[new], root, &state).is_empty());
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].0.contains("state=all&labels=todo-tracker"));
+        assert!(requests[1].0.starts_with("POST /repos/acme/api/issues "));
+        assert!(requests[1].1.contains(r#""labels":["todo-tracker"]"#));
+        assert!(requests[2].0.starts_with("PATCH /repos/acme/api/issues/5 "));
+        assert!(requests[2].1.contains(r#""state":"closed""#));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(ISSUES_FILE);
+        state.save(&path).unwrap();
+        assert_eq!(SyncState::load(&path).unwrap().issues, state.issues);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod github;
+pub mod github_org;
+pub mod issue_sync;
+pub mod remind;
+pub mod slack;
+pub mod webhook;
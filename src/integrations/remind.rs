@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::error::Result;
+use crate::integrations::github::GitHub;
+use crate::model::TodoItem;
+
+/// Label of the issue `todos remind --open-issue` keeps up to date, so
+/// repeated runs edit one issue instead of opening another.
+pub const REMINDER_LABEL: &str = "stale-todos";
+
+const REMINDER_TITLE: &str = "Remove TODOs that reference closed issues";
+
+/// The number in an issue reference such as `#123`. Slugs like `#retry-bug`
+/// aren't issue numbers and are skipped.
+pub fn issue_number(reference: &str) -> Option<u64> {
+    reference.strip_prefix('#')?.parse().ok()
+}
+
+/// What the tracker says about one referenced issue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IssueState {
+    pub title: String,
+    /// Closing date (YYYY-MM-DD), or `None` while the issue is open
+    pub closed_at: Option<String>,
+    pub url: String,
+}
+
+fn parse_issue(issue: &Value) -> IssueState {
+    let closed = issue["state"].as_str() == Some("closed");
+    IssueState {
+        title: issue["title"].as_str().unwrap_or_default().to_string(),
+        closed_at: closed.then(|| {
+            let date = issue["closed_at"].as_str().unwrap_or_default();
+            date.get(..10).unwrap_or(date).to_string()
+        }),
+        url: issue["html_url"].as_str().unwrap_or_default().to_string(),
+    }
+}
+
+/// A TODO that outlived the issue it references.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StaleTodo {
+    /// Path relative to the scan root
+    pub file: String,
+    pub line: usize,
+    pub tag: String,
+    pub message: String,
+    pub issue: String,
+    pub issue_title: String,
+    pub closed_at: String,
+    pub issue_url: String,
+}
+
+/// Look up every issue number the items reference in `repo` (`owner/name`)
+/// and return the items whose issue is closed, by file and line. Issues that
+/// couldn't be looked up are returned alongside with the error.
+pub fn find_stale(
+    github: &GitHub,
+    repo: &str,
+    items: &[TodoItem],
+    root: &Path,
+) -> (Vec<StaleTodo>, Vec<(String, String)>) {
+    let mut issues: BTreeMap<u64, Option<IssueState>> = BTreeMap::new();
+    let mut errors = Vec::new();
+    for number in items.iter().filter_map(|i| i.issue.as_deref().and_then(issue_number)) {
+        if issues.contains_key(&number) {
+            continue;
+        }
+        let state = match github.get(&format!("/repos/{}/issues/{}", repo, number)) {
+            Ok(issue) => Some(parse_issue(&issue)),
+            Err(e) => {
+                errors.push((format!("#{}", number), e.to_string()));
+                None
+            }
+        };
+        issues.insert(number, state);
+    }
+
+    let mut stale: Vec<StaleTodo> = items
+        .iter()
+        .filter_map(|item| {
+            let reference = item.issue.as_deref()?;
+            let issue = issues.get(&issue_number(reference)?)?.as_ref()?;
+            Some(StaleTodo {
+                file: item.relative_path(root),
+                line: item.line,
+                tag: item.tag.as_str().to_string(),
+                message: item.message.trim().to_string(),
+                issue: reference.to_string(),
+                issue_title: issue.title.clone(),
+                closed_at: issue.closed_at.clone()?,
+                issue_url: issue.url.clone(),
+            })
+        })
+        .collect();
+    stale.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    (stale, errors)
+}
+
+pub fn render_text(repo: &str, stale: &[StaleTodo]) -> String {
+    let mut out = String::new();
+    if stale.is_empty() {
+        writeln!(out, "No TODOs reference closed issues in {}", repo).unwrap();
+        return out;
+    }
+    writeln!(out, "{} TODO(s) reference closed issues in {}:", stale.len(), repo).unwrap();
+    for todo in stale {
+        writeln!(out).unwrap();
+        writeln!(out, "  {}:{}  [{}] {}", todo.file, todo.line, todo.tag, todo.message).unwrap();
+        writeln!(
+            out,
+            "      {} closed {}: {}",
+            todo.issue, todo.closed_at, todo.issue_title
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// Markdown list for the reminder issue and chat messages.
+pub fn render_markdown(stale: &[StaleTodo]) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "These TODOs reference issues that are closed. Remove them, or point them at an open issue if the work remains."
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    for todo in stale {
+        writeln!(
+            out,
+            "- `{}:{}` {}: {} ({}, closed {})",
+            todo.file, todo.line, todo.tag, todo.message, todo.issue, todo.closed_at
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// Open the reminder issue, or update the open one labelled
+/// [`REMINDER_LABEL`], and return its URL.
+pub fn open_reminder_issue(github: &GitHub, repo: &str, stale: &[StaleTodo]) -> Result<String> {
+    let body = render_markdown(stale);
+    let existing = github.get(&format!(
+        "/repos/{}/issues?state=open&labels={}&per_page=1",
+        repo, REMINDER_LABEL
+    ))?;
+    let issue = match existing.as_array().and_then(|issues| issues.first()) {
+        Some(issue) => {
+            let number = issue["number"].as_u64().unwrap_or_default();
+            github.patch(
+                &format!("/repos/{}/issues/{}", repo, number),
+                &json!({ "body": body }),
+            )?
+        }
+        None => github.post(
+            &format!("/repos/{}/issues", repo),
+            &json!({ "title": REMINDER_TITLE, "body": body, "labels": [REMINDER_LABEL] }),
+        )?,
+    };
+    Ok(issue["html_url"].as_str().unwrap_or_default().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrations::github::fake;
+    use crate::model::TodoTag;
+    use std::path::PathBuf;
+
+    fn item(file: &str, line: usize, issue: Option<&str>) -> TodoItem {
+        TodoItem {
+            tag: TodoTag::Todo,
+            message: format!("fix {}", line),
+            file: PathBuf::from("/repo").join(file),
+            line,
+            column: 1,
+            author: None,
+            issue: issue.map(str::to_string),
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_issue_number() {
+        assert_eq!(issue_number("#123"), Some(123));
+        assert_eq!(issue_number("#retry-bug"), None);
+        assert_eq!(issue_number("123"), None);
+    }
+
+    #[test]
+    fn test_find_stale_looks_each_issue_up_once() {
+        let (url, server) = fake::serve(|_| {
+            vec![
+                fake::response(
+                    "200 OK",
+                    None,
+                    r#"{"state": "closed", "closed_at": "2024-05-01T10:00:00Z", "title": "Retries", "html_url": "https://github.com/acme/api/issues/7"}"#,
+                ),
+                fake::response("200 OK", None, r#"{"state": "open", "title": "Open one"}"#),
+            ]
+        });
+        let items = vec![
+            item("b.rs", 3, Some("#7")),
+            item("a.rs", 9, Some("#7")),
+            item("a.rs", 1, Some("#8")),
+            item("c.rs", 1, Some("#slug")),
+            item("d.rs", 1, None),
+        ];
+        let github = GitHub::new(Some(&url), None);
+        let (stale, errors) = find_stale(&github, "acme/api", &items, Path::new("/repo"));
+        assert!(errors.is_empty());
+        let locations: Vec<(&str, usize)> = stale.iter().map(|s| (s.file.as_str(), s.line)).collect();
+        assert_eq!(locations, [("a.rs", 9), ("b.rs", 3)]);
+        assert_eq!(stale[0].closed_at, "2024-05-01");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].0.starts_with("GET /repos/acme/api/issues/7 "));
+        assert!(requests[1].0.starts_with("GET /repos/acme/api/issues/8 "));
+
+        let text = render_text("acme/api", &stale);
+        assert!(text.contains("  a.rs:9  [TODO] fix 9\n      #7 closed 2024-05-01: Retries\n"));
+        assert!(render_markdown(&stale).contains("- `b.rs:3` TODO: fix 3 (#7, closed 2024-05-01)"));
+    }
+
+    #[test]
+    fn test_reminder_issue_is_updated_when_open() {
+        let (url, server) = fake::serve(|_| {
+            vec![
+                fake::response("200 OK", None, r#"[{"number": 12}]"#),
+                fake::response("200 OK", None, r#"{"html_url": "https://github.com/acme/api/issues/12"}"#),
+            ]
+        });
+        let github = GitHub::new(Some(&url), Some("t".to_string()));
+        let issue = open_reminder_issue(&github, "acme/api", &[]).unwrap();
+        assert_eq!(issue, "https://github.com/acme/api/issues/12");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].0.contains("labels=stale-todos"));
+        assert!(requests[1].0.starts_with("PATCH /repos/acme/api/issues/12 "));
+        assert!(requests[1].1.contains("\"body\""));
+    }
+}
@@ -0,0 +1,35 @@
+use crate::error::{Result, TodoError};
+
+/// Post `text` to a Slack incoming webhook. Slack renders its own flavour of
+/// markdown, which covers the lists and code spans used in reports.
+pub fn post_message(webhook_url: &str, text: &str) -> Result<()> {
+    let body = serde_json::json!({ "text": text }).to_string();
+    ureq::post(webhook_url)
+        .header("Content-Type", "application/json")
+        .send(body.as_bytes())
+        .map_err(|e| TodoError::Http(format!("Slack webhook: {}", e)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrations::github::fake;
+
+    #[test]
+    fn test_post_message() {
+        let (url, server) = fake::serve(|_| {
+            vec![
+                fake::response("200 OK", None, "ok"),
+                fake::response("403 Forbidden", None, "invalid_token"),
+            ]
+        });
+        post_message(&format!("{}/services/T/B/X", url), "2 *stale* TODOs").unwrap();
+        let err = post_message(&url, "again").unwrap_err();
+        assert!(err.to_string().contains("Slack webhook"), "{}", err);
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].0.starts_with("POST /services/T/B/X "));
+        assert_eq!(requests[0].1, r#"{"text":"2 *stale* TODOs"}"#);
+    }
+}
@@ -0,0 +1,135 @@
+use serde_json::{json, Value};
+
+use crate::config::WebhookConfig;
+use crate::error::{Result, TodoError};
+use crate::git::diff::diff_items;
+use crate::model::{ScanResult, TodoItem};
+
+/// `event` of the payload posted when TODOs appear or disappear.
+pub const CHANGED_EVENT: &str = "todos.changed";
+
+/// The JSON posted to `hook` after a rescan: the items it cares about that
+/// appeared and disappeared since `previous`, with paths relative to the
+/// scan root. `None` when none did.
+pub fn payload(hook: &WebhookConfig, previous: &ScanResult, current: &ScanResult) -> Option<Value> {
+    let wanted = |item: &TodoItem| {
+        hook.tags.as_ref().is_none_or(|tags| {
+            tags.iter().any(|tag| tag.eq_ignore_ascii_case(item.tag.as_str()))
+        })
+    };
+    let root = &current.metadata.root_path;
+    let relative = |items: Vec<TodoItem>| -> Vec<TodoItem> {
+        items
+            .into_iter()
+            .filter(|item| wanted(item))
+            .map(|mut item| {
+                if let Ok(path) = item.file.strip_prefix(root) {
+                    item.file = path.to_path_buf();
+                }
+                item
+            })
+            .collect()
+    };
+    let (added, removed) = diff_items(&previous.items, &current.items);
+    let (added, removed) = (relative(added), relative(removed));
+    if added.is_empty() && removed.is_empty() {
+        return None;
+    }
+    Some(json!({
+        "event": CHANGED_EVENT,
+        "root": root,
+        "added": added,
+        "removed": removed,
+        "total_todos": current.stats.total_todos,
+    }))
+}
+
+/// POST `payload` as JSON to `url`.
+pub fn post(url: &str, payload: &Value) -> Result<()> {
+    ureq::post(url)
+        .header("Content-Type", "application/json")
+        .send(payload.to_string().as_bytes())
+        .map_err(|e| TodoError::Http(format!("Webhook {}: {}", url, e)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrations::github::fake;
+    use crate::model::{ScanMetadata, ScanStats, TodoTag};
+    use std::path::PathBuf;
+
+    fn make_result(items: &[(&str, TodoTag)]) -> ScanResult {
+        let items = items
+            .iter()
+            .map(|(message, tag)| TodoItem {
+                tag: tag.clone(),
+                message: message.to_string(),
+                file: PathBuf::from("/repo/src/a.rs"),
+                line: 1,
+                column: 4,
+                author: None,
+                issue: None,
+                priority: None,
+                context_line: format!("// {}: {}", tag.as_str(), message),
+                git_author: None,
+                git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
+            })
+            .collect();
+        let mut result = ScanResult {
+            items,
+            stats: ScanStats::new(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("/repo"),
+                timestamp: "t".to_string(),
+                blame_cache_hits: None,
+            },
+        };
+        result.recompute_stats();
+        result
+    }
+
+    #[test]
+    fn test_payload_lists_changes() {
+        let nocommit = TodoTag::Custom("NOCOMMIT".to_string());
+        let previous = make_result(&[("old", TodoTag::Todo)]);
+        let current = make_result(&[("debug log", nocommit.clone()), ("new", TodoTag::Todo)]);
+        let all = WebhookConfig::default();
+
+        let body = payload(&all, &previous, &current).unwrap();
+        assert_eq!(body["event"], CHANGED_EVENT);
+        assert_eq!(body["added"].as_array().unwrap().len(), 2);
+        assert_eq!(body["added"][0]["file"], "src/a.rs");
+        assert_eq!(body["removed"][0]["message"], "old");
+        assert_eq!(body["total_todos"], 2);
+
+        let only_nocommit = WebhookConfig {
+            tags: Some(vec!["nocommit".to_string()]),
+            ..Default::default()
+        };
+        let body = payload(&only_nocommit, &previous, &current).unwrap();
+        assert_eq!(body["added"].as_array().unwrap().len(), 1);
+        assert_eq!(body["removed"].as_array().unwrap().len(), 0);
+        assert!(payload(&only_nocommit, &current, &current).is_none());
+        assert!(payload(&only_nocommit, &previous, &previous).is_none());
+    }
+
+    #[test]
+    fn test_post() {
+        let (url, server) = fake::serve(|_| vec![fake::response("204 No Content", None, "")]);
+        post(&format!("{}/hooks/todos", url), &json!({"event": CHANGED_EVENT})).unwrap();
+        let requests = server.join().unwrap();
+        assert!(requests[0].0.starts_with("POST /hooks/todos "));
+        assert_eq!(requests[0].1, r#"{"event":"todos.changed"}"#);
+    }
+}
@@ -1,12 +1,40 @@
 pub mod error;
 pub mod model;
+pub mod ordering;
+pub mod spill;
+pub mod date;
 pub mod scanner;
 pub mod discovery;
 pub mod output;
+#[cfg(feature = "full")]
 pub mod cli;
 pub mod config;
+pub mod schema;
 pub mod filter;
+pub mod components;
+pub mod test_code;
+#[cfg(feature = "git")]
 pub mod git;
+#[cfg(feature = "git")]
+pub mod vcs;
 pub mod policy;
+pub mod lint;
+pub mod escalation;
+pub mod triage;
+pub mod baseline;
+pub mod suppressions;
+pub mod digest;
+#[cfg(feature = "git")]
+pub mod snapshot;
+#[cfg(feature = "git")]
+pub mod multi;
+#[cfg(feature = "github")]
+pub mod integrations;
+pub mod golden;
+pub mod heatmap;
+pub mod rpc;
+pub mod hook;
+pub mod post_scan;
+#[cfg(feature = "cache")]
 pub mod cache;
 pub mod progress;
@@ -0,0 +1,326 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::model::{ScanResult, TodoItem};
+use crate::policy::{is_exempt, PolicyViolation, ViolationSeverity};
+
+/// Messages that carry no information on their own, compared after normalization.
+const GENERIC_MESSAGES: &[&str] = &[
+    "fix",
+    "fix this",
+    "fix me",
+    "fixme",
+    "fix later",
+    "todo",
+    "later",
+    "cleanup",
+    "clean up",
+    "clean this up",
+    "refactor",
+    "refactor this",
+    "implement",
+    "implement this",
+    "handle this",
+    "do this",
+    "needs work",
+    "tbd",
+    "wip",
+];
+
+/// Message-quality rules evaluated by `check` as the `lint` category.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LintConfig {
+    /// Minimum message length in characters
+    pub min_length: Option<usize>,
+    /// Reject items with an empty message
+    pub deny_empty: Option<bool>,
+    /// Reject low-information messages such as "fix this"
+    pub deny_generic: Option<bool>,
+    /// Additional phrases treated as generic
+    pub generic_phrases: Option<Vec<String>>,
+    /// Regex template every message must match
+    pub pattern: Option<String>,
+}
+
+impl LintConfig {
+    pub fn is_empty(&self) -> bool {
+        self.min_length.is_none()
+            && !self.deny_empty.unwrap_or(false)
+            && !self.deny_generic.unwrap_or(false)
+            && self.pattern.is_none()
+    }
+
+    /// Names of the enabled rules, as reported in violations.
+    pub fn rules(&self) -> Vec<String> {
+        let mut rules = Vec::new();
+        if self.deny_empty.unwrap_or(false) {
+            rules.push("lint:empty".to_string());
+        }
+        if self.deny_generic.unwrap_or(false) {
+            rules.push("lint:generic".to_string());
+        }
+        if self.min_length.is_some() {
+            rules.push("lint:min_length".to_string());
+        }
+        if self.pattern.is_some() {
+            rules.push("lint:pattern".to_string());
+        }
+        rules
+    }
+
+    /// Compile the message template, reporting invalid regexes up front.
+    pub fn compiled_pattern(&self) -> Result<Option<Regex>> {
+        match self.pattern {
+            Some(ref p) => Ok(Some(Regex::new(p)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Normalize a message for generic-phrase comparison: lowercase, trimmed of
+/// surrounding whitespace and trailing punctuation.
+fn normalize(message: &str) -> String {
+    message
+        .trim()
+        .trim_end_matches(['.', '!', '?', ':'])
+        .trim()
+        .to_lowercase()
+}
+
+fn is_generic(message: &str, extra: &[String]) -> bool {
+    let normalized = normalize(message);
+    GENERIC_MESSAGES.contains(&normalized.as_str())
+        || extra.iter().any(|p| normalize(p) == normalized)
+}
+
+/// A lint violation, or none when the item is exempt from the rule.
+fn violation(rule: &str, item: &TodoItem, detail: &str) -> Option<PolicyViolation> {
+    let rule = format!("lint:{}", rule);
+    if is_exempt(item, &rule) {
+        return None;
+    }
+    Some(PolicyViolation {
+        rule,
+        message: format!(
+            "{} at {}:{} {}",
+            item.tag,
+            item.file.display(),
+            item.line,
+            detail
+        ),
+        file: Some(item.file.display().to_string()),
+        line: Some(item.line),
+        severity: ViolationSeverity::Error,
+    })
+}
+
+/// Evaluate message-quality rules against every item. Each item reports at most
+/// one lint violation: the first failing rule in the order empty, generic,
+/// min_length, pattern.
+pub fn check_lint(result: &ScanResult, config: &LintConfig) -> Vec<PolicyViolation> {
+    let mut violations = Vec::new();
+    // Invalid patterns are rejected before check runs; skip the rule if one slips through
+    let pattern = config.compiled_pattern().ok().flatten();
+    let extra = config.generic_phrases.clone().unwrap_or_default();
+
+    for item in &result.items {
+        let message = item.message.trim();
+
+        if message.is_empty() {
+            if config.deny_empty.unwrap_or(false) {
+                violations.extend(violation("empty", item, "has an empty message"));
+            }
+            continue;
+        }
+
+        if config.deny_generic.unwrap_or(false) && is_generic(message, &extra) {
+            violations.extend(violation(
+                "generic",
+                item,
+                &format!("has a generic message \"{}\"", message),
+            ));
+            continue;
+        }
+
+        if let Some(min) = config.min_length {
+            let len = message.chars().count();
+            if len < min {
+                violations.extend(violation(
+                    "min_length",
+                    item,
+                    &format!("message is {} characters, minimum is {}", len, min),
+                ));
+                continue;
+            }
+        }
+
+        if let Some(ref re) = pattern {
+            if !re.is_match(message) {
+                violations.extend(violation(
+                    "pattern",
+                    item,
+                    &format!("message does not match template {}", re.as_str()),
+                ));
+            }
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats, TodoTag};
+    use std::path::PathBuf;
+
+    fn make_item(message: &str, line: usize) -> TodoItem {
+        TodoItem {
+            tag: TodoTag::Todo,
+            message: message.to_string(),
+            file: PathBuf::from("src/main.rs"),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    fn make_result(items: Vec<TodoItem>) -> ScanResult {
+        ScanResult {
+            items,
+            stats: ScanStats::new(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("."),
+                timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_empty_config_is_empty() {
+        assert!(LintConfig::default().is_empty());
+        let config = LintConfig {
+            deny_empty: Some(false),
+            ..Default::default()
+        };
+        assert!(config.is_empty());
+    }
+
+    #[test]
+    fn test_deny_empty() {
+        let result = make_result(vec![make_item("", 1), make_item("real work", 2)]);
+        let config = LintConfig {
+            deny_empty: Some(true),
+            ..Default::default()
+        };
+        let violations = check_lint(&result, &config);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "lint:empty");
+        assert_eq!(violations[0].line, Some(1));
+    }
+
+    #[test]
+    fn test_empty_not_reported_when_rule_off() {
+        let result = make_result(vec![make_item("", 1)]);
+        let config = LintConfig {
+            min_length: Some(10),
+            ..Default::default()
+        };
+        assert!(check_lint(&result, &config).is_empty());
+    }
+
+    #[test]
+    fn test_deny_generic_builtin_phrases() {
+        let result = make_result(vec![
+            make_item("Fix this!", 1),
+            make_item("later", 2),
+            make_item("fix the retry loop", 3),
+        ]);
+        let config = LintConfig {
+            deny_generic: Some(true),
+            ..Default::default()
+        };
+        let violations = check_lint(&result, &config);
+        assert_eq!(violations.len(), 2);
+        assert!(violations.iter().all(|v| v.rule == "lint:generic"));
+    }
+
+    #[test]
+    fn test_deny_generic_extra_phrases() {
+        let result = make_result(vec![make_item("look into it", 1)]);
+        let config = LintConfig {
+            deny_generic: Some(true),
+            generic_phrases: Some(vec!["Look into it".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(check_lint(&result, &config).len(), 1);
+    }
+
+    #[test]
+    fn test_min_length_counts_chars() {
+        let result = make_result(vec![make_item("short", 1), make_item("long enough message", 2)]);
+        let config = LintConfig {
+            min_length: Some(10),
+            ..Default::default()
+        };
+        let violations = check_lint(&result, &config);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "lint:min_length");
+        assert!(violations[0].message.contains("5 characters"));
+    }
+
+    #[test]
+    fn test_pattern_template() {
+        let result = make_result(vec![
+            make_item("[PROJ-12] migrate schema", 1),
+            make_item("migrate schema", 2),
+        ]);
+        let config = LintConfig {
+            pattern: Some(r"^\[[A-Z]+-\d+\]".to_string()),
+            ..Default::default()
+        };
+        let violations = check_lint(&result, &config);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "lint:pattern");
+        assert_eq!(violations[0].line, Some(2));
+    }
+
+    #[test]
+    fn test_one_violation_per_item() {
+        let result = make_result(vec![make_item("fix", 1)]);
+        let config = LintConfig {
+            deny_generic: Some(true),
+            min_length: Some(10),
+            pattern: Some("^x".to_string()),
+            ..Default::default()
+        };
+        let violations = check_lint(&result, &config);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "lint:generic");
+    }
+
+    #[test]
+    fn test_invalid_pattern_reported() {
+        let config = LintConfig {
+            pattern: Some("([".to_string()),
+            ..Default::default()
+        };
+        assert!(config.compiled_pattern().is_err());
+    }
+}
@@ -1,28 +1,107 @@
 use anyhow::Result;
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, ValueEnum};
 
-use todo_tracker::cache::CacheDb;
-use todo_tracker::cli::{Cli, ColorMode, Commands};
-use todo_tracker::config::Config;
+use todo_tracker::cache::result::{rebase, relativize, result_key};
+use todo_tracker::cache::{archive, CacheDb};
+use todo_tracker::cli::{
+    BaselineCommand, CacheCommand, Cli, ColorMode, Commands, ConfigCommand, GroupBy, HookCommand,
+};
+use todo_tracker::components::{ComponentMap, UNASSIGNED};
+use todo_tracker::test_code::TestPaths;
+use todo_tracker::config::{Config, OutputConfig};
+use todo_tracker::date::{parse_days, unix_now, Date, DateFilter};
 use todo_tracker::discovery::FileDiscovery;
+use todo_tracker::error::TodoError;
+use todo_tracker::escalation::{EscalationConfig, Escalator};
 use todo_tracker::filter::FilterCriteria;
-use todo_tracker::model::{Priority, ScanResult, ScanStats};
-use todo_tracker::output::{format_output, OutputFormat};
-use todo_tracker::git::blame::enrich_with_blame;
-use todo_tracker::git::diff::{diff_staged, diff_todos, DiffResult};
-use todo_tracker::git::utils::{is_git_repo, repo_root};
-use todo_tracker::policy::{check_policies, PolicyConfig};
-use todo_tracker::scanner::regex::RegexScanner;
-use todo_tracker::scanner::ScanOrchestrator;
+use todo_tracker::golden;
+use todo_tracker::heatmap;
+use todo_tracker::hook::{self, CommitMsgMode};
+use todo_tracker::integrations::github::{self, GitHub};
+use todo_tracker::integrations::github_org::{self, OrgOptions};
+use todo_tracker::integrations::issue_sync::{self, SyncState};
+use todo_tracker::integrations::{remind, slack, webhook};
+use todo_tracker::model::{Priority, ScanResult, ScanStats, TodoItem};
+use todo_tracker::multi::{self, MultiReport, RepoReport, ReposFile};
+use todo_tracker::suppressions;
+use todo_tracker::output::text::{self, format_summary, TextFormatter};
+use todo_tracker::output::charset;
+use todo_tracker::output::csv::{parse_delimiter, CsvColumn, CsvFormatter};
+use todo_tracker::output::json::{to_json, JsonFormatter, JsonStream};
+use todo_tracker::output::annotations::AnnotationLevel;
+use todo_tracker::output::encrypt::Encryption;
+use todo_tracker::output::links::{self, LinkTemplate};
+use todo_tracker::output::github_actions::GithubActionsFormatter;
+use todo_tracker::output::junit::JunitFormatter;
+use todo_tracker::output::sarif::SarifFormatter;
+use todo_tracker::output::glyphs::{self, Glyphs};
+use todo_tracker::output::theme::{self, Theme};
+use todo_tracker::output::{format_output, OutputFormat, OutputFormatter};
+use todo_tracker::digest::{self, build_digests};
+use todo_tracker::git::blame::{
+    author_ages, blame_items_cached, enrich_with_blame, enrich_with_blame_cached,
+    enrich_with_commit_info, AuthorAge, IGNORE_REVS_FILE,
+};
+use todo_tracker::git::diff::{
+    added_lines, changed_files, diff_hunks, diff_staged, diff_todos, staged_files, DiffResult,
+};
+use todo_tracker::git::branches;
+use todo_tracker::git::notes;
+use todo_tracker::git::prs;
+use todo_tracker::git::trace;
+use todo_tracker::git::utils::{git_command, is_git_repo, repo_root};
+use todo_tracker::lint::LintConfig;
+use todo_tracker::policy::{
+    budget_report, check_policies, exempt_items, has_errors, CheckReport, CheckTimings,
+    PolicyConfig,
+};
+use todo_tracker::post_scan::run_post_scan;
+use todo_tracker::rpc;
+use todo_tracker::scanner::regex::{RegexScanner, TagBoundary};
+use todo_tracker::scanner::{FileScanner, ScanOrchestrator};
+use todo_tracker::schema;
+use todo_tracker::snapshot;
+use todo_tracker::ordering;
+use todo_tracker::baseline::{Baseline, BASELINE_FILE};
+use todo_tracker::triage::{self, BulkAction, TriageStore, TRIAGE_FILE};
+use todo_tracker::vcs::{self, Vcs};
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    cli.config = Config::try_load(None)?;
+    let config = cli.config.clone();
+    if let Some(ref output_config) = config.output {
+        apply_output_config(&mut cli, &matches, output_config)?;
+    }
 
     // Handle color mode
     match cli.color {
         ColorMode::Always => colored::control::set_override(true),
         ColorMode::Never => colored::control::set_override(false),
-        ColorMode::Auto => {} // colored crate auto-detects TTY
+        ColorMode::Auto => {
+            let no_color = std::env::var("NO_COLOR").ok();
+            let force = std::env::var("CLICOLOR_FORCE").ok();
+            // Otherwise the colored crate auto-detects TTY
+            if let Some(on) = theme::color_from_env(no_color.as_deref(), force.as_deref()) {
+                colored::control::set_override(on);
+            }
+        }
+    }
+    let ascii = cli.ascii || charset::detect_ascii_from_env();
+    charset::set_ascii(ascii);
+    if let Some(ref theme_config) = config.theme {
+        theme::set_theme(Theme::from_config(theme_config)?);
+    }
+    if let Some(ref output_config) = config.output {
+        let glyphs = Glyphs::from_config(output_config)?;
+        glyphs::set_glyphs(if ascii { glyphs.ascii_only() } else { glyphs });
+    }
+
+    // Other commands would silently write plaintext
+    let scanning = matches!(cli.command, None | Some(Commands::Scan { .. } | Commands::List));
+    if cli.encrypt.is_some() && !scanning {
+        anyhow::bail!("--encrypt is only supported by scan and list");
     }
 
     // Handle commands
@@ -36,13 +115,207 @@ fn main() -> Result<()> {
             std::fs::write(config_path, Config::default_template())?;
             println!("Created .todo-tracker.toml");
         }
-        Some(Commands::Stats) => run_stats(&cli)?,
-        Some(Commands::Diff { ref range, staged }) => run_diff(&cli, range, staged)?,
-        Some(Commands::Check { ref max_todos, ref require_issue, ref deny, diff_only: _, staged_only: _ }) => {
-            run_check(&cli, *max_todos, require_issue.clone(), deny.clone())?;
+        Some(Commands::Stats { blame }) => run_stats(&cli, blame)?,
+        Some(Commands::Diff {
+            ref range,
+            staged,
+            ref against_snapshot,
+            hunks,
+        }) => run_diff(&cli, range, staged, hunks, against_snapshot.as_deref())?,
+        Some(Commands::Check {
+            ref max_todos,
+            ref max_todos_per_kloc,
+            max_skipped_tests,
+            max_panic_sites,
+            exempt_test_code,
+            ref require_issue,
+            ref deny,
+            diff_only,
+            staged_only,
+            ref baseline,
+            ref min_message_length,
+            deny_empty_message,
+            deny_generic_message,
+            ref message_pattern,
+            max_age_days,
+            warn_after_days,
+            error_after_days,
+            ref against_file,
+            update_golden,
+            budget,
+            ref report_file,
+        }) => {
+            let lint_overrides = LintConfig {
+                min_length: *min_message_length,
+                deny_empty: deny_empty_message.then_some(true),
+                deny_generic: deny_generic_message.then_some(true),
+                generic_phrases: None,
+                pattern: message_pattern.clone(),
+            };
+            // CLI thresholds override the [escalation] section of the config file
+            let file_escalation = cli.config.escalation.clone().unwrap_or_default();
+            let escalation = EscalationConfig {
+                warn_after_days: warn_after_days.or(file_escalation.warn_after_days),
+                error_after_days: error_after_days.or(file_escalation.error_after_days),
+            };
+            let policy = PolicyConfig {
+                max_todos: *max_todos,
+                max_todos_per_kloc: *max_todos_per_kloc,
+                require_issue: require_issue
+                    .as_ref()
+                    .map(|s| s.split(',').map(|t| t.trim().to_string()).collect()),
+                deny_tags: deny
+                    .as_ref()
+                    .map(|s| s.split(',').map(|t| t.trim().to_string()).collect()),
+                max_age_days,
+                escalation: (!escalation.is_empty()).then_some(escalation),
+                component_budgets: cli.config.component_budgets.clone(),
+                max_skipped_tests,
+                max_panic_sites,
+                exempt_test_code,
+                ..Default::default()
+            };
+            let scope = if staged_only {
+                CheckScope::Staged
+            } else if diff_only {
+                CheckScope::Diff
+            } else {
+                CheckScope::All
+            };
+            let baseline = baseline.as_ref().map(|file| match file {
+                Some(file) => std::path::PathBuf::from(file),
+                None => std::path::Path::new(&cli.path).join(BASELINE_FILE),
+            });
+            run_check(
+                &cli,
+                policy,
+                lint_overrides,
+                scope,
+                baseline.as_deref(),
+                against_file.as_deref(),
+                update_golden,
+                budget,
+                report_file.as_deref(),
+            )?;
+        }
+        Some(Commands::Triage {
+            all,
+            ref set_priority,
+            ref set_author,
+            ref set_issue,
+            ignore,
+            write_back,
+        }) => {
+            let priority = match set_priority {
+                Some(p) => Some(
+                    Priority::from_str_tag(p)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown priority: {}", p))?,
+                ),
+                None => None,
+            };
+            let bulk = BulkAction {
+                priority,
+                author: set_author.clone(),
+                issue: set_issue.clone(),
+                ignore,
+            };
+            run_triage(&cli, all, bulk, write_back)?;
+        }
+        Some(Commands::Export {
+            ref format,
+            ref output,
+            ref repo,
+            dry_run,
+        }) => match format.as_str() {
+            "github-issues" => run_export_issues(&cli, repo.as_deref(), dry_run)?,
+            _ => run_export(&cli, output.as_deref())?,
+        },
+        Some(Commands::Import { format: _, ref file, write_back }) => {
+            run_import(&cli, file, write_back)?
+        }
+        Some(Commands::Digest { author_emails, ref out_dir, send }) => {
+            run_digest(&cli, author_emails || send, out_dir.as_deref(), send)?
+        }
+        Some(Commands::Snapshot {
+            keep,
+            list,
+            git_notes,
+        }) => run_snapshot(&cli, keep, list, git_notes)?,
+        Some(Commands::Cache { ref command }) => run_cache(&cli, command)?,
+        Some(Commands::Hook {
+            hook:
+                HookCommand::CommitMsg {
+                    ref file,
+                    ref mode,
+                    ref trailer,
+                },
+        }) => run_hook_commit_msg(&cli, file, mode.as_deref(), trailer.as_deref())?,
+        Some(Commands::Blame {
+            ref sort,
+            ref since,
+            ref until,
+            ref age_over,
+            commit_info,
+        }) => {
+            let dates = DateFilter {
+                since: since.as_deref().map(Date::parse).transpose()?,
+                until: until.as_deref().map(Date::parse).transpose()?,
+                age_over_days: age_over.as_deref().map(parse_days).transpose()?,
+            };
+            run_blame(&cli, sort.clone(), dates, commit_info)?
+        }
+        Some(Commands::Heatmap { depth }) => run_heatmap(&cli, depth)?,
+        Some(Commands::Suppressions) => run_suppressions(&cli)?,
+        Some(Commands::Baseline {
+            command: BaselineCommand::Write { ref output },
+        }) => run_baseline_write(&cli, output.as_deref())?,
+        Some(Commands::Config {
+            command: ConfigCommand::Schema,
+        }) => println!("{}", to_json(&schema::config_schema(), cli.json_compact)?),
+        Some(Commands::Report {
+            by_pr,
+            ref since,
+            ref until,
+        }) => {
+            if !by_pr {
+                anyhow::bail!("Specify a report (--by-pr)");
+            }
+            run_report_by_pr(
+                &cli,
+                since.as_deref().map(Date::parse).transpose()?,
+                until.as_deref().map(Date::parse).transpose()?,
+            )?
+        }
+        Some(Commands::Multi {
+            ref repos,
+            ref github_org,
+            include_archived,
+            include_forks,
+            ref clone_dir,
+            no_update,
+        }) => {
+            let source = match (repos, github_org) {
+                (_, Some(org)) => RepoSource::GithubOrg(OrgOptions {
+                    org: org.clone(),
+                    include_archived,
+                    include_forks,
+                }),
+                (Some(repos), None) => RepoSource::File(repos.clone()),
+                (None, None) => anyhow::bail!("multi needs --repos or --github-org"),
+            };
+            run_multi(&cli, source, clone_dir.as_deref(), no_update)?
         }
-        Some(Commands::Blame { ref sort, ref since }) => run_blame(&cli, sort.clone(), since.clone())?,
-        Some(Commands::List) | Some(Commands::Scan) | None => run_scan(&cli)?,
+        Some(Commands::AuditBranches { ref base }) => run_audit_branches(&cli, base.as_deref())?,
+        Some(Commands::Trace { ref target }) => run_trace(&cli, target)?,
+        Some(Commands::Remind {
+            ref repo,
+            open_issue,
+            ref slack_webhook,
+        }) => run_remind(&cli, repo.as_deref(), open_issue, slack_webhook.as_deref())?,
+        Some(Commands::Random { context, seed }) => run_random(&cli, context, seed)?,
+        Some(Commands::Serve { poll_interval }) => run_serve(&cli, poll_interval)?,
+        Some(Commands::Scan { ref subpath }) => run_scan(&cli, subpath.as_deref())?,
+        Some(Commands::List) | None => run_scan(&cli, None)?,
     }
 
     Ok(())
@@ -61,31 +334,219 @@ fn build_filter(cli: &Cli) -> FilterCriteria {
         file_pattern: cli.file.clone(),
         priority: cli.priority.as_ref().and_then(|p| Priority::from_str_tag(p)),
         has_issue: if cli.has_issue { Some(true) } else { None },
+        no_message: if cli.no_message { Some(true) } else { None },
+    }
+}
+
+/// Per-item enrichment from the config: each item's component, from the
+/// `[components]` section, and whether it is test code, by the `[test_code]`
+/// path patterns. Built once, then applied to a whole result or to each
+/// streamed chunk.
+struct Enrichment {
+    components: Option<ComponentMap>,
+    test_paths: TestPaths,
+}
+
+impl Enrichment {
+    fn new(config: &Config, root: &std::path::Path) -> Result<Self> {
+        let components = match config.components {
+            Some(ref components) => Some(ComponentMap::new(root, components)?),
+            None => None,
+        };
+        let test_code = config.test_code.clone().unwrap_or_default();
+        let test_paths = TestPaths::new(root, test_code.paths.as_deref())?;
+        Ok(Enrichment {
+            components,
+            test_paths,
+        })
+    }
+
+    fn apply(&self, result: &mut ScanResult) {
+        if let Some(ref components) = self.components {
+            components.assign(result);
+        }
+        self.test_paths.classify(result);
     }
 }
 
+fn enrich(config: &Config, result: &mut ScanResult) -> Result<()> {
+    Enrichment::new(config, &result.metadata.root_path)?.apply(result);
+    Ok(())
+}
+
 fn apply_filter(filter: &FilterCriteria, result: &mut ScanResult) {
     if !filter.is_empty() {
-        let original_files_scanned = result.stats.files_scanned;
         result.items = filter.apply(&result.items);
-        // Recompute stats after filtering
-        result.stats = ScanStats::new();
-        result.stats.files_scanned = original_files_scanned;
-        let mut files_set = std::collections::HashSet::new();
-        for item in &result.items {
-            result.stats.add_item(item);
-            files_set.insert(item.file.clone());
+        result.recompute_stats();
+    }
+}
+
+/// Apply recorded triage decisions (ignores and assignments) to a scan result.
+/// Returns the items hidden by ignore decisions.
+fn apply_triage(result: &mut ScanResult) -> Result<Vec<TodoItem>> {
+    let store = TriageStore::load(&result.metadata.root_path.join(TRIAGE_FILE))?;
+    Ok(store.apply(result))
+}
+
+/// The suppressed items section shown after text output under
+/// `--show-suppressed`, or nothing.
+fn suppressed_section(cli: &Cli, filter: &FilterCriteria, suppressed: &[TodoItem]) -> String {
+    if !cli.show_suppressed {
+        return String::new();
+    }
+    let suppressed = filter.apply(suppressed);
+    if suppressed.is_empty() {
+        return String::new();
+    }
+    format!("\n{}", text::format_suppressed(&suppressed))
+}
+
+/// Use the [output] config section's format and color where the command
+/// line doesn't give one.
+fn apply_output_config(
+    cli: &mut Cli,
+    matches: &clap::ArgMatches,
+    output: &OutputConfig,
+) -> Result<()> {
+    if let Some(ref format) = output.format {
+        if !given_on_command_line(matches, "format") {
+            cli.format = format.clone();
+        }
+    }
+    if let Some(ref color) = output.color {
+        if !given_on_command_line(matches, "color") {
+            cli.color = ColorMode::from_str(color, true).map_err(|_| {
+                anyhow::anyhow!(
+                    "Invalid color '{}' in [output] (expected auto, always or never)",
+                    color
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether a global flag was passed, before or after the subcommand.
+fn given_on_command_line(matches: &clap::ArgMatches, id: &str) -> bool {
+    let mut current = Some(matches);
+    while let Some(matches) = current {
+        if matches
+            .value_source(id)
+            .is_some_and(|source| source != clap::parser::ValueSource::DefaultValue)
+        {
+            return true;
+        }
+        current = matches.subcommand().map(|(_, sub)| sub);
+    }
+    false
+}
+
+/// Files discovered outside a git repository before asking to continue.
+const CONFIRM_FILES_OUTSIDE_REPO: usize = 10_000;
+
+/// The scanner, with the tag matching settings of the [scan] config section.
+fn build_scanner(config: &Config) -> Result<RegexScanner> {
+    let mut scanner = RegexScanner::new()?;
+    if let Some(ref scan) = config.scan {
+        if let Some(ref boundary) = scan.tag_boundary {
+            scanner = scanner.with_boundary(TagBoundary::parse(boundary)?);
+        }
+        if let Some(ref chars) = scan.tag_prefix_chars {
+            scanner = scanner.with_prefix_chars(chars);
+        }
+        scanner = scanner.with_case_insensitive(scan.case_insensitive.unwrap_or(false))?;
+        if let Some(ref tags) = scan.tags {
+            scanner = scanner.with_tags(tags)?;
+        }
+        if let Some(ref tags) = scan.language_tags {
+            scanner = scanner.with_language_tags(tags)?;
+        }
+        scanner = scanner.with_skipped_tests(scan.skipped_tests.unwrap_or(false))?;
+        scanner = scanner.with_deprecations(scan.deprecations.unwrap_or(false))?;
+        if scan.panic_audit == Some(true) {
+            #[cfg(feature = "precise")]
+            {
+                scanner = scanner.with_panic_audit(true);
+            }
+            #[cfg(not(feature = "precise"))]
+            anyhow::bail!("panic_audit needs todos built with the `precise` feature");
+        }
+    }
+    Ok(scanner)
+}
+
+fn build_discovery(cli: &Cli, root: &str) -> FileDiscovery {
+    let config = &cli.config;
+    let mut discovery = FileDiscovery::new(root);
+    if let Some(ref scan) = config.scan {
+        if let Some(size) = scan.max_file_size {
+            discovery = discovery.with_max_file_size(size);
         }
-        result.stats.files_with_todos = files_set.len();
+        if let Some(respect) = scan.respect_gitignore {
+            discovery = discovery.with_gitignore(respect);
+        }
+    }
+    if let Some(patterns) = config.filter.as_ref().and_then(|f| f.exclude_patterns.clone()) {
+        discovery = discovery.with_exclude_patterns(patterns);
+    }
+    let discovery = discovery
+        .with_default_excludes(!cli.no_default_excludes)
+        .with_follow_symlinks(cli.follow_symlinks)
+        .with_incremental(cli.incremental_discovery)
+        .with_max_depth(cli.max_depth)
+        .with_max_files(cli.max_files);
+    if cli.max_files.is_some() || is_git_repo(std::path::Path::new(root)) {
+        return discovery;
+    }
+    discovery.with_confirm(CONFIRM_FILES_OUTSIDE_REPO, Box::new(confirm_large_scan))
+}
+
+/// Ask before a scan outside a git repository grows large (e.g. `--path /`).
+/// Without a terminal to ask on, warn and keep going.
+fn confirm_large_scan(root: &std::path::Path, count: usize) -> bool {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        eprintln!(
+            "warning: {} is not in a git repository and has more than {} files; \
+             use --max-files or --max-depth to limit the scan",
+            root.display(),
+            count - 1
+        );
+        return true;
+    }
+    eprint!(
+        "{} is not in a git repository and has more than {} files. Continue scanning? [y/N] ",
+        root.display(),
+        count - 1
+    );
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
     }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
 }
 
 fn open_cache(cli: &Cli) -> Option<CacheDb> {
     let path = std::path::Path::new(&cli.path);
     match CacheDb::open(path) {
-        Ok(db) => {
+        Ok(mut db) => {
+            if db.is_read_only() {
+                eprintln!(
+                    "warning: cache is locked by another todos process or not writable; \
+                     using it read-only"
+                );
+            }
             if cli.clear_cache {
-                let _ = db.clear();
+                if let Err(e) = db.clear() {
+                    eprintln!("warning: failed to clear cache: {}", e);
+                }
+            }
+            if let Some(namespace) = cache_namespace(&cli.config, path) {
+                if let Err(e) = db.use_namespace(&namespace) {
+                    eprintln!("warning: failed to switch cache namespace: {}", e);
+                }
             }
             Some(db)
         }
@@ -93,49 +554,756 @@ fn open_cache(cli: &Cli) -> Option<CacheDb> {
     }
 }
 
-fn run_scan(cli: &Cli) -> Result<()> {
-    let scanner = RegexScanner::new()?;
-    let discovery = FileDiscovery::new(&cli.path);
+/// The cache namespace for the tree at `root` when `[cache] branch_namespaces`
+/// is on: the checked-out branch, or `""` on a detached HEAD.
+fn cache_namespace(config: &Config, root: &std::path::Path) -> Option<String> {
+    config
+        .cache
+        .as_ref()
+        .and_then(|c| c.branch_namespaces)
+        .unwrap_or(false)
+        .then(|| branches::current_branch(root).unwrap_or_default())
+}
+
+/// Scan with the per-file cache, reporting paths from the repository root
+/// (see [`report_from_repo_root`]). With `--cache-result`, a clean working
+/// tree reuses the whole result stored for its commit and config.
+fn scan(cli: &Cli, orchestrator: &ScanOrchestrator, cache: Option<&CacheDb>) -> Result<ScanResult> {
+    let root = orchestrator.root();
+    let key = match cache {
+        // Ignored files don't show in `git status`, so without the ignore
+        // rules the commit doesn't describe everything that is scanned
+        Some(_) if cli.cache_result && orchestrator.discovery().respects_gitignore() => {
+            result_key(root, &cli.config, &orchestrator.settings_stamp())
+        }
+        _ => None,
+    };
+    let cached = match (cache, key.as_ref()) {
+        (Some(db), Some(key)) => db.get_result(key).map(|result| rebase(result, root)),
+        _ => None,
+    };
+    let mut result = match cached {
+        Some(result) => result,
+        None => {
+            let result = orchestrator.scan_with_cache(cache)?;
+            if let (Some(db), Some(key)) = (cache, key.as_ref()) {
+                if let Err(e) = db.store_result(key, &relativize(&result)) {
+                    eprintln!("warning: failed to cache scan result: {}", e);
+                }
+            }
+            result
+        }
+    };
+    report_from_repo_root(cli, &mut result);
+    Ok(result)
+}
+
+fn run_scan(cli: &Cli, subpath: Option<&str>) -> Result<()> {
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
     let cache = open_cache(cli);
     let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
+    let encryption = output_encryption(cli)?;
 
-    let mut result = orchestrator.scan_with_cache(cache.as_ref())?;
+    if let Some(budget_mb) = cli.memory_budget {
+        if subpath.is_none() {
+            if encryption.is_some() {
+                anyhow::bail!("--memory-budget can't encrypt output; it is written file by file");
+            }
+            return run_scan_streaming(cli, &orchestrator, cache.as_ref(), budget_mb);
+        }
+    }
+
+    let mut result = match (subpath, cache.as_ref()) {
+        (Some(subpath), Some(db)) => {
+            let mut result = orchestrator.scan_subtree(std::path::Path::new(subpath), db)?;
+            report_from_repo_root(cli, &mut result);
+            result
+        }
+        (Some(_), None) => anyhow::bail!("Partial rescan needs the scan cache, which could not be opened"),
+        (None, _) => scan(cli, &orchestrator, cache.as_ref())?,
+    };
+    let suppressed = apply_triage(&mut result)?;
+    enrich(&cli.config, &mut result)?;
 
     let filter = build_filter(cli);
     apply_filter(&filter, &mut result);
 
+    let format = output_format(cli)?;
+    if let Some(lines) = annotated_lines(cli, &format)? {
+        with_local_paths(&mut result, |result| retain_on_lines(&mut result.items, &lines));
+        result.recompute_stats();
+    }
+    if let Some(vcs) = output_blame_vcs(cli, &format)? {
+        let hits = with_local_paths(&mut result, |result| {
+            enrich_with_blame_cached(&mut result.items, vcs.as_ref(), cache.as_ref())
+        });
+        result.metadata.blame_cache_hits = cache.as_ref().map(|_| hits);
+    }
+    if let Some(command) = post_scan_hook(cli) {
+        let root = result.metadata.root_path.clone();
+        result = run_post_scan(&command, result, &root)?;
+    }
+    if cli.deterministic {
+        ordering::make_deterministic(&mut result, &deterministic_timestamp()?);
+    }
+
+    let output = format_result(cli, &result, format.clone())?;
+    match &encryption {
+        // auto-ci encrypts the artifact; annotations on stdout stay readable
+        Some(encryption) if !is_auto_ci(cli) => {
+            if !matches!(format, OutputFormat::Json | OutputFormat::Sarif) {
+                anyhow::bail!("--encrypt supports json and sarif output");
+            }
+            use std::io::Write;
+            std::io::stdout().write_all(&encryption.encrypt(output.as_bytes())?)?;
+        }
+        _ => print!("{}", output),
+    }
+    if matches!(format, OutputFormat::Text) {
+        print!("{}", suppressed_section(cli, &filter, &suppressed));
+    }
+    if wants_piped_summary(cli, &format) {
+        let mut summary = text::format_brief_summary(&result.stats, &result.metadata);
+        if format == OutputFormat::Sarif {
+            let sarif = sarif_formatter(cli)?;
+            let levels: Vec<AnnotationLevel> = result
+                .items
+                .iter()
+                .map(|item| sarif.level(item))
+                .filter(|level| *level >= sarif.min_level)
+                .collect();
+            let count = |level| levels.iter().filter(|l| **l == level).count();
+            summary.push_str(&format!(
+                "; SARIF: {} error(s), {} warning(s), {} note(s)",
+                count(AnnotationLevel::Error),
+                count(AnnotationLevel::Warning),
+                count(AnnotationLevel::Note)
+            ));
+        }
+        eprintln!("{}", summary);
+    }
+    if is_auto_ci(cli) {
+        let mut artifact = to_json(&result, cli.json_compact)?.into_bytes();
+        if let Some(encryption) = &encryption {
+            artifact = encryption.encrypt(&artifact)?;
+        }
+        std::fs::write(&cli.ci_artifact, artifact).map_err(|e| {
+            anyhow::anyhow!("Failed to write {}: {}", cli.ci_artifact, e)
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Whether to print a summary to stderr: json, csv, tsv or sarif output is
+/// going to a pipe or file, and `--quiet` wasn't given.
+fn wants_piped_summary(cli: &Cli, format: &OutputFormat) -> bool {
+    use std::io::IsTerminal;
+
+    let machine_readable = matches!(
+        format,
+        OutputFormat::Json | OutputFormat::Csv | OutputFormat::Tsv | OutputFormat::Sarif
+    );
+    machine_readable && !cli.quiet && !std::io::stdout().is_terminal()
+}
+
+/// The time `--deterministic` output is made at: SOURCE_DATE_EPOCH, the
+/// reproducible-builds convention, or the Unix epoch.
+fn source_date_epoch() -> Result<i64> {
+    Ok(match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(value) if !value.trim().is_empty() => value.trim().parse::<i64>().map_err(|_| {
+            anyhow::anyhow!("SOURCE_DATE_EPOCH must be a number of seconds, got '{}'", value)
+        })?,
+        _ => 0,
+    })
+}
+
+/// The scan time `--deterministic` reports, from `source_date_epoch`.
+fn deterministic_timestamp() -> Result<String> {
+    let secs = source_date_epoch()?;
+    let time = secs.rem_euclid(86_400);
+    Ok(format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        Date::from_timestamp(secs),
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    ))
+}
+
+/// The `--encrypt` encryption, if given.
+fn output_encryption(cli: &Cli) -> Result<Option<Encryption>> {
+    Ok(cli.encrypt.as_deref().map(Encryption::parse).transpose()?)
+}
+
+/// Whether `--format auto-ci` was asked for.
+fn is_auto_ci(cli: &Cli) -> bool {
+    OutputFormat::from_str(&cli.format).is_ok_and(|format| format == OutputFormat::AutoCi)
+}
+
+/// The `--format` to write, with `auto-ci` resolved to the format of the CI
+/// system the run is in, or text outside CI.
+fn output_format(cli: &Cli) -> Result<OutputFormat> {
     let format = OutputFormat::from_str(&cli.format).map_err(|e| anyhow::anyhow!(e))?;
+    if format != OutputFormat::AutoCi {
+        return Ok(format);
+    }
+    Ok(OutputFormat::detect_ci(|name| std::env::var(name).ok()).unwrap_or(OutputFormat::Text))
+}
+
+/// When `scan_root` is a subdirectory of a git repository, the repository
+/// root and the subdirectory's path within it, `/`-separated. `None` with
+/// `--no-root-resolve`, outside git, or when scanning the whole repository.
+fn resolved_root(cli: &Cli, scan_root: &std::path::Path) -> Option<(std::path::PathBuf, String)> {
+    if cli.no_root_resolve {
+        return None;
+    }
+    let scan_root = scan_root.canonicalize().ok()?;
+    let repo = repo_root(&scan_root).ok()?.canonicalize().ok()?;
+    let subtree = scan_root.strip_prefix(&repo).ok()?.to_string_lossy().replace('\\', "/");
+    (!subtree.is_empty()).then_some((repo, subtree))
+}
+
+/// Report the items of a subtree scan relative to the repository root, as
+/// git does, unless `--no-root-resolve` is given, so every command prints
+/// and fingerprints an item the same way from any directory. The paths no
+/// longer open from the working directory afterwards; steps that read the
+/// files go through [`with_local_paths`].
+fn report_from_repo_root(cli: &Cli, result: &mut ScanResult) {
+    if let Some(root) = resolved_root(cli, &result.metadata.root_path) {
+        rebase_on_repo_root(&root, result);
+    }
+}
+
+/// Where `file`, a path of a result rooted at `root`, opens from the working
+/// directory: paths reported from the repository root are joined to it.
+fn local_path(root: &std::path::Path, file: &std::path::Path) -> std::path::PathBuf {
+    if file.is_relative() && !file.starts_with(root) {
+        root.join(file)
+    } else {
+        file.to_path_buf()
+    }
+}
+
+/// Run `f`, which reads the scanned files, with the paths of `result` made
+/// local, then put the reported paths back.
+fn with_local_paths<R>(result: &mut ScanResult, f: impl FnOnce(&mut ScanResult) -> R) -> R {
+    let root = result.metadata.root_path.clone();
+    let mut reported = std::collections::HashMap::new();
+    let mut localize = |file: std::path::PathBuf| {
+        let local = local_path(&root, &file);
+        if local != file {
+            reported.insert(local.clone(), file);
+        }
+        local
+    };
+    for item in &mut result.items {
+        item.file = localize(std::mem::take(&mut item.file));
+    }
+    result.stats.lines_by_file = std::mem::take(&mut result.stats.lines_by_file)
+        .into_iter()
+        .map(|(path, lines)| (localize(path), lines))
+        .collect();
+
+    let value = f(result);
+
+    let restore = |file: std::path::PathBuf| reported.get(&file).cloned().unwrap_or(file);
+    for item in &mut result.items {
+        item.file = restore(std::mem::take(&mut item.file));
+    }
+    result.stats.lines_by_file = std::mem::take(&mut result.stats.lines_by_file)
+        .into_iter()
+        .map(|(path, lines)| (restore(path), lines))
+        .collect();
+    value
+}
+
+/// `report_from_repo_root` with the root from `resolved_root` already known.
+fn rebase_on_repo_root(root: &(std::path::PathBuf, String), result: &mut ScanResult) {
+    let (repo, subtree) = root;
+    let scan_root = std::mem::replace(&mut result.metadata.root_path, repo.clone());
+    let from_repo = |path: &std::path::Path| {
+        let relative = path.strip_prefix(&scan_root).unwrap_or(path);
+        let relative = relative.strip_prefix(".").unwrap_or(relative);
+        std::path::Path::new(&subtree).join(relative)
+    };
+    for item in &mut result.items {
+        item.file = from_repo(&item.file);
+    }
+    result.stats.lines_by_file = std::mem::take(&mut result.stats.lines_by_file)
+        .into_iter()
+        .map(|(path, lines)| (from_repo(&path), lines))
+        .collect();
+}
+
+/// Item URLs from `[output] link_template`, or else derived from the
+/// `origin` remote, with `{commit}` resolved to HEAD and paths made relative
+/// to the repository root.
+fn link_template(cli: &Cli) -> Result<Option<LinkTemplate>> {
+    let scan_root = std::path::Path::new(&cli.path);
+    let repo = repo_root(scan_root).ok();
+    let configured = cli.config.output.as_ref().and_then(|o| o.link_template.clone());
+    let commit = |repo: &std::path::Path| {
+        git_command(&["rev-parse", "HEAD"], repo).map(|head| head.trim().to_string())
+    };
+    let (template, commit) = match (configured, &repo) {
+        (Some(template), Some(repo)) if LinkTemplate::uses_commit(&template) => {
+            let commit = commit(repo)?;
+            (template, commit)
+        }
+        (Some(template), None) if LinkTemplate::uses_commit(&template) => {
+            anyhow::bail!("link_template uses {{commit}}, which needs a git repository")
+        }
+        (Some(template), _) => (template, String::new()),
+        // Links derived from the remote are left out when they can't be
+        // built, e.g. before the first commit
+        (None, Some(repo)) => {
+            let derived = git_command(&["remote", "get-url", "origin"], repo)
+                .ok()
+                .and_then(|url| links::template_from_remote(&url));
+            match (derived, commit(repo)) {
+                (Some(template), Ok(commit)) => (template, commit),
+                _ => return Ok(None),
+            }
+        }
+        (None, None) => return Ok(None),
+    };
+    // Items are already relative to the repository root unless root
+    // resolution is off
+    let prefix = match (&repo, scan_root.canonicalize()) {
+        _ if !cli.no_root_resolve => String::new(),
+        (Some(repo), Ok(scan_root)) => scan_root
+            .strip_prefix(repo.canonicalize().unwrap_or_else(|_| repo.clone()))
+            .map(|p| p.display().to_string())
+            .unwrap_or_default(),
+        _ => String::new(),
+    };
+    Ok(Some(LinkTemplate::new(&template, &commit, &prefix)))
+}
+
+/// The `[hooks] post_scan` command, if one is configured. A repository's
+/// config only runs it with `--allow-hooks`, so scanning a checkout doesn't
+/// execute commands it names.
+fn post_scan_hook(cli: &Cli) -> Option<String> {
+    use colored::Colorize;
+
+    let command = cli.config.hooks.as_ref()?.post_scan.clone().filter(|c| !c.trim().is_empty())?;
+    if cli.config.trusted || cli.allow_hooks {
+        return Some(command);
+    }
+    eprintln!(
+        "{} not running the post_scan hook of the repository's .todo-tracker.toml \
+         ('{}'); pass --allow-hooks to run it",
+        "warning:".yellow(),
+        command
+    );
+    None
+}
+
+/// Format a result, applying the global flags that configure the text and
+/// CSV formatters.
+fn format_result(cli: &Cli, result: &ScanResult, format: OutputFormat) -> Result<String> {
+    let output = match format {
+        OutputFormat::Text => TextFormatter {
+            show_summary: true,
+            max_width: text_width(cli),
+            snippets: cli.snippets,
+            group_by_component: cli.group_by == GroupBy::Component,
+        }
+        .format(result)?,
+        _ if cli.group_by == GroupBy::Component => {
+            anyhow::bail!("--group-by component supports text output only")
+        }
+        OutputFormat::Csv | OutputFormat::Tsv => csv_formatter(cli, &format)?.format(result)?,
+        OutputFormat::Json => JsonFormatter {
+            compact: cli.json_compact,
+            fields: cli.fields.as_deref().map(split_list),
+            omit: cli.omit.as_deref().map(split_list).unwrap_or_default(),
+            links: link_template(cli)?,
+        }
+        .format(result)?,
+        OutputFormat::Sarif => sarif_formatter(cli)?.format(result)?,
+        OutputFormat::GithubActions => GithubActionsFormatter {
+            min_level: annotate_min_level(cli)?,
+            max_annotations: cli.max_annotations,
+        }
+        .format(result)?,
+        _ => format_output(result, format)?,
+    };
+    Ok(output)
+}
+
+/// Line numbers per canonical file path.
+type ChangedLines = std::collections::HashMap<std::path::PathBuf, std::collections::HashSet<usize>>;
+
+/// With `--annotate-changed-only` and SARIF or github-actions output, the
+/// lines the pull request adds.
+fn annotated_lines(cli: &Cli, format: &OutputFormat) -> Result<Option<ChangedLines>> {
+    if !cli.annotate_changed_only
+        || !matches!(format, OutputFormat::Sarif | OutputFormat::GithubActions)
+    {
+        return Ok(None);
+    }
+    let vcs = open_work_tree(cli)?;
+    handle_shallow(cli, vcs.as_ref())?;
+    let base = match cli.annotate_base.clone() {
+        Some(base) => base,
+        None => match std::env::var("GITHUB_BASE_REF").ok().filter(|b| !b.is_empty()) {
+            Some(branch) => format!("origin/{}", branch),
+            None => branches::default_base(vcs.root()).map_err(|e| {
+                anyhow::anyhow!("{}\nhint: pass --annotate-base", e)
+            })?,
+        },
+    };
+    let lines = added_lines(&base, "HEAD", vcs.as_ref())
+        .map_err(|e| anyhow::anyhow!("Failed to diff against {}: {}", base, e))?;
+    Ok(Some(
+        lines
+            .into_iter()
+            .filter_map(|(path, lines)| Some((path.canonicalize().ok()?, lines)))
+            .collect(),
+    ))
+}
+
+/// Keep the items on `lines`.
+fn retain_on_lines(items: &mut Vec<TodoItem>, lines: &ChangedLines) {
+    let mut canonical = std::collections::HashMap::new();
+    items.retain(|item| {
+        let path = canonical
+            .entry(item.file.clone())
+            .or_insert_with(|| item.file.canonicalize().ok());
+        path.as_ref()
+            .and_then(|path| lines.get(path))
+            .is_some_and(|lines| lines.contains(&item.line))
+    });
+}
+
+/// The lowest level annotated in SARIF and github-actions output.
+fn annotate_min_level(cli: &Cli) -> Result<AnnotationLevel> {
+    let level = cli.annotate_min_level.as_deref().unwrap_or("note");
+    AnnotationLevel::from_str(level).map_err(|e| anyhow::anyhow!(e))
+}
+
+/// Split a comma-separated flag value, dropping empty entries.
+fn split_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// CSV or TSV formatter with the columns selected by `--csv-columns` and
+/// the separator from `--delimiter`.
+fn csv_formatter(cli: &Cli, format: &OutputFormat) -> Result<CsvFormatter> {
+    let mut formatter = match format {
+        OutputFormat::Tsv => CsvFormatter::tsv(),
+        _ => CsvFormatter::default(),
+    };
+    formatter.columns = cli.csv_columns.as_deref().map(CsvColumn::parse_list).transpose()?;
+    if let Some(ref delimiter) = cli.delimiter {
+        formatter.delimiter = parse_delimiter(delimiter)?;
+    }
+    if cli.deterministic {
+        formatter.today = Some(Date::from_timestamp(source_date_epoch()?));
+    }
+    Ok(formatter)
+}
+
+/// SARIF formatter with age escalation and `--annotate-min-level`.
+fn sarif_formatter(cli: &Cli) -> Result<SarifFormatter> {
+    Ok(SarifFormatter {
+        escalation: sarif_escalation(&cli.config),
+        min_level: annotate_min_level(cli)?,
+    })
+}
+
+/// Age escalation for SARIF output, from the [escalation] config section.
+fn sarif_escalation(config: &Config) -> Option<Escalator> {
+    let config = config.escalation.clone().filter(|e| !e.is_empty())?;
+    Some(Escalator {
+        config,
+        today: Date::today(),
+    })
+}
+
+/// The repository to blame with when the output needs blame data (selected
+/// CSV columns, or SARIF with age escalation), else `None`.
+fn output_blame_vcs(cli: &Cli, format: &OutputFormat) -> Result<Option<Box<dyn Vcs>>> {
+    let is_csv = matches!(format, OutputFormat::Csv | OutputFormat::Tsv);
+    let escalated_sarif =
+        matches!(format, OutputFormat::Sarif) && sarif_escalation(&cli.config).is_some();
+    if (is_csv && csv_formatter(cli, format)?.needs_blame()) || escalated_sarif {
+        let vcs = open_work_tree(cli)?;
+        handle_shallow(cli, vcs.as_ref())?;
+        Ok(Some(vcs))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Column budget for text output: the terminal width, unless `--full`.
+fn text_width(cli: &Cli) -> Option<usize> {
+    if cli.full {
+        None
+    } else {
+        text::terminal_width()
+    }
+}
+
+/// `run_scan` under `--memory-budget`: items arrive in canonical order from
+/// the spill buffer and are triaged, filtered and printed one file at a time.
+fn run_scan_streaming(
+    cli: &Cli,
+    orchestrator: &ScanOrchestrator,
+    cache: Option<&CacheDb>,
+    budget_mb: usize,
+) -> Result<()> {
+    use std::io::Write;
+
+    if is_auto_ci(cli) {
+        anyhow::bail!("--memory-budget can't write the whole result for --format auto-ci");
+    }
+    let format = output_format(cli)?;
+    if !matches!(
+        format,
+        OutputFormat::Text
+            | OutputFormat::Json
+            | OutputFormat::Csv
+            | OutputFormat::Tsv
+            | OutputFormat::Count
+            | OutputFormat::GithubActions
+            | OutputFormat::VsCode
+    ) {
+        anyhow::bail!(
+            "--memory-budget supports text, json, csv, tsv, count, github-actions and vscode \
+             output"
+        );
+    }
+    if format == OutputFormat::Json && (cli.fields.is_some() || cli.omit.is_some()) {
+        anyhow::bail!("--memory-budget can't select JSON fields; items are written one by one");
+    }
+
+    if post_scan_hook(cli).is_some() {
+        anyhow::bail!("--memory-budget can't run the post_scan hook, which needs the whole result");
+    }
+    if cli.group_by == GroupBy::Component {
+        anyhow::bail!("--memory-budget can't group by component; output is written file by file");
+    }
+    if cli.max_annotations.is_some() {
+        anyhow::bail!(
+            "--memory-budget can't rank items for --max-annotations; output is written file by file"
+        );
+    }
+
+    let (items, scan_stats, metadata) =
+        orchestrator.scan_spilled(cache, budget_mb.saturating_mul(1024 * 1024))?;
+    // Chunks are reported from the repository root, as `scan` does
+    let repo_root = resolved_root(cli, &metadata.root_path);
+    let state_root = repo_root.as_ref().map_or(&metadata.root_path, |(repo, _)| repo);
+    let store = TriageStore::load(&state_root.join(TRIAGE_FILE))?;
+    let enrichment = Enrichment::new(&cli.config, state_root)?;
+    let filter = build_filter(cli);
+    let max_width = text_width(cli);
+    let csv = csv_formatter(cli, &format)?;
+    let min_level = annotate_min_level(cli)?;
+    let changed_lines = annotated_lines(cli, &format)?;
+    let blame_vcs = output_blame_vcs(cli, &format)?;
+    let timestamp = cli.deterministic.then(deterministic_timestamp).transpose()?;
+
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    let mut stats = ScanStats {
+        files_scanned: scan_stats.files_scanned,
+        lines_of_code: scan_stats.lines_of_code,
+        lines_by_language: scan_stats.lines_by_language,
+        ..ScanStats::new()
+    };
+    let links = match format {
+        OutputFormat::Json => link_template(cli)?,
+        _ => None,
+    };
+    let mut json = JsonStream::new(cli.json_compact, links);
+    let mut groups_written = 0;
+    let mut suppressed: Vec<TodoItem> = Vec::new();
+
+    let mut emit = |group: Vec<TodoItem>, out: &mut dyn Write, stats: &mut ScanStats| -> Result<()> {
+        let mut chunk = ScanResult {
+            items: group,
+            stats: ScanStats::new(),
+            metadata: metadata.clone(),
+        };
+        if let Some(ref root) = repo_root {
+            rebase_on_repo_root(root, &mut chunk);
+        }
+        let hidden = store.apply(&mut chunk);
+        stats.suppressed += hidden.len();
+        if cli.show_suppressed {
+            suppressed.extend(hidden);
+        }
+        enrichment.apply(&mut chunk);
+        apply_filter(&filter, &mut chunk);
+        if let Some(ref lines) = changed_lines {
+            with_local_paths(&mut chunk, |chunk| retain_on_lines(&mut chunk.items, lines));
+        }
+        if chunk.items.is_empty() {
+            return Ok(());
+        }
+        if let Some(ref vcs) = blame_vcs {
+            with_local_paths(&mut chunk, |chunk| enrich_with_blame(&mut chunk.items, vcs.as_ref()));
+        }
+        if let Some(ref timestamp) = timestamp {
+            ordering::make_deterministic(&mut chunk, timestamp);
+        }
+        stats.files_with_todos += 1;
+        for item in &chunk.items {
+            stats.add_item(item);
+        }
+        let text = match format {
+            OutputFormat::Count => String::new(),
+            OutputFormat::Text => {
+                let text = TextFormatter {
+                    show_summary: false,
+                    max_width,
+                    snippets: cli.snippets,
+                    group_by_component: false,
+                }
+                .format(&chunk)?;
+                if groups_written > 0 {
+                    format!("\n{}", text)
+                } else {
+                    text
+                }
+            }
+            OutputFormat::Json => json.items(&chunk)?,
+            OutputFormat::GithubActions => GithubActionsFormatter {
+                min_level,
+                ..Default::default()
+            }
+            .format(&chunk)?,
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                let text = csv.format(&chunk)?;
+                // Only the first chunk keeps the header row
+                match text.split_once('\n') {
+                    Some((_, rows)) if groups_written > 0 => rows.to_string(),
+                    _ => text,
+                }
+            }
+            _ => format_output(&chunk, format.clone())?,
+        };
+        out.write_all(text.as_bytes())?;
+        groups_written += 1;
+        Ok(())
+    };
 
-    let output = format_output(&result, format)?;
-    print!("{}", output);
+    let mut group: Vec<TodoItem> = Vec::new();
+    for item in items {
+        let item = item?;
+        if group.last().is_some_and(|last| last.file != item.file) {
+            emit(std::mem::take(&mut group), &mut out, &mut stats)?;
+        }
+        group.push(item);
+    }
+    if !group.is_empty() {
+        emit(group, &mut out, &mut stats)?;
+    }
+    let mut metadata = metadata;
+    if let Some((repo, _)) = repo_root {
+        metadata.root_path = repo;
+    }
+    if let Some(ref timestamp) = timestamp {
+        ordering::make_metadata_deterministic(&mut metadata, timestamp);
+    }
 
+    match format {
+        OutputFormat::Count => write!(out, "{}", stats.total_todos)?,
+        OutputFormat::Json => write!(out, "{}", json.finish(&stats, &metadata)?)?,
+        OutputFormat::Text => {
+            writeln!(out)?;
+            write!(out, "{}", format_summary(&stats, &metadata))?;
+            write!(out, "{}", suppressed_section(cli, &filter, &suppressed))?;
+        }
+        _ => {}
+    }
+    out.flush()?;
+    if wants_piped_summary(cli, &format) {
+        eprintln!("{}", text::format_brief_summary(&stats, &metadata));
+    }
     Ok(())
 }
 
-fn run_stats(cli: &Cli) -> Result<()> {
-    let scanner = RegexScanner::new()?;
-    let discovery = FileDiscovery::new(&cli.path);
+fn run_stats(cli: &Cli, blame: bool) -> Result<()> {
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
     let cache = open_cache(cli);
     let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
 
-    let mut result = orchestrator.scan_with_cache(cache.as_ref())?;
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+    apply_triage(&mut result)?;
+    enrich(&cli.config, &mut result)?;
 
     let filter = build_filter(cli);
     apply_filter(&filter, &mut result);
 
+    let ages = if blame {
+        let vcs = open_work_tree(cli)?;
+        handle_shallow(cli, vcs.as_ref())?;
+        let hits = with_local_paths(&mut result, |result| {
+            enrich_with_blame_cached(&mut result.items, vcs.as_ref(), cache.as_ref())
+        });
+        result.metadata.blame_cache_hits = cache.as_ref().map(|_| hits);
+        Some(author_ages(&result.items, Date::today()))
+    } else {
+        None
+    };
+
     // JSON output mode
     if cli.format == "json" {
-        let json = serde_json::to_string_pretty(&result.stats)?;
-        println!("{}", json);
+        let mut json = serde_json::to_value(&result.stats)?;
+        if let Some(ref ages) = ages {
+            json["author_ages"] = serde_json::to_value(ages)?;
+        }
+        println!("{}", to_json(&json, cli.json_compact)?);
         return Ok(());
     }
 
     // Text stats with Unicode bar charts
     print_stats(&result);
+    if let Some(ref ages) = ages {
+        print_author_ages(ages);
+    }
 
     Ok(())
 }
 
+fn print_author_ages(ages: &[AuthorAge]) {
+    println!();
+    println!("Author Ages (git blame, oldest average first):");
+    if ages.is_empty() {
+        println!("  (no blame information)");
+        return;
+    }
+    let width = ages.iter().map(|a| a.author.len()).max().unwrap_or(0).max(6);
+    println!(
+        "  {:<width$} {:>5} {:>8} {:>8}",
+        "Author",
+        "TODOs",
+        "Avg age",
+        "Max age",
+        width = width
+    );
+    for age in ages {
+        println!(
+            "  {:<width$} {:>5} {:>7}d {:>7}d",
+            age.author,
+            age.count,
+            age.avg_days,
+            age.max_days,
+            width = width
+        );
+    }
+}
+
 fn print_stats(result: &ScanResult) {
     const MAX_BAR: usize = 20;
 
@@ -144,20 +1312,15 @@ fn print_stats(result: &ScanResult) {
         println!("  (no items found)");
     } else {
         let mut tag_counts: Vec<(&String, &usize)> = result.stats.by_tag.iter().collect();
-        tag_counts.sort_by(|a, b| b.1.cmp(a.1));
+        tag_counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
 
         let max_count = *tag_counts.iter().map(|(_, c)| *c).max().unwrap_or(&1);
         let total = result.stats.total_todos;
         let max_label_len = tag_counts.iter().map(|(t, _)| t.len()).max().unwrap_or(0);
 
         for (tag, count) in &tag_counts {
-            let bar_len = if max_count > 0 {
-                (**count * MAX_BAR) / max_count
-            } else {
-                0
-            }
-            .max(1);
-            let bar: String = "\u{2588}".repeat(bar_len);
+            let bar_len = (**count * MAX_BAR).checked_div(max_count).unwrap_or(0).max(1);
+            let bar = charset::bar(bar_len);
             let pct = if total > 0 {
                 (**count as f64 / total as f64 * 100.0) as usize
             } else {
@@ -174,7 +1337,90 @@ fn print_stats(result: &ScanResult) {
         }
     }
 
-    // Top files by TODO count
+    // Priority distribution, most urgent first
+    println!();
+    println!("Priority Distribution:");
+    let stats = &result.stats;
+    if stats.total_todos == 0 {
+        println!("  (no items found)");
+    } else {
+        let with_priority: usize = stats.by_priority.values().sum();
+        let mut rows: Vec<(&str, usize)> = ["critical", "high", "medium", "low"]
+            .iter()
+            .map(|p| (*p, stats.by_priority.get(*p).copied().unwrap_or(0)))
+            .collect();
+        rows.push(("none", stats.total_todos - with_priority));
+
+        let max_count = rows.iter().map(|(_, c)| *c).max().unwrap_or(1);
+        for (priority, count) in &rows {
+            let bar_len = (*count * MAX_BAR).checked_div(max_count).unwrap_or(0);
+            let pct = *count * 100 / stats.total_todos;
+            println!(
+                "  {:<8} {:20} {:>3} ({:>2}%)",
+                priority,
+                charset::bar(bar_len),
+                count,
+                pct
+            );
+        }
+    }
+
+    // Issue linkage
+    println!();
+    println!(
+        "Issue Linkage: {} linked, {} unlinked ({}% linked)",
+        stats.with_issue,
+        stats.without_issue,
+        (stats.with_issue * 100).checked_div(stats.total_todos).unwrap_or(0)
+    );
+
+    // Components, largest first, with items outside every component last
+    if !stats.by_component.is_empty() {
+        println!();
+        println!("Components:");
+        let mut components: Vec<(&str, usize)> =
+            stats.by_component.iter().map(|(c, n)| (c.as_str(), *n)).collect();
+        components.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        let assigned: usize = stats.by_component.values().sum();
+        if assigned < stats.total_todos {
+            components.push((UNASSIGNED, stats.total_todos - assigned));
+        }
+        let max_count = components.iter().map(|(_, c)| *c).max().unwrap_or(1);
+        let width = components.iter().map(|(c, _)| c.len()).max().unwrap_or(0);
+        for (component, count) in &components {
+            let bar_len = (*count * MAX_BAR).checked_div(max_count).unwrap_or(0).max(1);
+            println!(
+                "  {:<width$} {:20} {:>3} ({:>2}%)",
+                component,
+                charset::bar(bar_len),
+                count,
+                *count * 100 / stats.total_todos,
+                width = width
+            );
+        }
+    }
+
+    if stats.suppressed > 0 {
+        println!();
+        println!("Suppressed: {} (hidden by triage ignore decisions)", stats.suppressed);
+    }
+
+    // Lines of code and TODO density, largest languages first
+    if let Some(density) = stats.todos_per_kloc() {
+        println!();
+        println!(
+            "Lines of Code: {} ({:.1} TODOs/KLOC)",
+            stats.lines_of_code, density
+        );
+        let mut languages: Vec<(&String, &usize)> = stats.lines_by_language.iter().collect();
+        languages.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let width = languages.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+        for (language, lines) in languages {
+            println!("  {:<width$} {:>8}", language, lines, width = width);
+        }
+    }
+
+    // Top files by TODO count
     println!();
     println!("Top Files (by TODO count):");
     let mut file_counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
@@ -188,20 +1434,15 @@ fn print_stats(result: &ScanResult) {
         println!("  (no items found)");
     } else {
         let mut file_list: Vec<(String, usize)> = file_counts.into_iter().collect();
-        file_list.sort_by(|a, b| b.1.cmp(&a.1));
+        file_list.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
         file_list.truncate(10); // Show top 10
 
         let max_count = file_list.iter().map(|(_, c)| *c).max().unwrap_or(1);
         let max_label_len = file_list.iter().map(|(f, _)| f.len()).max().unwrap_or(0);
 
         for (file, count) in &file_list {
-            let bar_len = if max_count > 0 {
-                (*count * MAX_BAR) / max_count
-            } else {
-                0
-            }
-            .max(1);
-            let bar: String = "\u{2588}".repeat(bar_len);
+            let bar_len = (*count * MAX_BAR).checked_div(max_count).unwrap_or(0).max(1);
+            let bar = charset::bar(bar_len);
             println!(
                 "  {:<width$} {:20} {}",
                 file,
@@ -227,19 +1468,14 @@ fn print_stats(result: &ScanResult) {
         println!("  (no authors found)");
     } else {
         let mut author_list: Vec<(String, usize)> = author_counts.into_iter().collect();
-        author_list.sort_by(|a, b| b.1.cmp(&a.1));
+        author_list.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
 
         let max_count = author_list.iter().map(|(_, c)| *c).max().unwrap_or(1);
         let max_label_len = author_list.iter().map(|(a, _)| a.len()).max().unwrap_or(0);
 
         for (author, count) in &author_list {
-            let bar_len = if max_count > 0 {
-                (*count * MAX_BAR) / max_count
-            } else {
-                0
-            }
-            .max(1);
-            let bar: String = "\u{2588}".repeat(bar_len);
+            let bar_len = (*count * MAX_BAR).checked_div(max_count).unwrap_or(0).max(1);
+            let bar = charset::bar(bar_len);
             println!(
                 "  {:<width$} {:20} {}",
                 author,
@@ -256,38 +1492,192 @@ fn print_stats(result: &ScanResult) {
         "Total: {} items in {} files ({} files scanned)",
         result.stats.total_todos, result.stats.files_with_todos, result.stats.files_scanned
     );
+    if result.stats.empty_messages > 0 {
+        println!(
+            "Empty messages: {} (list them with --no-message)",
+            result.stats.empty_messages
+        );
+    }
+    if result.stats.in_test_code > 0 {
+        println!(
+            "Test code: {} ({} in production code)",
+            result.stats.in_test_code,
+            result.stats.total_todos - result.stats.in_test_code
+        );
+    }
+    if result.stats.skipped_tests > 0 {
+        println!("Disabled tests: {}", result.stats.skipped_tests);
+    }
+    if result.stats.deprecations > 0 {
+        println!("Deprecations: {}", result.stats.deprecations);
+    }
+    if result.stats.panic_sites > 0 {
+        println!("Panic sites: {}", result.stats.panic_sites);
+    }
+    if result.stats.non_canonical_case > 0 {
+        println!(
+            "Non-canonical tag case: {} (e.g. todo or Fixme instead of TODO)",
+            result.stats.non_canonical_case
+        );
+    }
 }
 
-fn run_diff(cli: &Cli, range: &str, staged: bool) -> Result<()> {
-    use colored::Colorize;
+/// The repository at `--path`. Failing that, the error says what to do.
+fn open_vcs(cli: &Cli) -> Result<Box<dyn Vcs>> {
+    vcs::detect(std::path::Path::new(&cli.path))
+        .map_err(|e| anyhow::anyhow!("{}\nhint: {}", e, vcs_hint(&e)))
+}
 
-    let path = std::path::Path::new(&cli.path);
-    if !is_git_repo(path) {
-        anyhow::bail!("Not a git repository: {}", cli.path);
+/// [`open_vcs`] for commands that scan or blame the working tree.
+fn open_work_tree(cli: &Cli) -> Result<Box<dyn Vcs>> {
+    ignore_blame_revs(cli, require_work_tree(open_vcs(cli)?)?)
+}
+
+/// Have blame look past the commits in `--ignore-revs-file`, `[blame]
+/// ignore_revs_file`, or else `.git-blame-ignore-revs` when the repository
+/// has one. A missing file is an error unless it is the default.
+fn ignore_blame_revs(cli: &Cli, mut vcs: Box<dyn Vcs>) -> Result<Box<dyn Vcs>> {
+    let configured = cli.config.blame.as_ref().and_then(|b| b.ignore_revs_file.clone());
+    let file = match (cli.ignore_revs_file.as_deref(), configured) {
+        (Some(""), _) => return Ok(vcs),
+        (Some(file), _) => std::path::PathBuf::from(file),
+        (None, Some(file)) => vcs.root().join(file),
+        (None, None) => {
+            let default = vcs.root().join(IGNORE_REVS_FILE);
+            // Mercurial can't use it; blame goes on as before
+            if default.is_file() && vcs.ignore_revs_file(&default).is_err() {
+                eprintln!("warning: {} blame ignores {}", vcs.name(), IGNORE_REVS_FILE);
+            }
+            return Ok(vcs);
+        }
+    };
+    vcs.ignore_revs_file(&file)?;
+    Ok(vcs)
+}
+
+/// `vcs`, unless it is a bare repository, which has no working tree.
+fn require_work_tree(vcs: Box<dyn Vcs>) -> Result<Box<dyn Vcs>> {
+    if vcs.is_bare() {
+        anyhow::bail!(
+            "{} is a bare repository with no working tree\nhint: only `todos diff <base>..<head>`, `todos report --by-pr` and `todos audit-branches` work there",
+            vcs.root().display()
+        );
     }
+    Ok(vcs)
+}
 
-    let root = repo_root(path).map_err(|e| anyhow::anyhow!(e))?;
-    let scanner = RegexScanner::new()?;
+/// What to do about a shallow clone.
+const SHALLOW_HINT: &str =
+    "pass --auto-fetch-depth, or check out full history (fetch-depth: 0 with actions/checkout)";
 
-    let result: DiffResult = if staged {
-        diff_staged(&scanner, &root).map_err(|e| anyhow::anyhow!(e))?
-    } else if range.is_empty() {
-        anyhow::bail!("Specify a ref range (e.g., main..HEAD) or use --staged");
-    } else {
-        let parts: Vec<&str> = range.splitn(2, "..").collect();
-        if parts.len() != 2 || parts[0].is_empty() || parts[1].is_empty() {
-            anyhow::bail!("Invalid range format. Use base..head (e.g., main..HEAD)");
+/// Fetch more history for a shallow clone when `--auto-fetch-depth` asks
+/// for it. Otherwise warn: blame then pins older lines on the clone's
+/// cut-off commit, and diff bases before it are missing.
+fn handle_shallow(cli: &Cli, vcs: &dyn Vcs) -> Result<()> {
+    if !vcs.is_shallow() {
+        return Ok(());
+    }
+    match cli.auto_fetch_depth {
+        Some(depth) => vcs
+            .deepen((depth > 0).then_some(depth))
+            .map_err(|e| anyhow::anyhow!("Failed to fetch more history: {}", e)),
+        None => {
+            eprintln!("warning: shallow clone; blame for older lines is incomplete and older diff bases are missing");
+            eprintln!("hint: {}", SHALLOW_HINT);
+            Ok(())
+        }
+    }
+}
+
+/// What to do about a repository that couldn't be opened.
+fn vcs_hint(e: &TodoError) -> &'static str {
+    match e {
+        TodoError::GitNotFound => {
+            "install git (https://git-scm.com/downloads) and make sure it is on PATH"
+        }
+        _ => "run todos inside a git or Mercurial checkout, or point --path into one",
+    }
+}
+
+fn diff_vcs(
+    scanner: &RegexScanner,
+    range: &str,
+    staged: bool,
+    hunks: bool,
+    vcs: &dyn Vcs,
+) -> Result<DiffResult> {
+    if staged {
+        if hunks {
+            return diff_hunks(scanner, vcs.working_parent(), None, vcs)
+                .map_err(|e| anyhow::anyhow!(e));
+        }
+        return diff_staged(scanner, vcs).map_err(|e| anyhow::anyhow!(e));
+    }
+    if range.is_empty() {
+        anyhow::bail!("Specify a ref range (e.g., main..HEAD), --staged or --against-snapshot");
+    }
+    let parts: Vec<&str> = range.splitn(2, "..").collect();
+    if parts.len() != 2 || parts[0].is_empty() || parts[1].is_empty() {
+        anyhow::bail!("Invalid range format. Use base..head (e.g., main..HEAD)");
+    }
+    if hunks {
+        return diff_hunks(scanner, parts[0], Some(parts[1]), vcs).map_err(|e| anyhow::anyhow!(e));
+    }
+    diff_todos(scanner, parts[0], parts[1], vcs).map_err(|e| anyhow::anyhow!(e))
+}
+
+fn run_diff(
+    cli: &Cli,
+    range: &str,
+    staged: bool,
+    hunks: bool,
+    against_snapshot: Option<&str>,
+) -> Result<()> {
+    use colored::Colorize;
+
+    let path = std::path::Path::new(&cli.path);
+    let result: DiffResult = if let Some(id) = against_snapshot {
+        let (id, stored) = snapshot::load_snapshot(path, id)?;
+        let discovery = build_discovery(cli, &cli.path);
+        let cache = open_cache(cli);
+        let orchestrator = ScanOrchestrator::new(Box::new(build_scanner(&cli.config)?), discovery);
+        let mut current = scan(cli, &orchestrator, cache.as_ref())?;
+        apply_triage(&mut current)?;
+        // Both sides go through the filters, with the snapshot's paths
+        // reported like the current ones so path filters see the same paths
+        let mut stored = relativize(&stored);
+        if resolved_root(cli, path).is_some() {
+            stored.metadata.root_path = current.metadata.root_path.clone();
+        } else {
+            stored = rebase(stored, &current.metadata.root_path);
         }
-        diff_todos(&scanner, parts[0], parts[1], &root).map_err(|e| anyhow::anyhow!(e))?
+        let filter = build_filter(cli);
+        apply_filter(&filter, &mut stored);
+        apply_filter(&filter, &mut current);
+        snapshot::diff_against_snapshot(&id, &stored, &current)
+    } else {
+        // --staged compares against the working tree
+        let vcs = if staged { open_work_tree(cli)? } else { open_vcs(cli)? };
+        handle_shallow(cli, vcs.as_ref())?;
+        diff_vcs(&build_scanner(&cli.config)?, range, staged, hunks, vcs.as_ref()).map_err(|e| {
+            if vcs.is_shallow() {
+                anyhow::anyhow!("{}\nhint: {}", e, SHALLOW_HINT)
+            } else {
+                e
+            }
+        })?
     };
 
     // JSON output
     if cli.format == "json" {
-        let json = serde_json::to_string_pretty(&result)?;
-        println!("{}", json);
+        println!("{}", to_json(&result, cli.json_compact)?);
         return Ok(());
     }
 
+    for error in &result.errors {
+        eprintln!("{} {}: {}", "warning:".yellow(), error.file, error.message);
+    }
+
     // Text output
     println!(
         "TODO diff: {} -> {}",
@@ -339,36 +1729,220 @@ fn run_diff(cli: &Cli, range: &str, staged: bool) -> Result<()> {
     Ok(())
 }
 
+/// Which files `check` evaluates policies on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CheckScope {
+    All,
+    /// Files changed between the base branch and HEAD (`--diff-only`)
+    Diff,
+    /// Files in the index (`--staged-only`)
+    Staged,
+}
+
+/// Canonical paths of the files `scope` covers, or `None` for every file.
+/// `--diff-only` diffs against origin/$GITHUB_BASE_REF when it is set, else
+/// the default branch.
+fn scoped_files(
+    cli: &Cli,
+    scope: CheckScope,
+) -> Result<Option<std::collections::HashSet<std::path::PathBuf>>> {
+    if scope == CheckScope::All {
+        return Ok(None);
+    }
+    let vcs = open_work_tree(cli)?;
+    if vcs.name() != "git" {
+        anyhow::bail!("--diff-only and --staged-only require a git repository");
+    }
+    let root = vcs.root();
+    let files = if scope == CheckScope::Staged {
+        staged_files(root).map_err(|e| anyhow::anyhow!(e))?
+    } else {
+        handle_shallow(cli, vcs.as_ref())?;
+        let base = match std::env::var("GITHUB_BASE_REF").ok().filter(|b| !b.is_empty()) {
+            Some(branch) => format!("origin/{}", branch),
+            None => branches::default_base(root).map_err(|e| anyhow::anyhow!(e))?,
+        };
+        changed_files(&base, "HEAD", root)
+            .map_err(|e| anyhow::anyhow!("Failed to diff against {}: {}", base, e))?
+    };
+    Ok(Some(
+        files
+            .into_iter()
+            .filter(|f| f.in_head())
+            .filter_map(|f| f.path.canonicalize().ok())
+            .collect(),
+    ))
+}
+
+/// Keep the items and line counts of `files`, so aggregate limits and
+/// density only measure those files.
+fn retain_files(result: &mut ScanResult, files: &std::collections::HashSet<std::path::PathBuf>) {
+    let in_scope = |path: &std::path::Path| {
+        path.canonicalize().is_ok_and(|path| files.contains(&path))
+    };
+    result.items.retain(|item| in_scope(&item.file));
+    result.stats.lines_by_file.retain(|path, _| in_scope(path));
+    result.stats.lines_of_code = result.stats.lines_by_file.values().sum();
+    result.recompute_stats();
+}
+
+#[allow(clippy::too_many_arguments)]
 fn run_check(
     cli: &Cli,
-    max_todos: Option<usize>,
-    require_issue: Option<String>,
-    deny: Option<String>,
+    mut config: PolicyConfig,
+    lint_overrides: LintConfig,
+    scope: CheckScope,
+    baseline: Option<&std::path::Path>,
+    against_file: Option<&str>,
+    update_golden: bool,
+    budget: bool,
+    report_file: Option<&str>,
 ) -> Result<()> {
-    let scanner = RegexScanner::new()?;
-    let discovery = FileDiscovery::new(&cli.path);
+    let started = std::time::Instant::now();
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
     let cache = open_cache(cli);
     let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
 
-    let mut result = orchestrator.scan_with_cache(cache.as_ref())?;
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+    apply_triage(&mut result)?;
+    enrich(&cli.config, &mut result)?;
 
     let filter = build_filter(cli);
     apply_filter(&filter, &mut result);
+    if let Some(files) = scoped_files(cli, scope)? {
+        with_local_paths(&mut result, |result| retain_files(result, &files));
+    }
+    if let Some(path) = against_file {
+        with_local_paths(&mut result, |result| {
+            golden::exclude_golden_file(std::path::Path::new(path), result)
+        });
+    }
+    let baseline_match = match baseline {
+        Some(path) => {
+            let matched = Baseline::load(path)?.apply(&mut result);
+            Some((path, matched))
+        }
+        None => None,
+    };
 
-    let config = PolicyConfig {
-        max_todos,
-        require_issue: require_issue
-            .map(|s| s.split(',').map(|t| t.trim().to_string()).collect()),
-        deny_tags: deny
-            .map(|s| s.split(',').map(|t| t.trim().to_string()).collect()),
-        max_age_days: None,
+    // CLI lint flags override the [lint] section of the config file
+    let file_lint = cli.config.lint.clone().unwrap_or_default();
+    let lint = LintConfig {
+        min_length: lint_overrides.min_length.or(file_lint.min_length),
+        deny_empty: lint_overrides.deny_empty.or(file_lint.deny_empty),
+        deny_generic: lint_overrides.deny_generic.or(file_lint.deny_generic),
+        generic_phrases: file_lint.generic_phrases,
+        pattern: lint_overrides.pattern.or(file_lint.pattern),
     };
+    lint.compiled_pattern()
+        .map_err(|e| anyhow::anyhow!("Invalid lint message pattern: {}", e))?;
+
+    config.lint = if lint.is_empty() { None } else { Some(lint) };
 
+    if config.escalation.is_some() || config.max_age_days.is_some() {
+        let vcs = open_work_tree(cli)?;
+        handle_shallow(cli, vcs.as_ref())?;
+        with_local_paths(&mut result, |result| {
+            enrich_with_blame_cached(&mut result.items, vcs.as_ref(), cache.as_ref())
+        });
+    }
+
+    if cli.deterministic {
+        ordering::make_deterministic(&mut result, &deterministic_timestamp()?);
+    }
+
+    let check_started = std::time::Instant::now();
     let violations = check_policies(&result, &config);
+    let check_ms = check_started.elapsed().as_millis() as u64;
+
+    // With --format junit, stdout carries the XML report alone
+    let junit = matches!(output_format(cli), Ok(OutputFormat::Junit));
+    let say = |line: String| {
+        if junit {
+            eprintln!("{}", line)
+        } else {
+            println!("{}", line)
+        }
+    };
+
+    let mut golden_mismatch = false;
+    if let Some(path) = against_file {
+        let path = std::path::Path::new(path);
+        if update_golden {
+            golden::update_golden(path, &result)?;
+            say(format!("Updated {} ({} item(s)).", path.display(), result.items.len()));
+        } else if let Some(diff) = golden::check_golden(path, &result)? {
+            eprintln!("TODOs differ from {}:", path.display());
+            eprint!("{}", diff);
+            eprintln!("Run with --update-golden to accept the changes.");
+            golden_mismatch = true;
+        }
+    }
 
-    if violations.is_empty() {
-        println!("All checks passed.");
+    if let Some((path, matched)) = baseline_match {
+        say(format!(
+            "Baseline {}: {} known item(s) not checked, {} fixed.",
+            path.display(),
+            matched.known,
+            matched.fixed
+        ));
+        if matched.fixed > 0 {
+            say("Run `todos baseline write` to lock in the fixes.".to_string());
+        }
+        say(String::new());
+    }
+
+    let exempt = exempt_items(&result);
+    if !exempt.is_empty() {
+        say(format!("Exemptions ({}):", exempt.len()));
+        for item in &exempt {
+            let allows: Vec<String> = item.exemptions.iter().map(|r| format!("allow:{}", r)).collect();
+            say(format!(
+                "  {}:{} [{}] {} {}",
+                item.file.display(),
+                item.line,
+                item.tag,
+                allows.join(", "),
+                item.message
+            ));
+        }
+        say(String::new());
+    }
+
+    let baseline = snapshot::load_snapshot(std::path::Path::new(&cli.path), "latest").ok();
+    let budgets = budget_report(&result, &config, baseline.as_ref().map(|(_, b)| b));
+    if budget {
+        let label = baseline.as_ref().map(|(id, _)| format!("snapshot {}", id));
+        say(text::format_budget(&budgets, label.as_deref()));
+    }
+
+    if let Some(path) = report_file {
+        let timings = if cli.deterministic {
+            CheckTimings { scan_ms: 0, check_ms: 0, total_ms: 0 }
+        } else {
+            CheckTimings {
+                scan_ms: result.metadata.scan_duration_ms,
+                check_ms,
+                total_ms: started.elapsed().as_millis() as u64,
+            }
+        };
+        let report =
+            CheckReport::new(&result, &config, violations.clone(), budgets, golden_mismatch, timings);
+        std::fs::write(path, to_json(&report, cli.json_compact)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+    }
+
+    if junit {
+        let report = JunitFormatter { violations: violations.clone() }.format(&result)?;
+        print!("{}", report);
+    }
+
+    if violations.is_empty() && !golden_mismatch {
+        say("All checks passed.".to_string());
         Ok(())
+    } else if violations.is_empty() {
+        std::process::exit(1);
     } else {
         use colored::Colorize;
         for v in &violations {
@@ -379,58 +1953,264 @@ fn run_check(
             eprintln!("[{}] {}: {}", prefix, v.rule, v.message);
         }
         eprintln!();
+        // Warnings are reported but don't fail the check
+        if !has_errors(&violations) && !golden_mismatch {
+            say(format!("All checks passed with {} warning(s).", violations.len()));
+            return Ok(());
+        }
         eprintln!("{} policy violation(s) found.", violations.len());
         std::process::exit(1);
     }
 }
 
-fn run_blame(cli: &Cli, sort: Option<String>, since: Option<String>) -> Result<()> {
-    use colored::Colorize;
+fn run_digest(cli: &Cli, by_email: bool, out_dir: Option<&str>, send: bool) -> Result<()> {
+    let vcs = open_work_tree(cli)?;
+    let root = vcs.root().to_path_buf();
+    let scanner = build_scanner(&cli.config)?;
+    let root_str = root.to_str().unwrap_or(".");
+    let discovery = build_discovery(cli, root_str);
+    let cache = open_cache(cli);
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
 
-    let path = std::path::Path::new(&cli.path);
-    if !is_git_repo(path) {
-        anyhow::bail!("Not a git repository: {}", cli.path);
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+    apply_triage(&mut result)?;
+
+    let filter = build_filter(cli);
+    apply_filter(&filter, &mut result);
+
+    let (infos, _) = blame_items_cached(&result.items, vcs.as_ref(), cache.as_ref());
+    let digests = build_digests(&result.items, &infos, by_email);
+    let markdown = matches!(cli.format.as_str(), "markdown" | "md");
+    let render = |d: &digest::AuthorDigest| {
+        if markdown {
+            digest::render_markdown(d, &root)
+        } else {
+            digest::render_text(d, &root)
+        }
+    };
+
+    if send {
+        let config = cli.config.digest.clone().unwrap_or_default();
+        let subject = config.get_subject();
+        let mut sent = 0;
+        for d in &digests {
+            let Some(ref email) = d.email else {
+                eprintln!("Skipping {}: no blame email", d.name);
+                continue;
+            };
+            digest::send_smtp(&config, email, &subject, &render(d))?;
+            sent += 1;
+        }
+        eprintln!("Sent {} digest(s)", sent);
+        return Ok(());
     }
 
-    let root = repo_root(path).map_err(|e| anyhow::anyhow!(e))?;
-    let scanner = RegexScanner::new()?;
-    let root_str = root.to_str().unwrap_or(".");
-    let discovery = FileDiscovery::new(root_str);
+    if let Some(dir) = out_dir {
+        let dir = std::path::Path::new(dir);
+        std::fs::create_dir_all(dir)?;
+        let ext = if markdown { "md" } else { "txt" };
+        for d in &digests {
+            std::fs::write(dir.join(format!("{}.{}", d.slug(), ext)), render(d))?;
+        }
+        eprintln!("Wrote {} digest(s) to {}", digests.len(), dir.display());
+        return Ok(());
+    }
+
+    for (i, d) in digests.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        print!("{}", render(d));
+    }
+    Ok(())
+}
+
+fn run_snapshot(cli: &Cli, keep: usize, list: bool, git_notes: bool) -> Result<()> {
+    let root = std::path::Path::new(&cli.path);
+    if list && git_notes {
+        let repo = repo_root(root).map_err(|e| anyhow::anyhow!(e))?;
+        for note in notes::read_notes(&repo).map_err(|e| anyhow::anyhow!(e))? {
+            let tags: Vec<String> = note
+                .by_tag
+                .iter()
+                .map(|(tag, count)| format!("{}={}", tag, count))
+                .collect();
+            println!(
+                "{} {} {} {}",
+                &note.commit[..note.commit.len().min(7)],
+                note.taken_at,
+                note.total_todos,
+                tags.join(",")
+            );
+        }
+        return Ok(());
+    }
+    if list {
+        for id in snapshot::list_snapshots(root) {
+            println!("{}", id);
+        }
+        return Ok(());
+    }
+
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
     let cache = open_cache(cli);
     let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
 
-    let mut result = orchestrator.scan_with_cache(cache.as_ref())?;
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+    apply_triage(&mut result)?;
+    enrich(&cli.config, &mut result)?;
 
     let filter = build_filter(cli);
     apply_filter(&filter, &mut result);
 
-    enrich_with_blame(&mut result.items, &root);
+    let (id, path) = snapshot::write_snapshot(root, &result)?;
+    if let Some(ref db) = cache {
+        db.record_history(&id, &result.stats)
+            .map_err(|e| anyhow::anyhow!(e))?;
+    }
+    let pruned = snapshot::prune_snapshots(root, keep.max(1))?;
+    if git_notes {
+        let repo = repo_root(root).map_err(|e| anyhow::anyhow!(e))?;
+        let summary = notes::NoteSummary::from_stats(&id, &result.stats);
+        let commit = notes::write_note(&repo, "HEAD", &summary).map_err(|e| anyhow::anyhow!(e))?;
+        eprintln!(
+            "Recorded summary on {} in {}",
+            &commit[..commit.len().min(7)],
+            notes::NOTES_REF
+        );
+    }
 
-    // Filter by --since if provided
-    if let Some(ref since_date) = since {
-        result.items.retain(|item| {
-            item.git_date
-                .as_ref()
-                .map(|d| d.as_str() >= since_date.as_str())
-                .unwrap_or(false)
+    eprintln!(
+        "Snapshot {} written to {} ({} items{})",
+        id,
+        path.display(),
+        result.stats.total_todos,
+        if pruned > 0 {
+            format!(", pruned {} old snapshot(s)", pruned)
+        } else {
+            String::new()
+        }
+    );
+    Ok(())
+}
+
+fn run_hook_commit_msg(
+    cli: &Cli,
+    file: &str,
+    mode: Option<&str>,
+    trailer: Option<&str>,
+) -> Result<()> {
+    let config = cli.config.hook.clone().unwrap_or_default();
+    let mode = CommitMsgMode::parse(mode.or(config.commit_msg.as_deref()).unwrap_or("block"))?;
+
+    let vcs = open_work_tree(cli)?;
+    let scanner = build_scanner(&cli.config)?;
+    let diff = diff_hunks(&scanner, vcs.working_parent(), None, vcs.as_ref())
+        .map_err(|e| anyhow::anyhow!(e))?;
+    let missing = hook::missing_issue(&diff.added);
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    match mode {
+        CommitMsgMode::Block => {
+            eprintln!(
+                "Commit blocked: {} new TODO(s) without an issue reference:",
+                missing.len()
+            );
+            for item in &missing {
+                eprintln!(
+                    "  {}:{} [{}] {}",
+                    item.relative_path(vcs.root()),
+                    item.line,
+                    item.tag,
+                    item.message
+                );
+            }
+            eprintln!("Add a reference such as TODO(#123) or commit with --no-verify.");
+            std::process::exit(1);
+        }
+        CommitMsgMode::Trailer => {
+            let key = trailer
+                .or(config.trailer.as_deref())
+                .unwrap_or(hook::DEFAULT_TRAILER);
+            let message = std::fs::read_to_string(file)
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file, e))?;
+            std::fs::write(file, hook::append_trailer(&message, key, missing.len()))?;
+        }
+    }
+    Ok(())
+}
+
+fn run_blame(
+    cli: &Cli,
+    sort: Option<String>,
+    dates: DateFilter,
+    commit_info: bool,
+) -> Result<()> {
+    use colored::Colorize;
+
+    // Without git, e.g. in an exported tarball, still list the TODOs
+    let vcs = match vcs::detect(std::path::Path::new(&cli.path)) {
+        Ok(vcs) => Some(ignore_blame_revs(cli, require_work_tree(vcs)?)?),
+        Err(e) => {
+            eprintln!("warning: {}; showing TODOs without blame", e);
+            eprintln!("hint: {}", vcs_hint(&e));
+            None
+        }
+    };
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
+    let cache = open_cache(cli);
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
+
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+    apply_triage(&mut result)?;
+    enrich(&cli.config, &mut result)?;
+
+    let filter = build_filter(cli);
+    apply_filter(&filter, &mut result);
+
+    if let Some(ref vcs) = vcs {
+        handle_shallow(cli, vcs.as_ref())?;
+        let hits = with_local_paths(&mut result, |result| {
+            enrich_with_blame_cached(&mut result.items, vcs.as_ref(), cache.as_ref())
         });
+        result.metadata.blame_cache_hits = cache.as_ref().map(|_| hits);
+
+        if commit_info {
+            enrich_with_commit_info(&mut result.items, vcs.as_ref());
+        }
     }
 
-    // Sort by date if requested
+    // Filter by --since/--until/--age-over if provided
+    if !dates.is_empty() {
+        let today = Date::today();
+        result
+            .items
+            .retain(|item| dates.matches(item.git_date.as_deref(), today));
+        result.recompute_stats();
+    }
+
+    // Sort by date if requested, newest first; undated items go last
     if let Some(ref sort_field) = sort {
         if sort_field == "date" {
-            result.items.sort_by(|a, b| {
-                let da = a.git_date.as_deref().unwrap_or("");
-                let db = b.git_date.as_deref().unwrap_or("");
-                db.cmp(da)
+            result.items.sort_by_key(|item| {
+                std::cmp::Reverse(item.git_date.as_deref().and_then(|d| Date::parse(d).ok()))
             });
         }
     }
 
     // JSON output
     if cli.format == "json" {
-        let json = serde_json::to_string_pretty(&result)?;
-        println!("{}", json);
+        println!("{}", to_json(&result, cli.json_compact)?);
+        return Ok(());
+    }
+
+    // Other formats carry the blame data in their own columns and fields
+    if cli.format != "text" {
+        print!("{}", format_result(cli, &result, output_format(cli)?)?);
         return Ok(());
     }
 
@@ -438,6 +2218,12 @@ fn run_blame(cli: &Cli, sort: Option<String>, since: Option<String>) -> Result<(
     for item in &result.items {
         let author = item.git_author.as_deref().unwrap_or("unknown");
         let date = item.git_date.as_deref().unwrap_or("unknown");
+        // A shallow clone's cut-off: the line is at least this old
+        let date = if item.blame_incomplete {
+            format!("<={}", date)
+        } else {
+            date.to_string()
+        };
         println!(
             "{} {} {} {}:{} [{}] {}",
             date.dimmed(),
@@ -448,6 +2234,19 @@ fn run_blame(cli: &Cli, sort: Option<String>, since: Option<String>) -> Result<(
             item.tag.to_string().yellow(),
             item.message
         );
+        if let Some(ref summary) = item.git_summary {
+            let commit = item.git_commit.as_deref().unwrap_or("");
+            let pr = item
+                .pr_number
+                .map(|n| format!(" (PR #{})", n))
+                .unwrap_or_default();
+            println!(
+                "    {} {}{}",
+                commit.get(..8).unwrap_or(commit).dimmed(),
+                summary,
+                pr.cyan()
+            );
+        }
     }
 
     if result.items.is_empty() {
@@ -459,3 +2258,744 @@ fn run_blame(cli: &Cli, sort: Option<String>, since: Option<String>) -> Result<(
 
     Ok(())
 }
+
+fn run_heatmap(cli: &Cli, depth: usize) -> Result<()> {
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
+    let cache = open_cache(cli);
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
+
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+    apply_triage(&mut result)?;
+
+    let filter = build_filter(cli);
+    apply_filter(&filter, &mut result);
+
+    let root = heatmap::build_heatmap(&result, depth);
+    match cli.format.as_str() {
+        "text" => print!("{}", heatmap::render_text(&root)),
+        "html" => print!("{}", heatmap::render_html(&root)),
+        other => anyhow::bail!("heatmap supports text and html output, not {}", other),
+    }
+
+    Ok(())
+}
+
+/// Write the items a check would see, before any baseline, as the baseline.
+fn run_baseline_write(cli: &Cli, output: Option<&str>) -> Result<()> {
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
+    let cache = open_cache(cli);
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
+
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+    apply_triage(&mut result)?;
+    let filter = build_filter(cli);
+    apply_filter(&filter, &mut result);
+
+    let path = match output {
+        Some(output) => std::path::PathBuf::from(output),
+        None => std::path::Path::new(&cli.path).join(BASELINE_FILE),
+    };
+    let baseline = Baseline::from_result(&result);
+    baseline.save(&path)?;
+    println!("Wrote {} item(s) to {}.", baseline.items.len(), path.display());
+    Ok(())
+}
+
+fn run_suppressions(cli: &Cli) -> Result<()> {
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
+    let cache = open_cache(cli);
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
+
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+    let root = result.metadata.root_path.clone();
+    let store_path = root.join(TRIAGE_FILE);
+    let store = TriageStore::load(&store_path)?;
+    let suppressed = store.apply(&mut result);
+
+    let filter = build_filter(cli);
+    apply_filter(&filter, &mut result);
+    let suppressed = filter.apply(&suppressed);
+
+    // Blame says who committed each suppression; outside a repository the
+    // list still shows what is suppressed
+    let vcs = open_work_tree(cli).ok();
+    if let Some(ref vcs) = vcs {
+        result.items.retain(|item| !item.exemptions.is_empty());
+        with_local_paths(&mut result, |result| {
+            enrich_with_blame_cached(&mut result.items, vcs.as_ref(), cache.as_ref())
+        });
+    }
+    let mut list = suppressions::collect(&result.items, &suppressed, &store, &root);
+    let store_json = std::fs::read_to_string(&store_path).unwrap_or_default();
+    let store_blame = match vcs {
+        Some(ref vcs) if !store_json.is_empty() => vcs.blame_file(&store_path).unwrap_or_default(),
+        _ => std::collections::HashMap::new(),
+    };
+    for entry in &mut list {
+        let blame = suppressions::decision_line(&store_json, &entry.fingerprint)
+            .and_then(|line| store_blame.get(&line))
+            .filter(|info| !info.commit.chars().all(|c| c == '0'));
+        if let Some(info) = blame {
+            entry.added_by = Some(info.author.clone());
+            entry.added_on = Some(info.date.clone());
+        }
+    }
+
+    if cli.format == "json" {
+        println!("{}", to_json(&list, cli.json_compact)?);
+    } else {
+        print!("{}", suppressions::render_text(&list, Date::today()));
+    }
+    Ok(())
+}
+
+fn run_report_by_pr(cli: &Cli, since: Option<Date>, until: Option<Date>) -> Result<()> {
+    use colored::Colorize;
+
+    let vcs = open_vcs(cli)?;
+    if vcs.name() != "git" {
+        anyhow::bail!("report --by-pr requires a git repository");
+    }
+    let commits = prs::landed_commits(vcs.root(), since, until).map_err(|e| anyhow::anyhow!(e))?;
+    let (deltas, errors) = prs::pr_deltas(&build_scanner(&cli.config)?, vcs.as_ref(), &commits)
+        .map_err(|e| anyhow::anyhow!(e))?;
+    for error in &errors {
+        eprintln!("{} {}: {}", "warning:".yellow(), error.file, error.message);
+    }
+
+    if cli.format == "json" {
+        println!("{}", to_json(&deltas, cli.json_compact)?);
+    } else {
+        print!("{}", prs::render_text(&deltas));
+    }
+    Ok(())
+}
+
+/// Where `todos multi` gets its list of repositories.
+enum RepoSource {
+    File(String),
+    GithubOrg(OrgOptions),
+}
+
+fn run_multi(cli: &Cli, source: RepoSource, clone_dir: Option<&str>, no_update: bool) -> Result<()> {
+    use colored::Colorize;
+
+    // Repos, the directory their paths are relative to, the default clone
+    // directory and the git settings clones need
+    let (specs, base, default_clone_dir, git_config) = match source {
+        RepoSource::File(ref repos_path) => {
+            let repos_path = std::path::Path::new(repos_path);
+            let repos = ReposFile::load(repos_path)?;
+            let base = match repos_path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => std::path::PathBuf::from("."),
+            };
+            let clone_dir = base.join(
+                repos
+                    .clone_dir
+                    .as_deref()
+                    .unwrap_or(std::path::Path::new(multi::DEFAULT_CLONE_DIR)),
+            );
+            (repos.repos, base, clone_dir, Vec::new())
+        }
+        RepoSource::GithubOrg(ref options) => {
+            let github = GitHub::from_env();
+            if !github.has_token() {
+                eprintln!(
+                    "{} GITHUB_TOKEN is not set; only public repositories of {} will be listed",
+                    "warning:".yellow(),
+                    options.org
+                );
+            }
+            let specs = github_org::list_repos(&github, options).map_err(|e| anyhow::anyhow!(e))?;
+            let clone_dir = std::path::Path::new(multi::DEFAULT_CLONE_DIR).join(&options.org);
+            (specs, std::path::PathBuf::from("."), clone_dir, github.git_config())
+        }
+    };
+    let clone_dir = clone_dir.map_or(default_clone_dir, std::path::PathBuf::from);
+    let filter = build_filter(cli);
+
+    let scan_repo = |dir: &std::path::Path| -> Result<ScanResult> {
+        let discovery = build_discovery(cli, &dir.to_string_lossy());
+        let orchestrator = ScanOrchestrator::new(Box::new(build_scanner(&cli.config)?), discovery);
+        let cache = CacheDb::open(dir).ok();
+        let mut result = orchestrator.scan_with_cache(cache.as_ref())?;
+        apply_triage(&mut result)?;
+        apply_filter(&filter, &mut result);
+        Ok(result)
+    };
+
+    let mut reports = Vec::new();
+    for spec in &specs {
+        let result = multi::checkout(spec, &base, &clone_dir, !no_update, &git_config)
+            .map_err(anyhow::Error::from)
+            .and_then(|dir| scan_repo(&dir));
+        reports.push(match result {
+            Ok(result) => RepoReport::scanned(spec, &result),
+            Err(e) => {
+                eprintln!("{} {}: {}", "warning:".yellow(), spec.name(), e);
+                RepoReport::failed(spec, e.to_string())
+            }
+        });
+    }
+    let report = MultiReport::new(reports);
+
+    match cli.format.as_str() {
+        "json" => println!("{}", to_json(&report, cli.json_compact)?),
+        "text" => print!("{}", multi::render_text(&report)),
+        other => anyhow::bail!("multi supports text and json output, not {}", other),
+    }
+    Ok(())
+}
+
+fn run_remind(
+    cli: &Cli,
+    repo: Option<&str>,
+    open_issue: bool,
+    slack_webhook: Option<&str>,
+) -> Result<()> {
+    use colored::Colorize;
+
+    let root = std::path::Path::new(&cli.path);
+    let repo = match repo {
+        Some(repo) => repo.to_string(),
+        None => github::current_repo(root).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not tell which GitHub repository the issues are in\nhint: pass --repo owner/name"
+            )
+        })?,
+    };
+    let github = GitHub::from_env();
+    if !github.has_token() && open_issue {
+        anyhow::bail!("--open-issue needs a token in GITHUB_TOKEN or GH_TOKEN");
+    }
+
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
+    let cache = open_cache(cli);
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+    apply_triage(&mut result)?;
+    apply_filter(&build_filter(cli), &mut result);
+
+    let (stale, errors) =
+        remind::find_stale(&github, &repo, &result.items, &result.metadata.root_path);
+    for (issue, error) in &errors {
+        eprintln!("{} could not look up {}: {}", "warning:".yellow(), issue, error);
+    }
+
+    match cli.format.as_str() {
+        "json" => println!("{}", to_json(&stale, cli.json_compact)?),
+        "markdown" | "md" => print!("{}", remind::render_markdown(&stale)),
+        "text" => print!("{}", remind::render_text(&repo, &stale)),
+        other => anyhow::bail!("remind supports text, markdown and json output, not {}", other),
+    }
+
+    if stale.is_empty() {
+        return Ok(());
+    }
+    if open_issue {
+        let url = remind::open_reminder_issue(&github, &repo, &stale).map_err(|e| anyhow::anyhow!(e))?;
+        eprintln!("Reminder issue: {}", url);
+    }
+    if let Some(webhook) = slack_webhook {
+        let text = format!("*{}*: {}", repo, remind::render_markdown(&stale));
+        slack::post_message(webhook, &text).map_err(|e| anyhow::anyhow!(e))?;
+        eprintln!("Posted {} stale TODO(s) to Slack", stale.len());
+    }
+    Ok(())
+}
+
+fn run_audit_branches(cli: &Cli, base: Option<&str>) -> Result<()> {
+    use colored::Colorize;
+
+    let vcs = open_vcs(cli)?;
+    if vcs.name() != "git" {
+        anyhow::bail!("audit-branches requires a git repository");
+    }
+    handle_shallow(cli, vcs.as_ref())?;
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => branches::default_base(vcs.root()).map_err(|e| anyhow::anyhow!(e))?,
+    };
+    let ahead = branches::remote_branches_ahead(vcs.root(), &base).map_err(|e| anyhow::anyhow!(e))?;
+    let scanner = build_scanner(&cli.config)?;
+    let (audits, errors) = branches::audit_branches(&scanner, vcs.as_ref(), &ahead)
+        .map_err(|e| anyhow::anyhow!(e))?;
+    for error in &errors {
+        eprintln!("{} {}: {}", "warning:".yellow(), error.file, error.message);
+    }
+
+    if cli.format == "json" {
+        println!("{}", to_json(&audits, cli.json_compact)?);
+    } else {
+        print!("{}", branches::render_text(&base, &audits));
+    }
+    Ok(())
+}
+
+fn run_trace(cli: &Cli, target: &str) -> Result<()> {
+    use colored::Colorize;
+
+    let vcs = open_work_tree(cli)?;
+    if vcs.name() != "git" {
+        anyhow::bail!("trace requires a git repository");
+    }
+    handle_shallow(cli, vcs.as_ref())?;
+
+    let scanner = build_scanner(&cli.config)?;
+    // Paths in the repository, resolved from the working directory even
+    // when they no longer exist there
+    let in_repo = |path: &std::path::Path| -> Option<std::path::PathBuf> {
+        let root = vcs.root().canonicalize().ok()?;
+        let path = match path.canonicalize() {
+            Ok(path) => path,
+            Err(_) => std::env::current_dir().ok()?.canonicalize().ok()?.join(path),
+        };
+        Some(vcs.root().join(path.strip_prefix(&root).ok()?))
+    };
+    let item = match trace::parse_location(target) {
+        Some((file, line, Some(rev))) => {
+            let path = in_repo(&file).ok_or_else(|| {
+                anyhow::anyhow!("{} is outside the repository", file.display())
+            })?;
+            trace::item_at_rev(&scanner, vcs.as_ref(), &path, line, &rev)
+                .map_err(|e| anyhow::anyhow!("Can't read {} at {}: {}", file.display(), rev, e))?
+                .ok_or_else(|| anyhow::anyhow!("No TODO at {} in {}", file.display(), rev))?
+        }
+        Some((file, _, None)) if !file.exists() => anyhow::bail!(
+            "{} isn't in the working tree\n\
+             hint: name a committed version as {}@<rev>, or trace the TODO by its fingerprint",
+            file.display(),
+            target
+        ),
+        Some((file, line, None)) => scanner
+            .scan_file(&file)?
+            .into_iter()
+            .find(|item| item.line == line)
+            .ok_or_else(|| anyhow::anyhow!("No TODO at {}", target))?,
+        None => {
+            let discovery = build_discovery(cli, &cli.path);
+            let cache = open_cache(cli);
+            let current_scanner = build_scanner(&cli.config)?;
+            let orchestrator = ScanOrchestrator::new(Box::new(current_scanner), discovery);
+            let root = std::path::Path::new(&cli.path);
+            // Triage keys of repeated TODOs add `-<n>` to the fingerprint
+            let target = target.split_once('-').map_or(target, |(fingerprint, _)| fingerprint);
+            let current = scan(cli, &orchestrator, cache.as_ref())?;
+            let current_root = current.metadata.root_path.clone();
+            let current = current
+                .items
+                .into_iter()
+                .find(|item| item.fingerprint(&current_root) == target);
+            let scan_root = in_repo(root).unwrap_or_else(|| vcs.root().to_path_buf());
+            match current {
+                Some(item) => item,
+                None => trace::find_removed(&scanner, vcs.as_ref(), target, &scan_root)?
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "No TODO has fingerprint {} in the working tree or HEAD's history\n\
+                             hint: see the fingerprint column of `todos export`",
+                            target
+                        )
+                    })?,
+            }
+        }
+    };
+
+    let (history, errors) = trace::trace(&scanner, vcs.as_ref(), &item.tag, &item.message)
+        .map_err(|e| anyhow::anyhow!(e))?;
+    for error in &errors {
+        eprintln!("{} {}: {}", "warning:".yellow(), error.file, error.message);
+    }
+
+    if cli.format == "json" {
+        println!("{}", to_json(&history, cli.json_compact)?);
+    } else {
+        print!("{}", trace::render_text(&history));
+    }
+    Ok(())
+}
+
+fn run_random(cli: &Cli, context: usize, seed: Option<u64>) -> Result<()> {
+    use colored::Colorize;
+
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
+    let cache = open_cache(cli);
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
+
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+    apply_triage(&mut result)?;
+
+    let filter = build_filter(cli);
+    apply_filter(&filter, &mut result);
+
+    if result.items.is_empty() {
+        println!("No TODOs found. Nothing to fix today.");
+        return Ok(());
+    }
+
+    let pool = result.items.len();
+    let seed = seed.unwrap_or_else(|| {
+        use std::hash::{BuildHasher, Hasher};
+        std::collections::hash_map::RandomState::new().build_hasher().finish()
+    });
+    let mut item = result.items.swap_remove((splitmix64(seed) % pool as u64) as usize);
+
+    // Blame is only worth it for the one item we show
+    if let Ok(vcs) = open_work_tree(cli) {
+        let local = local_path(&result.metadata.root_path, &item.file);
+        let reported = std::mem::replace(&mut item.file, local);
+        enrich_with_blame(std::slice::from_mut(&mut item), vcs.as_ref());
+        item.file = reported;
+    }
+
+    if cli.format == "json" {
+        println!("{}", to_json(&item, cli.json_compact)?);
+        return Ok(());
+    }
+
+    let git_line = match (&item.git_author, &item.git_date) {
+        (Some(author), Some(date)) => Some(match item.git_commit {
+            Some(ref commit) => format!(
+                "Added by {} on {} ({})",
+                author,
+                date,
+                commit.get(..8).unwrap_or(commit)
+            ),
+            None => format!("Not committed yet ({})", date),
+        }),
+        _ => None,
+    };
+    let single = ScanResult {
+        items: vec![item],
+        stats: ScanStats::new(),
+        metadata: result.metadata,
+    };
+    let formatter = TextFormatter {
+        show_summary: false,
+        max_width: text_width(cli),
+        snippets: Some(context),
+        group_by_component: false,
+    };
+    print!("{}", formatter.format(&single)?);
+    if let Some(line) = git_line {
+        println!("  {}", line.dimmed());
+    }
+    println!();
+    println!("Picked 1 of {} TODOs. Fix one today!", pool);
+
+    Ok(())
+}
+
+/// SplitMix64 step: spreads nearby seeds across the whole range.
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn run_triage(cli: &Cli, all: bool, bulk: BulkAction, write_back: bool) -> Result<()> {
+    use std::io::IsTerminal;
+
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
+    let cache = open_cache(cli);
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
+
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+
+    let filter = build_filter(cli);
+    apply_filter(&filter, &mut result);
+
+    let root = result.metadata.root_path.as_path();
+    let store_path = root.join(TRIAGE_FILE);
+    let mut store = TriageStore::load(&store_path)?;
+
+    let pending: Vec<_> = triage::keyed(&result.items, root)
+        .into_iter()
+        .filter(|(key, _)| all || store.get(key).is_none())
+        .collect();
+
+    if pending.is_empty() {
+        println!("Nothing to triage.");
+        return Ok(());
+    }
+
+    let decided = if !bulk.is_empty() {
+        triage::apply_bulk(&pending, &mut store, root, &bulk)
+    } else {
+        if !std::io::stdin().is_terminal() {
+            eprintln!("Reading triage commands from stdin (use --set-* flags for bulk triage)");
+        }
+        let stdin = std::io::stdin();
+        triage::run_interactive(&pending, &mut store, root, stdin.lock(), std::io::stdout())?
+    };
+
+    store.save(&store_path)?;
+    println!();
+    println!(
+        "Recorded {} decision(s) in {} ({} item(s) pending)",
+        decided.len(),
+        store_path.display(),
+        pending.len() - decided.len()
+    );
+
+    if write_back {
+        let local: Vec<_> = pending
+            .iter()
+            .map(|(key, item)| {
+                let file = local_path(root, &item.file);
+                (key.clone(), TodoItem { file, ..item.clone() })
+            })
+            .collect();
+        let rewritten = triage::write_back(&local, &store)?;
+        println!("Rewrote {} source line(s)", rewritten);
+    }
+
+    Ok(())
+}
+
+fn run_export(cli: &Cli, output: Option<&str>) -> Result<()> {
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
+    let cache = open_cache(cli);
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
+
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+
+    let filter = build_filter(cli);
+    apply_filter(&filter, &mut result);
+
+    let root = result.metadata.root_path.as_path();
+    let store = TriageStore::load(&root.join(TRIAGE_FILE))?;
+    let csv = triage::export_csv(&result.items, &store, root)?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, csv)?;
+            eprintln!("Exported {} item(s) to {}", result.items.len(), path);
+        }
+        None => print!("{}", csv),
+    }
+    Ok(())
+}
+
+fn run_export_issues(cli: &Cli, repo: Option<&str>, dry_run: bool) -> Result<()> {
+    let root = std::path::Path::new(&cli.path);
+    let state_path = root.join(issue_sync::ISSUES_FILE);
+    let mut state = SyncState::load(&state_path)?;
+    let repo = match repo.map(str::to_string).or_else(|| state.repo.clone()) {
+        Some(repo) => repo,
+        None => github::current_repo(root).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not tell which GitHub repository to export to\nhint: pass --repo owner/name"
+            )
+        })?,
+    };
+    if let Some(ref synced) = state.repo {
+        if *synced != repo {
+            anyhow::bail!(
+                "{} tracks issues in {}, not {}\nhint: move it aside to export to another repository",
+                state_path.display(),
+                synced,
+                repo
+            );
+        }
+    }
+    let github = GitHub::from_env();
+    if !github.has_token() && !dry_run {
+        anyhow::bail!("exporting issues needs a token in GITHUB_TOKEN or GH_TOKEN");
+    }
+
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
+    let cache = open_cache(cli);
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+    apply_triage(&mut result)?;
+    apply_filter(&build_filter(cli), &mut result);
+
+    // Without a token (a dry run) private repositories can't be listed
+    if github.has_token() {
+        let adopted = issue_sync::adopt_existing(&github, &repo, &mut state)
+            .map_err(|e| anyhow::anyhow!(e))?;
+        if adopted > 0 {
+            eprintln!("Found {} exported issue(s) missing from {}", adopted, state_path.display());
+        }
+    }
+    let mut actions = issue_sync::plan(&result.items, &result.metadata.root_path, &state);
+    if !dry_run {
+        let applied = issue_sync::apply(&github, &repo, &mut actions, &mut state);
+        // Save whatever was applied before reporting a failure, so the
+        // issues already written aren't created again next time
+        state.save(&state_path)?;
+        applied.map_err(|e| anyhow::anyhow!(e))?;
+    }
+
+    match cli.format.as_str() {
+        "json" => println!("{}", to_json(&actions, cli.json_compact)?),
+        _ => print!("{}", issue_sync::render_text(&repo, &actions, dry_run)),
+    }
+    Ok(())
+}
+
+fn run_import(cli: &Cli, file: &str, write_back: bool) -> Result<()> {
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
+    let cache = open_cache(cli);
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
+
+    let mut result = scan(cli, &orchestrator, cache.as_ref())?;
+
+    let root = result.metadata.root_path.clone();
+    let store_path = root.join(TRIAGE_FILE);
+    let mut store = TriageStore::load(&store_path)?;
+
+    let reader = std::fs::File::open(file)
+        .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", file, e))?;
+    let summary = triage::import_csv(reader, &result.items, &mut store, &root)?;
+    store.save(&store_path)?;
+
+    println!(
+        "Imported {}: {} updated, {} cleared, {} unchanged, {} unmatched",
+        file, summary.updated, summary.cleared, summary.unchanged, summary.unmatched
+    );
+
+    if write_back {
+        let rewritten = with_local_paths(&mut result, |result| {
+            triage::write_back(&triage::keyed(&result.items, &root), &store)
+        })?;
+        println!("Rewrote {} source line(s)", rewritten);
+    }
+
+    Ok(())
+}
+
+fn run_cache(cli: &Cli, command: &CacheCommand) -> Result<()> {
+    let root = std::path::Path::new(&cli.path);
+    match command {
+        CacheCommand::Warm => run_cache_warm(cli),
+        CacheCommand::Export { file } => {
+            let db = CacheDb::open(root).map_err(|e| anyhow::anyhow!(e))?;
+            archive::export(&db, std::path::Path::new(file)).map_err(|e| anyhow::anyhow!(e))
+        }
+        CacheCommand::Import { file } => {
+            archive::import(root, std::path::Path::new(file)).map_err(|e| anyhow::anyhow!(e))
+        }
+        CacheCommand::Gc { older_than } => run_cache_gc(root, older_than.as_deref()),
+    }
+}
+
+/// Remove cache namespaces of branches that no longer exist, or that weren't
+/// used for `older_than` days. The current branch's namespace and the
+/// default one are always kept.
+fn run_cache_gc(root: &std::path::Path, older_than: Option<&str>) -> Result<()> {
+    let max_age = older_than.map(parse_days).transpose()?;
+    let db = CacheDb::open(root).map_err(|e| anyhow::anyhow!(e))?;
+    let branches = branches::local_branches(root).ok();
+    let current = branches::current_branch(root).unwrap_or_default();
+    let now = unix_now();
+    let abandoned: Vec<String> = db
+        .namespaces()
+        .into_iter()
+        .filter(|(name, _)| !name.is_empty() && *name != current)
+        .filter(|(name, last_used)| {
+            let deleted = branches.as_ref().is_some_and(|b| !b.contains(name));
+            let stale = max_age.is_some_and(|days| now - last_used >= days as i64 * 86_400);
+            deleted || stale
+        })
+        .map(|(name, _)| name)
+        .collect();
+    if abandoned.is_empty() {
+        println!("No abandoned cache namespaces");
+        return Ok(());
+    }
+    db.remove_namespaces(&abandoned).map_err(|e| anyhow::anyhow!(e))?;
+    for name in &abandoned {
+        println!("Removed cache namespace '{}'", name);
+    }
+    Ok(())
+}
+
+/// Bring the cache up to date with the tree and print nothing. Meant for a
+/// CI step right after the cache is restored, so it copes with whatever the
+/// restore left behind rather than failing the build.
+fn run_cache_warm(cli: &Cli) -> Result<()> {
+    let root = std::path::Path::new(&cli.path);
+    match CacheDb::discard_if_corrupt(root) {
+        Ok(true) => eprintln!("warning: discarded a corrupt cache; rebuilding it"),
+        Ok(false) => {}
+        Err(e) => eprintln!("warning: failed to check the cache: {}", e),
+    }
+    let Some(cache) = open_cache(cli) else {
+        eprintln!("warning: cache could not be opened; nothing to warm");
+        return Ok(());
+    };
+    if !cache.is_writer() {
+        eprintln!("warning: another todos process is writing the cache; not warming it");
+        return Ok(());
+    }
+    let scanner = build_scanner(&cli.config)?;
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), build_discovery(cli, &cli.path));
+    scan(cli, &orchestrator, Some(&cache))?;
+    Ok(())
+}
+
+fn run_serve(cli: &Cli, poll_interval: u64) -> Result<()> {
+    let scanner = build_scanner(&cli.config)?;
+    let discovery = build_discovery(cli, &cli.path);
+    let cache = open_cache(cli);
+    let orchestrator = ScanOrchestrator::new(Box::new(scanner), discovery);
+    let filter = build_filter(cli);
+
+    // Every request sees the tree as of the last rescan; the cache keeps
+    // rescans of unchanged files cheap
+    let rescan = || -> todo_tracker::error::Result<ScanResult> {
+        let mut result = orchestrator.scan_with_cache(cache.as_ref())?;
+        TriageStore::load(&result.metadata.root_path.join(TRIAGE_FILE))?.apply(&mut result);
+        apply_filter(&filter, &mut result);
+        Ok(result)
+    };
+    let mut server = rpc::RpcServer::new(Box::new(rescan))?;
+    let mut hooks = cli.config.webhooks.clone().unwrap_or_default();
+    // A repository's config could send its TODOs anywhere, so its webhooks
+    // need --allow-hooks
+    if !hooks.is_empty() && !cli.config.trusted && !cli.allow_hooks {
+        let urls: Vec<&str> = hooks.iter().map(|hook| hook.url.as_str()).collect();
+        eprintln!(
+            "warning: not posting to the webhooks of the repository's .todo-tracker.toml \
+             ({}); pass --allow-hooks to use them",
+            urls.join(", ")
+        );
+        hooks.clear();
+    }
+    for hook in &hooks {
+        eprintln!("Posting TODO changes to {}", hook.url);
+    }
+    if !hooks.is_empty() {
+        // stdout carries the protocol, so delivery failures only warn on stderr
+        server = server.with_on_change(Box::new(move |previous, current| {
+            for hook in &hooks {
+                let Some(payload) = webhook::payload(hook, previous, current) else {
+                    continue;
+                };
+                if let Err(e) = webhook::post(&hook.url, &payload) {
+                    eprintln!("warning: {}", e);
+                }
+            }
+        }));
+    }
+    rpc::serve(
+        &mut server,
+        std::io::BufReader::new(std::io::stdin()),
+        std::io::stdout(),
+        std::time::Duration::from_secs(poll_interval.max(1)),
+    )?;
+    Ok(())
+}
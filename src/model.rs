@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Priority {
     Low,
     Medium,
@@ -19,6 +20,15 @@ impl Priority {
             _ => None,
         }
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Critical => "critical",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -32,6 +42,7 @@ pub enum TodoTag {
 }
 
 impl TodoTag {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_uppercase().as_str() {
             "TODO" => TodoTag::Todo,
@@ -75,29 +86,240 @@ pub struct TodoItem {
     // Git enrichment fields (Phase 3)
     pub git_author: Option<String>,
     pub git_date: Option<String>,
+    /// Commit that introduced the line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    /// Subject line of that commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_summary: Option<String>,
+    /// Pull/merge request number referenced by that commit's message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pr_number: Option<u64>,
+    /// Policy rules the comment opts out of with `allow:<rule>` metadata
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exemptions: Vec<String>,
+    /// Blame stops at a shallow clone's cut-off: the line is older than
+    /// `git_date` and `git_author` may not have written it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub blame_incomplete: bool,
+    /// Component owning the file, from the `[components]` config section
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    /// The item is in test code: a test file by path, or a `#[cfg(test)]`
+    /// module
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub in_test_code: bool,
+    /// Fields added by a `post_scan` hook, kept so JSON output carries them
+    #[serde(flatten, default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+/// Tag of disabled tests, reported when `[scan] skipped_tests` is on.
+pub const SKIPPED_TEST_TAG: &str = "SKIPPED_TEST";
+
+/// Tag of deprecated APIs, reported when `[scan] deprecations` is on.
+pub const DEPRECATED_TAG: &str = "DEPRECATED";
+
+/// Tag of Rust `unwrap`, `expect` and `panic!` call sites, reported when
+/// `[scan] panic_audit` is on.
+pub const PANIC_TAG: &str = "PANIC";
+
+impl TodoItem {
+    /// Whether the item is a disabled test rather than a TODO comment.
+    pub fn is_skipped_test(&self) -> bool {
+        self.tag.as_str() == SKIPPED_TEST_TAG
+    }
+
+    /// Whether the item is a deprecated API rather than a TODO comment.
+    pub fn is_deprecation(&self) -> bool {
+        self.tag.as_str() == DEPRECATED_TAG
+    }
+
+    /// Whether the item is a call site that can panic rather than a TODO
+    /// comment.
+    pub fn is_panic_site(&self) -> bool {
+        self.tag.as_str() == PANIC_TAG
+    }
+
+    /// Whether the item is a code marker (a disabled test, a deprecation or a
+    /// panic site) rather than a TODO comment.
+    pub fn is_code_marker(&self) -> bool {
+        self.is_skipped_test() || self.is_deprecation() || self.is_panic_site()
+    }
+
+    /// The item's file path relative to `root`, with `/` separators.
+    pub fn relative_path(&self, root: &Path) -> String {
+        let rel = self.file.strip_prefix(root).unwrap_or(&self.file);
+        rel.display().to_string().replace('\\', "/")
+    }
+
+    /// Stable identity for an item: a hash of its root-relative path, tag and
+    /// message. Line numbers are excluded so edits elsewhere in the file don't
+    /// change the identity.
+    pub fn fingerprint(&self, root: &Path) -> String {
+        let key = format!(
+            "{}\0{}\0{}",
+            self.relative_path(root),
+            self.tag.as_str(),
+            self.message.trim()
+        );
+        format!("{:016x}", fnv1a64(key.as_bytes()))
+    }
+
+    /// Whether the tag is written as in [`TodoTag::as_str`] on its line.
+    /// Doc-comment tags such as `@todo` and items whose line doesn't hold the
+    /// tag at `column` count as canonical.
+    pub fn has_canonical_case(&self) -> bool {
+        let Some(written) = self.context_line.get(self.column.saturating_sub(1)..) else {
+            return true;
+        };
+        if written.starts_with(['@', '\\']) {
+            return true;
+        }
+        let tag = self.tag.as_str();
+        match written.get(..tag.len()) {
+            Some(written) if written.eq_ignore_ascii_case(tag) => written == tag,
+            _ => true,
+        }
+    }
 }
 
+/// 64-bit FNV-1a, used for fingerprints because it is stable across Rust releases.
+pub(crate) fn fnv1a64(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Blame info for a specific line
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameInfo {
+    pub author: String,
+    pub email: String,
+    pub date: String,
+    pub commit: String,
+    /// The line blames to the boundary commit of a shallow clone, standing
+    /// in for the history that wasn't fetched
+    #[serde(default)]
+    pub incomplete: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ScanStats {
     pub files_scanned: usize,
     pub files_with_todos: usize,
     pub total_todos: usize,
+    #[serde(serialize_with = "crate::ordering::sorted_map")]
     pub by_tag: std::collections::HashMap<String, usize>,
+    /// Items whose extracted message is empty
+    #[serde(default)]
+    pub empty_messages: usize,
+    /// Items per priority level; items without a priority are not counted
+    #[serde(default, serialize_with = "crate::ordering::sorted_map")]
+    pub by_priority: std::collections::HashMap<String, usize>,
+    /// Items per component; items outside every component are not counted
+    #[serde(
+        default,
+        skip_serializing_if = "std::collections::HashMap::is_empty",
+        serialize_with = "crate::ordering::sorted_map"
+    )]
+    pub by_component: std::collections::HashMap<String, usize>,
+    /// Items whose tag isn't written in its canonical uppercase form, such
+    /// as `todo` or `Fixme`; only found with `case_insensitive` scanning.
+    /// Doc-comment tags like `@todo` are canonical.
+    #[serde(default)]
+    pub non_canonical_case: usize,
+    /// Disabled tests, found with `[scan] skipped_tests`. They are items
+    /// too, and count towards `total_todos` and `by_tag`.
+    #[serde(default)]
+    pub skipped_tests: usize,
+    /// Deprecated APIs, found with `[scan] deprecations`. Like disabled tests
+    /// they count towards `total_todos` and `by_tag`.
+    #[serde(default)]
+    pub deprecations: usize,
+    /// Rust `unwrap`, `expect` and `panic!` call sites, found with `[scan]
+    /// panic_audit`. They count towards `total_todos` and `by_tag` too.
+    #[serde(default)]
+    pub panic_sites: usize,
+    /// Items in test code; the rest are in production code
+    #[serde(default)]
+    pub in_test_code: usize,
+    /// Items that reference an issue
+    #[serde(default)]
+    pub with_issue: usize,
+    /// Items without an issue reference
+    #[serde(default)]
+    pub without_issue: usize,
+    /// Non-blank lines across all scanned files
+    #[serde(default)]
+    pub lines_of_code: usize,
+    /// Lines of code per language, by file extension
+    #[serde(default, serialize_with = "crate::ordering::sorted_map")]
+    pub lines_by_language: std::collections::HashMap<String, usize>,
+    /// Items hidden by triage ignore decisions
+    #[serde(default)]
+    pub suppressed: usize,
+    /// Lines of code per scanned file, for per-directory density. Not serialized.
+    #[serde(skip)]
+    pub lines_by_file: std::collections::HashMap<PathBuf, usize>,
 }
 
 impl ScanStats {
     pub fn new() -> Self {
-        Self {
-            files_scanned: 0,
-            files_with_todos: 0,
-            total_todos: 0,
-            by_tag: std::collections::HashMap::new(),
-        }
+        Self::default()
     }
 
     pub fn add_item(&mut self, item: &TodoItem) {
         self.total_todos += 1;
         *self.by_tag.entry(item.tag.as_str().to_string()).or_insert(0) += 1;
+        if item.message.trim().is_empty() {
+            self.empty_messages += 1;
+        }
+        if item.is_skipped_test() {
+            self.skipped_tests += 1;
+        }
+        if item.is_deprecation() {
+            self.deprecations += 1;
+        }
+        if item.is_panic_site() {
+            self.panic_sites += 1;
+        }
+        if item.in_test_code {
+            self.in_test_code += 1;
+        }
+        if !item.has_canonical_case() {
+            self.non_canonical_case += 1;
+        }
+        if let Some(ref priority) = item.priority {
+            *self.by_priority.entry(priority.as_str().to_string()).or_insert(0) += 1;
+        }
+        if let Some(ref component) = item.component {
+            *self.by_component.entry(component.clone()).or_insert(0) += 1;
+        }
+        if item.issue.is_some() {
+            self.with_issue += 1;
+        } else {
+            self.without_issue += 1;
+        }
+    }
+
+    /// Record the lines of code of one scanned file.
+    pub fn add_file_lines(&mut self, path: &Path, lines: usize) {
+        self.lines_of_code += lines;
+        let language = crate::scanner::loc::language_name(path);
+        *self.lines_by_language.entry(language.to_string()).or_insert(0) += lines;
+        self.lines_by_file.insert(path.to_path_buf(), lines);
+    }
+
+    /// TODOs per thousand lines of code, or `None` when no lines were counted.
+    pub fn todos_per_kloc(&self) -> Option<f64> {
+        if self.lines_of_code == 0 {
+            return None;
+        }
+        Some(self.total_todos as f64 * 1000.0 / self.lines_of_code as f64)
     }
 }
 
@@ -106,6 +328,9 @@ pub struct ScanMetadata {
     pub scan_duration_ms: u64,
     pub root_path: PathBuf,
     pub timestamp: String,
+    /// Files whose blame came from the cache, when blame ran with one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blame_cache_hits: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -114,3 +339,22 @@ pub struct ScanResult {
     pub stats: ScanStats,
     pub metadata: ScanMetadata,
 }
+
+impl ScanResult {
+    /// Rebuild item-derived stats after items were added, removed or edited.
+    /// `files_scanned`, the suppressed count and the line counts are preserved.
+    pub fn recompute_stats(&mut self) {
+        let previous = std::mem::take(&mut self.stats);
+        self.stats.files_scanned = previous.files_scanned;
+        self.stats.suppressed = previous.suppressed;
+        self.stats.lines_of_code = previous.lines_of_code;
+        self.stats.lines_by_language = previous.lines_by_language;
+        self.stats.lines_by_file = previous.lines_by_file;
+        let mut files_set = std::collections::HashSet::new();
+        for item in &self.items {
+            self.stats.add_item(item);
+            files_set.insert(item.file.clone());
+        }
+        self.stats.files_with_todos = files_set.len();
+    }
+}
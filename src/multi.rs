@@ -0,0 +1,342 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TodoError};
+use crate::git::utils::{git_command, git_command_with_config, is_git_repo};
+use crate::model::{ScanResult, ScanStats, TodoItem};
+
+/// Where clones are kept, relative to the repos file.
+pub const DEFAULT_CLONE_DIR: &str = ".todo-tracker/repos";
+
+/// A `repos.toml` file listing the repositories `todos multi` scans:
+///
+/// ```toml
+/// clone_dir = "/var/cache/todos"   # optional
+///
+/// [[repo]]
+/// url = "https://github.com/acme/billing.git"
+/// branch = "develop"               # optional, defaults to the remote HEAD
+///
+/// [[repo]]
+/// name = "web"
+/// path = "../web"                  # an existing checkout, scanned as is
+/// ```
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ReposFile {
+    /// Directory clones are kept in, relative to the repos file
+    pub clone_dir: Option<PathBuf>,
+    #[serde(default, rename = "repo")]
+    pub repos: Vec<RepoSpec>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RepoSpec {
+    /// Section title and clone directory name; defaults to the last
+    /// component of `url` or `path`
+    pub name: Option<String>,
+    /// Clone from here into the clone directory, and update on each run
+    pub url: Option<String>,
+    /// Scan an existing checkout instead of cloning, relative to the repos file
+    pub path: Option<PathBuf>,
+    pub branch: Option<String>,
+}
+
+impl RepoSpec {
+    pub fn name(&self) -> String {
+        if let Some(ref name) = self.name {
+            return name.clone();
+        }
+        let source = match (&self.url, &self.path) {
+            (Some(url), _) => url.as_str(),
+            (None, Some(path)) => path.to_str().unwrap_or(""),
+            (None, None) => "",
+        };
+        let source = source.trim_end_matches(['/', '\\']);
+        let last = source.rsplit(['/', '\\', ':']).next().unwrap_or(source);
+        last.strip_suffix(".git").unwrap_or(last).to_string()
+    }
+}
+
+impl ReposFile {
+    /// Load and check a repos file: every repo needs exactly one of `url` and
+    /// `path`, and names must be unique since they name the clone directories.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let file: ReposFile = toml::from_str(&contents)
+            .map_err(|e| TodoError::Config(format!("{}: {}", path.display(), e)))?;
+        let mut names = HashSet::new();
+        for repo in &file.repos {
+            let name = repo.name();
+            if repo.url.is_some() == repo.path.is_some() {
+                return Err(TodoError::Config(format!(
+                    "{}: repo '{}' needs exactly one of url and path",
+                    path.display(),
+                    name
+                )));
+            }
+            if name.is_empty() || !names.insert(name.clone()) {
+                return Err(TodoError::Config(format!(
+                    "{}: repo names must be unique and non-empty (got '{}'); set name",
+                    path.display(),
+                    name
+                )));
+            }
+        }
+        Ok(file)
+    }
+}
+
+/// Make a repo available locally and return its directory. URLs are cloned
+/// with `--depth 1` into `clone_dir`, or fetched and reset to the remote
+/// branch when the clone exists and `update` is set. Paths are used as is,
+/// relative to `base`. `git_config` is passed to the clone and fetch through
+/// the environment, which keeps credentials out of the clone's config and
+/// out of the process list.
+pub fn checkout(
+    spec: &RepoSpec,
+    base: &Path,
+    clone_dir: &Path,
+    update: bool,
+    git_config: &[(String, String)],
+) -> Result<PathBuf> {
+    let Some(ref url) = spec.url else {
+        let path = base.join(spec.path.as_deref().unwrap_or(Path::new("")));
+        if !path.is_dir() {
+            return Err(TodoError::Config(format!("{} is not a directory", path.display())));
+        }
+        return Ok(path);
+    };
+
+    let name = spec.name();
+    let dest = clone_dir.join(&name);
+    let mut args: Vec<&str> = Vec::new();
+    if !dest.exists() {
+        std::fs::create_dir_all(clone_dir)?;
+        args.extend(["clone", "--quiet", "--depth", "1"]);
+        if let Some(ref branch) = spec.branch {
+            args.extend(["--branch", branch]);
+        }
+        args.extend(["--", url, &name]);
+        git_command_with_config(&args, git_config, clone_dir)?;
+    } else if !is_git_repo(&dest) {
+        return Err(TodoError::NotARepo(dest));
+    } else if update {
+        args.extend(["fetch", "--quiet", "--depth", "1", "origin"]);
+        git_command_with_config(&args, git_config, &dest)?;
+        git_command(&["reset", "--quiet", "--hard", "@{upstream}"], &dest)?;
+    }
+    Ok(dest)
+}
+
+/// One repository's section of a multi-repo report. Item paths are relative
+/// to the repository.
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoReport {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Set when the repo couldn't be cloned, updated or scanned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub stats: ScanStats,
+    pub items: Vec<TodoItem>,
+}
+
+impl RepoReport {
+    pub fn scanned(spec: &RepoSpec, result: &ScanResult) -> Self {
+        let root = &result.metadata.root_path;
+        let mut items = result.items.clone();
+        for item in &mut items {
+            item.file = PathBuf::from(item.relative_path(root));
+        }
+        RepoReport {
+            name: spec.name(),
+            url: spec.url.clone(),
+            error: None,
+            stats: result.stats.clone(),
+            items,
+        }
+    }
+
+    pub fn failed(spec: &RepoSpec, error: String) -> Self {
+        RepoReport {
+            name: spec.name(),
+            url: spec.url.clone(),
+            error: Some(error),
+            stats: ScanStats::default(),
+            items: Vec::new(),
+        }
+    }
+}
+
+/// Totals across every repository that was scanned.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MultiTotals {
+    pub repos: usize,
+    pub failed: usize,
+    pub files_scanned: usize,
+    pub total_todos: usize,
+    pub by_tag: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MultiReport {
+    pub repos: Vec<RepoReport>,
+    pub totals: MultiTotals,
+}
+
+impl MultiReport {
+    pub fn new(repos: Vec<RepoReport>) -> Self {
+        let mut totals = MultiTotals {
+            repos: repos.len(),
+            ..Default::default()
+        };
+        for repo in &repos {
+            if repo.error.is_some() {
+                totals.failed += 1;
+            }
+            totals.files_scanned += repo.stats.files_scanned;
+            totals.total_todos += repo.stats.total_todos;
+            for (tag, count) in &repo.stats.by_tag {
+                *totals.by_tag.entry(tag.clone()).or_default() += count;
+            }
+        }
+        MultiReport { repos, totals }
+    }
+}
+
+fn tag_counts<'a>(by_tag: impl IntoIterator<Item = (&'a String, &'a usize)>) -> String {
+    let mut counts: Vec<(&String, &usize)> = by_tag.into_iter().collect();
+    counts.sort();
+    counts
+        .iter()
+        .map(|(tag, count)| format!("{} {}", count, tag))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Per-repo sections with tag counts, most TODOs first, then org totals.
+pub fn render_text(report: &MultiReport) -> String {
+    let mut out = String::new();
+    let mut repos: Vec<&RepoReport> = report.repos.iter().collect();
+    repos.sort_by_key(|r| std::cmp::Reverse(r.stats.total_todos));
+    for repo in repos {
+        match repo.error {
+            Some(ref error) => writeln!(out, "{}: failed: {}", repo.name, error).unwrap(),
+            None => {
+                writeln!(
+                    out,
+                    "{}: {} TODO(s) in {} file(s)",
+                    repo.name, repo.stats.total_todos, repo.stats.files_scanned
+                )
+                .unwrap();
+                if repo.stats.total_todos > 0 {
+                    writeln!(out, "  {}", tag_counts(&repo.stats.by_tag)).unwrap();
+                }
+            }
+        }
+    }
+    let totals = &report.totals;
+    writeln!(out).unwrap();
+    write!(
+        out,
+        "Total: {} TODO(s) across {} repositories",
+        totals.total_todos,
+        totals.repos - totals.failed
+    )
+    .unwrap();
+    if totals.failed > 0 {
+        write!(out, " ({} failed)", totals.failed).unwrap();
+    }
+    writeln!(out).unwrap();
+    if totals.total_todos > 0 {
+        writeln!(out, "  {}", tag_counts(&totals.by_tag)).unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(url: Option<&str>, path: Option<&str>) -> RepoSpec {
+        RepoSpec {
+            name: None,
+            url: url.map(str::to_string),
+            path: path.map(PathBuf::from),
+            branch: None,
+        }
+    }
+
+    #[test]
+    fn test_repo_names() {
+        assert_eq!(spec(Some("https://github.com/acme/billing.git"), None).name(), "billing");
+        assert_eq!(spec(Some("git@github.com:acme/web"), None).name(), "web");
+        assert_eq!(spec(None, Some("../services/auth/")).name(), "auth");
+    }
+
+    #[test]
+    fn test_checkout_keeps_credentials_out_of_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.git").to_string_lossy().into_owned();
+        let config = [("http.extraHeader".to_string(), "Authorization: Basic s3cret".to_string())];
+        let err = checkout(&spec(Some(&missing), None), dir.path(), dir.path(), true, &config)
+            .unwrap_err();
+        assert!(err.to_string().contains("clone"), "{}", err);
+        assert!(!err.to_string().contains("s3cret"), "{}", err);
+    }
+
+    #[test]
+    fn test_load_rejects_duplicates_and_ambiguous_repos() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("repos.toml");
+
+        std::fs::write(&file, "[[repo]]\nurl = \"a/x.git\"\n[[repo]]\npath = \"b/x\"\n").unwrap();
+        let err = ReposFile::load(&file).unwrap_err();
+        assert!(err.to_string().contains("unique"), "{}", err);
+
+        std::fs::write(&file, "[[repo]]\nurl = \"a/x.git\"\npath = \"x\"\n").unwrap();
+        let err = ReposFile::load(&file).unwrap_err();
+        assert!(err.to_string().contains("exactly one of url and path"), "{}", err);
+
+        std::fs::write(&file, "clone_dir = \"c\"\n[[repo]]\nurl = \"a/x.git\"\nbranch = \"dev\"\n").unwrap();
+        let repos = ReposFile::load(&file).unwrap();
+        assert_eq!(repos.clone_dir, Some(PathBuf::from("c")));
+        assert_eq!(repos.repos[0].branch.as_deref(), Some("dev"));
+    }
+
+    #[test]
+    fn test_report_totals_and_text() {
+        let mut stats = ScanStats {
+            files_scanned: 3,
+            total_todos: 2,
+            ..Default::default()
+        };
+        stats.by_tag.insert("TODO".to_string(), 2);
+        let ok = RepoReport {
+            name: "billing".to_string(),
+            url: None,
+            error: None,
+            stats: stats.clone(),
+            items: Vec::new(),
+        };
+        let failed = RepoReport::failed(
+            &spec(Some("https://example.com/web.git"), None),
+            "clone failed".to_string(),
+        );
+        let report = MultiReport::new(vec![ok.clone(), ok, failed]);
+        assert_eq!(report.totals.total_todos, 4);
+        assert_eq!(report.totals.failed, 1);
+        assert_eq!(report.totals.by_tag["TODO"], 4);
+
+        let text = render_text(&report);
+        assert!(text.contains("billing: 2 TODO(s) in 3 file(s)\n  2 TODO\n"));
+        assert!(text.contains("web: failed: clone failed"));
+        assert!(text.contains("Total: 4 TODO(s) across 2 repositories (1 failed)"));
+    }
+}
@@ -0,0 +1,334 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use serde::Serializer;
+
+use crate::model::{ScanMetadata, ScanResult, TodoItem};
+
+// Canonical result ordering.
+//
+// Sorting `PathBuf`s directly compares `OsStr`s, which differs by platform:
+// macOS hands back decomposed (NFD) file names while Linux and Windows keep
+// whatever bytes were written, and Windows paths use `\` separators. To get
+// byte-identical output everywhere, paths are ordered by `path_key`:
+//
+// 1. the path is converted to UTF-8 (lossily) and `\` becomes `/`;
+// 2. a leading `./` is dropped;
+// 3. Latin letters followed by a combining accent are composed (NFC), so
+//    `e` + U+0301 and `é` compare equal;
+// 4. keys are compared component by component, each by UTF-8 bytes, so
+//    `a/b` sorts before `a-b` just as directory listings do.
+//
+// Items are then ordered by line, column, tag and message, which makes the
+// order total.
+
+/// Base letter, combining mark and the precomposed character they form.
+/// Covers Latin-1 Supplement and Latin Extended-A.
+const COMPOSITIONS: &[(char, char, char)] = &[
+    ('A', '\u{0300}', '\u{00C0}'), ('A', '\u{0301}', '\u{00C1}'), ('A', '\u{0302}', '\u{00C2}'),
+    ('A', '\u{0303}', '\u{00C3}'), ('A', '\u{0304}', '\u{0100}'), ('A', '\u{0306}', '\u{0102}'),
+    ('A', '\u{0308}', '\u{00C4}'), ('A', '\u{030A}', '\u{00C5}'), ('A', '\u{0328}', '\u{0104}'),
+    ('C', '\u{0301}', '\u{0106}'), ('C', '\u{0302}', '\u{0108}'), ('C', '\u{0307}', '\u{010A}'),
+    ('C', '\u{030C}', '\u{010C}'), ('C', '\u{0327}', '\u{00C7}'), ('D', '\u{030C}', '\u{010E}'),
+    ('E', '\u{0300}', '\u{00C8}'), ('E', '\u{0301}', '\u{00C9}'), ('E', '\u{0302}', '\u{00CA}'),
+    ('E', '\u{0304}', '\u{0112}'), ('E', '\u{0306}', '\u{0114}'), ('E', '\u{0307}', '\u{0116}'),
+    ('E', '\u{0308}', '\u{00CB}'), ('E', '\u{030C}', '\u{011A}'), ('E', '\u{0328}', '\u{0118}'),
+    ('G', '\u{0302}', '\u{011C}'), ('G', '\u{0306}', '\u{011E}'), ('G', '\u{0307}', '\u{0120}'),
+    ('G', '\u{0327}', '\u{0122}'), ('H', '\u{0302}', '\u{0124}'), ('I', '\u{0300}', '\u{00CC}'),
+    ('I', '\u{0301}', '\u{00CD}'), ('I', '\u{0302}', '\u{00CE}'), ('I', '\u{0303}', '\u{0128}'),
+    ('I', '\u{0304}', '\u{012A}'), ('I', '\u{0306}', '\u{012C}'), ('I', '\u{0307}', '\u{0130}'),
+    ('I', '\u{0308}', '\u{00CF}'), ('I', '\u{0328}', '\u{012E}'), ('J', '\u{0302}', '\u{0134}'),
+    ('K', '\u{0327}', '\u{0136}'), ('L', '\u{0301}', '\u{0139}'), ('L', '\u{030C}', '\u{013D}'),
+    ('L', '\u{0327}', '\u{013B}'), ('N', '\u{0301}', '\u{0143}'), ('N', '\u{0303}', '\u{00D1}'),
+    ('N', '\u{030C}', '\u{0147}'), ('N', '\u{0327}', '\u{0145}'), ('O', '\u{0300}', '\u{00D2}'),
+    ('O', '\u{0301}', '\u{00D3}'), ('O', '\u{0302}', '\u{00D4}'), ('O', '\u{0303}', '\u{00D5}'),
+    ('O', '\u{0304}', '\u{014C}'), ('O', '\u{0306}', '\u{014E}'), ('O', '\u{0308}', '\u{00D6}'),
+    ('O', '\u{030B}', '\u{0150}'), ('R', '\u{0301}', '\u{0154}'), ('R', '\u{030C}', '\u{0158}'),
+    ('R', '\u{0327}', '\u{0156}'), ('S', '\u{0301}', '\u{015A}'), ('S', '\u{0302}', '\u{015C}'),
+    ('S', '\u{030C}', '\u{0160}'), ('S', '\u{0327}', '\u{015E}'), ('T', '\u{030C}', '\u{0164}'),
+    ('T', '\u{0327}', '\u{0162}'), ('U', '\u{0300}', '\u{00D9}'), ('U', '\u{0301}', '\u{00DA}'),
+    ('U', '\u{0302}', '\u{00DB}'), ('U', '\u{0303}', '\u{0168}'), ('U', '\u{0304}', '\u{016A}'),
+    ('U', '\u{0306}', '\u{016C}'), ('U', '\u{0308}', '\u{00DC}'), ('U', '\u{030A}', '\u{016E}'),
+    ('U', '\u{030B}', '\u{0170}'), ('U', '\u{0328}', '\u{0172}'), ('W', '\u{0302}', '\u{0174}'),
+    ('Y', '\u{0301}', '\u{00DD}'), ('Y', '\u{0302}', '\u{0176}'), ('Y', '\u{0308}', '\u{0178}'),
+    ('Z', '\u{0301}', '\u{0179}'), ('Z', '\u{0307}', '\u{017B}'), ('Z', '\u{030C}', '\u{017D}'),
+    ('a', '\u{0300}', '\u{00E0}'), ('a', '\u{0301}', '\u{00E1}'), ('a', '\u{0302}', '\u{00E2}'),
+    ('a', '\u{0303}', '\u{00E3}'), ('a', '\u{0304}', '\u{0101}'), ('a', '\u{0306}', '\u{0103}'),
+    ('a', '\u{0308}', '\u{00E4}'), ('a', '\u{030A}', '\u{00E5}'), ('a', '\u{0328}', '\u{0105}'),
+    ('c', '\u{0301}', '\u{0107}'), ('c', '\u{0302}', '\u{0109}'), ('c', '\u{0307}', '\u{010B}'),
+    ('c', '\u{030C}', '\u{010D}'), ('c', '\u{0327}', '\u{00E7}'), ('d', '\u{030C}', '\u{010F}'),
+    ('e', '\u{0300}', '\u{00E8}'), ('e', '\u{0301}', '\u{00E9}'), ('e', '\u{0302}', '\u{00EA}'),
+    ('e', '\u{0304}', '\u{0113}'), ('e', '\u{0306}', '\u{0115}'), ('e', '\u{0307}', '\u{0117}'),
+    ('e', '\u{0308}', '\u{00EB}'), ('e', '\u{030C}', '\u{011B}'), ('e', '\u{0328}', '\u{0119}'),
+    ('g', '\u{0302}', '\u{011D}'), ('g', '\u{0306}', '\u{011F}'), ('g', '\u{0307}', '\u{0121}'),
+    ('g', '\u{0327}', '\u{0123}'), ('h', '\u{0302}', '\u{0125}'), ('i', '\u{0300}', '\u{00EC}'),
+    ('i', '\u{0301}', '\u{00ED}'), ('i', '\u{0302}', '\u{00EE}'), ('i', '\u{0303}', '\u{0129}'),
+    ('i', '\u{0304}', '\u{012B}'), ('i', '\u{0306}', '\u{012D}'), ('i', '\u{0308}', '\u{00EF}'),
+    ('i', '\u{0328}', '\u{012F}'), ('j', '\u{0302}', '\u{0135}'), ('k', '\u{0327}', '\u{0137}'),
+    ('l', '\u{0301}', '\u{013A}'), ('l', '\u{030C}', '\u{013E}'), ('l', '\u{0327}', '\u{013C}'),
+    ('n', '\u{0301}', '\u{0144}'), ('n', '\u{0303}', '\u{00F1}'), ('n', '\u{030C}', '\u{0148}'),
+    ('n', '\u{0327}', '\u{0146}'), ('o', '\u{0300}', '\u{00F2}'), ('o', '\u{0301}', '\u{00F3}'),
+    ('o', '\u{0302}', '\u{00F4}'), ('o', '\u{0303}', '\u{00F5}'), ('o', '\u{0304}', '\u{014D}'),
+    ('o', '\u{0306}', '\u{014F}'), ('o', '\u{0308}', '\u{00F6}'), ('o', '\u{030B}', '\u{0151}'),
+    ('r', '\u{0301}', '\u{0155}'), ('r', '\u{030C}', '\u{0159}'), ('r', '\u{0327}', '\u{0157}'),
+    ('s', '\u{0301}', '\u{015B}'), ('s', '\u{0302}', '\u{015D}'), ('s', '\u{030C}', '\u{0161}'),
+    ('s', '\u{0327}', '\u{015F}'), ('t', '\u{030C}', '\u{0165}'), ('t', '\u{0327}', '\u{0163}'),
+    ('u', '\u{0300}', '\u{00F9}'), ('u', '\u{0301}', '\u{00FA}'), ('u', '\u{0302}', '\u{00FB}'),
+    ('u', '\u{0303}', '\u{0169}'), ('u', '\u{0304}', '\u{016B}'), ('u', '\u{0306}', '\u{016D}'),
+    ('u', '\u{0308}', '\u{00FC}'), ('u', '\u{030A}', '\u{016F}'), ('u', '\u{030B}', '\u{0171}'),
+    ('u', '\u{0328}', '\u{0173}'), ('w', '\u{0302}', '\u{0175}'), ('y', '\u{0301}', '\u{00FD}'),
+    ('y', '\u{0302}', '\u{0177}'), ('y', '\u{0308}', '\u{00FF}'), ('z', '\u{0301}', '\u{017A}'),
+    ('z', '\u{0307}', '\u{017C}'), ('z', '\u{030C}', '\u{017E}'),
+];
+
+fn compose(base: char, mark: char) -> Option<char> {
+    COMPOSITIONS
+        .binary_search_by(|&(b, m, _)| (b, m).cmp(&(base, mark)))
+        .ok()
+        .map(|i| COMPOSITIONS[i].2)
+}
+
+/// Normalized, platform-independent form of a path used for ordering.
+pub fn path_key(path: &Path) -> String {
+    let raw = path.to_string_lossy().replace('\\', "/");
+    let raw = raw.strip_prefix("./").unwrap_or(&raw);
+    let mut out = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        if let Some(composed) = out.chars().last().and_then(|prev| compose(prev, c)) {
+            out.pop();
+            out.push(composed);
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn compare_keys(a: &str, b: &str) -> Ordering {
+    a.split('/').cmp(b.split('/'))
+}
+
+/// Compare two paths in canonical order.
+pub fn compare_paths(a: &Path, b: &Path) -> Ordering {
+    compare_keys(&path_key(a), &path_key(b))
+}
+
+/// Sort key giving the order of [`compare_paths`], for sorting many paths
+/// with `sort_by_cached_key` instead of normalizing them on every comparison.
+pub fn path_sort_key(path: &Path) -> Vec<String> {
+    path_key(path).split('/').map(str::to_string).collect()
+}
+
+/// Compare two items in canonical order: path, line, column, tag, message.
+pub fn compare_items(a: &TodoItem, b: &TodoItem) -> Ordering {
+    compare_paths(&a.file, &b.file)
+        .then(a.line.cmp(&b.line))
+        .then(a.column.cmp(&b.column))
+        .then_with(|| a.tag.as_str().cmp(b.tag.as_str()))
+        .then_with(|| a.message.cmp(&b.message))
+}
+
+/// Sort items into canonical order.
+pub fn sort_items(items: &mut [TodoItem]) {
+    items.sort_by_cached_key(|item| {
+        (
+            path_sort_key(&item.file),
+            item.line,
+            item.column,
+            item.tag.as_str().to_string(),
+            item.message.clone(),
+        )
+    });
+}
+
+/// Group items by file in canonical path order, keeping each group's items in
+/// their incoming order. Returns the display path with each group.
+pub fn group_by_file(items: &[TodoItem]) -> Vec<(String, Vec<&TodoItem>)> {
+    let mut groups: Vec<(String, String, Vec<&TodoItem>)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for item in items {
+        let key = path_key(&item.file);
+        match index.get(&key) {
+            Some(&i) => groups[i].2.push(item),
+            None => {
+                index.insert(key.clone(), groups.len());
+                groups.push((key, item.file.display().to_string(), vec![item]));
+            }
+        }
+    }
+    groups.sort_by(|a, b| compare_keys(&a.0, &b.0));
+    groups
+        .into_iter()
+        .map(|(_, display, items)| (display, items))
+        .collect()
+}
+
+/// Strip everything from `result` that differs between runs over the same
+/// files, for `--deterministic`: item paths become root-relative keys as in
+/// [`path_key`], the root becomes `.`, durations and cache counts are
+/// cleared, `timestamp` replaces the scan time and items are put in
+/// canonical order.
+pub fn make_deterministic(result: &mut ScanResult, timestamp: &str) {
+    let root = result.metadata.root_path.clone();
+    make_metadata_deterministic(&mut result.metadata, timestamp);
+    for item in &mut result.items {
+        item.file = PathBuf::from(path_key(Path::new(&item.relative_path(&root))));
+    }
+    result.stats.lines_by_file = std::mem::take(&mut result.stats.lines_by_file)
+        .into_iter()
+        .map(|(path, lines)| {
+            let relative = path.strip_prefix(&root).unwrap_or(&path);
+            (PathBuf::from(path_key(relative)), lines)
+        })
+        .collect();
+    sort_items(&mut result.items);
+}
+
+/// The metadata part of [`make_deterministic`], for output written without
+/// a whole result.
+pub fn make_metadata_deterministic(metadata: &mut ScanMetadata, timestamp: &str) {
+    metadata.root_path = PathBuf::from(".");
+    metadata.scan_duration_ms = 0;
+    metadata.timestamp = timestamp.to_string();
+    metadata.blame_cache_hits = None;
+}
+
+/// Serialize a map with its keys sorted, for maps stored as `HashMap`.
+pub fn sorted_map<S: Serializer, V: serde::Serialize>(
+    map: &HashMap<String, V>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_map(map.iter().collect::<BTreeMap<_, _>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TodoTag;
+    use std::path::PathBuf;
+
+    fn make_item(file: &str, line: usize, message: &str) -> TodoItem {
+        TodoItem {
+            tag: TodoTag::Todo,
+            message: message.to_string(),
+            file: PathBuf::from(file),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_table_is_sorted() {
+        assert!(COMPOSITIONS
+            .windows(2)
+            .all(|w| (w[0].0, w[0].1) < (w[1].0, w[1].1)));
+    }
+
+    #[test]
+    fn test_path_key_normalizes() {
+        assert_eq!(path_key(Path::new("./src\\caf\u{0065}\u{0301}.rs")), "src/caf\u{00E9}.rs");
+        assert_eq!(path_key(Path::new("src/caf\u{00E9}.rs")), "src/caf\u{00E9}.rs");
+        assert_eq!(path_key(Path::new("n\u{0303}o")), "\u{00F1}o");
+    }
+
+    #[test]
+    fn test_compare_is_component_wise() {
+        assert_eq!(compare_paths(Path::new("a/b"), Path::new("a-b")), Ordering::Less);
+        assert_eq!(compare_paths(Path::new("a/z"), Path::new("a.rs")), Ordering::Less);
+        assert_eq!(
+            compare_paths(Path::new("./x/e\u{0301}"), Path::new("x/\u{00E9}")),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn test_sort_items_total_order() {
+        let mut items = vec![
+            make_item("a-b/x.rs", 1, "dash"),
+            make_item("a/b.rs", 2, "second"),
+            make_item("./a/b.rs", 2, "first"),
+            make_item("a/b.rs", 1, "top"),
+        ];
+        sort_items(&mut items);
+        let messages: Vec<&str> = items.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(messages, vec!["top", "first", "second", "dash"]);
+        assert!(items.windows(2).all(|w| compare_items(&w[0], &w[1]) != Ordering::Greater));
+    }
+
+    #[test]
+    fn test_group_by_file_merges_equivalent_paths() {
+        let items = vec![
+            make_item("b.rs", 1, "b"),
+            make_item("caf\u{00E9}.rs", 1, "composed"),
+            make_item("cafe\u{0301}.rs", 2, "decomposed"),
+            make_item("a/z.rs", 1, "a"),
+        ];
+        let groups = group_by_file(&items);
+        let paths: Vec<&str> = groups.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, vec!["a/z.rs", "b.rs", "caf\u{00E9}.rs"]);
+        assert_eq!(groups[2].1.len(), 2);
+    }
+
+    #[test]
+    fn test_make_deterministic() {
+        use crate::model::{ScanMetadata, ScanStats};
+
+        let run = |root: &str, duration| {
+            let items = vec![
+                make_item(&format!("{}/src/b.rs", root), 1, "b"),
+                make_item(&format!("{}/src/cafe\u{0301}.rs", root), 3, "cafe"),
+            ];
+            let mut result = ScanResult {
+                items,
+                stats: ScanStats::default(),
+                metadata: ScanMetadata {
+                    scan_duration_ms: duration,
+                    root_path: PathBuf::from(root),
+                    timestamp: format!("run at {}", duration),
+                    blame_cache_hits: Some(duration as usize),
+                },
+            };
+            result.items.reverse();
+            make_deterministic(&mut result, "1970-01-01T00:00:00Z");
+            serde_json::to_string(&result).unwrap()
+        };
+        let first = run("/ci/build-1", 25);
+        assert_eq!(first, run("/ci/build-2", 40));
+        assert!(first.contains(r#""file":"src/b.rs""#), "{}", first);
+        assert!(first.contains("\"file\":\"src/caf\u{00E9}.rs\""), "{}", first);
+        let metadata =
+            r#"{"scan_duration_ms":0,"root_path":".","timestamp":"1970-01-01T00:00:00Z"}"#;
+        assert!(first.ends_with(&format!(r#""metadata":{}}}"#, metadata)), "{}", first);
+    }
+
+    #[test]
+    fn test_sorted_map_serializes_in_key_order() {
+        #[derive(serde::Serialize)]
+        struct Wrapper {
+            #[serde(serialize_with = "sorted_map")]
+            map: HashMap<String, usize>,
+        }
+        let map: HashMap<String, usize> = ["XXX", "BUG", "TODO", "FIXME", "HACK"]
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (k.to_string(), i))
+            .collect();
+        let json = serde_json::to_string(&Wrapper { map }).unwrap();
+        assert_eq!(json, r#"{"map":{"BUG":1,"FIXME":3,"HACK":4,"TODO":2,"XXX":0}}"#);
+    }
+}
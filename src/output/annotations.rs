@@ -0,0 +1,32 @@
+/// Severity of an annotation in SARIF or GitHub Actions output, lowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum AnnotationLevel {
+    #[default]
+    Note,
+    Warning,
+    Error,
+}
+
+impl AnnotationLevel {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> std::result::Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "note" | "notice" => Ok(AnnotationLevel::Note),
+            "warning" => Ok(AnnotationLevel::Warning),
+            "error" => Ok(AnnotationLevel::Error),
+            other => Err(format!(
+                "Unknown annotation level '{}' (expected note, warning or error)",
+                other
+            )),
+        }
+    }
+
+    /// The SARIF `level` value.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AnnotationLevel::Note => "note",
+            AnnotationLevel::Warning => "warning",
+            AnnotationLevel::Error => "error",
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt::Write;
+
+use crate::error::Result;
+use crate::model::ScanResult;
+use crate::output::OutputFormatter;
+
+/// Azure Pipelines `task.logissue` logging commands, which show each item as
+/// a warning or error on the run's summary and in its log.
+pub struct AzurePipelinesFormatter;
+
+/// Escape a logging command property value.
+fn escape_property(value: &str) -> String {
+    escape_message(value).replace(';', "%3B").replace(']', "%5D")
+}
+
+/// Escape a logging command message.
+fn escape_message(value: &str) -> String {
+    value
+        .replace('%', "%AZP25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+impl OutputFormatter for AzurePipelinesFormatter {
+    fn format(&self, result: &ScanResult) -> Result<String> {
+        let root = &result.metadata.root_path;
+        let mut out = String::new();
+        for item in &result.items {
+            let kind = match item.tag.as_str() {
+                "FIXME" | "BUG" => "error",
+                _ => "warning",
+            };
+            writeln!(
+                out,
+                "##vso[task.logissue type={};sourcepath={};linenumber={};columnnumber={};code={}]{}: {}",
+                kind,
+                escape_property(&item.relative_path(root)),
+                item.line,
+                item.column,
+                escape_property(item.tag.as_str()),
+                item.tag,
+                escape_message(&item.message)
+            )
+            .unwrap();
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats, TodoItem, TodoTag};
+    use std::path::PathBuf;
+
+    fn make_item(tag: TodoTag, message: &str, file: &str, line: usize) -> TodoItem {
+        TodoItem {
+            tag,
+            message: message.to_string(),
+            file: PathBuf::from(file),
+            line,
+            column: 4,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_logissue_commands() {
+        let result = ScanResult {
+            items: vec![
+                make_item(TodoTag::Todo, "raise to 100%", "./src/a.rs", 3),
+                make_item(TodoTag::Bug, "off by one", "./src/b;c.rs", 9),
+            ],
+            stats: ScanStats::default(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("."),
+                timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
+            },
+        };
+        let output = AzurePipelinesFormatter.format(&result).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "##vso[task.logissue type=warning;sourcepath=src/a.rs;linenumber=3;\
+                 columnnumber=4;code=TODO]TODO: raise to 100%AZP25",
+                "##vso[task.logissue type=error;sourcepath=src/b%3Bc.rs;linenumber=9;\
+                 columnnumber=4;code=BUG]BUG: off by one",
+            ]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use serde_json::{json, Value};
+
+use crate::error::Result;
+use crate::model::{Priority, ScanResult, TodoItem, TodoTag};
+use crate::output::OutputFormatter;
+
+/// Bitbucket accepts at most this many annotations per report.
+const MAX_ANNOTATIONS: usize = 1000;
+
+/// Code Insights report plus annotations, as one JSON document:
+/// `{"report": {...}, "annotations": [...]}`. CI uploads `report` with
+/// `PUT .../reports/todo-tracker` and `annotations` with
+/// `POST .../reports/todo-tracker/annotations`. Bitbucket Cloud uses
+/// snake_case fields; `server` switches to the Bitbucket Server/Data Center shape.
+pub struct BitbucketFormatter {
+    pub server: bool,
+}
+
+fn severity(item: &TodoItem) -> &'static str {
+    match item.priority {
+        Some(Priority::Critical) => "CRITICAL",
+        Some(Priority::High) => "HIGH",
+        Some(Priority::Medium) => "MEDIUM",
+        Some(Priority::Low) => "LOW",
+        None => match item.tag {
+            TodoTag::Fixme | TodoTag::Bug => "HIGH",
+            TodoTag::Hack | TodoTag::Xxx => "MEDIUM",
+            _ => "LOW",
+        },
+    }
+}
+
+fn annotation_type(item: &TodoItem) -> &'static str {
+    match item.tag {
+        TodoTag::Bug => "BUG",
+        _ => "CODE_SMELL",
+    }
+}
+
+impl OutputFormatter for BitbucketFormatter {
+    fn format(&self, result: &ScanResult) -> Result<String> {
+        let root = &result.metadata.root_path;
+        let total = result.items.len();
+        let blocking = result
+            .items
+            .iter()
+            .filter(|item| matches!(severity(item), "HIGH" | "CRITICAL"))
+            .count();
+
+        let mut details = format!(
+            "{} TODO comment(s) in {} file(s).",
+            total, result.stats.files_with_todos
+        );
+        if total > MAX_ANNOTATIONS {
+            details.push_str(&format!(" Showing the first {}.", MAX_ANNOTATIONS));
+        }
+
+        let annotations: Vec<Value> = result
+            .items
+            .iter()
+            .take(MAX_ANNOTATIONS)
+            .map(|item| {
+                let summary = format!("{}: {}", item.tag, item.message);
+                let path = item.relative_path(root);
+                if self.server {
+                    json!({
+                        "externalId": item.fingerprint(root),
+                        "path": path,
+                        "line": item.line,
+                        "message": summary,
+                        // Server has no CRITICAL severity
+                        "severity": match severity(item) {
+                            "CRITICAL" => "HIGH",
+                            s => s,
+                        },
+                        "type": annotation_type(item),
+                    })
+                } else {
+                    json!({
+                        "external_id": item.fingerprint(root),
+                        "path": path,
+                        "line": item.line,
+                        "summary": summary,
+                        "annotation_type": annotation_type(item),
+                        "severity": severity(item),
+                    })
+                }
+            })
+            .collect();
+
+        let outcome = if blocking > 0 { "FAILED" } else { "PASSED" };
+        let data = json!([
+            {"title": "TODOs", "type": "NUMBER", "value": total},
+            {"title": "High severity", "type": "NUMBER", "value": blocking},
+        ]);
+        let report = if self.server {
+            json!({
+                "title": "TODO tracker",
+                "details": details,
+                "result": outcome,
+                "reporter": "todo-tracker",
+                "data": data,
+            })
+        } else {
+            json!({
+                "title": "TODO tracker",
+                "details": details,
+                "report_type": "CODE_SMELL",
+                "result": outcome,
+                "reporter": "todo-tracker",
+                "data": data,
+            })
+        };
+
+        let doc = json!({
+            "report": report,
+            "annotations": annotations,
+        });
+        serde_json::to_string_pretty(&doc)
+            .map_err(|e| crate::error::TodoError::Config(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats};
+    use std::path::PathBuf;
+
+    fn make_item(tag: TodoTag, message: &str, file: &str, line: usize) -> TodoItem {
+        TodoItem {
+            tag,
+            message: message.to_string(),
+            file: PathBuf::from(file),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    fn make_result(items: Vec<TodoItem>) -> ScanResult {
+        ScanResult {
+            stats: ScanStats {
+                total_todos: items.len(),
+                files_with_todos: 1,
+                ..Default::default()
+            },
+            items,
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("."),
+                timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
+            },
+        }
+    }
+
+    fn render(result: &ScanResult, server: bool) -> Value {
+        let output = BitbucketFormatter { server }.format(result).unwrap();
+        serde_json::from_str(&output).unwrap()
+    }
+
+    #[test]
+    fn test_cloud_report_and_annotations() {
+        let result = make_result(vec![
+            make_item(TodoTag::Todo, "later", "./src/a.rs", 3),
+            make_item(TodoTag::Bug, "crash", "./src/b.rs", 9),
+        ]);
+        let doc = render(&result, false);
+        assert_eq!(doc["report"]["report_type"], "CODE_SMELL");
+        assert_eq!(doc["report"]["result"], "FAILED");
+        assert_eq!(doc["report"]["data"][0]["value"], 2);
+
+        let annotations = doc["annotations"].as_array().unwrap();
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0]["path"], "src/a.rs");
+        assert_eq!(annotations[0]["severity"], "LOW");
+        assert_eq!(annotations[0]["summary"], "TODO: later");
+        assert_eq!(annotations[1]["annotation_type"], "BUG");
+        assert_eq!(annotations[1]["external_id"].as_str().unwrap().len(), 16);
+    }
+
+    #[test]
+    fn test_server_shape_and_passed_result() {
+        let mut item = make_item(TodoTag::Todo, "tune", "src/a.rs", 1);
+        item.priority = Some(Priority::Critical);
+        let doc = render(&make_result(vec![item]), true);
+        assert!(doc["report"].get("report_type").is_none());
+        let annotation = &doc["annotations"][0];
+        assert_eq!(annotation["message"], "TODO: tune");
+        assert_eq!(annotation["severity"], "HIGH");
+        assert!(annotation.get("externalId").is_some());
+
+        let doc = render(&make_result(vec![make_item(TodoTag::Todo, "fyi", "a.rs", 1)]), true);
+        assert_eq!(doc["report"]["result"], "PASSED");
+    }
+
+    #[test]
+    fn test_annotations_capped() {
+        let items = (1..=MAX_ANNOTATIONS + 5)
+            .map(|n| make_item(TodoTag::Todo, "x", "a.rs", n))
+            .collect();
+        let doc = render(&make_result(items), false);
+        assert_eq!(doc["annotations"].as_array().unwrap().len(), MAX_ANNOTATIONS);
+        assert!(doc["report"]["details"].as_str().unwrap().contains("first 1000"));
+    }
+}
@@ -0,0 +1,68 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Restrict text output and progress bars to ASCII for the rest of the process.
+pub fn set_ascii(ascii: bool) {
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+pub fn is_ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// Whether the terminal is unlikely to render box-drawing and block
+/// characters: `TERM=dumb`, a locale that is set but not UTF-8, or a Windows
+/// console other than Windows Terminal. `locale` is the first non-empty of
+/// `LC_ALL`, `LC_CTYPE` and `LANG`; an unset locale is assumed to be UTF-8.
+pub fn detect_ascii(term: Option<&str>, locale: Option<&str>, windows_terminal: bool) -> bool {
+    if term == Some("dumb") {
+        return true;
+    }
+    if cfg!(windows) {
+        return !windows_terminal;
+    }
+    match locale {
+        Some(locale) => {
+            let locale = locale.to_lowercase();
+            !(locale.contains("utf-8") || locale.contains("utf8"))
+        }
+        None => false,
+    }
+}
+
+/// `detect_ascii` applied to the process environment.
+pub fn detect_ascii_from_env() -> bool {
+    let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let locale = var("LC_ALL").or_else(|| var("LC_CTYPE")).or_else(|| var("LANG"));
+    let windows_terminal = var("WT_SESSION").is_some() || var("TERM_PROGRAM").is_some();
+    detect_ascii(var("TERM").as_deref(), locale.as_deref(), windows_terminal)
+}
+
+/// A horizontal bar of `len` cells for charts.
+pub fn bar(len: usize) -> String {
+    let cell = if is_ascii() { "#" } else { "\u{2588}" };
+    cell.repeat(len)
+}
+
+/// A horizontal rule of `len` cells.
+pub fn rule(len: usize) -> String {
+    let cell = if is_ascii() { "-" } else { "\u{2500}" };
+    cell.repeat(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_ascii() {
+        assert!(detect_ascii(Some("dumb"), Some("en_US.UTF-8"), true));
+        if !cfg!(windows) {
+            assert!(!detect_ascii(Some("xterm"), Some("en_US.UTF-8"), false));
+            assert!(!detect_ascii(Some("xterm"), Some("C.utf8"), false));
+            assert!(detect_ascii(Some("xterm"), Some("C"), false));
+            assert!(!detect_ascii(None, None, false));
+        }
+    }
+}
@@ -1,49 +1,236 @@
 use ::csv as csv_crate;
 
-use crate::error::Result;
-use crate::model::ScanResult;
+use crate::date::Date;
+use crate::error::{Result, TodoError};
+use crate::model::{ScanResult, TodoItem};
 use crate::output::OutputFormatter;
 
-pub struct CsvFormatter;
+/// A column of CSV output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvColumn {
+    File,
+    Line,
+    Column,
+    Tag,
+    Message,
+    Author,
+    Issue,
+    Priority,
+    ContextLine,
+    GitAuthor,
+    GitDate,
+    GitCommit,
+    /// Days since the line was last changed, from blame
+    Age,
+}
+
+impl CsvColumn {
+    pub const ALL: [CsvColumn; 13] = [
+        CsvColumn::File,
+        CsvColumn::Line,
+        CsvColumn::Column,
+        CsvColumn::Tag,
+        CsvColumn::Message,
+        CsvColumn::Author,
+        CsvColumn::Issue,
+        CsvColumn::Priority,
+        CsvColumn::ContextLine,
+        CsvColumn::GitAuthor,
+        CsvColumn::GitDate,
+        CsvColumn::GitCommit,
+        CsvColumn::Age,
+    ];
+
+    /// The columns written when none are selected.
+    pub const DEFAULT: [CsvColumn; 8] = [
+        CsvColumn::File,
+        CsvColumn::Line,
+        CsvColumn::Column,
+        CsvColumn::Tag,
+        CsvColumn::Message,
+        CsvColumn::Author,
+        CsvColumn::Issue,
+        CsvColumn::Priority,
+    ];
+
+    /// Blame columns added to the default layout when blame data is present.
+    pub const GIT: [CsvColumn; 3] = [CsvColumn::GitAuthor, CsvColumn::GitDate, CsvColumn::GitCommit];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CsvColumn::File => "file",
+            CsvColumn::Line => "line",
+            CsvColumn::Column => "column",
+            CsvColumn::Tag => "tag",
+            CsvColumn::Message => "message",
+            CsvColumn::Author => "author",
+            CsvColumn::Issue => "issue",
+            CsvColumn::Priority => "priority",
+            CsvColumn::ContextLine => "context_line",
+            CsvColumn::GitAuthor => "git_author",
+            CsvColumn::GitDate => "git_date",
+            CsvColumn::GitCommit => "git_commit",
+            CsvColumn::Age => "age",
+        }
+    }
+
+    /// Whether the column is filled from git blame.
+    pub fn needs_blame(&self) -> bool {
+        matches!(
+            self,
+            CsvColumn::GitAuthor | CsvColumn::GitDate | CsvColumn::GitCommit | CsvColumn::Age
+        )
+    }
+
+    pub fn parse(name: &str) -> Result<Self> {
+        let name = name.trim().to_lowercase();
+        CsvColumn::ALL
+            .into_iter()
+            .find(|c| c.name() == name)
+            .ok_or_else(|| {
+                let known: Vec<&str> = CsvColumn::ALL.iter().map(|c| c.name()).collect();
+                TodoError::Config(format!(
+                    "Unknown CSV column '{}' (expected one of: {})",
+                    name,
+                    known.join(", ")
+                ))
+            })
+    }
+
+    /// Parse a comma-separated column list such as `file,line,tag`.
+    pub fn parse_list(list: &str) -> Result<Vec<Self>> {
+        list.split(',')
+            .filter(|c| !c.trim().is_empty())
+            .map(CsvColumn::parse)
+            .collect()
+    }
+
+    fn value(&self, item: &TodoItem, today: Date) -> String {
+        match self {
+            CsvColumn::File => item.file.display().to_string(),
+            CsvColumn::Line => item.line.to_string(),
+            CsvColumn::Column => item.column.to_string(),
+            CsvColumn::Tag => item.tag.as_str().to_string(),
+            CsvColumn::Message => item.message.clone(),
+            CsvColumn::Author => item.author.clone().unwrap_or_default(),
+            CsvColumn::Issue => item.issue.clone().unwrap_or_default(),
+            CsvColumn::Priority => item
+                .priority
+                .as_ref()
+                .map_or(String::new(), |p| p.as_str().to_string()),
+            CsvColumn::ContextLine => item.context_line.clone(),
+            CsvColumn::GitAuthor => item.git_author.clone().unwrap_or_default(),
+            CsvColumn::GitDate => item.git_date.clone().unwrap_or_default(),
+            CsvColumn::GitCommit => item.git_commit.clone().unwrap_or_default(),
+            CsvColumn::Age => item
+                .git_date
+                .as_deref()
+                .and_then(|d| Date::parse(d).ok())
+                .map_or(String::new(), |d| d.days_until(today).max(0).to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvFormatter {
+    /// Columns to write, in order. `None` writes the default eight columns,
+    /// plus the git columns when any item carries blame data.
+    pub columns: Option<Vec<CsvColumn>>,
+    /// Field separator
+    pub delimiter: u8,
+    /// The day the age column counts to, if not today
+    pub today: Option<Date>,
+}
+
+impl Default for CsvFormatter {
+    fn default() -> Self {
+        CsvFormatter {
+            columns: None,
+            delimiter: b',',
+            today: None,
+        }
+    }
+}
+
+/// Parse a `--delimiter` value: a single ASCII character, or `tab` / `\t`.
+pub fn parse_delimiter(s: &str) -> Result<u8> {
+    match s {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        _ if s.len() == 1 && s.is_ascii() && s != "\"" && s != "\n" => Ok(s.as_bytes()[0]),
+        _ => Err(TodoError::Config(format!(
+            "Invalid delimiter '{}' (expected a single ASCII character or \"tab\")",
+            s
+        ))),
+    }
+}
+
+impl CsvFormatter {
+    /// Tab-separated values: fields are never quoted, and tabs and line
+    /// breaks inside them become spaces.
+    pub fn tsv() -> Self {
+        CsvFormatter {
+            delimiter: b'\t',
+            ..Default::default()
+        }
+    }
+
+    fn is_tsv(&self) -> bool {
+        self.delimiter == b'\t'
+    }
+
+    /// Whether any selected column needs blame data.
+    pub fn needs_blame(&self) -> bool {
+        self.columns.iter().flatten().any(|c| c.needs_blame())
+    }
+
+    fn columns_for(&self, result: &ScanResult) -> Vec<CsvColumn> {
+        if let Some(ref columns) = self.columns {
+            return columns.clone();
+        }
+        let mut columns = CsvColumn::DEFAULT.to_vec();
+        let blamed = result
+            .items
+            .iter()
+            .any(|item| item.git_author.is_some() || item.git_date.is_some());
+        if blamed {
+            columns.extend(CsvColumn::GIT);
+        }
+        columns
+    }
+}
 
 impl OutputFormatter for CsvFormatter {
     fn format(&self, result: &ScanResult) -> Result<String> {
-        let mut wtr = csv_crate::WriterBuilder::new().from_writer(Vec::new());
+        let mut builder = csv_crate::WriterBuilder::new();
+        builder.delimiter(self.delimiter);
+        if self.is_tsv() {
+            builder.quote_style(csv_crate::QuoteStyle::Never);
+        }
+        let mut wtr = builder.from_writer(Vec::new());
+        let columns = self.columns_for(result);
+        let today = self.today.unwrap_or_else(Date::today);
 
         // Write header row
-        wtr.write_record(["file", "line", "column", "tag", "message", "author", "issue", "priority"])
-            .map_err(|e| crate::error::TodoError::Config(e.to_string()))?;
+        wtr.write_record(columns.iter().map(|c| c.name()))
+            .map_err(|e| TodoError::Config(e.to_string()))?;
 
         // Write one row per item
         for item in &result.items {
-            let priority_str = item.priority.as_ref().map_or(String::new(), |p| {
-                match p {
-                    crate::model::Priority::Low => "low".to_string(),
-                    crate::model::Priority::Medium => "medium".to_string(),
-                    crate::model::Priority::High => "high".to_string(),
-                    crate::model::Priority::Critical => "critical".to_string(),
-                }
-            });
-
-            wtr.write_record(&[
-                item.file.display().to_string(),
-                item.line.to_string(),
-                item.column.to_string(),
-                item.tag.as_str().to_string(),
-                item.message.clone(),
-                item.author.clone().unwrap_or_default(),
-                item.issue.clone().unwrap_or_default(),
-                priority_str,
-            ])
-            .map_err(|e| crate::error::TodoError::Config(e.to_string()))?;
+            let values = columns.iter().map(|c| c.value(item, today));
+            let values: Vec<String> = if self.is_tsv() {
+                values.map(|v| v.replace(['\t', '\n', '\r'], " ")).collect()
+            } else {
+                values.collect()
+            };
+            wtr.write_record(&values)
+                .map_err(|e| TodoError::Config(e.to_string()))?;
         }
 
         let bytes = wtr
             .into_inner()
-            .map_err(|e| crate::error::TodoError::Config(e.to_string()))?;
+            .map_err(|e| TodoError::Config(e.to_string()))?;
 
-        String::from_utf8(bytes)
-            .map_err(|e| crate::error::TodoError::Config(e.to_string()))
+        String::from_utf8(bytes).map_err(|e| TodoError::Config(e.to_string()))
     }
 }
 
@@ -68,6 +255,14 @@ mod tests {
                 context_line: "// TODO(alice): Add error handling #123".to_string(),
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
             },
             TodoItem {
                 tag: TodoTag::Fixme,
@@ -81,6 +276,14 @@ mod tests {
                 context_line: "// FIXME: This is broken".to_string(),
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
             },
         ];
 
@@ -95,18 +298,20 @@ mod tests {
                 files_with_todos: 1,
                 total_todos: 2,
                 by_tag,
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 25,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         }
     }
 
     #[test]
     fn test_csv_has_header() {
-        let formatter = CsvFormatter;
+        let formatter = CsvFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
         let first_line = output.lines().next().unwrap();
@@ -115,7 +320,7 @@ mod tests {
 
     #[test]
     fn test_csv_row_count() {
-        let formatter = CsvFormatter;
+        let formatter = CsvFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
         let lines: Vec<&str> = output.lines().collect();
@@ -125,7 +330,7 @@ mod tests {
 
     #[test]
     fn test_csv_data_row_content() {
-        let formatter = CsvFormatter;
+        let formatter = CsvFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
         let lines: Vec<&str> = output.lines().collect();
@@ -140,7 +345,7 @@ mod tests {
 
     #[test]
     fn test_csv_priority_field() {
-        let formatter = CsvFormatter;
+        let formatter = CsvFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
         let lines: Vec<&str> = output.lines().collect();
@@ -151,7 +356,7 @@ mod tests {
 
     #[test]
     fn test_csv_empty_result() {
-        let formatter = CsvFormatter;
+        let formatter = CsvFormatter::default();
         let result = ScanResult {
             items: vec![],
             stats: ScanStats {
@@ -159,11 +364,13 @@ mod tests {
                 files_with_todos: 0,
                 total_todos: 0,
                 by_tag: HashMap::new(),
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 0,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         };
         let output = formatter.format(&result).unwrap();
@@ -175,7 +382,7 @@ mod tests {
 
     #[test]
     fn test_csv_message_with_comma() {
-        let formatter = CsvFormatter;
+        let formatter = CsvFormatter::default();
         let items = vec![TodoItem {
             tag: TodoTag::Todo,
             message: "Fix this, please".to_string(),
@@ -188,6 +395,14 @@ mod tests {
             context_line: String::new(),
             git_author: None,
             git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
         }];
 
         let mut by_tag = HashMap::new();
@@ -200,11 +415,13 @@ mod tests {
                 files_with_todos: 1,
                 total_todos: 1,
                 by_tag,
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 1,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         };
         let output = formatter.format(&result).unwrap();
@@ -214,4 +431,60 @@ mod tests {
             "Commas in fields should be properly quoted"
         );
     }
+
+    #[test]
+    fn test_csv_git_columns_and_selection() {
+        let mut result = sample_result();
+        result.items[0].git_author = Some("bob".to_string());
+        result.items[0].git_date = Some("2024-03-01".to_string());
+        let output = CsvFormatter::default().format(&result).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            "file,line,column,tag,message,author,issue,priority,git_author,git_date,git_commit"
+        );
+        assert!(lines[1].ends_with(",bob,2024-03-01,"));
+
+        let formatter = CsvFormatter {
+            columns: Some(CsvColumn::parse_list("git_date, file,line").unwrap()),
+            ..Default::default()
+        };
+        let output = formatter.format(&result).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "git_date,file,line");
+        assert_eq!(lines[1], "2024-03-01,src/main.rs,12");
+        assert!(CsvColumn::parse_list("file,owner").is_err());
+    }
+
+    #[test]
+    fn test_csv_age_column() {
+        let mut item = sample_result().items.remove(0);
+        let today = Date::parse("2024-03-11").unwrap();
+        assert_eq!(CsvColumn::Age.value(&item, today), "");
+        item.git_date = Some("2024-03-01".to_string());
+        assert_eq!(CsvColumn::Age.value(&item, today), "10");
+        assert!(CsvColumn::Age.needs_blame() && !CsvColumn::Tag.needs_blame());
+    }
+
+    #[test]
+    fn test_tsv_and_custom_delimiter() {
+        let mut result = sample_result();
+        result.items[0].message = "tab\there, \"quoted\"".to_string();
+        let output = CsvFormatter::tsv().format(&result).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "file\tline\tcolumn\ttag\tmessage\tauthor\tissue\tpriority");
+        assert_eq!(
+            lines[1],
+            "src/main.rs\t12\t5\tTODO\ttab here, \"quoted\"\talice\t123\t"
+        );
+
+        let formatter = CsvFormatter {
+            delimiter: parse_delimiter(";").unwrap(),
+            ..Default::default()
+        };
+        let output = formatter.format(&result).unwrap();
+        assert!(output.starts_with("file;line;column;"));
+        assert_eq!(parse_delimiter("tab").unwrap(), b'\t');
+        assert!(parse_delimiter(";;").is_err());
+    }
 }
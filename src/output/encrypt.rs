@@ -0,0 +1,88 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::error::{Result, TodoError};
+
+/// Encryption for output written to CI artifact stores, from `--encrypt`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Encryption {
+    /// Encrypt to an age recipient (`age1...` or an SSH public key) with the
+    /// `age` command
+    Age { recipient: String },
+}
+
+impl Encryption {
+    /// Parse a `scheme:recipient` spec such as `age:age1ql3z...`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid = || {
+            TodoError::Config(format!(
+                "Invalid --encrypt '{}': expected age:<recipient>",
+                spec
+            ))
+        };
+        let (scheme, recipient) = spec.split_once(':').ok_or_else(invalid)?;
+        let recipient = recipient.trim();
+        match scheme {
+            "age" if !recipient.is_empty() => Ok(Encryption::Age {
+                recipient: recipient.to_string(),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Encrypt `plaintext` into ASCII-armored ciphertext.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let Encryption::Age { recipient } = self;
+        let mut child = Command::new("age")
+            .args(["--encrypt", "--armor", "--recipient", recipient])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => {
+                    TodoError::Encrypt("age is not installed or not on PATH".to_string())
+                }
+                _ => TodoError::Encrypt(format!("failed to run age: {}", e)),
+            })?;
+
+        // Write from a thread so age can't block on a full stdout pipe while
+        // we're still writing its input
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let input = plaintext.to_vec();
+        let writer = std::thread::spawn(move || stdin.write_all(&input));
+        let output = child.wait_with_output()?;
+        let written = writer.join().unwrap_or(Ok(()));
+
+        if !output.status.success() {
+            let detail = String::from_utf8_lossy(&output.stderr);
+            return Err(TodoError::Encrypt(format!(
+                "age failed ({}): {}",
+                output.status,
+                detail.trim()
+            )));
+        }
+        written?;
+        Ok(output.stdout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_encryption() {
+        assert_eq!(
+            Encryption::parse("age:age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p")
+                .unwrap(),
+            Encryption::Age {
+                recipient: "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p"
+                    .to_string()
+            }
+        );
+        assert!(Encryption::parse("age:").is_err());
+        assert!(Encryption::parse("gpg:ops@example.com").is_err());
+        assert!(Encryption::parse("age1ql3z").is_err());
+    }
+}
@@ -1,25 +1,59 @@
 use std::fmt::Write;
 
 use crate::error::Result;
-use crate::model::ScanResult;
+use crate::model::{ScanResult, TodoItem};
+use crate::output::annotations::AnnotationLevel;
 use crate::output::OutputFormatter;
 
-pub struct GithubActionsFormatter;
+#[derive(Default)]
+pub struct GithubActionsFormatter {
+    /// Items annotated below this level are left out
+    pub min_level: AnnotationLevel,
+    /// Annotate at most this many items, most severe and highest priority
+    /// first, then add one notice counting the rest
+    pub max_annotations: Option<usize>,
+}
+
+/// The workflow command an item is annotated with.
+fn level(item: &TodoItem) -> AnnotationLevel {
+    match item.tag.as_str() {
+        "FIXME" | "BUG" => AnnotationLevel::Error,
+        _ => AnnotationLevel::Warning,
+    }
+}
 
 impl OutputFormatter for GithubActionsFormatter {
     fn format(&self, result: &ScanResult) -> Result<String> {
         let mut out = String::new();
-        for item in &result.items {
-            let level = match item.tag.as_str() {
-                "FIXME" | "BUG" => "error",
-                "HACK" | "XXX" => "warning",
-                _ => "warning",
-            };
+        let mut annotated: Vec<(&TodoItem, AnnotationLevel)> = result
+            .items
+            .iter()
+            .map(|item| (item, level(item)))
+            .filter(|(_, level)| *level >= self.min_level)
+            .collect();
+        let mut overflow = Vec::new();
+        if let Some(max) = self.max_annotations.filter(|max| annotated.len() > *max) {
+            // Rank by index so the kept items stay in scan order
+            let mut ranked: Vec<usize> = (0..annotated.len()).collect();
+            ranked.sort_by_key(|&i| {
+                let (item, level) = annotated[i];
+                std::cmp::Reverse((level, item.priority.clone()))
+            });
+            let mut keep = vec![false; annotated.len()];
+            for &i in &ranked[..max] {
+                keep[i] = true;
+            }
+            let (kept, rest): (Vec<_>, Vec<_>) =
+                annotated.into_iter().zip(keep).partition(|(_, keep)| *keep);
+            annotated = kept.into_iter().map(|(annotation, _)| annotation).collect();
+            overflow = rest.into_iter().map(|(annotation, _)| annotation).collect();
+        }
+        for (item, level) in annotated {
             let file = item.file.display().to_string().replace('\\', "/");
             writeln!(
                 out,
                 "::{level} file={file},line={line},col={col}::{tag}: {msg}",
-                level = level,
+                level = level.as_str(),
                 file = file,
                 line = item.line,
                 col = item.column,
@@ -28,6 +62,19 @@ impl OutputFormatter for GithubActionsFormatter {
             )
             .unwrap();
         }
+        if !overflow.is_empty() {
+            let errors = overflow.iter().filter(|(_, l)| *l == AnnotationLevel::Error).count();
+            writeln!(
+                out,
+                "::notice title=todo-tracker::{} more TODO(s) not annotated \
+                 ({} error(s), {} warning(s)); the limit is {}",
+                overflow.len(),
+                errors,
+                overflow.len() - errors,
+                self.max_annotations.unwrap_or_default()
+            )
+            .unwrap();
+        }
         Ok(out)
     }
 }
@@ -52,6 +99,14 @@ mod tests {
             context_line: String::new(),
             git_author: None,
             git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
         }
     }
 
@@ -64,11 +119,13 @@ mod tests {
                 files_with_todos: 1,
                 total_todos: total,
                 by_tag: HashMap::new(),
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 0,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         }
     }
@@ -82,7 +139,7 @@ mod tests {
             10,
             5,
         )]);
-        let formatter = GithubActionsFormatter;
+        let formatter = GithubActionsFormatter::default();
         let output = formatter.format(&result).unwrap();
         assert!(output.contains("::warning file=src/main.rs,line=10,col=5::TODO: fix this"));
     }
@@ -96,7 +153,7 @@ mod tests {
             20,
             3,
         )]);
-        let formatter = GithubActionsFormatter;
+        let formatter = GithubActionsFormatter::default();
         let output = formatter.format(&result).unwrap();
         assert!(output.contains("::error file=src/lib.rs,line=20,col=3::FIXME: broken"));
     }
@@ -110,7 +167,7 @@ mod tests {
             5,
             1,
         )]);
-        let formatter = GithubActionsFormatter;
+        let formatter = GithubActionsFormatter::default();
         let output = formatter.format(&result).unwrap();
         assert!(output.contains("::error file=src/app.rs,line=5,col=1::BUG: crash here"));
     }
@@ -124,7 +181,7 @@ mod tests {
             15,
             2,
         )]);
-        let formatter = GithubActionsFormatter;
+        let formatter = GithubActionsFormatter::default();
         let output = formatter.format(&result).unwrap();
         assert!(output.contains("::warning file=src/util.rs,line=15,col=2::HACK: workaround"));
     }
@@ -135,16 +192,71 @@ mod tests {
             make_item(TodoTag::Todo, "first", "src/a.rs", 1, 1),
             make_item(TodoTag::Fixme, "second", "src/b.rs", 2, 1),
         ]);
-        let formatter = GithubActionsFormatter;
+        let formatter = GithubActionsFormatter::default();
         let output = formatter.format(&result).unwrap();
         let lines: Vec<&str> = output.trim().lines().collect();
         assert_eq!(lines.len(), 2);
     }
 
+    #[test]
+    fn test_min_level_drops_warnings() {
+        let result = make_result(vec![
+            make_item(TodoTag::Todo, "first", "src/a.rs", 1, 1),
+            make_item(TodoTag::Fixme, "second", "src/b.rs", 2, 1),
+        ]);
+        let formatter = GithubActionsFormatter {
+            min_level: AnnotationLevel::Error,
+            ..Default::default()
+        };
+        let output = formatter.format(&result).unwrap();
+        assert_eq!(output, "::error file=src/b.rs,line=2,col=1::FIXME: second\n");
+    }
+
+    #[test]
+    fn test_max_annotations_keeps_most_severe() {
+        let mut urgent = make_item(TodoTag::Todo, "urgent", "src/c.rs", 3, 1);
+        urgent.priority = Some(crate::model::Priority::High);
+        let result = make_result(vec![
+            make_item(TodoTag::Todo, "first", "src/a.rs", 1, 1),
+            make_item(TodoTag::Fixme, "second", "src/b.rs", 2, 1),
+            urgent,
+            make_item(TodoTag::Bug, "fourth", "src/d.rs", 4, 1),
+        ]);
+        let formatter = GithubActionsFormatter {
+            max_annotations: Some(2),
+            ..Default::default()
+        };
+        let output = formatter.format(&result).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("FIXME: second"));
+        assert!(lines[1].contains("BUG: fourth"));
+        assert_eq!(
+            lines[2],
+            "::notice title=todo-tracker::2 more TODO(s) not annotated \
+             (0 error(s), 2 warning(s)); the limit is 2"
+        );
+
+        let formatter = GithubActionsFormatter {
+            max_annotations: Some(3),
+            ..Default::default()
+        };
+        let output = formatter.format(&result).unwrap();
+        assert!(output.contains("TODO: urgent"));
+        assert!(!output.contains("TODO: first"));
+        assert!(output.contains("1 more TODO(s)"));
+
+        let formatter = GithubActionsFormatter {
+            max_annotations: Some(4),
+            ..Default::default()
+        };
+        assert!(!formatter.format(&result).unwrap().contains("::notice"));
+    }
+
     #[test]
     fn test_empty_result() {
         let result = make_result(vec![]);
-        let formatter = GithubActionsFormatter;
+        let formatter = GithubActionsFormatter::default();
         let output = formatter.format(&result).unwrap();
         assert!(output.is_empty());
     }
@@ -158,7 +270,7 @@ mod tests {
             1,
             1,
         )]);
-        let formatter = GithubActionsFormatter;
+        let formatter = GithubActionsFormatter::default();
         let output = formatter.format(&result).unwrap();
         assert!(output.contains("file=src/nested/file.rs"));
         assert!(!output.contains('\\'));
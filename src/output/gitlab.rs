@@ -0,0 +1,102 @@
+use serde_json::{json, Value};
+
+use crate::error::Result;
+use crate::model::{Priority, ScanResult, TodoItem, TodoTag};
+use crate::output::OutputFormatter;
+
+/// GitLab Code Quality report: a JSON array of issues. Save it as the job's
+/// `artifacts:reports:codequality` file and merge requests show the items
+/// added on their lines.
+pub struct GitLabFormatter;
+
+fn severity(item: &TodoItem) -> &'static str {
+    match item.priority {
+        Some(Priority::Critical) => "critical",
+        Some(Priority::High) => "major",
+        Some(Priority::Medium) => "minor",
+        Some(Priority::Low) => "info",
+        None => match item.tag {
+            TodoTag::Fixme | TodoTag::Bug => "major",
+            TodoTag::Hack | TodoTag::Xxx => "minor",
+            _ => "info",
+        },
+    }
+}
+
+impl OutputFormatter for GitLabFormatter {
+    fn format(&self, result: &ScanResult) -> Result<String> {
+        let root = &result.metadata.root_path;
+        let issues: Vec<Value> = result
+            .items
+            .iter()
+            .map(|item| {
+                json!({
+                    "description": format!("{}: {}", item.tag, item.message),
+                    "check_name": format!("todo-tracker/{}", item.tag.as_str().to_lowercase()),
+                    "fingerprint": item.fingerprint(root),
+                    "severity": severity(item),
+                    "location": {
+                        "path": item.relative_path(root),
+                        "lines": {"begin": item.line}
+                    }
+                })
+            })
+            .collect();
+        serde_json::to_string_pretty(&issues)
+            .map_err(|e| crate::error::TodoError::Config(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_code_quality_issues() {
+        let item = TodoItem {
+            tag: TodoTag::Fixme,
+            message: "handle timeouts".to_string(),
+            file: PathBuf::from("/repo/src/net.rs"),
+            line: 12,
+            column: 4,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        };
+        let mut low = item.clone();
+        low.message = "log retries".to_string();
+        low.priority = Some(Priority::Low);
+        let result = ScanResult {
+            items: vec![item, low],
+            stats: ScanStats::default(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("/repo"),
+                timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
+            },
+        };
+        let output = GitLabFormatter.format(&result).unwrap();
+        let issues: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(issues[0]["description"], "FIXME: handle timeouts");
+        assert_eq!(issues[0]["check_name"], "todo-tracker/fixme");
+        assert_eq!(issues[0]["severity"], "major");
+        assert_eq!(issues[0]["location"]["path"], "src/net.rs");
+        assert_eq!(issues[0]["location"]["lines"]["begin"], 12);
+        assert_eq!(issues[0]["fingerprint"].as_str().unwrap().len(), 16);
+        assert_eq!(issues[1]["severity"], "info");
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::config::OutputConfig;
+use crate::error::{Result, TodoError};
+use crate::model::{Priority, TodoTag};
+
+/// Which indicators text and markdown output put next to items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GlyphMode {
+    #[default]
+    None,
+    /// Emoji per tag and per priority
+    Emoji,
+    /// `[!!!]`-style priority markers only
+    Ascii,
+}
+
+impl GlyphMode {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" | "off" => Ok(GlyphMode::None),
+            "emoji" => Ok(GlyphMode::Emoji),
+            "ascii" => Ok(GlyphMode::Ascii),
+            other => Err(TodoError::Config(format!(
+                "Unknown glyphs setting '{}' (expected none, emoji or ascii)",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Glyphs {
+    pub mode: GlyphMode,
+    /// Emoji overrides keyed by tag name
+    pub tag_emoji: HashMap<String, String>,
+}
+
+impl Glyphs {
+    pub fn from_config(config: &OutputConfig) -> Result<Self> {
+        let mode = match config.glyphs {
+            Some(ref s) => GlyphMode::parse(s)?,
+            None => GlyphMode::None,
+        };
+        let tag_emoji = config
+            .tag_emoji
+            .iter()
+            .flatten()
+            .map(|(tag, emoji)| (tag.to_uppercase(), emoji.clone()))
+            .collect();
+        Ok(Glyphs { mode, tag_emoji })
+    }
+
+    /// Fall back from emoji to ASCII markers, e.g. under `--ascii`.
+    pub fn ascii_only(mut self) -> Self {
+        if self.mode == GlyphMode::Emoji {
+            self.mode = GlyphMode::Ascii;
+        }
+        self
+    }
+
+    pub fn priority(&self, priority: Option<&Priority>) -> Option<&'static str> {
+        let priority = priority?;
+        match self.mode {
+            GlyphMode::None => None,
+            GlyphMode::Emoji => Some(match priority {
+                Priority::Critical => "\u{1f525}",
+                Priority::High => "\u{23eb}",
+                Priority::Medium => "\u{1f538}",
+                Priority::Low => "\u{1f53d}",
+            }),
+            GlyphMode::Ascii => Some(match priority {
+                Priority::Critical => "[!!!]",
+                Priority::High => "[!!]",
+                Priority::Medium => "[!]",
+                Priority::Low => "[.]",
+            }),
+        }
+    }
+
+    pub fn tag(&self, tag: &TodoTag) -> Option<&str> {
+        if self.mode != GlyphMode::Emoji {
+            return None;
+        }
+        if let Some(emoji) = self.tag_emoji.get(tag.as_str()) {
+            return Some(emoji);
+        }
+        Some(match tag {
+            TodoTag::Todo => "\u{1f4dd}",
+            TodoTag::Fixme => "\u{1f527}",
+            TodoTag::Hack => "\u{1fa79}",
+            TodoTag::Bug => "\u{1f41b}",
+            TodoTag::Xxx => "\u{2757}",
+            TodoTag::Custom(_) => "\u{1f4cc}",
+        })
+    }
+
+    /// `message` with the priority glyph in front, if any.
+    pub fn decorate_message(&self, priority: Option<&Priority>, message: &str) -> String {
+        match self.priority(priority) {
+            Some(glyph) => format!("{} {}", glyph, message),
+            None => message.to_string(),
+        }
+    }
+}
+
+static ACTIVE: RwLock<Option<Glyphs>> = RwLock::new(None);
+
+/// Set the glyphs used by text and markdown output for the rest of the process.
+pub fn set_glyphs(glyphs: Glyphs) {
+    *ACTIVE.write().unwrap_or_else(|e| e.into_inner()) = Some(glyphs);
+}
+
+/// The glyphs set with `set_glyphs`, or none.
+pub fn current() -> Glyphs {
+    ACTIVE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyph_modes() {
+        let config = OutputConfig {
+            glyphs: Some("emoji".to_string()),
+            tag_emoji: Some(HashMap::from([("note".to_string(), "N".to_string())])),
+            ..Default::default()
+        };
+        let glyphs = Glyphs::from_config(&config).unwrap();
+        assert_eq!(glyphs.tag(&TodoTag::Bug), Some("\u{1f41b}"));
+        assert_eq!(glyphs.tag(&TodoTag::Custom("NOTE".to_string())), Some("N"));
+        assert_eq!(
+            glyphs.decorate_message(Some(&Priority::Critical), "fix"),
+            "\u{1f525} fix"
+        );
+        assert_eq!(glyphs.decorate_message(None, "fix"), "fix");
+
+        let ascii = glyphs.ascii_only();
+        assert_eq!(ascii.tag(&TodoTag::Bug), None);
+        assert_eq!(ascii.priority(Some(&Priority::Critical)), Some("[!!!]"));
+
+        assert_eq!(Glyphs::default().priority(Some(&Priority::High)), None);
+        assert!(GlyphMode::parse("sparkles").is_err());
+    }
+}
@@ -1,16 +1,200 @@
 use crate::error::Result;
-use crate::model::ScanResult;
+use crate::model::{ScanMetadata, ScanResult, ScanStats};
+use crate::output::links::LinkTemplate;
 use crate::output::OutputFormatter;
 
-pub struct JsonFormatter;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Default)]
+pub struct JsonFormatter {
+    /// Write everything on one line instead of pretty printing
+    pub compact: bool,
+    /// Keep only these dotted paths, e.g. `items.file` or `stats`
+    pub fields: Option<Vec<String>>,
+    /// Drop these dotted paths, e.g. `context_line`
+    pub omit: Vec<String>,
+    /// Adds a `url` field to each item
+    pub links: Option<LinkTemplate>,
+}
 
 impl OutputFormatter for JsonFormatter {
     fn format(&self, result: &ScanResult) -> Result<String> {
-        serde_json::to_string_pretty(result)
-            .map_err(|e| crate::error::TodoError::Config(e.to_string()))
+        if self.fields.is_none() && self.omit.is_empty() && self.links.is_none() {
+            return to_json(result, self.compact);
+        }
+        let mut value = serde_json::to_value(result)
+            .map_err(|e| crate::error::TodoError::Config(e.to_string()))?;
+        if let (Some(links), Some(items)) = (&self.links, value["items"].as_array_mut()) {
+            let root = &result.metadata.root_path;
+            for (item, json) in result.items.iter().zip(items) {
+                json["url"] = Value::String(links.url(item, root));
+            }
+        }
+        if let Some(ref fields) = self.fields {
+            let paths: Vec<Vec<&str>> = fields.iter().map(|f| field_path(&value, f)).collect();
+            value = select(&value, &paths);
+        }
+        for field in &self.omit {
+            let path = field_path(&value, field);
+            remove(&mut value, &path);
+        }
+        to_json(&value, self.compact)
+    }
+}
+
+/// Split a dotted field name into a path from the top of the document.
+/// Names that don't start with a top-level key (`items`, `stats`,
+/// `metadata`) are taken as item fields, so `file` means `items.file`.
+fn field_path<'a>(value: &Value, field: &'a str) -> Vec<&'a str> {
+    let mut path: Vec<&str> = field.split('.').map(str::trim).filter(|s| !s.is_empty()).collect();
+    if let Some(first) = path.first() {
+        if value.get(first).is_none() {
+            path.insert(0, "items");
+        }
+    }
+    path
+}
+
+/// The parts of `value` covered by `paths`. Arrays are projected element
+/// by element.
+fn select(value: &Value, paths: &[Vec<&str>]) -> Value {
+    if paths.iter().any(|p| p.is_empty()) {
+        return value.clone();
+    }
+    match value {
+        Value::Array(elements) => Value::Array(elements.iter().map(|e| select(e, paths)).collect()),
+        Value::Object(map) => {
+            let mut out = serde_json::Map::new();
+            for (key, child) in map {
+                let tails: Vec<Vec<&str>> = paths
+                    .iter()
+                    .filter(|p| p[0] == key)
+                    .map(|p| p[1..].to_vec())
+                    .collect();
+                if !tails.is_empty() {
+                    out.insert(key.clone(), select(child, &tails));
+                }
+            }
+            Value::Object(out)
+        }
+        other => other.clone(),
+    }
+}
+
+fn remove(value: &mut Value, path: &[&str]) {
+    match value {
+        Value::Array(elements) => elements.iter_mut().for_each(|e| remove(e, path)),
+        Value::Object(map) => match path {
+            [] => {}
+            [key] => {
+                map.remove(*key);
+            }
+            [key, rest @ ..] => {
+                if let Some(child) = map.get_mut(*key) {
+                    remove(child, rest);
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+/// Serialize `value` pretty printed, or on a single line when `compact`.
+pub fn to_json<T: serde::Serialize + ?Sized>(value: &T, compact: bool) -> Result<String> {
+    let json = if compact {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    };
+    json.map_err(|e| crate::error::TodoError::Config(e.to_string()))
+}
+
+/// Writes the JSON document of a scan result a few items at a time, for
+/// `--memory-budget`. The output matches [`JsonFormatter`] without fields
+/// or omissions.
+#[derive(Debug)]
+pub struct JsonStream {
+    compact: bool,
+    links: Option<LinkTemplate>,
+    items_written: usize,
+}
+
+impl JsonStream {
+    pub fn new(compact: bool, links: Option<LinkTemplate>) -> Self {
+        JsonStream {
+            compact,
+            links,
+            items_written: 0,
+        }
+    }
+
+    /// The next part of the `items` array: the items of `chunk`.
+    pub fn items(&mut self, chunk: &ScanResult) -> Result<String> {
+        let mut out = String::new();
+        for item in &chunk.items {
+            let separator = match (self.items_written, self.compact) {
+                (0, true) => "{\"items\":[",
+                (0, false) => "{\n  \"items\": [\n    ",
+                (_, true) => ",",
+                (_, false) => ",\n    ",
+            };
+            out.push_str(separator);
+            let json = match self.links {
+                Some(ref links) => {
+                    let mut json = to_value(item)?;
+                    json["url"] = Value::String(links.url(item, &chunk.metadata.root_path));
+                    self.nested(&json, 2)?
+                }
+                None => self.nested(item, 2)?,
+            };
+            out.push_str(&json);
+            self.items_written += 1;
+        }
+        Ok(out)
+    }
+
+    /// The rest of the document, after the last item.
+    pub fn finish(&self, stats: &ScanStats, metadata: &ScanMetadata) -> Result<String> {
+        let start = match (self.items_written, self.compact) {
+            (0, true) => "{\"items\":[",
+            (0, false) => "{\n  \"items\": [",
+            (_, true) => "",
+            (_, false) => "\n  ",
+        };
+        // With links the formatter goes through `Value`, whose keys are sorted
+        let rest = match self.links {
+            Some(_) => [
+                ("metadata", self.nested(&to_value(metadata)?, 1)?),
+                ("stats", self.nested(&to_value(stats)?, 1)?),
+            ],
+            None => [
+                ("stats", self.nested(stats, 1)?),
+                ("metadata", self.nested(metadata, 1)?),
+            ],
+        };
+        let mut out = format!("{}]", start);
+        for (key, json) in rest {
+            if self.compact {
+                out.push_str(&format!(",\"{}\":{}", key, json));
+            } else {
+                out.push_str(&format!(",\n  \"{}\": {}", key, json));
+            }
+        }
+        out.push_str(if self.compact { "}" } else { "\n}" });
+        Ok(out)
+    }
+
+    /// `value` as JSON nested `depth` levels deep in the document.
+    fn nested<T: serde::Serialize + ?Sized>(&self, value: &T, depth: usize) -> Result<String> {
+        let json = to_json(value, self.compact)?;
+        Ok(json.replace('\n', &format!("\n{}", "  ".repeat(depth))))
     }
 }
 
+fn to_value<T: serde::Serialize + ?Sized>(value: &T) -> Result<Value> {
+    serde_json::to_value(value).map_err(|e| crate::error::TodoError::Config(e.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -32,6 +216,14 @@ mod tests {
                 context_line: "// TODO(alice): Add error handling #123".to_string(),
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
             },
             TodoItem {
                 tag: TodoTag::Fixme,
@@ -45,6 +237,14 @@ mod tests {
                 context_line: "// FIXME: This is broken".to_string(),
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
             },
         ];
 
@@ -59,18 +259,46 @@ mod tests {
                 files_with_todos: 1,
                 total_todos: 2,
                 by_tag,
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 25,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         }
     }
 
+    #[test]
+    fn test_json_stream_matches_formatter() {
+        let result = sample_result();
+        let chunk = |items: &[TodoItem]| ScanResult {
+            items: items.to_vec(),
+            ..result.clone()
+        };
+        let links = LinkTemplate::new("https://example.com/{path}#L{line}", "abc", "");
+        for (compact, links) in [(false, None), (true, None), (false, Some(links))] {
+            let formatter = JsonFormatter {
+                compact,
+                links: links.clone(),
+                ..Default::default()
+            };
+            let mut stream = JsonStream::new(compact, links.clone());
+            let mut output = stream.items(&chunk(&result.items[..1])).unwrap();
+            output += &stream.items(&chunk(&result.items[1..])).unwrap();
+            output += &stream.finish(&result.stats, &result.metadata).unwrap();
+            assert_eq!(output, formatter.format(&result).unwrap());
+
+            let stream = JsonStream::new(compact, links);
+            let output = stream.finish(&result.stats, &result.metadata).unwrap();
+            assert_eq!(output, formatter.format(&chunk(&[])).unwrap());
+        }
+    }
+
     #[test]
     fn test_json_is_valid() {
-        let formatter = JsonFormatter;
+        let formatter = JsonFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
 
@@ -80,7 +308,7 @@ mod tests {
 
     #[test]
     fn test_json_contains_items() {
-        let formatter = JsonFormatter;
+        let formatter = JsonFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
 
@@ -91,7 +319,7 @@ mod tests {
 
     #[test]
     fn test_json_item_fields() {
-        let formatter = JsonFormatter;
+        let formatter = JsonFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
 
@@ -100,11 +328,17 @@ mod tests {
         assert_eq!(first_item["message"], "Add error handling");
         assert_eq!(first_item["line"], 12);
         assert_eq!(first_item["author"], "alice");
+        // Commit details are only present for blamed items
+        let fields = first_item.as_object().unwrap();
+        assert!(fields.contains_key("git_author"));
+        for key in ["git_commit", "git_summary", "pr_number"] {
+            assert!(!fields.contains_key(key), "{}", key);
+        }
     }
 
     #[test]
     fn test_json_contains_stats() {
-        let formatter = JsonFormatter;
+        let formatter = JsonFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
 
@@ -116,7 +350,7 @@ mod tests {
 
     #[test]
     fn test_json_empty_result() {
-        let formatter = JsonFormatter;
+        let formatter = JsonFormatter::default();
         let result = ScanResult {
             items: vec![],
             stats: ScanStats {
@@ -124,11 +358,13 @@ mod tests {
                 files_with_todos: 0,
                 total_todos: 0,
                 by_tag: HashMap::new(),
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 0,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         };
         let output = formatter.format(&result).unwrap();
@@ -137,4 +373,70 @@ mod tests {
         let items = parsed["items"].as_array().unwrap();
         assert!(items.is_empty());
     }
+
+    #[test]
+    fn test_json_compact_is_one_line() {
+        let result = sample_result();
+        let compact = JsonFormatter {
+            compact: true,
+            ..Default::default()
+        }
+        .format(&result).unwrap();
+        assert_eq!(compact.lines().count(), 1);
+        let pretty = JsonFormatter::default().format(&result).unwrap();
+        assert!(compact.len() < pretty.len());
+        let a: serde_json::Value = serde_json::from_str(&compact).unwrap();
+        let b: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_json_fields_and_omit() {
+        let result = sample_result();
+        let formatter = JsonFormatter {
+            fields: Some(vec![
+                "file".to_string(),
+                "items.line".to_string(),
+                "stats.total_todos".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format(&result).unwrap()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "items": [
+                    {"file": "src/main.rs", "line": 12},
+                    {"file": "src/main.rs", "line": 45}
+                ],
+                "stats": {"total_todos": 2}
+            })
+        );
+
+        let formatter = JsonFormatter {
+            omit: vec!["context_line".to_string(), "metadata".to_string()],
+            ..Default::default()
+        };
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format(&result).unwrap()).unwrap();
+        assert!(value.get("metadata").is_none());
+        assert!(value["items"][0].get("context_line").is_none());
+        assert_eq!(value["items"][0]["message"], "Add error handling");
+    }
+
+    #[test]
+    fn test_json_item_urls() {
+        let formatter = JsonFormatter {
+            fields: Some(vec!["line".to_string(), "url".to_string()]),
+            links: Some(LinkTemplate::new("https://example.com/{path}#L{line}", "", "")),
+            ..Default::default()
+        };
+        let value: serde_json::Value =
+            serde_json::from_str(&formatter.format(&sample_result()).unwrap()).unwrap();
+        assert_eq!(
+            value["items"][1],
+            serde_json::json!({"line": 45, "url": "https://example.com/src/main.rs#L45"})
+        );
+    }
 }
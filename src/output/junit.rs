@@ -0,0 +1,250 @@
+use std::fmt::Write;
+
+use crate::error::Result;
+use crate::model::{ScanResult, TodoItem};
+use crate::ordering::group_by_file;
+use crate::output::OutputFormatter;
+use crate::policy::{PolicyViolation, ViolationSeverity};
+
+/// Name of the suite holding violations of repository-wide rules such as
+/// `max_todos`, which belong to no single item.
+const POLICY_SUITE: &str = "policy";
+
+/// JUnit XML, which Jenkins and GitLab render as test results. Each item is
+/// a test case, in one suite per file. Error violations on an item are its
+/// failures and warnings go to its `system-out`; violations of
+/// repository-wide rules become test cases of a `policy` suite.
+#[derive(Debug, Clone, Default)]
+pub struct JunitFormatter {
+    /// Violations from `todos check`; empty when listing
+    pub violations: Vec<PolicyViolation>,
+}
+
+/// Escape a value for XML text or a double-quoted attribute, dropping
+/// control characters XML 1.0 doesn't allow.
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+impl JunitFormatter {
+    fn item_violations(&self, item: &TodoItem) -> Vec<&PolicyViolation> {
+        let file = item.file.display().to_string();
+        self.violations
+            .iter()
+            .filter(|v| v.file.as_deref() == Some(file.as_str()) && v.line == Some(item.line))
+            .collect()
+    }
+
+    fn write_case(
+        out: &mut String,
+        classname: &str,
+        name: &str,
+        location: Option<(&str, usize)>,
+        violations: &[&PolicyViolation],
+    ) {
+        write!(out, "    <testcase classname=\"{}\" name=\"{}\"", escape(classname), escape(name))
+            .unwrap();
+        if let Some((file, line)) = location {
+            write!(out, " file=\"{}\" line=\"{}\"", escape(file), line).unwrap();
+        }
+        if violations.is_empty() {
+            writeln!(out, "/>").unwrap();
+            return;
+        }
+        writeln!(out, ">").unwrap();
+        let mut warnings = Vec::new();
+        for v in violations {
+            match v.severity {
+                ViolationSeverity::Error => writeln!(
+                    out,
+                    "      <failure type=\"{}\" message=\"{}\">{}</failure>",
+                    escape(&v.rule),
+                    escape(&v.message),
+                    escape(&v.message)
+                )
+                .unwrap(),
+                ViolationSeverity::Warning => {
+                    warnings.push(format!("warning: {}: {}", v.rule, v.message))
+                }
+            }
+        }
+        if !warnings.is_empty() {
+            writeln!(out, "      <system-out>{}</system-out>", escape(&warnings.join("\n")))
+                .unwrap();
+        }
+        writeln!(out, "    </testcase>").unwrap();
+    }
+}
+
+fn failures(violations: &[&PolicyViolation]) -> usize {
+    violations
+        .iter()
+        .filter(|v| matches!(v.severity, ViolationSeverity::Error))
+        .count()
+}
+
+impl OutputFormatter for JunitFormatter {
+    fn format(&self, result: &ScanResult) -> Result<String> {
+        let root = &result.metadata.root_path;
+        let mut suites = String::new();
+        let (mut tests, mut total_failures) = (0, 0);
+
+        for (_, items) in group_by_file(&result.items) {
+            let file = items[0].relative_path(root);
+            let cases: Vec<(&TodoItem, Vec<&PolicyViolation>)> = items
+                .iter()
+                .map(|item| (*item, self.item_violations(item)))
+                .collect();
+            let suite_failures: usize = cases.iter().map(|(_, v)| failures(v)).sum();
+            writeln!(
+                suites,
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">",
+                escape(&file),
+                cases.len(),
+                suite_failures
+            )
+            .unwrap();
+            for (item, violations) in &cases {
+                let name = format!("L{} {}: {}", item.line, item.tag, item.message);
+                Self::write_case(&mut suites, &file, &name, Some((&file, item.line)), violations);
+            }
+            writeln!(suites, "  </testsuite>").unwrap();
+            tests += cases.len();
+            total_failures += suite_failures;
+        }
+
+        let policy: Vec<&PolicyViolation> =
+            self.violations.iter().filter(|v| v.file.is_none()).collect();
+        if !policy.is_empty() {
+            writeln!(
+                suites,
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">",
+                POLICY_SUITE,
+                policy.len(),
+                failures(&policy)
+            )
+            .unwrap();
+            for v in &policy {
+                Self::write_case(&mut suites, POLICY_SUITE, &v.rule, None, &[v]);
+            }
+            writeln!(suites, "  </testsuite>").unwrap();
+            tests += policy.len();
+            total_failures += failures(&policy);
+        }
+
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(
+            out,
+            "<testsuites name=\"todo-tracker\" tests=\"{}\" failures=\"{}\">",
+            tests, total_failures
+        )
+        .unwrap();
+        out.push_str(&suites);
+        writeln!(out, "</testsuites>").unwrap();
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats, TodoTag};
+    use std::path::PathBuf;
+
+    fn make_item(tag: TodoTag, file: &str, line: usize, message: &str) -> TodoItem {
+        TodoItem {
+            tag,
+            message: message.to_string(),
+            file: PathBuf::from(file),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    fn violation(
+        rule: &str,
+        at: Option<(&str, usize)>,
+        severity: ViolationSeverity,
+    ) -> PolicyViolation {
+        PolicyViolation {
+            rule: rule.to_string(),
+            message: format!("{} violated", rule),
+            file: at.map(|(file, _)| file.to_string()),
+            line: at.map(|(_, line)| line),
+            severity,
+        }
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let result = ScanResult {
+            items: vec![
+                make_item(TodoTag::Todo, "src/main.rs", 3, "use <T> & \"quotes\""),
+                make_item(TodoTag::Fixme, "src/lib.rs", 8, "broken"),
+            ],
+            stats: ScanStats::default(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("."),
+                timestamp: String::new(),
+                blame_cache_hits: None,
+            },
+        };
+        let formatter = JunitFormatter {
+            violations: vec![
+                violation("deny_tags", Some(("src/lib.rs", 8)), ViolationSeverity::Error),
+                violation("escalation", Some(("src/lib.rs", 8)), ViolationSeverity::Warning),
+                violation("max_todos", None, ViolationSeverity::Error),
+            ],
+        };
+        let xml = formatter.format(&result).unwrap();
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuites name=\"todo-tracker\" tests=\"3\" failures=\"2\">\n\
+             \x20 <testsuite name=\"src/lib.rs\" tests=\"1\" failures=\"1\">\n"), "{}", xml);
+        assert!(xml.contains(
+            "    <testcase classname=\"src/lib.rs\" name=\"L8 FIXME: broken\" \
+             file=\"src/lib.rs\" line=\"8\">\n\
+             \x20     <failure type=\"deny_tags\" message=\"deny_tags violated\">\
+             deny_tags violated</failure>\n\
+             \x20     <system-out>warning: escalation: escalation violated</system-out>\n"
+        ));
+        assert!(xml.contains("name=\"L3 TODO: use &lt;T&gt; &amp; &quot;quotes&quot;\" \
+                              file=\"src/main.rs\" line=\"3\"/>"));
+        assert!(xml.contains(
+            "  <testsuite name=\"policy\" tests=\"1\" failures=\"1\">\n\
+             \x20   <testcase classname=\"policy\" name=\"max_todos\">\n"
+        ));
+        assert!(xml.ends_with("</testsuites>\n"));
+
+        let empty = JunitFormatter::default().format(&result).unwrap();
+        assert!(empty.contains("tests=\"2\" failures=\"0\""));
+        assert!(!empty.contains("policy"));
+    }
+}
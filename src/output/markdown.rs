@@ -1,9 +1,8 @@
-use std::collections::BTreeMap;
 use std::fmt::Write;
 
 use crate::error::Result;
 use crate::model::{Priority, ScanResult, TodoItem};
-use crate::output::OutputFormatter;
+use crate::output::{glyphs, OutputFormatter};
 
 pub struct MarkdownFormatter;
 
@@ -21,12 +20,10 @@ impl OutputFormatter for MarkdownFormatter {
             return Ok(out);
         }
 
+        let glyphs = glyphs::current();
+
         // Group items by file path
-        let mut groups: BTreeMap<String, Vec<&TodoItem>> = BTreeMap::new();
-        for item in &result.items {
-            let path = item.file.display().to_string();
-            groups.entry(path).or_default().push(item);
-        }
+        let groups = crate::ordering::group_by_file(&result.items);
 
         for (path, items) in &groups {
             writeln!(out, "## {}", path).unwrap();
@@ -34,12 +31,16 @@ impl OutputFormatter for MarkdownFormatter {
 
             for item in items {
                 let meta = format_metadata(item);
+                let tag = match glyphs.tag(&item.tag) {
+                    Some(emoji) => format!("{} **{}**", emoji, item.tag.as_str()),
+                    None => format!("**{}**", item.tag.as_str()),
+                };
                 write!(
                     out,
-                    "- **{}** (L{}): {}",
-                    item.tag.as_str(),
+                    "- {} (L{}): {}",
+                    tag,
                     item.line,
-                    item.message
+                    glyphs.decorate_message(item.priority.as_ref(), &item.message)
                 )
                 .unwrap();
                 if !meta.is_empty() {
@@ -81,6 +82,14 @@ fn format_metadata(item: &TodoItem) -> String {
         parts.push(p.to_string());
     }
 
+    // Blame context, when enrichment ran
+    match (&item.git_author, &item.git_date) {
+        (Some(author), Some(date)) => parts.push(format!("added by {} on {}", author, date)),
+        (Some(author), None) => parts.push(format!("added by {}", author)),
+        (None, Some(date)) => parts.push(format!("added on {}", date)),
+        (None, None) => {}
+    }
+
     if parts.is_empty() {
         String::new()
     } else {
@@ -123,6 +132,14 @@ mod tests {
                 context_line: "// TODO(alice): Add error handling #123".to_string(),
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
             },
             TodoItem {
                 tag: TodoTag::Fixme,
@@ -136,6 +153,14 @@ mod tests {
                 context_line: "// FIXME: This is broken".to_string(),
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
             },
             TodoItem {
                 tag: TodoTag::Hack,
@@ -149,6 +174,14 @@ mod tests {
                 context_line: "// HACK: Temporary workaround".to_string(),
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
             },
         ];
 
@@ -164,11 +197,13 @@ mod tests {
                 files_with_todos: 2,
                 total_todos: 3,
                 by_tag,
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 42,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         }
     }
@@ -209,6 +244,17 @@ mod tests {
         assert!(output.contains("*(p:high)*"), "Should show priority");
     }
 
+    #[test]
+    fn test_markdown_has_blame_context() {
+        let mut result = sample_result();
+        result.items[0].git_author = Some("bob".to_string());
+        result.items[0].git_date = Some("2024-03-01".to_string());
+        result.items[1].git_date = Some("2024-04-01".to_string());
+        let output = MarkdownFormatter.format(&result).unwrap();
+        assert!(output.contains("*(alice, #123, added by bob on 2024-03-01)*"));
+        assert!(output.contains("*(p:high, added on 2024-04-01)*"));
+    }
+
     #[test]
     fn test_markdown_has_summary() {
         let formatter = MarkdownFormatter;
@@ -229,11 +275,13 @@ mod tests {
                 files_with_todos: 0,
                 total_todos: 0,
                 by_tag: HashMap::new(),
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 10,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         };
         let output = formatter.format(&result).unwrap();
@@ -256,6 +304,14 @@ mod tests {
             context_line: "// HACK: Temporary workaround".to_string(),
             git_author: None,
             git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
         }];
 
         let mut by_tag = HashMap::new();
@@ -268,11 +324,13 @@ mod tests {
                 files_with_todos: 1,
                 total_todos: 1,
                 by_tag,
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 1,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         };
         let output = formatter.format(&result).unwrap();
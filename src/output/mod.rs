@@ -1,9 +1,23 @@
 pub mod text;
+pub mod annotations;
 pub mod json;
+#[cfg(feature = "csv")]
 pub mod csv;
 pub mod markdown;
 pub mod sarif;
 pub mod github_actions;
+pub mod azure;
+pub mod gitlab;
+pub mod bitbucket;
+pub mod teamcity;
+pub mod vscode;
+pub mod junit;
+pub mod charset;
+pub mod glyphs;
+pub mod theme;
+pub mod paint;
+pub mod encrypt;
+pub mod links;
 
 use crate::error::Result;
 use crate::model::ScanResult;
@@ -17,53 +31,156 @@ pub enum OutputFormat {
     Text,
     Json,
     Csv,
+    Tsv,
     Markdown,
     Count,
     Sarif,
     GithubActions,
+    AzurePipelines,
+    GitLab,
+    Bitbucket,
+    BitbucketServer,
+    TeamCity,
+    VsCode,
+    Junit,
+    /// The format of the CI system the run is in, see [`OutputFormat::detect_ci`]
+    AutoCi,
 }
 
 impl OutputFormat {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> std::result::Result<Self, String> {
         match s.to_lowercase().as_str() {
             "text" => Ok(OutputFormat::Text),
             "json" => Ok(OutputFormat::Json),
             "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
             "markdown" | "md" => Ok(OutputFormat::Markdown),
             "count" => Ok(OutputFormat::Count),
             "sarif" => Ok(OutputFormat::Sarif),
             "github-actions" | "github_actions" | "ga" => Ok(OutputFormat::GithubActions),
+            "azure-pipelines" | "azure_pipelines" | "azure" => Ok(OutputFormat::AzurePipelines),
+            "gitlab" | "gitlab-codequality" => Ok(OutputFormat::GitLab),
+            "bitbucket" => Ok(OutputFormat::Bitbucket),
+            "bitbucket-server" | "bitbucket_server" => Ok(OutputFormat::BitbucketServer),
+            "teamcity" => Ok(OutputFormat::TeamCity),
+            "vscode" => Ok(OutputFormat::VsCode),
+            "junit" => Ok(OutputFormat::Junit),
+            "auto-ci" | "auto_ci" => Ok(OutputFormat::AutoCi),
             other => Err(format!("Unknown output format: {}", other)),
         }
     }
+
+    /// The native format of the CI system whose environment variables `env`
+    /// returns: GitHub Actions, GitLab CI, Azure Pipelines, TeamCity or
+    /// Bitbucket Pipelines. `None` outside CI.
+    pub fn detect_ci(env: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let set = |name: &str| env(name).is_some_and(|value| !value.is_empty());
+        if env("GITHUB_ACTIONS").as_deref() == Some("true") {
+            Some(OutputFormat::GithubActions)
+        } else if set("GITLAB_CI") {
+            Some(OutputFormat::GitLab)
+        } else if set("TF_BUILD") {
+            Some(OutputFormat::AzurePipelines)
+        } else if set("TEAMCITY_VERSION") {
+            Some(OutputFormat::TeamCity)
+        } else if set("BITBUCKET_BUILD_NUMBER") {
+            Some(OutputFormat::Bitbucket)
+        } else {
+            None
+        }
+    }
 }
 
 pub fn format_output(result: &ScanResult, format: OutputFormat) -> Result<String> {
     match format {
         OutputFormat::Text => {
-            let formatter = text::TextFormatter { show_summary: true };
+            let formatter = text::TextFormatter::new(true);
             formatter.format(result)
         }
         OutputFormat::Count => Ok(format!("{}", result.stats.total_todos)),
         OutputFormat::Json => {
-            let formatter = json::JsonFormatter;
+            let formatter = json::JsonFormatter::default();
             formatter.format(result)
         }
+        #[cfg(feature = "csv")]
         OutputFormat::Csv => {
-            let formatter = csv::CsvFormatter;
+            let formatter = csv::CsvFormatter::default();
             formatter.format(result)
         }
+        #[cfg(feature = "csv")]
+        OutputFormat::Tsv => {
+            let formatter = csv::CsvFormatter::tsv();
+            formatter.format(result)
+        }
+        #[cfg(not(feature = "csv"))]
+        OutputFormat::Csv | OutputFormat::Tsv => Err(crate::error::TodoError::Config(
+            "CSV output needs the `csv` feature".to_string(),
+        )),
         OutputFormat::Markdown => {
             let formatter = markdown::MarkdownFormatter;
             formatter.format(result)
         }
         OutputFormat::Sarif => {
-            let formatter = sarif::SarifFormatter;
+            let formatter = sarif::SarifFormatter::default();
             formatter.format(result)
         }
         OutputFormat::GithubActions => {
-            let formatter = github_actions::GithubActionsFormatter;
+            let formatter = github_actions::GithubActionsFormatter::default();
+            formatter.format(result)
+        }
+        OutputFormat::AzurePipelines => {
+            let formatter = azure::AzurePipelinesFormatter;
+            formatter.format(result)
+        }
+        OutputFormat::GitLab => {
+            let formatter = gitlab::GitLabFormatter;
+            formatter.format(result)
+        }
+        OutputFormat::Bitbucket => {
+            let formatter = bitbucket::BitbucketFormatter { server: false };
+            formatter.format(result)
+        }
+        OutputFormat::BitbucketServer => {
+            let formatter = bitbucket::BitbucketFormatter { server: true };
+            formatter.format(result)
+        }
+        OutputFormat::TeamCity => {
+            let formatter = teamcity::TeamCityFormatter;
+            formatter.format(result)
+        }
+        OutputFormat::VsCode => {
+            let formatter = vscode::VsCodeFormatter;
+            formatter.format(result)
+        }
+        OutputFormat::Junit => {
+            let formatter = junit::JunitFormatter::default();
             formatter.format(result)
         }
+        OutputFormat::AutoCi => {
+            let detected = OutputFormat::detect_ci(|name| std::env::var(name).ok());
+            format_output(result, detected.unwrap_or(OutputFormat::Text))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_ci() {
+        let detect = |vars: &[(&str, &str)]| {
+            OutputFormat::detect_ci(|name| {
+                vars.iter().find(|(n, _)| *n == name).map(|(_, v)| v.to_string())
+            })
+        };
+        assert_eq!(detect(&[("GITHUB_ACTIONS", "true")]), Some(OutputFormat::GithubActions));
+        assert_eq!(detect(&[("GITLAB_CI", "true")]), Some(OutputFormat::GitLab));
+        assert_eq!(detect(&[("TF_BUILD", "True")]), Some(OutputFormat::AzurePipelines));
+        assert_eq!(detect(&[("TEAMCITY_VERSION", "2024.1")]), Some(OutputFormat::TeamCity));
+        assert_eq!(detect(&[("BITBUCKET_BUILD_NUMBER", "7")]), Some(OutputFormat::Bitbucket));
+        assert_eq!(detect(&[("GITHUB_ACTIONS", "false"), ("GITLAB_CI", "")]), None);
+        assert_eq!(detect(&[]), None);
     }
 }
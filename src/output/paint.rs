@@ -0,0 +1,134 @@
+// Terminal colors for the text formatters. With the `color` feature this is
+// the `colored` crate; without it, the same names with every style a no-op,
+// so formatters are written once and minimal builds print plain text.
+
+#[cfg(feature = "color")]
+pub use colored::{control, Color, ColoredString, Colorize};
+
+#[cfg(not(feature = "color"))]
+pub use self::plain::{control, Color, ColoredString, Colorize};
+
+#[cfg(not(feature = "color"))]
+mod plain {
+    use std::fmt;
+    use std::str::FromStr;
+
+    /// Foreground colors accepted in theme styles.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Color {
+        Black,
+        Red,
+        Green,
+        Yellow,
+        Blue,
+        Magenta,
+        Cyan,
+        White,
+        BrightBlack,
+        BrightRed,
+        BrightGreen,
+        BrightYellow,
+        BrightBlue,
+        BrightMagenta,
+        BrightCyan,
+        BrightWhite,
+    }
+
+    impl FromStr for Color {
+        type Err = ();
+
+        /// Same names as `colored`: "red", "bright red", "bright_red"
+        fn from_str(s: &str) -> Result<Self, ()> {
+            let name = s.to_lowercase().replace(['_', ' '], "");
+            Ok(match name.as_str() {
+                "black" => Color::Black,
+                "red" => Color::Red,
+                "green" => Color::Green,
+                "yellow" => Color::Yellow,
+                "blue" => Color::Blue,
+                "magenta" | "purple" => Color::Magenta,
+                "cyan" => Color::Cyan,
+                "white" => Color::White,
+                "brightblack" => Color::BrightBlack,
+                "brightred" => Color::BrightRed,
+                "brightgreen" => Color::BrightGreen,
+                "brightyellow" => Color::BrightYellow,
+                "brightblue" => Color::BrightBlue,
+                "brightmagenta" | "brightpurple" => Color::BrightMagenta,
+                "brightcyan" => Color::BrightCyan,
+                "brightwhite" => Color::BrightWhite,
+                _ => return Err(()),
+            })
+        }
+    }
+
+    /// Text that would have been styled; displays as-is.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ColoredString(String);
+
+    impl fmt::Display for ColoredString {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            // Pass through width and alignment like `colored` does
+            f.pad(&self.0)
+        }
+    }
+
+    /// The styling methods the formatters use, all returning the text unchanged.
+    pub trait Colorize: Sized {
+        fn normal(self) -> ColoredString;
+
+        fn color(self, _color: Color) -> ColoredString {
+            self.normal()
+        }
+        fn bold(self) -> ColoredString {
+            self.normal()
+        }
+        fn dimmed(self) -> ColoredString {
+            self.normal()
+        }
+        fn italic(self) -> ColoredString {
+            self.normal()
+        }
+        fn underline(self) -> ColoredString {
+            self.normal()
+        }
+        fn red(self) -> ColoredString {
+            self.normal()
+        }
+        fn green(self) -> ColoredString {
+            self.normal()
+        }
+        fn yellow(self) -> ColoredString {
+            self.normal()
+        }
+        fn blue(self) -> ColoredString {
+            self.normal()
+        }
+        fn cyan(self) -> ColoredString {
+            self.normal()
+        }
+    }
+
+    impl Colorize for &str {
+        fn normal(self) -> ColoredString {
+            ColoredString(self.to_string())
+        }
+    }
+
+    impl Colorize for String {
+        fn normal(self) -> ColoredString {
+            ColoredString(self)
+        }
+    }
+
+    impl Colorize for ColoredString {
+        fn normal(self) -> ColoredString {
+            self
+        }
+    }
+
+    /// Nothing to turn on or off without colors.
+    pub mod control {
+        pub fn set_override(_override_colorize: bool) {}
+    }
+}
@@ -1,24 +1,51 @@
 use serde_json::{json, Value};
 
 use crate::error::Result;
-use crate::model::ScanResult;
+use crate::escalation::Escalator;
+use crate::model::{ScanResult, TodoItem};
+use crate::output::annotations::AnnotationLevel;
 use crate::output::OutputFormatter;
+use crate::policy::ViolationSeverity;
 
-pub struct SarifFormatter;
+#[derive(Default)]
+pub struct SarifFormatter {
+    /// Raises the level of items older than its thresholds; the tag's level
+    /// is kept when it is higher
+    pub escalation: Option<Escalator>,
+    /// Results below this level are left out
+    pub min_level: AnnotationLevel,
+}
+
+impl SarifFormatter {
+    /// The level of an item's result: its tag's level, raised by age
+    /// escalation.
+    pub fn level(&self, item: &TodoItem) -> AnnotationLevel {
+        let tag_level = match item.tag.as_str() {
+            "FIXME" | "BUG" => AnnotationLevel::Error,
+            "HACK" | "XXX" => AnnotationLevel::Warning,
+            _ => AnnotationLevel::Note,
+        };
+        match self.escalation.as_ref().and_then(|e| e.escalate(item)) {
+            Some((ViolationSeverity::Error, _)) => AnnotationLevel::Error,
+            Some((ViolationSeverity::Warning, _)) => tag_level.max(AnnotationLevel::Warning),
+            _ => tag_level,
+        }
+    }
+}
 
 impl OutputFormatter for SarifFormatter {
     fn format(&self, result: &ScanResult) -> Result<String> {
         let results: Vec<Value> = result
             .items
             .iter()
-            .map(|item| {
-                json!({
+            .filter_map(|item| {
+                let level = self.level(item);
+                if level < self.min_level {
+                    return None;
+                }
+                Some(json!({
                     "ruleId": format!("todo-tracker/{}", item.tag.as_str().to_lowercase()),
-                    "level": match item.tag.as_str() {
-                        "FIXME" | "BUG" => "error",
-                        "HACK" | "XXX" => "warning",
-                        _ => "note"
-                    },
+                    "level": level.as_str(),
                     "message": {
                         "text": format!("{}: {}", item.tag, item.message)
                     },
@@ -33,7 +60,7 @@ impl OutputFormatter for SarifFormatter {
                             }
                         }
                     }]
-                })
+                }))
             })
             .collect();
 
@@ -66,6 +93,8 @@ impl OutputFormatter for SarifFormatter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::date::Date;
+    use crate::escalation::EscalationConfig;
     use crate::model::{ScanMetadata, ScanStats, TodoItem, TodoTag};
     use std::collections::HashMap;
     use std::path::PathBuf;
@@ -84,6 +113,14 @@ mod tests {
                 context_line: "// TODO: Add tests".to_string(),
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
             },
             TodoItem {
                 tag: TodoTag::Fixme,
@@ -97,6 +134,14 @@ mod tests {
                 context_line: "// FIXME: Handle error".to_string(),
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
             },
         ];
 
@@ -111,18 +156,20 @@ mod tests {
                 files_with_todos: 2,
                 total_todos: 2,
                 by_tag,
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 10,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         }
     }
 
     #[test]
     fn test_sarif_is_valid_json() {
-        let formatter = SarifFormatter;
+        let formatter = SarifFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
@@ -131,7 +178,7 @@ mod tests {
 
     #[test]
     fn test_sarif_version() {
-        let formatter = SarifFormatter;
+        let formatter = SarifFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
@@ -140,7 +187,7 @@ mod tests {
 
     #[test]
     fn test_sarif_has_runs() {
-        let formatter = SarifFormatter;
+        let formatter = SarifFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
@@ -150,7 +197,7 @@ mod tests {
 
     #[test]
     fn test_sarif_tool_name() {
-        let formatter = SarifFormatter;
+        let formatter = SarifFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
@@ -162,7 +209,7 @@ mod tests {
 
     #[test]
     fn test_sarif_results_count() {
-        let formatter = SarifFormatter;
+        let formatter = SarifFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
@@ -172,7 +219,7 @@ mod tests {
 
     #[test]
     fn test_sarif_rule_id_format() {
-        let formatter = SarifFormatter;
+        let formatter = SarifFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
@@ -183,7 +230,7 @@ mod tests {
 
     #[test]
     fn test_sarif_level_mapping() {
-        let formatter = SarifFormatter;
+        let formatter = SarifFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
@@ -192,9 +239,44 @@ mod tests {
         assert_eq!(results[1]["level"], "error"); // FIXME -> error
     }
 
+    #[test]
+    fn test_sarif_level_escalated_by_age() {
+        let mut result = sample_result();
+        result.items[0].git_date = Some("2023-01-01".to_string());
+        result.items[1].git_date = Some("2023-01-01".to_string());
+        let formatter = SarifFormatter {
+            escalation: Some(Escalator {
+                config: EscalationConfig {
+                    warn_after_days: Some(365),
+                    error_after_days: None,
+                },
+                today: Date::parse("2025-01-01").unwrap(),
+            }),
+            ..Default::default()
+        };
+        let output = formatter.format(&result).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let results = parsed["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results[0]["level"], "warning"); // old TODO -> warning
+        assert_eq!(results[1]["level"], "error"); // FIXME stays an error
+    }
+
+    #[test]
+    fn test_sarif_min_level() {
+        let formatter = SarifFormatter {
+            min_level: AnnotationLevel::Warning,
+            ..Default::default()
+        };
+        let output = formatter.format(&sample_result()).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let results = parsed["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["level"], "error");
+    }
+
     #[test]
     fn test_sarif_location_info() {
-        let formatter = SarifFormatter;
+        let formatter = SarifFormatter::default();
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
@@ -206,7 +288,7 @@ mod tests {
 
     #[test]
     fn test_sarif_empty_result() {
-        let formatter = SarifFormatter;
+        let formatter = SarifFormatter::default();
         let result = ScanResult {
             items: vec![],
             stats: ScanStats {
@@ -214,11 +296,13 @@ mod tests {
                 files_with_todos: 0,
                 total_todos: 0,
                 by_tag: HashMap::new(),
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 0,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         };
         let output = formatter.format(&result).unwrap();
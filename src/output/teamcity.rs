@@ -0,0 +1,155 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::error::Result;
+use crate::model::ScanResult;
+use crate::output::OutputFormatter;
+
+/// TeamCity service messages. One `inspectionType` is declared per tag, then
+/// each item is reported as an `inspection`, which TeamCity lists on the
+/// build's Inspections tab.
+pub struct TeamCityFormatter;
+
+/// Escape a value for a service message attribute.
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '|' => out.push_str("||"),
+            '\'' => out.push_str("|'"),
+            '\n' => out.push_str("|n"),
+            '\r' => out.push_str("|r"),
+            '[' => out.push_str("|["),
+            ']' => out.push_str("|]"),
+            '\u{0085}' => out.push_str("|x"),
+            '\u{2028}' => out.push_str("|l"),
+            '\u{2029}' => out.push_str("|p"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn type_id(tag: &str) -> String {
+    format!("todo-tracker.{}", tag)
+}
+
+impl OutputFormatter for TeamCityFormatter {
+    fn format(&self, result: &ScanResult) -> Result<String> {
+        let root = &result.metadata.root_path;
+        let mut out = String::new();
+
+        let tags: BTreeSet<String> = result.items.iter().map(|i| i.tag.to_string()).collect();
+        for tag in &tags {
+            writeln!(
+                out,
+                "##teamcity[inspectionType id='{}' name='{}' description='{} comments' category='TODO comments']",
+                escape(&type_id(tag)),
+                escape(tag),
+                escape(tag)
+            )
+            .unwrap();
+        }
+
+        for item in &result.items {
+            let severity = match item.tag.as_str() {
+                "FIXME" | "BUG" => "ERROR",
+                "HACK" | "XXX" => "WARNING",
+                _ => "WEAK WARNING",
+            };
+            writeln!(
+                out,
+                "##teamcity[inspection typeId='{}' message='{}' file='{}' line='{}' SEVERITY='{}']",
+                escape(&type_id(&item.tag.to_string())),
+                escape(&item.message),
+                escape(&item.relative_path(root)),
+                item.line,
+                severity
+            )
+            .unwrap();
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats, TodoItem, TodoTag};
+    use std::path::PathBuf;
+
+    fn make_item(tag: TodoTag, message: &str, file: &str, line: usize) -> TodoItem {
+        TodoItem {
+            tag,
+            message: message.to_string(),
+            file: PathBuf::from(file),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    fn make_result(items: Vec<TodoItem>) -> ScanResult {
+        ScanResult {
+            stats: ScanStats {
+                total_todos: items.len(),
+                ..Default::default()
+            },
+            items,
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("."),
+                timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("it's [x] a|b\r\n"), "it|'s |[x|] a||b|r|n");
+        assert_eq!(escape("plain"), "plain");
+    }
+
+    #[test]
+    fn test_types_then_inspections() {
+        let result = make_result(vec![
+            make_item(TodoTag::Todo, "later", "./src/a.rs", 3),
+            make_item(TodoTag::Fixme, "don't [panic]", "./src/b.rs", 9),
+            make_item(TodoTag::Todo, "again", "./src/c.rs", 1),
+        ]);
+        let output = TeamCityFormatter.format(&result).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[0],
+            "##teamcity[inspectionType id='todo-tracker.FIXME' name='FIXME' description='FIXME comments' category='TODO comments']"
+        );
+        assert!(lines[1].contains("id='todo-tracker.TODO'"));
+        assert_eq!(
+            lines[2],
+            "##teamcity[inspection typeId='todo-tracker.TODO' message='later' file='src/a.rs' line='3' SEVERITY='WEAK WARNING']"
+        );
+        assert!(lines[3].contains("message='don|'t |[panic|]'"));
+        assert!(lines[3].contains("SEVERITY='ERROR'"));
+    }
+
+    #[test]
+    fn test_empty_result() {
+        let output = TeamCityFormatter.format(&make_result(vec![])).unwrap();
+        assert!(output.is_empty());
+    }
+}
@@ -1,26 +1,104 @@
 use std::collections::BTreeMap;
 use std::fmt::Write;
 
-use colored::*;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+use crate::components::UNASSIGNED;
 use crate::error::Result;
-use crate::model::{Priority, ScanResult, TodoItem, TodoTag};
+use crate::model::{Priority, ScanMetadata, ScanResult, ScanStats, TodoItem, TodoTag};
+use crate::output::charset;
+use crate::output::glyphs::{self, Glyphs};
+use crate::output::paint::{ColoredString, Colorize};
+use crate::output::theme::{self, Theme};
 use crate::output::OutputFormatter;
+use crate::policy::Budget;
+
+/// Messages are never truncated below this many columns; on narrower
+/// terminals the line wraps instead.
+const MIN_MESSAGE_WIDTH: usize = 20;
 
 pub struct TextFormatter {
     pub show_summary: bool,
+    /// Truncate messages so each line fits this many columns; `None` prints
+    /// messages in full.
+    pub max_width: Option<usize>,
+    /// Show the source line under each item, plus this many lines of
+    /// context on either side; `None` shows no snippets.
+    pub snippets: Option<usize>,
+    /// Put the file groups under a heading per component
+    pub group_by_component: bool,
+}
+
+impl TextFormatter {
+    pub fn new(show_summary: bool) -> Self {
+        TextFormatter {
+            show_summary,
+            max_width: None,
+            snippets: None,
+            group_by_component: false,
+        }
+    }
+
+    /// A heading per component, in name order with unassigned items last,
+    /// over that component's items grouped by file.
+    fn format_by_component(&self, result: &ScanResult) -> Result<String> {
+        let theme = theme::current();
+        let mut components: BTreeMap<Option<&str>, Vec<TodoItem>> = BTreeMap::new();
+        for item in &result.items {
+            components.entry(item.component.as_deref()).or_default().push(item.clone());
+        }
+        let by_file = TextFormatter {
+            show_summary: false,
+            group_by_component: false,
+            ..*self
+        };
+
+        let mut out = String::new();
+        let unassigned = components.remove(&None);
+        let groups = components
+            .into_iter()
+            .chain(unassigned.map(|items| (None, items)));
+        for (index, (component, items)) in groups.enumerate() {
+            if index > 0 {
+                writeln!(out).unwrap();
+            }
+            let name = component.unwrap_or(UNASSIGNED);
+            let heading = format!("{} ({})", name, items.len());
+            writeln!(out, "{}", theme.path.paint(&heading)).unwrap();
+            let part = ScanResult {
+                items,
+                stats: ScanStats::default(),
+                metadata: result.metadata.clone(),
+            };
+            for line in by_file.format(&part)?.lines() {
+                if line.is_empty() {
+                    writeln!(out).unwrap();
+                } else {
+                    writeln!(out, "  {}", line).unwrap();
+                }
+            }
+        }
+
+        if self.show_summary {
+            writeln!(out).unwrap();
+            out.push_str(&format_summary(&result.stats, &result.metadata));
+        }
+        Ok(out)
+    }
 }
 
 impl OutputFormatter for TextFormatter {
     fn format(&self, result: &ScanResult) -> Result<String> {
+        if self.group_by_component {
+            return self.format_by_component(result);
+        }
         let mut out = String::new();
 
+        let theme = theme::current();
+        let glyphs = glyphs::current();
+
         // Group items by file path
-        let mut groups: BTreeMap<String, Vec<&TodoItem>> = BTreeMap::new();
-        for item in &result.items {
-            let path = item.file.display().to_string();
-            groups.entry(path).or_default().push(item);
-        }
+        let groups = crate::ordering::group_by_file(&result.items);
 
         let mut first_group = true;
         for (path, items) in &groups {
@@ -29,62 +107,302 @@ impl OutputFormatter for TextFormatter {
             }
             first_group = false;
 
-            // File path in bold
-            writeln!(out, "{}", path.bold()).unwrap();
-
-            for item in items {
-                let line_str = format!("L{}", item.line);
-                let tag_str = colorize_tag(&item.tag);
-                let meta = format_metadata(item);
+            writeln!(out, "{}", theme.path.paint(path)).unwrap();
+
+            // Column widths are shared by every line in the group
+            let lines: Vec<String> = items.iter().map(|i| format!("L{}", i.line)).collect();
+            let metas: Vec<String> = items.iter().map(|i| format_metadata(i)).collect();
+            let line_width = lines.iter().map(|l| l.len()).max().unwrap_or(0).max(5);
+            let labels: Vec<String> = items.iter().map(|i| tag_label(&theme, &glyphs, &i.tag)).collect();
+            let tag_width = labels
+                .iter()
+                .map(|l| l.width())
+                .max()
+                .unwrap_or(0)
+                .max(theme.tag_width());
+            let meta_width = metas.iter().map(|m| m.width()).max().unwrap_or(0);
+            let message_width = self.max_width.map(|max| {
+                let used = 2 + line_width + 2 + tag_width + 1;
+                let meta = if meta_width > 0 { meta_width + 1 } else { 0 };
+                max.saturating_sub(used + meta).max(MIN_MESSAGE_WIDTH)
+            });
+            let messages: Vec<String> = items
+                .iter()
+                .map(|i| {
+                    let message = glyphs.decorate_message(i.priority.as_ref(), &i.message);
+                    match message_width {
+                        Some(width) => truncate(&message, width),
+                        None => message,
+                    }
+                })
+                .collect();
+            let message_column = messages.iter().map(|m| m.width()).max().unwrap_or(0);
+            // Context windows need the file itself; the item line alone is cached
+            let source: Option<Vec<String>> = match self.snippets {
+                // Paths reported from the repository root don't resolve
+                // from a subdirectory
+                Some(context) if context > 0 => std::fs::read(&items[0].file)
+                    .or_else(|_| std::fs::read(result.metadata.root_path.join(&items[0].file)))
+                    .ok()
+                    .map(|bytes| String::from_utf8_lossy(&bytes).lines().map(String::from).collect()),
+                _ => None,
+            };
+
+            for (index, item) in items.iter().enumerate() {
+                let label = &labels[index];
+                let message = &messages[index];
+                let meta = &metas[index];
 
                 write!(
                     out,
-                    "  {:>5}  {:<6} {}",
-                    line_str.dimmed().cyan(),
-                    tag_str,
-                    item.message
+                    "  {}  {}{} {}",
+                    theme.line.paint(&format!("{:>width$}", lines[index], width = line_width)),
+                    colorize_tag(&theme, &item.tag, label),
+                    " ".repeat(tag_width.saturating_sub(label.width())),
+                    message
                 )
                 .unwrap();
 
                 if !meta.is_empty() {
-                    write!(out, " {}", meta.dimmed()).unwrap();
+                    let padding = message_column.saturating_sub(message.width());
+                    write!(out, "{} {}", " ".repeat(padding), theme.metadata.paint(meta)).unwrap();
                 }
                 writeln!(out).unwrap();
+
+                if let Some(context) = self.snippets {
+                    let indent = 2 + line_width + 2;
+                    let snippet = snippet_lines(item, source.as_deref(), context);
+                    self.write_snippet(&mut out, &theme, indent, item.line, &snippet);
+                }
             }
         }
 
         if self.show_summary {
             writeln!(out).unwrap();
-            writeln!(out, "{}", format_summary_rule()).unwrap();
-            writeln!(
+            out.push_str(&format_summary(&result.stats, &result.metadata));
+        }
+
+        Ok(out)
+    }
+}
+
+impl TextFormatter {
+    fn write_snippet(
+        &self,
+        out: &mut String,
+        theme: &Theme,
+        indent: usize,
+        item_line: usize,
+        snippet: &[(usize, &str)],
+    ) {
+        let number_width = snippet
+            .iter()
+            .map(|(n, _)| n.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let gutter = if charset::is_ascii() { "|" } else { "\u{2502}" };
+        // Drop the indentation shared by the whole window
+        let dedent = snippet
+            .iter()
+            .filter(|(_, code)| !code.trim().is_empty())
+            .map(|(_, code)| code.len() - code.trim_start().len())
+            .min()
+            .unwrap_or(0);
+
+        for (number, code) in snippet {
+            let code = code.get(dedent..).unwrap_or("").trim_end();
+            let marker = if *number == item_line { ">" } else { " " };
+            let prefix_width = indent + 2 + number_width + 3;
+            let code = match self.max_width {
+                Some(max) => truncate(code, max.saturating_sub(prefix_width).max(MIN_MESSAGE_WIDTH)),
+                None => code.to_string(),
+            };
+            write!(
                 out,
-                "{} TODOs in {} files (scanned {} files in {}ms)",
-                result.stats.total_todos,
-                result.stats.files_with_todos,
-                result.stats.files_scanned,
-                result.metadata.scan_duration_ms,
+                "{}{} {:>width$} {}",
+                " ".repeat(indent),
+                marker,
+                number,
+                gutter,
+                width = number_width
             )
             .unwrap();
-
-            let breakdown = format_tag_breakdown(&result.stats.by_tag);
-            if !breakdown.is_empty() {
-                writeln!(out, "  {}", breakdown).unwrap();
+            if !code.is_empty() {
+                write!(out, " {}", theme.snippet.paint(&code)).unwrap();
             }
+            writeln!(out).unwrap();
         }
+    }
+}
 
-        Ok(out)
+/// Numbered source lines around `item`. Falls back to the stored
+/// `context_line` when the file could not be read or has changed.
+fn snippet_lines<'a>(
+    item: &'a TodoItem,
+    source: Option<&'a [String]>,
+    context: usize,
+) -> Vec<(usize, &'a str)> {
+    match source {
+        Some(lines)
+            if item.line.checked_sub(1).and_then(|i| lines.get(i)) == Some(&item.context_line) =>
+        {
+            let first = item.line.saturating_sub(context).max(1);
+            let last = (item.line + context).min(lines.len());
+            (first..=last).map(|n| (n, lines[n - 1].as_str())).collect()
+        }
+        _ => vec![(item.line, item.context_line.as_str())],
+    }
+}
+
+/// Width of the terminal on stdout, or `None` when stdout is not a terminal.
+pub fn terminal_width() -> Option<usize> {
+    #[cfg(feature = "color")]
+    return console::Term::stdout()
+        .size_checked()
+        .map(|(_, cols)| cols as usize);
+    // Without terminal support lines are never truncated
+    #[cfg(not(feature = "color"))]
+    None
+}
+
+/// Cut `s` to at most `width` display columns, ending in an ellipsis when
+/// anything was dropped.
+fn truncate(s: &str, width: usize) -> String {
+    if s.width() <= width {
+        return s.to_string();
+    }
+    let ellipsis = if charset::is_ascii() { "..." } else { "\u{2026}" };
+    let budget = width.saturating_sub(ellipsis.width());
+    let mut out = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > budget {
+            break;
+        }
+        used += w;
+        out.push(c);
+    }
+    out.push_str(ellipsis);
+    out
+}
+
+/// The summary block printed after the items.
+pub fn format_summary(stats: &ScanStats, metadata: &ScanMetadata) -> String {
+    let mut out = String::new();
+    writeln!(out, "{}", format_summary_rule()).unwrap();
+    writeln!(
+        out,
+        "{} TODOs in {} files (scanned {} files in {}ms)",
+        stats.total_todos, stats.files_with_todos, stats.files_scanned, metadata.scan_duration_ms,
+    )
+    .unwrap();
+
+    let breakdown = format_tag_breakdown(&stats.by_tag);
+    if !breakdown.is_empty() {
+        writeln!(out, "  {}", breakdown).unwrap();
+    }
+    if stats.suppressed > 0 {
+        let note = format!(
+            "{} suppressed by triage (--show-suppressed to list)",
+            stats.suppressed
+        );
+        writeln!(out, "  {}", note.dimmed()).unwrap();
     }
+    out
 }
 
-fn colorize_tag(tag: &TodoTag) -> ColoredString {
-    let s = tag.as_str();
-    match tag {
-        TodoTag::Todo => s.yellow(),
-        TodoTag::Fixme => s.red(),
-        TodoTag::Hack => s.magenta(),
-        TodoTag::Bug => s.red().bold(),
-        TodoTag::Xxx => s.magenta().bold(),
-        TodoTag::Custom(_) => s.white(),
+/// A one-line summary for stderr, printed when machine-readable output is
+/// piped so that CI logs still show the outcome.
+pub fn format_brief_summary(stats: &ScanStats, metadata: &ScanMetadata) -> String {
+    let mut out = format!(
+        "todos: {} TODOs in {} files (scanned {} files in {}ms)",
+        stats.total_todos, stats.files_with_todos, stats.files_scanned, metadata.scan_duration_ms,
+    );
+    let breakdown = format_tag_breakdown(&stats.by_tag);
+    if !breakdown.is_empty() {
+        write!(out, "; {}", breakdown).unwrap();
+    }
+    out
+}
+
+/// Items hidden by triage ignore decisions, dimmed, under a heading.
+pub fn format_suppressed(items: &[TodoItem]) -> String {
+    let mut out = String::new();
+    writeln!(out, "{}", format!("Suppressed ({}):", items.len()).dimmed()).unwrap();
+    for item in items {
+        let line = format!(
+            "  {}:{}  {}  {}",
+            item.file.display(),
+            item.line,
+            item.tag.as_str(),
+            item.message
+        );
+        writeln!(out, "{}", line.dimmed()).unwrap();
+    }
+    out
+}
+
+/// Budget table for `check --budget`: each rule's value against its limit, the
+/// headroom left and the trend since the baseline.
+pub fn format_budget(budgets: &[Budget], baseline: Option<&str>) -> String {
+    let mut out = String::new();
+    if budgets.is_empty() {
+        writeln!(out, "Budget: no limits configured (--max-todos, --max-todos-per-kloc)").unwrap();
+        return out;
+    }
+    match baseline {
+        Some(name) => writeln!(out, "Budget (trend since {}):", name).unwrap(),
+        None => writeln!(out, "Budget:").unwrap(),
+    }
+    let width = budgets.iter().map(|b| b.rule.len()).max().unwrap_or(0);
+    for b in budgets {
+        let p = b.precision;
+        let headroom = b.headroom();
+        let left = if headroom >= 0.0 {
+            format!("{:.*} left", p, headroom).green()
+        } else {
+            format!("{:.*} over", p, -headroom).red()
+        };
+        let mut line = format!(
+            "  {:<width$}  {:.*} / {:.*}  {}",
+            b.rule,
+            p,
+            b.current,
+            p,
+            b.limit,
+            left,
+            width = width
+        );
+        if let Some(base) = b.baseline {
+            let (up, down, flat) = if charset::is_ascii() {
+                ("^", "v", "=")
+            } else {
+                ("\u{2191}", "\u{2193}", "\u{2192}")
+            };
+            // Compare at the precision shown, so "+0.0" never gets an arrow
+            let delta = b.current - base;
+            let (arrow, delta) = match (delta * 10f64.powi(p as i32)).round() {
+                d if d > 0.0 => (up.red(), delta),
+                d if d < 0.0 => (down.green(), delta),
+                _ => (flat.normal(), 0.0),
+            };
+            write!(line, "  {} {:+.*}", arrow, p, delta).unwrap();
+        }
+        writeln!(out, "{}", line).unwrap();
+    }
+    out
+}
+
+fn colorize_tag(theme: &Theme, tag: &TodoTag, label: &str) -> ColoredString {
+    theme.tag_style(tag).paint(label)
+}
+
+/// Tag label with its emoji, if glyphs are on.
+fn tag_label(theme: &Theme, glyphs: &Glyphs, tag: &TodoTag) -> String {
+    match glyphs.tag(tag) {
+        Some(emoji) => format!("{} {}", emoji, theme.tag_label(tag)),
+        None => theme.tag_label(tag),
     }
 }
 
@@ -121,7 +439,7 @@ fn format_metadata(item: &TodoItem) -> String {
 }
 
 fn format_summary_rule() -> String {
-    format!("\u{2500}\u{2500} Summary {}", "\u{2500}".repeat(30))
+    format!("{} Summary {}", charset::rule(2), charset::rule(30))
 }
 
 fn format_tag_breakdown(by_tag: &std::collections::HashMap<String, usize>) -> String {
@@ -171,6 +489,14 @@ mod tests {
                 context_line: "// TODO(alice): Add error handling #123".to_string(),
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
             },
             TodoItem {
                 tag: TodoTag::Fixme,
@@ -184,6 +510,14 @@ mod tests {
                 context_line: "// FIXME: This is broken".to_string(),
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
             },
             TodoItem {
                 tag: TodoTag::Hack,
@@ -197,6 +531,14 @@ mod tests {
                 context_line: "// HACK: Temporary workaround".to_string(),
                 git_author: None,
                 git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
             },
         ];
 
@@ -212,11 +554,13 @@ mod tests {
                 files_with_todos: 2,
                 total_todos: 3,
                 by_tag,
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 42,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         }
     }
@@ -224,9 +568,9 @@ mod tests {
     #[test]
     fn test_format_contains_file_paths() {
         // Disable colors for deterministic test output
-        colored::control::set_override(false);
+        crate::output::paint::control::set_override(false);
 
-        let formatter = TextFormatter { show_summary: true };
+        let formatter = TextFormatter::new(true);
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
 
@@ -236,9 +580,9 @@ mod tests {
 
     #[test]
     fn test_format_contains_line_numbers() {
-        colored::control::set_override(false);
+        crate::output::paint::control::set_override(false);
 
-        let formatter = TextFormatter { show_summary: true };
+        let formatter = TextFormatter::new(true);
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
 
@@ -249,9 +593,9 @@ mod tests {
 
     #[test]
     fn test_format_contains_tags() {
-        colored::control::set_override(false);
+        crate::output::paint::control::set_override(false);
 
-        let formatter = TextFormatter { show_summary: true };
+        let formatter = TextFormatter::new(true);
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
 
@@ -262,9 +606,9 @@ mod tests {
 
     #[test]
     fn test_format_contains_messages() {
-        colored::control::set_override(false);
+        crate::output::paint::control::set_override(false);
 
-        let formatter = TextFormatter { show_summary: true };
+        let formatter = TextFormatter::new(true);
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
 
@@ -275,9 +619,9 @@ mod tests {
 
     #[test]
     fn test_format_contains_metadata() {
-        colored::control::set_override(false);
+        crate::output::paint::control::set_override(false);
 
-        let formatter = TextFormatter { show_summary: true };
+        let formatter = TextFormatter::new(true);
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
 
@@ -288,9 +632,9 @@ mod tests {
 
     #[test]
     fn test_format_summary() {
-        colored::control::set_override(false);
+        crate::output::paint::control::set_override(false);
 
-        let formatter = TextFormatter { show_summary: true };
+        let formatter = TextFormatter::new(true);
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
 
@@ -308,19 +652,52 @@ mod tests {
         assert!(output.contains("HACK: 1"), "Should contain HACK count");
     }
 
+    #[test]
+    fn test_format_brief_summary() {
+        let result = sample_result();
+        let summary = format_brief_summary(&result.stats, &result.metadata);
+        assert!(!summary.contains('\n'));
+        assert!(summary.starts_with("todos: 3 TODOs in 2 files (scanned 15 files in 42ms); "));
+        assert!(summary.contains("FIXME: 1"));
+    }
+
     #[test]
     fn test_format_no_summary() {
-        colored::control::set_override(false);
+        crate::output::paint::control::set_override(false);
 
-        let formatter = TextFormatter {
-            show_summary: false,
-        };
+        let formatter = TextFormatter::new(false);
         let result = sample_result();
         let output = formatter.format(&result).unwrap();
 
         assert!(!output.contains("Summary"), "Should not contain Summary when disabled");
     }
 
+    #[test]
+    fn test_format_budget() {
+        crate::output::paint::control::set_override(false);
+
+        let budget = |rule: &str, current, limit, baseline, precision| Budget {
+            rule: rule.to_string(),
+            current,
+            limit,
+            baseline,
+            precision,
+        };
+        let output = format_budget(
+            &[
+                budget("max_todos", 42.0, 50.0, Some(39.0), 0),
+                budget("max_todos_per_kloc", 5.5, 5.0, Some(5.52), 1),
+            ],
+            Some("snapshot 2026-02-05T000000Z"),
+        );
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "Budget (trend since snapshot 2026-02-05T000000Z):");
+        assert_eq!(lines[1], "  max_todos           42 / 50  8 left  \u{2191} +3");
+        assert_eq!(lines[2], "  max_todos_per_kloc  5.5 / 5.0  0.5 over  \u{2192} +0.0");
+
+        assert!(format_budget(&[], None).contains("no limits configured"));
+    }
+
     #[test]
     fn test_format_metadata_all_fields() {
         let item = TodoItem {
@@ -335,6 +712,14 @@ mod tests {
             context_line: String::new(),
             git_author: None,
             git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
         };
 
         let meta = format_metadata(&item);
@@ -355,6 +740,14 @@ mod tests {
             context_line: String::new(),
             git_author: None,
             git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
         };
 
         let meta = format_metadata(&item);
@@ -375,6 +768,14 @@ mod tests {
             context_line: String::new(),
             git_author: None,
             git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
         };
 
         let meta = format_metadata(&item);
@@ -383,9 +784,9 @@ mod tests {
 
     #[test]
     fn test_empty_result() {
-        colored::control::set_override(false);
+        crate::output::paint::control::set_override(false);
 
-        let formatter = TextFormatter { show_summary: true };
+        let formatter = TextFormatter::new(true);
         let result = ScanResult {
             items: vec![],
             stats: ScanStats {
@@ -393,11 +794,13 @@ mod tests {
                 files_with_todos: 0,
                 total_todos: 0,
                 by_tag: HashMap::new(),
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 10,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         };
         let output = formatter.format(&result).unwrap();
@@ -411,11 +814,76 @@ mod tests {
     #[test]
     fn test_colorize_tag_variants() {
         // Just verify the function doesn't panic for all variants
-        colorize_tag(&TodoTag::Todo);
-        colorize_tag(&TodoTag::Fixme);
-        colorize_tag(&TodoTag::Hack);
-        colorize_tag(&TodoTag::Bug);
-        colorize_tag(&TodoTag::Xxx);
-        colorize_tag(&TodoTag::Custom("WARN".to_string()));
+        for theme in [Theme::default(), Theme::high_contrast(), Theme::monochrome()] {
+            colorize_tag(&theme, &TodoTag::Todo, "label");
+            colorize_tag(&theme, &TodoTag::Fixme, "label");
+            colorize_tag(&theme, &TodoTag::Hack, "label");
+            colorize_tag(&theme, &TodoTag::Bug, "label");
+            colorize_tag(&theme, &TodoTag::Xxx, "label");
+            colorize_tag(&theme, &TodoTag::Custom("WARN".to_string()), "label");
+        }
+    }
+
+    #[test]
+    fn test_truncates_to_width_and_aligns_metadata() {
+        crate::output::paint::control::set_override(false);
+
+        let mut result = sample_result();
+        result.items[1].message = "This is broken in a way that takes a long sentence to explain".to_string();
+        let formatter = TextFormatter {
+            show_summary: false,
+            max_width: Some(60),
+            snippets: None,
+            group_by_component: false,
+        };
+        let output = formatter.format(&result).unwrap();
+        let lines: Vec<&str> = output.lines().filter(|l| l.contains("  L")).collect();
+
+        assert!(lines.iter().all(|l| l.width() <= 60), "{}", output);
+        assert!(lines[2].contains("\u{2026} (p:high)"), "{}", output);
+        // Metadata starts in the same column for every item in the file
+        let meta_column = |l: &str| l[..l.find('(').unwrap()].width();
+        assert_eq!(meta_column(lines[1]), meta_column(lines[2]));
+
+        let full = TextFormatter::new(false).format(&result).unwrap();
+        assert!(full.contains("takes a long sentence to explain"));
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("abcdefghij", 5), "abcd\u{2026}");
+        assert_eq!(truncate("日本語のテキスト", 7), "日本語\u{2026}");
+    }
+
+    #[test]
+    fn test_snippets() {
+        crate::output::paint::control::set_override(false);
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.rs");
+        std::fs::write(&file, "fn main() {\n    // TODO: tidy\n    run();\n}\n").unwrap();
+        let mut result = sample_result();
+        result.items.truncate(1);
+        result.items[0].file = file;
+        result.items[0].line = 2;
+        result.items[0].context_line = "    // TODO: tidy".to_string();
+
+        let mut formatter = TextFormatter::new(false);
+        formatter.snippets = Some(0);
+        let output = formatter.format(&result).unwrap();
+        assert!(output.contains("> 2 \u{2502} // TODO: tidy\n"), "{}", output);
+
+        formatter.snippets = Some(1);
+        let output = formatter.format(&result).unwrap();
+        assert!(output.contains("  1 \u{2502} fn main() {\n"), "{}", output);
+        assert!(output.contains("> 2 \u{2502}     // TODO: tidy\n"), "{}", output);
+        assert!(output.contains("  3 \u{2502}     run();\n"), "{}", output);
+
+        // A file that changed since the scan falls back to the stored line
+        std::fs::write(&result.items[0].file, "// rewritten\n").unwrap();
+        let output = formatter.format(&result).unwrap();
+        assert!(output.contains("> 2 \u{2502} // TODO: tidy\n"), "{}", output);
+        assert!(!output.contains("rewritten"));
     }
 }
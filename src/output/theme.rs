@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TodoError};
+use crate::model::TodoTag;
+use crate::output::paint::{Color, ColoredString, Colorize};
+
+/// `[theme]` config section.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ThemeConfig {
+    /// Built-in theme to start from: "default", "high-contrast" or "monochrome"
+    pub name: Option<String>,
+    /// Prefix each tag with a symbol so tags stay distinguishable without color
+    pub symbols: Option<bool>,
+    /// Style of file path headers, e.g. "bold"
+    pub path: Option<String>,
+    /// Style of `L<n>` line numbers
+    pub line: Option<String>,
+    /// Style of the `(author, #issue, p:priority)` suffix
+    pub metadata: Option<String>,
+    /// Style of source lines shown by `--snippets`
+    pub snippet: Option<String>,
+    /// Per-tag styles keyed by tag name, e.g. `TODO = "bright yellow bold"`
+    pub tags: Option<HashMap<String, String>>,
+}
+
+/// A foreground color plus text attributes, parsed from strings such as
+/// `"red"`, `"bright blue bold"` or `"dimmed"`. `"none"` is plain text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bold: bool,
+    pub dimmed: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl Style {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut style = Style::default();
+        let mut color_words: Vec<&str> = Vec::new();
+        for word in spec.split_whitespace() {
+            match word.to_lowercase().as_str() {
+                "bold" => style.bold = true,
+                "dimmed" | "dim" => style.dimmed = true,
+                "italic" => style.italic = true,
+                "underline" => style.underline = true,
+                "none" | "plain" => {}
+                _ => color_words.push(word),
+            }
+        }
+        if !color_words.is_empty() {
+            let name = color_words.join(" ");
+            let color = name.parse::<Color>().map_err(|_| {
+                TodoError::Config(format!("Unknown color '{}' in theme style '{}'", name, spec))
+            })?;
+            style.fg = Some(color);
+        }
+        Ok(style)
+    }
+
+    fn fg(color: Color) -> Self {
+        Style {
+            fg: Some(color),
+            ..Default::default()
+        }
+    }
+
+    fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    fn dimmed(mut self) -> Self {
+        self.dimmed = true;
+        self
+    }
+
+    fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    pub fn paint(&self, s: &str) -> ColoredString {
+        let mut out = s.normal();
+        if let Some(color) = self.fg {
+            out = out.color(color);
+        }
+        if self.bold {
+            out = out.bold();
+        }
+        if self.dimmed {
+            out = out.dimmed();
+        }
+        if self.italic {
+            out = out.italic();
+        }
+        if self.underline {
+            out = out.underline();
+        }
+        out
+    }
+}
+
+/// Styles used by the text formatter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub tags: HashMap<String, Style>,
+    /// Style for tags without an entry in `tags`
+    pub other_tag: Style,
+    pub path: Style,
+    pub line: Style,
+    pub metadata: Style,
+    pub snippet: Style,
+    pub symbols: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            tags: [
+                ("TODO", Style::fg(Color::Yellow)),
+                ("FIXME", Style::fg(Color::Red)),
+                ("HACK", Style::fg(Color::Magenta)),
+                ("BUG", Style::fg(Color::Red).bold()),
+                ("XXX", Style::fg(Color::Magenta).bold()),
+            ]
+            .into_iter()
+            .map(|(tag, style)| (tag.to_string(), style))
+            .collect(),
+            other_tag: Style::fg(Color::White),
+            path: Style::default().bold(),
+            line: Style::fg(Color::Cyan).dimmed(),
+            metadata: Style::default().dimmed(),
+            snippet: Style::default().dimmed(),
+            symbols: false,
+        }
+    }
+}
+
+impl Theme {
+    /// Bright, bold colors and no dimmed text, for low-contrast terminals.
+    pub fn high_contrast() -> Self {
+        Theme {
+            tags: [
+                ("TODO", Style::fg(Color::BrightYellow).bold()),
+                ("FIXME", Style::fg(Color::BrightRed).bold()),
+                ("HACK", Style::fg(Color::BrightMagenta).bold()),
+                ("BUG", Style::fg(Color::BrightRed).bold().underline()),
+                ("XXX", Style::fg(Color::BrightMagenta).bold().underline()),
+            ]
+            .into_iter()
+            .map(|(tag, style)| (tag.to_string(), style))
+            .collect(),
+            other_tag: Style::fg(Color::BrightWhite).bold(),
+            path: Style::fg(Color::BrightWhite).bold().underline(),
+            line: Style::fg(Color::BrightCyan),
+            metadata: Style::fg(Color::BrightWhite),
+            snippet: Style::default(),
+            symbols: false,
+        }
+    }
+
+    /// No colors; tags are told apart by symbols and weight instead of hue.
+    pub fn monochrome() -> Self {
+        Theme {
+            tags: [
+                ("FIXME", Style::default().bold()),
+                ("BUG", Style::default().bold().underline()),
+                ("XXX", Style::default().underline()),
+            ]
+            .into_iter()
+            .map(|(tag, style)| (tag.to_string(), style))
+            .collect(),
+            other_tag: Style::default(),
+            path: Style::default().bold(),
+            line: Style::default(),
+            metadata: Style::default(),
+            snippet: Style::default(),
+            symbols: true,
+        }
+    }
+
+    pub fn by_name(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "default" => Ok(Theme::default()),
+            "high-contrast" | "high_contrast" => Ok(Theme::high_contrast()),
+            "monochrome" | "mono" => Ok(Theme::monochrome()),
+            other => Err(TodoError::Config(format!(
+                "Unknown theme '{}' (expected default, high-contrast or monochrome)",
+                other
+            ))),
+        }
+    }
+
+    /// Start from the named built-in theme and apply the overrides.
+    pub fn from_config(config: &ThemeConfig) -> Result<Self> {
+        let mut theme = Theme::by_name(config.name.as_deref().unwrap_or("default"))?;
+        if let Some(symbols) = config.symbols {
+            theme.symbols = symbols;
+        }
+        if let Some(ref spec) = config.path {
+            theme.path = Style::parse(spec)?;
+        }
+        if let Some(ref spec) = config.line {
+            theme.line = Style::parse(spec)?;
+        }
+        if let Some(ref spec) = config.metadata {
+            theme.metadata = Style::parse(spec)?;
+        }
+        if let Some(ref spec) = config.snippet {
+            theme.snippet = Style::parse(spec)?;
+        }
+        for (tag, spec) in config.tags.iter().flatten() {
+            theme.tags.insert(tag.to_uppercase(), Style::parse(spec)?);
+        }
+        Ok(theme)
+    }
+
+    pub fn tag_style(&self, tag: &TodoTag) -> &Style {
+        self.tags.get(tag.as_str()).unwrap_or(&self.other_tag)
+    }
+
+    /// Tag label as shown in text output, with its symbol when enabled.
+    pub fn tag_label(&self, tag: &TodoTag) -> String {
+        if self.symbols {
+            format!("{} {}", tag_symbol(tag), tag.as_str())
+        } else {
+            tag.as_str().to_string()
+        }
+    }
+
+    /// Width the tag column is padded to.
+    pub fn tag_width(&self) -> usize {
+        if self.symbols {
+            8
+        } else {
+            6
+        }
+    }
+}
+
+fn tag_symbol(tag: &TodoTag) -> &'static str {
+    match tag {
+        TodoTag::Bug => "!!",
+        TodoTag::Fixme => "! ",
+        TodoTag::Xxx => "? ",
+        TodoTag::Hack => "~ ",
+        TodoTag::Todo => "* ",
+        TodoTag::Custom(_) => "- ",
+    }
+}
+
+static ACTIVE: RwLock<Option<Theme>> = RwLock::new(None);
+
+/// Set the theme used by text output for the rest of the process.
+pub fn set_theme(theme: Theme) {
+    *ACTIVE.write().unwrap_or_else(|e| e.into_inner()) = Some(theme);
+}
+
+/// The theme set with `set_theme`, or the default theme.
+pub fn current() -> Theme {
+    ACTIVE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+/// Color preference from the `NO_COLOR` and `CLICOLOR_FORCE` conventions, for
+/// `--color=auto`. `NO_COLOR` wins when both are set; `None` leaves the
+/// decision to terminal detection.
+pub fn color_from_env(no_color: Option<&str>, clicolor_force: Option<&str>) -> Option<bool> {
+    if no_color.is_some_and(|v| !v.is_empty()) {
+        Some(false)
+    } else if clicolor_force.is_some_and(|v| !v.is_empty() && v != "0") {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_style() {
+        let style = Style::parse("bright blue bold").unwrap();
+        assert_eq!(style.fg, Some(Color::BrightBlue));
+        assert!(style.bold && !style.dimmed);
+        assert_eq!(Style::parse("none").unwrap(), Style::default());
+        assert!(Style::parse("chartreuse").is_err());
+    }
+
+    #[test]
+    fn test_from_config_overrides_preset() {
+        let config = ThemeConfig {
+            name: Some("monochrome".to_string()),
+            tags: Some(HashMap::from([("note".to_string(), "green".to_string())])),
+            ..Default::default()
+        };
+        let theme = Theme::from_config(&config).unwrap();
+        assert!(theme.symbols);
+        assert_eq!(
+            theme.tag_style(&TodoTag::Custom("NOTE".to_string())).fg,
+            Some(Color::Green)
+        );
+        assert_eq!(theme.tag_style(&TodoTag::Todo), &Style::default());
+        assert_eq!(theme.tag_label(&TodoTag::Bug), "!! BUG");
+
+        let bad = ThemeConfig {
+            name: Some("neon".to_string()),
+            ..Default::default()
+        };
+        assert!(Theme::from_config(&bad).is_err());
+    }
+
+    #[test]
+    fn test_color_from_env() {
+        assert_eq!(color_from_env(None, None), None);
+        assert_eq!(color_from_env(Some("1"), Some("1")), Some(false));
+        assert_eq!(color_from_env(Some(""), Some("1")), Some(true));
+        assert_eq!(color_from_env(None, Some("0")), None);
+    }
+}
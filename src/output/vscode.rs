@@ -0,0 +1,120 @@
+use std::fmt::Write;
+
+use crate::error::Result;
+use crate::model::ScanResult;
+use crate::output::OutputFormatter;
+
+/// One line per item as `severity(file,line,col): TAG message`, with paths
+/// relative to the scan root, for a VS Code task's problem matcher:
+///
+/// ```json
+/// "problemMatcher": {
+///   "owner": "todos",
+///   "fileLocation": ["relative", "${workspaceFolder}"],
+///   "pattern": {
+///     "regexp": "^(error|warning|info)\\((.*),(\\d+),(\\d+)\\): (\\S+) (.*)$",
+///     "severity": 1,
+///     "file": 2,
+///     "line": 3,
+///     "column": 4,
+///     "code": 5,
+///     "message": 6
+///   }
+/// }
+/// ```
+pub struct VsCodeFormatter;
+
+impl OutputFormatter for VsCodeFormatter {
+    fn format(&self, result: &ScanResult) -> Result<String> {
+        let root = &result.metadata.root_path;
+        let mut out = String::new();
+        for item in &result.items {
+            let severity = match item.tag.as_str() {
+                "FIXME" | "BUG" => "error",
+                "HACK" | "XXX" => "warning",
+                _ => "info",
+            };
+            // The matcher is line based
+            let message: String = item
+                .message
+                .chars()
+                .map(|c| if c == '\n' || c == '\r' { ' ' } else { c })
+                .collect();
+            writeln!(
+                out,
+                "{}({},{},{}): {} {}",
+                severity,
+                item.relative_path(root),
+                item.line,
+                item.column,
+                item.tag,
+                message
+            )
+            .unwrap();
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats, TodoItem, TodoTag};
+    use std::path::PathBuf;
+
+    fn make_item(tag: TodoTag, message: &str, file: &str, line: usize, col: usize) -> TodoItem {
+        TodoItem {
+            tag,
+            message: message.to_string(),
+            file: PathBuf::from(file),
+            line,
+            column: col,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_problem_matcher_lines() {
+        let items = vec![
+            make_item(TodoTag::Todo, "later", "/repo/src/a.rs", 3, 5),
+            make_item(TodoTag::Bug, "off by one,\nsee (x)", "/repo/src/b, c.rs", 9, 1),
+            make_item(TodoTag::Hack, "temporary", "/repo/lib.rs", 1, 4),
+        ];
+        let result = ScanResult {
+            stats: ScanStats {
+                total_todos: items.len(),
+                ..Default::default()
+            },
+            items,
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("/repo"),
+                timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
+            },
+        };
+        let output = VsCodeFormatter.format(&result).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "info(src/a.rs,3,5): TODO later",
+                "error(src/b, c.rs,9,1): BUG off by one, see (x)",
+                "warning(lib.rs,1,4): HACK temporary",
+            ]
+        );
+    }
+}
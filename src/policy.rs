@@ -1,17 +1,39 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-use crate::model::ScanResult;
+use crate::date::Date;
+use crate::escalation::{self, EscalationConfig, Escalator};
+use crate::lint::{check_lint, LintConfig};
+use crate::model::{ScanResult, TodoItem};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PolicyConfig {
     /// Maximum number of TODOs allowed
     pub max_todos: Option<usize>,
+    /// Maximum TODOs per thousand lines of code
+    pub max_todos_per_kloc: Option<f64>,
     /// Tags that require an issue reference (e.g., ["FIXME", "BUG"])
     pub require_issue: Option<Vec<String>>,
     /// Tags that are completely denied (e.g., ["NOCOMMIT"])
     pub deny_tags: Option<Vec<String>>,
     /// Maximum age in days for TODOs (requires git blame data)
     pub max_age_days: Option<u64>,
+    /// Message-quality rules (reported under the `lint` category)
+    pub lint: Option<LintConfig>,
+    /// Age thresholds that make old items warnings or errors (requires git
+    /// blame data)
+    pub escalation: Option<EscalationConfig>,
+    /// Maximum TODOs per component, checked as `max_todos:<component>`
+    pub component_budgets: Option<BTreeMap<String, usize>>,
+    /// Maximum disabled tests, when `[scan] skipped_tests` reports them
+    pub max_skipped_tests: Option<usize>,
+    /// Maximum panic sites, when `[scan] panic_audit` reports them
+    pub max_panic_sites: Option<usize>,
+    /// Leave items in test code out of `max_todos`, `max_todos_per_kloc` and
+    /// component budgets
+    #[serde(default)]
+    pub exempt_test_code: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,17 +60,352 @@ impl std::fmt::Display for ViolationSeverity {
     }
 }
 
+/// Whether `item` opts out of `rule` with an `allow:` token. A token names the
+/// rule or a prefix of it ending at `_` or `:`, so `allow:max_age` covers
+/// `max_age_days` and `allow:lint` covers every `lint:` rule.
+pub fn is_exempt(item: &TodoItem, rule: &str) -> bool {
+    item.exemptions.iter().any(|allow| {
+        rule.strip_prefix(allow.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(['_', ':']))
+    })
+}
+
+/// Items carrying exemption tokens, for the audit listing in check output.
+pub fn exempt_items(result: &ScanResult) -> Vec<&TodoItem> {
+    result
+        .items
+        .iter()
+        .filter(|item| !item.exemptions.is_empty())
+        .collect()
+}
+
+/// Whether an aggregate TODO limit leaves `item` out: code markers always,
+/// test code when `exempt_test_code` is set.
+fn uncounted(item: &TodoItem, config: &PolicyConfig) -> bool {
+    item.is_code_marker() || (config.exempt_test_code && item.in_test_code)
+}
+
+/// Items counted by an aggregate rule: all of them, less code markers
+/// (disabled tests, deprecations and panic sites), exempt test code and
+/// those exempt from it.
+fn counted(result: &ScanResult, config: &PolicyConfig, rule: &str) -> usize {
+    let uncounted = result
+        .items
+        .iter()
+        .filter(|i| uncounted(i, config) || is_exempt(i, rule))
+        .count();
+    result.stats.total_todos.saturating_sub(uncounted)
+}
+
+/// Disabled tests, less those exempt from `max_skipped_tests`.
+fn counted_skipped(result: &ScanResult) -> usize {
+    result
+        .items
+        .iter()
+        .filter(|i| i.is_skipped_test() && !is_exempt(i, "max_skipped_tests"))
+        .count()
+}
+
+/// Panic sites, less those exempt from `max_panic_sites`.
+fn counted_panics(result: &ScanResult) -> usize {
+    result
+        .items
+        .iter()
+        .filter(|i| i.is_panic_site() && !is_exempt(i, "max_panic_sites"))
+        .count()
+}
+
+/// Items in `component`, less those left out as by [`counted`].
+fn counted_component(result: &ScanResult, config: &PolicyConfig, component: &str) -> usize {
+    let rule = component_rule(component);
+    result
+        .items
+        .iter()
+        .filter(|i| i.component.as_deref() == Some(component))
+        .filter(|i| !uncounted(i, config) && !is_exempt(i, &rule))
+        .count()
+}
+
+/// Rule name of a component's budget. `allow:max_todos` covers every component.
+fn component_rule(component: &str) -> String {
+    format!("max_todos:{}", component)
+}
+
+/// TODOs per thousand lines, less items exempt from `max_todos_per_kloc`.
+fn counted_density(result: &ScanResult, config: &PolicyConfig) -> Option<f64> {
+    let lines = result.stats.lines_of_code;
+    (lines > 0)
+        .then(|| counted(result, config, "max_todos_per_kloc") as f64 * 1000.0 / lines as f64)
+}
+
+/// An aggregate rule's current value against its limit, for the budget report.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Budget {
+    pub rule: String,
+    pub current: f64,
+    pub limit: f64,
+    /// Value of the same rule in the baseline, when there is one
+    pub baseline: Option<f64>,
+    /// Decimal places to show values with
+    #[serde(skip)]
+    pub precision: usize,
+}
+
+impl Budget {
+    /// How far below the limit the current value is (negative when over).
+    pub fn headroom(&self) -> f64 {
+        self.limit - self.current
+    }
+}
+
+/// Budgets for the configured aggregate rules (`max_todos`,
+/// `max_todos_per_kloc`, component budgets, `max_skipped_tests` and
+/// `max_panic_sites`), with trends against `baseline` if given.
+pub fn budget_report(
+    result: &ScanResult,
+    config: &PolicyConfig,
+    baseline: Option<&ScanResult>,
+) -> Vec<Budget> {
+    let mut budgets = Vec::new();
+    if let Some(max) = config.max_todos {
+        budgets.push(Budget {
+            rule: "max_todos".to_string(),
+            current: counted(result, config, "max_todos") as f64,
+            limit: max as f64,
+            baseline: baseline.map(|b| counted(b, config, "max_todos") as f64),
+            precision: 0,
+        });
+    }
+    let density = counted_density(result, config);
+    if let (Some(max), Some(density)) = (config.max_todos_per_kloc, density) {
+        budgets.push(Budget {
+            rule: "max_todos_per_kloc".to_string(),
+            current: density,
+            limit: max,
+            baseline: baseline.and_then(|b| counted_density(b, config)),
+            precision: 1,
+        });
+    }
+    for (component, max) in config.component_budgets.iter().flatten() {
+        budgets.push(Budget {
+            rule: component_rule(component),
+            current: counted_component(result, config, component) as f64,
+            limit: *max as f64,
+            baseline: baseline.map(|b| counted_component(b, config, component) as f64),
+            precision: 0,
+        });
+    }
+    if let Some(max) = config.max_skipped_tests {
+        budgets.push(Budget {
+            rule: "max_skipped_tests".to_string(),
+            current: counted_skipped(result) as f64,
+            limit: max as f64,
+            baseline: baseline.map(|b| counted_skipped(b) as f64),
+            precision: 0,
+        });
+    }
+    if let Some(max) = config.max_panic_sites {
+        budgets.push(Budget {
+            rule: "max_panic_sites".to_string(),
+            current: counted_panics(result) as f64,
+            limit: max as f64,
+            baseline: baseline.map(|b| counted_panics(b) as f64),
+            precision: 0,
+        });
+    }
+    budgets
+}
+
+/// Names of the rules `config` enables, in the order `check_policies` runs them.
+pub fn evaluated_rules(config: &PolicyConfig) -> Vec<String> {
+    let mut rules = Vec::new();
+    if config.max_todos.is_some() {
+        rules.push("max_todos".to_string());
+    }
+    if config.max_todos_per_kloc.is_some() {
+        rules.push("max_todos_per_kloc".to_string());
+    }
+    for component in config.component_budgets.iter().flat_map(|b| b.keys()) {
+        rules.push(component_rule(component));
+    }
+    if config.max_skipped_tests.is_some() {
+        rules.push("max_skipped_tests".to_string());
+    }
+    if config.max_panic_sites.is_some() {
+        rules.push("max_panic_sites".to_string());
+    }
+    if config.require_issue.is_some() {
+        rules.push("require_issue".to_string());
+    }
+    if config.deny_tags.is_some() {
+        rules.push("deny_tags".to_string());
+    }
+    if config.max_age_days.is_some() {
+        rules.push("max_age_days".to_string());
+    }
+    if let Some(ref lint) = config.lint {
+        rules.extend(lint.rules());
+    }
+    if config.escalation.as_ref().is_some_and(|e| !e.is_empty()) {
+        rules.push(escalation::RULE.to_string());
+    }
+    rules
+}
+
+/// Machine-readable outcome of `check`, written by `--report-file`.
+#[derive(Debug, Serialize)]
+pub struct CheckReport {
+    pub passed: bool,
+    pub rules: Vec<String>,
+    pub counts: CheckCounts,
+    pub violations: Vec<PolicyViolation>,
+    pub exemptions: Vec<CheckExemption>,
+    pub budgets: Vec<Budget>,
+    /// Whether the TODO list differed from the `--against-file` golden file
+    pub golden_mismatch: bool,
+    pub timings: CheckTimings,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckCounts {
+    pub files_scanned: usize,
+    pub files_with_todos: usize,
+    pub total_todos: usize,
+    pub lines_of_code: usize,
+    pub by_tag: BTreeMap<String, usize>,
+    pub violations: usize,
+    pub exemptions: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckExemption {
+    pub file: String,
+    pub line: usize,
+    pub tag: String,
+    pub rules: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckTimings {
+    pub scan_ms: u64,
+    pub check_ms: u64,
+    pub total_ms: u64,
+}
+
+impl CheckReport {
+    pub fn new(
+        result: &ScanResult,
+        config: &PolicyConfig,
+        violations: Vec<PolicyViolation>,
+        budgets: Vec<Budget>,
+        golden_mismatch: bool,
+        timings: CheckTimings,
+    ) -> Self {
+        let exemptions: Vec<CheckExemption> = exempt_items(result)
+            .into_iter()
+            .map(|item| CheckExemption {
+                file: item.file.display().to_string(),
+                line: item.line,
+                tag: item.tag.to_string(),
+                rules: item.exemptions.clone(),
+            })
+            .collect();
+        let stats = &result.stats;
+        CheckReport {
+            passed: !has_errors(&violations) && !golden_mismatch,
+            rules: evaluated_rules(config),
+            counts: CheckCounts {
+                files_scanned: stats.files_scanned,
+                files_with_todos: stats.files_with_todos,
+                total_todos: stats.total_todos,
+                lines_of_code: stats.lines_of_code,
+                by_tag: stats.by_tag.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+                violations: violations.len(),
+                exemptions: exemptions.len(),
+            },
+            violations,
+            exemptions,
+            budgets,
+            golden_mismatch,
+            timings,
+        }
+    }
+}
+
 pub fn check_policies(result: &ScanResult, config: &PolicyConfig) -> Vec<PolicyViolation> {
     let mut violations = Vec::new();
 
     // Check max_todos
     if let Some(max) = config.max_todos {
-        if result.stats.total_todos > max {
+        let total = counted(result, config, "max_todos");
+        if total > max {
             violations.push(PolicyViolation {
                 rule: "max_todos".to_string(),
+                message: format!("Found {} TODOs, maximum allowed is {}", total, max),
+                file: None,
+                line: None,
+                severity: ViolationSeverity::Error,
+            });
+        }
+    }
+
+    // Check max_todos_per_kloc
+    let density = counted_density(result, config);
+    if let (Some(max), Some(density)) = (config.max_todos_per_kloc, density) {
+        if density > max {
+            violations.push(PolicyViolation {
+                rule: "max_todos_per_kloc".to_string(),
+                message: format!(
+                    "Found {:.1} TODOs per 1000 lines of code, maximum allowed is {}",
+                    density, max
+                ),
+                file: None,
+                line: None,
+                severity: ViolationSeverity::Error,
+            });
+        }
+    }
+
+    // Check per-component budgets
+    for (component, max) in config.component_budgets.iter().flatten() {
+        let total = counted_component(result, config, component);
+        if total > *max {
+            violations.push(PolicyViolation {
+                rule: component_rule(component),
                 message: format!(
-                    "Found {} TODOs, maximum allowed is {}",
-                    result.stats.total_todos, max
+                    "Found {} TODOs in component {}, maximum allowed is {}",
+                    total, component, max
+                ),
+                file: None,
+                line: None,
+                severity: ViolationSeverity::Error,
+            });
+        }
+    }
+
+    // Check max_skipped_tests
+    if let Some(max) = config.max_skipped_tests {
+        let total = counted_skipped(result);
+        if total > max {
+            violations.push(PolicyViolation {
+                rule: "max_skipped_tests".to_string(),
+                message: format!("Found {} disabled tests, maximum allowed is {}", total, max),
+                file: None,
+                line: None,
+                severity: ViolationSeverity::Error,
+            });
+        }
+    }
+
+    // Check max_panic_sites
+    if let Some(max) = config.max_panic_sites {
+        let total = counted_panics(result);
+        if total > max {
+            violations.push(PolicyViolation {
+                rule: "max_panic_sites".to_string(),
+                message: format!(
+                    "Found {} unwrap/expect/panic! call sites, maximum allowed is {}",
+                    total, max
                 ),
                 file: None,
                 line: None,
@@ -61,7 +418,10 @@ pub fn check_policies(result: &ScanResult, config: &PolicyConfig) -> Vec<PolicyV
     if let Some(ref require_tags) = config.require_issue {
         for item in &result.items {
             let tag_upper = item.tag.as_str().to_uppercase();
-            if require_tags.iter().any(|t| t.to_uppercase() == tag_upper) && item.issue.is_none() {
+            if require_tags.iter().any(|t| t.to_uppercase() == tag_upper)
+                && item.issue.is_none()
+                && !is_exempt(item, "require_issue")
+            {
                 violations.push(PolicyViolation {
                     rule: "require_issue".to_string(),
                     message: format!(
@@ -82,7 +442,7 @@ pub fn check_policies(result: &ScanResult, config: &PolicyConfig) -> Vec<PolicyV
     if let Some(ref deny) = config.deny_tags {
         for item in &result.items {
             let tag_upper = item.tag.as_str().to_uppercase();
-            if deny.iter().any(|t| t.to_uppercase() == tag_upper) {
+            if deny.iter().any(|t| t.to_uppercase() == tag_upper) && !is_exempt(item, "deny_tags") {
                 violations.push(PolicyViolation {
                     rule: "deny_tags".to_string(),
                     message: format!(
@@ -99,13 +459,68 @@ pub fn check_policies(result: &ScanResult, config: &PolicyConfig) -> Vec<PolicyV
         }
     }
 
+    // Check max_age_days
+    if let Some(max) = config.max_age_days {
+        violations.extend(check_max_age(result, max, Date::today()));
+    }
+
+    // Check message lint rules
+    if let Some(ref lint) = config.lint {
+        violations.extend(check_lint(result, lint));
+    }
+
+    // Escalate old items
+    if let Some(ref escalation) = config.escalation {
+        let escalator = Escalator {
+            config: escalation.clone(),
+            today: Date::today(),
+        };
+        violations.extend(escalator.check(result));
+    }
+
     violations
 }
 
+/// Items whose line was last committed more than `max` days before `today`.
+/// Items without blame data are never too old.
+fn check_max_age(result: &ScanResult, max: u64, today: Date) -> Vec<PolicyViolation> {
+    result
+        .items
+        .iter()
+        .filter(|item| !is_exempt(item, "max_age_days"))
+        .filter_map(|item| {
+            let age = escalation::age_days(item, today).filter(|age| *age > max)?;
+            Some(PolicyViolation {
+                rule: "max_age_days".to_string(),
+                message: format!(
+                    "{} at {}:{} is {} days old, maximum allowed is {}",
+                    item.tag,
+                    item.file.display(),
+                    item.line,
+                    age,
+                    max
+                ),
+                file: Some(item.file.display().to_string()),
+                line: Some(item.line),
+                severity: ViolationSeverity::Error,
+            })
+        })
+        .collect()
+}
+
+/// Whether any violation is an error; warnings alone don't fail `check`.
+pub fn has_errors(violations: &[PolicyViolation]) -> bool {
+    violations
+        .iter()
+        .any(|v| matches!(v.severity, ViolationSeverity::Error))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{ScanMetadata, ScanStats, TodoItem, TodoTag};
+    use crate::model::{
+        ScanMetadata, ScanStats, TodoItem, TodoTag, PANIC_TAG, SKIPPED_TEST_TAG,
+    };
     use std::collections::HashMap;
     use std::path::PathBuf;
 
@@ -122,6 +537,14 @@ mod tests {
             context_line: String::new(),
             git_author: None,
             git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
         }
     }
 
@@ -138,11 +561,13 @@ mod tests {
                 files_with_todos: 2,
                 total_todos: total,
                 by_tag,
+                ..Default::default()
             },
             metadata: ScanMetadata {
                 scan_duration_ms: 10,
                 root_path: PathBuf::from("."),
                 timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
             },
         }
     }
@@ -319,4 +744,251 @@ mod tests {
         assert!(rules.contains(&"require_issue"));
         assert!(rules.contains(&"deny_tags"));
     }
+
+    #[test]
+    fn test_lint_rules_reported_with_policies() {
+        let mut item = make_item("TODO", "src/main.rs", 1, None);
+        item.message = String::new();
+        let result = make_result(vec![item, make_item("HACK", "src/lib.rs", 2, None)]);
+        let config = PolicyConfig {
+            deny_tags: Some(vec!["HACK".to_string()]),
+            lint: Some(LintConfig {
+                deny_empty: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let violations = check_policies(&result, &config);
+        let rules: Vec<&str> = violations.iter().map(|v| v.rule.as_str()).collect();
+        assert_eq!(rules, vec!["deny_tags", "lint:empty"]);
+    }
+
+    #[test]
+    fn test_is_exempt_matches_rule_prefixes() {
+        let mut item = make_item("TODO", "src/main.rs", 1, None);
+        item.exemptions = vec!["max_age".to_string(), "lint".to_string()];
+        assert!(is_exempt(&item, "max_age_days"));
+        assert!(is_exempt(&item, "lint:generic"));
+        assert!(!is_exempt(&item, "max_todos"));
+        item.exemptions = vec!["max".to_string()];
+        assert!(!is_exempt(&item, "maximum"));
+    }
+
+    #[test]
+    fn test_exempt_items_skip_rules() {
+        let mut exempt = make_item("FIXME", "src/main.rs", 1, None);
+        exempt.exemptions = vec!["require_issue".to_string(), "max_todos".to_string()];
+        exempt.message = String::new();
+        let result = make_result(vec![
+            exempt,
+            make_item("FIXME", "src/lib.rs", 2, None),
+            make_item("TODO", "src/lib.rs", 3, None),
+        ]);
+        let config = PolicyConfig {
+            max_todos: Some(2),
+            require_issue: Some(vec!["FIXME".to_string()]),
+            lint: Some(LintConfig {
+                deny_empty: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let violations = check_policies(&result, &config);
+        let rules: Vec<&str> = violations.iter().map(|v| v.rule.as_str()).collect();
+        // Only lint:empty still applies to the exempt item
+        assert_eq!(rules, vec!["require_issue", "lint:empty"]);
+        assert_eq!(violations[0].line, Some(2));
+        assert_eq!(exempt_items(&result).len(), 1);
+    }
+
+    #[test]
+    fn test_max_age_days() {
+        let mut items = vec![
+            make_item("TODO", "a.rs", 1, None),
+            make_item("FIXME", "a.rs", 2, None),
+            make_item("TODO", "b.rs", 3, None),
+            make_item("TODO", "b.rs", 4, None),
+        ];
+        items[0].git_date = Some("2024-01-01T10:00:00Z".to_string());
+        items[1].git_date = Some("2024-05-01".to_string());
+        items[2].git_date = Some("2023-01-01".to_string());
+        items[2].exemptions = vec!["max_age".to_string()];
+        let result = make_result(items);
+
+        let today = Date::parse("2024-06-01").unwrap();
+        let violations = check_max_age(&result, 90, today);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "max_age_days");
+        assert_eq!(violations[0].message, "TODO at a.rs:1 is 152 days old, maximum allowed is 90");
+        assert_eq!(violations[0].file.as_deref(), Some("a.rs"));
+        assert_eq!(violations[0].line, Some(1));
+        assert!(has_errors(&violations));
+
+        let config = PolicyConfig {
+            max_age_days: Some(90),
+            ..Default::default()
+        };
+        assert_eq!(evaluated_rules(&config), ["max_age_days"]);
+    }
+
+    #[test]
+    fn test_component_budgets() {
+        let mut items = vec![
+            make_item("TODO", "billing/a.rs", 1, None),
+            make_item("TODO", "billing/a.rs", 2, None),
+            make_item("TODO", "billing/b.rs", 1, None),
+            make_item("TODO", "search/a.rs", 1, None),
+        ];
+        for item in &mut items[..3] {
+            item.component = Some("billing".to_string());
+        }
+        items[2].exemptions = vec!["max_todos".to_string()];
+        items[3].component = Some("search".to_string());
+        let result = make_result(items);
+        let config = PolicyConfig {
+            component_budgets: Some(
+                [("billing".to_string(), 1), ("search".to_string(), 1)].into_iter().collect(),
+            ),
+            ..Default::default()
+        };
+
+        let violations = check_policies(&result, &config);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "max_todos:billing");
+        assert_eq!(
+            violations[0].message,
+            "Found 2 TODOs in component billing, maximum allowed is 1"
+        );
+        assert_eq!(evaluated_rules(&config), ["max_todos:billing", "max_todos:search"]);
+        let budgets = budget_report(&result, &config, None);
+        assert_eq!((budgets[0].current, budgets[1].current), (2.0, 1.0));
+    }
+
+    #[test]
+    fn test_max_skipped_tests() {
+        let mut items = vec![
+            make_item("TODO", "src/lib.rs", 1, None),
+            make_item(SKIPPED_TEST_TAG, "tests/a.rs", 3, None),
+            make_item(SKIPPED_TEST_TAG, "tests/a.rs", 9, None),
+            make_item(SKIPPED_TEST_TAG, "tests/b.rs", 2, None),
+        ];
+        items[3].exemptions = vec!["max_skipped_tests".to_string()];
+        let result = make_result(items);
+        let config = PolicyConfig {
+            max_todos: Some(1),
+            max_skipped_tests: Some(1),
+            ..Default::default()
+        };
+
+        // Disabled tests don't count as TODOs
+        let violations = check_policies(&result, &config);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "max_skipped_tests");
+        assert_eq!(violations[0].message, "Found 2 disabled tests, maximum allowed is 1");
+        let budgets = budget_report(&result, &config, None);
+        assert_eq!((budgets[0].current, budgets[1].current), (1.0, 2.0));
+    }
+
+    #[test]
+    fn test_exempt_test_code() {
+        let mut items = vec![
+            make_item("TODO", "src/lib.rs", 1, None),
+            make_item("TODO", "tests/a.rs", 1, None),
+            make_item("TODO", "tests/a.rs", 2, None),
+        ];
+        items[1].in_test_code = true;
+        items[2].in_test_code = true;
+        let result = make_result(items);
+        let mut config = PolicyConfig {
+            max_todos: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(check_policies(&result, &config).len(), 1);
+
+        config.exempt_test_code = true;
+        assert!(check_policies(&result, &config).is_empty());
+        assert_eq!(budget_report(&result, &config, None)[0].current, 1.0);
+    }
+
+    #[test]
+    fn test_max_panic_sites() {
+        let result = make_result(vec![
+            make_item("TODO", "src/lib.rs", 1, None),
+            make_item(PANIC_TAG, "src/lib.rs", 4, None),
+            make_item(PANIC_TAG, "src/lib.rs", 8, None),
+        ]);
+        let config = PolicyConfig {
+            max_todos: Some(1),
+            max_panic_sites: Some(1),
+            ..Default::default()
+        };
+        let violations = check_policies(&result, &config);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "max_panic_sites");
+        assert_eq!(evaluated_rules(&config), ["max_todos", "max_panic_sites"]);
+    }
+
+    #[test]
+    fn test_budget_report() {
+        let mut result = make_result(vec![
+            make_item("TODO", "src/main.rs", 1, None),
+            make_item("TODO", "src/main.rs", 2, None),
+            make_item("TODO", "src/main.rs", 3, None),
+        ]);
+        result.stats.lines_of_code = 1000;
+        let baseline = make_result(vec![make_item("TODO", "src/main.rs", 1, None)]);
+        let config = PolicyConfig {
+            max_todos: Some(5),
+            max_todos_per_kloc: Some(2.0),
+            ..Default::default()
+        };
+
+        let budgets = budget_report(&result, &config, Some(&baseline));
+        assert_eq!(budgets.len(), 2);
+        assert_eq!(budgets[0].rule, "max_todos");
+        assert_eq!((budgets[0].current, budgets[0].baseline), (3.0, Some(1.0)));
+        assert_eq!(budgets[0].headroom(), 2.0);
+        // The baseline has no line counts, so there's no density to compare with
+        assert_eq!(budgets[1].rule, "max_todos_per_kloc");
+        assert_eq!((budgets[1].current, budgets[1].baseline), (3.0, None));
+        assert_eq!(budgets[1].headroom(), -1.0);
+
+        assert!(budget_report(&result, &PolicyConfig::default(), None).is_empty());
+    }
+
+    #[test]
+    fn test_check_report() {
+        let mut exempt = make_item("FIXME", "src/main.rs", 1, None);
+        exempt.exemptions = vec!["require_issue".to_string()];
+        let result = make_result(vec![exempt, make_item("FIXME", "src/lib.rs", 2, None)]);
+        let config = PolicyConfig {
+            max_todos: Some(10),
+            require_issue: Some(vec!["FIXME".to_string()]),
+            lint: Some(LintConfig {
+                min_length: Some(3),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let violations = check_policies(&result, &config);
+        let budgets = budget_report(&result, &config, None);
+        let timings = CheckTimings {
+            scan_ms: 10,
+            check_ms: 1,
+            total_ms: 12,
+        };
+        let report = CheckReport::new(&result, &config, violations, budgets, false, timings);
+
+        assert!(!report.passed);
+        assert_eq!(report.rules, vec!["max_todos", "require_issue", "lint:min_length"]);
+        assert_eq!(report.counts.violations, 1);
+        assert_eq!(report.counts.exemptions, 1);
+        assert_eq!(report.exemptions[0].rules, vec!["require_issue"]);
+        assert_eq!(report.budgets[0].headroom(), 8.0);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["counts"]["by_tag"]["FIXME"], 2);
+        assert_eq!(json["violations"][0]["rule"], "require_issue");
+        assert!(json["budgets"][0].get("precision").is_none());
+    }
 }
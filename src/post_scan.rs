@@ -0,0 +1,145 @@
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TodoError};
+use crate::model::ScanResult;
+
+/// `[hooks]` config section.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HooksConfig {
+    /// Shell command given the scan result as JSON on stdin. JSON it prints
+    /// replaces the result before formatting; printing nothing keeps it.
+    pub post_scan: Option<String>,
+}
+
+/// Run `command` through the shell in `root`, piping it `result` as JSON, and
+/// return the result it prints back, or `result` unchanged if it prints
+/// nothing. Fields the command adds to items are kept in their `extra` map
+/// and show up in JSON output.
+pub fn run_post_scan(command: &str, result: ScanResult, root: &Path) -> Result<ScanResult> {
+    let fail = |message: String| TodoError::Hook(format!("post_scan: {}", message));
+    let input = serde_json::to_vec(&result).map_err(|e| fail(e.to_string()))?;
+
+    let mut child = shell(command)
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| fail(format!("failed to run '{}': {}", command, e)))?;
+
+    // Write from a thread so a script that prints before it has read all of
+    // its input can't deadlock against us
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = std::thread::spawn(move || {
+        // A script that ignores its input may exit before reading it all
+        let _ = stdin.write_all(&input);
+    });
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let reader = std::thread::spawn(move || {
+        let mut err = String::new();
+        let _ = stderr.read_to_string(&mut err);
+        err
+    });
+    let mut stdout = Vec::new();
+    child
+        .stdout
+        .take()
+        .expect("stdout is piped")
+        .read_to_end(&mut stdout)?;
+    let status = child.wait()?;
+    let _ = writer.join();
+    let stderr = reader.join().unwrap_or_default();
+
+    if !status.success() {
+        let detail = stderr.trim();
+        return Err(fail(if detail.is_empty() {
+            format!("'{}' failed ({})", command, status)
+        } else {
+            format!("'{}' failed ({}): {}", command, status, detail)
+        }));
+    }
+    if stdout.iter().all(u8::is_ascii_whitespace) {
+        return Ok(result);
+    }
+    serde_json::from_slice(&stdout)
+        .map_err(|e| fail(format!("'{}' printed an invalid scan result: {}", command, e)))
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats, TodoItem, TodoTag};
+    use std::path::PathBuf;
+
+    fn result() -> ScanResult {
+        ScanResult {
+            items: vec![TodoItem {
+                tag: TodoTag::Todo,
+                message: "retry".to_string(),
+                file: PathBuf::from("src/billing/pay.rs"),
+                line: 3,
+                column: 4,
+                author: None,
+                issue: None,
+                priority: None,
+                context_line: "// TODO: retry".to_string(),
+                git_author: None,
+                git_date: None,
+                git_commit: None,
+                git_summary: None,
+                pr_number: None,
+                exemptions: Vec::new(),
+                blame_incomplete: false,
+                component: None,
+                in_test_code: false,
+                extra: Default::default(),
+            }],
+            stats: ScanStats::default(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("."),
+                timestamp: String::new(),
+                blame_cache_hits: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_post_scan_replaces_result() {
+        let dir = tempfile::tempdir().unwrap();
+        // Add a field to every item without a JSON tool
+        let script = r#"sed 's/"line":/"service":"billing","line":/g'"#;
+        let enriched = run_post_scan(script, result(), dir.path()).unwrap();
+        assert_eq!(enriched.items[0].extra["service"], "billing");
+        let json = serde_json::to_string(&enriched.items[0]).unwrap();
+        assert!(json.contains(r#""service":"billing""#), "{}", json);
+
+        // No output keeps the result
+        let kept = run_post_scan("cat > /dev/null", result(), dir.path()).unwrap();
+        assert_eq!(kept.items[0].message, "retry");
+        assert!(kept.items[0].extra.is_empty());
+
+        let err = run_post_scan("echo nope >&2; exit 3", result(), dir.path()).unwrap_err();
+        assert!(err.to_string().ends_with("nope"), "{}", err);
+        let err = run_post_scan("echo '{'", result(), dir.path()).unwrap_err();
+        assert!(err.to_string().contains("invalid scan result"), "{}", err);
+    }
+}
@@ -1,21 +1,45 @@
+#[cfg(feature = "progress")]
 use std::io::IsTerminal;
 
+#[cfg(feature = "progress")]
 use indicatif::{ProgressBar, ProgressStyle};
 
+#[cfg(feature = "progress")]
+use crate::output::charset;
+
+/// Stand-in for builds without the `progress` feature, which never show a bar
+#[cfg(not(feature = "progress"))]
+struct ProgressBar;
+
+#[cfg(not(feature = "progress"))]
+impl ProgressBar {
+    fn inc(&self, _delta: u64) {}
+
+    fn finish_and_clear(&self) {}
+}
+
 pub struct ScanProgress {
     bar: Option<ProgressBar>,
 }
 
 impl ScanProgress {
+    #[cfg(not(feature = "progress"))]
+    pub fn new(_total: u64) -> Self {
+        ScanProgress { bar: None }
+    }
+
+    #[cfg(feature = "progress")]
     pub fn new(total: u64) -> Self {
         let bar = if std::io::stderr().is_terminal() && total > 1000 {
             let pb = ProgressBar::new(total);
-            pb.set_style(
-                ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} files ({eta})")
-                    .unwrap()
-                    .progress_chars("=>-"),
-            );
+            let mut style = ProgressStyle::default_bar()
+                .template("{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} files ({eta})")
+                .unwrap()
+                .progress_chars("=>-");
+            if charset::is_ascii() {
+                style = style.tick_chars("-\\|/ ");
+            }
+            pb.set_style(style);
             Some(pb)
         } else {
             None
@@ -0,0 +1,472 @@
+use std::io::{BufRead, Write};
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::Result;
+use crate::filter::FilterCriteria;
+use crate::model::{Priority, ScanResult, ScanStats};
+
+/// JSON-RPC 2.0 error codes.
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// Method of the notification sent to subscribers when the TODO list changes.
+pub const CHANGED_NOTIFICATION: &str = "todos/changed";
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: Option<String>,
+    /// Absent for notifications, which get no response
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// `query` parameters. All are optional and AND-combined.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct QueryParams {
+    tags: Option<Vec<String>>,
+    authors: Option<Vec<String>>,
+    /// Glob on the file path
+    file: Option<String>,
+    priority: Option<String>,
+    has_issue: Option<bool>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileParams {
+    file: String,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Rescans the tree and returns a fresh result.
+pub type Rescan<'a> = Box<dyn FnMut() -> Result<ScanResult> + 'a>;
+
+/// Called with the previous and the new result when a rescan changed it.
+pub type OnChange<'a> = Box<dyn FnMut(&ScanResult, &ScanResult) + 'a>;
+
+/// Answers JSON-RPC requests from an in-memory scan result, so editor
+/// plugins can query TODOs without respawning scans.
+///
+/// Methods: `query` (filtered items), `statsForFile`, `rescan`,
+/// `subscribe` / `unsubscribe` (push `todos/changed` notifications when a
+/// rescan changes the result) and `shutdown`.
+pub struct RpcServer<'a> {
+    result: ScanResult,
+    rescan: Rescan<'a>,
+    on_change: Option<OnChange<'a>>,
+    subscribed: bool,
+    shutdown: bool,
+}
+
+impl<'a> RpcServer<'a> {
+    /// Runs the first scan.
+    pub fn new(mut rescan: Rescan<'a>) -> Result<Self> {
+        let result = rescan()?;
+        Ok(RpcServer {
+            result,
+            rescan,
+            on_change: None,
+            subscribed: false,
+            shutdown: false,
+        })
+    }
+
+    /// Call `on_change` after every rescan that changes the result. The tree
+    /// is then rescanned every poll, even with no client subscribed.
+    pub fn with_on_change(mut self, on_change: OnChange<'a>) -> Self {
+        self.on_change = Some(on_change);
+        self
+    }
+
+    pub fn is_subscribed(&self) -> bool {
+        self.subscribed
+    }
+
+    /// Whether anyone is told about changes, so polling is worthwhile.
+    pub fn is_watched(&self) -> bool {
+        self.subscribed || self.on_change.is_some()
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown
+    }
+
+    /// Handle one line of input. Returns the response to write, if any.
+    pub fn handle_line(&mut self, line: &str) -> Option<Value> {
+        let request: Request = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                let code = if serde_json::from_str::<Value>(line).is_ok() {
+                    INVALID_REQUEST
+                } else {
+                    PARSE_ERROR
+                };
+                return Some(error_response(Value::Null, RpcError::new(code, e.to_string())));
+            }
+        };
+        let id = request.id.clone();
+        let outcome = if request.jsonrpc.as_deref() != Some("2.0") {
+            Err(RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""))
+        } else {
+            self.dispatch(&request.method, request.params)
+        };
+        let id = id?;
+        Some(match outcome {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(e) => error_response(id, e),
+        })
+    }
+
+    fn dispatch(&mut self, method: &str, params: Value) -> std::result::Result<Value, RpcError> {
+        match method {
+            "query" => {
+                let params: QueryParams = parse_params(params)?;
+                self.query(params)
+            }
+            "statsForFile" => {
+                let params: FileParams = parse_params(params)?;
+                Ok(self.stats_for_file(&params.file))
+            }
+            "rescan" => {
+                self.refresh().map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()))?;
+                Ok(to_value(&self.result.stats))
+            }
+            "subscribe" => {
+                self.subscribed = true;
+                Ok(json!({"subscribed": true}))
+            }
+            "unsubscribe" => {
+                self.subscribed = false;
+                Ok(json!({"subscribed": false}))
+            }
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            other => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method '{}'", other),
+            )),
+        }
+    }
+
+    fn query(&self, params: QueryParams) -> std::result::Result<Value, RpcError> {
+        let priority = match params.priority {
+            Some(ref p) => Some(Priority::from_str_tag(p).ok_or_else(|| {
+                RpcError::new(INVALID_PARAMS, format!("Unknown priority '{}'", p))
+            })?),
+            None => None,
+        };
+        let filter = FilterCriteria {
+            tags: params.tags,
+            authors: params.authors,
+            file_pattern: params.file,
+            priority,
+            has_issue: params.has_issue,
+            no_message: None,
+        };
+        let mut items = filter.apply(&self.result.items);
+        let total = items.len();
+        if let Some(limit) = params.limit {
+            items.truncate(limit);
+        }
+        Ok(json!({"total": total, "items": items}))
+    }
+
+    /// Stats of the items in one file, given relative to the scan root.
+    fn stats_for_file(&self, file: &str) -> Value {
+        let root = &self.result.metadata.root_path;
+        let file = file.trim_start_matches("./");
+        let mut stats = ScanStats::new();
+        for item in &self.result.items {
+            if item.relative_path(root).trim_start_matches("./") == file {
+                stats.add_item(item);
+            }
+        }
+        stats.files_scanned = 1;
+        stats.files_with_todos = usize::from(stats.total_todos > 0);
+        to_value(&stats)
+    }
+
+    /// Rescan; returns whether the set of items changed.
+    pub fn refresh(&mut self) -> Result<bool> {
+        let result = (self.rescan)()?;
+        let changed = item_keys(&result) != item_keys(&self.result);
+        if let (true, Some(on_change)) = (changed, self.on_change.as_mut()) {
+            on_change(&self.result, &result);
+        }
+        self.result = result;
+        Ok(changed)
+    }
+
+    /// The notification sent to subscribers after a change.
+    pub fn changed_notification(&self) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "method": CHANGED_NOTIFICATION,
+            "params": to_value(&self.result.stats),
+        })
+    }
+}
+
+/// What identifies the result for change detection: each item's
+/// fingerprint and line.
+fn item_keys(result: &ScanResult) -> Vec<(String, usize)> {
+    result
+        .items
+        .iter()
+        .map(|item| (item.fingerprint(&result.metadata.root_path), item.line))
+        .collect()
+}
+
+/// Deserialize request params; omitted params read as `{}`.
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> std::result::Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn to_value<T: serde::Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": error.code, "message": error.message},
+    })
+}
+
+/// Serve newline-delimited JSON-RPC from `input` to `output` until
+/// `shutdown` or end of input. While a client is subscribed, or changes go to
+/// a callback, the tree is rescanned every `poll`; subscribed clients get a
+/// notification written when it changed.
+pub fn serve<R, W>(server: &mut RpcServer, input: R, mut output: W, poll: Duration) -> Result<()>
+where
+    R: BufRead + Send + 'static,
+    W: Write,
+{
+    let (tx, rx) = crossbeam_channel::unbounded();
+    std::thread::spawn(move || {
+        for line in input.lines() {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let write = |output: &mut W, value: &Value| -> Result<()> {
+        writeln!(output, "{}", value)?;
+        output.flush()?;
+        Ok(())
+    };
+
+    while !server.is_shutdown() {
+        match rx.recv_timeout(poll) {
+            Ok(line) => {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if let Some(response) = server.handle_line(&line) {
+                    write(&mut output, &response)?;
+                }
+            }
+            Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
+                if server.is_watched() && server.refresh()? && server.is_subscribed() {
+                    write(&mut output, &server.changed_notification())?;
+                }
+            }
+            Err(crossbeam_channel::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, TodoItem, TodoTag};
+    use std::cell::RefCell;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+
+    fn make_item(file: &str, line: usize, tag: TodoTag) -> TodoItem {
+        TodoItem {
+            tag,
+            message: format!("item {}", line),
+            file: PathBuf::from(file),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    fn make_result(items: Vec<TodoItem>) -> ScanResult {
+        let mut result = ScanResult {
+            items,
+            stats: ScanStats::default(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("/repo"),
+                timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
+            },
+        };
+        result.recompute_stats();
+        result
+    }
+
+    fn server(results: Rc<RefCell<Vec<ScanResult>>>) -> RpcServer<'static> {
+        RpcServer::new(Box::new(move || {
+            let mut results = results.borrow_mut();
+            let next = if results.len() > 1 {
+                results.remove(0)
+            } else {
+                results[0].clone()
+            };
+            Ok(next)
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_query_and_stats_for_file() {
+        let results = Rc::new(RefCell::new(vec![make_result(vec![
+            make_item("/repo/src/a.rs", 1, TodoTag::Todo),
+            make_item("/repo/src/a.rs", 2, TodoTag::Fixme),
+            make_item("/repo/src/b.rs", 1, TodoTag::Todo),
+        ])]));
+        let mut server = server(results);
+
+        let response = server
+            .handle_line(r#"{"jsonrpc":"2.0","id":1,"method":"query","params":{"tags":["TODO"],"limit":1}}"#)
+            .unwrap();
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["total"], 2);
+        assert_eq!(response["result"]["items"].as_array().unwrap().len(), 1);
+
+        let response = server
+            .handle_line(r#"{"jsonrpc":"2.0","id":2,"method":"statsForFile","params":{"file":"src/a.rs"}}"#)
+            .unwrap();
+        assert_eq!(response["result"]["total_todos"], 2);
+        assert_eq!(response["result"]["by_tag"]["FIXME"], 1);
+    }
+
+    #[test]
+    fn test_errors_and_notifications() {
+        let results = Rc::new(RefCell::new(vec![make_result(vec![])]));
+        let mut server = server(results);
+
+        let response = server.handle_line("{not json").unwrap();
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+        let response = server
+            .handle_line(r#"{"jsonrpc":"2.0","id":"x","method":"frobnicate"}"#)
+            .unwrap();
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(response["id"], "x");
+        let response = server
+            .handle_line(r#"{"jsonrpc":"2.0","id":3,"method":"query","params":{"priority":"urgent"}}"#)
+            .unwrap();
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        let response = server
+            .handle_line(r#"{"jsonrpc":"2.0","id":4,"method":"statsForFile"}"#)
+            .unwrap();
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        // Notifications get no response
+        assert!(server
+            .handle_line(r#"{"jsonrpc":"2.0","method":"subscribe"}"#)
+            .is_none());
+        assert!(server.is_subscribed());
+    }
+
+    #[test]
+    fn test_refresh_detects_changes() {
+        let results = Rc::new(RefCell::new(vec![
+            make_result(vec![]),
+            make_result(vec![]),
+            make_result(vec![make_item("/repo/a.rs", 1, TodoTag::Todo)]),
+        ]));
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let seen = changes.clone();
+        let mut server = server(results).with_on_change(Box::new(move |previous, current| {
+            seen.borrow_mut().push((previous.items.len(), current.items.len()));
+        }));
+        assert!(server.is_watched());
+        assert!(!server.refresh().unwrap());
+        assert!(server.refresh().unwrap());
+        assert_eq!(*changes.borrow(), [(0, 1)]);
+        let notification = server.changed_notification();
+        assert_eq!(notification["method"], CHANGED_NOTIFICATION);
+        assert_eq!(notification["params"]["total_todos"], 1);
+    }
+
+    #[test]
+    fn test_serve_until_shutdown() {
+        let results = Rc::new(RefCell::new(vec![make_result(vec![make_item(
+            "/repo/a.rs",
+            1,
+            TodoTag::Todo,
+        )])]));
+        let mut server = server(results);
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"rescan"}"#,
+            "\n\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":3,"method":"rescan"}"#,
+            "\n"
+        );
+        let mut output = Vec::new();
+        serve(
+            &mut server,
+            std::io::Cursor::new(input),
+            &mut output,
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        let lines: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["result"]["total_todos"], 1);
+        assert_eq!(lines[1]["id"], 2);
+    }
+}
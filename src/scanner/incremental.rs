@@ -3,8 +3,17 @@ use std::path::Path;
 
 use crate::cache::CacheDb;
 use crate::error::Result;
-use crate::model::TodoItem;
-use crate::scanner::FileScanner;
+use crate::model::{fnv1a64, TodoItem};
+use crate::scanner::{loc, FileScanner};
+
+/// Outcome of scanning one file through the cache.
+pub struct ScannedFile {
+    pub items: Vec<TodoItem>,
+    /// Lines of code in the file
+    pub lines: usize,
+    /// Whether the results came from the cache
+    pub from_cache: bool,
+}
 
 pub struct IncrementalScanner<'a> {
     scanner: &'a dyn FileScanner,
@@ -13,12 +22,12 @@ pub struct IncrementalScanner<'a> {
 
 impl<'a> IncrementalScanner<'a> {
     pub fn new(scanner: &'a dyn FileScanner, cache: &'a CacheDb) -> Self {
+        let _ = cache.use_scanner_stamp(&scanner.settings_stamp());
         Self { scanner, cache }
     }
 
     /// Scan a file, using cache if fingerprint matches.
-    /// Returns (items, from_cache) where from_cache indicates if results came from cache.
-    pub fn scan_file(&self, path: &Path) -> Result<(Vec<TodoItem>, bool)> {
+    pub fn scan_file(&self, path: &Path) -> Result<ScannedFile> {
         let metadata = fs::metadata(path)?;
         let mtime = metadata
             .modified()
@@ -31,12 +40,39 @@ impl<'a> IncrementalScanner<'a> {
         // Check cache
         if self.cache.is_file_fresh(path, mtime, size) {
             let items = self.cache.get_todos(path);
-            return Ok((items, true));
+            // Caches written before line counts existed have no count yet
+            let lines = match self.cache.get_lines(path) {
+                Some(lines) => lines,
+                None => loc::count_file(path).unwrap_or(0),
+            };
+            return Ok(ScannedFile {
+                items,
+                lines,
+                from_cache: true,
+            });
+        }
+
+        // A fresh checkout, e.g. on a CI runner that restored an exported
+        // cache, changes every mtime but no content
+        let hash = fs::read(path).ok().map(|bytes| format!("{:016x}", fnv1a64(&bytes)));
+        if let (Some(hash), Some(lines)) = (hash.as_deref(), self.cache.get_lines(path)) {
+            if self.cache.get_hash(path).as_deref() == Some(hash) {
+                let _ = self.cache.touch_file(path, mtime, size);
+                return Ok(ScannedFile {
+                    items: self.cache.get_todos(path),
+                    lines,
+                    from_cache: true,
+                });
+            }
         }
 
         // Scan and cache
-        let items = self.scanner.scan_file(path)?;
-        let _ = self.cache.store_file(path, mtime, size, &items);
-        Ok((items, false))
+        let (items, lines) = self.scanner.scan_file_counted(path)?;
+        let _ = self.cache.store_file(path, mtime, size, lines, hash.as_deref(), &items);
+        Ok(ScannedFile {
+            items,
+            lines,
+            from_cache: false,
+        })
     }
 }
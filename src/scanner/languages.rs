@@ -106,6 +106,12 @@ pub struct LanguageDatabase {
     by_extension: HashMap<&'static str, &'static Language>,
 }
 
+impl Default for LanguageDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LanguageDatabase {
     pub fn new() -> Self {
         let mut by_extension = HashMap::new();
@@ -120,6 +126,19 @@ impl LanguageDatabase {
     pub fn from_extension(&self, ext: &str) -> Option<&'static Language> {
         self.by_extension.get(ext).copied()
     }
+
+    /// Look a language up by its name, ignoring case.
+    pub fn from_name(&self, name: &str) -> Option<&'static Language> {
+        ALL_LANGUAGES
+            .iter()
+            .find(|lang| lang.name.eq_ignore_ascii_case(name))
+            .copied()
+    }
+
+    /// The names of all known languages.
+    pub fn names(&self) -> Vec<&'static str> {
+        ALL_LANGUAGES.iter().map(|lang| lang.name).collect()
+    }
 }
 
 #[cfg(test)]
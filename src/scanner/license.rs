@@ -0,0 +1,82 @@
+use crate::scanner::languages::Language;
+
+/// Lowercase phrases that mark a comment block as license text.
+const LICENSE_MARKERS: &[&str] = &[
+    "spdx-license-identifier",
+    "copyright",
+    "all rights reserved",
+    "licensed under",
+    "permission is hereby granted",
+    "redistribution and use in source and binary forms",
+    "general public license",
+    "mozilla public license",
+    "apache license",
+];
+
+/// The 1-based first and last line of the file's license header: its first
+/// comment block, if that block reads like license text. Only blank lines and
+/// a shebang may come before it. Tags in a header are legal boilerplate
+/// ("XXX Corporation"), not work to track.
+pub fn license_header(content: &str, lang: &Language) -> Option<(usize, usize)> {
+    let mut lines = content.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+    let is_shebang = |n: usize, line: &str| n == 1 && line.starts_with("#!");
+    let (first, first_text) = lines.find(|(n, line)| !line.is_empty() && !is_shebang(*n, line))?;
+
+    let mut last = first;
+    let mut text = first_text.to_lowercase();
+    match (lang.block_comment_start, lang.block_comment_end) {
+        (Some(start), Some(end)) if first_text.starts_with(start) => {
+            let mut closed = first_text[start.len()..].contains(end);
+            for (n, line) in lines {
+                if closed {
+                    break;
+                }
+                last = n;
+                text.push('\n');
+                text.push_str(&line.to_lowercase());
+                closed = line.contains(end);
+            }
+        }
+        _ if lang.line_comments.iter().any(|p| first_text.starts_with(p)) => {
+            for (n, line) in lines {
+                if !lang.line_comments.iter().any(|p| line.starts_with(p)) {
+                    break;
+                }
+                last = n;
+                text.push('\n');
+                text.push_str(&line.to_lowercase());
+            }
+        }
+        _ => return None,
+    }
+
+    LICENSE_MARKERS
+        .iter()
+        .any(|marker| text.contains(marker))
+        .then_some((first, last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::languages::LanguageDatabase;
+
+    fn header(content: &str, ext: &str) -> Option<(usize, usize)> {
+        let db = LanguageDatabase::new();
+        license_header(content, db.from_extension(ext).unwrap())
+    }
+
+    #[test]
+    fn test_license_header_blocks() {
+        let block = "\n/*\n * Copyright (c) 2019 XXX Corporation\n */\n// TODO: real\n";
+        assert_eq!(header(block, "rs"), Some((2, 4)));
+
+        let lines = "#!/usr/bin/env python\n# SPDX-License-Identifier: MIT\n# XXX Corp\n\n# TODO: x\n";
+        assert_eq!(header(lines, "py"), Some((2, 3)));
+
+        // The first comment isn't license text
+        assert_eq!(header("// Config parsing.\n// TODO: real\n\n// Copyright\n", "rs"), None);
+        // Code comes first
+        assert_eq!(header("fn main() {}\n// Copyright 2020\n", "rs"), None);
+    }
+}
@@ -0,0 +1,49 @@
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::error::Result;
+use crate::scanner::languages::LanguageDatabase;
+
+/// Language name used for files with an unknown extension.
+pub const OTHER_LANGUAGE: &str = "Other";
+
+/// Lines of code in `content`: every line that is not blank. Comments count,
+/// since TODOs live in them and density is about how much text there is to
+/// read, not how much of it compiles.
+pub fn count_lines(content: &str) -> usize {
+    content.lines().filter(|line| !line.trim().is_empty()).count()
+}
+
+pub fn count_file(path: &Path) -> Result<usize> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(count_lines(&content))
+}
+
+/// Language of a file by extension, e.g. "Rust", or `OTHER_LANGUAGE`.
+pub fn language_name(path: &Path) -> &'static str {
+    static DB: OnceLock<LanguageDatabase> = OnceLock::new();
+    path.extension()
+        .and_then(|e| e.to_str())
+        .and_then(|ext| DB.get_or_init(LanguageDatabase::new).from_extension(ext))
+        .map(|lang| lang.name)
+        .unwrap_or(OTHER_LANGUAGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_lines_skips_blank_lines() {
+        assert_eq!(count_lines(""), 0);
+        assert_eq!(count_lines("fn main() {\n\n    // TODO: x\n   \n}\n"), 3);
+    }
+
+    #[test]
+    fn test_language_name() {
+        assert_eq!(language_name(Path::new("src/main.rs")), "Rust");
+        assert_eq!(language_name(Path::new("app.py")), "Python");
+        assert_eq!(language_name(Path::new("README")), OTHER_LANGUAGE);
+        assert_eq!(language_name(Path::new("notes.xyz")), OTHER_LANGUAGE);
+    }
+}
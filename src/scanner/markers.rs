@@ -0,0 +1,214 @@
+use regex::Regex;
+
+use crate::error::Result;
+use crate::model::{DEPRECATED_TAG, SKIPPED_TEST_TAG};
+
+/// A kind of code marker reported as an item alongside TODO comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MarkerKind {
+    /// A disabled test
+    SkippedTest,
+    /// An API slated for removal
+    Deprecated,
+}
+
+impl MarkerKind {
+    /// The tag items of this kind are reported under.
+    pub fn tag(self) -> &'static str {
+        match self {
+            MarkerKind::SkippedTest => SKIPPED_TEST_TAG,
+            MarkerKind::Deprecated => DEPRECATED_TAG,
+        }
+    }
+}
+
+/// Where a marker is written: in code, or in a comment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Place {
+    Code,
+    Comment,
+}
+
+/// Markers per kind and language. The first group that matches, if any, is
+/// the reason, note or test name given with the marker.
+const MARKERS: &[(MarkerKind, &[&str], Place, &str)] = &[
+    (MarkerKind::SkippedTest, &["Rust"], Place::Code, r#"#\[ignore(?:\s*=\s*"([^"]*)")?\]"#),
+    (
+        MarkerKind::SkippedTest,
+        &["JavaScript", "TypeScript"],
+        Place::Code,
+        r#"\b(?:(?:it|test|describe)\.skip|xit|xtest|xdescribe)\(\s*(?:['"`]([^'"`]*))?"#,
+    ),
+    (
+        MarkerKind::SkippedTest,
+        &["Java"],
+        Place::Code,
+        r#"@(?:Disabled|Ignore)\b(?:\(\s*"([^"]*)")?"#,
+    ),
+    (
+        MarkerKind::SkippedTest,
+        &["Python"],
+        Place::Code,
+        r#"@(?:pytest\.mark\.skip|unittest\.skip)\b(?:\(\s*(?:reason\s*=\s*)?['"]([^'"]*))?"#,
+    ),
+    (MarkerKind::SkippedTest, &["Go"], Place::Code, r#"\bt\.Skip(?:f|Now)?\(\s*(?:"([^"]*))?"#),
+    (
+        MarkerKind::SkippedTest,
+        &["C#"],
+        Place::Code,
+        r#"\[\s*(?:Fact|Theory)\s*\(\s*Skip\s*=\s*"([^"]*)""#,
+    ),
+    (
+        MarkerKind::Deprecated,
+        &["Rust"],
+        Place::Code,
+        concat!(
+            r#"#\[deprecated(?:\s*=\s*"([^"]*)""#,
+            r#"|\s*\([^)]*?\bnote\s*=\s*"([^"]*)"[^)]*\)|\s*\([^)]*\))?\]"#
+        ),
+    ),
+    (MarkerKind::Deprecated, &["Java"], Place::Code, r"@Deprecated\b"),
+    (
+        MarkerKind::Deprecated,
+        &["Java", "JavaScript", "TypeScript", "C", "C++"],
+        Place::Comment,
+        r"[@\\]deprecated\b[ \t]*(.*?)[ \t]*(?:\*/)?$",
+    ),
+    (
+        MarkerKind::Deprecated,
+        &["C", "C++"],
+        Place::Code,
+        r#"\[\[deprecated(?:\(\s*"([^"]*)"\s*\))?\]\]"#,
+    ),
+    (
+        MarkerKind::Deprecated,
+        &["Python"],
+        Place::Code,
+        r#"@(?:typing_extensions\.|warnings\.)?deprecated\(\s*['"]([^'"]*)"#,
+    ),
+    (MarkerKind::Deprecated, &["C#"], Place::Code, r#"\[\s*Obsolete\b(?:\(\s*"([^"]*)")?"#),
+];
+
+/// Finds code markers of some kinds: disabled tests (`#[ignore]`, `it.skip`,
+/// `@Disabled`, `xit`, ...) and deprecations (`#[deprecated]`, `@Deprecated`,
+/// `@deprecated`, ...).
+pub struct CodeMarkers {
+    markers: Vec<(MarkerKind, &'static [&'static str], Place, Regex)>,
+}
+
+impl CodeMarkers {
+    pub fn new(kinds: &[MarkerKind]) -> Result<Self> {
+        let markers = MARKERS
+            .iter()
+            .filter(|(kind, ..)| kinds.contains(kind))
+            .map(|(kind, languages, place, pattern)| {
+                Ok((*kind, *languages, *place, Regex::new(pattern)?))
+            })
+            .collect::<Result<_>>()?;
+        Ok(CodeMarkers { markers })
+    }
+
+    /// The kind and byte offset of a marker in `line` of a `language` file,
+    /// with the reason or note given with it, or else the marker itself.
+    /// `in_comment` says whether the line is part of a comment.
+    pub fn find(
+        &self,
+        language: &str,
+        line: &str,
+        in_comment: bool,
+    ) -> Option<(MarkerKind, usize, String)> {
+        let place = if in_comment { Place::Comment } else { Place::Code };
+        self.markers
+            .iter()
+            .filter(|(_, languages, p, _)| *p == place && languages.contains(&language))
+            .find_map(|(kind, _, _, pattern)| Some((*kind, pattern.captures(line)?)))
+            .map(|(kind, cap)| {
+                let whole = cap.get(0).unwrap();
+                let groups: Vec<_> = cap.iter().skip(1).flatten().collect();
+                let message = groups
+                    .iter()
+                    .map(|reason| reason.as_str().trim())
+                    .find(|reason| !reason.is_empty())
+                    .unwrap_or_else(|| {
+                        // The marker, without an empty reason's quotes
+                        let end = groups.first().map_or(whole.end(), |g| g.start());
+                        line[whole.start()..end]
+                            .trim_end_matches(|c: char| c.is_whitespace() || "(\"'`".contains(c))
+                    });
+                (kind, whole.start(), message.to_string())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skip_markers() {
+        let markers = CodeMarkers::new(&[MarkerKind::SkippedTest]).unwrap();
+        let find = |language: &str, line: &str| {
+            markers.find(language, line, false).map(|(_, start, message)| (start, message))
+        };
+        assert_eq!(find("Rust", "    #[ignore]"), Some((4, "#[ignore]".to_string())));
+        assert_eq!(
+            find("Rust", r#"#[ignore = "needs network"]"#),
+            Some((0, "needs network".to_string()))
+        );
+        assert_eq!(
+            find("TypeScript", "  it.skip('loads the user', async () => {"),
+            Some((2, "loads the user".to_string()))
+        );
+        assert_eq!(
+            find("JavaScript", "xit(\"retries\", () => {}"),
+            Some((0, "retries".to_string()))
+        );
+        assert_eq!(find("Java", "    @Disabled"), Some((4, "@Disabled".to_string())));
+        assert_eq!(
+            find("Python", "@pytest.mark.skip(reason=\"flaky on CI\")"),
+            Some((0, "flaky on CI".to_string()))
+        );
+        assert_eq!(find("Go", "\tt.Skip(\"slow\")"), Some((1, "slow".to_string())));
+        assert_eq!(
+            find("C#", "[Fact(Skip = \"broken\")]"),
+            Some((0, "broken".to_string()))
+        );
+
+        // Other languages' markers and look-alikes
+        assert_eq!(find("Python", "#[ignore]"), None);
+        assert_eq!(find("JavaScript", "exit(1)"), None);
+        assert_eq!(find("Python", "@pytest.mark.skipif(sys.platform == 'win32')"), None);
+        // Kinds that weren't asked for
+        assert_eq!(find("Rust", "#[deprecated]"), None);
+    }
+
+    #[test]
+    fn test_deprecation_markers() {
+        let markers = CodeMarkers::new(&[MarkerKind::Deprecated]).unwrap();
+        let find = |language: &str, line: &str, in_comment: bool| {
+            markers.find(language, line, in_comment).map(|(kind, _, message)| {
+                assert_eq!(kind, MarkerKind::Deprecated);
+                message
+            })
+        };
+        assert_eq!(find("Rust", "#[deprecated]", false).unwrap(), "#[deprecated]");
+        assert_eq!(find("Rust", "#[deprecated = \"use b\"]", false).unwrap(), "use b");
+        assert_eq!(
+            find("Rust", "#[deprecated(since = \"1.2\", note = \"use b\")]", false).unwrap(),
+            "use b"
+        );
+        let since = "#[deprecated(since = \"1.2\")]";
+        assert_eq!(find("Rust", since, false).unwrap(), since);
+        assert_eq!(find("Java", "  @Deprecated", false).unwrap(), "@Deprecated");
+        assert_eq!(find("Java", " * @deprecated use {@link B}", true).unwrap(), "use {@link B}");
+        assert_eq!(find("TypeScript", "/** @deprecated */", true).unwrap(), "@deprecated");
+        assert_eq!(find("C++", "/// \\deprecated use b()", true).unwrap(), "use b()");
+        assert_eq!(find("C++", "[[deprecated(\"use b\")]] void a();", false).unwrap(), "use b");
+        assert_eq!(find("Python", "@deprecated(\"use b\")", false).unwrap(), "use b");
+        assert_eq!(find("C#", "[Obsolete(\"use B\")]", false).unwrap(), "use B");
+
+        // Javadoc tags only count in comments, annotations only in code
+        assert_eq!(find("Java", "@deprecated", false), None);
+        assert_eq!(find("Java", "// @Deprecated", true), None);
+    }
+}
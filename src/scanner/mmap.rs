@@ -1,20 +1,20 @@
+#[cfg(not(target_family = "wasm"))]
 use std::fs::File;
 use std::path::Path;
 
+#[cfg(not(target_family = "wasm"))]
 use memmap2::Mmap;
 
-/// Memory-map a file for reading. Falls back to regular read for small files.
+/// Memory-map a file for reading. Falls back to regular read for small files,
+/// and on wasm32, which has no memory maps.
 pub fn read_file_contents(path: &Path) -> std::io::Result<String> {
-    let metadata = std::fs::metadata(path)?;
-    let size = metadata.len();
-
-    if size > 256 * 1024 {
+    #[cfg(not(target_family = "wasm"))]
+    if std::fs::metadata(path)?.len() > 256 * 1024 {
         // > 256KB: use mmap
         let file = File::open(path)?;
         let mmap = unsafe { Mmap::map(&file)? };
-        String::from_utf8(mmap.to_vec())
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
-    } else {
-        std::fs::read_to_string(path)
+        return String::from_utf8(mmap.to_vec())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e));
     }
+    std::fs::read_to_string(path)
 }
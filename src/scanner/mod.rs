@@ -1,25 +1,111 @@
 pub mod languages;
+pub mod license;
+pub mod markers;
 pub mod regex;
+#[cfg(feature = "cache")]
 pub mod incremental;
+pub mod loc;
 pub mod mmap;
 #[cfg(feature = "precise")]
+pub mod panics;
+#[cfg(feature = "precise")]
 pub mod treesitter;
 
 use std::collections::HashSet;
 use std::path::Path;
+#[cfg(feature = "cache")]
+use std::path::{Component, PathBuf};
 use std::time::Instant;
 
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
+#[cfg(feature = "cache")]
 use crate::cache::CacheDb;
 use crate::discovery::FileDiscovery;
 use crate::error::Result;
 use crate::model::{ScanMetadata, ScanResult, ScanStats, TodoItem};
+use crate::scanner::regex::RegexScanner;
+#[cfg(feature = "cache")]
 use crate::progress::ScanProgress;
+#[cfg(feature = "cache")]
 use crate::scanner::incremental::IncrementalScanner;
+#[cfg(feature = "cache")]
+use crate::spill::{SortedItems, SpillBuffer};
 
 pub trait FileScanner: Send + Sync {
     fn scan_file(&self, path: &Path) -> Result<Vec<TodoItem>>;
+
+    /// Scan a file and count its lines of code. Scanners that already walk
+    /// every line should override this to avoid reading the file twice.
+    fn scan_file_counted(&self, path: &Path) -> Result<(Vec<TodoItem>, usize)> {
+        let items = self.scan_file(path)?;
+        Ok((items, loc::count_file(path).unwrap_or(0)))
+    }
+
+    /// The settings that decide what the scanner finds, so items cached by a
+    /// scanner with other settings are found again rather than reused.
+    fn settings_stamp(&self) -> String {
+        String::new()
+    }
+}
+
+/// Sort the items of every scanned file and compute the stats of a scan of
+/// `files_scanned` files.
+fn collect_scanned(
+    scanned: Vec<(&Path, Vec<TodoItem>, usize)>,
+    files_scanned: usize,
+) -> (Vec<TodoItem>, ScanStats) {
+    let mut stats = ScanStats::new();
+    let mut all_items: Vec<TodoItem> = Vec::new();
+    for (path, items, lines) in scanned {
+        stats.add_file_lines(path, lines);
+        all_items.extend(items);
+    }
+
+    crate::ordering::sort_items(&mut all_items);
+
+    stats.files_scanned = files_scanned;
+    stats.files_with_todos = all_items
+        .iter()
+        .map(|item| &item.file)
+        .collect::<HashSet<_>>()
+        .len();
+    for item in &all_items {
+        stats.add_item(item);
+    }
+    (all_items, stats)
+}
+
+/// Scan sources held in memory rather than on disk, e.g. files a web page
+/// was given, as a scan of `root` would. Paths select each file's comment
+/// syntax and appear in the items as given. Nothing is timed, since
+/// wasm32-unknown-unknown has no clock: the duration is 0 and the
+/// timestamp is empty.
+pub fn scan_sources<'a>(
+    scanner: &RegexScanner,
+    root: &Path,
+    sources: impl IntoIterator<Item = (&'a Path, &'a str)>,
+) -> ScanResult {
+    let scanned: Vec<(&Path, Vec<TodoItem>, usize)> = sources
+        .into_iter()
+        .map(|(path, content)| {
+            let (items, lines) = scanner.scan_source(path, content);
+            (path, items, lines)
+        })
+        .collect();
+    let files_scanned = scanned.len();
+    let (items, stats) = collect_scanned(scanned, files_scanned);
+    ScanResult {
+        items,
+        stats,
+        metadata: ScanMetadata {
+            scan_duration_ms: 0,
+            root_path: root.to_path_buf(),
+            timestamp: String::new(),
+            blame_cache_hits: None,
+        },
+    }
 }
 
 pub struct ScanOrchestrator {
@@ -32,49 +118,54 @@ impl ScanOrchestrator {
         Self { scanner, discovery }
     }
 
-    pub fn scan(&self) -> Result<ScanResult> {
-        let start = Instant::now();
-
-        let files = self.discovery.discover()?;
-        let files_scanned = files.len();
+    pub fn root(&self) -> &Path {
+        self.discovery.root()
+    }
 
-        let mut all_items: Vec<TodoItem> = files
-            .par_iter()
-            .filter_map(|path| self.scanner.scan_file(path).ok())
-            .flatten()
-            .collect();
+    pub fn discovery(&self) -> &FileDiscovery {
+        &self.discovery
+    }
 
-        all_items.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    /// The scanner and discovery settings, which together with the files
+    /// decide the result.
+    pub fn settings_stamp(&self) -> String {
+        format!("{}\0{}", self.scanner.settings_stamp(), self.discovery.settings_stamp())
+    }
 
-        // Build stats
-        let files_with_todos = all_items
-            .iter()
-            .map(|item| &item.file)
-            .collect::<HashSet<_>>()
-            .len();
+    pub fn scan(&self) -> Result<ScanResult> {
+        let start = Instant::now();
 
-        let mut stats = ScanStats::new();
-        stats.files_scanned = files_scanned;
-        stats.files_with_todos = files_with_todos;
-        for item in &all_items {
-            stats.add_item(item);
-        }
+        let files = self.discovery.discover()?;
+        #[cfg(feature = "parallel")]
+        let scanned: Vec<(&Path, Vec<TodoItem>, usize)> =
+            files.par_iter().filter_map(|path| self.scan_one(path)).collect();
+        #[cfg(not(feature = "parallel"))]
+        let scanned: Vec<(&Path, Vec<TodoItem>, usize)> =
+            files.iter().filter_map(|path| self.scan_one(path)).collect();
+        let (items, stats) = collect_scanned(scanned, files.len());
 
         let elapsed = start.elapsed();
         let metadata = ScanMetadata {
             scan_duration_ms: elapsed.as_millis() as u64,
             root_path: self.discovery.root().to_path_buf(),
             timestamp: format!("{:?}", std::time::SystemTime::now()),
+            blame_cache_hits: None,
         };
 
         Ok(ScanResult {
-            items: all_items,
+            items,
             stats,
             metadata,
         })
     }
 
+    fn scan_one<'a>(&self, path: &'a Path) -> Option<(&'a Path, Vec<TodoItem>, usize)> {
+        let (items, lines) = self.scanner.scan_file_counted(path).ok()?;
+        Some((path, items, lines))
+    }
+
     /// Scan with optional cache support for incremental scanning.
+    #[cfg(feature = "cache")]
     pub fn scan_with_cache(&self, cache: Option<&CacheDb>) -> Result<ScanResult> {
         let cache = match cache {
             Some(c) => c,
@@ -82,27 +173,30 @@ impl ScanOrchestrator {
         };
 
         let start = Instant::now();
-        let files = self.discovery.discover()?;
+        let files = self.discovery.discover_cached(Some(cache))?;
         let files_scanned = files.len();
         let progress = ScanProgress::new(files_scanned as u64);
 
         let incremental = IncrementalScanner::new(self.scanner.as_ref(), cache);
 
         let mut all_items: Vec<TodoItem> = Vec::new();
+        let mut stats = ScanStats::new();
         let mut from_cache_count: usize = 0;
 
         // Use sequential iteration for cache (SQLite is single-writer)
         for path in &files {
             match incremental.scan_file(path) {
-                Ok((items, was_cached)) => {
-                    if was_cached {
+                Ok(scanned) => {
+                    if scanned.from_cache {
                         from_cache_count += 1;
                     }
-                    all_items.extend(items);
+                    stats.add_file_lines(path, scanned.lines);
+                    all_items.extend(scanned.items);
                 }
                 Err(_) => {
                     // Fallback: try direct scan
-                    if let Ok(items) = self.scanner.scan_file(path) {
+                    if let Ok((items, lines)) = self.scanner.scan_file_counted(path) {
+                        stats.add_file_lines(path, lines);
                         all_items.extend(items);
                     }
                 }
@@ -112,7 +206,7 @@ impl ScanOrchestrator {
 
         progress.finish();
 
-        all_items.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+        crate::ordering::sort_items(&mut all_items);
 
         let files_with_todos = all_items
             .iter()
@@ -120,7 +214,6 @@ impl ScanOrchestrator {
             .collect::<HashSet<_>>()
             .len();
 
-        let mut stats = ScanStats::new();
         stats.files_scanned = files_scanned;
         stats.files_with_todos = files_with_todos;
         for item in &all_items {
@@ -132,6 +225,7 @@ impl ScanOrchestrator {
             scan_duration_ms: elapsed.as_millis() as u64,
             root_path: self.discovery.root().to_path_buf(),
             timestamp: format!("{:?}", std::time::SystemTime::now()),
+            blame_cache_hits: None,
         };
 
         if from_cache_count > 0 {
@@ -147,6 +241,143 @@ impl ScanOrchestrator {
             metadata,
         })
     }
+
+    /// Scan with bounded memory: items go through a `SpillBuffer` that writes
+    /// sorted runs to temp files past `budget_bytes`. Files are scanned one at
+    /// a time (with the cache, if given) and stats are computed as they go.
+    #[cfg(feature = "cache")]
+    pub fn scan_spilled(
+        &self,
+        cache: Option<&CacheDb>,
+        budget_bytes: usize,
+    ) -> Result<(SortedItems, ScanStats, ScanMetadata)> {
+        let start = Instant::now();
+        let files = self.discovery.discover_cached(cache)?;
+        let incremental = cache.map(|c| IncrementalScanner::new(self.scanner.as_ref(), c));
+
+        let mut buffer = SpillBuffer::new(budget_bytes);
+        for path in &files {
+            let scanned = match incremental {
+                Some(ref incremental) => incremental.scan_file(path).map(|s| (s.items, s.lines)),
+                None => self.scanner.scan_file_counted(path),
+            };
+            let (items, lines) = scanned
+                .or_else(|_| self.scanner.scan_file_counted(path))
+                .unwrap_or_default();
+            buffer.push_file_items(items)?;
+            buffer.add_file_lines(path, lines);
+        }
+
+        let runs = buffer.spilled_runs();
+        let (mut stats, items) = buffer.finish()?;
+        stats.files_scanned = files.len();
+
+        let elapsed = start.elapsed();
+        if runs > 0 {
+            eprintln!("Spilled items to {} temporary run(s)", runs);
+        }
+        let metadata = ScanMetadata {
+            scan_duration_ms: elapsed.as_millis() as u64,
+            root_path: self.discovery.root().to_path_buf(),
+            timestamp: format!("{:?}", std::time::SystemTime::now()),
+            blame_cache_hits: None,
+        };
+        Ok((items, stats, metadata))
+    }
+
+    /// Rescan only the files under `subpath` (relative to the root) and take
+    /// everything else from the cache, giving a complete result for the whole
+    /// root. Cached files that no longer exist are dropped. Falls back to a
+    /// full cached scan when the cache holds nothing yet.
+    #[cfg(feature = "cache")]
+    pub fn scan_subtree(&self, subpath: &Path, cache: &CacheDb) -> Result<ScanResult> {
+        let cached = cache.cached_paths();
+        if cached.is_empty() {
+            return self.scan_with_cache(Some(cache));
+        }
+
+        let start = Instant::now();
+        // Drop `.` components so paths match the cache keys of a full scan
+        let subtree: PathBuf = std::iter::once(self.discovery.root().as_os_str())
+            .chain(
+                subpath
+                    .components()
+                    .filter(|c| !matches!(c, Component::CurDir))
+                    .map(|c| c.as_os_str()),
+            )
+            .collect();
+        let files = self.discovery.discover_under(&subtree)?;
+        let incremental = IncrementalScanner::new(self.scanner.as_ref(), cache);
+
+        let mut all_items: Vec<TodoItem> = Vec::new();
+        let mut stats = ScanStats::new();
+        for path in &files {
+            match incremental.scan_file(path) {
+                Ok(scanned) => {
+                    stats.add_file_lines(path, scanned.lines);
+                    all_items.extend(scanned.items);
+                }
+                Err(_) => {
+                    if let Ok((items, lines)) = self.scanner.scan_file_counted(path) {
+                        stats.add_file_lines(path, lines);
+                        all_items.extend(items);
+                    }
+                }
+            }
+        }
+
+        let mut from_cache_count = 0;
+        for path in cached {
+            if path.starts_with(&subtree) || !path.starts_with(self.discovery.root()) {
+                continue;
+            }
+            if !path.is_file() {
+                continue;
+            }
+            from_cache_count += 1;
+            let lines = match cache.get_lines(&path) {
+                Some(lines) => lines,
+                None => loc::count_file(&path).unwrap_or(0),
+            };
+            stats.add_file_lines(&path, lines);
+            all_items.extend(cache.get_todos(&path));
+        }
+
+        crate::ordering::sort_items(&mut all_items);
+
+        let files_scanned = files.len() + from_cache_count;
+        let files_with_todos = all_items
+            .iter()
+            .map(|item| &item.file)
+            .collect::<HashSet<_>>()
+            .len();
+
+        stats.files_scanned = files_scanned;
+        stats.files_with_todos = files_with_todos;
+        for item in &all_items {
+            stats.add_item(item);
+        }
+
+        let elapsed = start.elapsed();
+        eprintln!(
+            "Rescanned {} files under {} ({} from cache) in {}ms",
+            files.len(),
+            subtree.display(),
+            from_cache_count,
+            elapsed.as_millis()
+        );
+
+        Ok(ScanResult {
+            items: all_items,
+            stats,
+            metadata: ScanMetadata {
+                scan_duration_ms: elapsed.as_millis() as u64,
+                root_path: self.discovery.root().to_path_buf(),
+                timestamp: format!("{:?}", std::time::SystemTime::now()),
+                blame_cache_hits: None,
+            },
+        })
+    }
 }
 
 #[cfg(test)]
@@ -190,6 +421,14 @@ mod tests {
             context_line: String::new(),
             git_author: None,
             git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
         }
     }
 
@@ -284,4 +523,95 @@ mod tests {
         assert_eq!(result.metadata.root_path, dir.path());
         assert!(!result.metadata.timestamp.is_empty());
     }
+
+    #[test]
+    fn test_settings_stamp_covers_scanner_and_discovery() {
+        let orchestrator = |tags: &[&str], depth| {
+            let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+            let scanner = RegexScanner::new().unwrap().with_tags(&tags).unwrap();
+            let discovery = FileDiscovery::new(".").with_max_depth(depth);
+            ScanOrchestrator::new(Box::new(scanner), discovery).settings_stamp()
+        };
+        let stamp = orchestrator(&["TODO"], None);
+        assert_eq!(orchestrator(&["TODO"], None), stamp);
+        assert_ne!(orchestrator(&["TODO", "NOTE"], None), stamp);
+        assert_ne!(orchestrator(&["TODO"], Some(1)), stamp);
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn test_scan_subtree_serves_rest_from_cache() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("a")).unwrap();
+        std::fs::create_dir(dir.path().join("b")).unwrap();
+        let file_a = dir.path().join("a").join("x.rs");
+        let file_b = dir.path().join("b").join("y.rs");
+        let file_gone = dir.path().join("b").join("gone.rs");
+        std::fs::write(&file_a, "// TODO: old a").unwrap();
+        std::fs::write(&file_b, "// TODO: b").unwrap();
+        std::fs::write(&file_gone, "// TODO: gone").unwrap();
+
+        let cache = CacheDb::open_in_memory().unwrap();
+        let old = MockScanner::new(vec![
+            make_todo(file_a.to_str().unwrap(), 1, TodoTag::Todo, "old a"),
+            make_todo(file_b.to_str().unwrap(), 1, TodoTag::Todo, "b"),
+            make_todo(file_gone.to_str().unwrap(), 1, TodoTag::Todo, "gone"),
+        ]);
+        ScanOrchestrator::new(Box::new(old), FileDiscovery::new(dir.path()))
+            .scan_with_cache(Some(&cache))
+            .unwrap();
+
+        std::fs::write(&file_a, "// TODO: new a!").unwrap();
+        std::fs::remove_file(&file_gone).unwrap();
+        // The new scanner would report nothing for b, proving b comes from the cache
+        let new = MockScanner::new(vec![make_todo(
+            file_a.to_str().unwrap(),
+            1,
+            TodoTag::Todo,
+            "new a",
+        )]);
+        let result = ScanOrchestrator::new(Box::new(new), FileDiscovery::new(dir.path()))
+            .scan_subtree(Path::new("./a"), &cache)
+            .unwrap();
+
+        let messages: Vec<&str> = result.items.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(messages, vec!["new a", "b"]);
+        assert_eq!(result.stats.files_scanned, 2);
+        assert_eq!(result.metadata.root_path, dir.path());
+    }
+
+    #[test]
+    fn test_scan_sources_matches_file_scan() {
+        let dir = TempDir::new().unwrap();
+        let rust = "fn main() {}\n// TODO(alice): one\n\n// FIXME: two\n";
+        let python = "# HACK: three\nx = 'TODO: not a comment'\n";
+        std::fs::write(dir.path().join("a.rs"), rust).unwrap();
+        std::fs::write(dir.path().join("b.py"), python).unwrap();
+
+        let from_disk = ScanOrchestrator::new(
+            Box::new(RegexScanner::new().unwrap()),
+            FileDiscovery::new(dir.path().to_str().unwrap()),
+        )
+        .scan()
+        .unwrap();
+        let a = dir.path().join("a.rs");
+        let b = dir.path().join("b.py");
+        let in_memory = scan_sources(
+            &RegexScanner::new().unwrap(),
+            dir.path(),
+            [(b.as_path(), python), (a.as_path(), rust)],
+        );
+
+        let summary = |r: &ScanResult| -> Vec<(PathBuf, usize, String)> {
+            r.items
+                .iter()
+                .map(|i| (i.file.clone(), i.line, i.message.clone()))
+                .collect()
+        };
+        assert_eq!(summary(&in_memory), summary(&from_disk));
+        assert_eq!(in_memory.stats.total_todos, 3);
+        assert_eq!(in_memory.stats.files_scanned, 2);
+        assert_eq!(in_memory.stats.lines_of_code, from_disk.stats.lines_of_code);
+        assert_eq!(in_memory.items[0].author.as_deref(), Some("alice"));
+    }
 }
@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Node, Parser, Query, QueryCursor};
+
+use crate::error::{Result, TodoError};
+use crate::model::{TodoItem, TodoTag, PANIC_TAG};
+
+/// `.unwrap()` and `.expect(..)` calls, and `panic!` invocations.
+const QUERY: &str = "
+(call_expression function: (field_expression field: (field_identifier) @site) arguments: (_) @args)
+(macro_invocation macro: (identifier) @site (token_tree) @args)
+";
+
+/// Methods and macros that panic on failure.
+const PANICKING: &[&str] = &["unwrap", "expect", "panic"];
+
+/// Call sites in Rust `source` that can panic: `unwrap`, `expect` and
+/// `panic!`. Sites inside `#[test]` functions and `#[cfg(test)]` modules are
+/// left out, since panicking is how tests fail.
+pub fn find_panic_sites(path: &Path, source: &str) -> Result<Vec<TodoItem>> {
+    let failure = |message: String| TodoError::ParseFailure {
+        lang: "Rust".to_string(),
+        message,
+    };
+    let language = tree_sitter_rust::LANGUAGE.into();
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language)
+        .map_err(|e| failure(format!("Failed to set language: {}", e)))?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| failure("Failed to parse source code".to_string()))?;
+    let query = Query::new(&language, QUERY)
+        .map_err(|e| failure(format!("Failed to create panic query: {}", e)))?;
+
+    let lines: Vec<&str> = source.lines().collect();
+    let mut items = Vec::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+    while let Some(found) = matches.next() {
+        let (site, args) = (found.captures[0].node, found.captures[1].node);
+        let name = &source[site.byte_range()];
+        if !PANICKING.contains(&name) || in_test_code(site, source) {
+            continue;
+        }
+        let is_macro = found.pattern_index == 1;
+        let args = source[args.byte_range()].split_whitespace().collect::<Vec<_>>().join(" ");
+        let position = site.start_position();
+        items.push(TodoItem {
+            tag: TodoTag::Custom(PANIC_TAG.to_string()),
+            message: format!("{}{}{}", name, if is_macro { "!" } else { "" }, args),
+            file: path.to_path_buf(),
+            line: position.row + 1,
+            column: position.column + 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: lines.get(position.row).unwrap_or(&"").to_string(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        });
+    }
+    Ok(items)
+}
+
+/// Whether `node` is inside a function or module with a test attribute, such
+/// as `#[test]`, `#[tokio::test]` or `#[cfg(test)]`.
+fn in_test_code(node: Node, source: &str) -> bool {
+    let mut current = node.parent();
+    while let Some(ancestor) = current {
+        if matches!(ancestor.kind(), "function_item" | "mod_item") {
+            let mut sibling = ancestor.prev_named_sibling();
+            while let Some(attribute) = sibling {
+                match attribute.kind() {
+                    "attribute_item" => {
+                        let text = &source[attribute.byte_range()];
+                        if text.contains("test") {
+                            return true;
+                        }
+                    }
+                    "line_comment" | "block_comment" => {}
+                    _ => break,
+                }
+                sibling = attribute.prev_named_sibling();
+            }
+        }
+        current = ancestor.parent();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panic_sites() {
+        let source = r#"fn load() -> Config {
+    let text = std::fs::read_to_string("a.toml").unwrap();
+    let n = text.parse::<u32>().unwrap_or(0);
+    if n == 0 {
+        panic!("empty
+            config");
+    }
+    toml::from_str(&text).expect("valid config")
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn loads() {
+        load().port.unwrap();
+    }
+}
+"#;
+        let items = find_panic_sites(Path::new("a.rs"), source).unwrap();
+        let found: Vec<(usize, usize, &str)> = items
+            .iter()
+            .map(|i| (i.line, i.column, i.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (2, 50, "unwrap()"),
+                (5, 9, "panic!(\"empty config\")"),
+                (8, 27, "expect(\"valid config\")"),
+            ]
+        );
+        assert!(items.iter().all(|i| i.is_panic_site()));
+    }
+}
@@ -1,28 +1,281 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::Path;
 
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
-use crate::error::Result;
-use crate::model::{Priority, TodoItem, TodoTag};
+use crate::error::{Result, TodoError};
+use crate::model::{Priority, TodoItem, TodoTag, DEPRECATED_TAG, PANIC_TAG, SKIPPED_TEST_TAG};
 use crate::scanner::languages::{Language, LanguageDatabase};
+use crate::scanner::license::license_header;
+use crate::scanner::markers::{CodeMarkers, MarkerKind};
 use crate::scanner::FileScanner;
 
+/// Characters that may directly precede a tag by default, besides whitespace
+/// and the comment marker. `-`, `.`, `:` and `$` are left out so that
+/// `XXX-XXX`, `build.TODO`, `Error::BUG` and `$XXX` aren't tags.
+pub const DEFAULT_PREFIX_CHARS: &str = "/*#!;([{<\"'`,|";
+
+/// Where a tag may start and end.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagBoundary {
+    /// A whole word, preceded by whitespace, the comment marker or one of
+    /// the prefix characters
+    #[default]
+    Word,
+    /// Anywhere, even inside a longer word such as `DEBUG`
+    Substring,
+}
+
+impl TagBoundary {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "word" => Ok(TagBoundary::Word),
+            "substring" => Ok(TagBoundary::Substring),
+            other => Err(TodoError::Config(format!(
+                "Unknown tag boundary '{}' (expected word or substring)",
+                other
+            ))),
+        }
+    }
+}
+
 pub struct RegexScanner {
+    /// A tag, or a doc-comment tag such as `@todo` or Doxygen's `\bug` in
+    /// group 1 with its name in group 2
     pattern: Regex,
-    metadata_pattern: Regex,
     language_db: LanguageDatabase,
+    boundary: TagBoundary,
+    prefix_chars: String,
+    case_insensitive: bool,
+    /// Tags every language is scanned for, [`DEFAULT_TAGS`] unless replaced
+    tags: Vec<String>,
+    /// Extra tags per language name, on top of `tags`
+    language_tags: BTreeMap<&'static str, Vec<String>>,
+    /// The pattern for each language in `language_tags`
+    language_patterns: HashMap<&'static str, Regex>,
+    /// Code markers reported besides tags, such as disabled tests
+    marker_kinds: BTreeSet<MarkerKind>,
+    /// Finds `marker_kinds`, when there are any
+    markers: Option<CodeMarkers>,
+    /// Whether Rust panic sites are reported too; needs the `precise` feature
+    panic_audit: bool,
+}
+
+/// Tags every language is scanned for by default.
+pub const DEFAULT_TAGS: &[&str] = &["TODO", "FIXME", "HACK", "BUG", "XXX"];
+
+/// Tags of code markers, which items are told apart by. A comment tag of
+/// the same name would count as a marker rather than a TODO.
+const RESERVED_TAGS: [&str; 3] = [SKIPPED_TEST_TAG, DEPRECATED_TAG, PANIC_TAG];
+
+fn check_reserved(tags: &[String]) -> Result<()> {
+    match tags.iter().find(|t| RESERVED_TAGS.contains(&t.as_str())) {
+        Some(tag) => Err(TodoError::Config(format!(
+            "Tag '{}' is reserved for code markers ([scan] skipped_tests, deprecations and \
+             panic_audit)",
+            tag
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The pattern for `tags`, matching bare tags in any case if
+/// `case_insensitive`.
+fn tag_pattern<S: AsRef<str>>(tags: &[S], case_insensitive: bool) -> Result<Regex> {
+    let mut tags: Vec<String> = tags.iter().map(|t| regex::escape(t.as_ref())).collect();
+    // Longest first, so FIXME isn't cut short by a FIX tag
+    tags.sort_by_key(|t| std::cmp::Reverse(t.len()));
+    let tags = tags.join("|");
+    let bare = if case_insensitive { format!("(?i:{})", tags) } else { tags.clone() };
+    Ok(Regex::new(&format!(r"([@\\](?i:({}))\b)|{}", tags, bare))?)
 }
 
 impl RegexScanner {
     pub fn new() -> Result<Self> {
-        let pattern = Regex::new(r"\b(TODO|FIXME|HACK|BUG|XXX)\b")?;
-        let metadata_pattern = Regex::new(r"\b(TODO|FIXME|HACK|BUG|XXX)\(([^)]*)\)")?;
         Ok(RegexScanner {
-            pattern,
-            metadata_pattern,
+            pattern: tag_pattern(DEFAULT_TAGS, false)?,
             language_db: LanguageDatabase::new(),
+            boundary: TagBoundary::default(),
+            prefix_chars: DEFAULT_PREFIX_CHARS.to_string(),
+            case_insensitive: false,
+            tags: DEFAULT_TAGS.iter().map(|t| t.to_string()).collect(),
+            language_tags: BTreeMap::new(),
+            language_patterns: HashMap::new(),
+            marker_kinds: BTreeSet::new(),
+            markers: None,
+            panic_audit: false,
         })
     }
+
+    /// Also report Rust `unwrap`, `expect` and `panic!` call sites as items
+    /// tagged [`crate::model::PANIC_TAG`].
+    #[cfg(feature = "precise")]
+    pub fn with_panic_audit(mut self, enabled: bool) -> Self {
+        self.panic_audit = enabled;
+        self
+    }
+
+    /// Also report disabled tests (`#[ignore]`, `it.skip`, `@Disabled`, ...)
+    /// as items tagged [`crate::model::SKIPPED_TEST_TAG`].
+    pub fn with_skipped_tests(self, enabled: bool) -> Result<Self> {
+        self.with_markers(MarkerKind::SkippedTest, enabled)
+    }
+
+    /// Also report deprecations (`#[deprecated]`, `@Deprecated`, Javadoc's
+    /// `@deprecated`, ...) as items tagged [`crate::model::DEPRECATED_TAG`].
+    pub fn with_deprecations(self, enabled: bool) -> Result<Self> {
+        self.with_markers(MarkerKind::Deprecated, enabled)
+    }
+
+    fn with_markers(mut self, kind: MarkerKind, enabled: bool) -> Result<Self> {
+        if enabled {
+            self.marker_kinds.insert(kind);
+        } else {
+            self.marker_kinds.remove(&kind);
+        }
+        let kinds: Vec<MarkerKind> = self.marker_kinds.iter().copied().collect();
+        self.markers = if kinds.is_empty() { None } else { Some(CodeMarkers::new(&kinds)?) };
+        Ok(self)
+    }
+
+    /// Also match `todo`, `Todo` and other casings of the tags, reported
+    /// under the canonical uppercase tag.
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Result<Self> {
+        if case_insensitive != self.case_insensitive {
+            self.case_insensitive = case_insensitive;
+            self.build_patterns()?;
+        }
+        Ok(self)
+    }
+
+    /// Scan for these tags instead of [`DEFAULT_TAGS`], e.g. to add `NOTE`
+    /// or `NOCOMMIT`. Tags are matched in uppercase.
+    pub fn with_tags(mut self, tags: &[String]) -> Result<Self> {
+        let tags: Vec<String> = tags
+            .iter()
+            .map(|t| t.trim().to_uppercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+        if tags.is_empty() {
+            return Err(TodoError::Config("[scan] tags can't be empty".to_string()));
+        }
+        check_reserved(&tags)?;
+        self.tags = tags;
+        self.build_patterns()?;
+        Ok(self)
+    }
+
+    /// Scan files of some languages for extra tags, e.g. `UNDONE` in C#. Keys
+    /// are language names as in [`Language::name`], in any case.
+    pub fn with_language_tags(mut self, tags: &BTreeMap<String, Vec<String>>) -> Result<Self> {
+        self.language_tags.clear();
+        for (name, extra) in tags {
+            let language = self.language_db.from_name(name).ok_or_else(|| {
+                TodoError::Config(format!(
+                    "Unknown language '{}' in [scan.language_tags] (known: {})",
+                    name,
+                    self.language_db.names().join(", ")
+                ))
+            })?;
+            let extra: Vec<String> =
+                extra.iter().map(|t| t.trim().to_uppercase()).filter(|t| !t.is_empty()).collect();
+            check_reserved(&extra)?;
+            self.language_tags.entry(language.name).or_default().extend(extra);
+        }
+        self.build_patterns()?;
+        Ok(self)
+    }
+
+    /// Rebuild the default and per-language patterns from the settings.
+    fn build_patterns(&mut self) -> Result<()> {
+        self.pattern = tag_pattern(&self.tags, self.case_insensitive)?;
+        self.language_patterns.clear();
+        for (&language, extra) in &self.language_tags {
+            let mut tags: Vec<&str> = self.tags.iter().map(String::as_str).collect();
+            tags.extend(extra.iter().map(String::as_str));
+            let pattern = tag_pattern(&tags, self.case_insensitive)?;
+            self.language_patterns.insert(language, pattern);
+        }
+        Ok(())
+    }
+
+    pub fn with_boundary(mut self, boundary: TagBoundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Replace the characters allowed directly before a tag in word mode.
+    pub fn with_prefix_chars(mut self, chars: &str) -> Self {
+        self.prefix_chars = chars.to_string();
+        self
+    }
+
+    /// Whether the match at `start..end` of `line` is a tag rather than part
+    /// of a longer word.
+    fn is_delimited(&self, line: &str, start: usize, end: usize) -> bool {
+        if self.boundary == TagBoundary::Substring {
+            return true;
+        }
+        let before = &line[..start];
+        let starts_word = match before.chars().next_back() {
+            None => true,
+            Some(c) if c.is_whitespace() || self.prefix_chars.contains(c) => true,
+            // Directly after the comment marker, as in `--TODO` or `<!--TODO`
+            Some(_) => before.trim_start().chars().all(|c| c.is_ascii_punctuation()),
+        };
+        let mut after = line[end..].chars();
+        let ends_word = match after.next() {
+            None => true,
+            Some(c) if c.is_alphanumeric() || c == '_' => false,
+            // Joined to another word, as in `XXX-XXX`, `TODO-list` or `TODO.md`;
+            // a number, as in `TODO-123`, is a ticket reference
+            Some('-' | '.') => !after.next().is_some_and(|c| c.is_alphabetic() || c == '_'),
+            Some(_) => true,
+        };
+        starts_word && ends_word
+    }
+}
+
+/// Follows `#[cfg(test)]` modules through a Rust file by brace depth. Braces
+/// in string literals throw the count off, which is rare enough in code
+/// leading up to a test module.
+#[derive(Default)]
+struct TestModules {
+    depth: usize,
+    /// A `#[cfg(test)]` attribute is waiting for its item
+    attribute: bool,
+    /// Depth outside the test module being walked through
+    module: Option<usize>,
+}
+
+impl TestModules {
+    /// Whether the walk is inside a test module.
+    fn inside(&self) -> bool {
+        self.module.is_some()
+    }
+
+    /// Walk past a trimmed code line. Returns whether it is in a test module.
+    fn code_line(&mut self, trimmed: &str) -> bool {
+        if trimmed.starts_with("#[cfg(test)]") {
+            self.attribute = true;
+        } else if self.attribute && !trimmed.is_empty() && !trimmed.starts_with("#[") {
+            self.attribute = false;
+            let item = trimmed.trim_start_matches("pub ").trim_start_matches("pub(crate) ");
+            if item.starts_with("mod ") && self.module.is_none() {
+                self.module = Some(self.depth);
+            }
+        }
+        let inside = self.inside();
+        let code = trimmed.split("//").next().unwrap_or_default();
+        self.depth += code.matches('{').count();
+        self.depth = self.depth.saturating_sub(code.matches('}').count());
+        if self.module.is_some_and(|outside| self.depth <= outside) {
+            self.module = None;
+        }
+        inside
+    }
 }
 
 /// Check if a trimmed line starts with any of the language's line comment prefixes.
@@ -32,12 +285,20 @@ fn is_line_comment(trimmed: &str, lang: &Language) -> bool {
         .any(|prefix| trimmed.starts_with(prefix))
 }
 
-/// Parse metadata from the parenthesized content of a tag, e.g. "alice, #123, p:high".
-/// Returns (author, issue, priority).
-fn parse_metadata(contents: &str) -> (Option<String>, Option<String>, Option<Priority>) {
-    let mut author: Option<String> = None;
-    let mut issue: Option<String> = None;
-    let mut priority: Option<Priority> = None;
+/// Metadata from the parenthesized content of a tag.
+#[derive(Debug, Default, PartialEq)]
+struct Metadata {
+    author: Option<String>,
+    issue: Option<String>,
+    priority: Option<Priority>,
+    exemptions: Vec<String>,
+}
+
+/// Parse metadata from the parenthesized content of a tag, e.g.
+/// "alice, #123, p:high, allow:max-age". Exemption rule names are lowercased
+/// with `-` read as `_`, matching the rule names in check output.
+fn parse_metadata(contents: &str) -> Metadata {
+    let mut metadata = Metadata::default();
 
     for part in contents.split(',') {
         let part = part.trim();
@@ -45,19 +306,22 @@ fn parse_metadata(contents: &str) -> (Option<String>, Option<String>, Option<Pri
             continue;
         }
         if part.starts_with('#') {
-            issue = Some(part.to_string());
+            metadata.issue = Some(part.to_string());
         } else if let Some(p) = Priority::from_str_tag(part) {
-            priority = Some(p);
-        } else if author.is_none() {
+            metadata.priority = Some(p);
+        } else if let Some(rule) = part.strip_prefix("allow:").filter(|r| !r.is_empty()) {
+            metadata.exemptions.push(rule.to_lowercase().replace('-', "_"));
+        } else if metadata.author.is_none() {
             // First non-issue, non-priority token is the author
-            author = Some(part.to_string());
+            metadata.author = Some(part.to_string());
         }
     }
 
-    (author, issue, priority)
+    metadata
 }
 
 /// Extract the message text that follows a TODO tag (and optional metadata parens) on the line.
+/// Pass the line cut where the next tag begins to keep that tag's text out.
 fn extract_message(line: &str, tag_start: usize, tag_end: usize) -> String {
     let rest = &line[tag_end..];
 
@@ -89,7 +353,33 @@ fn extract_message(line: &str, tag_start: usize, tag_end: usize) -> String {
 
 impl FileScanner for RegexScanner {
     fn scan_file(&self, path: &Path) -> Result<Vec<TodoItem>> {
+        self.scan_file_counted(path).map(|(items, _)| items)
+    }
+
+    fn scan_file_counted(&self, path: &Path) -> Result<(Vec<TodoItem>, usize)> {
         let content = std::fs::read_to_string(path)?;
+        Ok(self.scan_source(path, &content))
+    }
+
+    fn settings_stamp(&self) -> String {
+        format!(
+            "{:?}\0{}\0{}\0{:?}\0{:?}\0{:?}\0{}",
+            self.boundary,
+            self.prefix_chars,
+            self.case_insensitive,
+            self.tags,
+            self.language_tags,
+            self.marker_kinds,
+            self.panic_audit
+        )
+    }
+}
+
+impl RegexScanner {
+    /// Scan source text that isn't on disk, e.g. a file in a browser, as if
+    /// it were the file at `path`. The extension of `path` selects the
+    /// comment syntax. Returns the items and the count of non-blank lines.
+    pub fn scan_source(&self, path: &Path, content: &str) -> (Vec<TodoItem>, usize) {
         let ext = path
             .extension()
             .and_then(|e| e.to_str())
@@ -98,10 +388,22 @@ impl FileScanner for RegexScanner {
 
         let mut items = Vec::new();
         let mut block_depth: usize = 0;
+        let mut code_lines = 0;
+        let header = language.and_then(|lang| license_header(content, lang));
+        let pattern = language
+            .and_then(|lang| self.language_patterns.get(lang.name))
+            .unwrap_or(&self.pattern);
+        let mut test_modules = language
+            .filter(|lang| lang.name == "Rust")
+            .map(|_| TestModules::default());
 
         for (line_idx, line) in content.lines().enumerate() {
             let line_number = line_idx + 1;
             let trimmed = line.trim_start();
+            if !trimmed.is_empty() {
+                code_lines += 1;
+            }
+            let in_header = header.is_some_and(|(first, last)| (first..=last).contains(&line_number));
 
             // Track block comment depth and determine if this line is in a comment
             let in_comment = if let Some(lang) = language {
@@ -152,59 +454,117 @@ impl FileScanner for RegexScanner {
                 true
             };
 
-            if !in_comment {
+            let in_test_module = match test_modules.as_mut() {
+                Some(modules) if !in_comment => modules.code_line(trimmed),
+                Some(modules) => modules.inside(),
+                None => false,
+            };
+
+            let marker = self
+                .markers
+                .as_ref()
+                .zip(language)
+                .filter(|_| !in_header)
+                .and_then(|(markers, lang)| markers.find(lang.name, line, in_comment));
+            if let Some((kind, start, message)) = marker {
+                items.push(TodoItem {
+                    tag: TodoTag::Custom(kind.tag().to_string()),
+                    message,
+                    file: path.to_path_buf(),
+                    line: line_number,
+                    column: start + 1,
+                    author: None,
+                    issue: None,
+                    priority: None,
+                    context_line: line.to_string(),
+                    git_author: None,
+                    git_date: None,
+                    git_commit: None,
+                    git_summary: None,
+                    pr_number: None,
+                    exemptions: Vec::new(),
+                    blame_incomplete: false,
+                    component: None,
+                    in_test_code: in_test_module,
+                    extra: Default::default(),
+                });
+            }
+            if !in_comment || in_header {
                 continue;
             }
 
-            // Try metadata pattern first (TAG with parens)
-            for cap in self.metadata_pattern.captures_iter(line) {
-                let full_match = cap.get(0).unwrap();
-                let tag_str = &cap[1];
-                let metadata_str = &cap[2];
-                let tag = TodoTag::from_str(tag_str);
-                let (author, issue, priority) = parse_metadata(metadata_str);
-                let message =
-                    extract_message(line, full_match.start(), full_match.end());
+            // Each tag's message ends where the next tag on the line begins
+            let tags: Vec<(usize, usize, TodoTag)> = pattern
+                .captures_iter(line)
+                .filter_map(|cap| {
+                    let whole = cap.get(0).unwrap();
+                    let tag = TodoTag::from_str(cap.get(2).unwrap_or(whole).as_str());
+                    // A bare tag in another case only counts when it is written
+                    // as one, so "add it to the todo list" stays prose
+                    let recased = self.case_insensitive
+                        && cap.get(2).is_none()
+                        && whole.as_str() != tag.as_str();
+                    if recased && !line[whole.end()..].starts_with([':', '(']) {
+                        return None;
+                    }
+                    self.is_delimited(line, whole.start(), whole.end())
+                        .then_some((whole.start(), whole.end(), tag))
+                })
+                .collect();
+            let mut covered = 0;
+            for (index, (start, end, tag)) in tags.iter().enumerate() {
+                // A tag word inside another tag's metadata, as in TODO(BUG-team)
+                if *start < covered {
+                    continue;
+                }
+                let metadata = line[*end..]
+                    .strip_prefix('(')
+                    .and_then(|rest| rest.find(')').map(|close| &rest[..close]));
+                let tag_end = metadata.map_or(*end, |m| end + m.len() + 2);
+                covered = tag_end;
+                let next = tags[index + 1..]
+                    .iter()
+                    .map(|(start, _, _)| *start)
+                    .find(|&start| start >= tag_end)
+                    .unwrap_or(line.len());
+                let segment = if next < line.len() { line[..next].trim_end() } else { line };
+                let message = extract_message(segment, *start, tag_end);
+                let metadata = metadata.map(parse_metadata).unwrap_or_default();
 
                 items.push(TodoItem {
-                    tag,
+                    tag: tag.clone(),
                     message,
                     file: path.to_path_buf(),
                     line: line_number,
-                    column: full_match.start() + 1,
-                    author,
-                    issue,
-                    priority,
+                    column: start + 1,
+                    author: metadata.author,
+                    issue: metadata.issue,
+                    priority: metadata.priority,
                     context_line: line.to_string(),
                     git_author: None,
                     git_date: None,
+                    git_commit: None,
+                    git_summary: None,
+                    pr_number: None,
+                    exemptions: metadata.exemptions,
+                    blame_incomplete: false,
+                    component: None,
+                    in_test_code: in_test_module,
+                    extra: Default::default(),
                 });
             }
+        }
 
-            // If metadata pattern didn't match, try bare pattern
-            if self.metadata_pattern.captures_iter(line).count() == 0 {
-                for mat in self.pattern.find_iter(line) {
-                    let tag = TodoTag::from_str(mat.as_str());
-                    let message = extract_message(line, mat.start(), mat.end());
-
-                    items.push(TodoItem {
-                        tag,
-                        message,
-                        file: path.to_path_buf(),
-                        line: line_number,
-                        column: mat.start() + 1,
-                        author: None,
-                        issue: None,
-                        priority: None,
-                        context_line: line.to_string(),
-                        git_author: None,
-                        git_date: None,
-                    });
-                }
+        #[cfg(feature = "precise")]
+        if self.panic_audit && language.is_some_and(|lang| lang.name == "Rust") {
+            // Unparseable files just have no panic sites
+            if let Ok(sites) = crate::scanner::panics::find_panic_sites(path, content) {
+                items.extend(sites);
+                items.sort_by_key(|item| (item.line, item.column));
             }
         }
 
-        Ok(items)
+        (items, code_lines)
     }
 }
 
@@ -284,6 +644,17 @@ mod tests {
         assert_eq!(items[0].message, "crashes on startup");
     }
 
+    #[test]
+    fn test_todo_with_exemptions() {
+        let scanner = RegexScanner::new().unwrap();
+        let path = write_temp_file("// TODO(alice, #123, allow:max-age, allow:Require-Issue): keep\n", "rs");
+        let items = scanner.scan_file(Path::new(&path)).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].author, Some("alice".to_string()));
+        assert_eq!(items[0].issue, Some("#123".to_string()));
+        assert_eq!(items[0].exemptions, vec!["max_age", "require_issue"]);
+    }
+
     #[test]
     fn test_block_comment_todo() {
         let scanner = RegexScanner::new().unwrap();
@@ -306,6 +677,39 @@ mod tests {
         assert_eq!(items[0].line, 2);
     }
 
+    #[test]
+    fn test_license_header_skipped() {
+        let scanner = RegexScanner::new().unwrap();
+        let content = "// Copyright 2021 XXX Corporation
+// SPDX-License-Identifier: MIT
+
+// TODO: real
+";
+        let (items, _) = scanner.scan_source(Path::new("lib.rs"), content);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].line, 4);
+    }
+
+    #[test]
+    fn test_multiple_tags_on_one_line() {
+        let scanner = RegexScanner::new().unwrap();
+        let content = "// TODO(alice): x  FIXME: y\n// HACK(BUG-team, #4): z BUG(bob) w\n";
+        let (items, _) = scanner.scan_source(Path::new("a.rs"), content);
+        let found: Vec<(&str, &str, usize, Option<&str>)> = items
+            .iter()
+            .map(|i| (i.tag.as_str(), i.message.as_str(), i.column, i.author.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("TODO", "x", 4, Some("alice")),
+                ("FIXME", "y", 20, None),
+                ("HACK", "z", 4, Some("BUG-team")),
+                ("BUG", "w", 26, Some("bob")),
+            ]
+        );
+    }
+
     #[test]
     fn test_false_positive_rejection_in_code() {
         let scanner = RegexScanner::new().unwrap();
@@ -416,4 +820,203 @@ fn bar() {}
         assert_eq!(items.len(), 1);
         assert_eq!(items[0].issue, Some("#issue-slug".to_string()));
     }
+
+    fn tags_in(scanner: &RegexScanner, path: &str, content: &str) -> Vec<(String, String)> {
+        let (items, _) = scanner.scan_source(Path::new(path), content);
+        items.into_iter().map(|i| (i.tag.to_string(), i.message)).collect()
+    }
+
+    #[test]
+    fn test_doc_comment_tags() {
+        let scanner = RegexScanner::new().unwrap();
+        // Javadoc and JSDoc
+        let javadoc = "/**\n * Parses input.\n * @todo Handle BOMs\n * @FIXME leaks\n */\n";
+        assert_eq!(
+            tags_in(&scanner, "A.java", javadoc),
+            [("TODO".into(), "Handle BOMs".into()), ("FIXME".into(), "leaks".into())]
+        );
+        assert_eq!(
+            tags_in(&scanner, "a.js", "/** @todo(alice) cache results */\n"),
+            [("TODO".to_string(), "cache results */".to_string())]
+        );
+        // Doxygen
+        assert_eq!(
+            tags_in(&scanner, "a.cpp", "/// \\bug Off by one\n//! \\todo tidy\n"),
+            [("BUG".into(), "Off by one".into()), ("TODO".into(), "tidy".into())]
+        );
+        // Not doc tags
+        assert!(tags_in(&scanner, "a.js", "// mail @todos and @bugfix\n").is_empty());
+    }
+
+    #[test]
+    fn test_tag_boundaries() {
+        let scanner = RegexScanner::new().unwrap();
+        for line in [
+            "// Format: XXX-XXX-XXXX",
+            "// see TODO.md",
+            "// keep a TODO-list",
+            "// returns Error::BUG",
+            "// costs $XXX",
+            "// DEBUG: verbose",
+        ] {
+            assert!(tags_in(&scanner, "a.rs", line).is_empty(), "{}", line);
+        }
+        for line in ["//TODO: a", "-- TODO: a", "--TODO: a", "/* (TODO: a) */", "# [TODO] a"] {
+            let path = if line.starts_with("--") { "a.sql" } else { "a.c" };
+            let path = if line.starts_with('#') { "a.py" } else { path };
+            assert_eq!(tags_in(&scanner, path, line).len(), 1, "{}", line);
+        }
+        // A ticket number after the tag is not a longer word
+        assert_eq!(tags_in(&scanner, "a.rs", "// TODO-123: jira style").len(), 1);
+        assert_eq!(tags_in(&scanner, "a.rs", "// FIXME.2: dotted").len(), 1);
+
+        let scanner = RegexScanner::new().unwrap().with_prefix_chars("/:");
+        assert_eq!(tags_in(&scanner, "a.rs", "// returns Error::BUG").len(), 1);
+        assert!(tags_in(&scanner, "a.rs", "// (TODO) a").is_empty());
+
+        let scanner = RegexScanner::new().unwrap().with_boundary(TagBoundary::Substring);
+        assert_eq!(tags_in(&scanner, "a.rs", "// DEBUG: verbose")[0].0, "BUG");
+    }
+
+    #[test]
+    fn test_case_insensitive_tags() {
+        let content = "// todo: lower\n// Fixme: title\n// TODO: upper\n// debugging\n";
+        let scanner = RegexScanner::new().unwrap();
+        assert_eq!(tags_in(&scanner, "a.rs", content).len(), 1);
+
+        let scanner = RegexScanner::new().unwrap().with_case_insensitive(true).unwrap();
+        let tags = tags_in(&scanner, "a.rs", content);
+        let names: Vec<&str> = tags.iter().map(|(tag, _)| tag.as_str()).collect();
+        assert_eq!(names, ["TODO", "FIXME", "TODO"]);
+        assert_eq!(tags[1].1, "title");
+        let content = format!("{}// @todo x\n", content);
+        let (items, _) = scanner.scan_source(Path::new("a.rs"), &content);
+        let canonical: Vec<bool> = items.iter().map(TodoItem::has_canonical_case).collect();
+        assert_eq!(canonical, [false, false, true, true]);
+        assert_ne!(scanner.settings_stamp(), RegexScanner::new().unwrap().settings_stamp());
+    }
+
+    #[test]
+    fn test_case_insensitive_ignores_prose() {
+        let scanner = RegexScanner::new().unwrap().with_case_insensitive(true).unwrap();
+        let content = "// add this to the todo list
+// the bug was in the parser
+\
+                       // Todo(alice): owned
+// fixme: lower
+// TODO upper without colon
+";
+        let tags = tags_in(&scanner, "a.rs", content);
+        let found: Vec<(&str, &str)> =
+            tags.iter().map(|(tag, message)| (tag.as_str(), message.as_str())).collect();
+        assert_eq!(
+            found,
+            [("TODO", "owned"), ("FIXME", "lower"), ("TODO", "upper without colon")]
+        );
+    }
+
+    #[test]
+    fn test_skipped_tests() {
+        let content = "#[test]\n#[ignore = \"slow\"]\nfn a() {}\n// #[ignore]\n// TODO: b\n";
+        let scanner = RegexScanner::new().unwrap();
+        assert_eq!(tags_in(&scanner, "a.rs", content).len(), 1);
+
+        let scanner = RegexScanner::new().unwrap().with_skipped_tests(true).unwrap();
+        let (items, _) = scanner.scan_source(Path::new("a.rs"), content);
+        let found: Vec<(&str, usize, &str)> = items
+            .iter()
+            .map(|i| (i.tag.as_str(), i.line, i.message.as_str()))
+            .collect();
+        assert_eq!(found, [("SKIPPED_TEST", 2, "slow"), ("TODO", 5, "b")]);
+        assert!(items[0].is_skipped_test());
+    }
+
+    #[test]
+    fn test_deprecations() {
+        let content =
+            "/**\n * Old API.\n * @deprecated use {@link #b}\n */\n@Deprecated\nvoid a() {}\n";
+        let scanner = RegexScanner::new().unwrap().with_deprecations(true).unwrap();
+        let (items, _) = scanner.scan_source(Path::new("A.java"), content);
+        let found: Vec<(usize, &str)> =
+            items.iter().map(|i| (i.line, i.message.as_str())).collect();
+        assert_eq!(found, [(3, "use {@link #b}"), (5, "@Deprecated")]);
+        assert!(items.iter().all(|i| i.is_deprecation() && i.is_code_marker()));
+        assert_eq!(items[0].column, 4);
+
+        let scanner = scanner.with_deprecations(false).unwrap();
+        assert!(scanner.scan_source(Path::new("A.java"), content).0.is_empty());
+    }
+
+    #[test]
+    fn test_test_module_items() {
+        let content = "\
+// TODO: prod
+#[cfg(test)]
+#[allow(unused)]
+mod tests {
+    fn helper() {
+        // TODO: in tests
+    }
+}
+// TODO: prod again
+";
+        let scanner = RegexScanner::new().unwrap();
+        let (items, _) = scanner.scan_source(Path::new("lib.rs"), content);
+        let found: Vec<(usize, bool)> = items.iter().map(|i| (i.line, i.in_test_code)).collect();
+        assert_eq!(found, [(1, false), (6, true), (9, false)]);
+
+        // Only Rust has test modules
+        let (items, _) = scanner.scan_source(Path::new("lib.c"), content);
+        assert!(items.iter().all(|i| !i.in_test_code));
+    }
+
+    #[test]
+    fn test_language_tags() {
+        let config: BTreeMap<String, Vec<String>> = [
+            ("c#".to_string(), vec!["UNDONE".to_string()]),
+            ("JavaScript".to_string(), vec!["pending".to_string()]),
+        ]
+        .into_iter()
+        .collect();
+        let scanner = RegexScanner::new().unwrap().with_language_tags(&config).unwrap();
+        let content = "// UNDONE: paging\n// PENDING: flaky\n// TODO: x\n";
+        let tags = |path: &str| -> Vec<String> {
+            tags_in(&scanner, path, content).into_iter().map(|(tag, _)| tag).collect()
+        };
+        assert_eq!(tags("a.cs"), ["UNDONE", "TODO"]);
+        assert_eq!(tags("a.js"), ["PENDING", "TODO"]);
+        assert_eq!(tags("a.rs"), ["TODO"]);
+
+        let unknown = [("Cobol".to_string(), vec!["NOTE".to_string()])].into_iter().collect();
+        let err = RegexScanner::new().unwrap().with_language_tags(&unknown).err().unwrap();
+        assert!(err.to_string().contains("Unknown language 'Cobol'"), "{}", err);
+    }
+
+    #[test]
+    fn test_custom_tags() {
+        let tags = ["TODO".to_string(), "note".to_string(), "NOCOMMIT".to_string()];
+        let scanner = RegexScanner::new().unwrap().with_tags(&tags).unwrap();
+        let content = "// NOTE: cached\n// FIXME: not tracked\n// NOCOMMIT debug\n// TODO: x\n";
+        let found = tags_in(&scanner, "a.rs", content);
+        let found: Vec<&str> = found.iter().map(|(tag, _)| tag.as_str()).collect();
+        assert_eq!(found, ["NOTE", "NOCOMMIT", "TODO"]);
+        assert_ne!(scanner.settings_stamp(), RegexScanner::new().unwrap().settings_stamp());
+
+        // Language tags add to the custom tags
+        let extra = [("Rust".to_string(), vec!["FIXME".to_string()])].into_iter().collect();
+        let scanner = scanner.with_language_tags(&extra).unwrap();
+        assert_eq!(tags_in(&scanner, "a.rs", content).len(), 4);
+
+        assert!(RegexScanner::new().unwrap().with_tags(&[" ".to_string()]).is_err());
+
+        // Marker tags would make TODOs count as markers
+        let err = RegexScanner::new()
+            .unwrap()
+            .with_tags(&["TODO".to_string(), "deprecated".to_string()])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("'DEPRECATED' is reserved"), "{}", err);
+        let extra = [("Rust".to_string(), vec!["Panic".to_string()])].into_iter().collect();
+        assert!(RegexScanner::new().unwrap().with_language_tags(&extra).is_err());
+    }
 }
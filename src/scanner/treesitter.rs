@@ -71,30 +71,27 @@ impl TreeSitterScanner {
 
     /// Extract all comment node byte ranges from the parsed tree.
     fn extract_comment_ranges(
+        language_name: &str,
         language: Language,
         source_code: &str,
     ) -> Result<Vec<(usize, usize)>> {
+        let failure = |message: String| TodoError::ParseFailure {
+            lang: language_name.to_string(),
+            message,
+        };
         let mut parser = Parser::new();
         parser
             .set_language(&language)
-            .map_err(|e| TodoError::Scan {
-                file: "treesitter".to_string(),
-                message: format!("Failed to set language: {}", e),
-            })?;
-
-        let tree = parser.parse(source_code, None).ok_or_else(|| {
-            TodoError::Scan {
-                file: "treesitter".to_string(),
-                message: "Failed to parse source code".to_string(),
-            }
-        })?;
+            .map_err(|e| failure(format!("Failed to set language: {}", e)))?;
+
+        let tree = parser
+            .parse(source_code, None)
+            .ok_or_else(|| failure("Failed to parse source code".to_string()))?;
 
         // Query for comment nodes - tree-sitter comment nodes are typically named "comment"
         let query_string = "(comment) @comment";
-        let query = Query::new(&language, query_string).map_err(|e| TodoError::Scan {
-            file: "treesitter".to_string(),
-            message: format!("Failed to create comment query: {}", e),
-        })?;
+        let query = Query::new(&language, query_string)
+            .map_err(|e| failure(format!("Failed to create comment query: {}", e)))?;
 
         let mut cursor = QueryCursor::new();
         let mut matches = cursor.matches(&query, tree.root_node(), source_code.as_bytes());
@@ -191,6 +188,10 @@ impl TreeSitterScanner {
 }
 
 impl FileScanner for TreeSitterScanner {
+    fn settings_stamp(&self) -> String {
+        self.inner.settings_stamp()
+    }
+
     fn scan_file(&self, path: &Path) -> Result<Vec<TodoItem>> {
         // First, get regex candidates
         let candidates = self.inner.scan_file(path)?;
@@ -220,10 +221,11 @@ impl FileScanner for TreeSitterScanner {
         let source_code = crate::scanner::mmap::read_file_contents(path)?;
 
         // Extract comment ranges from tree-sitter
-        let comment_ranges = match Self::extract_comment_ranges(ts_language, &source_code) {
-            Ok(ranges) => ranges,
-            Err(_) => return Ok(candidates), // Parse error, fall back to regex results
-        };
+        let comment_ranges =
+            match Self::extract_comment_ranges(language_name, ts_language, &source_code) {
+                Ok(ranges) => ranges,
+                Err(_) => return Ok(candidates), // Parse error, fall back to regex results
+            };
 
         // Verify candidates against comment ranges
         let (verified, stats) = Self::verify_candidates(candidates, &comment_ranges, &source_code);
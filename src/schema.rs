@@ -0,0 +1,214 @@
+use serde_json::{json, Map, Value};
+
+/// JSON Schema (draft 7) for `.todo-tracker.toml`, for editor completion and
+/// validation through taplo or Even Better TOML.
+pub fn config_schema() -> Value {
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "todo-tracker configuration",
+        "description": "The .todo-tracker.toml config file",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "scan": section("What to scan and which tags to match", &[
+                ("max_file_size", integer("Skip files larger than this many bytes (default 1MB)")),
+                ("respect_gitignore", boolean("Skip files ignored by .gitignore (default true)")),
+                ("tags", string_list("Tags to scan for, replacing TODO, FIXME, HACK, BUG and XXX")),
+                ("tag_boundary", one_of(
+                    "Where tags may start and end",
+                    &["word", "substring"],
+                )),
+                ("tag_prefix_chars", string("Characters allowed right before a tag in word mode")),
+                ("case_insensitive", boolean("Also match todo, Todo, fixme, ...")),
+                ("language_tags", map(
+                    "Extra tags per language name, e.g. \"C#\" = [\"UNDONE\"]",
+                    string_list("Extra tags"),
+                )),
+                ("skipped_tests", boolean("Also report #[ignore], it.skip, @Disabled, xit, ...")),
+                ("deprecations", boolean("Also report #[deprecated], @Deprecated, ...")),
+                ("panic_audit", boolean(
+                    "Also report Rust unwrap/expect/panic! call sites (precise builds)",
+                )),
+            ]),
+            "output": section("Output defaults; --format and --color override them", &[
+                ("format", string(
+                    "Default output format: text, json, csv, tsv, markdown, count, sarif, ...",
+                )),
+                ("color", one_of("Color mode", &["auto", "always", "never"])),
+                ("glyphs", one_of("Item indicators", &["none", "emoji", "ascii"])),
+                ("tag_emoji", map("Emoji per tag when glyphs = \"emoji\"", string("Emoji"))),
+                ("link_template", string(
+                    "URL of an item's line, with {path}, {line}, {column} and {commit}; \
+                     adds a url to each item in JSON output",
+                )),
+            ]),
+            "filter": section("Files to leave out of every scan", &[
+                ("exclude_patterns", string_list(
+                    "Gitignore-style patterns of paths to skip, e.g. \"*.min.js\"",
+                )),
+            ]),
+            "lint": section("Message-quality rules enforced by `todos check`", &[
+                ("min_length", integer("Minimum message length in characters")),
+                ("deny_empty", boolean("Reject items with an empty message")),
+                ("deny_generic", boolean("Reject low-information messages such as \"fix this\"")),
+                ("generic_phrases", string_list("Additional phrases treated as generic")),
+                ("pattern", string("Regex template every message must match")),
+            ]),
+            "escalation": section("Age thresholds (git blame) for check and SARIF output", &[
+                ("warn_after_days", integer("Items older than this many days are warnings")),
+                ("error_after_days", integer("Items older than this many days are errors")),
+            ]),
+            "components": map(
+                "Path patterns (gitignore syntax) to component names",
+                string("Component name"),
+            ),
+            "component_budgets": map(
+                "Maximum TODOs per component, enforced by `todos check`",
+                integer("Maximum TODOs"),
+            ),
+            "test_code": section("How test code is told apart from production code", &[
+                ("paths", string_list("Path patterns of test files, replacing the defaults")),
+            ]),
+            "cache": section("The scan cache in .todo-tracker/", &[
+                ("branch_namespaces", boolean("Keep cached files and items per git branch")),
+            ]),
+            "blame": section("How git blame attributes lines", &[
+                ("ignore_revs_file", string(
+                    "File of commits blame looks past, such as mass reformats \
+                     (default .git-blame-ignore-revs)",
+                )),
+            ]),
+            "webhooks": {
+                "description": "URLs `todos serve` posts to when TODOs appear or disappear",
+                "type": "array",
+                "items": section("A webhook", &[
+                    ("url", string("URL to POST the todos.changed event to")),
+                    ("tags", string_list("Only report items with these tags")),
+                ]),
+            },
+            "digest": section("Email settings for `todos digest --send`", &[
+                ("smtp_host", string("SMTP server host")),
+                ("smtp_port", integer("SMTP server port")),
+                ("allow_remote", boolean(
+                    "Send through a server that isn't on this machine; mail is not encrypted",
+                )),
+                ("from", string("Envelope and header sender address")),
+                ("subject", string("Subject line of the digest emails")),
+            ]),
+            "hook": section("Git hook behavior", &[
+                ("commit_msg", one_of(
+                    "What `hook commit-msg` does about new TODOs without an issue",
+                    &["block", "trailer"],
+                )),
+                ("trailer", string("Trailer key used in trailer mode")),
+            ]),
+            "hooks": section("Commands run around a scan", &[
+                ("post_scan", string(
+                    "Shell command given the scan result as JSON on stdin; JSON it prints \
+                     replaces the result",
+                )),
+            ]),
+            "theme": section("Colors and styles of text output", &[
+                ("name", one_of(
+                    "Built-in theme to start from",
+                    &["default", "high-contrast", "monochrome"],
+                )),
+                ("symbols", boolean("Prefix each tag with a symbol")),
+                ("path", string("Style of file path headers, e.g. \"bold\"")),
+                ("line", string("Style of L<n> line numbers")),
+                ("metadata", string("Style of the (author, #issue, p:priority) suffix")),
+                ("snippet", string("Style of source lines shown by --snippets")),
+                ("tags", map("Styles per tag, e.g. TODO = \"yellow bold\"", string("Style"))),
+            ]),
+        },
+    })
+}
+
+/// A table with known keys only.
+fn section(description: &str, properties: &[(&str, Value)]) -> Value {
+    let properties: Map<String, Value> = properties
+        .iter()
+        .map(|(name, schema)| (name.to_string(), schema.clone()))
+        .collect();
+    json!({
+        "description": description,
+        "type": "object",
+        "additionalProperties": false,
+        "properties": properties,
+    })
+}
+
+/// A table with free-form keys and values of one schema.
+fn map(description: &str, values: Value) -> Value {
+    json!({ "description": description, "type": "object", "additionalProperties": values })
+}
+
+fn string(description: &str) -> Value {
+    json!({ "description": description, "type": "string" })
+}
+
+fn one_of(description: &str, values: &[&str]) -> Value {
+    json!({ "description": description, "type": "string", "enum": values })
+}
+
+fn boolean(description: &str) -> Value {
+    json!({ "description": description, "type": "boolean" })
+}
+
+fn integer(description: &str) -> Value {
+    json!({ "description": description, "type": "integer", "minimum": 0 })
+}
+
+fn string_list(description: &str) -> Value {
+    json!({ "description": description, "type": "array", "items": { "type": "string" } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, WebhookConfig};
+
+    /// Every key `Config` knows, with the tables filled in, is in the schema.
+    #[test]
+    fn test_schema_covers_config() {
+        let config = Config {
+            scan: Some(Default::default()),
+            output: Some(Default::default()),
+            filter: Some(Default::default()),
+            lint: Some(Default::default()),
+            escalation: Some(Default::default()),
+            components: Some(Default::default()),
+            component_budgets: Some(Default::default()),
+            test_code: Some(Default::default()),
+            cache: Some(Default::default()),
+            blame: Some(Default::default()),
+            webhooks: Some(vec![WebhookConfig::default()]),
+            digest: Some(Default::default()),
+            hook: Some(Default::default()),
+            hooks: Some(Default::default()),
+            theme: Some(Default::default()),
+            trusted: false,
+        };
+        let schema = config_schema();
+        let config = serde_json::to_value(&config).unwrap();
+        for (section, value) in config.as_object().unwrap() {
+            let section_schema = &schema["properties"][section];
+            assert!(section_schema.is_object(), "{} missing from the schema", section);
+            let (value, section_schema) = match value {
+                Value::Array(items) => (&items[0], &section_schema["items"]),
+                _ => (value, section_schema),
+            };
+            if section_schema["additionalProperties"] != false {
+                continue;
+            }
+            for key in value.as_object().unwrap().keys() {
+                assert!(
+                    section_schema["properties"][key].is_object(),
+                    "{}.{} missing from the schema",
+                    section,
+                    key
+                );
+            }
+        }
+    }
+}
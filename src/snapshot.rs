@@ -0,0 +1,290 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::date::{unix_now, Date};
+use crate::error::{Result, TodoError};
+use crate::git::diff::{diff_items, DiffResult};
+use crate::model::{ScanResult, TodoItem};
+
+/// Snapshot directory, relative to the scan root.
+pub const SNAPSHOT_DIR: &str = ".todo-tracker/snapshots";
+
+/// Default number of snapshots kept by `todos snapshot`.
+pub const DEFAULT_KEEP: usize = 30;
+
+/// Snapshot id for a Unix timestamp, e.g. `2026-02-05T143000Z`. Ids sort
+/// chronologically and are safe to use as file names on every platform. A
+/// second snapshot in the same second gets a counter, as in
+/// `2026-02-05T143000Z-2`.
+pub fn snapshot_id(secs: i64) -> String {
+    let day_secs = secs.rem_euclid(86400);
+    format!(
+        "{}T{:02}{:02}{:02}Z",
+        Date::from_timestamp(secs),
+        day_secs / 3600,
+        day_secs % 3600 / 60,
+        day_secs % 60
+    )
+}
+
+fn now_id() -> String {
+    snapshot_id(unix_now())
+}
+
+/// Stored snapshot ids, oldest first.
+pub fn list_snapshots(root: &Path) -> Vec<String> {
+    let dir = root.join(SNAPSHOT_DIR);
+    let mut ids: Vec<String> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                e.file_name()
+                    .to_str()
+                    .and_then(|n| n.strip_suffix(".json"))
+                    .map(|s| s.to_string())
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    ids.sort_by_cached_key(|id| match id.rsplit_once("Z-").map(|(at, n)| (at, n.parse())) {
+        Some((at, Ok(n))) => (format!("{}Z", at), n),
+        _ => (id.clone(), 1),
+    });
+    ids
+}
+
+/// Write `result` as a new snapshot and return its id and path.
+pub fn write_snapshot(root: &Path, result: &ScanResult) -> Result<(String, PathBuf)> {
+    write_snapshot_as(root, result, &now_id())
+}
+
+fn write_snapshot_as(root: &Path, result: &ScanResult, id: &str) -> Result<(String, PathBuf)> {
+    use std::io::Write;
+
+    let dir = root.join(SNAPSHOT_DIR);
+    fs::create_dir_all(&dir)?;
+    let json = serde_json::to_string_pretty(result)
+        .map_err(|e| TodoError::Config(format!("Failed to serialize snapshot: {}", e)))?;
+    // Never overwrite a snapshot taken earlier in the same second
+    let mut n = 1;
+    loop {
+        let id = if n == 1 { id.to_string() } else { format!("{}-{}", id, n) };
+        let path = dir.join(format!("{}.json", id));
+        match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(json.as_bytes())?;
+                return Ok((id, path));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Delete the oldest snapshots so at most `keep` remain. Returns how many were removed.
+pub fn prune_snapshots(root: &Path, keep: usize) -> Result<usize> {
+    let ids = list_snapshots(root);
+    let excess = ids.len().saturating_sub(keep);
+    for id in &ids[..excess] {
+        fs::remove_file(root.join(SNAPSHOT_DIR).join(format!("{}.json", id)))?;
+    }
+    Ok(excess)
+}
+
+/// Load a snapshot by id, or the newest one for `latest`.
+pub fn load_snapshot(root: &Path, id: &str) -> Result<(String, ScanResult)> {
+    let id = if id == "latest" {
+        list_snapshots(root).pop().ok_or_else(|| {
+            TodoError::Config("No snapshots found; run `todos snapshot` first".to_string())
+        })?
+    } else {
+        id.trim_end_matches(".json").to_string()
+    };
+    let path = root.join(SNAPSHOT_DIR).join(format!("{}.json", id));
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| TodoError::Config(format!("Failed to read snapshot {}: {}", id, e)))?;
+    let result = serde_json::from_str(&contents)
+        .map_err(|e| TodoError::Config(format!("Invalid snapshot {}: {}", id, e)))?;
+    Ok((id, result))
+}
+
+fn relative_items(result: &ScanResult) -> Vec<TodoItem> {
+    result
+        .items
+        .iter()
+        .map(|item| {
+            let mut item = item.clone();
+            item.file = PathBuf::from(item.relative_path(&result.metadata.root_path));
+            item
+        })
+        .collect()
+}
+
+/// Compare a stored snapshot with a fresh scan. Paths are compared relative to
+/// each result's scan root, so a snapshot taken from another checkout location
+/// still lines up.
+pub fn diff_against_snapshot(id: &str, snapshot: &ScanResult, current: &ScanResult) -> DiffResult {
+    let (added, removed) = diff_items(&relative_items(snapshot), &relative_items(current));
+    DiffResult {
+        added,
+        removed,
+        base_ref: format!("snapshot:{}", id),
+        head_ref: "working-tree".to_string(),
+        errors: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats, TodoTag};
+
+    fn make_result(total: usize) -> ScanResult {
+        ScanResult {
+            items: Vec::new(),
+            stats: ScanStats {
+                total_todos: total,
+                ..Default::default()
+            },
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("."),
+                timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
+            },
+        }
+    }
+
+    fn make_item(file: &str, line: usize, message: &str) -> TodoItem {
+        TodoItem {
+            tag: TodoTag::Todo,
+            message: message.to_string(),
+            file: PathBuf::from(file),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_diff_against_snapshot_ignores_root_and_line_moves() {
+        let mut snapshot = make_result(2);
+        snapshot.metadata.root_path = PathBuf::from("/old/checkout");
+        snapshot.items = vec![
+            make_item("/old/checkout/src/a.rs", 3, "kept"),
+            make_item("/old/checkout/src/a.rs", 9, "fixed"),
+        ];
+        let mut current = make_result(2);
+        current.items = vec![
+            make_item("./src/a.rs", 5, "kept"),
+            make_item("./src/b.rs", 1, "new"),
+        ];
+
+        let diff = diff_against_snapshot("2026-02-05T000000Z", &snapshot, &current);
+        assert_eq!(diff.base_ref, "snapshot:2026-02-05T000000Z");
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].file, PathBuf::from("src/b.rs"));
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].message, "fixed");
+    }
+
+    #[test]
+    fn test_snapshot_id_format() {
+        assert_eq!(snapshot_id(0), "1970-01-01T000000Z");
+        assert_eq!(snapshot_id(1697328000 + 3661), "2023-10-15T010101Z");
+    }
+
+    #[test]
+    fn test_write_list_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        write_snapshot_as(dir.path(), &make_result(1), "2026-02-05T000000Z").unwrap();
+        write_snapshot_as(dir.path(), &make_result(2), "2026-02-06T000000Z").unwrap();
+
+        assert_eq!(
+            list_snapshots(dir.path()),
+            vec!["2026-02-05T000000Z", "2026-02-06T000000Z"]
+        );
+
+        let (id, latest) = load_snapshot(dir.path(), "latest").unwrap();
+        assert_eq!(id, "2026-02-06T000000Z");
+        assert_eq!(latest.stats.total_todos, 2);
+
+        let (_, first) = load_snapshot(dir.path(), "2026-02-05T000000Z").unwrap();
+        assert_eq!(first.stats.total_todos, 1);
+    }
+
+    #[test]
+    fn test_snapshots_in_the_same_second_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = "2026-02-05T000000Z";
+        for total in 1..=11 {
+            write_snapshot_as(dir.path(), &make_result(total), id).unwrap();
+        }
+        write_snapshot_as(dir.path(), &make_result(12), "2026-02-05T000001Z").unwrap();
+
+        let ids = list_snapshots(dir.path());
+        assert_eq!(ids.len(), 12);
+        assert_eq!(ids[..3], [id, "2026-02-05T000000Z-2", "2026-02-05T000000Z-3"]);
+        assert_eq!(ids[10], "2026-02-05T000000Z-11");
+        let (latest, _) = load_snapshot(dir.path(), "latest").unwrap();
+        assert_eq!(latest, "2026-02-05T000001Z");
+        let (_, second) = load_snapshot(dir.path(), "2026-02-05T000000Z-2").unwrap();
+        assert_eq!(second.stats.total_todos, 2);
+    }
+
+    #[test]
+    fn test_load_snapshot_without_newer_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshots = dir.path().join(SNAPSHOT_DIR);
+        fs::create_dir_all(&snapshots).unwrap();
+        fs::write(
+            snapshots.join("2026-01-01T000000Z.json"),
+            r#"{"items": [],
+                "stats": {"files_scanned": 3, "files_with_todos": 1, "total_todos": 2,
+                          "by_tag": {"TODO": 2}},
+                "metadata": {"scan_duration_ms": 5, "root_path": ".",
+                             "timestamp": "2026-01-01T00:00:00Z"}}"#,
+        )
+        .unwrap();
+
+        let (_, old) = load_snapshot(dir.path(), "latest").unwrap();
+        assert_eq!(old.stats.total_todos, 2);
+        assert_eq!(old.stats.empty_messages, 0);
+    }
+
+    #[test]
+    fn test_load_missing_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_snapshot(dir.path(), "latest").is_err());
+        assert!(load_snapshot(dir.path(), "2026-01-01T000000Z").is_err());
+    }
+
+    #[test]
+    fn test_prune_keeps_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        for day in 1..=4 {
+            let id = format!("2026-02-0{}T000000Z", day);
+            write_snapshot_as(dir.path(), &make_result(day), &id).unwrap();
+        }
+
+        assert_eq!(prune_snapshots(dir.path(), 2).unwrap(), 2);
+        assert_eq!(
+            list_snapshots(dir.path()),
+            vec!["2026-02-03T000000Z", "2026-02-04T000000Z"]
+        );
+        assert_eq!(prune_snapshots(dir.path(), 5).unwrap(), 0);
+    }
+}
@@ -0,0 +1,244 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+
+use crate::error::{Result, TodoError};
+use crate::model::{ScanStats, TodoItem};
+use crate::ordering::{compare_items, sort_items};
+
+/// Rough heap footprint of an item, used to decide when to spill.
+fn approx_size(item: &TodoItem) -> usize {
+    std::mem::size_of::<TodoItem>()
+        + item.file.as_os_str().len()
+        + item.message.len()
+        + item.context_line.len()
+        + item.author.as_ref().map_or(0, |s| s.len())
+        + item.issue.as_ref().map_or(0, |s| s.len())
+}
+
+/// Collects scan items under a memory budget. Stats are computed as items
+/// arrive; once the buffered items exceed the budget they are sorted and
+/// written to an anonymous temp file as JSON lines. `finish` merges the
+/// sorted runs back into one stream in canonical order.
+pub struct SpillBuffer {
+    budget_bytes: usize,
+    buffer: Vec<TodoItem>,
+    buffered_bytes: usize,
+    runs: Vec<File>,
+    stats: ScanStats,
+}
+
+impl SpillBuffer {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            budget_bytes,
+            buffer: Vec::new(),
+            buffered_bytes: 0,
+            runs: Vec::new(),
+            stats: ScanStats::new(),
+        }
+    }
+
+    /// Add the items found in one file.
+    pub fn push_file_items(&mut self, items: Vec<TodoItem>) -> Result<()> {
+        if !items.is_empty() {
+            self.stats.files_with_todos += 1;
+        }
+        for item in items {
+            self.stats.add_item(&item);
+            self.buffered_bytes += approx_size(&item);
+            self.buffer.push(item);
+        }
+        if self.buffered_bytes > self.budget_bytes {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Record the lines of code of one scanned file.
+    pub fn add_file_lines(&mut self, path: &std::path::Path, lines: usize) {
+        self.stats.add_file_lines(path, lines);
+    }
+
+    /// Number of sorted runs written to disk so far.
+    pub fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    fn spill(&mut self) -> Result<()> {
+        sort_items(&mut self.buffer);
+        let mut file = tempfile::tempfile()?;
+        {
+            let mut writer = BufWriter::new(&mut file);
+            for item in self.buffer.drain(..) {
+                serde_json::to_writer(&mut writer, &item)
+                    .map_err(|e| TodoError::Cache(format!("Failed to spill items: {}", e)))?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+        }
+        file.seek(SeekFrom::Start(0))?;
+        self.runs.push(file);
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+
+    /// Stats of everything pushed, plus all items in canonical order.
+    pub fn finish(mut self) -> Result<(ScanStats, SortedItems)> {
+        sort_items(&mut self.buffer);
+        let mut sources: Vec<Box<dyn Iterator<Item = Result<TodoItem>>>> = self
+            .runs
+            .into_iter()
+            .map(|file| {
+                let lines = BufReader::new(file).lines();
+                Box::new(lines.map(|line| {
+                    let line = line?;
+                    serde_json::from_str(&line).map_err(|e| {
+                        TodoError::Cache(format!("Failed to read spilled items: {}", e))
+                    })
+                })) as Box<dyn Iterator<Item = Result<TodoItem>>>
+            })
+            .collect();
+        sources.push(Box::new(self.buffer.into_iter().map(Ok)));
+
+        let mut items = SortedItems {
+            sources,
+            heap: BinaryHeap::new(),
+            pending_error: None,
+        };
+        for index in 0..items.sources.len() {
+            items.refill(index);
+        }
+        Ok((self.stats, items))
+    }
+}
+
+struct HeapEntry {
+    item: TodoItem,
+    source: usize,
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed: BinaryHeap is a max-heap and the smallest item goes first
+        compare_items(&other.item, &self.item).then(other.source.cmp(&self.source))
+    }
+}
+
+/// K-way merge over sorted runs.
+pub struct SortedItems {
+    sources: Vec<Box<dyn Iterator<Item = Result<TodoItem>>>>,
+    heap: BinaryHeap<HeapEntry>,
+    pending_error: Option<TodoError>,
+}
+
+impl SortedItems {
+    fn refill(&mut self, source: usize) {
+        match self.sources[source].next() {
+            Some(Ok(item)) => self.heap.push(HeapEntry { item, source }),
+            Some(Err(e)) => self.pending_error = Some(e),
+            None => {}
+        }
+    }
+}
+
+impl Iterator for SortedItems {
+    type Item = Result<TodoItem>;
+
+    fn next(&mut self) -> Option<Result<TodoItem>> {
+        if let Some(e) = self.pending_error.take() {
+            return Some(Err(e));
+        }
+        let entry = self.heap.pop()?;
+        self.refill(entry.source);
+        Some(Ok(entry.item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TodoTag;
+    use std::path::PathBuf;
+
+    fn make_item(file: &str, line: usize) -> TodoItem {
+        TodoItem {
+            tag: TodoTag::Todo,
+            message: format!("item {}", line),
+            file: PathBuf::from(file),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_merges_spilled_runs_in_order() {
+        // A tiny budget spills after every file
+        let mut buffer = SpillBuffer::new(1);
+        buffer
+            .push_file_items(vec![make_item("c.rs", 2), make_item("c.rs", 1)])
+            .unwrap();
+        buffer.push_file_items(vec![make_item("a.rs", 5)]).unwrap();
+        buffer.push_file_items(vec![]).unwrap();
+        buffer.push_file_items(vec![make_item("b.rs", 1)]).unwrap();
+        assert_eq!(buffer.spilled_runs(), 3);
+
+        let (stats, items) = buffer.finish().unwrap();
+        assert_eq!(stats.total_todos, 4);
+        assert_eq!(stats.files_with_todos, 3);
+        let order: Vec<(String, usize)> = items
+            .map(|i| i.unwrap())
+            .map(|i| (i.file.display().to_string(), i.line))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("a.rs".to_string(), 5),
+                ("b.rs".to_string(), 1),
+                ("c.rs".to_string(), 1),
+                ("c.rs".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_spill_under_budget() {
+        let mut buffer = SpillBuffer::new(1 << 20);
+        buffer
+            .push_file_items(vec![make_item("b.rs", 1), make_item("a.rs", 1)])
+            .unwrap();
+        assert_eq!(buffer.spilled_runs(), 0);
+        let (_, items) = buffer.finish().unwrap();
+        let files: Vec<String> = items.map(|i| i.unwrap().file.display().to_string()).collect();
+        assert_eq!(files, vec!["a.rs", "b.rs"]);
+    }
+}
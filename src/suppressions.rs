@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::date::Date;
+use crate::model::TodoItem;
+use crate::triage::TriageStore;
+
+/// How an item is kept out of reports or policy checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuppressionKind {
+    /// An ignore decision in the triage store
+    TriageIgnore,
+    /// `allow:` tokens in the TODO's own metadata
+    Exemption,
+}
+
+impl SuppressionKind {
+    fn heading(self) -> &'static str {
+        match self {
+            SuppressionKind::TriageIgnore => "Triage ignores",
+            SuppressionKind::Exemption => "Exemption tokens",
+        }
+    }
+}
+
+/// One active suppression, with who added it and when.
+#[derive(Debug, Clone, Serialize)]
+pub struct Suppression {
+    pub kind: SuppressionKind,
+    /// Path relative to the scan root
+    pub file: String,
+    pub line: usize,
+    pub tag: String,
+    pub message: String,
+    /// Rules an exemption opts out of; empty for triage ignores, which hide
+    /// the item everywhere
+    pub rules: Vec<String>,
+    /// Who committed the suppression, per git blame; `None` when it isn't
+    /// committed yet
+    pub added_by: Option<String>,
+    /// When the suppression was committed or, for uncommitted triage
+    /// decisions, recorded (YYYY-MM-DD)
+    pub added_on: Option<String>,
+    /// Fingerprint of the item, the key of a triage decision
+    #[serde(skip)]
+    pub fingerprint: String,
+}
+
+impl Suppression {
+    /// Days since the suppression was added, when that is known.
+    pub fn age_days(&self, today: Date) -> Option<u64> {
+        let date = self.added_on.as_deref()?;
+        let date = Date::parse(date.get(..10).unwrap_or(date)).ok()?;
+        Some((today.days_since_epoch() - date.days_since_epoch()).max(0) as u64)
+    }
+}
+
+/// The suppressions in effect: triage ignores for the `suppressed` items and
+/// exemption tokens on `items`. Blame data on the items, if any, says who
+/// added an exemption; triage ignores carry their decision date only.
+pub fn collect(
+    items: &[TodoItem],
+    suppressed: &[TodoItem],
+    store: &TriageStore,
+    root: &Path,
+) -> Vec<Suppression> {
+    let mut suppressions = Vec::new();
+    // Identical TODOs have one decision each, matched up in line order
+    let mut suppressed: Vec<&TodoItem> = suppressed.iter().collect();
+    suppressed.sort_by_key(|item| (item.line, item.column));
+    let mut repeats: HashMap<String, usize> = HashMap::new();
+    for item in suppressed {
+        let fingerprint = item.fingerprint(root);
+        let repeat = repeats.entry(fingerprint.clone()).or_default();
+        let key = store.ignored_keys(&fingerprint).get(*repeat).map(|key| key.to_string());
+        *repeat += 1;
+        let Some((key, decision)) = key.and_then(|key| store.get(&key).map(|d| (key, d))) else {
+            continue;
+        };
+        suppressions.push(Suppression {
+            kind: SuppressionKind::TriageIgnore,
+            file: item.relative_path(root),
+            line: item.line,
+            tag: item.tag.to_string(),
+            message: item.message.clone(),
+            rules: Vec::new(),
+            added_by: None,
+            added_on: Some(decision.decided_at.clone()),
+            fingerprint: key,
+        });
+    }
+    for item in items.iter().filter(|item| !item.exemptions.is_empty()) {
+        let committed = item.git_commit.is_some();
+        suppressions.push(Suppression {
+            kind: SuppressionKind::Exemption,
+            file: item.relative_path(root),
+            line: item.line,
+            tag: item.tag.to_string(),
+            message: item.message.clone(),
+            rules: item.exemptions.clone(),
+            added_by: item.git_author.clone().filter(|_| committed),
+            added_on: item.git_date.clone().filter(|_| committed),
+            fingerprint: item.fingerprint(root),
+        });
+    }
+    suppressions.sort_by(|a, b| (a.kind, &a.file, a.line).cmp(&(b.kind, &b.file, b.line)));
+    suppressions
+}
+
+/// The 1-based line of the triage store file holding the decision keyed by
+/// `fingerprint`, to blame for who added it.
+pub fn decision_line(store_json: &str, fingerprint: &str) -> Option<usize> {
+    let key = format!("\"{}\":", fingerprint);
+    store_json
+        .lines()
+        .position(|line| line.trim_start().starts_with(&key))
+        .map(|index| index + 1)
+}
+
+/// Suppressions grouped by kind, oldest first within a kind.
+pub fn render_text(suppressions: &[Suppression], today: Date) -> String {
+    let mut out = String::new();
+    if suppressions.is_empty() {
+        writeln!(out, "No active suppressions.").unwrap();
+        return out;
+    }
+    writeln!(out, "Suppressions ({}):", suppressions.len()).unwrap();
+    for kind in [SuppressionKind::TriageIgnore, SuppressionKind::Exemption] {
+        let mut group: Vec<&Suppression> = suppressions.iter().filter(|s| s.kind == kind).collect();
+        if group.is_empty() {
+            continue;
+        }
+        group.sort_by_key(|s| std::cmp::Reverse(s.age_days(today)));
+        writeln!(out, "\n{} ({}):", kind.heading(), group.len()).unwrap();
+        for s in group {
+            let rules: Vec<String> = s.rules.iter().map(|r| format!("allow:{} ", r)).collect();
+            let who = s.added_by.as_deref().unwrap_or("not committed");
+            let age = match s.age_days(today) {
+                Some(days) => format!("{} days ago", days),
+                None => "date unknown".to_string(),
+            };
+            writeln!(
+                out,
+                "  {}:{} [{}] {}{}  ({}, {})",
+                s.file,
+                s.line,
+                s.tag,
+                rules.concat(),
+                s.message,
+                who,
+                age
+            )
+            .unwrap();
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TodoTag;
+    use crate::triage::TriageDecision;
+    use std::path::PathBuf;
+
+    fn item(file: &str, line: usize, message: &str) -> TodoItem {
+        TodoItem {
+            tag: TodoTag::Todo,
+            message: message.to_string(),
+            file: PathBuf::from("/repo").join(file),
+            line,
+            column: 1,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: String::new(),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_collect_and_render() {
+        let root = Path::new("/repo");
+        let ignored = item("a.rs", 3, "noisy");
+        let mut store = TriageStore::default();
+        let mut decision = TriageDecision::for_item(&ignored, root);
+        decision.ignored = true;
+        decision.decided_at = "2024-05-01".to_string();
+        store.record(ignored.fingerprint(root), decision);
+
+        let mut exempt = item("b.rs", 7, "legacy");
+        exempt.exemptions = vec!["max_todos".to_string()];
+        exempt.git_author = Some("alice".to_string());
+        exempt.git_date = Some("2024-01-01T09:00:00Z".to_string());
+        exempt.git_commit = Some("abc123".to_string());
+        let mut uncommitted = item("c.rs", 1, "new");
+        uncommitted.exemptions = vec!["lint".to_string()];
+        uncommitted.git_author = Some("Not Committed Yet".to_string());
+        let items = vec![item("d.rs", 1, "plain"), exempt, uncommitted];
+
+        let suppressions = collect(&items, &[ignored], &store, root);
+        let found: Vec<(SuppressionKind, &str, Option<&str>)> = suppressions
+            .iter()
+            .map(|s| (s.kind, s.file.as_str(), s.added_by.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (SuppressionKind::TriageIgnore, "a.rs", None),
+                (SuppressionKind::Exemption, "b.rs", Some("alice")),
+                (SuppressionKind::Exemption, "c.rs", None),
+            ]
+        );
+
+        let today = Date::parse("2024-06-01").unwrap();
+        assert_eq!(suppressions[1].age_days(today), Some(152));
+        let text = render_text(&suppressions, today);
+        assert!(text.starts_with("Suppressions (3):\n\nTriage ignores (1):\n"), "{}", text);
+        assert!(text.contains("  a.rs:3 [TODO] noisy  (not committed, 31 days ago)"), "{}", text);
+        assert!(text.contains("  b.rs:7 [TODO] allow:max_todos legacy  (alice, 152 days ago)"));
+        assert!(text.contains("  c.rs:1 [TODO] allow:lint new  (not committed, date unknown)"));
+        assert_eq!(render_text(&[], today), "No active suppressions.\n");
+    }
+
+    #[test]
+    fn test_decision_line() {
+        let json = "{\n  \"decisions\": {\n    \"abc\": {\n      \"ignored\": true\n    }\n  }\n}";
+        assert_eq!(decision_line(json, "abc"), Some(3));
+        assert_eq!(decision_line(json, "def"), None);
+    }
+}
@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TodoError};
+use crate::model::ScanResult;
+
+/// Path patterns (gitignore syntax) of test files when `[test_code] paths`
+/// isn't set.
+pub const DEFAULT_TEST_PATHS: &[&str] = &[
+    "tests/",
+    "test/",
+    "__tests__/",
+    "spec/",
+    "testdata/",
+    "*_test.go",
+    "*_test.py",
+    "test_*.py",
+    "*_test.rs",
+    "*.test.*",
+    "*.spec.*",
+    "*Test.java",
+    "*Tests.java",
+    "*Tests.cs",
+    "*_spec.rb",
+];
+
+/// `[test_code]` config section.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TestCodeConfig {
+    /// Path patterns of test files, replacing [`DEFAULT_TEST_PATHS`]
+    pub paths: Option<Vec<String>>,
+}
+
+/// Tells test files from production files by path.
+pub struct TestPaths {
+    matcher: Gitignore,
+}
+
+impl TestPaths {
+    /// Patterns are relative to `root`; `None` uses [`DEFAULT_TEST_PATHS`].
+    pub fn new(root: &Path, patterns: Option<&[String]>) -> Result<Self> {
+        let mut builder = GitignoreBuilder::new(root);
+        let defaults: Vec<String>;
+        let patterns = match patterns {
+            Some(patterns) => patterns,
+            None => {
+                defaults = DEFAULT_TEST_PATHS.iter().map(|p| p.to_string()).collect();
+                &defaults
+            }
+        };
+        for pattern in patterns {
+            builder.add_line(None, pattern).map_err(|e| {
+                TodoError::Config(format!("Invalid test code pattern '{}': {}", pattern, e))
+            })?;
+        }
+        let matcher = builder
+            .build()
+            .map_err(|e| TodoError::Config(format!("Invalid test code patterns: {}", e)))?;
+        Ok(TestPaths { matcher })
+    }
+
+    /// Whether `path`, under the root or relative to it, is a test file.
+    pub fn is_test_file(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(self.matcher.path()).unwrap_or(path);
+        self.matcher
+            .matched_path_or_any_parents(relative, false)
+            .is_ignore()
+    }
+
+    /// Mark items in test files as test code and recount the stats. Items
+    /// the scanner found in test modules stay marked.
+    pub fn classify(&self, result: &mut ScanResult) {
+        for item in &mut result.items {
+            item.in_test_code |= self.is_test_file(&item.file);
+        }
+        result.recompute_stats();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_default_test_paths() {
+        let root = PathBuf::from("/repo");
+        let paths = TestPaths::new(&root, None).unwrap();
+        let is_test = |path: &str| paths.is_test_file(&root.join(path));
+        assert!(is_test("tests/cli_test.rs"));
+        assert!(is_test("web/src/__tests__/app.tsx"));
+        assert!(is_test("pkg/server/handler_test.go"));
+        assert!(is_test("web/src/app.test.ts"));
+        assert!(is_test("src/test/java/com/acme/BillingTest.java"));
+        assert!(!is_test("src/main.rs"));
+        assert!(!is_test("src/testing.rs"));
+        assert!(!is_test("pkg/server/handler.go"));
+
+        let custom = TestPaths::new(&root, Some(&["qa/".to_string()])).unwrap();
+        assert!(custom.is_test_file(Path::new("qa/smoke.py")));
+        assert!(!custom.is_test_file(Path::new("tests/a.rs")));
+    }
+}
@@ -0,0 +1,846 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::date::Date;
+use crate::error::{Result, TodoError};
+use crate::model::{Priority, ScanResult, TodoItem};
+
+/// Default triage store location, relative to the scan root.
+pub const TRIAGE_FILE: &str = ".todo-triage.json";
+
+/// A recorded triage decision for one item, keyed in the store by
+/// [`triage_keys`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TriageDecision {
+    pub file: String,
+    pub tag: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<String>,
+    #[serde(default)]
+    pub ignored: bool,
+    pub decided_at: String,
+}
+
+impl TriageDecision {
+    pub fn for_item(item: &TodoItem, root: &Path) -> Self {
+        TriageDecision {
+            file: item.relative_path(root),
+            tag: item.tag.as_str().to_string(),
+            message: item.message.clone(),
+            decided_at: today(),
+            ..Default::default()
+        }
+    }
+
+    /// True when nothing was assigned and the item was not ignored.
+    pub fn is_empty(&self) -> bool {
+        self.priority.is_none() && self.author.is_none() && self.issue.is_none() && !self.ignored
+    }
+}
+
+/// Persisted triage decisions. Subsequent runs apply them to scan results and
+/// `todos triage` skips items that already have one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TriageStore {
+    pub decisions: BTreeMap<String, TriageDecision>,
+}
+
+impl TriageStore {
+    /// Load the store from `path`. A missing file yields an empty store.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(TriageStore::default());
+        }
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            TodoError::Config(format!("Failed to parse triage file {}: {}", path.display(), e))
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| TodoError::Config(e.to_string()))?;
+        std::fs::write(path, json + "\n")?;
+        Ok(())
+    }
+
+    pub fn get(&self, fingerprint: &str) -> Option<&TriageDecision> {
+        self.decisions.get(fingerprint)
+    }
+
+    pub fn record(&mut self, fingerprint: String, decision: TriageDecision) {
+        self.decisions.insert(fingerprint, decision);
+    }
+
+    pub fn remove(&mut self, fingerprint: &str) -> Option<TriageDecision> {
+        self.decisions.remove(fingerprint)
+    }
+
+    /// Keys of the ignore decisions for the identical TODOs with
+    /// `fingerprint`, in occurrence order.
+    pub fn ignored_keys(&self, fingerprint: &str) -> Vec<&str> {
+        let mut keys: Vec<(usize, &str)> = self
+            .decisions
+            .iter()
+            .filter(|(_, decision)| decision.ignored)
+            .filter_map(|(key, _)| {
+                let repeat = match key.strip_prefix(fingerprint)? {
+                    "" => 0,
+                    suffix => suffix.strip_prefix('-')?.parse().ok()?,
+                };
+                Some((repeat, key.as_str()))
+            })
+            .collect();
+        keys.sort();
+        keys.into_iter().map(|(_, key)| key).collect()
+    }
+
+    /// Apply decisions to a scan result: ignored items are removed and assigned
+    /// fields override the values parsed from source. Returns the suppressed
+    /// items, which are also counted in `stats.suppressed`.
+    pub fn apply(&self, result: &mut ScanResult) -> Vec<TodoItem> {
+        if self.decisions.is_empty() {
+            return Vec::new();
+        }
+        let root = result.metadata.root_path.clone();
+        let mut suppressed = Vec::new();
+        for (key, mut item) in keyed(&std::mem::take(&mut result.items), &root) {
+            match self.get(&key) {
+                Some(decision) if decision.ignored => suppressed.push(item),
+                Some(decision) => {
+                    apply_decision(&mut item, decision);
+                    result.items.push(item);
+                }
+                None => result.items.push(item),
+            }
+        }
+        result.recompute_stats();
+        result.stats.suppressed += suppressed.len();
+        suppressed
+    }
+}
+
+fn apply_decision(item: &mut TodoItem, decision: &TriageDecision) {
+    if decision.priority.is_some() {
+        item.priority = decision.priority.clone();
+    }
+    if decision.author.is_some() {
+        item.author = decision.author.clone();
+    }
+    if decision.issue.is_some() {
+        item.issue = decision.issue.clone();
+    }
+}
+
+/// Store keys for `items`: each item's fingerprint, with `-<n>` appended for
+/// the n-th repeat of an identical TODO in the same file, counting in line
+/// order. Identical TODOs share a fingerprint, and this keeps their decisions
+/// apart the way the baseline counts repeats.
+pub fn triage_keys(items: &[TodoItem], root: &Path) -> Vec<String> {
+    let fingerprints: Vec<String> = items.iter().map(|item| item.fingerprint(root)).collect();
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    order.sort_by_key(|&i| (&fingerprints[i], items[i].line, items[i].column));
+    let mut keys = fingerprints.clone();
+    let mut repeat = 0;
+    for (n, &i) in order.iter().enumerate() {
+        let same = n > 0 && fingerprints[order[n - 1]] == fingerprints[i];
+        repeat = if same { repeat + 1 } else { 0 };
+        if repeat > 0 {
+            keys[i] = format!("{}-{}", fingerprints[i], repeat);
+        }
+    }
+    keys
+}
+
+/// `items` paired with their [`triage_keys`].
+pub fn keyed(items: &[TodoItem], root: &Path) -> Vec<(String, TodoItem)> {
+    triage_keys(items, root).into_iter().zip(items.iter().cloned()).collect()
+}
+
+fn today() -> String {
+    Date::today().to_string()
+}
+
+/// Normalize an issue reference so it round-trips through metadata parsing.
+fn normalize_issue(issue: &str) -> String {
+    let issue = issue.trim();
+    if issue.starts_with('#') {
+        issue.to_string()
+    } else {
+        format!("#{}", issue)
+    }
+}
+
+/// Actions applied to every matching item by non-interactive triage.
+#[derive(Debug, Clone, Default)]
+pub struct BulkAction {
+    pub priority: Option<Priority>,
+    pub author: Option<String>,
+    pub issue: Option<String>,
+    pub ignore: bool,
+}
+
+impl BulkAction {
+    pub fn is_empty(&self) -> bool {
+        self.priority.is_none() && self.author.is_none() && self.issue.is_none() && !self.ignore
+    }
+}
+
+/// Record the same decision for every item, given with its store key (see
+/// [`keyed`]). Returns the keys recorded.
+pub fn apply_bulk(
+    items: &[(String, TodoItem)],
+    store: &mut TriageStore,
+    root: &Path,
+    action: &BulkAction,
+) -> Vec<String> {
+    let mut decided = Vec::new();
+    for (fp, item) in items {
+        let mut decision = store
+            .get(fp)
+            .cloned()
+            .unwrap_or_else(|| TriageDecision::for_item(item, root));
+        if action.priority.is_some() {
+            decision.priority = action.priority.clone();
+        }
+        if let Some(ref author) = action.author {
+            decision.author = Some(author.clone());
+        }
+        if let Some(ref issue) = action.issue {
+            decision.issue = Some(normalize_issue(issue));
+        }
+        decision.ignored = decision.ignored || action.ignore;
+        decision.decided_at = today();
+        store.record(fp.clone(), decision);
+        decided.push(fp.clone());
+    }
+    decided
+}
+
+const HELP: &str = "\
+  p <level>   set priority (low, medium, high, critical)
+  a <name>    set author
+  i <issue>   set issue reference
+  x           ignore this item
+  X           ignore this and the remaining items in the same file
+  s           skip without recording a decision
+  <enter>     record the decision and move to the next item
+  q           record and quit
+";
+
+/// Walk items, given with their store keys, one by one, reading commands
+/// from `input` and prompting on `output`. Returns the keys decided during
+/// the session.
+pub fn run_interactive<R: BufRead, W: Write>(
+    items: &[(String, TodoItem)],
+    store: &mut TriageStore,
+    root: &Path,
+    mut input: R,
+    mut output: W,
+) -> Result<Vec<String>> {
+    let mut decided = Vec::new();
+    let mut ignore_file: Option<std::path::PathBuf> = None;
+
+    'items: for (idx, (fp, item)) in items.iter().enumerate() {
+        let fp = fp.clone();
+        let mut decision = store
+            .get(&fp)
+            .cloned()
+            .unwrap_or_else(|| TriageDecision::for_item(item, root));
+
+        if ignore_file.as_ref() == Some(&item.file) {
+            decision.ignored = true;
+            decision.decided_at = today();
+            store.record(fp.clone(), decision);
+            decided.push(fp);
+            continue;
+        }
+
+        writeln!(
+            output,
+            "[{}/{}] {}:{} [{}] {}",
+            idx + 1,
+            items.len(),
+            item.relative_path(root),
+            item.line,
+            item.tag,
+            item.message
+        )?;
+        writeln!(output, "    {}", item.context_line.trim())?;
+
+        loop {
+            write!(output, "triage> ")?;
+            output.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                // EOF behaves like quit
+                if !decision.is_empty() {
+                    store.record(fp.clone(), decision);
+                    decided.push(fp);
+                }
+                break 'items;
+            }
+            let line = line.trim();
+            let (cmd, arg) = match line.split_once(char::is_whitespace) {
+                Some((c, a)) => (c, a.trim()),
+                None => (line, ""),
+            };
+
+            match cmd {
+                "" | "n" => {
+                    if !decision.is_empty() {
+                        decision.decided_at = today();
+                        store.record(fp.clone(), decision);
+                        decided.push(fp);
+                    }
+                    continue 'items;
+                }
+                "p" => match Priority::from_str_tag(arg) {
+                    Some(p) => decision.priority = Some(p),
+                    None => writeln!(output, "unknown priority: {}", arg)?,
+                },
+                "a" if !arg.is_empty() => decision.author = Some(arg.to_string()),
+                "i" if !arg.is_empty() => decision.issue = Some(normalize_issue(arg)),
+                "x" | "X" => {
+                    decision.ignored = true;
+                    decision.decided_at = today();
+                    store.record(fp.clone(), decision);
+                    decided.push(fp);
+                    if cmd == "X" {
+                        ignore_file = Some(item.file.clone());
+                    }
+                    continue 'items;
+                }
+                "s" => continue 'items,
+                "q" => {
+                    if !decision.is_empty() {
+                        decision.decided_at = today();
+                        store.record(fp.clone(), decision);
+                        decided.push(fp);
+                    }
+                    break 'items;
+                }
+                _ => write!(output, "{}", HELP)?,
+            }
+        }
+    }
+
+    Ok(decided)
+}
+
+/// Rewrite the tag on `line` (starting at 1-based byte `column`) as
+/// `TAG(author, #issue, p:level)`, replacing any existing metadata parens.
+/// Returns `None` when the tag is no longer at that position.
+fn rewrite_line(line: &str, column: usize, item: &TodoItem, decision: &TriageDecision) -> Option<String> {
+    let tag = item.tag.as_str();
+    let start = column.checked_sub(1)?;
+    if !line.get(start..)?.starts_with(tag) {
+        return None;
+    }
+    let tag_end = start + tag.len();
+    let rest = &line[tag_end..];
+    let after = if rest.starts_with('(') {
+        match rest.find(')') {
+            Some(close) => &rest[close + 1..],
+            None => rest,
+        }
+    } else {
+        rest
+    };
+
+    let mut parts: Vec<String> = Vec::new();
+    if let Some(author) = decision.author.as_ref().or(item.author.as_ref()) {
+        parts.push(author.clone());
+    }
+    if let Some(issue) = decision.issue.as_ref().or(item.issue.as_ref()) {
+        parts.push(normalize_issue(issue));
+    }
+    if let Some(priority) = decision.priority.as_ref().or(item.priority.as_ref()) {
+        parts.push(format!("p:{}", priority.as_str()));
+    }
+
+    let meta = if parts.is_empty() {
+        String::new()
+    } else {
+        format!("({})", parts.join(", "))
+    };
+    Some(format!("{}{}{}{}", &line[..start], tag, meta, after))
+}
+
+/// Write non-ignored decisions back into source comments, for items given
+/// with their store keys. Returns the number of lines rewritten.
+pub fn write_back(items: &[(String, TodoItem)], store: &TriageStore) -> Result<usize> {
+    let mut by_file: BTreeMap<&Path, Vec<(&TodoItem, &TriageDecision)>> = BTreeMap::new();
+    for (key, item) in items {
+        if let Some(decision) = store.get(key) {
+            if !decision.ignored && !decision.is_empty() {
+                by_file.entry(item.file.as_path()).or_default().push((item, decision));
+            }
+        }
+    }
+
+    let mut rewritten = 0;
+    for (file, edits) in by_file {
+        let content = std::fs::read_to_string(file)?;
+        let mut lines: Vec<String> = content.split_inclusive('\n').map(String::from).collect();
+        for (item, decision) in edits {
+            let Some(line) = lines.get_mut(item.line - 1) else {
+                continue;
+            };
+            let ending_len = line.len() - line.trim_end_matches(['\r', '\n']).len();
+            let (body, ending) = line.split_at(line.len() - ending_len);
+            if let Some(new_body) = rewrite_line(body, item.column, item, decision) {
+                if new_body != body {
+                    *line = format!("{}{}", new_body, ending);
+                    rewritten += 1;
+                }
+            }
+        }
+        std::fs::write(file, lines.concat())?;
+    }
+    Ok(rewritten)
+}
+
+#[cfg(feature = "csv")]
+const EXCHANGE_HEADER: [&str; 9] = [
+    "fingerprint",
+    "file",
+    "line",
+    "tag",
+    "message",
+    "priority",
+    "author",
+    "issue",
+    "ignored",
+];
+
+#[cfg(feature = "csv")]
+/// Export items with their effective triage values as CSV. The `fingerprint`
+/// column identifies rows on import; `priority`, `author`, `issue` and
+/// `ignored` are the editable columns.
+pub fn export_csv(items: &[TodoItem], store: &TriageStore, root: &Path) -> Result<String> {
+    let to_err = |e: ::csv::Error| TodoError::Config(e.to_string());
+    let mut wtr = ::csv::WriterBuilder::new().from_writer(Vec::new());
+    wtr.write_record(EXCHANGE_HEADER).map_err(to_err)?;
+
+    for (fp, item) in keyed(items, root) {
+        let mut effective = item.clone();
+        let ignored = match store.get(&fp) {
+            Some(decision) => {
+                apply_decision(&mut effective, decision);
+                decision.ignored
+            }
+            None => false,
+        };
+        wtr.write_record([
+            fp,
+            item.relative_path(root),
+            item.line.to_string(),
+            item.tag.as_str().to_string(),
+            item.message.clone(),
+            effective.priority.as_ref().map(|p| p.as_str().to_string()).unwrap_or_default(),
+            effective.author.unwrap_or_default(),
+            effective.issue.unwrap_or_default(),
+            if ignored { "yes".to_string() } else { String::new() },
+        ])
+        .map_err(to_err)?;
+    }
+
+    let bytes = wtr.into_inner().map_err(|e| TodoError::Config(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| TodoError::Config(e.to_string()))
+}
+
+/// Outcome of importing an edited export.
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    pub updated: usize,
+    pub cleared: usize,
+    pub unchanged: usize,
+    /// Rows whose fingerprint no longer matches a scanned item
+    pub unmatched: usize,
+}
+
+#[cfg(feature = "csv")]
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value.map(str::trim).filter(|v| !v.is_empty()).map(String::from)
+}
+
+#[cfg(feature = "csv")]
+/// Read an edited CSV export and record the differences from the source
+/// comments as triage decisions. Rows are matched to `items` by fingerprint.
+pub fn import_csv<R: std::io::Read>(
+    reader: R,
+    items: &[TodoItem],
+    store: &mut TriageStore,
+    root: &Path,
+) -> Result<ImportSummary> {
+    let by_fp: std::collections::HashMap<String, &TodoItem> =
+        triage_keys(items, root).into_iter().zip(items).collect();
+
+    let mut rdr = ::csv::Reader::from_reader(reader);
+    let headers = rdr
+        .headers()
+        .map_err(|e| TodoError::Config(e.to_string()))?
+        .clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+    let fp_col = column("fingerprint").ok_or_else(|| {
+        TodoError::Config("Import file has no 'fingerprint' column".to_string())
+    })?;
+    let (priority_col, author_col, issue_col, ignored_col) =
+        (column("priority"), column("author"), column("issue"), column("ignored"));
+
+    let mut summary = ImportSummary::default();
+    for (idx, record) in rdr.records().enumerate() {
+        let record = record.map_err(|e| TodoError::Config(e.to_string()))?;
+        let row = idx + 2; // 1-based, after the header
+        let fp = record.get(fp_col).unwrap_or("").trim().to_string();
+        let Some(item) = by_fp.get(&fp) else {
+            summary.unmatched += 1;
+            continue;
+        };
+        let cell = |col: Option<usize>| non_empty(col.and_then(|c| record.get(c)));
+
+        let priority = match cell(priority_col) {
+            Some(p) => Some(Priority::from_str_tag(&p).ok_or_else(|| {
+                TodoError::Config(format!("Row {}: unknown priority '{}'", row, p))
+            })?),
+            None => None,
+        };
+        let ignored = cell(ignored_col)
+            .map(|v| matches!(v.to_lowercase().as_str(), "yes" | "y" | "true" | "1" | "x"))
+            .unwrap_or(false);
+
+        // Only values that differ from the source comment become decisions
+        let mut decision = TriageDecision::for_item(item, root);
+        decision.priority = priority.filter(|p| item.priority.as_ref() != Some(p));
+        decision.author = cell(author_col).filter(|a| item.author.as_ref() != Some(a));
+        decision.issue = cell(issue_col)
+            .map(|i| normalize_issue(&i))
+            .filter(|i| item.issue.as_ref() != Some(i));
+        decision.ignored = ignored;
+
+        let existing = store.get(&fp);
+        if decision.is_empty() {
+            if existing.is_some() {
+                store.remove(&fp);
+                summary.cleared += 1;
+            } else {
+                summary.unchanged += 1;
+            }
+            continue;
+        }
+        let same = existing.is_some_and(|e| {
+            e.priority == decision.priority
+                && e.author == decision.author
+                && e.issue == decision.issue
+                && e.ignored == decision.ignored
+        });
+        if same {
+            summary.unchanged += 1;
+        } else {
+            store.record(fp, decision);
+            summary.updated += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ScanMetadata, ScanStats, TodoTag};
+    use std::io::Cursor;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    fn make_item(file: &str, line: usize, message: &str) -> TodoItem {
+        TodoItem {
+            tag: TodoTag::Todo,
+            message: message.to_string(),
+            file: PathBuf::from(file),
+            line,
+            column: 4,
+            author: None,
+            issue: None,
+            priority: None,
+            context_line: format!("// TODO: {}", message),
+            git_author: None,
+            git_date: None,
+            git_commit: None,
+            git_summary: None,
+            pr_number: None,
+            exemptions: Vec::new(),
+            blame_incomplete: false,
+            component: None,
+            in_test_code: false,
+            extra: Default::default(),
+        }
+    }
+
+    fn make_result(items: Vec<TodoItem>) -> ScanResult {
+        let mut result = ScanResult {
+            items,
+            stats: ScanStats::new(),
+            metadata: ScanMetadata {
+                scan_duration_ms: 0,
+                root_path: PathBuf::from("."),
+                timestamp: "2026-02-05T00:00:00Z".to_string(),
+                blame_cache_hits: None,
+            },
+        };
+        result.recompute_stats();
+        result
+    }
+
+    #[test]
+    fn test_interactive_assign_and_ignore() {
+        let items = vec![
+            make_item("./src/a.rs", 1, "first"),
+            make_item("./src/a.rs", 2, "second"),
+            make_item("./src/b.rs", 3, "third"),
+        ];
+        let mut store = TriageStore::default();
+        let input = Cursor::new("p high\na alice\ni 42\n\nx\ns\n");
+        let mut output = Vec::new();
+        let keyed = keyed(&items, Path::new("."));
+        let decided =
+            run_interactive(&keyed, &mut store, Path::new("."), input, &mut output).unwrap();
+
+        assert_eq!(decided.len(), 2);
+        let first = store.get(&items[0].fingerprint(Path::new("."))).unwrap();
+        assert_eq!(first.priority, Some(Priority::High));
+        assert_eq!(first.author.as_deref(), Some("alice"));
+        assert_eq!(first.issue.as_deref(), Some("#42"));
+        assert_eq!(first.file, "src/a.rs");
+        assert!(store.get(&items[1].fingerprint(Path::new("."))).unwrap().ignored);
+        assert!(store.get(&items[2].fingerprint(Path::new("."))).is_none());
+        assert!(String::from_utf8(output).unwrap().contains("[1/3] src/a.rs:1 [TODO] first"));
+    }
+
+    #[test]
+    fn test_interactive_ignore_rest_of_file() {
+        let items = vec![
+            make_item("src/a.rs", 1, "first"),
+            make_item("src/a.rs", 2, "second"),
+            make_item("src/b.rs", 1, "third"),
+        ];
+        let mut store = TriageStore::default();
+        let decided = run_interactive(
+            &keyed(&items, Path::new(".")),
+            &mut store,
+            Path::new("."),
+            Cursor::new("X\nq\n"),
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(decided.len(), 2);
+        assert!(store.decisions.values().all(|d| d.ignored && d.decided_at == today()));
+    }
+
+    #[test]
+    fn test_interactive_eof_stops() {
+        let items = vec![make_item("src/a.rs", 1, "first")];
+        let mut store = TriageStore::default();
+        let items = keyed(&items, Path::new("."));
+        let decided =
+            run_interactive(&items, &mut store, Path::new("."), Cursor::new(""), Vec::new())
+                .unwrap();
+        assert!(decided.is_empty());
+    }
+
+    #[test]
+    fn test_apply_bulk() {
+        let items = vec![make_item("src/a.rs", 1, "first"), make_item("src/b.rs", 1, "second")];
+        let mut store = TriageStore::default();
+        let action = BulkAction {
+            priority: Some(Priority::Low),
+            ..Default::default()
+        };
+        let items = keyed(&items, Path::new("."));
+        let decided = apply_bulk(&items, &mut store, Path::new("."), &action);
+        assert_eq!(decided.len(), 2);
+        assert!(store
+            .decisions
+            .values()
+            .all(|d| d.priority == Some(Priority::Low) && !d.ignored));
+    }
+
+    #[test]
+    fn test_store_apply_suppresses_and_overrides() {
+        let items = vec![make_item("src/a.rs", 1, "keep"), make_item("src/a.rs", 2, "drop")];
+        let mut store = TriageStore::default();
+        let root = Path::new(".");
+        let mut keep = TriageDecision::for_item(&items[0], root);
+        keep.author = Some("bob".to_string());
+        store.record(items[0].fingerprint(root), keep);
+        let mut drop = TriageDecision::for_item(&items[1], root);
+        drop.ignored = true;
+        store.record(items[1].fingerprint(root), drop);
+
+        let mut result = make_result(items);
+        let suppressed = store.apply(&mut result);
+        assert_eq!(suppressed.len(), 1);
+        assert_eq!(suppressed[0].message, "drop");
+        assert_eq!(result.stats.suppressed, 1);
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(result.items[0].author.as_deref(), Some("bob"));
+        assert_eq!(result.stats.total_todos, 1);
+    }
+
+    #[test]
+    fn test_identical_todos_have_separate_decisions() {
+        let root = Path::new(".");
+        let items = vec![
+            make_item("src/a.rs", 9, "same"),
+            make_item("src/a.rs", 2, "same"),
+            make_item("src/b.rs", 1, "same"),
+        ];
+        let keys = triage_keys(&items, root);
+        let fp = items[0].fingerprint(root);
+        assert_eq!(keys[1], fp);
+        assert_eq!(keys[0], format!("{}-1", fp));
+        assert_eq!(keys[2], items[2].fingerprint(root));
+
+        // Ignoring the second copy leaves the first one reported
+        let mut store = TriageStore::default();
+        let action = BulkAction {
+            ignore: true,
+            ..Default::default()
+        };
+        apply_bulk(&keyed(&items, root)[..1], &mut store, root, &action);
+        assert_eq!(store.ignored_keys(&fp), [keys[0].as_str()]);
+        let mut result = make_result(items);
+        let suppressed = store.apply(&mut result);
+        assert_eq!(suppressed.len(), 1);
+        assert_eq!(suppressed[0].line, 9);
+        assert_eq!(result.items.len(), 2);
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(TRIAGE_FILE);
+        assert!(TriageStore::load(&path).unwrap().decisions.is_empty());
+
+        let item = make_item("src/a.rs", 1, "first");
+        let mut store = TriageStore::default();
+        let mut decision = TriageDecision::for_item(&item, Path::new("."));
+        decision.priority = Some(Priority::Critical);
+        store.record("abc".to_string(), decision.clone());
+        store.save(&path).unwrap();
+
+        let loaded = TriageStore::load(&path).unwrap();
+        assert_eq!(loaded.get("abc"), Some(&decision));
+    }
+
+    #[test]
+    fn test_rewrite_line_adds_and_replaces_metadata() {
+        let item = make_item("src/a.rs", 1, "first");
+        let decision = TriageDecision {
+            author: Some("alice".to_string()),
+            priority: Some(Priority::High),
+            ..Default::default()
+        };
+        assert_eq!(
+            rewrite_line("// TODO: first", 4, &item, &decision).unwrap(),
+            "// TODO(alice, p:high): first"
+        );
+        assert_eq!(
+            rewrite_line("// TODO(bob): first", 4, &item, &decision).unwrap(),
+            "// TODO(alice, p:high): first"
+        );
+        assert!(rewrite_line("// moved", 4, &item, &decision).is_none());
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_export_import_round_trip() {
+        let root = Path::new(".");
+        let mut with_meta = make_item("src/a.rs", 1, "first");
+        with_meta.author = Some("bob".to_string());
+        let items = vec![with_meta, make_item("src/a.rs", 2, "second")];
+        let store = TriageStore::default();
+
+        let csv = export_csv(&items, &store, root).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "fingerprint,file,line,tag,message,priority,author,issue,ignored"
+        );
+        assert!(lines.next().unwrap().ends_with(",src/a.rs,1,TODO,first,,bob,,"));
+
+        // Unedited export imports as a no-op
+        let mut store = TriageStore::default();
+        let summary = import_csv(csv.as_bytes(), &items, &mut store, root).unwrap();
+        assert_eq!(summary.unchanged, 2);
+        assert!(store.decisions.is_empty());
+
+        // Edit: prioritize the first, ignore the second
+        let edited = csv
+            .replace(",first,,bob,,", ",first,P1,bob,42,")
+            .replace(",second,,,,", ",second,,,,yes");
+        let summary = import_csv(edited.as_bytes(), &items, &mut store, root).unwrap();
+        assert_eq!(summary.updated, 2);
+        let first = store.get(&items[0].fingerprint(root)).unwrap();
+        assert_eq!(first.priority, Some(Priority::High));
+        assert_eq!(first.issue.as_deref(), Some("#42"));
+        assert!(first.author.is_none(), "unchanged author is not recorded");
+        assert!(store.get(&items[1].fingerprint(root)).unwrap().ignored);
+
+        // Re-export reflects the decisions; clearing a row removes its decision
+        let csv = export_csv(&items, &store, root).unwrap();
+        assert!(csv.contains(",second,,,,yes"));
+        let cleared = csv.replace(",second,,,,yes", ",second,,,,");
+        let summary = import_csv(cleared.as_bytes(), &items, &mut store, root).unwrap();
+        assert_eq!(summary.cleared, 1);
+        assert_eq!(summary.unchanged, 1);
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_import_rejects_bad_input() {
+        let items = vec![make_item("src/a.rs", 1, "first")];
+        let mut store = TriageStore::default();
+        let root = Path::new(".");
+        assert!(import_csv("file,line\nsrc/a.rs,1\n".as_bytes(), &items, &mut store, root).is_err());
+
+        let fp = items[0].fingerprint(root);
+        let bad = format!("fingerprint,priority\n{},urgent\n", fp);
+        let err = import_csv(bad.as_bytes(), &items, &mut store, root).unwrap_err();
+        assert!(err.to_string().contains("Row 2"));
+
+        let unknown = "fingerprint,priority\ndeadbeef,high\n";
+        let summary = import_csv(unknown.as_bytes(), &items, &mut store, root).unwrap();
+        assert_eq!(summary.unmatched, 1);
+    }
+
+    #[test]
+    fn test_write_back_edits_source() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.rs");
+        std::fs::write(&file, "fn main() {}\r\n// TODO: first\r\n").unwrap();
+
+        let mut item = make_item(file.to_str().unwrap(), 2, "first");
+        item.column = 4;
+        let mut store = TriageStore::default();
+        let mut decision = TriageDecision::for_item(&item, dir.path());
+        decision.issue = Some("#7".to_string());
+        store.record(item.fingerprint(dir.path()), decision);
+
+        let rewritten = write_back(&keyed(&[item], dir.path()), &store).unwrap();
+        assert_eq!(rewritten, 1);
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "fn main() {}\r\n// TODO(#7): first\r\n"
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use crate::error::{Result, TodoError};
+use crate::git::blame::{self, BlameInfo};
+use crate::git::cat_file::CatFile;
+use crate::git::diff::{self, ChangedFile};
+use crate::git::utils::{
+    git_command, git_dir, is_bare_repo, is_git_repo, is_shallow, partial_clone_remote, repo_root,
+};
+use crate::model::fnv1a64;
+use crate::vcs::{repo_relative, worktree_relative, Vcs};
+
+/// Git implementation of [`Vcs`], backed by the `git` module. Linked
+/// worktrees and `GIT_DIR`/`GIT_WORK_TREE` layouts need nothing special, as
+/// git resolves them itself; a bare repository's root is its git directory.
+/// Sparse checkouts need nothing either, since revisions are read from the
+/// object store rather than the working tree.
+pub struct GitVcs {
+    root: PathBuf,
+    shallow: AtomicBool,
+    bare: bool,
+    /// Promisor remote of a partial clone
+    promisor: Option<String>,
+    /// Started on the first `file_at_rev`
+    cat_file: Mutex<Option<CatFile>>,
+    /// File of commits blame looks past, with a hash of its contents
+    ignore_revs: Option<(PathBuf, u64)>,
+}
+
+impl GitVcs {
+    pub fn open(path: &Path) -> Result<Self> {
+        if !is_git_repo(path) {
+            if is_bare_repo(path) {
+                let root = git_dir(path)?;
+                return Ok(GitVcs {
+                    shallow: AtomicBool::new(is_shallow(&root)),
+                    promisor: partial_clone_remote(&root),
+                    root,
+                    bare: true,
+                    cat_file: Mutex::new(None),
+                    ignore_revs: None,
+                });
+            }
+            // Tell a missing git apart from a path outside any repository
+            return Err(match git_command(&["--version"], path) {
+                Err(TodoError::GitNotFound) => TodoError::GitNotFound,
+                _ => TodoError::NotARepo(path.to_path_buf()),
+            });
+        }
+        let root = repo_root(path)?;
+        Ok(GitVcs {
+            shallow: AtomicBool::new(is_shallow(&root)),
+            promisor: partial_clone_remote(&root),
+            root,
+            bare: false,
+            cat_file: Mutex::new(None),
+            ignore_revs: None,
+        })
+    }
+}
+
+impl Vcs for GitVcs {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn working_parent(&self) -> &'static str {
+        "HEAD"
+    }
+
+    fn blame_file(&self, file: &Path) -> Result<HashMap<usize, BlameInfo>> {
+        let ignore_revs = self.ignore_revs.as_ref().map(|(path, _)| path.as_path());
+        blame::blame_file(file, &self.root, self.is_shallow(), ignore_revs)
+    }
+
+    fn ignore_revs_file(&mut self, file: &Path) -> Result<()> {
+        let read = |file: &Path| -> std::io::Result<_> {
+            Ok((file.canonicalize()?, std::fs::read(file)?))
+        };
+        let (path, contents) = read(file).map_err(|e| {
+            TodoError::Config(format!("Failed to read ignore-revs file {}: {}", file.display(), e))
+        })?;
+        self.ignore_revs = Some((path, fnv1a64(&contents)));
+        Ok(())
+    }
+
+    fn blame_options_key(&self) -> String {
+        match self.ignore_revs {
+            Some((_, hash)) => format!(" ignore-revs:{:016x}", hash),
+            None => String::new(),
+        }
+    }
+
+    fn changed_files(&self, base: &str, head: &str) -> Result<Vec<ChangedFile>> {
+        diff::changed_files(base, head, &self.root)
+    }
+
+    fn pending_files(&self) -> Result<Vec<ChangedFile>> {
+        diff::staged_files(&self.root)
+    }
+
+    fn file_at_rev(&self, file: &Path, rev: &str) -> Result<Vec<u8>> {
+        let object = format!("{}:{}", rev, repo_relative(file, &self.root));
+        if object.contains('\n') {
+            // cat-file reads one object name per line
+            return diff::file_at_ref(file, rev, &self.root);
+        }
+        let mut cat_file = self.cat_file.lock().unwrap_or_else(PoisonError::into_inner);
+        let process = match cat_file.as_mut() {
+            Some(process) => process,
+            None => cat_file.insert(CatFile::spawn(&self.root)?),
+        };
+        let result = process.read(&object);
+        if let Err(TodoError::Io(_)) = result {
+            // Out of step with its output; start afresh on the next read
+            *cat_file = None;
+        }
+        result
+    }
+
+    fn pending_rev(&self) -> Option<&'static str> {
+        // `:0:<path>`, the staged version
+        Some(":0")
+    }
+
+    fn prefetch(&self, files: &[&Path], rev: &str) -> Result<()> {
+        let Some(remote) = &self.promisor else {
+            return Ok(());
+        };
+        let blobs = diff::missing_blobs(files, rev, &self.root)?;
+        if blobs.is_empty() {
+            return Ok(());
+        }
+        diff::fetch_blobs(&blobs, remote, &self.root).map_err(|e| {
+            let e = match e {
+                TodoError::Git(message) => message,
+                e => e.to_string(),
+            };
+            TodoError::Git(format!(
+                "Could not fetch {} file(s) at {} from '{}', the promisor remote of this partial clone: {}",
+                blobs.len(),
+                rev,
+                remote,
+                e
+            ))
+        })
+    }
+
+    fn unified_diff(&self, base: &str, head: &str) -> Result<String> {
+        git_command(
+            &["diff", "-U0", "--no-color", "--no-ext-diff", &format!("{}...{}", base, head)],
+            &self.root,
+        )
+    }
+
+    fn pending_diff(&self) -> Result<String> {
+        git_command(
+            &["diff", "-U0", "--no-color", "--no-ext-diff", "--staged"],
+            &self.root,
+        )
+    }
+
+    fn commit_message(&self, rev: &str) -> Result<String> {
+        git_command(&["log", "-1", "--format=%B", rev], &self.root)
+    }
+
+    fn last_commit(&self, file: &Path) -> Result<String> {
+        let rel = worktree_relative(file, &self.root);
+        git_command(&["log", "-1", "--format=%H", "--", &rel], &self.root)
+            .map(|out| out.trim().to_string())
+    }
+
+    fn is_bare(&self) -> bool {
+        self.bare
+    }
+
+    fn is_shallow(&self) -> bool {
+        self.shallow.load(Ordering::Relaxed)
+    }
+
+    fn deepen(&self, depth: Option<u32>) -> Result<()> {
+        let deepen = depth.map(|n| format!("--deepen={}", n));
+        let arg = deepen.as_deref().unwrap_or("--unshallow");
+        git_command(&["fetch", "--quiet", arg], &self.root)?;
+        self.shallow.store(is_shallow(&self.root), Ordering::Relaxed);
+        Ok(())
+    }
+}
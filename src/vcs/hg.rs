@@ -0,0 +1,237 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::date::Date;
+use crate::error::{Result, TodoError};
+use crate::git::blame::BlameInfo;
+use crate::git::diff::{ChangeStatus, ChangedFile};
+use crate::vcs::{repo_relative, worktree_relative, Vcs};
+
+/// One annotate line: node, user, email, "unixtime offset" date, separated by tabs.
+const ANNOTATE_TEMPLATE: &str =
+    "{lines % '{node}\\t{user|person}\\t{user|email}\\t{date|hgdate}\\n'}";
+
+/// Run an hg command in the given directory and return stdout as a String.
+fn hg_command(args: &[&str], dir: &Path) -> Result<String> {
+    let stdout = hg_command_raw(args, dir)?;
+    String::from_utf8(stdout).map_err(|e| TodoError::Git(format!("Invalid UTF-8 in hg output: {}", e)))
+}
+
+/// Run an hg command and return stdout as raw bytes.
+fn hg_command_raw(args: &[&str], dir: &Path) -> Result<Vec<u8>> {
+    let output = Command::new("hg")
+        .args(args)
+        .current_dir(dir)
+        .env("HGPLAIN", "1")
+        .output()
+        .map_err(|e| TodoError::Git(format!("Failed to execute hg: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("no repository found") {
+            return Err(TodoError::NotARepo(dir.to_path_buf()));
+        }
+        return Err(TodoError::Git(format!("hg {} failed: {}", args.join(" "), stderr.trim())));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Mercurial implementation of [`Vcs`]. Mercurial has no staging area, so
+/// `diff --staged` compares uncommitted changes against the working parent.
+pub struct HgVcs {
+    root: PathBuf,
+}
+
+impl HgVcs {
+    pub fn open(path: &Path) -> Result<Self> {
+        let output = hg_command(&["root"], path)?;
+        Ok(HgVcs {
+            root: PathBuf::from(output.trim()),
+        })
+    }
+}
+
+/// Parse annotate output produced with [`ANNOTATE_TEMPLATE`]; line numbers are
+/// implied by output order.
+fn parse_annotate(output: &str) -> HashMap<usize, BlameInfo> {
+    let mut result = HashMap::new();
+    for (idx, line) in output.lines().enumerate() {
+        let parts: Vec<&str> = line.splitn(4, '\t').collect();
+        if parts.len() != 4 {
+            continue;
+        }
+        let date = parts[3]
+            .split_whitespace()
+            .next()
+            .and_then(|ts| ts.parse::<i64>().ok())
+            .map(|ts| Date::from_timestamp(ts).to_string())
+            .unwrap_or_default();
+        result.insert(
+            idx + 1,
+            BlameInfo {
+                author: parts[1].to_string(),
+                email: parts[2].to_string(),
+                date,
+                commit: parts[0].to_string(),
+                incomplete: false,
+            },
+        );
+    }
+    result
+}
+
+/// Parse `hg status -C` output. A copy or rename lists its source on the
+/// following indented line; the removal half of a rename is dropped so the
+/// move maps old to new instead of showing up as a deletion.
+fn parse_status(output: &str, root: &Path) -> Vec<ChangedFile> {
+    let mut files: Vec<ChangedFile> = Vec::new();
+    for line in output.lines() {
+        if let Some(source) = line.strip_prefix("  ") {
+            if let Some(file) = files.last_mut() {
+                file.source = Some(root.join(source.trim()));
+                file.status = ChangeStatus::Copied;
+            }
+        } else if let Some((status, path)) = line.split_once(' ') {
+            let status = match status {
+                "A" => ChangeStatus::Added,
+                "R" => ChangeStatus::Deleted,
+                _ => ChangeStatus::Modified,
+            };
+            files.push(ChangedFile {
+                path: root.join(path.trim()),
+                source: None,
+                status,
+            });
+        }
+    }
+
+    let removed: HashSet<PathBuf> = files
+        .iter()
+        .filter(|f| f.status == ChangeStatus::Deleted)
+        .map(|f| f.path.clone())
+        .collect();
+    let mut renamed_from = HashSet::new();
+    for file in &mut files {
+        if let Some(ref source) = file.source {
+            if removed.contains(source) {
+                file.status = ChangeStatus::Renamed;
+                renamed_from.insert(source.clone());
+            }
+        }
+    }
+    files
+        .into_iter()
+        .filter(|f| !(f.status == ChangeStatus::Deleted && renamed_from.contains(&f.path)))
+        .collect()
+}
+
+impl Vcs for HgVcs {
+    fn name(&self) -> &'static str {
+        "hg"
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn working_parent(&self) -> &'static str {
+        "."
+    }
+
+    fn blame_file(&self, file: &Path) -> Result<HashMap<usize, BlameInfo>> {
+        let rel = worktree_relative(file, &self.root);
+        let output = hg_command(&["annotate", "-T", ANNOTATE_TEMPLATE, &rel], &self.root)?;
+        Ok(parse_annotate(&output))
+    }
+
+    fn changed_files(&self, base: &str, head: &str) -> Result<Vec<ChangedFile>> {
+        let output = hg_command(
+            &["status", "-mar", "-C", "--rev", base, "--rev", head],
+            &self.root,
+        )?;
+        Ok(parse_status(&output, &self.root))
+    }
+
+    fn pending_files(&self) -> Result<Vec<ChangedFile>> {
+        let output = hg_command(&["status", "-mar", "-C"], &self.root)?;
+        Ok(parse_status(&output, &self.root))
+    }
+
+    fn file_at_rev(&self, file: &Path, rev: &str) -> Result<Vec<u8>> {
+        let rel = repo_relative(file, &self.root);
+        hg_command_raw(&["cat", "-r", rev, &rel], &self.root)
+    }
+
+    fn unified_diff(&self, base: &str, head: &str) -> Result<String> {
+        hg_command(&["diff", "-U", "0", "-r", base, "-r", head], &self.root)
+    }
+
+    fn pending_diff(&self) -> Result<String> {
+        hg_command(&["diff", "-U", "0"], &self.root)
+    }
+
+    fn commit_message(&self, rev: &str) -> Result<String> {
+        hg_command(&["log", "-r", rev, "-T", "{desc}"], &self.root)
+    }
+
+    fn last_commit(&self, file: &Path) -> Result<String> {
+        let rel = worktree_relative(file, &self.root);
+        hg_command(&["log", "-r", "reverse(::.)", "-l", "1", "-T", "{node}", &rel], &self.root)
+            .map(|out| out.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_annotate() {
+        let output = "\
+0123456789abcdef0123456789abcdef01234567\tAlice Smith\talice@example.com\t1697328000 0
+89abcdef0123456789abcdef0123456789abcdef\tBob\tbob@example.com\t1709164800 -3600
+";
+        let result = parse_annotate(output);
+        assert_eq!(result.len(), 2);
+
+        let line1 = result.get(&1).unwrap();
+        assert_eq!(line1.author, "Alice Smith");
+        assert_eq!(line1.email, "alice@example.com");
+        assert_eq!(line1.date, "2023-10-15");
+        assert_eq!(line1.commit, "0123456789abcdef0123456789abcdef01234567");
+
+        let line2 = result.get(&2).unwrap();
+        assert_eq!(line2.author, "Bob");
+        assert_eq!(line2.date, "2024-02-29");
+    }
+
+    #[test]
+    fn test_parse_status_maps_renames_and_copies() {
+        let output = "M src/lib.rs\nA src/new.rs\n  src/old.rs\nA src/copy.rs\n  src/lib.rs\nR src/old.rs\nR gone.rs\n";
+        let files = parse_status(output, Path::new("/repo"));
+        let paths: Vec<&Path> = files.iter().map(|f| f.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("/repo/src/lib.rs"),
+                Path::new("/repo/src/new.rs"),
+                Path::new("/repo/src/copy.rs"),
+                Path::new("/repo/gone.rs"),
+            ]
+        );
+        assert_eq!(files[1].base_path(), Path::new("/repo/src/old.rs"));
+        assert_eq!(files[1].status, ChangeStatus::Renamed);
+        assert_eq!(files[2].base_path(), Path::new("/repo/src/lib.rs"));
+        assert_eq!(files[2].status, ChangeStatus::Copied);
+        assert_eq!(files[3].status, ChangeStatus::Deleted);
+    }
+
+    #[test]
+    fn test_parse_annotate_skips_malformed_lines() {
+        let result = parse_annotate("garbage\nnode\tA\ta@b.c\t0 0\n");
+        assert_eq!(result.len(), 1);
+        assert!(result.contains_key(&2));
+    }
+}
@@ -0,0 +1,155 @@
+pub mod git;
+pub mod hg;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::{Result, TodoError};
+use crate::git::blame::BlameInfo;
+use crate::git::diff::ChangedFile;
+
+pub use self::git::GitVcs;
+pub use self::hg::HgVcs;
+
+/// Version-control operations used by `blame`, `diff` and change scoping.
+/// Paths passed in and returned are absolute (joined onto `root`).
+pub trait Vcs {
+    /// Short name for messages, e.g. "git"
+    fn name(&self) -> &'static str;
+
+    /// Repository root directory; for a bare repository, its git directory
+    fn root(&self) -> &Path;
+
+    /// Revision the working tree is compared against for `diff --staged`
+    fn working_parent(&self) -> &'static str;
+
+    /// Line number -> blame info for one file
+    fn blame_file(&self, file: &Path) -> Result<HashMap<usize, BlameInfo>>;
+
+    /// Files changed between two revisions, with rename/copy sources
+    fn changed_files(&self, base: &str, head: &str) -> Result<Vec<ChangedFile>>;
+
+    /// Files with changes ready to commit: the index for git, the working
+    /// directory for VCSs without a staging area
+    fn pending_files(&self) -> Result<Vec<ChangedFile>>;
+
+    /// Raw file contents at a revision
+    fn file_at_rev(&self, file: &Path, rev: &str) -> Result<Vec<u8>>;
+
+    /// Revision for `file_at_rev` holding the contents `pending_files` would
+    /// commit: the index for git. `None` for VCSs without a staging area,
+    /// which commit the working directory as it is.
+    fn pending_rev(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Make the contents of `files` at `rev` available locally before they
+    /// are read one by one. A partial clone fetches them in one request
+    /// instead of one on-demand fetch per file.
+    fn prefetch(&self, _files: &[&Path], _rev: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Zero-context unified diff between two revisions
+    fn unified_diff(&self, base: &str, head: &str) -> Result<String>;
+
+    /// Zero-context unified diff of the changes `pending_files` reports
+    fn pending_diff(&self) -> Result<String>;
+
+    /// Full message of a commit
+    fn commit_message(&self, rev: &str) -> Result<String>;
+
+    /// Have blame look past the commits listed in `file`, one hash per line
+    /// as in git's `--ignore-revs-file`, attributing their lines to the
+    /// commits before
+    fn ignore_revs_file(&mut self, _file: &Path) -> Result<()> {
+        Err(TodoError::Config(format!("{} blame can't ignore revisions", self.name())))
+    }
+
+    /// Identifies blame options, such as the ignored revisions, that change
+    /// blame without any file's last commit changing; part of the blame
+    /// cache key
+    fn blame_options_key(&self) -> String {
+        String::new()
+    }
+
+    /// Last commit on the working parent that touched `file`; empty for
+    /// files never committed. Blame for a file only changes when this does,
+    /// or when the file has uncommitted edits.
+    fn last_commit(&self, file: &Path) -> Result<String>;
+
+    /// Whether there is no working tree, as in a bare clone on a server:
+    /// revisions can be compared, but there are no files to scan or blame
+    fn is_bare(&self) -> bool {
+        false
+    }
+
+    /// Whether history is cut off, as in a CI shallow clone. Blame then
+    /// pins older lines on the cut-off commit, and diff bases may be missing.
+    fn is_shallow(&self) -> bool {
+        false
+    }
+
+    /// Fetch `depth` more commits of history, or all of it
+    fn deepen(&self, _depth: Option<u32>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Detect the repository containing `path`, trying git first, then Mercurial.
+/// Fails with `NotARepo`, or `GitNotFound` when git is missing and the path
+/// isn't a Mercurial checkout either.
+pub fn detect(path: &Path) -> Result<Box<dyn Vcs>> {
+    let git_error = match GitVcs::open(path) {
+        Ok(git) => return Ok(Box::new(git)),
+        Err(e) => e,
+    };
+    if let Ok(hg) = HgVcs::open(path) {
+        return Ok(Box::new(hg));
+    }
+    Err(match git_error {
+        TodoError::GitNotFound => TodoError::GitNotFound,
+        _ => TodoError::NotARepo(path.to_path_buf()),
+    })
+}
+
+/// Path relative to the repository root with `/` separators, as VCS commands expect.
+pub(crate) fn repo_relative(file: &Path, root: &Path) -> String {
+    let relative = file.strip_prefix(root).unwrap_or(file);
+    relative.to_str().unwrap_or("").replace('\\', "/")
+}
+
+/// [`repo_relative`] for a file in the working tree, whose path may instead
+/// be relative to the current directory, as when scanning a subdirectory.
+pub(crate) fn worktree_relative(file: &Path, root: &Path) -> String {
+    if !file.starts_with(root) {
+        if let (Ok(file), Ok(root)) = (file.canonicalize(), root.canonicalize()) {
+            if file.starts_with(&root) {
+                return repo_relative(&file, &root);
+            }
+        }
+    }
+    repo_relative(file, root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_relative() {
+        let root = Path::new("/repo");
+        assert_eq!(repo_relative(Path::new("/repo/src/a.rs"), root), "src/a.rs");
+        assert_eq!(repo_relative(Path::new("src/a.rs"), root), "src/a.rs");
+    }
+
+    #[test]
+    fn test_detect_outside_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        // A bare temp dir is neither a git nor a Mercurial checkout
+        match detect(dir.path()) {
+            Ok(vcs) => panic!("unexpected {} repository", vcs.name()),
+            Err(e) => assert!(matches!(e, TodoError::NotARepo(_)), "{}", e),
+        }
+    }
+}
@@ -1,8 +1,11 @@
+// The CLI is only built with the `full` feature
+#![cfg(feature = "full")]
+
 use assert_cmd::Command;
 use predicates::prelude::*;
 
 fn todos() -> Command {
-    Command::cargo_bin("todos").unwrap()
+    assert_cmd::cargo::cargo_bin_cmd!("todos")
 }
 
 #[test]
@@ -92,3 +95,2279 @@ fn test_empty_directory() {
         .success()
         .stdout(predicate::str::contains("0 TODOs in 0 files"));
 }
+
+#[test]
+fn test_invalid_config_warns_and_uses_defaults() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("main.rs"), "// TODO: one\n").unwrap();
+    std::fs::write(dir.path().join(".todo-tracker.toml"), "[scan]\nmax_file_size = \"big\"\n")
+        .unwrap();
+    todos()
+        .current_dir(dir.path())
+        .args(["--format", "count"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1"))
+        .stderr(predicate::str::contains("using the default config"))
+        .stderr(predicate::str::contains(".todo-tracker.toml"))
+        .stderr(predicate::str::contains("scan.max_file_size"));
+}
+
+#[test]
+fn test_check_lint_empty_message() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("main.rs"), "// TODO:\n// TODO: real message here\n").unwrap();
+    todos()
+        .args(["--color=never", "--path", dir.path().to_str().unwrap(), "check", "--deny-empty-message"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("lint:empty"))
+        .stderr(predicate::str::contains("1 policy violation(s) found."));
+}
+
+#[test]
+fn test_check_exemption_tokens() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("main.rs"),
+        "// FIXME(alice, allow:require-issue): vendored workaround\n// FIXME: untracked\n",
+    )
+    .unwrap();
+    todos()
+        .args(["--color=never", "--path", dir.path().to_str().unwrap(), "check", "--require-issue", "FIXME"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Exemptions (1):"))
+        .stdout(predicate::str::contains(
+            "main.rs:1 [FIXME] allow:require_issue vendored workaround",
+        ))
+        .stderr(predicate::str::contains("1 policy violation(s) found."));
+}
+
+#[test]
+fn test_no_message_filter_and_stats() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("main.rs"), "// TODO:\n// FIXME\n// TODO: real message\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+    todos()
+        .args(["--color=never", "--path", path, "--no-message", "--format=count"])
+        .assert()
+        .success()
+        .stdout("2");
+    todos()
+        .args(["--path", path, "--format=json", "stats"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"empty_messages\": 2"));
+}
+
+#[test]
+fn test_config_file_settings() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: kept\n").unwrap();
+    std::fs::write(dir.path().join("big.rs"), format!("// TODO: too big\n{}", "x".repeat(100)))
+        .unwrap();
+    std::fs::create_dir(dir.path().join("gen")).unwrap();
+    std::fs::write(dir.path().join("gen/b.rs"), "// TODO: generated\n").unwrap();
+    std::fs::write(
+        dir.path().join(".todo-tracker.toml"),
+        "[scan]\nmax_file_size = 64\n\n[filter]\nexclude_patterns = [\"gen/\"]\n\n\
+         [output]\nformat = \"count\"\ncolor = \"always\"\n",
+    )
+    .unwrap();
+
+    todos().current_dir(dir.path()).assert().success().stdout("1");
+    // Flags override the config file, before or after the subcommand
+    todos()
+        .current_dir(dir.path())
+        .args(["list", "--format", "text"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("kept"))
+        .stdout(predicate::str::contains("\u{1b}["));
+    todos()
+        .current_dir(dir.path())
+        .args(["--format=text", "--color=never"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{1b}[").not());
+}
+
+#[test]
+fn test_json_item_urls() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::create_dir(dir.path().join("web")).unwrap();
+    std::fs::write(dir.path().join("web/a.ts"), "\n// TODO: link me\n").unwrap();
+    std::fs::write(
+        dir.path().join(".todo-tracker.toml"),
+        "[output]\nlink_template = \"https://git.example.com/app/{path}#L{line}\"\n",
+    )
+    .unwrap();
+
+    // Paths are relative to the repository root, not the scanned directory
+    todos()
+        .current_dir(dir.path())
+        .args(["--path", "web", "--format", "json", "--fields", "url"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""url": "https://git.example.com/app/web/a.ts#L2""#));
+
+    std::fs::write(
+        dir.path().join(".todo-tracker.toml"),
+        "[output]\nlink_template = \"https://git.example.com/app/blob/{commit}/{path}\"\n",
+    )
+    .unwrap();
+    todos()
+        .current_dir(dir.path())
+        .args(["--format", "json"])
+        .assert()
+        .failure();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "first"]);
+    let head = std::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    let head = String::from_utf8(head.stdout).unwrap();
+    todos()
+        .current_dir(dir.path())
+        .args(["--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("/blob/{}/web/a.ts", head.trim())));
+
+    // Without a template, the origin remote says where the repository lives
+    std::fs::remove_file(dir.path().join(".todo-tracker.toml")).unwrap();
+    git(dir.path(), &["remote", "add", "origin", "git@gitlab.com:acme/app.git"]);
+    todos()
+        .current_dir(dir.path())
+        .args(["--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            r#""url": "https://gitlab.com/acme/app/-/blob/{}/web/a.ts#L2""#,
+            head.trim()
+        )));
+}
+
+#[test]
+fn test_subdirectory_paths_from_repo_root() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::create_dir(dir.path().join("web")).unwrap();
+    std::fs::write(dir.path().join("web/a.ts"), "// TODO: in web\n").unwrap();
+    std::fs::write(dir.path().join("b.rs"), "// TODO: outside\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "first"]);
+    let web = dir.path().join("web");
+
+    // Only the subtree is scanned, but reported as git would
+    for args in [&["--format", "json"][..], &["--format", "json", "blame"]] {
+        todos()
+            .current_dir(&web)
+            .args(args)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#""file": "web/a.ts""#))
+            .stdout(predicate::str::contains("b.rs").not());
+    }
+    todos()
+        .current_dir(&web)
+        .args(["--format", "csv", "--memory-budget", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("web/a.ts,"));
+    todos()
+        .current_dir(&web)
+        .args(["--format", "json", "blame"])
+        .assert()
+        .stdout(predicate::str::contains(r#""git_author": "Test""#));
+    todos()
+        .current_dir(&web)
+        .args(["--no-root-resolve", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""file": "./a.ts""#));
+}
+
+#[test]
+fn test_subdirectory_paths_match_across_commands() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::create_dir(dir.path().join("web")).unwrap();
+    std::fs::write(dir.path().join("web/a.ts"), "// TODO: in web\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "first"]);
+    let web = dir.path().join("web");
+
+    // The same fingerprint and path whichever directory export runs from
+    let export = |cwd: &std::path::Path| {
+        let output = todos().current_dir(cwd).args(["export", "csv"]).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        let csv = String::from_utf8(output.stdout).unwrap();
+        csv.lines().find(|l| l.contains("in web")).unwrap().to_string()
+    };
+    let from_web = export(&web);
+    assert!(from_web.contains("web/a.ts"), "{}", from_web);
+    assert_eq!(from_web, export(dir.path()));
+
+    // Commands that read the files still find them
+    let output = todos()
+        .current_dir(&web)
+        .args(["--format", "json", "random"])
+        .output()
+        .unwrap();
+    let item: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(item["file"], "web/a.ts");
+    assert_eq!(item["git_author"], "Test");
+}
+
+#[test]
+fn test_triage_bulk_ignore_persists() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("main.rs"), "// TODO: keep\n// HACK: drop\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+    todos()
+        .args(["--path", path, "--tag=HACK", "triage", "--ignore"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Recorded 1 decision(s)"));
+    todos()
+        .args(["--path", path, "--format=count"])
+        .assert()
+        .success()
+        .stdout("1");
+}
+
+#[test]
+fn test_suppressions_report() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("a.rs"), "// TODO(allow:max_todos): legacy\n// HACK: drop\n")
+        .unwrap();
+    let path = dir.path().to_str().unwrap();
+    todos()
+        .args(["--path", path, "--tag=HACK", "triage", "--ignore"])
+        .assert()
+        .success();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "init"]);
+
+    todos()
+        .args(["--path", path, "suppressions"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Suppressions (2):\n\nTriage ignores (1):\n"))
+        .stdout(predicate::str::contains("  a.rs:2 [HACK] drop  (Test, 0 days ago)"))
+        .stdout(predicate::str::contains(
+            "  a.rs:1 [TODO] allow:max_todos legacy  (Test, 0 days ago)",
+        ));
+    let output = todos()
+        .args(["--path", path, "--format", "json", "suppressions"])
+        .output()
+        .unwrap();
+    let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(list[0]["kind"], "triage_ignore");
+    assert_eq!(list[1]["rules"][0], "max_todos");
+}
+
+#[test]
+fn test_show_suppressed_lists_ignored_items() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("main.rs"), "// TODO: keep\n// HACK: drop\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+    todos()
+        .args(["--path", path, "--tag=HACK", "triage", "--ignore"])
+        .assert()
+        .success();
+
+    todos()
+        .args(["--path", path, "--color=never"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("1 suppressed by triage")
+                .and(predicate::str::contains("HACK  drop").not()),
+        );
+    // Same listing with and without a memory budget
+    for budget in [None, Some("--memory-budget=1")] {
+        let mut cmd = todos();
+        cmd.args(["--path", path, "--color=never", "--show-suppressed"]);
+        cmd.args(budget);
+        cmd.assert().success().stdout(
+            predicate::str::contains("Suppressed (1):").and(predicate::str::contains("HACK  drop")),
+        );
+    }
+    todos()
+        .args(["--path", path, "--format=json", "stats"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"suppressed\": 1"));
+}
+
+#[test]
+fn test_snapshot_writes_and_lists() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("main.rs"), "// TODO: first\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+    todos()
+        .args(["--path", path, "snapshot", "--keep", "1"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("(1 items)"));
+    let snapshots = std::fs::read_dir(dir.path().join(".todo-tracker/snapshots")).unwrap();
+    assert_eq!(snapshots.count(), 1);
+    todos()
+        .args(["--path", path, "snapshot", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with("Z\n"));
+}
+
+#[test]
+fn test_diff_against_latest_snapshot_without_git() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("main.rs"), "// TODO: old\n// FIXME: kept\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+    todos().args(["--path", path, "snapshot"]).assert().success();
+    std::fs::write(dir.path().join("main.rs"), "// TODO: brand new\n// FIXME: kept\n").unwrap();
+    todos()
+        .args(["--color=never", "--path", path, "diff", "--against-snapshot", "latest"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Summary: 1 added, 1 removed"));
+    // Filters apply to the snapshot and the current scan alike
+    todos()
+        .args(["--color=never", "--path", path, "--tag", "FIXME"])
+        .args(["diff", "--against-snapshot", "latest"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No TODO changes detected."));
+}
+
+#[test]
+fn test_blame_rejects_malformed_dates() {
+    todos()
+        .args(["blame", "--since", "2024-02-30"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid date"));
+    todos()
+        .args(["blame", "--age-over", "soon"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("number of days"));
+}
+
+#[test]
+fn test_blame_outside_a_repository_lists_without_blame() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("lib.rs"), "// TODO: ship the tarball\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .args(["--color=never", "--path", path, "blame"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("unknown"))
+        .stdout(predicate::str::contains("ship the tarball"))
+        .stderr(predicate::str::contains("showing TODOs without blame"));
+    todos()
+        .args(["--path", path, "diff", "HEAD~1..HEAD"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Not a git or Mercurial repository"))
+        .stderr(predicate::str::contains("hint:"));
+}
+
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn test_diff_hunks_reports_duplicate_todo_on_added_line() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("main.rs"), "// TODO: same\nfn a() {}\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "init"]);
+    std::fs::write(dir.path().join("main.rs"), "// TODO: same\nfn a() {}\n// TODO: same\n").unwrap();
+    git(dir.path(), &["add", "."]);
+
+    let path = dir.path().to_str().unwrap();
+    todos()
+        .args(["--color=never", "--path", path, "diff", "--staged"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No TODO changes detected."));
+    todos()
+        .args(["--color=never", "--path", path, "diff", "--staged", "--hunks"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("main.rs:3 [TODO] same"))
+        .stdout(predicate::str::contains("Summary: 1 added, 0 removed"));
+}
+
+#[test]
+fn test_diff_ignores_renamed_file() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("old.rs"), "// TODO: moves along\nfn a() {}\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "init"]);
+    git(dir.path(), &["mv", "old.rs", "new.rs"]);
+    git(dir.path(), &["commit", "-q", "-m", "rename"]);
+
+    todos()
+        .args(["--color=never", "--path", dir.path().to_str().unwrap()])
+        .args(["diff", "HEAD~1..HEAD"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No TODO changes detected."));
+}
+
+#[test]
+fn test_diff_handles_added_deleted_and_binary_files() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("gone.rs"), "// TODO: deleted with file\n").unwrap();
+    std::fs::write(dir.path().join("blob.rs"), b"// TODO: binary\0\x01").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "init"]);
+    git(dir.path(), &["rm", "-q", "gone.rs"]);
+    std::fs::write(dir.path().join("blob.rs"), b"// TODO: still binary\0\x02").unwrap();
+    std::fs::write(dir.path().join("new.rs"), "// TODO: brand new file\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "change"]);
+
+    let output = todos()
+        .args(["--format=json", "--path", dir.path().to_str().unwrap()])
+        .args(["diff", "HEAD~1..HEAD"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["added"].as_array().unwrap().len(), 1);
+    assert_eq!(json["removed"].as_array().unwrap().len(), 1);
+    assert_eq!(json["errors"].as_array().unwrap().len(), 0);
+}
+
+#[test]
+fn test_hook_commit_msg_blocks_or_appends_trailer() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(
+        dir.path().join("main.rs"),
+        "// TODO: untracked\n// TODO(#12): tracked\n",
+    )
+    .unwrap();
+    git(dir.path(), &["add", "."]);
+    let msg = dir.path().join("COMMIT_EDITMSG");
+    std::fs::write(&msg, "Add main\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .args(["--path", path, "hook", "commit-msg"])
+        .arg(&msg)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("1 new TODO(s) without an issue reference"));
+    todos()
+        .args(["--path", path, "hook", "commit-msg", "--mode", "trailer"])
+        .arg(&msg)
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(&msg).unwrap(),
+        "Add main\n\nIntroduces-TODO: 1\n"
+    );
+}
+
+#[test]
+fn test_hook_commit_msg_reads_staged_content() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    let file = dir.path().join("main.rs");
+    std::fs::write(&file, "fn main() {}\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "first"]);
+    let msg = dir.path().join("COMMIT_EDITMSG");
+    std::fs::write(&msg, "Change main\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+    let hook = || todos().args(["--path", path, "hook", "commit-msg"]).arg(&msg).assert();
+
+    // An unstaged TODO on a staged line isn't committed
+    std::fs::write(&file, "fn main() {}\nfn two() {}\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    std::fs::write(&file, "fn main() {}\n// TODO: not staged\n").unwrap();
+    hook().success();
+
+    // A staged TODO is, even when unstaged edits move it
+    std::fs::write(&file, "fn main() {}\n// TODO: staged\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    std::fs::write(&file, "// unstaged\nfn main() {}\n// TODO: staged\n").unwrap();
+    hook().failure().stderr(predicate::str::contains("main.rs:2 [TODO] staged"));
+}
+
+#[test]
+fn test_check_against_golden_file() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("main.rs"), "// TODO: first\nfn main() {}\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+    let golden = dir.path().join("expected.txt");
+    let golden = golden.to_str().unwrap();
+
+    todos()
+        .args(["--path", path, "check", "--against-file", golden, "--update-golden"])
+        .assert()
+        .success();
+    assert_eq!(std::fs::read_to_string(golden).unwrap(), "main.rs: TODO: first\n");
+
+    // Moving the TODO doesn't change the golden output
+    std::fs::write(dir.path().join("main.rs"), "\n\n// TODO: first\nfn main() {}\n").unwrap();
+    todos()
+        .args(["--path", path, "check", "--against-file", golden])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All checks passed."));
+
+    std::fs::write(dir.path().join("main.rs"), "// TODO: first\n// FIXME: second one\n").unwrap();
+    todos()
+        .args(["--path", path, "check", "--against-file", golden])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("+ main.rs: FIXME: second one"))
+        .stderr(predicate::str::contains("--update-golden"));
+}
+
+#[test]
+fn test_scan_subpath_partial_rescan() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::create_dir(dir.path().join("api")).unwrap();
+    std::fs::create_dir(dir.path().join("web")).unwrap();
+    std::fs::write(dir.path().join("api/main.rs"), "// TODO: api task\n").unwrap();
+    std::fs::write(dir.path().join("web/app.js"), "// TODO: web task\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    todos().args(["--path", path, "--format=count"]).assert().success().stdout("2");
+
+    std::fs::write(dir.path().join("api/main.rs"), "// TODO: api task\n// FIXME: another\n").unwrap();
+    todos()
+        .args(["--path", path, "--format=count", "scan", "api"])
+        .assert()
+        .success()
+        .stdout("3")
+        .stderr(predicate::str::contains("Rescanned 1 files"));
+}
+
+#[test]
+fn test_max_depth_and_max_files() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::create_dir_all(dir.path().join("a/b")).unwrap();
+    std::fs::write(dir.path().join("top.rs"), "// TODO: top\n").unwrap();
+    std::fs::write(dir.path().join("a/b/deep.rs"), "// TODO: deep\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .args(["--path", path, "--format=count", "--max-depth", "1"])
+        .assert()
+        .success()
+        .stdout("1");
+    todos()
+        .args(["--path", path, "--max-files", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("more than 1 files"));
+}
+
+#[test]
+fn test_memory_budget_matches_regular_scan() {
+    let dir = tempfile::TempDir::new().unwrap();
+    for name in ["a.rs", "b.rs", "c.rs"] {
+        std::fs::write(dir.path().join(name), "// TODO: one\n// FIXME: two\n").unwrap();
+    }
+    let path = dir.path().to_str().unwrap();
+
+    let regular = todos().args(["--path", path, "--format=csv"]).output().unwrap();
+    todos()
+        .args(["--path", path, "--format=csv", "--memory-budget", "0"])
+        .assert()
+        .success()
+        .stdout(String::from_utf8(regular.stdout).unwrap())
+        .stderr(predicate::str::contains("Spilled items"));
+    todos()
+        .args(["--path", path, "--format=sarif", "--memory-budget", "1"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_memory_budget_json_matches_regular_scan() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::create_dir_all(dir.path().join("billing/tests")).unwrap();
+    std::fs::write(dir.path().join("billing/lib.rs"), "// TODO: one\n// FIXME: two\n").unwrap();
+    std::fs::write(dir.path().join("billing/tests/api.rs"), "// TODO: three\n").unwrap();
+    std::fs::write(dir.path().join("main.rs"), "// HACK: four\n").unwrap();
+    std::fs::write(
+        dir.path().join(".todo-tracker.toml"),
+        "[components]\n\"billing/\" = \"billing\"\n",
+    )
+    .unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    for compact in [None, Some("--json-compact")] {
+        let mut args = vec!["--path", path, "--format=json", "--deterministic"];
+        args.extend(compact);
+        let regular = todos().current_dir(dir.path()).args(&args).output().unwrap();
+        let regular = String::from_utf8(regular.stdout).unwrap();
+        assert!(regular.contains("\"component\""), "{}", regular);
+        assert!(regular.contains("\"in_test_code\""), "{}", regular);
+        todos()
+            .current_dir(dir.path())
+            .args(&args)
+            .args(["--memory-budget", "1"])
+            .assert()
+            .success()
+            .stdout(regular);
+    }
+}
+
+#[test]
+fn test_tsv_and_delimiter() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one, two\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    let regular = todos().args(["--path", path, "--format=tsv"]).output().unwrap();
+    let regular = String::from_utf8(regular.stdout).unwrap();
+    assert!(regular.starts_with("file\tline\tcolumn\ttag\tmessage"));
+    assert!(regular.contains("\tTODO\tone, two\t"));
+    todos()
+        .args(["--path", path, "--format=tsv", "--memory-budget", "1"])
+        .assert()
+        .success()
+        .stdout(regular);
+    todos()
+        .args(["--path", path, "--format=csv", "--delimiter=;", "--csv-columns=tag,message"])
+        .assert()
+        .success()
+        .stdout("tag;message\nTODO;one, two\n");
+    todos()
+        .args(["--path", path, "--format=csv", "--delimiter=ab"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid delimiter"));
+}
+
+#[test]
+fn test_json_compact() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    for args in [&["--format=json"][..], &["--format=json", "stats"][..]] {
+        let output = todos()
+            .args(["--path", path, "--json-compact"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout.trim_end().lines().count(), 1, "{}", stdout);
+        serde_json::from_str::<serde_json::Value>(&stdout).unwrap();
+    }
+
+    todos()
+        .args(["--path", path, "--format=json", "--json-compact", "--fields=file,line,stats.total_todos"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""line":1}],"stats":{"total_todos":1}}"#));
+    todos()
+        .args(["--path", path, "--format=json", "--omit=context_line"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("context_line").not());
+}
+
+#[test]
+fn test_theme_config_and_color_env() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// BUG: crash\n").unwrap();
+    std::fs::write(
+        dir.path().join(".todo-tracker.toml"),
+        "[theme]\nname = \"monochrome\"\n",
+    )
+    .unwrap();
+
+    todos()
+        .current_dir(dir.path())
+        .env("NO_COLOR", "1")
+        .env("CLICOLOR_FORCE", "1")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("!! BUG").and(predicate::str::contains("\u{1b}[").not()));
+    todos()
+        .current_dir(dir.path())
+        .env_remove("NO_COLOR")
+        .env("CLICOLOR_FORCE", "1")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\u{1b}[1;4m!! BUG"));
+}
+
+#[test]
+fn test_ascii_output() {
+    todos()
+        .args(["--path", "tests/fixtures", "--ascii", "stats"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("#").and(predicate::str::contains("\u{2588}").not()));
+    todos()
+        .args(["--path", "tests/fixtures", "--color=never"])
+        .env("TERM", "dumb")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-- Summary ---"));
+}
+
+#[test]
+fn test_glyphs_from_config() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// BUG(p:critical): crash\n").unwrap();
+    std::fs::write(dir.path().join(".todo-tracker.toml"), "[output]\nglyphs = \"emoji\"\n").unwrap();
+
+    todos()
+        .current_dir(dir.path())
+        .args(["--color=never", "--format=markdown"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("- \u{1f41b} **BUG** (L1): \u{1f525} crash"));
+    todos()
+        .current_dir(dir.path())
+        .args(["--color=never", "--ascii"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("BUG    [!!!] crash"));
+}
+
+#[test]
+fn test_random_picks_one_matching_item() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("a.rs"),
+        "fn a() {}\n// TODO: low one\n// FIXME(p:high): the important one\n",
+    )
+    .unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .args(["--path", path, "--color=never", "--priority", "high", "random", "--seed", "7"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("the important one")
+                .and(predicate::str::contains("fn a() {}"))
+                .and(predicate::str::contains("Picked 1 of 1 TODOs")),
+        );
+    todos()
+        .args(["--path", path, "--tag", "BUG", "random"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No TODOs found"));
+}
+
+#[test]
+fn test_stats_priority_and_issue_linkage() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("a.rs"),
+        "// TODO(#12, p:high): linked\n// TODO: plain\n// FIXME(p:high): urgent\n",
+    )
+    .unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .args(["--path", path, "--format=json", "stats"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("\"high\": 2")
+                .and(predicate::str::contains("\"with_issue\": 1"))
+                .and(predicate::str::contains("\"without_issue\": 2")),
+        );
+    todos()
+        .args(["--path", path, "stats"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Issue Linkage: 1 linked, 2 unlinked (33% linked)"));
+}
+
+#[test]
+fn test_lines_of_code_and_density() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("a.rs"),
+        "fn main() {\n\n    // TODO: one\n    run();\n}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("b.py"), "a = 1\nb = 2\n\nc = 3\nd = 4\ne = 5\nf = 6\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    // The second run reads line counts back from the cache
+    for _ in 0..2 {
+        todos()
+            .args(["--path", path, "stats"])
+            .assert()
+            .success()
+            .stdout(
+                predicate::str::contains("Lines of Code: 10 (100.0 TODOs/KLOC)")
+                    .and(predicate::str::contains("Python")),
+            );
+    }
+    todos()
+        .args(["--path", path, "--format=json", "stats"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"lines_of_code\": 10"));
+    todos()
+        .args(["--path", path, "check", "--max-todos-per-kloc", "50"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("max_todos_per_kloc"));
+    todos()
+        .args(["--path", path, "check", "--max-todos-per-kloc", "200"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_stats_blame_author_ages() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("a.rs"), "// TODO: ancient\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "init", "--date=2020-01-01T00:00:00Z"]);
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .args(["--path", path, "--format=json", "stats", "--blame"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"author\": \"Test\"").and(predicate::str::contains("\"count\": 1")));
+    todos()
+        .args(["--path", path, "stats", "--blame"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Author Ages"));
+}
+
+#[test]
+fn test_blame_csv_and_markdown_carry_git_context() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("a.rs"), "// TODO: ancient\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "init", "--date=2020-01-01T00:00:00Z"]);
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .args(["--path", path, "--format=csv", "blame"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("priority,git_author,git_date,git_commit")
+                .and(predicate::str::contains(",Test,2020-01-01")),
+        );
+    todos()
+        .args(["--path", path, "--format=csv", "--columns=tag,git_author", "blame"])
+        .assert()
+        .success()
+        .stdout("tag,git_author\nTODO,Test\n");
+    todos()
+        .args(["--path", path, "--format=markdown", "blame"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("added by Test on 2020-01-01"));
+    todos()
+        .args(["--path", path, "--format=csv", "--columns=owner"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown CSV column 'owner'"));
+}
+
+#[test]
+fn test_scan_csv_columns_with_age() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("a.rs"), "// TODO(p:high): ancient\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "init", "--date=2020-01-01T00:00:00Z"]);
+    let path = dir.path().to_str().unwrap();
+
+    let output = todos()
+        .args(["--path", path, "--format=csv", "--csv-columns=tag,priority,age"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("tag,priority,age"));
+    let row: Vec<&str> = lines.next().unwrap().split(',').collect();
+    assert_eq!(&row[..2], ["TODO", "high"]);
+    assert!(row[2].parse::<u64>().unwrap() > 2000);
+}
+
+#[test]
+fn test_heatmap_text_and_html() {
+    todos()
+        .args(["--path", "tests", "--color=never", "heatmap", "--depth", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("    fixtures/"));
+    todos()
+        .args(["--path", "tests", "--format=html", "heatmap"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("<title>TODO heatmap</title>"));
+    todos()
+        .args(["--path", "tests", "--format=csv", "heatmap"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_serve_json_rpc() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n// FIXME: two\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    let input = concat!(
+        r#"{"jsonrpc":"2.0","id":1,"method":"query","params":{"tags":["FIXME"]}}"#,
+        "\n",
+        r#"{"jsonrpc":"2.0","id":2,"method":"statsForFile","params":{"file":"a.rs"}}"#,
+        "\n",
+        r#"{"jsonrpc":"2.0","id":3,"method":"shutdown"}"#,
+        "\n"
+    );
+    let output = todos()
+        .args(["--path", path, "serve"])
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let responses: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(responses.len(), 3);
+    assert_eq!(responses[0]["result"]["total"], 1);
+    assert_eq!(responses[0]["result"]["items"][0]["message"], "two");
+    assert_eq!(responses[1]["result"]["total_todos"], 2);
+    assert_eq!(responses[2]["id"], 3);
+
+    // Webhooks of the scanned tree's config need --allow-hooks
+    std::fs::write(
+        dir.path().join(".todo-tracker.toml"),
+        "[[webhooks]]\nurl = \"http://127.0.0.1:9/todos\"\n",
+    )
+    .unwrap();
+    let shutdown = "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"shutdown\"}\n";
+    todos()
+        .current_dir(dir.path())
+        .arg("serve")
+        .write_stdin(shutdown)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("not posting to the webhooks"))
+        .stderr(predicate::str::contains("http://127.0.0.1:9/todos"));
+    todos()
+        .current_dir(dir.path())
+        .args(["serve", "--allow-hooks"])
+        .write_stdin(shutdown)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Posting TODO changes to http://127.0.0.1:9/todos"));
+}
+
+#[test]
+fn test_snapshot_git_notes() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n// FIXME: two\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "init"]);
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .env("GIT_AUTHOR_NAME", "Test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "Test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .args(["--path", path, "snapshot", "--git-notes"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("refs/notes/todo-tracker"));
+
+    let output = std::process::Command::new("git")
+        .args(["notes", "--ref=refs/notes/todo-tracker", "show", "HEAD"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    let note: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(note["total_todos"], 2);
+    assert_eq!(note["by_tag"]["FIXME"], 1);
+
+    todos()
+        .args(["--path", path, "snapshot", "--list", "--git-notes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(" 2 FIXME=1,TODO=1"));
+}
+
+#[test]
+fn test_report_by_pr() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q", "-b", "main"]);
+    std::fs::write(dir.path().join("a.rs"), "// TODO: base\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "init"]);
+
+    git(dir.path(), &["checkout", "-q", "-b", "feature"]);
+    std::fs::write(dir.path().join("b.rs"), "// TODO: one\n// FIXME: two\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "Add b"]);
+    git(dir.path(), &["checkout", "-q", "main"]);
+    git(
+        dir.path(),
+        &["merge", "-q", "--no-ff", "-m", "Merge pull request #12 from alice/feature", "feature"],
+    );
+
+    std::fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+    git(dir.path(), &["commit", "-q", "-am", "Drop base TODO (#13)"]);
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .args(["--color=never", "--path", path, "report", "--by-pr"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("TODOs by PR: 2 PRs, 2 changed the TODO count"))
+        .stdout(predicate::str::is_match(r"#12\s+2\s+0\s+\+2").unwrap())
+        .stdout(predicate::str::is_match(r"#13\s+0\s+1\s+-1").unwrap());
+
+    todos()
+        .args(["--path", path, "--format", "json", "report", "--by-pr", "--until", "2000-01-01"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[]"));
+}
+
+#[test]
+fn test_multi_repo_report() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let service = dir.path().join("service");
+    std::fs::create_dir(&service).unwrap();
+    git(&service, &["init", "-q"]);
+    std::fs::write(service.join("a.rs"), "// TODO: one\n// FIXME: two\n").unwrap();
+    git(&service, &["add", "."]);
+    git(&service, &["commit", "-qm", "init"]);
+    std::fs::create_dir(dir.path().join("local")).unwrap();
+    std::fs::write(dir.path().join("local/b.py"), "# TODO: three\n").unwrap();
+    let repos = dir.path().join("repos.toml");
+    std::fs::write(
+        &repos,
+        format!(
+            "[[repo]]\nurl = \"{}\"\n\n[[repo]]\npath = \"local\"\n\n[[repo]]\nname = \"gone\"\nurl = \"{}\"\n",
+            service.display(),
+            dir.path().join("missing").display()
+        ),
+    )
+    .unwrap();
+    let repos = repos.to_str().unwrap();
+
+    let output = todos()
+        .args(["--format", "json", "multi", "--repos", repos])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["repos"][0]["name"], "service");
+    assert_eq!(report["repos"][0]["items"][0]["file"], "a.rs");
+    assert_eq!(report["repos"][1]["stats"]["total_todos"], 1);
+    assert!(report["repos"][2]["error"].is_string());
+    assert_eq!(report["totals"]["total_todos"], 3);
+    assert_eq!(report["totals"]["failed"], 1);
+    assert!(dir.path().join(".todo-tracker/repos/service/a.rs").exists());
+
+    // Existing clones are updated from their remote
+    std::fs::write(service.join("c.rs"), "// TODO: four\n").unwrap();
+    git(&service, &["add", "."]);
+    git(&service, &["commit", "-qm", "more"]);
+    todos()
+        .args(["--tag", "TODO", "multi", "--repos", repos])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("service: 2 TODO(s)"))
+        .stdout(predicate::str::contains("Total: 3 TODO(s) across 2 repositories (1 failed)"));
+    todos()
+        .args(["multi", "--no-update", "--repos", repos])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("service: 3 TODO(s)"));
+}
+
+#[test]
+fn test_audit_branches() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let origin = dir.path().join("origin");
+    std::fs::create_dir(&origin).unwrap();
+    git(&origin, &["init", "-q", "-b", "main"]);
+    std::fs::write(origin.join("a.rs"), "// TODO: base\n").unwrap();
+    git(&origin, &["add", "."]);
+    git(&origin, &["commit", "-qm", "init"]);
+    git(&origin, &["checkout", "-qb", "feature"]);
+    std::fs::write(origin.join("b.rs"), "// TODO: one\n// FIXME: two\n").unwrap();
+    git(&origin, &["add", "."]);
+    git(&origin, &["commit", "-qm", "Add b"]);
+    git(&origin, &["checkout", "-q", "main"]);
+    // Changes on main since the branch forked don't count against it
+    std::fs::write(origin.join("a.rs"), "// TODO: main only\n").unwrap();
+    git(&origin, &["commit", "-qam", "Main moves on"]);
+    git(&origin, &["branch", "merged"]);
+    git(dir.path(), &["clone", "-q", "origin", "clone"]);
+    let path = dir.path().join("clone");
+    let path = path.to_str().unwrap();
+
+    let output = todos()
+        .args(["--path", path, "--format", "json", "audit-branches"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let audits: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let audits = audits.as_array().unwrap();
+    assert_eq!(audits.len(), 1);
+    assert_eq!(audits[0]["branch"], "origin/feature");
+    assert_eq!(audits[0]["added"], 2);
+    assert_eq!(audits[0]["removed"], 0);
+    assert_eq!(audits[0]["behind"], 1);
+
+    todos()
+        .args(["--color=never", "--path", path, "audit-branches"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Branches ahead of origin/main: 1"))
+        .stdout(predicate::str::is_match(r"origin/feature\s+2\s+0\s+\+2").unwrap());
+}
+
+#[test]
+fn test_trace_follows_todo_across_files() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("a.rs"), "fn a() {}\n// TODO: fix the parser\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "Add parser"]);
+    std::fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+    std::fs::write(dir.path().join("b.rs"), "\n\n// TODO: fix the parser\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "Move parser"]);
+    let path = dir.path().to_str().unwrap();
+
+    let output = todos()
+        .current_dir(dir.path())
+        .args(["--path", path, "--format", "json", "trace", "b.rs:3"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let trace: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let events = trace["events"].as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["kind"], "introduced");
+    assert_eq!(events[0]["to"], "a.rs:2");
+    assert_eq!(events[0]["subject"], "Add parser");
+    assert_eq!(events[1]["kind"], "moved");
+    assert_eq!(events[1]["from"], "a.rs:2");
+    assert_eq!(events[1]["to"], "b.rs:3");
+
+    todos()
+        .current_dir(dir.path())
+        .args(["--path", path, "trace", "a.rs:1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No TODO at a.rs:1"));
+
+    // A removed TODO is traced by its committed location or its fingerprint
+    let fingerprint = {
+        let output = todos()
+            .args(["--path", path, "export", "csv"])
+            .output()
+            .unwrap();
+        let csv = String::from_utf8(output.stdout).unwrap();
+        let header: Vec<&str> = csv.lines().next().unwrap().split(',').collect();
+        let column = header.iter().position(|c| *c == "fingerprint").unwrap();
+        csv.lines().nth(1).unwrap().split(',').nth(column).unwrap().to_string()
+    };
+    std::fs::remove_file(dir.path().join("b.rs")).unwrap();
+    git(dir.path(), &["commit", "-qam", "Fix parser"]);
+    todos()
+        .current_dir(dir.path())
+        .args(["--path", path, "trace", "b.rs:3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("b.rs isn't in the working tree"))
+        .stderr(predicate::str::contains("b.rs:3@<rev>"));
+    for target in ["b.rs:3@HEAD~1", fingerprint.as_str()] {
+        let output = todos()
+            .current_dir(dir.path())
+            .args(["--path", path, "--format", "json", "trace", target])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        let trace: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let events = trace["events"].as_array().unwrap();
+        assert_eq!(events.len(), 3, "{}", trace);
+        assert_eq!(events[2]["kind"], "removed");
+        assert_eq!(events[2]["from"], "b.rs:3");
+    }
+    todos()
+        .current_dir(dir.path())
+        .args(["--path", path, "trace", "b.rs:3@HEAD~2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Can't read b.rs at HEAD~2"));
+}
+
+#[test]
+fn test_check_budget_report() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+    todos().args(["--path", path, "snapshot"]).assert().success();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n// TODO: two\n").unwrap();
+
+    todos()
+        .env("TERM", "dumb")
+        .args(["--color=never", "--path", path, "check", "--max-todos", "5", "--budget"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Budget (trend since snapshot "))
+        .stdout(predicate::str::contains("max_todos  2 / 5  3 left  ^ +1"))
+        .stdout(predicate::str::contains("All checks passed."));
+}
+
+#[test]
+fn test_check_report_file() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n// HACK: two\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+    let report_path = dir.path().join("check-report.json");
+
+    todos()
+        .args(["--path", path, "check", "--max-todos", "1", "--deny", "HACK", "--report-file"])
+        .arg(&report_path)
+        .assert()
+        .failure();
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&report_path).unwrap()).unwrap();
+    assert_eq!(report["passed"], false);
+    assert_eq!(report["rules"], serde_json::json!(["max_todos", "deny_tags"]));
+    assert_eq!(report["counts"]["total_todos"], 2);
+    assert_eq!(report["counts"]["violations"], 2);
+    assert_eq!(report["budgets"][0]["limit"], 1.0);
+    assert!(report["timings"]["total_ms"].is_u64());
+}
+
+#[test]
+fn test_cache_warm_export_import() {
+    let from = tempfile::TempDir::new().unwrap();
+    let to = tempfile::TempDir::new().unwrap();
+    for dir in [&from, &to] {
+        std::fs::write(dir.path().join("a.rs"), "// TODO: one\n// FIXME: two\n").unwrap();
+    }
+    let archive = from.path().join("todos-cache.tar.gz");
+
+    todos()
+        .current_dir(from.path())
+        .args(["cache", "warm"])
+        .assert()
+        .success()
+        .stdout("");
+    assert!(from.path().join(".todo-tracker/cache.db").exists());
+    todos()
+        .current_dir(from.path())
+        .args(["cache", "export", archive.to_str().unwrap()])
+        .assert()
+        .success();
+
+    todos()
+        .current_dir(to.path())
+        .args(["cache", "import", archive.to_str().unwrap()])
+        .assert()
+        .success();
+    todos()
+        .current_dir(to.path())
+        .args(["--format", "count"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("2"));
+
+    // A cache mangled by a restore step is rebuilt rather than failing CI
+    std::fs::write(to.path().join(".todo-tracker/cache.db"), "garbage").unwrap();
+    todos()
+        .current_dir(to.path())
+        .args(["cache", "warm"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("discarded a corrupt cache"));
+    todos()
+        .current_dir(to.path())
+        .args(["cache", "import", "missing.tar.gz"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cache_branch_namespaces_gc() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q", "-b", "main"]);
+    std::fs::write(dir.path().join(".todo-tracker.toml"), "[cache]\nbranch_namespaces = true\n")
+        .unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "init"]);
+    let warm = || todos().current_dir(dir.path()).args(["cache", "warm"]).assert().success();
+
+    warm();
+    git(dir.path(), &["checkout", "-q", "-b", "feature"]);
+    warm();
+    git(dir.path(), &["checkout", "-q", "-b", "spike"]);
+    warm();
+    todos()
+        .current_dir(dir.path())
+        .args(["cache", "gc"])
+        .assert()
+        .success()
+        .stdout("No abandoned cache namespaces\n");
+
+    git(dir.path(), &["checkout", "-q", "main"]);
+    git(dir.path(), &["branch", "-q", "-D", "feature"]);
+    todos()
+        .current_dir(dir.path())
+        .args(["cache", "gc"])
+        .assert()
+        .success()
+        .stdout("Removed cache namespace 'feature'\n");
+    todos()
+        .current_dir(dir.path())
+        .args(["cache", "gc", "--older-than", "0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("'spike'"))
+        .stdout(predicate::str::contains("'main'").not());
+}
+
+#[test]
+fn test_annotate_changed_only() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q", "-b", "main"]);
+    std::fs::write(dir.path().join("a.rs"), "// TODO: old\nfn a() {}\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "init"]);
+    git(dir.path(), &["checkout", "-q", "-b", "feature"]);
+    std::fs::write(dir.path().join("a.rs"), "// TODO: old\nfn a() {}\n// FIXME: new\n").unwrap();
+    std::fs::write(dir.path().join("b.rs"), "// HACK: added file\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "feature"]);
+
+    let args = ["--format", "github-actions", "--annotate-changed-only", "--annotate-base", "main"];
+    todos()
+        .current_dir(dir.path())
+        .args(args)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("FIXME: new"))
+        .stdout(predicate::str::contains("HACK: added file"))
+        .stdout(predicate::str::contains("TODO: old").not());
+    // Other formats list everything
+    todos()
+        .current_dir(dir.path())
+        .args(["--annotate-changed-only", "--annotate-base", "main", "--format", "count"])
+        .assert()
+        .success()
+        .stdout("3");
+    todos()
+        .current_dir(dir.path())
+        .args(["--format", "sarif", "--annotate-changed-only", "--annotate-base", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to diff against nope"));
+}
+
+#[test]
+fn test_check_junit_report() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: fine\n// FIXME: denied\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .args(["--path", path, "--format", "junit", "check", "--deny", "FIXME"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::starts_with("<?xml"))
+        .stdout(predicate::str::contains(r#"name="todo-tracker" tests="2" failures="1">"#))
+        .stdout(predicate::str::contains(r#"name="L1 TODO: fine" file="a.rs" line="1"/>"#))
+        .stdout(predicate::str::contains(r#"<failure type="deny_tags""#))
+        .stderr(predicate::str::contains("1 policy violation(s) found."));
+    todos()
+        .args(["--path", path, "--format", "junit", "check"])
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with("</testsuites>\n"))
+        .stderr(predicate::str::contains("All checks passed."));
+}
+
+#[test]
+fn test_check_baseline() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: old\n// FIXME: older\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .args(["--path", path, "check", "--baseline"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("todos baseline write"));
+    todos()
+        .args(["--path", path, "baseline", "write"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote 2 item(s)"));
+    assert!(dir.path().join(".todo-baseline.json").exists());
+
+    // Known items pass, even after moving down the file
+    std::fs::write(dir.path().join("a.rs"), "\n// FIXME: older\n// TODO: old\n").unwrap();
+    todos()
+        .args(["--path", path, "check", "--baseline", "--max-todos", "0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 known item(s) not checked, 0 fixed."));
+
+    std::fs::write(dir.path().join("a.rs"), "// TODO: old\n// TODO: new\n").unwrap();
+    todos()
+        .args(["--path", path, "check", "--baseline", "--max-todos", "0"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("1 known item(s) not checked, 1 fixed."))
+        .stderr(predicate::str::contains("Found 1 TODOs, maximum allowed is 0"));
+}
+
+#[test]
+fn test_check_diff_and_staged_only() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q", "-b", "main"]);
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n// TODO: two\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "init"]);
+    git(dir.path(), &["checkout", "-q", "-b", "feature"]);
+    std::fs::write(dir.path().join("b.rs"), "// FIXME: new\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "feature"]);
+    std::fs::write(dir.path().join("c.rs"), "// HACK: staged\n").unwrap();
+    git(dir.path(), &["add", "c.rs"]);
+    let check = |args: &[&str]| {
+        let mut cmd = todos();
+        cmd.current_dir(dir.path()).env_remove("GITHUB_BASE_REF").arg("check").args(args);
+        cmd.assert()
+    };
+
+    check(&["--max-todos", "1"]).code(1);
+    check(&["--max-todos", "1", "--diff-only"]).success();
+    check(&["--deny", "FIXME", "--diff-only"])
+        .code(1)
+        .stderr(predicate::str::contains("Denied tag FIXME found at"));
+    check(&["--deny", "FIXME", "--staged-only"]).success();
+    check(&["--deny", "HACK", "--staged-only"]).code(1);
+    check(&["--diff-only", "--staged-only"]).failure();
+}
+
+#[test]
+fn test_auto_ci_format() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n").unwrap();
+    let ci_vars = [
+        "GITHUB_ACTIONS",
+        "GITLAB_CI",
+        "TF_BUILD",
+        "TEAMCITY_VERSION",
+        "BITBUCKET_BUILD_NUMBER",
+    ];
+    let auto_ci = |var: Option<(&str, &str)>| {
+        let mut cmd = todos();
+        cmd.current_dir(dir.path()).args(["--format", "auto-ci"]);
+        for name in ci_vars {
+            cmd.env_remove(name);
+        }
+        if let Some((name, value)) = var {
+            cmd.env(name, value);
+        }
+        cmd.assert().success()
+    };
+
+    auto_ci(Some(("GITHUB_ACTIONS", "true")))
+        .stdout(predicate::str::starts_with("::warning file=./a.rs,line=1"));
+    let report = std::fs::read_to_string(dir.path().join("todos-report.json")).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["items"][0]["message"], "one");
+
+    auto_ci(Some(("TF_BUILD", "True")))
+        .stdout(predicate::str::starts_with("##vso[task.logissue type=warning;sourcepath=a.rs;"));
+    auto_ci(None).stdout(predicate::str::contains("TODO").and(predicate::str::contains("one")));
+}
+
+#[test]
+fn test_piped_summary_on_stderr() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n// FIXME: two\n").unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .args(["--path", path, "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("{"))
+        .stderr(predicate::str::starts_with("todos: 2 TODOs in 1 files (scanned 1 files in "));
+    todos()
+        .args(["--path", path, "--format", "sarif"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("; SARIF: 1 error(s), 0 warning(s), 1 note(s)"));
+    todos()
+        .args(["--path", path, "--format", "csv", "--quiet"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("todos:").not());
+    todos()
+        .args(["--path", path])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("todos:").not());
+}
+
+#[test]
+fn test_deterministic_output() {
+    let scan = |epoch: &str| {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/a.rs"), "// TODO: one\n// FIXME: two\n").unwrap();
+        let output = todos()
+            .env("SOURCE_DATE_EPOCH", epoch)
+            .args(["--path", dir.path().to_str().unwrap(), "--format", "json"])
+            .arg("--deterministic")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let first = scan("");
+    assert_eq!(first, scan(""));
+    assert!(first.contains(r#""file": "src/a.rs""#), "{}", first);
+    assert!(first.contains(r#""root_path": ".""#));
+    assert!(first.contains(r#""scan_duration_ms": 0"#));
+    assert!(first.contains(r#""timestamp": "1970-01-01T00:00:00Z""#));
+    assert!(scan("1700000000").contains(r#""timestamp": "2023-11-14T22:13:20Z""#));
+}
+
+#[test]
+fn test_deterministic_streaming_and_csv_age() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(["commit", "-qm", "first"])
+        .env("GIT_AUTHOR_DATE", "2023-11-04T12:00:00Z")
+        .env("GIT_COMMITTER_DATE", "2023-11-04T12:00:00Z")
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let path = dir.path().to_str().unwrap();
+
+    // Ages count to the SOURCE_DATE_EPOCH day, not today
+    todos()
+        .env("SOURCE_DATE_EPOCH", "1700000000")
+        .args(["--path", path, "--format", "csv", "--csv-columns", "file,age"])
+        .arg("--deterministic")
+        .assert()
+        .success()
+        .stdout("file,age\na.rs,10\n");
+    // The streaming path reports the same way as a regular scan
+    todos()
+        .args(["--path", path, "--color=never", "--memory-budget", "1"])
+        .arg("--deterministic")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("a.rs"))
+        .stdout(predicate::str::contains(path).not())
+        .stdout(predicate::str::contains(" 0ms"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_encrypt_output() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: secret plan\n").unwrap();
+    // A stand-in for age that shows its arguments and hides its input
+    let bin = tempfile::TempDir::new().unwrap();
+    let age = bin.path().join("age");
+    std::fs::write(&age, "#!/bin/sh\necho \"ENCRYPTED $*\"\ncat > /dev/null\n").unwrap();
+    std::fs::set_permissions(&age, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path_var = format!("{}:{}", bin.path().display(), std::env::var("PATH").unwrap());
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .env("PATH", &path_var)
+        .args(["--path", path, "--format", "json", "--encrypt", "age:age1abc"])
+        .assert()
+        .success()
+        .stdout("ENCRYPTED --encrypt --armor --recipient age1abc\n");
+    todos()
+        .env("PATH", &path_var)
+        .args(["--path", path, "--format", "csv", "--encrypt", "age:age1abc"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--encrypt supports json and sarif output"));
+    todos()
+        .args(["--path", path, "--format", "json", "--encrypt", "gpg:ops"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected age:<recipient>"));
+    // Commands that can't encrypt refuse rather than write plaintext
+    todos()
+        .env("PATH", &path_var)
+        .args(["--path", path, "--format", "json", "--encrypt", "age:age1abc", "stats"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("--encrypt is only supported by scan and list"));
+
+    // auto-ci encrypts the artifact only
+    let artifact = dir.path().join("report.json");
+    todos()
+        .env("PATH", &path_var)
+        .env("GITHUB_ACTIONS", "true")
+        .args(["--path", path, "--format", "auto-ci", "--encrypt", "age:age1abc"])
+        .args(["--ci-artifact", artifact.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("secret plan"));
+    let written = std::fs::read_to_string(&artifact).unwrap();
+    assert!(written.starts_with("ENCRYPTED "), "{}", written);
+    assert!(!written.contains("secret plan"));
+}
+
+#[test]
+fn test_cache_result_keyed_by_discovery_flags() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: top\n").unwrap();
+    std::fs::write(dir.path().join("sub/b.rs"), "// TODO: nested\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "first"]);
+    let path = dir.path().to_str().unwrap();
+
+    todos()
+        .args(["--path", path, "--format", "json", "--cache-result"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("nested"));
+    // A shallower walk must not be served the full cached result
+    todos()
+        .args(["--path", path, "--format", "json", "--cache-result", "--max-depth", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("top"))
+        .stdout(predicate::str::contains("nested").not());
+}
+
+#[test]
+fn test_cache_result_skipped_without_ignore_rules() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join(".gitignore"), "generated.rs\n").unwrap();
+    std::fs::write(
+        dir.path().join(".todo-tracker.toml"),
+        "[scan]\nrespect_gitignore = false\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: tracked\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "first"]);
+    let path = dir.path().to_str().unwrap();
+
+    // The ignored file leaves `git status` clean, so only scanning sees it
+    for message in ["first", "second"] {
+        std::fs::write(dir.path().join("generated.rs"), format!("// TODO: {}\n", message))
+            .unwrap();
+        todos()
+            .current_dir(dir.path())
+            .args(["--path", path, "--format", "json", "--cache-result"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(message));
+    }
+}
+
+#[test]
+fn test_blame_cache_hits_and_amend() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "first"]);
+    let path = dir.path().to_str().unwrap();
+
+    let blame = || -> serde_json::Value {
+        let output = todos()
+            .args(["--path", path, "--format", "json", "blame"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice(&output.stdout).unwrap()
+    };
+    assert_eq!(blame()["metadata"]["blame_cache_hits"], 0);
+    assert_eq!(blame()["metadata"]["blame_cache_hits"], 1);
+
+    // Rewriting the commit that last touched the file invalidates its blame
+    git(
+        dir.path(),
+        &["-c", "user.name=Other", "commit", "-q", "--amend", "--reset-author", "-m", "amended"],
+    );
+    let result = blame();
+    assert_eq!(result["metadata"]["blame_cache_hits"], 0);
+    assert_eq!(result["items"][0]["git_author"], "Other");
+}
+
+#[test]
+fn test_blame_ignore_revs_file() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("a.rs"), "fn a() {\n    // TODO: keep\n}\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "first"]);
+    std::fs::write(dir.path().join("a.rs"), "fn a() {\n  // TODO: keep\n}\n").unwrap();
+    git(dir.path(), &["-c", "user.name=Formatter", "commit", "-qam", "reformat"]);
+    let path = dir.path().to_str().unwrap();
+    let author = |args: &[&str]| -> serde_json::Value {
+        let output = todos()
+            .args(["--path", path, "--format", "json", "blame"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()["items"][0]
+            ["git_author"]
+            .clone()
+    };
+    assert_eq!(author(&[]), "Formatter");
+
+    // The reformat is looked past once listed, also through the blame cache
+    let head = std::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    std::fs::write(dir.path().join(".git-blame-ignore-revs"), head.stdout).unwrap();
+    assert_eq!(author(&[]), "Test");
+    assert_eq!(author(&["--ignore-revs-file", ""]), "Formatter");
+    todos()
+        .args(["--path", path, "blame", "--ignore-revs-file", "missing-revs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("missing-revs"));
+}
+
+#[test]
+fn test_blame_in_shallow_clone() {
+    let origin = tempfile::TempDir::new().unwrap();
+    git(origin.path(), &["init", "-q"]);
+    std::fs::write(origin.path().join("a.rs"), "// TODO: old\n").unwrap();
+    git(origin.path(), &["add", "."]);
+    git(origin.path(), &["commit", "-qm", "first"]);
+    std::fs::write(origin.path().join("a.rs"), "// TODO: old\n// TODO: new\n").unwrap();
+    git(origin.path(), &["commit", "-qam", "second"]);
+    std::fs::write(origin.path().join("b.rs"), "// TODO: newest\n").unwrap();
+    git(origin.path(), &["add", "."]);
+    git(origin.path(), &["commit", "-qm", "third"]);
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let url = format!("file://{}", origin.path().display());
+    git(dir.path(), &["clone", "-q", "--depth", "1", &url, "clone"]);
+    let path = dir.path().join("clone");
+    let path = path.to_str().unwrap();
+
+    let output = todos()
+        .args(["--path", path, "--format", "json", "blame"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("shallow clone"));
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let incomplete = |result: &serde_json::Value| {
+        result["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|item| item["blame_incomplete"] == true)
+            .count()
+    };
+    // Every line blames to the single fetched commit
+    assert_eq!(incomplete(&result), 3);
+
+    let output = todos()
+        .args(["--path", path, "--format", "json", "--auto-fetch-depth", "blame"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("shallow clone"));
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(incomplete(&result), 0);
+}
+
+#[test]
+fn test_diff_in_blobless_partial_clone() {
+    let origin = tempfile::TempDir::new().unwrap();
+    git(origin.path(), &["init", "-q"]);
+    git(origin.path(), &["config", "uploadpack.allowFilter", "true"]);
+    git(origin.path(), &["config", "uploadpack.allowAnySHA1InWant", "true"]);
+    std::fs::create_dir(origin.path().join("sub")).unwrap();
+    std::fs::write(origin.path().join("a.rs"), "// TODO: one\n").unwrap();
+    std::fs::write(origin.path().join("sub/b.rs"), "// TODO: sub\n").unwrap();
+    git(origin.path(), &["add", "."]);
+    git(origin.path(), &["commit", "-qm", "first"]);
+    std::fs::write(origin.path().join("a.rs"), "// TODO: one\n// FIXME: two\n").unwrap();
+    std::fs::write(origin.path().join("sub/b.rs"), "// HACK: sub\n").unwrap();
+    git(origin.path(), &["commit", "-qam", "second"]);
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let url = format!("file://{}", origin.path().display());
+    git(dir.path(), &["clone", "-q", "--filter=blob:none", &url, "clone"]);
+    let clone = dir.path().join("clone");
+    let path = clone.to_str().unwrap();
+
+    let output = todos()
+        .args(["--path", path, "--format", "json", "diff", "HEAD~1..HEAD"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["added"].as_array().unwrap().len(), 2);
+    assert_eq!(result["removed"].as_array().unwrap().len(), 1);
+    assert!(result["errors"].as_array().unwrap().is_empty());
+
+    // Missing contents can't be fetched once the promisor is unreachable
+    let fresh = dir.path().join("fresh");
+    git(dir.path(), &["clone", "-q", "--filter=blob:none", &url, "fresh"]);
+    git(&fresh, &["remote", "set-url", "origin", "file:///nonexistent"]);
+    todos()
+        .args(["--path", fresh.to_str().unwrap(), "diff", "HEAD~1..HEAD"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("promisor remote"));
+}
+
+#[test]
+fn test_linked_worktree_and_bare_repository() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let main = dir.path().join("main");
+    std::fs::create_dir(&main).unwrap();
+    git(&main, &["init", "-q"]);
+    std::fs::write(main.join("a.rs"), "// TODO: one\n").unwrap();
+    git(&main, &["add", "."]);
+    git(&main, &["commit", "-qm", "first"]);
+    git(&main, &["worktree", "add", "-q", "../linked"]);
+
+    let linked = dir.path().join("linked");
+    std::fs::write(linked.join("b.rs"), "// FIXME: two\n").unwrap();
+    git(&linked, &["add", "b.rs"]);
+    todos()
+        .args(["--color=never", "--path", linked.to_str().unwrap(), "blame"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Test"))
+        .stdout(predicate::str::contains("Total: 2 items"));
+    todos()
+        .args(["--color=never", "--path", linked.to_str().unwrap(), "diff", "--staged"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[FIXME] two"));
+
+    git(&linked, &["commit", "-qm", "second"]);
+    git(dir.path(), &["clone", "-q", "--bare", "linked", "bare.git"]);
+    let bare = dir.path().join("bare.git");
+    todos()
+        .args(["--color=never", "--path", bare.to_str().unwrap(), "diff", "HEAD~1..HEAD"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[FIXME] two"));
+    todos()
+        .args(["--path", bare.to_str().unwrap(), "blame"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("bare repository"));
+}
+
+#[test]
+fn test_remind_lists_todos_with_closed_issues() {
+    use std::io::{BufRead, BufReader, Write};
+
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("a.rs"),
+        "// TODO(#7): retry payments\n// TODO(#8): still open\n",
+    )
+    .unwrap();
+
+    // Answers the two issue lookups
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let api = format!("http://{}", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        for _ in 0..2 {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let body = if request.contains("/issues/7 ") {
+                r#"{"state": "closed", "closed_at": "2024-05-01T00:00:00Z", "title": "Retries"}"#
+            } else {
+                r#"{"state": "open", "title": "Open"}"#
+            };
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+
+    todos()
+        .env("GITHUB_API_URL", &api)
+        .env_remove("GITHUB_TOKEN")
+        .env_remove("GH_TOKEN")
+        .args(["--path", dir.path().to_str().unwrap(), "remind", "--repo", "acme/api"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 TODO(s) reference closed issues in acme/api"))
+        .stdout(predicate::str::contains("a.rs:1  [TODO] retry payments"))
+        .stdout(predicate::str::contains("#7 closed 2024-05-01: Retries"))
+        .stdout(predicate::str::contains("still open").not());
+    server.join().unwrap();
+}
+
+#[test]
+fn test_age_escalation_in_check_and_sarif() {
+    let dir = tempfile::TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    std::fs::write(dir.path().join("a.rs"), "// TODO: ancient\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    let status = std::process::Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(["commit", "-qm", "old"])
+        .env("GIT_AUTHOR_DATE", "2000-01-01T00:00:00Z")
+        .env("GIT_COMMITTER_DATE", "2000-01-01T00:00:00Z")
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    std::fs::write(dir.path().join("b.rs"), "// TODO: fresh\n").unwrap();
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-qm", "new"]);
+    let path = dir.path().to_str().unwrap();
+
+    // Warnings are reported without failing
+    todos()
+        .args(["--color=never", "--path", path, "check", "--warn-after-days", "365"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All checks passed with 1 warning(s)."))
+        .stderr(predicate::str::contains("[warning] age_escalation: TODO at"))
+        .stderr(predicate::str::contains("a.rs:1 is"));
+    todos()
+        .args(["--color=never", "--path", path, "check", "--error-after-days", "365"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("[error] age_escalation"));
+    todos()
+        .args(["--color=never", "--path", path, "check", "--max-age-days", "365"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("[error] max_age_days: TODO at"))
+        .stderr(predicate::str::contains("maximum allowed is 365"))
+        .stderr(predicate::str::contains("b.rs").not());
+
+    std::fs::write(
+        dir.path().join(".todo-tracker.toml"),
+        "[escalation]\nwarn_after_days = 365\n",
+    )
+    .unwrap();
+    let output = todos()
+        .current_dir(dir.path())
+        .args(["--format", "sarif"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let sarif: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let levels: Vec<(&str, &str)> = sarif["runs"][0]["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["message"]["text"].as_str().unwrap(),
+                r["level"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(levels, [("TODO: ancient", "warning"), ("TODO: fresh", "note")]);
+
+    // Notes stay out of the annotations when only warnings and up are wanted
+    let output = todos()
+        .current_dir(dir.path())
+        .args(["--format", "sarif", "--annotate-min-level", "warning"])
+        .output()
+        .unwrap();
+    let sarif: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let results = sarif["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["message"]["text"], "TODO: ancient");
+    todos()
+        .current_dir(dir.path())
+        .args(["--format", "github-actions", "--annotate-min-level", "error"])
+        .assert()
+        .success()
+        .stdout("");
+    todos()
+        .current_dir(dir.path())
+        .args(["--format", "github-actions", "--max-annotations", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("::warning").count(1))
+        .stdout(predicate::str::contains("::notice title=todo-tracker::1 more TODO(s)"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_post_scan_hook_enriches_json() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::create_dir(dir.path().join("billing")).unwrap();
+    std::fs::write(dir.path().join("billing/pay.rs"), "// TODO: retry payments\n").unwrap();
+    std::fs::write(
+        dir.path().join(".todo-tracker.toml"),
+        "[hooks]\npost_scan = \"sed 's/\\\"line\\\":/\\\"service\\\":\\\"billing\\\",\\\"line\\\":/'\"\n",
+    )
+    .unwrap();
+
+    let output = todos()
+        .current_dir(dir.path())
+        .args(["--format", "json", "--allow-hooks"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["items"][0]["service"], "billing");
+    assert_eq!(json["items"][0]["message"], "retry payments");
+
+    std::fs::write(dir.path().join(".todo-tracker.toml"), "[hooks]\npost_scan = \"exit 2\"\n")
+        .unwrap();
+    todos()
+        .current_dir(dir.path())
+        .arg("--allow-hooks")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("post_scan: 'exit 2' failed"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_post_scan_hook_of_repo_config_needs_allow_hooks() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.rs"), "// TODO: one\n").unwrap();
+    std::fs::write(
+        dir.path().join(".todo-tracker.toml"),
+        "[hooks]\npost_scan = \"touch ran\"\n",
+    )
+    .unwrap();
+
+    todos()
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("pass --allow-hooks to run it"));
+    assert!(!dir.path().join("ran").exists());
+
+    // The user's own config is trusted
+    std::fs::remove_file(dir.path().join(".todo-tracker.toml")).unwrap();
+    let home = tempfile::TempDir::new().unwrap();
+    std::fs::create_dir(home.path().join("todo-tracker")).unwrap();
+    std::fs::write(
+        home.path().join("todo-tracker/config.toml"),
+        "[hooks]\npost_scan = \"touch ran\"\n",
+    )
+    .unwrap();
+    todos()
+        .current_dir(dir.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .assert()
+        .success();
+    assert!(dir.path().join("ran").exists());
+}
+
+#[test]
+fn test_components_group_stats_and_budgets() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::create_dir_all(dir.path().join("services/billing")).unwrap();
+    std::fs::create_dir(dir.path().join("tools")).unwrap();
+    std::fs::write(
+        dir.path().join("services/billing/pay.rs"),
+        "// TODO: retry\n// FIXME: round\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("tools/gen.py"), "# TODO: tidy\n").unwrap();
+    std::fs::write(
+        dir.path().join(".todo-tracker.toml"),
+        "[components]\n\"services/billing/\" = \"billing\"\n\n[component_budgets]\nbilling = 1\n",
+    )
+    .unwrap();
+
+    todos()
+        .current_dir(dir.path())
+        .args(["--color=never", "--group-by", "component"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("billing (2)\n  ./services/billing/pay.rs\n"))
+        .stdout(predicate::str::contains("\n(no component) (1)\n  ./tools/gen.py\n"));
+
+    let output = todos()
+        .current_dir(dir.path())
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["items"][0]["component"], "billing");
+    assert!(json["items"][2].get("component").is_none());
+    assert_eq!(json["stats"]["by_component"]["billing"], 2);
+
+    todos()
+        .current_dir(dir.path())
+        .arg("stats")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Components:"))
+        .stdout(predicate::str::contains("(no component)"));
+    todos()
+        .current_dir(dir.path())
+        .arg("check")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "max_todos:billing: Found 2 TODOs in component billing, maximum allowed is 1",
+        ));
+}
+
+#[test]
+fn test_skipped_tests_mode() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("lib.rs"),
+        "// TODO: tidy\n#[test]\n#[ignore = \"flaky\"]\nfn a() {}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("app.test.js"), "xit('logs in', () => {});\n").unwrap();
+
+    // Off by default
+    todos()
+        .current_dir(dir.path())
+        .args(["check", "--max-todos", "1", "--max-skipped-tests", "0"])
+        .assert()
+        .success();
+
+    std::fs::write(dir.path().join(".todo-tracker.toml"), "[scan]\nskipped_tests = true\n")
+        .unwrap();
+    todos()
+        .current_dir(dir.path())
+        .arg("stats")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Disabled tests: 2"));
+    todos()
+        .current_dir(dir.path())
+        .args(["check", "--max-todos", "1", "--max-skipped-tests", "1"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "max_skipped_tests: Found 2 disabled tests, maximum allowed is 1",
+        ))
+        .stderr(predicate::str::contains("max_todos:").not());
+}
+
+#[test]
+fn test_test_code_classification() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::create_dir_all(dir.path().join("tests")).unwrap();
+    std::fs::write(
+        dir.path().join("src/lib.rs"),
+        "// TODO: prod\n#[cfg(test)]\nmod tests {\n    // TODO: unit\n}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("tests/it.rs"), "// TODO: integration\n").unwrap();
+
+    let output = todos()
+        .current_dir(dir.path())
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["stats"]["in_test_code"], 2);
+    let in_test: Vec<bool> = json["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item.get("in_test_code").is_some())
+        .collect();
+    assert_eq!(in_test, [false, true, true]);
+
+    todos()
+        .current_dir(dir.path())
+        .arg("stats")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Test code: 2 (1 in production code)"));
+    todos()
+        .current_dir(dir.path())
+        .args(["check", "--max-todos", "1"])
+        .assert()
+        .code(1);
+    todos()
+        .current_dir(dir.path())
+        .args(["check", "--max-todos", "1", "--exempt-test-code"])
+        .assert()
+        .success();
+}